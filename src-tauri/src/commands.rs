@@ -1,11 +1,23 @@
 use crate::database::{Database, NewHistoryRecord};
 use crate::voice_assistant::traits::AsrProcessor;
+use crate::voice_assistant::asr::cloud_provider::{CloudAsrOptions, CloudAsrProvider};
+use crate::voice_assistant::translate::normalize_language;
 use serde::{Deserialize, Serialize};
 use tauri::State;
 use std::sync::{Arc, Mutex};
 use base64::{Engine as _, engine::general_purpose::STANDARD};
+use tracing::{debug, info, warn, error};
 
 pub mod gpu_backend;
+pub mod error;
+pub mod history_export;
+pub mod benchmark;
+pub mod recordings;
+pub mod asr_profiles;
+pub mod transcript;
+pub mod service_status;
+
+pub use error::CommandError;
 
 #[derive(Debug, Serialize, Deserialize)]
 pub struct AudioDevice {
@@ -17,6 +29,9 @@ pub struct AudioDevice {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct HealthCheckRequest {
     pub endpoint: String,
+    /// OpenAI 兼容的云端服务没有 /health，带上 API key 时改为请求模型列表
+    #[serde(default)]
+    pub api_key: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -40,6 +55,7 @@ pub struct ServiceStatusResponse {
 pub struct LatencyDataResponse {
     pub current: i64,
     pub trend: String,
+    /// 最近窗口相对前一窗口的变化百分比
     pub trend_value: i64,
     pub history: Vec<LatencyHistoryPoint>,
 }
@@ -48,6 +64,80 @@ pub struct LatencyDataResponse {
 pub struct LatencyHistoryPoint {
     pub time: String,
     pub val: i64,
+    /// 分阶段耗时，用于堆叠图；旧记录没有
+    pub breakdown: Option<crate::database::LatencyBreakdown>,
+}
+
+/// 默认趋势窗口：比较最近 10 条和之前 10 条记录
+const DEFAULT_LATENCY_TREND_WINDOW: usize = 10;
+/// 变化幅度在该百分比以内视为持平
+const LATENCY_TREND_FLAT_PERCENT: f64 = 5.0;
+
+/// 计算延迟趋势：最近一个窗口的平均值相对前一个窗口的变化百分比。
+/// 记录不足两个窗口时窗口缩小到记录数的一半，至少 4 条记录才计算。
+/// 优先比较每秒音频的 ASR 推理耗时，这样录音长短和输入/后处理耗时不会影响趋势；
+/// 窗口内有缺少分阶段数据的旧记录时退回到比较总耗时
+fn latency_trend(records: &[crate::database::LatencyRecord], window: usize) -> (&'static str, i64) {
+    let mut sorted: Vec<&crate::database::LatencyRecord> = records.iter().collect();
+    sorted.sort_by(|a, b| b.recorded_at.cmp(&a.recorded_at));
+
+    let window = window.min(sorted.len() / 2);
+    if window < 2 {
+        return ("neutral", 0);
+    }
+    let compared = &sorted[..window * 2];
+
+    let values: Vec<f64> = compared
+        .iter()
+        .map(|r| crate::database::LatencyBreakdown::from_record(r)?.asr_ms_per_audio_second())
+        .collect::<Option<Vec<f64>>>()
+        .unwrap_or_else(|| compared.iter().map(|r| r.latency_ms as f64).collect());
+
+    let mean = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+    let (current, previous) = (mean(&values[..window]), mean(&values[window..]));
+    if previous <= 0.0 {
+        return ("neutral", 0);
+    }
+
+    let percent = (current - previous) / previous * 100.0;
+    let trend = if percent >= LATENCY_TREND_FLAT_PERCENT {
+        "up"
+    } else if percent <= -LATENCY_TREND_FLAT_PERCENT {
+        "down"
+    } else {
+        "neutral"
+    };
+    (trend, percent.round() as i64)
+}
+
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct LatencyPercentiles {
+    pub p50: i64,
+    pub p90: i64,
+    pub p99: i64,
+    pub max: i64,
+    pub count: usize,
+}
+
+/// 最近邻秩法计算百分位（SQLite 没有百分位函数），空数据返回全 0
+fn latency_percentiles(mut values: Vec<i64>) -> LatencyPercentiles {
+    if values.is_empty() {
+        return LatencyPercentiles::default();
+    }
+    values.sort_unstable();
+
+    let percentile = |p: f64| {
+        let rank = (p / 100.0 * values.len() as f64).ceil() as usize;
+        values[rank.clamp(1, values.len()) - 1]
+    };
+
+    LatencyPercentiles {
+        p50: percentile(50.0),
+        p90: percentile(90.0),
+        p99: percentile(99.0),
+        max: values[values.len() - 1],
+        count: values.len(),
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -69,6 +159,14 @@ pub struct AsrConfigRequest {
     pub cloud_endpoint: Option<String>,
     pub cloud_api_key: Option<String>,
     pub whisper_model: Option<String>, // NEW: Selected whisper model
+    #[serde(default)]
+    pub enable_vad: Option<bool>, // None keeps the stored value
+    #[serde(default)]
+    pub cloud_provider: Option<String>, // "custom" | "openai"，None keeps the stored value
+    #[serde(default)]
+    pub cloud_model: Option<String>,
+    #[serde(default)]
+    pub cloud_language: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -76,6 +174,101 @@ pub struct TranslationConfigRequest {
     pub provider: String,
     pub api_key: Option<String>,
     pub endpoint: Option<String>,
+    pub target_language: Option<String>, // 默认 "en"
+    pub source_language: Option<String>, // 为空时自动判断
+    pub model: Option<String>, // Ollama 等可选模型的服务使用
+    #[serde(default)]
+    pub timeout_secs: Option<i64>, // 默认 30 秒
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PostProcessConfigRequest {
+    pub convert_to_simplified: bool,
+    pub add_symbol: bool,
+    pub optimize_result: bool,
+    /// LLM 优化的时间预算（毫秒），为空时使用默认值
+    #[serde(default)]
+    pub optimize_timeout_ms: Option<i64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AudioPrepConfigRequest {
+    pub normalize_enabled: bool,
+    pub high_pass_enabled: bool,
+    pub noise_gate_enabled: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct NetworkRetryConfigRequest {
+    pub max_retries: i64,
+    pub time_budget_ms: i64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OverlayConfigRequest {
+    pub enabled: Option<bool>,
+    pub position: Option<String>, // "cursor", "top-left", "top-right", "bottom-left", "bottom-right"
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DictationCommandConfigRequest {
+    pub enabled: bool,
+    pub commands: Option<Vec<crate::voice_assistant::dictation_commands::DictationCommand>>, // None 表示使用默认命令
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct OutputProfileRequest {
+    pub app_pattern: String,
+    #[serde(default = "default_profile_injection_method")]
+    pub injection_method: String, // "default", "auto", "direct", "clipboard"
+    #[serde(default)]
+    pub trailing_text: String,
+    #[serde(default)]
+    pub capitalize_sentences: bool,
+    #[serde(default)]
+    pub typing_delays: Option<crate::database::TypingDelays>, // None 表示沿用全局的输入延迟
+}
+
+fn default_profile_injection_method() -> String {
+    "default".to_string()
+}
+
+impl OutputProfileRequest {
+    fn validate(&self) -> Result<(), String> {
+        if self.app_pattern.trim().is_empty() {
+            return Err("App name pattern must not be empty".to_string());
+        }
+        crate::voice_assistant::output_profiles::parse_injection_method(&self.injection_method)
+            .map_err(|e| e.to_string())?;
+        if let Some(delays) = &self.typing_delays {
+            let values = [
+                delays.clipboard_update_ms,
+                delays.keyboard_events_settle_ms,
+                delays.typing_complete_ms,
+                delays.character_interval_ms,
+                delays.short_operation_ms,
+            ];
+            if values.iter().any(|ms| !(0..=10_000).contains(ms)) {
+                return Err("Typing delays must be between 0 and 10000 ms".to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn typing_delays_json(&self) -> Result<Option<String>, String> {
+        self.typing_delays
+            .as_ref()
+            .map(|delays| serde_json::to_string(delays).map_err(|e| format!("Invalid typing delays: {}", e)))
+            .transpose()
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ReplacementRequest {
+    pub pattern: String,
+    pub replacement: String,
+    #[serde(default)]
+    pub is_regex: bool,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -86,6 +279,8 @@ pub struct HistoryRequest {
     pub audio_file_path: Option<String>,
     pub processor_type: Option<String>,
     pub processing_time_ms: Option<i64>,
+    #[serde(default)]
+    pub audio_duration_ms: Option<i64>,
     pub success: bool,
     pub error_message: Option<String>,
 }
@@ -97,6 +292,14 @@ pub struct AsrTestRequest {
     pub service_provider: String,
     pub endpoint: Option<String>,
     pub api_key: Option<String>,
+    #[serde(default)]
+    pub max_retries: Option<u32>, // Cloud ASR retries for 5xx/429/connection errors, defaults to the saved network retry config
+    #[serde(default)]
+    pub cloud_provider: Option<String>, // "custom" | "openai"，None 沿用已保存的配置
+    #[serde(default)]
+    pub cloud_model: Option<String>,
+    #[serde(default)]
+    pub cloud_language: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -117,39 +320,58 @@ pub struct HotkeyConfigRequest {
     pub anti_mistouch_enabled: bool,
     pub save_wav_files: bool,
     pub typing_delays: crate::database::TypingDelays,
+    #[serde(default)]
+    pub text_injection_method: Option<String>,
+    #[serde(default)]
+    pub hotkey_backend: Option<String>,
+    #[serde(default)]
+    pub recording_mode: Option<String>,
+    #[serde(default)]
+    pub max_recording_secs: Option<i64>,
+    #[serde(default)]
+    pub silence_auto_stop_enabled: Option<bool>,
+    #[serde(default)]
+    pub silence_auto_stop_ms: Option<i64>,
+    #[serde(default)]
+    pub preroll_ms: Option<i64>,
+    #[serde(default)]
+    pub queued_to_clipboard: Option<bool>,
+    /// 循环切换 ASR 配置方案的热键，空字符串清除
+    #[serde(default)]
+    pub cycle_profile_key: Option<String>,
 }
 
 // Initialize database
 #[tauri::command]
 pub async fn init_database(
     db_state: State<'_, DatabaseState>
-) -> Result<String, String> {
-    println!("🚀 Backend: init_database() called");
+) -> Result<String, CommandError> {
+    info!("🚀 Backend: init_database() called");
 
     // Check if database is already initialized
     {
-        println!("🔍 Backend: Checking if database already exists...");
+        debug!("🔍 Backend: Checking if database already exists...");
         let guard = db_state.lock().unwrap();
         if guard.is_some() {
-            println!("✅ Backend: Database already exists, skipping initialization");
+            info!("✅ Backend: Database already exists, skipping initialization");
             return Ok("Database already initialized".to_string());
         }
-        println!("🔍 Backend: No existing database found, proceeding with initialization");
+        debug!("🔍 Backend: No existing database found, proceeding with initialization");
     }
 
-    println!("🔍 Backend: Attempting to create new Database instance...");
+    debug!("🔍 Backend: Attempting to create new Database instance...");
     match Database::new().await {
         Ok(db) => {
-            println!("✅ Backend: Database created successfully, storing in state");
+            info!("✅ Backend: Database created successfully, storing in state");
             *db_state.lock().unwrap() = Some(db);
-            println!("✅ Backend: Database initialized and stored in state");
+            info!("✅ Backend: Database initialized and stored in state");
             Ok("Database initialized successfully".to_string())
         }
         Err(e) => {
-            eprintln!("❌ Backend: Failed to initialize database: {}", e);
-            println!("❌ Backend: Database initialization error details:");
-            println!("  - Error: {}", e);
-            Err(format!("Failed to initialize database: {}", e))
+            error!("❌ Backend: Failed to initialize database: {}", e);
+            error!("❌ Backend: Database initialization error details:");
+            debug!("  - Error: {}", e);
+            Err(CommandError::Database(format!("Failed to initialize database: {}", e)))
         }
     }
 }
@@ -158,46 +380,46 @@ pub async fn init_database(
 #[tauri::command]
 pub async fn get_asr_config(
     db_state: State<'_, DatabaseState>
-) -> Result<Option<crate::database::AsrConfig>, String> {
-    println!("🔍 Backend: get_asr_config() called");
+) -> Result<Option<crate::database::AsrConfig>, CommandError> {
+    debug!("🔍 Backend: get_asr_config() called");
 
     let db = {
-        println!("🔒 Backend: Acquiring database lock...");
+        debug!("🔒 Backend: Acquiring database lock...");
         let guard = db_state.lock().unwrap();
         let db_ref = guard.as_ref().cloned();
-        println!("🔓 Backend: Database lock released, database exists: {}", db_ref.is_some());
+        debug!("🔓 Backend: Database lock released, database exists: {}", db_ref.is_some());
         db_ref
     };
 
     match db {
         Some(database) => {
-            println!("✅ Backend: Database found, querying ASR config...");
+            info!("✅ Backend: Database found, querying ASR config...");
             match database.get_asr_config().await {
                 Ok(config) => {
                     if let Some(ref cfg) = config {
-                        println!("✅ Backend: ASR config found:");
-                        println!("  - ID: {}", cfg.id);
-                        println!("  - Service Provider: {}", cfg.service_provider);
-                        println!("  - Has Local Endpoint: {}", cfg.local_endpoint.is_some());
-                        println!("  - Has Local API Key: {}", cfg.local_api_key.is_some());
-                        println!("  - Has Cloud Endpoint: {}", cfg.cloud_endpoint.is_some());
-                        println!("  - Has Cloud API Key: {}", cfg.cloud_api_key.is_some());
-                        println!("  - Created At: {}", cfg.created_at);
-                        println!("  - Updated At: {}", cfg.updated_at);
+                        info!("✅ Backend: ASR config found:");
+                        debug!("  - ID: {}", cfg.id);
+                        debug!("  - Service Provider: {}", cfg.service_provider);
+                        debug!("  - Has Local Endpoint: {}", cfg.local_endpoint.is_some());
+                        debug!("  - Has Local API Key: {}", cfg.local_api_key.is_some());
+                        debug!("  - Has Cloud Endpoint: {}", cfg.cloud_endpoint.is_some());
+                        debug!("  - Has Cloud API Key: {}", cfg.cloud_api_key.is_some());
+                        debug!("  - Created At: {}", cfg.created_at);
+                        debug!("  - Updated At: {}", cfg.updated_at);
                     } else {
-                        println!("📥 Backend: No ASR config found in database");
+                        info!("📥 Backend: No ASR config found in database");
                     }
-                    Ok(config)
+                    Ok(config.map(|c| c.masked()))
                 },
                 Err(e) => {
-                    println!("❌ Backend: Database query failed: {}", e);
-                    Err(format!("Failed to get ASR config: {}", e))
+                    error!("❌ Backend: Database query failed: {}", e);
+                    Err(CommandError::Database(format!("Failed to get ASR config: {}", e)))
                 },
             }
         }
         None => {
-            println!("❌ Backend: Database not initialized");
-            Err("Database not initialized".to_string())
+            error!("❌ Backend: Database not initialized");
+            Err(CommandError::DatabaseNotInitialized)
         },
     }
 }
@@ -206,7 +428,13 @@ pub async fn get_asr_config(
 pub async fn save_asr_config(
     db_state: State<'_, DatabaseState>,
     request: AsrConfigRequest,
-) -> Result<crate::database::AsrConfig, String> {
+) -> Result<crate::database::AsrConfig, CommandError> {
+    let cloud_provider = match request.cloud_provider.as_deref() {
+        Some(provider) => Some(CloudAsrProvider::parse(provider)
+            .ok_or_else(|| CommandError::config_invalid("cloud_provider", format!("Unknown cloud ASR provider: {}", provider)))?),
+        None => None,
+    };
+
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
@@ -215,14 +443,16 @@ pub async fn save_asr_config(
     match db {
         Some(database) => {
             // Debug: Log the values being saved
-            println!("💾 Rust: Saving ASR config:");
-            println!("  - service_provider: {}", request.service_provider);
-            println!("  - whisper_model: {:?}", request.whisper_model);
-            println!("  - local_api_key present: {}", request.local_api_key.is_some());
-            println!("  - local_api_key length: {:?}", request.local_api_key.as_ref().map(|k| k.len()));
-            println!("  - local_api_key preview: {:?}", request.local_api_key.as_ref().map(|k| &k[..k.len().min(20)]));
-            println!("  - cloud_api_key present: {}", request.cloud_api_key.is_some());
-            println!("  - cloud_api_key length: {:?}", request.cloud_api_key.as_ref().map(|k| k.len()));
+            debug!("💾 Rust: Saving ASR config:");
+            debug!("  - service_provider: {}", request.service_provider);
+            debug!("  - whisper_model: {:?}", request.whisper_model);
+            debug!("  - enable_vad: {:?}", request.enable_vad);
+            debug!("  - cloud_provider: {:?}, cloud_model: {:?}, cloud_language: {:?}",
+                request.cloud_provider, request.cloud_model, request.cloud_language);
+            debug!("  - local_api_key present: {}, length: {}",
+                request.local_api_key.is_some(), request.local_api_key.as_ref().map_or(0, |k| k.len()));
+            debug!("  - cloud_api_key present: {}, length: {}",
+                request.cloud_api_key.is_some(), request.cloud_api_key.as_ref().map_or(0, |k| k.len()));
 
             match database.save_asr_config(
                 &request.service_provider,
@@ -231,30 +461,72 @@ pub async fn save_asr_config(
                 request.cloud_endpoint.as_deref(),
                 request.cloud_api_key.as_deref(),
                 request.whisper_model.as_deref(),
+                request.enable_vad,
+                cloud_provider.map(|p| p.as_str()),
+                request.cloud_model.as_deref(),
+                request.cloud_language.as_deref(),
             ).await {
                 Ok(config) => {
-                    println!("✅ Rust: ASR config saved successfully");
-                    Ok(config)
+                    info!("✅ Rust: ASR config saved successfully");
+                    Ok(config.masked())
                 },
                 Err(e) => {
-                    println!("❌ Rust: Failed to save ASR config: {}", e);
-                    Err(format!("Failed to save ASR config: {}", e))
+                    error!("❌ Rust: Failed to save ASR config: {}", e);
+                    Err(CommandError::Database(format!("Failed to save ASR config: {}", e)))
                 },
             }
         }
         None => {
-            println!("❌ Rust: Database not initialized");
-            Err("Database not initialized".to_string())
+            error!("❌ Rust: Database not initialized");
+            Err(CommandError::DatabaseNotInitialized)
         },
     }
 }
 
+/// 返回实际的 ASR 尝试顺序，第一个为主处理器
+#[tauri::command]
+pub async fn get_asr_fallback_chain() -> Result<Vec<crate::voice_assistant::ProcessorType>, CommandError> {
+    Ok(crate::voice_assistant::coordinator::effective_asr_processor_chain().await?)
+}
+
+/// 保存主处理器之后的备用顺序；None 和空列表都表示不使用备用处理器，云端备用需要在这里显式添加
+#[tauri::command]
+pub async fn save_asr_fallback_chain(
+    db_state: State<'_, DatabaseState>,
+    chain: Option<Vec<crate::voice_assistant::ProcessorType>>,
+) -> Result<Vec<crate::voice_assistant::ProcessorType>, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    let chain_json = chain
+        .as_ref()
+        .map(serde_json::to_string)
+        .transpose()
+        .map_err(|e| CommandError::config_invalid("fallback_chain", format!("Failed to serialize ASR fallback chain: {}", e)))?;
+    let saved = database
+        .save_asr_fallback_chain(chain_json.as_deref())
+        .await
+        .map_err(|e| CommandError::Database(format!("Failed to save ASR fallback chain: {}", e)))?;
+    if saved.is_none() {
+        return Err(CommandError::config_invalid("asr_config", "Save an ASR configuration before setting the fallback chain".to_string()));
+    }
+    info!("✅ ASR fallback chain saved: {:?}", chain);
+
+    if let Err(e) = crate::voice_assistant::coordinator::refresh_running_voice_assistant().await {
+        warn!("⚠️ Failed to refresh VoiceAssistant after saving ASR fallback chain: {}", e);
+    }
+    get_asr_fallback_chain().await
+}
+
 // Translation Configuration commands
 #[tauri::command]
 pub async fn get_translation_config(
     db_state: State<'_, DatabaseState>,
     provider: String,
-) -> Result<Option<crate::database::TranslationConfig>, String> {
+) -> Result<Option<crate::database::TranslationConfig>, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
@@ -262,11 +534,11 @@ pub async fn get_translation_config(
     match db {
         Some(database) => {
             match database.get_translation_config(&provider).await {
-                Ok(config) => Ok(config),
-                Err(e) => Err(format!("Failed to get translation config: {}", e)),
+                Ok(config) => Ok(config.map(|c| c.masked())),
+                Err(e) => Err(CommandError::Database(format!("Failed to get translation config: {}", e))),
             }
         }
-        None => Err("Database not initialized".to_string()),
+        None => Err(CommandError::DatabaseNotInitialized),
     }
 }
 
@@ -274,172 +546,1103 @@ pub async fn get_translation_config(
 pub async fn save_translation_config(
     db_state: State<'_, DatabaseState>,
     request: TranslationConfigRequest,
-) -> Result<crate::database::TranslationConfig, String> {
+) -> Result<crate::database::TranslationConfig, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
     };
     match db {
         Some(database) => {
+            use crate::voice_assistant::translate::MAX_TRANSLATE_TIMEOUT_SECS;
+            if let Some(timeout) = request.timeout_secs {
+                if !(1..=MAX_TRANSLATE_TIMEOUT_SECS).contains(&timeout) {
+                    return Err(CommandError::config_invalid("timeout_secs", format!("Translation timeout must be between 1 and {} seconds", MAX_TRANSLATE_TIMEOUT_SECS)));
+                }
+            }
+            let target_language = normalize_language(request.target_language.as_deref());
+            let source_language = normalize_language(request.source_language.as_deref());
             match database.save_translation_config(
                 &request.provider,
                 request.api_key.as_deref(),
                 request.endpoint.as_deref(),
+                target_language.as_deref(),
+                source_language.as_deref(),
+                request.model.as_deref().map(str::trim).filter(|m| !m.is_empty()),
+                request.timeout_secs,
             ).await {
-                Ok(config) => Ok(config),
-                Err(e) => Err(format!("Failed to save translation config: {}", e)),
+                Ok(config) => Ok(config.masked()),
+                Err(e) => Err(CommandError::Database(format!("Failed to save translation config: {}", e))),
             }
         }
-        None => Err("Database not initialized".to_string()),
+        None => Err(CommandError::DatabaseNotInitialized),
     }
 }
 
-// History commands
+/// 请求里没给时使用已保存的 Ollama 地址/模型，再回退到默认值
+async fn resolve_ollama_settings(endpoint: Option<String>, model: Option<String>) -> Result<(String, String), CommandError> {
+    use crate::voice_assistant::translate::ollama::{default_ollama_model, default_ollama_url};
+
+    let saved = get_translation_config_internal().await.map_err(CommandError::Database)?
+        .into_iter()
+        .find(|c| c.provider == "ollama");
+    let non_empty = |v: Option<String>| v.map(|s| s.trim().to_string()).filter(|s| !s.is_empty());
+
+    let endpoint = non_empty(endpoint)
+        .or_else(|| non_empty(saved.as_ref().and_then(|c| c.endpoint.clone())))
+        .unwrap_or_else(default_ollama_url);
+    let model = non_empty(model)
+        .or_else(|| non_empty(saved.as_ref().and_then(|c| c.model.clone())))
+        .unwrap_or_else(default_ollama_model);
+    Ok((endpoint, model))
+}
+
+/// 列出 Ollama 已拉取的模型，供设置页下拉选择
 #[tauri::command]
-pub async fn add_history_record(
+pub async fn list_ollama_models(
+    endpoint: Option<String>,
+) -> Result<Vec<crate::voice_assistant::translate::ollama::OllamaModel>, CommandError> {
+    let (endpoint, _) = resolve_ollama_settings(endpoint, None).await?;
+    Ok(crate::voice_assistant::translate::ollama::list_models(&endpoint).await?)
+}
+
+/// 检查 Ollama 是否可达以及配置的模型是否已拉取
+#[tauri::command]
+pub async fn test_ollama_connection(
+    endpoint: Option<String>,
+    model: Option<String>,
+) -> Result<String, CommandError> {
+    use crate::voice_assistant::translate::ollama::{list_models, model_matches, ollama_base_url};
+
+    let (endpoint, model) = resolve_ollama_settings(endpoint, model).await?;
+    info!("🦙 Testing Ollama connection: {} (model: {})", ollama_base_url(&endpoint), model);
+
+    let models = list_models(&endpoint).await?;
+    if models.iter().any(|m| model_matches(&m.name, &model)) {
+        Ok(format!("✅ Ollama is reachable and model {} is available ({} model(s) installed)", model, models.len()))
+    } else if models.is_empty() {
+        Err(CommandError::model_not_found(&model, format!("Ollama is reachable but no models are pulled — run `ollama pull {}`", model)))
+    } else {
+        Err(CommandError::model_not_found(&model, format!("Model {} not pulled — run `ollama pull {}`", model, model)))
+    }
+}
+
+/// 返回未脱敏的 API key，仅在用户明确点击"显示"时调用
+#[tauri::command]
+pub async fn reveal_api_key(
     db_state: State<'_, DatabaseState>,
-    request: HistoryRequest,
-) -> Result<crate::database::HistoryRecord, String> {
+    key_type: String,
+    provider: Option<String>,
+    confirmed: bool,
+) -> Result<Option<String>, CommandError> {
+    if !confirmed {
+        return Err(CommandError::Unauthorized("Revealing an API key requires explicit confirmation".to_string()));
+    }
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    debug!("🔓 Revealing {} API key on user request", key_type);
+    match key_type.as_str() {
+        "local" | "cloud" => {
+            let config = database.get_asr_config().await
+                .map_err(|e| CommandError::Database(format!("Failed to get ASR config: {}", e)))?;
+            Ok(config.and_then(|c| if key_type == "local" { c.local_api_key } else { c.cloud_api_key }))
+        }
+        "translation" => {
+            let provider = provider.ok_or_else(|| CommandError::config_invalid("provider", "Provider is required for translation keys".to_string()))?;
+            let config = database.get_translation_config(&provider).await
+                .map_err(|e| CommandError::Database(format!("Failed to get translation config: {}", e)))?;
+            Ok(config.and_then(|c| c.api_key))
+        }
+        other => Err(CommandError::config_invalid("key_type", format!("Unknown key type: {}", other))),
+    }
+}
+
+// Runtime settings commands
+#[tauri::command]
+pub async fn get_app_settings() -> Result<crate::voice_assistant::settings::Settings, CommandError> {
+    Ok(crate::voice_assistant::settings::current())
+}
+
+/// 保存一项运行时设置并立即生效，空值恢复默认
+#[tauri::command]
+pub async fn set_app_setting(
+    db_state: State<'_, DatabaseState>,
+    key: String,
+    value: String,
+) -> Result<crate::voice_assistant::settings::Settings, CommandError> {
+    use crate::voice_assistant::settings;
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    let updated = settings::save(&database, &key, &value).await?;
+    info!("⚙️ App setting {} = {:?}", key, value);
+
+    // 已加载的 Whisper 模型用新后端重新加载
+    if key == settings::WHISPER_BACKEND {
+        let backend = updated.whisper_backend().unwrap_or_else(crate::voice_assistant::global_whisper::auto_backend);
+        if let Err(e) = crate::voice_assistant::global_whisper::set_whisper_backend(backend).await {
+            warn!("⚠️ Failed to apply Whisper backend setting: {}", e);
+        }
+    }
+    if matches!(key.as_str(), settings::HTTP_API_ENABLED | settings::HTTP_API_PORT) {
+        if let Err(e) = crate::voice_assistant::http_api::apply_settings().await {
+            warn!("⚠️ Failed to apply local HTTP API setting: {}", e);
+        }
+    }
+    if let Err(e) = crate::voice_assistant::coordinator::refresh_running_voice_assistant().await {
+        warn!("⚠️ Failed to refresh voice assistant after settings change: {}", e);
+    }
+
+    Ok(settings::current())
+}
+
+/// 设置本地 Whisper 的解码方式，下一次识别即生效（包括 worker 进程）并持久化。
+/// 未提供的 beam_size / temperature / no_speech_threshold 恢复默认值
+#[tauri::command]
+pub async fn set_whisper_decoding(
+    db_state: State<'_, DatabaseState>,
+    strategy: String,
+    beam_size: Option<u32>,
+    temperature: Option<f32>,
+    no_speech_threshold: Option<f32>,
+) -> Result<crate::voice_assistant::asr::whisper_rs::WhisperDecoding, CommandError> {
+    use crate::voice_assistant::settings;
+
+    let values = [
+        (settings::WHISPER_DECODING_STRATEGY, strategy),
+        (settings::WHISPER_BEAM_SIZE, beam_size.map(|v| v.to_string()).unwrap_or_default()),
+        (settings::WHISPER_TEMPERATURE, temperature.map(|v| v.to_string()).unwrap_or_default()),
+        (settings::WHISPER_NO_SPEECH_THRESHOLD, no_speech_threshold.map(|v| v.to_string()).unwrap_or_default()),
+    ];
+
+    // 先全部校验，避免只保存了一部分
+    let mut candidate = settings::current();
+    for (key, value) in &values {
+        if !value.trim().is_empty() || *key == settings::WHISPER_DECODING_STRATEGY {
+            candidate.apply(key, value).map_err(|e| CommandError::config_invalid(key, e))?;
+        }
+    }
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+    for (key, value) in &values {
+        settings::save(&database, key, value).await?;
+    }
+
+    let decoding = settings::current().whisper_decoding;
+    info!("⚙️ Whisper decoding: {:?}", decoding);
+    Ok(decoding)
+}
+
+/// 设置本地 Whisper 的默认提示词，用来引导人名、术语的写法；空字符串清除
+#[tauri::command]
+pub async fn set_whisper_prompt(
+    db_state: State<'_, DatabaseState>,
+    prompt: String,
+) -> Result<Option<String>, CommandError> {
+    use crate::voice_assistant::settings;
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+    let updated = settings::save(&database, settings::DEFAULT_PROMPT, &prompt).await?;
+    info!("💬 Whisper prompt {}", if updated.default_prompt.is_some() { "updated" } else { "cleared" });
+    Ok(updated.default_prompt)
+}
+
+/// 设置本地 Whisper 的段落可信度阈值（0.0 - 1.0），低于该值的段落不输出；0 关闭过滤
+#[tauri::command]
+pub async fn set_whisper_min_segment_confidence(
+    db_state: State<'_, DatabaseState>,
+    threshold: f32,
+) -> Result<f32, CommandError> {
+    use crate::voice_assistant::settings;
+
+    let value = threshold.to_string();
+    settings::current()
+        .apply(settings::WHISPER_MIN_SEGMENT_CONFIDENCE, &value)
+        .map_err(|e| CommandError::config_invalid(settings::WHISPER_MIN_SEGMENT_CONFIDENCE, e))?;
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+    let updated = settings::save(&database, settings::WHISPER_MIN_SEGMENT_CONFIDENCE, &value).await?;
+
+    // 运行中的助手重新获取处理器时同步新阈值
+    if let Err(e) = crate::voice_assistant::coordinator::refresh_running_voice_assistant().await {
+        warn!("⚠️ Failed to refresh voice assistant after settings change: {}", e);
+    }
+    info!("🧹 Whisper minimum segment confidence: {}", updated.whisper_min_segment_confidence);
+    Ok(updated.whisper_min_segment_confidence)
+}
+
+// Post-processing Configuration commands
+#[tauri::command]
+pub async fn get_postprocess_config(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<crate::database::PostProcessConfig>, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
     };
     match db {
         Some(database) => {
-            let record = NewHistoryRecord {
-                record_type: request.record_type,
-                input_text: request.input_text,
-                output_text: request.output_text,
-                audio_file_path: request.audio_file_path,
-                processor_type: request.processor_type,
-                processing_time_ms: request.processing_time_ms,
-                success: request.success,
-                error_message: request.error_message,
-            };
-
-            match database.add_history_record(record).await {
-                Ok(history) => {
-                    // Emit events to notify frontend of new data
-                    crate::voice_assistant::coordinator::emit_new_history_record_event();
-                    crate::voice_assistant::coordinator::emit_service_status_updated_event();
-                    Ok(history)
-                },
-                Err(e) => Err(format!("Failed to add history record: {}", e)),
+            match database.get_postprocess_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(CommandError::Database(format!("Failed to get post-processing config: {}", e))),
             }
         }
-        None => Err("Database not initialized".to_string()),
+        None => Err(CommandError::DatabaseNotInitialized),
     }
 }
 
 #[tauri::command]
-pub async fn get_history_records(
+pub async fn save_postprocess_config(
     db_state: State<'_, DatabaseState>,
-    limit: Option<i64>,
-    record_type: Option<String>,
-) -> Result<Vec<crate::database::HistoryRecord>, String> {
+    request: PostProcessConfigRequest,
+) -> Result<crate::database::PostProcessConfig, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
     };
     match db {
         Some(database) => {
-            match database.get_history_records(limit, record_type.as_deref()).await {
-                Ok(records) => Ok(records),
-                Err(e) => Err(format!("Failed to get history records: {}", e)),
+            match database.save_postprocess_config(
+                request.convert_to_simplified,
+                request.add_symbol,
+                request.optimize_result,
+                request.optimize_timeout_ms
+                    .unwrap_or(crate::voice_assistant::settings::current().optimize_timeout_ms as i64)
+                    .max(0),
+            ).await {
+                Ok(config) => {
+                    // 正在运行的助手立即使用新的后处理选项
+                    if let Err(e) = crate::voice_assistant::coordinator::refresh_running_voice_assistant().await {
+                        warn!("⚠️ Failed to refresh VoiceAssistant after saving post-processing config: {}", e);
+                    }
+                    Ok(config)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to save post-processing config: {}", e))),
             }
         }
-        None => Err("Database not initialized".to_string()),
+        None => Err(CommandError::DatabaseNotInitialized),
     }
 }
 
+// Audio pre-processing configuration commands
 #[tauri::command]
-pub async fn get_history_stats(
-    db_state: State<'_, DatabaseState>
-) -> Result<(i64, i64, i64), String> {
+pub async fn get_audio_prep_config(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<crate::database::AudioPrepConfig>, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
     };
     match db {
         Some(database) => {
-            match database.get_history_stats().await {
-                Ok(stats) => Ok(stats),
-                Err(e) => Err(format!("Failed to get history stats: {}", e)),
+            match database.get_audio_prep_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(CommandError::Database(format!("Failed to get audio pre-processing config: {}", e))),
             }
         }
-        None => Err("Database not initialized".to_string()),
+        None => Err(CommandError::DatabaseNotInitialized),
     }
 }
 
 #[tauri::command]
-pub async fn cleanup_old_records(
+pub async fn save_audio_prep_config(
     db_state: State<'_, DatabaseState>,
-    days: i64,
-) -> Result<u64, String> {
+    request: AudioPrepConfigRequest,
+) -> Result<crate::database::AudioPrepConfig, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
     };
     match db {
         Some(database) => {
-            match database.cleanup_old_records(days).await {
-                Ok(count) => Ok(count),
-                Err(e) => Err(format!("Failed to cleanup old records: {}", e)),
+            match database.save_audio_prep_config(
+                request.normalize_enabled,
+                request.high_pass_enabled,
+                request.noise_gate_enabled,
+            ).await {
+                Ok(config) => {
+                    // 下一次识别立即生效
+                    crate::voice_assistant::audio_prep::set_options(config.options());
+                    Ok(config)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to save audio pre-processing config: {}", e))),
             }
         }
-        None => Err("Database not initialized".to_string()),
+        None => Err(CommandError::DatabaseNotInitialized),
     }
 }
 
-// Simple test command to verify frontend-backend connection
+// Network retry configuration commands
 #[tauri::command]
-pub async fn test_frontend_backend_connection() -> Result<String, String> {
-    println!("🔔 Backend: Frontend-backend connection test received!");
-    Ok("Backend connection successful!".to_string())
+pub async fn get_network_retry_config(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<crate::database::NetworkRetryConfig>, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.get_network_retry_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(CommandError::Database(format!("Failed to get network retry config: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
 }
 
-// Health check command - performed by Rust backend for better debugging
 #[tauri::command]
-pub async fn test_connection_health(
-    request: HealthCheckRequest,
-) -> Result<HealthCheckResponse, String> {
-    println!("🔍 Tauri Backend: Starting health check for: {}", request.endpoint);
-    println!("⏰ Current time: {:?}", chrono::Utc::now());
-    println!("📋 Request details: {:?}", request);
+pub async fn save_network_retry_config(
+    db_state: State<'_, DatabaseState>,
+    request: NetworkRetryConfigRequest,
+) -> Result<crate::database::NetworkRetryConfig, CommandError> {
+    use crate::voice_assistant::net::{MAX_RETRIES_LIMIT, MAX_TIME_BUDGET_MS, MIN_TIME_BUDGET_MS};
 
-    // Build health endpoint URL
-    let health_endpoint = if request.endpoint.ends_with("/inference") {
-        request.endpoint.replace("/inference", "/health")
-    } else if request.endpoint.ends_with("/inference/") {
-        format!("{}health", request.endpoint)
-    } else if request.endpoint.ends_with('/') {
-        format!("{}health", request.endpoint)
-    } else {
-        format!("{}/health", request.endpoint)
-    };
+    if !(0..=MAX_RETRIES_LIMIT as i64).contains(&request.max_retries) {
+        return Err(CommandError::config_invalid("max_retries", format!("max_retries must be between 0 and {}", MAX_RETRIES_LIMIT)));
+    }
+    if !(MIN_TIME_BUDGET_MS..=MAX_TIME_BUDGET_MS).contains(&request.time_budget_ms) {
+        return Err(CommandError::config_invalid("time_budget_ms", format!(
+            "time_budget_ms must be between {} and {}",
+            MIN_TIME_BUDGET_MS, MAX_TIME_BUDGET_MS
+        )));
+    }
 
-    println!("🔗 Testing health endpoint: {}", health_endpoint);
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.save_network_retry_config(request.max_retries, request.time_budget_ms).await {
+                Ok(config) => {
+                    crate::voice_assistant::net::set_retry_policy(config.policy());
+                    Ok(config)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to save network retry config: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
 
-    // Start timing
-    let start_time = std::time::Instant::now();
+// Overlay configuration commands
+#[tauri::command]
+pub async fn get_overlay_config(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<crate::database::OverlayConfig>, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.get_overlay_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(CommandError::Database(format!("Failed to get overlay config: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
 
-    // Create HTTP client with timeout
+#[tauri::command]
+pub async fn save_overlay_config(
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    request: OverlayConfigRequest,
+) -> Result<crate::database::OverlayConfig, CommandError> {
+    use crate::voice_assistant::overlay::{set_overlay_settings, OverlayPosition, OverlaySettings};
+
+    let position = match request.position.as_deref() {
+        Some(value) => Some(
+            OverlayPosition::parse(value).ok_or_else(|| CommandError::config_invalid("position", format!("Unknown overlay position: {}", value)))?,
+        ),
+        None => None,
+    };
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.save_overlay_config(request.enabled, position.map(|p| p.as_str())).await {
+                Ok(config) => {
+                    set_overlay_settings(&app, OverlaySettings::from(&config));
+                    Ok(config)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to save overlay config: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+/// 开关录音浮窗，位置保持不变
+#[tauri::command]
+pub async fn set_overlay_enabled(
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    enabled: bool,
+) -> Result<crate::database::OverlayConfig, CommandError> {
+    save_overlay_config(app, db_state, OverlayConfigRequest { enabled: Some(enabled), position: None }).await
+}
+
+// Dictation command configuration commands
+#[tauri::command]
+pub async fn get_dictation_command_config(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<crate::database::DictationCommandConfig>, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.get_dictation_command_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(CommandError::Database(format!("Failed to get dictation command config: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn save_dictation_command_config(
+    db_state: State<'_, DatabaseState>,
+    request: DictationCommandConfigRequest,
+) -> Result<crate::database::DictationCommandConfig, CommandError> {
+    use crate::voice_assistant::dictation_commands::{set_dictation_commands, validate_commands};
+
+    let commands_json = match &request.commands {
+        Some(commands) => {
+            validate_commands(commands).map_err(|e| CommandError::config_invalid("commands", e.to_string()))?;
+            Some(serde_json::to_string(commands).map_err(|e| CommandError::config_invalid("commands", format!("Invalid dictation commands: {}", e)))?)
+        }
+        None => None,
+    };
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.save_dictation_command_config(request.enabled, commands_json.as_deref()).await {
+                Ok(config) => {
+                    set_dictation_commands(config.enabled, config.commands());
+                    Ok(config)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to save dictation command config: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+/// 默认命令表，供设置页"恢复默认"使用
+#[tauri::command]
+pub async fn get_default_dictation_commands() -> Result<Vec<crate::voice_assistant::dictation_commands::DictationCommand>, CommandError> {
+    Ok(crate::voice_assistant::dictation_commands::default_commands())
+}
+
+// Transcription replacement (glossary) commands
+#[tauri::command]
+pub async fn get_replacements(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<crate::database::TranscriptionReplacement>, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.get_replacements().await {
+                Ok(replacements) => Ok(replacements),
+                Err(e) => Err(CommandError::Database(format!("Failed to get replacements: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn add_replacement(
+    db_state: State<'_, DatabaseState>,
+    request: ReplacementRequest,
+) -> Result<crate::database::TranscriptionReplacement, CommandError> {
+    // Validate (and compile) the pattern before storing it
+    crate::voice_assistant::replacements::ReplacementRule::compile(
+        &request.pattern,
+        &request.replacement,
+        request.is_regex,
+    ).map_err(|e| CommandError::config_invalid("pattern", e.to_string()))?;
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.add_replacement(&request.pattern, &request.replacement, request.is_regex).await {
+                Ok(record) => {
+                    if let Ok(records) = database.get_replacements().await {
+                        crate::voice_assistant::replacements::set_replacements(&records);
+                    }
+                    Ok(record)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to add replacement: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn delete_replacement(
+    db_state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<bool, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.delete_replacement(&id).await {
+                Ok(deleted) => {
+                    if let Ok(records) = database.get_replacements().await {
+                        crate::voice_assistant::replacements::set_replacements(&records);
+                    }
+                    Ok(deleted)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to delete replacement: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+// Per-application output profile commands
+#[tauri::command]
+pub async fn get_output_profiles(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<crate::database::OutputProfileRecord>, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.get_output_profiles().await {
+                Ok(profiles) => Ok(profiles),
+                Err(e) => Err(CommandError::Database(format!("Failed to get output profiles: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn add_output_profile(
+    db_state: State<'_, DatabaseState>,
+    request: OutputProfileRequest,
+) -> Result<crate::database::OutputProfileRecord, CommandError> {
+    request.validate().map_err(|e| CommandError::config_invalid("output_profile", e))?;
+    let typing_delays_json = request.typing_delays_json().map_err(|e| CommandError::config_invalid("typing_delays", e))?;
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.add_output_profile(
+                request.app_pattern.trim(),
+                request.injection_method.trim(),
+                &request.trailing_text,
+                request.capitalize_sentences,
+                typing_delays_json.as_deref(),
+            ).await {
+                Ok(record) => {
+                    if let Ok(records) = database.get_output_profiles().await {
+                        crate::voice_assistant::output_profiles::set_output_profiles(&records);
+                    }
+                    Ok(record)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to add output profile: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn update_output_profile(
+    db_state: State<'_, DatabaseState>,
+    id: String,
+    request: OutputProfileRequest,
+) -> Result<crate::database::OutputProfileRecord, CommandError> {
+    request.validate().map_err(|e| CommandError::config_invalid("output_profile", e))?;
+    let typing_delays_json = request.typing_delays_json().map_err(|e| CommandError::config_invalid("typing_delays", e))?;
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.update_output_profile(
+                &id,
+                request.app_pattern.trim(),
+                request.injection_method.trim(),
+                &request.trailing_text,
+                request.capitalize_sentences,
+                typing_delays_json.as_deref(),
+            ).await {
+                Ok(Some(record)) => {
+                    if let Ok(records) = database.get_output_profiles().await {
+                        crate::voice_assistant::output_profiles::set_output_profiles(&records);
+                    }
+                    Ok(record)
+                }
+                Ok(None) => Err(CommandError::Other(format!("Output profile not found: {}", id))),
+                Err(e) => Err(CommandError::Database(format!("Failed to update output profile: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn delete_output_profile(
+    db_state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<bool, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.delete_output_profile(&id).await {
+                Ok(deleted) => {
+                    if let Ok(records) = database.get_output_profiles().await {
+                        crate::voice_assistant::output_profiles::set_output_profiles(&records);
+                    }
+                    Ok(deleted)
+                }
+                Err(e) => Err(CommandError::Database(format!("Failed to delete output profile: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+/// 当前前台应用的名称，方便用户填写匹配规则
+#[tauri::command]
+pub async fn get_focused_app_name() -> Result<Option<String>, CommandError> {
+    tokio::task::spawn_blocking(crate::voice_assistant::output_profiles::detect_focused_app)
+        .await
+        .map_err(|e| CommandError::Other(format!("Failed to detect focused app: {}", e)))
+}
+
+// History commands
+#[tauri::command]
+pub async fn add_history_record(
+    db_state: State<'_, DatabaseState>,
+    request: HistoryRequest,
+) -> Result<crate::database::HistoryRecord, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            let record = NewHistoryRecord {
+                record_type: request.record_type,
+                input_text: request.input_text,
+                output_text: request.output_text,
+                audio_file_path: request.audio_file_path,
+                processor_type: request.processor_type,
+                processing_time_ms: request.processing_time_ms,
+                audio_duration_ms: request.audio_duration_ms,
+                success: request.success,
+                error_message: request.error_message,
+                translation_skipped: false,
+                detected_language: None,
+                confidence: None,
+                segments_json: None,
+            };
+            record.validate().map_err(|e| CommandError::config_invalid("record", e))?;
+
+            match database.add_history_record(record).await {
+                Ok(history) => {
+                    // Emit events to notify frontend of new data
+                    crate::voice_assistant::coordinator::emit_new_history_record_event();
+                    crate::voice_assistant::coordinator::emit_service_status_updated_event();
+                    Ok(history)
+                },
+                Err(e) => Err(CommandError::Database(format!("Failed to add history record: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn get_history_records(
+    db_state: State<'_, DatabaseState>,
+    limit: Option<i64>,
+    record_type: Option<String>,
+    query: Option<String>,
+    search: Option<String>,
+    date_from: Option<chrono::DateTime<chrono::Utc>>,
+    date_to: Option<chrono::DateTime<chrono::Utc>>,
+    success: Option<bool>,
+    offset: Option<i64>,
+    detected_language: Option<String>,
+) -> Result<crate::database::HistoryPage, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let filter = crate::database::HistoryQuery {
+        // `search` is accepted as an alias of `query`
+        query: query.or(search),
+        record_type,
+        date_from,
+        date_to,
+        success,
+        detected_language,
+        limit,
+        offset,
+    };
+    filter.validate().map_err(|e| CommandError::config_invalid("filter", e))?;
+    match db {
+        Some(database) => {
+            match database.search_history_records(&filter).await {
+                Ok(page) => Ok(page),
+                Err(e) => Err(CommandError::Database(format!("Failed to get history records: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn get_history_stats(
+    db_state: State<'_, DatabaseState>
+) -> Result<(i64, i64, i64), CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.get_history_stats().await {
+                Ok(stats) => Ok(stats),
+                Err(e) => Err(CommandError::Database(format!("Failed to get history stats: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn cleanup_old_records(
+    db_state: State<'_, DatabaseState>,
+    days: i64,
+) -> Result<u64, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.cleanup_old_records(days).await {
+                Ok(count) => Ok(count),
+                Err(e) => Err(CommandError::Database(format!("Failed to cleanup old records: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+#[tauri::command]
+pub async fn get_history_audio(
+    db_state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<String, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    let record = database.get_history_record(&id).await
+        .map_err(|e| CommandError::Database(format!("Failed to get history record: {}", e)))?
+        .ok_or_else(|| format!("History record not found: {}", id))?;
+
+    let audio_path = record.audio_file_path
+        .ok_or_else(|| CommandError::Audio("Audio missing: no audio file was saved for this record".to_string()))?;
+
+    match std::fs::read(&audio_path) {
+        Ok(bytes) => Ok(STANDARD.encode(bytes)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            // The WAV was deleted out from under us, drop the dangling reference
+            warn!("⚠️ Audio file missing for record {}: {}", id, audio_path);
+            let _ = database.clear_history_audio_path(&id).await;
+            Err(CommandError::Audio(format!("Audio missing: {} no longer exists", audio_path)))
+        }
+        Err(e) => Err(CommandError::Audio(format!("Failed to read audio file: {}", e))),
+    }
+}
+
+#[tauri::command]
+pub async fn export_history_record(
+    db_state: State<'_, DatabaseState>,
+    id: String,
+    format: String,
+) -> Result<String, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    let record = database.get_history_record(&id).await
+        .map_err(|e| CommandError::Database(format!("Failed to get history record: {}", e)))?
+        .ok_or_else(|| format!("History record not found: {}", id))?;
+
+    let audio_path = record.audio_file_path.as_ref()
+        .map(std::path::PathBuf::from)
+        .filter(|p| p.exists());
+
+    // Write next to the audio file when we have one, otherwise into the recordings dir
+    let export_dir = audio_path.as_ref()
+        .and_then(|p| p.parent().map(|d| d.to_path_buf()))
+        .unwrap_or_else(recordings::recordings_dir);
+    std::fs::create_dir_all(&export_dir)
+        .map_err(|e| format!("Failed to create export directory: {}", e))?;
+
+    let base_name = audio_path.as_ref()
+        .and_then(|p| p.file_stem().map(|s| s.to_string_lossy().to_string()))
+        .unwrap_or_else(|| format!("history_{}", record.id));
+    let text = record.output_text.clone().unwrap_or_default();
+
+    let (extension, contents) = match format.to_lowercase().as_str() {
+        "txt" => ("txt", text),
+        "srt" => {
+            // 会话记录按保存的每段时间输出，普通听写只有一段
+            let segments = record.segments_json.as_deref()
+                .and_then(crate::voice_assistant::transcript::parse_segments)
+                .filter(|segments| !segments.is_empty());
+            let contents = match segments {
+                Some(segments) => crate::voice_assistant::transcript::render_srt(&segments),
+                None => {
+                    let duration_ms = audio_path.as_ref()
+                        .and_then(|p| hound::WavReader::open(p).ok())
+                        .map(|r| r.duration() as i64 * 1000 / r.spec().sample_rate.max(1) as i64)
+                        .or(record.audio_duration_ms)
+                        .unwrap_or(0)
+                        .max(1000);
+                    format!("1\n00:00:00,000 --> {}\n{}\n", crate::voice_assistant::transcript::format_srt_timestamp(duration_ms), text)
+                }
+            };
+            ("srt", contents)
+        }
+        "json" => {
+            let json = serde_json::to_string_pretty(&record)
+                .map_err(|e| format!("Failed to serialize record: {}", e))?;
+            ("json", json)
+        }
+        other => return Err(CommandError::config_invalid("format", format!("Unsupported export format: {} (expected txt, srt or json)", other))),
+    };
+
+    let export_path = export_dir.join(format!("{}.{}", base_name, extension));
+    std::fs::write(&export_path, contents)
+        .map_err(|e| format!("Failed to write export file: {}", e))?;
+
+    info!("📤 Exported history record {} to {:?}", id, export_path);
+    Ok(export_path.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn cleanup_missing_audio_paths(
+    db_state: State<'_, DatabaseState>,
+) -> Result<u64, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    match db {
+        Some(database) => {
+            match database.cleanup_missing_audio_paths().await {
+                Ok(count) => Ok(count),
+                Err(e) => Err(CommandError::Database(format!("Failed to cleanup missing audio paths: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+// Backup commands
+#[tauri::command]
+pub async fn export_app_data(
+    db_state: State<'_, DatabaseState>,
+    path: String,
+    include_history: bool,
+    include_api_keys: bool,
+) -> Result<String, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    let backup = database.export_backup(include_history, include_api_keys).await
+        .map_err(|e| CommandError::Database(format!("Failed to export app data: {}", e)))?;
+
+    let content = serde_json::to_string_pretty(&backup)
+        .map_err(|e| format!("Failed to serialize backup: {}", e))?;
+    std::fs::write(&path, content)
+        .map_err(|e| format!("Failed to write backup file {}: {}", path, e))?;
+
+    info!("💾 Exported app data to {} (history: {}, api keys: {})", path, include_history, include_api_keys);
+    Ok(path)
+}
+
+#[tauri::command]
+pub async fn import_app_data(
+    app: tauri::AppHandle,
+    db_state: State<'_, DatabaseState>,
+    path: String,
+) -> Result<crate::database::BackupImportSummary, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    let content = std::fs::read_to_string(&path)
+        .map_err(|e| format!("Failed to read backup file {}: {}", path, e))?;
+    // 校验失败时直接返回，数据库保持不变
+    let backup = crate::database::AppBackup::parse(&content).map_err(|e| CommandError::config_invalid("backup", e))?;
+
+    let summary = database.import_backup(&backup).await
+        .map_err(|e| CommandError::Database(format!("Failed to import app data: {}", e)))?;
+    info!("📥 Imported app data from {}: {:?}", path, summary);
+
+    if let Err(e) = crate::voice_assistant::replacements::reload_replacements().await {
+        warn!("⚠️ Failed to reload replacements after import: {}", e);
+    }
+    if let Err(e) = crate::voice_assistant::audio_prep::reload_audio_prep_config().await {
+        warn!("⚠️ Failed to reload audio pre-processing config after import: {}", e);
+    }
+    if let Err(e) = crate::voice_assistant::net::reload_retry_config().await {
+        warn!("⚠️ Failed to reload network retry config after import: {}", e);
+    }
+    if let Err(e) = crate::voice_assistant::overlay::reload_overlay_config(&app).await {
+        warn!("⚠️ Failed to reload overlay config after import: {}", e);
+    }
+    if let Err(e) = crate::voice_assistant::dictation_commands::reload_dictation_commands().await {
+        warn!("⚠️ Failed to reload dictation commands after import: {}", e);
+    }
+    if let Err(e) = crate::voice_assistant::output_profiles::reload_output_profiles().await {
+        warn!("⚠️ Failed to reload output profiles after import: {}", e);
+    }
+    if let Err(e) = crate::voice_assistant::coordinator::refresh_running_voice_assistant().await {
+        warn!("⚠️ Failed to refresh VoiceAssistant after import: {}", e);
+    }
+
+    Ok(summary)
+}
+
+// Simple test command to verify frontend-backend connection
+#[tauri::command]
+pub async fn test_frontend_backend_connection() -> Result<String, CommandError> {
+    info!("🔔 Backend: Frontend-backend connection test received!");
+    Ok("Backend connection successful!".to_string())
+}
+
+// Health check command - performed by Rust backend for better debugging
+#[tauri::command]
+pub async fn test_connection_health(
+    request: HealthCheckRequest,
+) -> Result<HealthCheckResponse, CommandError> {
+    debug!("🔍 Tauri Backend: Starting health check for: {}", request.endpoint);
+    warn!("⏰ Current time: {:?}", chrono::Utc::now());
+
+    // Start timing
+    let start_time = std::time::Instant::now();
+
+    // Create HTTP client with timeout
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(10))
         .build()
         .map_err(|e| {
-            println!("❌ Failed to create HTTP client: {}", e);
-            format!("Failed to create HTTP client: {}", e)
+            error!("❌ Failed to create HTTP client: {}", e);
+            CommandError::from(e)
         })?;
 
+    if let Some(api_key) = request.api_key.as_deref() {
+        info!("🔗 Verifying API key against: {}", request.endpoint);
+        let result = crate::voice_assistant::asr::verify_api_key(&client, &request.endpoint, api_key).await;
+        return Ok(HealthCheckResponse {
+            healthy: result.is_ok(),
+            message: result.map_or_else(|e| e.to_string(), |_| "API key accepted".to_string()),
+            endpoint: request.endpoint,
+            status_code: None,
+            response_time_ms: start_time.elapsed().as_millis() as u64,
+            timestamp: chrono::Utc::now().to_rfc3339(),
+        });
+    }
+
+    // Build health endpoint URL
+    let health_endpoint = if request.endpoint.ends_with("/inference") {
+        request.endpoint.replace("/inference", "/health")
+    } else if request.endpoint.ends_with("/inference/") {
+        format!("{}health", request.endpoint)
+    } else if request.endpoint.ends_with('/') {
+        format!("{}health", request.endpoint)
+    } else {
+        format!("{}/health", request.endpoint)
+    };
+
+    info!("🔗 Testing health endpoint: {}", health_endpoint);
+
     // Make the request
     let response = match client.get(&health_endpoint).send().await {
         Ok(resp) => {
-            println!("📡 HTTP request completed");
+            debug!("📡 HTTP request completed");
             resp
         }
         Err(e) => {
-            println!("❌ HTTP request failed: {}", e);
+            error!("❌ HTTP request failed: {}", e);
             let response_time = start_time.elapsed().as_millis() as u64;
             return Ok(HealthCheckResponse {
                 endpoint: request.endpoint,
@@ -455,15 +1658,15 @@ pub async fn test_connection_health(
     let status_code = response.status();
     let response_time = start_time.elapsed().as_millis() as u64;
 
-    println!("📋 Response status: {}", status_code);
-    println!("⏱️ Response time: {}ms", response_time);
+    debug!("📋 Response status: {}", status_code);
+    info!("⏱️ Response time: {}ms", response_time);
 
     if status_code.is_success() {
         // Try to parse JSON response
         let response_body = response.text().await.unwrap_or_default();
         match serde_json::from_str::<serde_json::Value>(&response_body) {
             Ok(json_data) => {
-                println!("📊 Health check JSON response: {}", json_data);
+                debug!("📊 Health check JSON response: {}", json_data);
 
                 // Extract backend count if available
                 let backend_count = json_data
@@ -478,9 +1681,9 @@ pub async fn test_connection_health(
                     .unwrap_or(false);
 
                 if is_healthy {
-                    println!("✅ Service is healthy!");
+                    info!("✅ Service is healthy!");
                     if let Some(count) = backend_count {
-                        println!("📈 Total backends: {}", count);
+                        debug!("📈 Total backends: {}", count);
                     }
                     return Ok(HealthCheckResponse {
                         endpoint: request.endpoint,
@@ -494,7 +1697,7 @@ pub async fn test_connection_health(
                     let service_status = json_data.get("status")
                         .and_then(|s| s.as_str())
                         .unwrap_or("unknown");
-                    println!("⚠️ Service status: {}", service_status);
+                    warn!("⚠️ Service status: {}", service_status);
                     return Ok(HealthCheckResponse {
                         endpoint: request.endpoint,
                         healthy: false,
@@ -506,8 +1709,8 @@ pub async fn test_connection_health(
                 }
             }
             Err(e) => {
-                println!("❌ Failed to parse JSON: {}", e);
-                println!("📄 Raw response: {}", response_body);
+                error!("❌ Failed to parse JSON: {}", e);
+                debug!("📄 Raw response: {}", response_body);
                 Ok(HealthCheckResponse {
                     endpoint: request.endpoint,
                     healthy: false,
@@ -520,7 +1723,7 @@ pub async fn test_connection_health(
         }
     } else {
         let error_text = response.text().await.unwrap_or_default();
-        println!("❌ HTTP error response: {}", error_text);
+        error!("❌ HTTP error response: {}", error_text);
         Ok(HealthCheckResponse {
             endpoint: request.endpoint,
             healthy: false,
@@ -536,127 +1739,137 @@ pub async fn test_connection_health(
 #[tauri::command]
 pub async fn test_asr_transcription(
     request: AsrTestRequest,
-) -> Result<AsrTestResponse, String> {
-    println!("🎵 Starting ASR transcription test...");
-    println!("📁 Audio file: {}", request.file_name);
-    println!("🔧 Service provider: {}", request.service_provider);
-    println!("🔗 Endpoint: {:?}", request.endpoint);
+) -> Result<AsrTestResponse, CommandError> {
+    info!("🎵 Starting ASR transcription test...");
+    debug!("📁 Audio file: {}", request.file_name);
+    debug!("🔧 Service provider: {}", request.service_provider);
+    debug!("🔗 Endpoint: {:?}", request.endpoint);
 
     let start_time = std::time::Instant::now();
 
     // Decode base64 data
-    let audio_data = match STANDARD.decode(&request.audio_file_data) {
-        Ok(data) => data,
-        Err(e) => {
-            return Ok(AsrTestResponse {
-                success: false,
-                transcription: None,
-                processing_time_ms: start_time.elapsed().as_millis() as u64,
-                file_size: 0,
-                message: format!("Failed to decode base64 data: {}", e),
-                status_code: None,
-            });
-        }
-    };
+    let audio_data = STANDARD.decode(&request.audio_file_data)
+        .map_err(|e| CommandError::Audio(format!("Failed to decode base64 data: {}", e)))?;
 
     let file_size = audio_data.len() as u64;
+    debug!("📊 File size: {} bytes", file_size);
+
+    // Sniff the container from magic bytes and decode to 16kHz mono PCM,
+    // so MP3/OGG/FLAC uploads go through the same pipeline as WAV.
+    // The 10 minute limit is checked while decoding, before a huge file is fully decoded
+    const MAX_DURATION_SECS: f64 = 10.0 * 60.0;
+    use crate::voice_assistant::audio::{decode_bytes_to_samples, resample_to_16k_mono, TARGET_SAMPLE_RATE};
+    let format = crate::voice_assistant::audio_decoder::AudioFormat::detect(&audio_data);
+    let (samples, original_sample_rate) = decode_bytes_to_samples(&audio_data, Some(MAX_DURATION_SECS))
+        .map_err(|e| {
+            error!("❌ Failed to decode audio: {}", e);
+            CommandError::from(e)
+        })?;
+    let samples = resample_to_16k_mono(&samples, original_sample_rate, 1);
+    let duration_secs = samples.len() as f64 / TARGET_SAMPLE_RATE as f64;
 
-    // Check file size (2MB limit)
-    const MAX_FILE_SIZE: u64 = 2 * 1024 * 1024; // 2MB in bytes
-    if file_size > MAX_FILE_SIZE {
-        return Ok(AsrTestResponse {
-            success: false,
-            transcription: None,
-            processing_time_ms: start_time.elapsed().as_millis() as u64,
-            file_size,
-            message: format!("File too large: {} bytes (max: {} bytes)", file_size, MAX_FILE_SIZE),
-            status_code: None,
-        });
-    }
+    // 与热键录音走同一套预处理（归一化/高通/噪声门）
+    let samples = crate::voice_assistant::audio_prep::prepare(&samples, TARGET_SAMPLE_RATE);
 
-    println!("📊 File size: {} bytes", file_size);
-    println!("📖 Successfully decoded {} bytes of audio data", audio_data.len());
+    let audio_data = crate::voice_assistant::audio_decoder::encode_wav(&samples, TARGET_SAMPLE_RATE)?;
+
+    debug!("📖 Decoded {} audio: {:.1}s, {}Hz -> {}Hz mono",
+        format.name(), duration_secs, original_sample_rate, TARGET_SAMPLE_RATE);
 
     // Route to appropriate processor based on service provider
-    match request.service_provider.as_str() {
+    let response = match request.service_provider.as_str() {
         "local" => {
-            println!("🎯 Attempting Local Whisper (whisper-rs) for transcription");
-            println!("⚠️ Note: whisper-rs has known compatibility issues with some CPU configurations");
+            info!("🎯 Attempting Local Whisper (whisper-rs) for transcription");
+            warn!("⚠️ Note: whisper-rs has known compatibility issues with some CPU configurations");
             
             // Try local whisper first, but with immediate fallback if it fails
             match test_local_whisper_transcription(audio_data.clone(), file_size, start_time).await {
                 Ok(response) => {
                     if response.success {
-                        println!("✅ Local whisper succeeded!");
+                        info!("✅ Local whisper succeeded!");
+                        Ok(response)
+                    } else if response.status_code == Some(WHISPER_BUSY_STATUS_CODE) {
+                        // 忙碌不是故障，不切换到云端
                         Ok(response)
                     } else {
-                        println!("❌ Local whisper failed: {}", response.message);
-                        println!("🔄 Auto-switching to Cloud ASR fallback...");
+                        error!("❌ Local whisper failed: {}", response.message);
+                        info!("🔄 Auto-switching to Cloud ASR fallback...");
                         
                         // Try Cloud ASR fallback
-                        if let Some(endpoint) = std::env::var("GROQ_API_ENDPOINT").ok() {
-                            let api_key = std::env::var("GROQ_API_KEY").ok();
-                            test_cloud_asr_transcription(audio_data, file_size, start_time, &endpoint, api_key).await
-                        } else {
-                            println!("⚠️ No Cloud ASR configured");
-                            Ok(response)
+                        match resolve_cloud_fallback().await {
+                            Some(fallback) => fallback.transcribe(audio_data, file_size, start_time).await,
+                            None => {
+                                warn!("⚠️ No Cloud ASR configured");
+                                Ok(response)
+                            }
                         }
                     }
                 }
                 Err(e) => {
-                    println!("❌ Local whisper crashed: {}", e);
-                    println!("🔄 Auto-switching to Cloud ASR fallback...");
+                    error!("❌ Local whisper crashed: {}", e);
+                    info!("🔄 Auto-switching to Cloud ASR fallback...");
                     
                     // Try Cloud ASR fallback
-                    if let Some(endpoint) = std::env::var("GROQ_API_ENDPOINT").ok() {
-                        let api_key = std::env::var("GROQ_API_KEY").ok();
-                        test_cloud_asr_transcription(audio_data, file_size, start_time, &endpoint, api_key).await
-                    } else {
-                        Err(e)
+                    match resolve_cloud_fallback().await {
+                        Some(fallback) => fallback.transcribe(audio_data, file_size, start_time).await,
+                        None => Err(e),
                     }
                 }
             }
         }
         "cloud" => {
-            println!("☁️ Using Cloud ASR for transcription");
-            if let Some(endpoint) = request.endpoint {
-                test_cloud_asr_transcription(audio_data, file_size, start_time, &endpoint, request.api_key).await
-            } else {
-                Ok(AsrTestResponse {
-                    success: false,
-                    transcription: None,
-                    processing_time_ms: start_time.elapsed().as_millis() as u64,
-                    file_size,
-                    message: "No endpoint configured for Cloud ASR".to_string(),
-                    status_code: None,
-                })
+            info!("☁️ Using Cloud ASR for transcription");
+            let max_retries = request.max_retries.unwrap_or_else(|| crate::voice_assistant::net::retry_policy().max_retries);
+            let endpoint = request.endpoint
+                .ok_or_else(|| CommandError::config_invalid("endpoint", "No endpoint configured for Cloud ASR".to_string()))?;
+            let saved_config = get_asr_config_internal().await?.into_iter().next();
+            // 前端只拿得到脱敏 key，测试时换成数据库里保存的真实 key
+            let api_key = match request.api_key {
+                Some(key) if crate::database::crypto::is_masked(&key) => saved_config.as_ref().and_then(|c| c.cloud_api_key.clone()),
+                other => other,
+            };
+            // 请求里没给的格式/模型/语言沿用已保存的配置，方便保存前先测试
+            let mut options = saved_config.as_ref().map(CloudAsrOptions::from_config).unwrap_or_default();
+            if let Some(provider) = request.cloud_provider.as_deref() {
+                options.provider = CloudAsrProvider::parse(provider)
+                    .ok_or_else(|| CommandError::config_invalid("cloud_provider", format!("Unknown cloud ASR provider: {}", provider)))?;
+            }
+            if request.cloud_model.is_some() {
+                options.model = request.cloud_model.filter(|m| !m.trim().is_empty());
             }
+            if request.cloud_language.is_some() {
+                options.language = request.cloud_language.filter(|l| !l.trim().is_empty());
+            }
+            test_cloud_asr_transcription(audio_data, file_size, start_time, &endpoint, api_key, &options, max_retries).await
         }
         other => {
-            println!("❌ Unknown service provider: {}", other);
-            Ok(AsrTestResponse {
-                success: false,
-                transcription: None,
-                processing_time_ms: start_time.elapsed().as_millis() as u64,
-                file_size,
-                message: format!("Unknown service provider: {}", other),
-                status_code: None,
-            })
+            error!("❌ Unknown service provider: {}", other);
+            return Err(CommandError::config_invalid("service_provider", format!("Unknown service provider: {}", other)));
         }
+    }?;
+
+    // 鉴权失败单独作为错误返回，便于前端提示检查 API key
+    if !response.success && matches!(response.status_code, Some(401) | Some(403)) {
+        return Err(CommandError::Unauthorized(response.message));
     }
+
+    Ok(response)
 }
 
 // Local Whisper transcription helper function
+/// 本地 whisper 正在处理其他推理时返回的状态码（HTTP 409 Conflict）
+pub const WHISPER_BUSY_STATUS_CODE: u16 = 409;
+
 async fn test_local_whisper_transcription(
     audio_data: Vec<u8>,
     file_size: u64,
     start_time: std::time::Instant,
-) -> Result<AsrTestResponse, String> {
-    println!("🎯 Starting Local Whisper transcription...");
+) -> Result<AsrTestResponse, CommandError> {
+    info!("🎯 Starting Local Whisper transcription...");
 
     // First, do a quick health check of whisper-rs availability
     if !check_whisper_rs_health().await {
-        println!("❌ Whisper-rs health check failed - known compatibility issue detected");
+        error!("❌ Whisper-rs health check failed - known compatibility issue detected");
         return Ok(AsrTestResponse {
             success: false,
             transcription: None,
@@ -667,45 +1880,25 @@ async fn test_local_whisper_transcription(
         });
     }
 
-    // Create or get global WhisperRS processor
-    let model_path = std::env::var("WHISPER_MODEL_PATH")
-        .ok()
-        .and_then(|path| {
-            if std::path::Path::new(&path).exists() {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .or_else(|| {
-            // Try to find models in the default data directory using cross-platform API
-            let models_dir = crate::utils::platform::get_models_dir();
-
-            // Model preference order for testing (small to large)
-            let model_preferences = [
-                "ggml-small.bin",          // Good balance of speed and accuracy
-                "ggml-base.bin",           // Fastest
-                "ggml-medium.bin",         // Better accuracy
-                "ggml-large-v3-turbo.bin", // Best accuracy
-            ];
-
-            for model in model_preferences {
-                let model_file = models_dir.join(model);
-                if model_file.exists() {
-                    println!("✅ Found available model for testing: {}", model);
-                    return Some(model_file.to_string_lossy().to_string());
-                }
-            }
-            None
-        })
-        .unwrap_or_else(|| {
-            println!("⚠️ No Whisper model found in default directory");
-            println!("💡 Please download a model to {:?}", crate::utils::platform::get_models_dir());
-            println!("📥 Recommended: ggml-small.bin for good performance");
-            "ggml-small.bin".to_string() // Fallback for error message
-        });
+    // 与热键识别使用同一个模型，测试结果才有参考意义
+    let model_path = match crate::voice_assistant::asr::models::resolve_whisper_model(
+        crate::voice_assistant::asr::models::ModelPurpose::Dictation,
+        crate::voice_assistant::asr::models::configured_whisper_model().await.as_deref(),
+    ) {
+        Ok(path) => path,
+        Err(e) => {
+            return Ok(AsrTestResponse {
+                success: false,
+                transcription: None,
+                processing_time_ms: start_time.elapsed().as_millis() as u64,
+                file_size,
+                message: format!("{}. Please download a model in the model manager.", e),
+                status_code: None,
+            });
+        }
+    };
 
-    println!("🎯 Using Whisper model path: {}", model_path);
+    info!("🎯 Using Whisper model path: {}", model_path);
     
     let processor = match crate::voice_assistant::global_whisper::get_or_create_whisper_processor(&model_path).await {
         Ok(processor) => processor,
@@ -722,8 +1915,21 @@ async fn test_local_whisper_transcription(
     };
 
     // Convert audio bytes to WAV format and process
-    let audio_cursor = std::io::Cursor::new(audio_data.clone());
+    let audio_cursor = std::io::Cursor::new(audio_data);
     
+    // whisper 状态不可重入：已有推理时直接返回忙，而不是排队等待
+    let Some(inference) = crate::voice_assistant::global_whisper::try_begin_inference() else {
+        info!("⏳ Local Whisper is busy with another transcription");
+        return Ok(AsrTestResponse {
+            success: false,
+            transcription: None,
+            processing_time_ms: start_time.elapsed().as_millis() as u64,
+            file_size,
+            message: "Local Whisper is busy with another transcription, please try again shortly".to_string(),
+            status_code: Some(WHISPER_BUSY_STATUS_CODE),
+        });
+    };
+
     // Scope the processor lock to avoid holding it across await
     let transcription_result = {
         let processor_guard = processor.lock().unwrap();
@@ -731,47 +1937,24 @@ async fn test_local_whisper_transcription(
             audio_cursor,
             crate::voice_assistant::Mode::Transcriptions,
             "",
-        )
+        ).map(|transcript| transcript.text)
     };
+    drop(inference);
     
     let transcription_result = match transcription_result {
         Ok(result) => {
-            println!("✅ Local Whisper processing succeeded!");
+            info!("✅ Local Whisper processing succeeded!");
             result
         }
         Err(e) => {
-            println!("❌ Local Whisper processing failed: {}", e);
-            println!("🔄 Attempting fallback to Cloud ASR...");
-            
-            // Try fallback to Cloud ASR if available
-            let cloud_endpoint = std::env::var("GROQ_API_ENDPOINT").ok();
-            let cloud_api_key = std::env::var("GROQ_API_KEY").ok();
-            
-            if let (Some(endpoint), Some(api_key)) = (cloud_endpoint, cloud_api_key) {
-                println!("☁️ Using Cloud ASR fallback with Groq");
-                match test_cloud_asr_transcription(audio_data, file_size, start_time, &endpoint, Some(api_key)).await {
-                    Ok(cloud_response) => {
-                        if cloud_response.success {
-                            println!("✅ Cloud ASR fallback succeeded!");
-                            return Ok(cloud_response);
-                        } else {
-                            println!("❌ Cloud ASR fallback also failed: {}", cloud_response.message);
-                        }
-                    }
-                    Err(e) => {
-                        println!("❌ Cloud ASR fallback failed with error: {}", e);
-                    }
-                }
-            } else {
-                println!("⚠️ No Cloud ASR credentials available for fallback");
-            }
-            
+            // 云端兜底由调用方统一处理
+            error!("❌ Local Whisper processing failed: {}", e);
             return Ok(AsrTestResponse {
                 success: false,
                 transcription: None,
                 processing_time_ms: start_time.elapsed().as_millis() as u64,
                 file_size,
-                message: format!("Local Whisper processing failed: {}. Cloud fallback unavailable.", e),
+                message: format!("Local Whisper processing failed: {}", e),
                 status_code: None,
             });
         }
@@ -779,8 +1962,8 @@ async fn test_local_whisper_transcription(
 
     let processing_time = start_time.elapsed().as_millis() as u64;
 
-    println!("✅ Local Whisper transcription completed in {}ms", processing_time);
-    println!("📝 Result: {}", transcription_result);
+    info!("✅ Local Whisper transcription completed in {}ms", processing_time);
+    tracing::trace!("Local Whisper result: {}", crate::voice_assistant::redact(&transcription_result));
 
     Ok(AsrTestResponse {
         success: true,
@@ -792,6 +1975,50 @@ async fn test_local_whisper_transcription(
     })
 }
 
+/// 本地识别失败时使用的云端服务
+struct CloudFallback {
+    endpoint: String,
+    api_key: Option<String>,
+    options: CloudAsrOptions,
+}
+
+impl CloudFallback {
+    async fn transcribe(&self, audio_data: Vec<u8>, file_size: u64, start_time: std::time::Instant) -> Result<AsrTestResponse, CommandError> {
+        let max_retries = crate::voice_assistant::net::retry_policy().max_retries;
+        test_cloud_asr_transcription(audio_data, file_size, start_time, &self.endpoint, self.api_key.clone(), &self.options, max_retries).await
+    }
+}
+
+/// 优先使用设置中保存的云端配置；没有配置时才读取 GROQ_API_ENDPOINT/GROQ_API_KEY 环境变量
+async fn resolve_cloud_fallback() -> Option<CloudFallback> {
+    match get_asr_config_internal().await {
+        Ok(configs) => {
+            let saved = configs.into_iter().next().and_then(|config| {
+                let endpoint = config.cloud_endpoint.clone().filter(|e| !e.trim().is_empty())?;
+                Some(CloudFallback {
+                    endpoint,
+                    api_key: config.cloud_api_key.clone(),
+                    options: CloudAsrOptions::from_config(&config),
+                })
+            });
+            if let Some(fallback) = saved {
+                info!("☁️ Cloud ASR fallback credentials from settings ({})", fallback.endpoint);
+                return Some(fallback);
+            }
+        }
+        Err(e) => warn!("⚠️ Failed to load ASR config for cloud fallback: {}", e),
+    }
+
+    let endpoint = crate::voice_assistant::settings::current().groq_api_endpoint?;
+    info!("☁️ Cloud ASR fallback credentials from groq_api_endpoint setting/GROQ_API_KEY environment");
+    Some(CloudFallback {
+        endpoint,
+        api_key: std::env::var("GROQ_API_KEY").ok(),
+        // GROQ_API_ENDPOINT 兜底走 Groq 的 OpenAI 兼容接口
+        options: CloudAsrOptions::new(CloudAsrProvider::OpenAi).with_model("whisper-large-v3-turbo"),
+    })
+}
+
 // Cloud ASR transcription helper function
 async fn test_cloud_asr_transcription(
     audio_data: Vec<u8>,
@@ -799,67 +2026,57 @@ async fn test_cloud_asr_transcription(
     start_time: std::time::Instant,
     endpoint: &str,
     api_key: Option<String>,
-) -> Result<AsrTestResponse, String> {
-    println!("☁️ Starting Cloud ASR transcription...");
+    options: &CloudAsrOptions,
+    max_retries: u32,
+) -> Result<AsrTestResponse, CommandError> {
+    info!("☁️ Starting Cloud ASR transcription...");
 
     // Create HTTP client
     let client = reqwest::Client::builder()
         .timeout(std::time::Duration::from_secs(30))
         .build()
         .map_err(|e| {
-            println!("❌ Failed to create HTTP client: {}", e);
-            format!("Failed to create HTTP client: {}", e)
+            error!("❌ Failed to create HTTP client: {}", e);
+            CommandError::from(e)
         })?;
 
-    // Create multipart form with audio file
-    let form = reqwest::multipart::Form::new()
-        .part("audio", reqwest::multipart::Part::bytes(audio_data)
-            .file_name("test_audio.wav")
-            .mime_str("audio/wav")
-            .map_err(|e| format!("Failed to create form part: {}", e))?);
-
-    println!("🚀 Sending request to Cloud ASR endpoint: {}", endpoint);
-
-    // Clean API key - remove API_KEY= prefix if present
-    let clean_api_key = match api_key {
-        Some(ref key) => {
-            let trimmed = key.trim();
-            if trimmed.starts_with("API_KEY=") {
-                println!("🔧 Removing API_KEY= prefix from key");
-                Some(trimmed[8..].to_string()) // Remove "API_KEY=" prefix
-            } else {
-                println!("🔧 API key format looks normal");
-                Some(trimmed.to_string())
-            }
-        },
-        None => None,
-    };
+    info!("🚀 Sending {} request to Cloud ASR endpoint: {}", options.provider.as_str(), options.request_url(endpoint));
 
-    let request_builder = client
-        .post(endpoint)
-        .multipart(form);
+    let clean_api_key = api_key.as_deref()
+        .map(crate::voice_assistant::asr::cloud_provider::clean_api_key)
+        .filter(|k| !k.is_empty());
 
-    let request_builder = if let Some(ref key) = clean_api_key {
-        println!("🔑 Sending X-API-Key header");
-        request_builder.header("X-API-Key", key)
+    if clean_api_key.is_some() {
+        match options.provider {
+            CloudAsrProvider::Custom => debug!("🔑 Sending X-API-Key header"),
+            CloudAsrProvider::OpenAi => debug!("🔑 Sending Bearer token"),
+        }
     } else {
-        println!("🔑 No API key will be sent");
-        request_builder
-    };
+        debug!("🔑 No API key will be sent");
+    }
 
-    let response = match request_builder.send().await {
-        Ok(resp) => {
-            println!("📡 HTTP request completed");
-            resp
-        }
+    // 5xx/429 和连接错误按全局设置退避重试，其余 4xx（鉴权/参数错误）直接失败
+    let budget = crate::voice_assistant::net::RetryBudget::new(crate::voice_assistant::net::RetryPolicy {
+        max_retries,
+        ..crate::voice_assistant::net::retry_policy()
+    });
+    // Multipart forms are consumed on send, so rebuild one per attempt
+    let sent = crate::voice_assistant::net::send_with_retry("Cloud ASR", &budget, || {
+        options
+            .build_request(&client, endpoint, clean_api_key.as_deref(), audio_data.clone())
+            .map_err(Into::into)
+    })
+    .await;
+    let (response, attempt) = match sent {
+        Ok(sent) => (sent.response, sent.attempts - 1),
         Err(e) => {
-            println!("❌ HTTP request failed: {}", e);
+            error!("❌ {}", e);
             return Ok(AsrTestResponse {
                 success: false,
                 transcription: None,
                 processing_time_ms: start_time.elapsed().as_millis() as u64,
                 file_size,
-                message: format!("HTTP request failed: {}", e),
+                message: e.to_string(),
                 status_code: None,
             });
         }
@@ -868,25 +2085,24 @@ async fn test_cloud_asr_transcription(
     let status_code = response.status();
     let response_time = start_time.elapsed().as_millis() as u64;
 
-    println!("📋 Response status: {}", status_code);
-    println!("⏱️ Response time: {}ms", response_time);
+    debug!("📋 Response status: {}", status_code);
+    info!("⏱️ Response time: {}ms", response_time);
 
     if !status_code.is_success() {
         let error_text = match response.text().await {
             Ok(text) => text,
             Err(e) => format!("Failed to read error response: {}", e),
         };
-        println!("❌ HTTP error response: {}", error_text);
+        error!("❌ HTTP error response: {}", error_text);
         return Ok(AsrTestResponse {
             success: false,
             transcription: None,
             processing_time_ms: response_time,
             file_size,
-            message: format!("HTTP {} - {}", status_code, error_text),
+            message: format!("HTTP {} - {}{}", status_code, error_text, crate::voice_assistant::net::retry_summary(attempt)),
             status_code: Some(status_code.as_u16()),
         });
     }
-
     // Parse JSON response
     let response_body = match response.text().await {
         Ok(text) => text,
@@ -902,41 +2118,25 @@ async fn test_cloud_asr_transcription(
         }
     };
 
-    println!("📄 Raw response: {}", response_body);
+    debug!("📄 Raw response: {}", response_body);
 
     // Try to extract transcription from response
-    let transcription = if let Ok(json_data) = serde_json::from_str::<serde_json::Value>(&response_body) {
-        println!("📊 Parsed JSON response");
-
-        // Check for structured response format: {"code":0,"msg":"ok","data":"transcription_text"}
-        if let Some(data) = json_data.get("data").and_then(|v| v.as_str()) {
-            Some(data.to_string())
-        }
-        // Try other possible response formats
-        else if let Some(text) = json_data.get("text").and_then(|v| v.as_str()) {
-            Some(text.to_string())
-        } else if let Some(text) = json_data.get("transcription").and_then(|v| v.as_str()) {
-            Some(text.to_string())
-        } else if let Some(text) = json_data.get("result").and_then(|v| v.as_str()) {
-            Some(text.to_string())
-        } else if let Some(result) = json_data.get("result") {
-            if let Some(text) = result.get("text").and_then(|v| v.as_str()) {
-                Some(text.to_string())
-            } else {
-                println!("⚠️ Could not extract transcription from result: {}", result);
-                Some(format!("Complex response: {}", json_data))
-            }
-        } else {
-            println!("⚠️ Unknown JSON format, using raw response");
-            Some(response_body.clone())
-        }
-    } else {
-        println!("⚠️ Response is not JSON, using raw text");
-        Some(response_body.clone())
-    };
+    let transcription = options.parse_response(&response_body);
+    if transcription.is_none() {
+        error!("❌ No transcription text in {} response", options.provider.as_str());
+        return Ok(AsrTestResponse {
+            success: false,
+            transcription: None,
+            processing_time_ms: response_time,
+            file_size,
+            message: "No transcription text in response".to_string(),
+            status_code: Some(status_code.as_u16()),
+        });
+    }
 
     if let Some(ref text) = transcription {
-        println!("✅ Cloud ASR transcription result: {}", text);
+        info!("✅ Cloud ASR transcription received ({} chars)", text.chars().count());
+        tracing::trace!("Cloud ASR result: {}", crate::voice_assistant::redact(text));
     }
 
     Ok(AsrTestResponse {
@@ -944,78 +2144,29 @@ async fn test_cloud_asr_transcription(
         transcription,
         processing_time_ms: response_time,
         file_size,
-        message: "Cloud ASR transcription completed successfully".to_string(),
+        message: format!("Cloud ASR transcription completed successfully{}", crate::voice_assistant::net::retry_summary(attempt)),
         status_code: Some(status_code.as_u16()),
     })
 }
 
 // Helper function to create Local Whisper processor
 #[allow(dead_code)]
-async fn create_local_whisper_processor() -> Result<crate::voice_assistant::asr::whisper_rs::WhisperRSProcessor, String> {
+async fn create_local_whisper_processor() -> Result<crate::voice_assistant::asr::whisper_rs::WhisperRSProcessor, CommandError> {
     use crate::voice_assistant::asr::whisper_rs::{WhisperRSProcessor, WhisperRSConfig, SamplingStrategyConfig};
 
-    // Try to get model path from environment
-    let model_path = std::env::var("WHISPER_MODEL_PATH")
-        .ok()
-        .and_then(|path| {
-            if std::path::Path::new(&path).exists() {
-                Some(path)
-            } else {
-                None
-            }
-        })
-        .or_else(|| {
-            // Try to find models in the default data directory, preferring smaller models for CPU
-            let models_dir = crate::utils::platform::get_models_dir().to_string_lossy().to_string();
-
-            // Model preference order for CPU (smallest to largest)
-            let model_preferences = [
-                "ggml-base.bin",           // ~74MB
-                "ggml-small.bin",          // ~244MB
-                "ggml-medium.bin",         // ~769MB
-                "ggml-large-v3-turbo.bin", // ~1570MB
-            ];
-
-            for model in model_preferences {
-                let model_file = format!("{}/{}", models_dir, model);
-                if std::path::Path::new(&model_file).exists() {
-                    println!("✅ Found CPU-optimized model: {} ({}MB)",
-                            model,
-                            match model {
-                                "ggml-base.bin" => "74",
-                                "ggml-small.bin" => "244",
-                                "ggml-medium.bin" => "769",
-                                "ggml-large-v3-turbo.bin" => "1570",
-                                _ => "unknown",
-                            });
-                    return Some(model_file);
-                }
-            }
-            None
-        })
-        .unwrap_or_else(|| {
-            println!("⚠️ No Whisper model found. Please download a model to {:?}", crate::utils::platform::get_models_dir());
-            println!("💡 Recommended models for CPU: ggml-base.bin (fastest) or ggml-small.bin (balanced)");
-            println!("📥 Download from: https://huggingface.co/ggerganov/whisper.cpp/tree/main");
-            println!("🔧 Quick download commands:");
-            println!("   # For base model (fastest, 74MB):");
-            println!("   wget -O {}/ggml-base.bin \\", crate::utils::platform::get_models_dir().display());
-            println!("     https://huggingface.co/ggerganov/whisper.cpp/resolve/main/ggml-base.bin");
-            "./models/ggml-base.bin".to_string()
-        });
+    let model_path = crate::voice_assistant::asr::models::resolve_whisper_model(
+        crate::voice_assistant::asr::models::ModelPurpose::Cpu,
+        crate::voice_assistant::asr::models::configured_whisper_model().await.as_deref(),
+    )?;
 
-    println!("🎯 Using Whisper model: {}", model_path);
+    info!("🎯 Using Whisper model: {}", model_path);
 
-    // Check if VAD should be enabled via environment variable
-    let enable_vad = std::env::var("WHISPER_ENABLE_VAD")
-        .unwrap_or_else(|_| "false".to_string())
-        .parse::<bool>()
-        .unwrap_or(false);
+    let enable_vad = crate::voice_assistant::settings::current().whisper_enable_vad;
 
     if enable_vad {
-        println!("🎯 VAD enabled via WHISPER_ENABLE_VAD environment variable");
+        info!("🎯 VAD enabled via whisper_enable_vad setting");
     } else {
-        println!("ℹ️  VAD disabled (set WHISPER_ENABLE_VAD=true to enable)");
+        info!("ℹ️  VAD disabled (enable it in the ASR settings)");
     }
 
     // 🔥 简化：直接使用CPU后端，避免GPU detector死锁
@@ -1029,41 +2180,24 @@ async fn create_local_whisper_processor() -> Result<crate::voice_assistant::asr:
         use_gpu_if_available: false,
         gpu_device_id: None,
         output_format: crate::voice_assistant::asr::whisper_rs::OutputFormat::Text,
+        reuse_state: false,
+        min_segment_confidence: crate::voice_assistant::settings::current().whisper_min_segment_confidence,
     };
 
       // Use thread-safe creation with timeout to prevent crashes
-    println!("⏱️ Creating WhisperRSProcessor with safety timeout...");
+    info!("⏱️ Creating WhisperRSProcessor with safety timeout...");
     
-    let processor_result = std::thread::spawn(move || {
-        // Use a simple timeout mechanism
-        let (tx, rx) = std::sync::mpsc::channel();
-        
-        // Spawn the processor creation in a separate thread
-        std::thread::spawn(move || {
-            let result = WhisperRSProcessor::new(config);
-            let _ = tx.send(result);
-        });
-        
-        // Wait for up to 30 seconds for processor creation
-        match rx.recv_timeout(std::time::Duration::from_secs(30)) {
-            Ok(processor_result) => processor_result,
-            Err(_) => {
-                eprintln!("⏰ WhisperRSProcessor creation timed out after 30 seconds");
-                eprintln!("💡 This indicates a deadlock or infinite loop in whisper.cpp");
-                Err(crate::voice_assistant::VoiceError::Other(
-                    "WhisperRSProcessor creation timeout - possible whisper.cpp bug".to_string()
-                ))
-            }
-        }
-    }).join().unwrap_or_else(|_| {
-        eprintln!("💥 WhisperRSProcessor creation thread panicked!");
-        Err(crate::voice_assistant::VoiceError::Other(
-            "WhisperRSProcessor creation thread panicked".to_string()
-        ))
-    });
+    let processor_result = crate::utils::thread::create_with_timeout(
+        std::time::Duration::from_secs(30),
+        move || WhisperRSProcessor::new(config),
+    );
+    if let Err(crate::voice_assistant::VoiceError::Timeout(_)) = processor_result {
+        warn!("⏰ WhisperRSProcessor creation timed out after 30 seconds");
+        warn!("💡 This indicates a deadlock or infinite loop in whisper.cpp");
+    }
     
     processor_result.map_err(|e| {
-        format!("Failed to create Local Whisper processor: {}. This may be due to whisper.cpp compatibility issues with your CPU.", e)
+        CommandError::Whisper(format!("Failed to create Local Whisper processor: {}. This may be due to whisper.cpp compatibility issues with your CPU.", e))
     })
 }
 
@@ -1071,46 +2205,133 @@ async fn create_local_whisper_processor() -> Result<crate::voice_assistant::asr:
 #[tauri::command]
 pub async fn get_hotkey_config(
     db_state: State<'_, DatabaseState>
-) -> Result<Option<crate::database::HotkeyConfig>, String> {
+) -> Result<Option<crate::database::HotkeyConfig>, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
     };
 
-    match db {
-        Some(database) => {
-            match database.get_hotkey_config().await {
-                Ok(config) => Ok(config),
-                Err(e) => Err(format!("Failed to get hotkey config: {}", e)),
-            }
+    match db {
+        Some(database) => {
+            match database.get_hotkey_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(CommandError::Database(format!("Failed to get hotkey config: {}", e))),
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized),
+    }
+}
+
+/// 最长录音时长的允许范围（秒）
+const MIN_MAX_RECORDING_SECS: i64 = 5;
+const MAX_MAX_RECORDING_SECS: i64 = 3600;
+/// 静音自动停止时长的允许范围（毫秒）
+const MIN_SILENCE_AUTO_STOP_MS: i64 = 500;
+const MAX_SILENCE_AUTO_STOP_MS: i64 = 30000;
+/// 预录时长上限（毫秒），0 表示关闭
+const MAX_PREROLL_MS: i64 = 2000;
+
+#[tauri::command]
+pub async fn save_hotkey_config(
+    db_state: State<'_, DatabaseState>,
+    request: HotkeyConfigRequest,
+) -> Result<crate::database::HotkeyConfig, CommandError> {
+    debug!("🔧 Backend: save_hotkey_config() called with request:");
+    debug!("  - transcribe_key: {}", request.transcribe_key);
+    debug!("  - translate_key: {}", request.translate_key);
+    debug!("  - trigger_delay_ms: {}", request.trigger_delay_ms);
+    debug!("  - anti_mistouch_enabled: {}", request.anti_mistouch_enabled);
+    debug!("  - save_wav_files: {}", request.save_wav_files);
+    debug!("  - typing_delays: {:?}", request.typing_delays);
+    debug!("  - text_injection_method: {:?}", request.text_injection_method);
+    debug!("  - hotkey_backend: {:?}", request.hotkey_backend);
+    debug!("  - recording_mode: {:?}", request.recording_mode);
+    debug!("  - max_recording_secs: {:?}", request.max_recording_secs);
+    debug!("  - silence_auto_stop_enabled: {:?}", request.silence_auto_stop_enabled);
+    debug!("  - silence_auto_stop_ms: {:?}", request.silence_auto_stop_ms);
+    debug!("  - preroll_ms: {:?}", request.preroll_ms);
+
+    let text_injection_method = match request.text_injection_method.as_deref() {
+        Some(value) => Some(
+            crate::voice_assistant::TextInjectionMethod::parse(value)
+                .ok_or_else(|| CommandError::config_invalid("text_injection_method", format!(
+                    "Unknown text injection method: {} (expected auto, direct or clipboard)", value
+                )))?
+                .as_str()
+        ),
+        None => None,
+    };
+
+    let hotkey_backend = match request.hotkey_backend.as_deref() {
+        Some(value) => Some(
+            crate::voice_assistant::HotkeyBackend::parse(value)
+                .ok_or_else(|| CommandError::config_invalid("hotkey_backend", format!(
+                    "Unknown hotkey backend: {} (expected rdev or plugin)", value
+                )))?
+                .as_str()
+        ),
+        None => None,
+    };
+
+    let recording_mode = match request.recording_mode.as_deref() {
+        Some(value) => Some(
+            crate::voice_assistant::RecordingMode::parse(value)
+                .ok_or_else(|| CommandError::config_invalid("recording_mode", format!(
+                    "Unknown recording mode: {} (expected hold or toggle)", value
+                )))?
+                .as_str()
+        ),
+        None => None,
+    };
+
+    if let Some(secs) = request.max_recording_secs {
+        if !(MIN_MAX_RECORDING_SECS..=MAX_MAX_RECORDING_SECS).contains(&secs) {
+            return Err(CommandError::config_invalid("max_recording_secs", format!(
+                "Max recording duration must be between {} and {} seconds, got {}",
+                MIN_MAX_RECORDING_SECS, MAX_MAX_RECORDING_SECS, secs
+            )));
+        }
+    }
+
+    if let Some(ms) = request.silence_auto_stop_ms {
+        if !(MIN_SILENCE_AUTO_STOP_MS..=MAX_SILENCE_AUTO_STOP_MS).contains(&ms) {
+            return Err(CommandError::config_invalid("silence_auto_stop_ms", format!(
+                "Silence auto-stop duration must be between {} and {} ms, got {}",
+                MIN_SILENCE_AUTO_STOP_MS, MAX_SILENCE_AUTO_STOP_MS, ms
+            )));
         }
-        None => Err("Database not initialized".to_string()),
     }
-}
 
-#[tauri::command]
-pub async fn save_hotkey_config(
-    db_state: State<'_, DatabaseState>,
-    request: HotkeyConfigRequest,
-) -> Result<crate::database::HotkeyConfig, String> {
-    println!("🔧 Backend: save_hotkey_config() called with request:");
-    println!("  - transcribe_key: {}", request.transcribe_key);
-    println!("  - translate_key: {}", request.translate_key);
-    println!("  - trigger_delay_ms: {}", request.trigger_delay_ms);
-    println!("  - anti_mistouch_enabled: {}", request.anti_mistouch_enabled);
-    println!("  - save_wav_files: {}", request.save_wav_files);
-    println!("  - typing_delays: {:?}", request.typing_delays);
+    if let Some(ms) = request.preroll_ms {
+        if !(0..=MAX_PREROLL_MS).contains(&ms) {
+            return Err(CommandError::config_invalid("preroll_ms", format!(
+                "Pre-roll duration must be between 0 and {} ms, got {}",
+                MAX_PREROLL_MS, ms
+            )));
+        }
+    }
+
+    let cycle_profile_key = request.cycle_profile_key.as_deref().map(str::trim);
+    if let Some(key) = cycle_profile_key.filter(|key| !key.is_empty()) {
+        crate::voice_assistant::hotkey_parser::ParsedHotkey::parse(key)
+            .map_err(|e| CommandError::config_invalid("cycle_profile_key", format!("Invalid profile cycle hotkey: {}", e)))?;
+        if [&request.transcribe_key, &request.translate_key].iter().any(|k| k.trim().eq_ignore_ascii_case(key)) {
+            return Err(CommandError::config_invalid("cycle_profile_key", format!(
+                "Profile cycle hotkey {} is already used for transcribe/translate", key
+            )));
+        }
+    }
 
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
     };
 
-    println!("🗄️ Database state: {:?}", db.is_some());
+    debug!("🗄️ Database state: {:?}", db.is_some());
 
     match db {
         Some(database) => {
-            println!("📝 Calling database.save_hotkey_config...");
+            debug!("📝 Calling database.save_hotkey_config...");
             match database.save_hotkey_config(
                 &request.transcribe_key,
                 &request.translate_key,
@@ -1118,47 +2339,93 @@ pub async fn save_hotkey_config(
                 request.anti_mistouch_enabled,
                 request.save_wav_files,
                 Some(&request.typing_delays),
+                text_injection_method,
+                hotkey_backend,
+                recording_mode,
+                request.max_recording_secs,
+                request.silence_auto_stop_enabled,
+                request.silence_auto_stop_ms,
+                request.preroll_ms,
+                request.queued_to_clipboard,
+                cycle_profile_key,
             ).await {
                 Ok(config) => {
-                    println!("✅ Backend: Hotkey config saved successfully!");
-                    println!("  - Saved config ID: {}", config.id);
-                    println!("  - Saved clipboard_update_ms: {}", config.clipboard_update_ms);
-                    println!("  - Saved keyboard_events_settle_ms: {}", config.keyboard_events_settle_ms);
+                    info!("✅ Backend: Hotkey config saved successfully!");
+                    debug!("  - Saved config ID: {}", config.id);
+                    debug!("  - Saved clipboard_update_ms: {}", config.clipboard_update_ms);
+                    debug!("  - Saved keyboard_events_settle_ms: {}", config.keyboard_events_settle_ms);
+
+                    // 语音助手运行中时立即生效（插件后端会重新注册快捷键）
+                    match crate::voice_assistant::coordinator::apply_hotkey_config(&config) {
+                        Ok(true) => info!("✅ Backend: Hotkey config applied to running voice assistant"),
+                        Ok(false) => {}
+                        Err(e) => {
+                            error!("❌ Backend: Failed to apply hotkey config: {}", e);
+                            return Err(CommandError::config_invalid("transcribe_key", format!(
+                                "Hotkey config saved but could not be applied: {}", e
+                            )));
+                        }
+                    }
                     Ok(config)
                 },
                 Err(e) => {
-                    println!("❌ Backend: Failed to save hotkey config: {}", e);
-                    Err(format!("Failed to save hotkey config: {}", e))
+                    error!("❌ Backend: Failed to save hotkey config: {}", e);
+                    Err(CommandError::Database(format!("Failed to save hotkey config: {}", e)))
                 },
             }
         }
         None => {
-            println!("❌ Backend: Database not initialized");
-            Err("Database not initialized".to_string())
+            error!("❌ Backend: Database not initialized");
+            Err(CommandError::DatabaseNotInitialized)
         },
     }
 }
 
+/// 校验设置页输入的热键，返回规范写法（如 "CTRL-ALT-T" -> "Ctrl + Alt + T"）
+#[tauri::command]
+pub async fn validate_hotkey_string(hotkey: String) -> Result<String, CommandError> {
+    crate::voice_assistant::hotkey_parser::ParsedHotkey::try_parse(&hotkey)
+        .map(|parsed| parsed.get_display_name())
+        .map_err(|e| CommandError::config_invalid("hotkey", e.to_string()))
+}
+
+/// 等待用户按下新的热键组合（最多 10 秒），返回规范写法；录制期间热键不会触发听写
+#[tauri::command]
+pub async fn capture_hotkey() -> Result<String, CommandError> {
+    use crate::voice_assistant::hotkey_capture::{capture_next, CAPTURE_TIMEOUT};
+
+    let captured = tokio::task::spawn_blocking(|| capture_next(CAPTURE_TIMEOUT))
+        .await
+        .map_err(|e| CommandError::Other(format!("Hotkey capture task failed: {}", e)))?;
+    Ok(captured?)
+}
+
+/// 取消进行中的热键录制，返回是否有录制被取消
+#[tauri::command]
+pub async fn cancel_hotkey_capture() -> Result<bool, CommandError> {
+    Ok(crate::voice_assistant::hotkey_capture::cancel())
+}
+
 // Audio device commands
 #[tauri::command]
-pub async fn start_test_recording() -> Result<String, String> {
+pub async fn start_test_recording() -> Result<String, CommandError> {
     use std::sync::{Arc, Mutex};
     use crate::voice_assistant::AudioRecorder;
 
-    println!("🎤 Starting test recording...");
+    info!("🎤 Starting test recording...");
 
     // Create a new recorder
     let recorder = Arc::new(Mutex::new(AudioRecorder::new()
-        .map_err(|e| format!("Failed to create recorder: {}", e))?));
+        .map_err(|e| CommandError::Audio(format!("Failed to create recorder: {}", e)))?));
 
     // Start recording
     {
         let mut rec = recorder.lock().unwrap();
         rec.start_recording()
-            .map_err(|e| format!("Failed to start recording: {}", e))?;
+            .map_err(|e| CommandError::Audio(format!("Failed to start recording: {}", e)))?;
     }
 
-    println!("🔴 Recording started... Recording for 3 seconds");
+    info!("🔴 Recording started... Recording for 3 seconds");
 
     // Record for 3 seconds
     tokio::time::sleep(tokio::time::Duration::from_secs(3)).await;
@@ -1167,20 +2434,20 @@ pub async fn start_test_recording() -> Result<String, String> {
     let file_path = {
         let mut rec = recorder.lock().unwrap();
         rec.stop_recording()
-            .map_err(|e| format!("Failed to stop recording: {}", e))?
+            .map_err(|e| CommandError::Audio(format!("Failed to stop recording: {}", e)))?
     };
 
-    println!("✅ Test recording completed!");
-    println!("📁 Audio file saved to: {}", file_path);
+    info!("✅ Test recording completed!");
+    debug!("📁 Audio file saved to: {}", file_path);
 
     Ok(format!("Test recording completed. File saved to: {}", file_path))
 }
 
 #[tauri::command]
-pub async fn get_audio_devices() -> Result<Vec<AudioDevice>, String> {
-    println!("🎤 Getting available audio devices...");
-    println!("🖥️ Platform: {}", std::env::consts::OS);
-    println!("⏰ Current time: {:?}", std::time::SystemTime::now());
+pub async fn get_audio_devices() -> Result<Vec<AudioDevice>, CommandError> {
+    info!("🎤 Getting available audio devices...");
+    info!("🖥️ Platform: {}", std::env::consts::OS);
+    warn!("⏰ Current time: {:?}", std::time::SystemTime::now());
 
     // For now, return mock devices
     let mock_devices = vec![
@@ -1201,41 +2468,41 @@ pub async fn get_audio_devices() -> Result<Vec<AudioDevice>, String> {
         }
     ];
 
-    println!("🎭 Created {} mock devices", mock_devices.len());
+    info!("🎭 Created {} mock devices", mock_devices.len());
     Ok(mock_devices)
 }
 
 #[tauri::command]
-pub async fn test_microphone(device_id: String) -> Result<bool, String> {
-    println!("🎤 Starting microphone test...");
-    println!("🎯 Target device ID: {}", device_id);
-    println!("🖥️ Platform: {}", std::env::consts::OS);
-    println!("⏰ Test started at: {:?}", std::time::SystemTime::now());
+pub async fn test_microphone(device_id: String) -> Result<bool, CommandError> {
+    info!("🎤 Starting microphone test...");
+    info!("🎯 Target device ID: {}", device_id);
+    info!("🖥️ Platform: {}", std::env::consts::OS);
+    warn!("⏰ Test started at: {:?}", std::time::SystemTime::now());
 
     // Simulate test duration with progress
     for i in 1..=3 {
         tokio::time::sleep(tokio::time::Duration::from_millis(333)).await;
-        println!("  📊 Testing audio levels... {}/3", i);
+        debug!("  📊 Testing audio levels... {}/3", i);
     }
 
     // Simulate checking audio levels (in real implementation, you'd check actual audio)
     let simulated_audio_level = 0.75; // 75% of max level
-    println!("📈 Simulated audio level: {:.0}%", simulated_audio_level * 100.0);
+    debug!("📈 Simulated audio level: {:.0}%", simulated_audio_level * 100.0);
 
     // Determine success based on simulated conditions
     let success = simulated_audio_level > 0.1; // Success if we detect audio
 
     if success {
-        println!("✅ Microphone test successful!");
-        println!("🎵 Audio input detected and working properly");
-        println!("📊 Signal quality: Good");
+        info!("✅ Microphone test successful!");
+        info!("🎵 Audio input detected and working properly");
+        debug!("📊 Signal quality: Good");
     } else {
-        println!("❌ Microphone test failed");
-        println!("🔇 No audio input detected");
-        println!("📊 Signal quality: Poor/None");
+        error!("❌ Microphone test failed");
+        info!("🔇 No audio input detected");
+        debug!("📊 Signal quality: Poor/None");
     }
 
-    println!("⏰ Test completed at: {:?}", std::time::SystemTime::now());
+    warn!("⏰ Test completed at: {:?}", std::time::SystemTime::now());
 
     Ok(success)
 }
@@ -1245,7 +2512,7 @@ pub async fn test_microphone(device_id: String) -> Result<bool, String> {
 pub async fn get_service_status(
     service_name: Option<String>,
     db_state: State<'_, DatabaseState>
-) -> Result<ServiceStatusResponse, String> {
+) -> Result<ServiceStatusResponse, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
@@ -1254,11 +2521,11 @@ pub async fn get_service_status(
     match db {
         Some(database) => {
             let service = service_name.unwrap_or_else(|| "local_asr".to_string());
-            println!("🔍 Getting service status for: {}", service);
+            debug!("🔍 Getting service status for: {}", service);
 
             match database.get_service_status(&service).await {
                 Ok(Some(stats)) => {
-                    println!("✅ Service status found: {} ({})", stats.service_name, stats.status);
+                    info!("✅ Service status found: {} ({})", stats.service_name, stats.status);
                     Ok(ServiceStatusResponse {
                         active_service: stats.service_name,
                         status: stats.status,
@@ -1267,7 +2534,7 @@ pub async fn get_service_status(
                 }
                 Ok(None) => {
                     // Return default status if not found
-                    println!("⚠️ No service status found, returning default");
+                    warn!("⚠️ No service status found, returning default");
                     Ok(ServiceStatusResponse {
                         active_service: service,
                         status: "offline".to_string(),
@@ -1275,20 +2542,21 @@ pub async fn get_service_status(
                     })
                 }
                 Err(e) => {
-                    println!("❌ Failed to get service status: {}", e);
-                    Err(format!("Failed to get service status: {}", e))
+                    error!("❌ Failed to get service status: {}", e);
+                    Err(CommandError::Database(format!("Failed to get service status: {}", e)))
                 }
             }
         }
-        None => Err("Database not initialized".to_string())
+        None => Err(CommandError::DatabaseNotInitialized)
     }
 }
 
 #[tauri::command]
 pub async fn get_latency_data(
     service_name: Option<String>,
+    trend_window: Option<usize>,
     db_state: State<'_, DatabaseState>
-) -> Result<LatencyDataResponse, String> {
+) -> Result<LatencyDataResponse, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
@@ -1297,12 +2565,12 @@ pub async fn get_latency_data(
     match db {
         Some(database) => {
             let service = service_name.unwrap_or_else(|| "local_asr".to_string());
-            println!("🔍 Getting latency data for: {}", service);
+            debug!("🔍 Getting latency data for: {}", service);
 
             match database.get_latency_data(&service, 24).await { // Last 24 hours
                 Ok(records) => {
                     if records.is_empty() {
-                        println!("⚠️ No latency data found");
+                        warn!("⚠️ No latency data found");
                         return Ok(LatencyDataResponse {
                             current: 0,
                             trend: "neutral".to_string(),
@@ -1314,26 +2582,8 @@ pub async fn get_latency_data(
                     // Get the latest (most recent) latency value
                     let current_latency = records.first().map(|r| r.latency_ms).unwrap_or(0);
 
-                    // Calculate trend (compare with second-to-last record)
-                    let trend = if records.len() >= 2 {
-                        let previous_latency = records.get(1).map(|r| r.latency_ms).unwrap_or(0);
-                        if current_latency > previous_latency {
-                            "up"
-                        } else if current_latency < previous_latency {
-                            "down"
-                        } else {
-                            "neutral"
-                        }
-                    } else {
-                        "neutral"
-                    };
-
-                    let trend_value = if records.len() >= 2 {
-                        let previous_latency = records.get(1).map(|r| r.latency_ms).unwrap_or(0);
-                        current_latency - previous_latency
-                    } else {
-                        0
-                    };
+                    // Calculate trend from ASR time per second of audio
+                    let (trend, trend_value) = latency_trend(&records, trend_window.unwrap_or(DEFAULT_LATENCY_TREND_WINDOW));
 
                     // Convert to history points for frontend (last 12 records with time formatting)
                     let history: Vec<LatencyHistoryPoint> = records
@@ -1342,10 +2592,11 @@ pub async fn get_latency_data(
                         .map(|r| LatencyHistoryPoint {
                             time: r.recorded_at.format("%H:%M").to_string(),
                             val: r.latency_ms,
+                            breakdown: crate::database::LatencyBreakdown::from_record(r),
                         })
                         .collect();
 
-                    println!("✅ Latency data: {}ms (trend: {}, records: {})", current_latency, trend, records.len());
+                    info!("✅ Latency data: {}ms (trend: {} {}%, records: {})", current_latency, trend, trend_value, records.len());
                     Ok(LatencyDataResponse {
                         current: current_latency,
                         trend: trend.to_string(),
@@ -1354,19 +2605,141 @@ pub async fn get_latency_data(
                     })
                 }
                 Err(e) => {
-                    println!("❌ Failed to get latency data: {}", e);
-                    Err(format!("Failed to get latency data: {}", e))
+                    error!("❌ Failed to get latency data: {}", e);
+                    Err(CommandError::Database(format!("Failed to get latency data: {}", e)))
+                }
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized)
+    }
+}
+
+#[tauri::command]
+pub async fn get_latency_percentiles(
+    service_name: Option<String>,
+    hours_back: Option<i64>,
+    db_state: State<'_, DatabaseState>
+) -> Result<LatencyPercentiles, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+
+    match db {
+        Some(database) => {
+            let service = service_name.unwrap_or_else(|| "local_asr".to_string());
+            let hours = hours_back.unwrap_or(24).clamp(1, 24 * 365);
+
+            match database.get_latency_data(&service, hours).await {
+                Ok(records) => {
+                    let percentiles = latency_percentiles(records.iter().map(|r| r.latency_ms).collect());
+                    info!(
+                        "📈 Latency percentiles for {} ({}h): p50={}ms p90={}ms p99={}ms max={}ms (n={})",
+                        service, hours, percentiles.p50, percentiles.p90, percentiles.p99, percentiles.max, percentiles.count
+                    );
+                    Ok(percentiles)
                 }
+                Err(e) => Err(CommandError::Database(format!("Failed to get latency data: {}", e))),
             }
         }
-        None => Err("Database not initialized".to_string())
+        None => Err(CommandError::DatabaseNotInitialized)
+    }
+}
+
+/// 用量趋势报表：按本地日期或周、处理器聚合，日期格式 YYYY-MM-DD（含首尾）
+#[tauri::command]
+pub async fn get_usage_report(
+    db_state: State<'_, DatabaseState>,
+    start_date: String,
+    end_date: String,
+    granularity: Option<crate::database::ReportGranularity>,
+    utc_offset_minutes: Option<i32>,
+) -> Result<crate::database::UsageReport, CommandError> {
+    let parse = |date: &str| {
+        chrono::NaiveDate::parse_from_str(date, "%Y-%m-%d")
+            .map_err(|e| CommandError::config_invalid("date", format!("Invalid date '{}': {}", date, e)))
+    };
+    let query = crate::database::UsageReportQuery {
+        start_date: parse(&start_date)?,
+        end_date: parse(&end_date)?,
+        granularity: granularity.unwrap_or_default(),
+        // 前端不传时使用本机时区
+        utc_offset_minutes: utc_offset_minutes
+            .unwrap_or_else(|| chrono::Local::now().offset().local_minus_utc() / 60),
+    };
+    if query.end_date < query.start_date {
+        return Err(CommandError::config_invalid("end_date", format!("end_date {} is before start_date {}", end_date, start_date)));
     }
+    if !(-14 * 60..=14 * 60).contains(&query.utc_offset_minutes) {
+        return Err(CommandError::config_invalid("utc_offset_minutes", format!("Invalid UTC offset: {} minutes", query.utc_offset_minutes)));
+    }
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+
+    let report = database.get_usage_report(&query).await
+        .map_err(|e| CommandError::Database(format!("Failed to get usage report: {}", e)))?;
+    debug!("📈 Usage report {}..{}: {} usage rows, {} latency rows", start_date, end_date, report.usage.len(), report.latency.len());
+    Ok(report)
 }
 
 #[tauri::command]
 pub async fn get_usage_data(
     db_state: State<'_, DatabaseState>
-) -> Result<UsageDataResponse, String> {
+) -> Result<UsageDataResponse, CommandError> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+
+    match db {
+        Some(database) => {
+            debug!("🔍 Getting today's usage data");
+
+            match database.get_today_usage().await {
+                Ok(Some(usage)) => {
+                    let success_rate = if usage.total_requests > 0 {
+                        (usage.successful_requests as f64 / usage.total_requests as f64) * 100.0
+                    } else {
+                        0.0
+                    };
+
+                    info!("✅ Usage data: {} secs, {:.1}% success rate", usage.total_seconds, success_rate);
+                    Ok(UsageDataResponse {
+                        today_seconds: usage.total_seconds,
+                        success_rate,
+                        total_requests: usage.total_requests,
+                        successful_requests: usage.successful_requests,
+                    })
+                }
+                Ok(None) => {
+                    warn!("⚠️ No usage data found for today");
+                    Ok(UsageDataResponse {
+                        today_seconds: 0,
+                        success_rate: 0.0,
+                        total_requests: 0,
+                        successful_requests: 0,
+                    })
+                }
+                Err(e) => {
+                    error!("❌ Failed to get usage data: {}", e);
+                    Err(CommandError::Database(format!("Failed to get usage data: {}", e)))
+                }
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized)
+    }
+}
+
+// ASR result handler command
+#[tauri::command]
+pub async fn handle_asr_result(
+    db_state: State<'_, DatabaseState>,
+    result: crate::voice_assistant::coordinator::AsrResult,
+) -> Result<String, CommandError> {
     let db = {
         let guard = db_state.lock().unwrap();
         guard.as_ref().cloned()
@@ -1374,86 +2747,222 @@ pub async fn get_usage_data(
 
     match db {
         Some(database) => {
-            println!("🔍 Getting today's usage data");
+            debug!("📊 Handling ASR result: success={}, processor={}", result.success, result.processor_type);
+
+            let record = NewHistoryRecord {
+                record_type: result.record_type.unwrap_or_else(|| "asr".to_string()),
+                input_text: result.input_text,
+                output_text: Some(result.output_text.clone()),
+                audio_file_path: result.audio_file_path,
+                processor_type: Some(result.processor_type),
+                processing_time_ms: result.processing_time_ms,
+                audio_duration_ms: None,
+                success: result.success,
+                error_message: result.error_message,
+                translation_skipped: false,
+                detected_language: None,
+                confidence: None,
+                segments_json: None,
+            };
+            record.validate().map_err(|e| CommandError::config_invalid("record", e))?;
+
+            match database.add_history_record(record).await {
+                Ok(_) => {
+                    info!("✅ ASR result saved to database");
+                    Ok("ASR result saved successfully".to_string())
+                }
+                Err(e) => {
+                    error!("❌ Failed to save ASR result: {}", e);
+                    Err(CommandError::Database(format!("Failed to save ASR result: {}", e)))
+                }
+            }
+        }
+        None => Err(CommandError::DatabaseNotInitialized)
+    }
+}
+
+/// Helper function to get hotkey config from database for internal use
+pub async fn get_hotkey_config_from_database() -> Result<Option<crate::database::HotkeyConfig>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
+
+    if !database_path.exists() {
+        return Ok(None);
+    }
+
+    // Use global database pool to avoid repeated initialization
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_hotkey_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(format!("Failed to get hotkey config: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("Failed to create database: {}", e)),
+    }
+}
+
+pub async fn get_postprocess_config_internal() -> Result<Option<crate::database::PostProcessConfig>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
+
+    if !database_path.exists() {
+        return Ok(None);
+    }
+
+    // Use global database pool to avoid repeated initialization
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_postprocess_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(format!("Failed to get post-processing config: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("Failed to create database: {}", e)),
+    }
+}
+
+pub async fn get_audio_prep_config_internal() -> Result<Option<crate::database::AudioPrepConfig>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
+
+    if !database_path.exists() {
+        return Ok(None);
+    }
+
+    // Use global database pool to avoid repeated initialization
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_audio_prep_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(format!("Failed to get audio pre-processing config: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("Failed to create database: {}", e)),
+    }
+}
+
+pub async fn get_app_settings_internal() -> Result<Vec<crate::database::AppSetting>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
+
+    if !database_path.exists() {
+        return Ok(Vec::new());
+    }
+
+    // Use global database pool to avoid repeated initialization
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_app_settings().await {
+                Ok(settings) => Ok(settings),
+                Err(e) => Err(format!("Failed to get app settings: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("Failed to create database: {}", e)),
+    }
+}
+
+pub async fn get_network_retry_config_internal() -> Result<Option<crate::database::NetworkRetryConfig>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
+
+    if !database_path.exists() {
+        return Ok(None);
+    }
+
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_network_retry_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(format!("Failed to get network retry config: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("Failed to create database: {}", e)),
+    }
+}
+
+pub async fn get_overlay_config_internal() -> Result<Option<crate::database::OverlayConfig>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
+
+    if !database_path.exists() {
+        return Ok(None);
+    }
+
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_overlay_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(format!("Failed to get overlay config: {}", e)),
+            }
+        }
+        Err(e) => Err(format!("Failed to create database: {}", e)),
+    }
+}
+
+pub async fn get_dictation_command_config_internal() -> Result<Option<crate::database::DictationCommandConfig>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
 
-            match database.get_today_usage().await {
-                Ok(Some(usage)) => {
-                    let success_rate = if usage.total_requests > 0 {
-                        (usage.successful_requests as f64 / usage.total_requests as f64) * 100.0
-                    } else {
-                        0.0
-                    };
+    if !database_path.exists() {
+        return Ok(None);
+    }
 
-                    println!("✅ Usage data: {} secs, {:.1}% success rate", usage.total_seconds, success_rate);
-                    Ok(UsageDataResponse {
-                        today_seconds: usage.total_seconds,
-                        success_rate,
-                        total_requests: usage.total_requests,
-                        successful_requests: usage.successful_requests,
-                    })
-                }
-                Ok(None) => {
-                    println!("⚠️ No usage data found for today");
-                    Ok(UsageDataResponse {
-                        today_seconds: 0,
-                        success_rate: 0.0,
-                        total_requests: 0,
-                        successful_requests: 0,
-                    })
-                }
-                Err(e) => {
-                    println!("❌ Failed to get usage data: {}", e);
-                    Err(format!("Failed to get usage data: {}", e))
-                }
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_dictation_command_config().await {
+                Ok(config) => Ok(config),
+                Err(e) => Err(format!("Failed to get dictation command config: {}", e)),
             }
         }
-        None => Err("Database not initialized".to_string())
+        Err(e) => Err(format!("Failed to create database: {}", e)),
     }
 }
 
-// ASR result handler command
-#[tauri::command]
-pub async fn handle_asr_result(
-    db_state: State<'_, DatabaseState>,
-    result: crate::voice_assistant::coordinator::AsrResult,
-) -> Result<String, String> {
-    let db = {
-        let guard = db_state.lock().unwrap();
-        guard.as_ref().cloned()
-    };
-
-    match db {
-        Some(database) => {
-            println!("📊 Handling ASR result: success={}, processor={}", result.success, result.processor_type);
+pub async fn get_output_profiles_internal() -> Result<Vec<crate::database::OutputProfileRecord>, String> {
+    let database_path = std::env::current_dir()
+        .unwrap_or_else(|_| std::path::PathBuf::from("."))
+        .join(".tauri-data")
+        .join("databases")
+        .join("voice_assistant.db");
 
-            let record = NewHistoryRecord {
-                record_type: "asr".to_string(),
-                input_text: result.input_text,
-                output_text: Some(result.output_text.clone()),
-                audio_file_path: result.audio_file_path,
-                processor_type: Some(result.processor_type),
-                processing_time_ms: result.processing_time_ms,
-                success: result.success,
-                error_message: result.error_message,
-            };
+    if !database_path.exists() {
+        return Ok(Vec::new());
+    }
 
-            match database.add_history_record(record).await {
-                Ok(_) => {
-                    println!("✅ ASR result saved to database");
-                    Ok("ASR result saved successfully".to_string())
-                }
-                Err(e) => {
-                    println!("❌ Failed to save ASR result: {}", e);
-                    Err(format!("Failed to save ASR result: {}", e))
-                }
+    match Database::from_global_pool().await {
+        Ok(database) => {
+            match database.get_output_profiles().await {
+                Ok(profiles) => Ok(profiles),
+                Err(e) => Err(format!("Failed to get output profiles: {}", e)),
             }
         }
-        None => Err("Database not initialized".to_string())
+        Err(e) => Err(format!("Failed to create database: {}", e)),
     }
 }
 
-/// Helper function to get hotkey config from database for internal use
-pub async fn get_hotkey_config_from_database() -> Result<Option<crate::database::HotkeyConfig>, String> {
+pub async fn get_replacements_internal() -> Result<Vec<crate::database::TranscriptionReplacement>, String> {
     let database_path = std::env::current_dir()
         .unwrap_or_else(|_| std::path::PathBuf::from("."))
         .join(".tauri-data")
@@ -1461,15 +2970,15 @@ pub async fn get_hotkey_config_from_database() -> Result<Option<crate::database:
         .join("voice_assistant.db");
 
     if !database_path.exists() {
-        return Ok(None);
+        return Ok(Vec::new());
     }
 
     // Use global database pool to avoid repeated initialization
     match Database::from_global_pool().await {
         Ok(database) => {
-            match database.get_hotkey_config().await {
-                Ok(config) => Ok(config),
-                Err(e) => Err(format!("Failed to get hotkey config: {}", e)),
+            match database.get_replacements().await {
+                Ok(replacements) => Ok(replacements),
+                Err(e) => Err(format!("Failed to get replacements: {}", e)),
             }
         }
         Err(e) => Err(format!("Failed to create database: {}", e)),
@@ -1485,7 +2994,7 @@ pub async fn get_asr_config_internal() -> Result<Vec<crate::database::AsrConfig>
         .join("voice_assistant.db");
 
     if !database_path.exists() {
-        println!("⚠️ Database file not found at: {:?}", database_path);
+        warn!("⚠️ Database file not found at: {:?}", database_path);
         return Ok(Vec::new());
     }
 
@@ -1495,24 +3004,24 @@ pub async fn get_asr_config_internal() -> Result<Vec<crate::database::AsrConfig>
             match database.get_asr_config().await {
                 Ok(configs) => {
                     if let Some(ref config) = configs {
-                        println!("✅ Found ASR config: {} (local: {}, cloud: {})",
+                        info!("✅ Found ASR config: {} (local: {}, cloud: {})",
                             config.service_provider,
                             config.local_endpoint.is_some(),
                             config.cloud_endpoint.is_some());
                         Ok(vec![config.clone()])
                     } else {
-                        println!("⚠️ No ASR config found in database");
+                        warn!("⚠️ No ASR config found in database");
                         Ok(Vec::new())
                     }
                 }
                 Err(e) => {
-                    println!("❌ Failed to get ASR config: {}", e);
+                    error!("❌ Failed to get ASR config: {}", e);
                     Err(format!("Failed to get ASR config: {}", e))
                 }
             }
         }
         Err(e) => {
-            println!("❌ Failed to create database: {}", e);
+            error!("❌ Failed to create database: {}", e);
             Err(format!("Failed to create database: {}", e))
         }
     }
@@ -1526,31 +3035,38 @@ pub async fn get_translation_config_internal() -> Result<Vec<crate::database::Tr
         .join("voice_assistant.db");
 
     if !database_path.exists() {
-        println!("⚠️ Database file not found at: {:?}", database_path);
+        warn!("⚠️ Database file not found at: {:?}", database_path);
         return Ok(Vec::new());
     }
 
     // Use global database pool to avoid repeated initialization
     match Database::from_global_pool().await {
         Ok(database) => {
-            match database.get_translation_config("siliconflow").await {
-                Ok(config) => {
-                    if let Some(ref c) = config {
-                        println!("✅ Found translation config: {} ({})", c.provider, c.endpoint.is_some());
-                        Ok(vec![c.clone()])
-                    } else {
-                        println!("⚠️ No translation config found in database");
-                        Ok(Vec::new())
+            let mut configs = Vec::new();
+            for provider in ["siliconflow", "ollama", "deepl"] {
+                match database.get_translation_config(provider).await {
+                    Ok(Some(c)) => {
+                        info!("✅ Found translation config: {} ({})", c.provider, c.endpoint.is_some());
+                        configs.push(c);
+                    }
+                    Ok(None) => {}
+                    Err(e) => {
+                        error!("❌ Failed to get translation config: {}", e);
+                        return Err(format!("Failed to get translation config: {}", e));
                     }
                 }
-                Err(e) => {
-                    println!("❌ Failed to get translation config: {}", e);
-                    Err(format!("Failed to get translation config: {}", e))
-                }
             }
+
+            if configs.is_empty() {
+                warn!("⚠️ No translation config found in database");
+            }
+
+            // Most recently saved provider wins
+            configs.sort_by(|a, b| b.updated_at.cmp(&a.updated_at));
+            Ok(configs)
         }
         Err(e) => {
-            println!("❌ Failed to create database: {}", e);
+            error!("❌ Failed to create database: {}", e);
             Err(format!("Failed to create database: {}", e))
         }
     }
@@ -1558,14 +3074,14 @@ pub async fn get_translation_config_internal() -> Result<Vec<crate::database::Tr
 
 // Helper function to initialize database directly (without State wrapper)
 pub async fn init_database_direct() -> Result<Database, String> {
-    println!("🚀 Backend: init_database_direct() called");
+    info!("🚀 Backend: init_database_direct() called");
     match Database::new().await {
         Ok(db) => {
-            println!("✅ Backend: Database created successfully");
+            info!("✅ Backend: Database created successfully");
             Ok(db)
         }
         Err(e) => {
-            eprintln!("❌ Backend: Failed to initialize database: {}", e);
+            error!("❌ Backend: Failed to initialize database: {}", e);
             Err(format!("Failed to initialize database: {}", e))
         }
     }
@@ -1573,7 +3089,7 @@ pub async fn init_database_direct() -> Result<Database, String> {
 
 // Whisper-rs health check function - simplified since CPU compatibility is confirmed
 async fn check_whisper_rs_health() -> bool {
-    println!("🏥 Performing whisper-rs health check...");
+    info!("🏥 Performing whisper-rs health check...");
     
     // Check for available memory (whisper-rs can crash with insufficient memory)
     if let Ok(mem_info) = std::fs::read_to_string("/proc/meminfo") {
@@ -1586,16 +3102,16 @@ async fn check_whisper_rs_health() -> bool {
             
             let mem_gb = mem_kb / 1024 / 1024;
             if mem_gb < 4 {
-                println!("⚠️ Low memory detected ({}GB) - whisper-rs may be unstable", mem_gb);
-                println!("💡 Auto-switching to Cloud ASR for reliability");
+                warn!("⚠️ Low memory detected ({}GB) - whisper-rs may be unstable", mem_gb);
+                info!("💡 Auto-switching to Cloud ASR for reliability");
                 return false;
             }
             
-            println!("✅ Memory check passed: {}GB available", mem_gb);
+            info!("✅ Memory check passed: {}GB available", mem_gb);
         }
     }
     
-    println!("✅ Whisper-rs health check passed - CPU compatibility confirmed");
+    info!("✅ Whisper-rs health check passed - CPU compatibility confirmed");
     true
 }
 
@@ -1606,144 +3122,645 @@ pub struct WhisperModel {
     pub path: String,
     pub size_mb: f64,
     pub file_type: String,
+    /// "f16"、"q5_0"、"q8_0" 等
+    pub quantization: String,
     pub modified: String,
+    pub is_active: bool,
 }
 
+/// 设置模型目录并持久化，传入空字符串恢复默认目录；返回生效的目录
 #[tauri::command]
-pub fn scan_whisper_models() -> Result<Vec<WhisperModel>, String> {
-    println!("🔍 Scanning for available Whisper models...");
-    
-    let models_dir = crate::utils::platform::get_models_dir().to_string_lossy().to_string();
-    
-    if !std::path::Path::new(&models_dir).exists() {
-        println!("📁 Models directory does not exist: {}", models_dir);
-        return Ok(vec![]); // Return empty list instead of error
+pub fn set_models_dir(path: String) -> Result<String, CommandError> {
+    use crate::utils::platform::{get_models_dir, save_models_dir, MODELS_DIR_ENV};
+
+    let trimmed = path.trim();
+    if trimmed.is_empty() {
+        save_models_dir(None).map_err(|e| format!("Failed to reset models directory: {}", e))?;
+        debug!("📁 Models directory reset to default: {}", get_models_dir().display());
+    } else {
+        let dir = std::path::PathBuf::from(trimmed);
+        if !dir.is_absolute() {
+            return Err(CommandError::config_invalid("models_dir", format!("Models directory must be an absolute path: {}", trimmed)));
+        }
+        std::fs::create_dir_all(&dir)
+            .map_err(|e| format!("Failed to create models directory {}: {}", dir.display(), e))?;
+        save_models_dir(Some(&dir)).map_err(|e| format!("Failed to save models directory: {}", e))?;
+        debug!("📁 Models directory set to: {}", dir.display());
     }
-    
+
+    if std::env::var(MODELS_DIR_ENV).map(|v| !v.trim().is_empty()).unwrap_or(false) {
+        warn!("⚠️ {} is set and takes precedence over the saved models directory", MODELS_DIR_ENV);
+    }
+
+    Ok(get_models_dir().to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub fn scan_whisper_models() -> Result<Vec<WhisperModel>, CommandError> {
+    debug!("🔍 Scanning for available Whisper models...");
+
+    let active_model = crate::voice_assistant::settings::current()
+        .whisper_model_path
+        .map(std::path::PathBuf::from)
+        .filter(|path| path.is_file());
+
+    let models = scan_whisper_models_in(&crate::utils::platform::get_models_dir(), active_model.as_deref())?;
+    debug!("📊 Found {} total Whisper models", models.len());
+    Ok(models)
+}
+
+/// 扫描模型目录；当前激活的模型不在该目录时，同时扫描它所在的目录。按规范化路径去重
+pub fn scan_whisper_models_in(
+    models_dir: &std::path::Path,
+    active_model: Option<&std::path::Path>,
+) -> Result<Vec<WhisperModel>, String> {
+    let canonical = |path: &std::path::Path| std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let active_canonical = active_model.map(canonical);
+
+    let mut dirs = vec![models_dir.to_path_buf()];
+    if let Some(active_dir) = active_model.and_then(|p| p.parent()) {
+        if canonical(active_dir) != canonical(models_dir) {
+            dirs.push(active_dir.to_path_buf());
+        }
+    }
+
+    let mut seen = std::collections::HashSet::new();
     let mut models = Vec::new();
-    
-    // Scan the directory for .bin files
-    match std::fs::read_dir(&models_dir) {
-        Ok(entries) => {
-            for entry in entries {
-                let entry = match entry {
-                    Ok(e) => e,
-                    Err(e) => {
-                        eprintln!("Warning: Failed to read directory entry: {}", e);
-                        continue;
-                    }
-                };
-                
-                let path = entry.path();
-                
-                // Only look for .bin files (whisper models)
-                if path.extension().map_or(false, |ext| ext == "bin") {
-                    let metadata = match entry.metadata() {
-                        Ok(m) => m,
-                        Err(e) => {
-                            eprintln!("Warning: Failed to read metadata for {}: {}", path.display(), e);
-                            continue;
-                        }
-                    };
-                    
-                    if metadata.is_file() {
-                        let name = path.file_name()
-                            .and_then(|n| n.to_str())
-                            .unwrap_or("unknown")
-                            .to_string();
-                        
-                        // Skip VAD model - it's not for transcription
-                        if name.contains("vad") {
-                            println!("⚠️ Skipping VAD model: {} (not suitable for transcription)", name);
-                            continue;
-                        }
-                        
-                        let size_mb = metadata.len() as f64 / 1024.0 / 1024.0;
-                        
-                        let modified = metadata.modified()
-                            .ok()
-                            .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
-                            .map(|d| {
-                                let datetime = chrono::DateTime::from_timestamp(d.as_secs() as i64, 0);
-                                datetime.map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
-                                    .unwrap_or_else(|| "Unknown".to_string())
-                            })
-                            .unwrap_or_else(|| "Unknown".to_string());
-                        
-                        let file_type = if name.contains("base") {
-                            "Base (~74MB)".to_string()
-                        } else if name.contains("small") {
-                            "Small (~244MB)".to_string()
-                        } else if name.contains("medium") {
-                            "Medium (~769MB)".to_string()
-                        } else if name.contains("large") {
-                            if name.contains("turbo") {
-                                "Large V3 Turbo (~1.5GB)".to_string()
-                            } else {
-                                "Large (~1.5GB)".to_string()
-                            }
-                        } else if name.contains("tiny") {
-                            "Tiny (~39MB)".to_string()
-                        } else {
-                            format!("Custom ({:.1}MB)", size_mb)
-                        };
-                        
-                        models.push(WhisperModel {
-                            name,
-                            path: path.display().to_string(),
-                            size_mb,
-                            file_type,
-                            modified,
-                        });
-                        
-                        println!("✅ Found model: {} ({:.1} MB)", models.last().unwrap().name, size_mb);
-                    }
+
+    for (index, dir) in dirs.iter().enumerate() {
+        if !dir.exists() {
+            debug!("📁 Models directory does not exist: {}", dir.display());
+            continue;
+        }
+
+        let entries = match std::fs::read_dir(dir) {
+            Ok(entries) => entries,
+            // 主模型目录读取失败时报错，额外目录只提示
+            Err(e) if index == 0 => {
+                return Err(format!("Failed to read models directory {}: {}", dir.display(), e));
+            }
+            Err(e) => {
+                warn!("Warning: Failed to read active model directory {}: {}", dir.display(), e);
+                continue;
+            }
+        };
+
+        for entry in entries {
+            let entry = match entry {
+                Ok(e) => e,
+                Err(e) => {
+                    warn!("Warning: Failed to read directory entry: {}", e);
+                    continue;
                 }
+            };
+
+            let path = entry.path();
+            let canonical_path = canonical(&path);
+            if seen.contains(&canonical_path) {
+                continue;
+            }
+
+            if let Some(mut model) = whisper_model_from_path(&path) {
+                model.is_active = active_canonical.as_ref() == Some(&canonical_path);
+                info!("✅ Found model: {} ({:.1} MB)", model.name, model.size_mb);
+                seen.insert(canonical_path);
+                models.push(model);
             }
-        }
-        Err(e) => {
-            return Err(format!("Failed to read models directory {}: {}", models_dir, e));
         }
     }
-    
+
     // Sort models by size (largest first) and then by name
     models.sort_by(|a, b| {
         b.size_mb.partial_cmp(&a.size_mb)
             .unwrap_or(std::cmp::Ordering::Equal)
             .then(a.name.cmp(&b.name))
     });
-    
-    println!("📊 Found {} total Whisper models", models.len());
+
     Ok(models)
 }
 
+/// 只接受 .bin 模型文件，跳过 VAD 模型
+fn whisper_model_from_path(path: &std::path::Path) -> Option<WhisperModel> {
+    // Only look for .bin files (whisper models)
+    if !path.extension().map_or(false, |ext| ext == "bin") {
+        return None;
+    }
+
+    let metadata = match std::fs::metadata(path) {
+        Ok(m) => m,
+        Err(e) => {
+            warn!("Warning: Failed to read metadata for {}: {}", path.display(), e);
+            return None;
+        }
+    };
+    if !metadata.is_file() {
+        return None;
+    }
+
+    let name = path.file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+
+    // Skip VAD model - it's not for transcription
+    if name.contains("vad") {
+        warn!("⚠️ Skipping VAD model: {} (not suitable for transcription)", name);
+        return None;
+    }
+
+    let size_mb = metadata.len() as f64 / 1024.0 / 1024.0;
+
+    let modified = metadata.modified()
+        .ok()
+        .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
+        .map(|d| {
+            let datetime = chrono::DateTime::from_timestamp(d.as_secs() as i64, 0);
+            datetime.map(|dt| dt.format("%Y-%m-%d %H:%M").to_string())
+                .unwrap_or_else(|| "Unknown".to_string())
+        })
+        .unwrap_or_else(|| "Unknown".to_string());
+
+    // 文件名中的尺寸和量化后缀，文件头可读时以文件头为准
+    let info = crate::voice_assistant::asr::model_file::inspect_model_file(path);
+
+    Some(WhisperModel {
+        name,
+        path: path.display().to_string(),
+        size_mb,
+        file_type: info.label(size_mb),
+        quantization: info.quantization.as_str().to_string(),
+        modified,
+        is_active: false,
+    })
+}
+
 #[tauri::command]
-pub fn set_active_whisper_model(model_path: String) -> Result<String, String> {
-    println!("🎯 Setting active Whisper model: {}", model_path);
+pub async fn set_active_whisper_model(model_path: String) -> Result<String, CommandError> {
+    info!("🎯 Setting active Whisper model: {}", model_path);
     
     // Validate that the model file exists
     if !std::path::Path::new(&model_path).exists() {
-        return Err(format!("Model file does not exist: {}", model_path));
+        return Err(CommandError::model_not_found(&model_path, format!("Model file does not exist: {}", model_path)));
     }
     
-    // Set environment variable for the current session
-    std::env::set_var("WHISPER_MODEL_PATH", &model_path);
+    // 保存到运行时设置，重启后仍使用该模型
+    let database = Database::from_global_pool().await
+        .map_err(|e| CommandError::Database(format!("Failed to create database: {}", e)))?;
+    crate::voice_assistant::settings::save(&database, crate::voice_assistant::settings::WHISPER_MODEL_PATH, &model_path).await?;
     
-    println!("✅ Active Whisper model set to: {}", model_path);
+    info!("✅ Active Whisper model set to: {}", model_path);
     Ok(format!("Successfully set active model to: {}", std::path::Path::new(&model_path).file_name().and_then(|n| n.to_str()).unwrap_or(&model_path)))
 }
 
 #[tauri::command]
-pub fn get_active_whisper_model() -> Result<Option<String>, String> {
-    match std::env::var("WHISPER_MODEL_PATH") {
-        Ok(path) => {
+pub fn get_active_whisper_model() -> Result<Option<String>, CommandError> {
+    match crate::voice_assistant::settings::current().whisper_model_path {
+        Some(path) => {
             if std::path::Path::new(&path).exists() {
                 Ok(Some(path))
             } else {
-                println!("⚠️ WHISPER_MODEL_PATH is set but file doesn't exist: {}", path);
+                warn!("⚠️ Active Whisper model file doesn't exist: {}", path);
                 Ok(None)
             }
         }
-        Err(_) => Ok(None), // No environment variable set
+        None => Ok(None), // No model selected yet
+    }
+}
+
+/// 确认要删除的模型文件位于模型目录内（拒绝 `..` 和符号链接逃逸），返回规范化路径
+pub fn resolve_model_in_dir(
+    models_dir: &std::path::Path,
+    model_path: &str,
+) -> Result<std::path::PathBuf, String> {
+    let models_dir = std::fs::canonicalize(models_dir)
+        .map_err(|e| format!("Models directory {} is not accessible: {}", models_dir.display(), e))?;
+    let path = std::fs::canonicalize(model_path)
+        .map_err(|e| format!("Model file does not exist: {} ({})", model_path, e))?;
+
+    if !path.starts_with(&models_dir) {
+        return Err(format!(
+            "Refusing to delete {}: it is outside the models directory {}",
+            model_path,
+            models_dir.display()
+        ));
+    }
+    if !path.is_file() {
+        return Err(format!("Not a model file: {}", model_path));
+    }
+    Ok(path)
+}
+
+/// 删除模型文件，返回释放的字节数。当前激活的模型不能删除
+#[tauri::command]
+pub async fn delete_whisper_model(model_path: String) -> Result<u64, CommandError> {
+    info!("🗑️ Deleting Whisper model: {}", model_path);
+
+    let path = resolve_model_in_dir(&crate::utils::platform::get_models_dir(), &model_path).map_err(|e| CommandError::config_invalid("model_path", e))?;
+    let same_model = |other: &str| std::fs::canonicalize(other).map(|p| p == path).unwrap_or(false);
+
+    if crate::voice_assistant::settings::current().whisper_model_path.map(|active| same_model(&active)).unwrap_or(false) {
+        return Err(CommandError::config_invalid("model_path", format!(
+            "{} is the active model. Switch to another model before deleting it.",
+            path.file_name().and_then(|n| n.to_str()).unwrap_or(&model_path)
+        )));
+    }
+
+    // 模型仍在内存中时先释放 whisper 上下文
+    let loaded = {
+        let manager = crate::voice_assistant::global_whisper::get_global_whisper_manager().read().await;
+        manager.get_current_model_path().map(same_model).unwrap_or(false)
+    };
+    if loaded {
+        crate::voice_assistant::global_whisper::clear_whisper_processor().await?;
+    }
+
+    let freed = std::fs::metadata(&path)
+        .map_err(|e| format!("Failed to read model file metadata: {}", e))?
+        .len();
+    std::fs::remove_file(&path)
+        .map_err(|e| format!("Failed to delete model {}: {}", path.display(), e))?;
+
+    info!("✅ Deleted {} ({:.1} MB freed)", path.display(), freed as f64 / 1024.0 / 1024.0);
+    Ok(freed)
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    // Minimal HTTP server that answers 503 for the first `failures` requests, then 200
+    async fn spawn_flaky_server(failures: usize) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let hits_clone = hits.clone();
+
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = match listener.accept().await {
+                    Ok(conn) => conn,
+                    Err(_) => break,
+                };
+                let hit = hits_clone.fetch_add(1, Ordering::SeqCst);
+
+                // Drain the request so the client doesn't see a reset
+                let mut buf = Vec::new();
+                let mut chunk = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut chunk).await.unwrap_or(0);
+                    if n == 0 {
+                        break;
+                    }
+                    buf.extend_from_slice(&chunk[..n]);
+                    let text = String::from_utf8_lossy(&buf).to_lowercase();
+                    if let Some(header_end) = text.find("\r\n\r\n") {
+                        let content_length = text[..header_end]
+                            .lines()
+                            .find_map(|l| l.strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap_or(0)))
+                            .unwrap_or(0);
+                        if buf.len() >= header_end + 4 + content_length {
+                            break;
+                        }
+                    }
+                }
+
+                let response = if hit < failures {
+                    "HTTP/1.1 503 Service Unavailable\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()
+                } else {
+                    let body = r#"{"text":"hello world"}"#;
+                    format!(
+                        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                        body.len(),
+                        body
+                    )
+                };
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        (format!("http://{}/asr", addr), hits)
+    }
+
+    #[tokio::test]
+    async fn test_cloud_asr_retries_until_success() {
+        let (endpoint, hits) = spawn_flaky_server(2).await;
+
+        let response = test_cloud_asr_transcription(
+            vec![0u8; 16],
+            16,
+            std::time::Instant::now(),
+            &endpoint,
+            None,
+            &CloudAsrOptions::default(),
+            crate::voice_assistant::net::DEFAULT_MAX_RETRIES,
+        )
+        .await
+        .unwrap();
+
+        assert!(response.success);
+        assert_eq!(response.transcription.as_deref(), Some("hello world"));
+        assert!(response.message.contains("after 2 retries"));
+        assert_eq!(hits.load(Ordering::SeqCst), 3);
+    }
+
+    #[tokio::test]
+    async fn test_cloud_asr_gives_up_after_max_retries() {
+        let (endpoint, hits) = spawn_flaky_server(usize::MAX).await;
+
+        let response = test_cloud_asr_transcription(
+            vec![0u8; 16],
+            16,
+            std::time::Instant::now(),
+            &endpoint,
+            None,
+            &CloudAsrOptions::default(),
+            1,
+        )
+        .await
+        .unwrap();
+
+        assert!(!response.success);
+        assert!(response.message.contains("after 1 retry"));
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_cloud_asr_openai_provider() {
+        let (endpoint, hits) = spawn_flaky_server(0).await;
+        let options = CloudAsrOptions {
+            language: Some("en".to_string()),
+            ..CloudAsrOptions::new(CloudAsrProvider::OpenAi)
+        };
+
+        let response = test_cloud_asr_transcription(
+            vec![0u8; 16],
+            16,
+            std::time::Instant::now(),
+            &endpoint,
+            Some("API_KEY=sk-test".to_string()),
+            &options,
+            0,
+        )
+        .await
+        .unwrap();
+
+        assert!(response.success);
+        assert_eq!(response.transcription.as_deref(), Some("hello world"));
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    const LOG_MACROS: [&str; 8] = ["println!(", "eprintln!(", "print!(", "trace!(", "debug!(", "info!(", "warn!(", "error!("];
+    const SECRET_IDENTS: [&str; 3] = ["api_key", "secret", "password"];
+    // 只输出是否存在和长度是允许的
+    const SAFE_SUFFIXES: [&str; 4] = [".is_some()", ".is_none()", ".len()", ".as_ref().map_or(0, |k| k.len())"];
+
+    /// 扫描日志宏调用，返回在格式化参数中直接输出密钥变量的行号（从 1 开始）
+    fn find_secret_logging(source: &str) -> Vec<usize> {
+        let lines: Vec<&str> = source.lines().collect();
+        let mut findings = Vec::new();
+        let mut i = 0;
+
+        while i < lines.len() {
+            let trimmed = lines[i].trim_start();
+            let Some(start) = LOG_MACROS.iter().filter_map(|m| trimmed.find(m)).min() else {
+                i += 1;
+                continue;
+            };
+            if trimmed.starts_with("//") {
+                i += 1;
+                continue;
+            }
+
+            // 多行宏调用拼接到括号配平为止
+            let first_line = i;
+            let mut invocation = trimmed[start..].to_string();
+            while invocation.matches('(').count() > invocation.matches(')').count() && i + 1 < lines.len() {
+                i += 1;
+                invocation.push_str(lines[i].trim());
+            }
+            i += 1;
+
+            // 跳过格式字符串本身，只检查参数
+            let args = match invocation.find('"') {
+                Some(open) => {
+                    let rest = &invocation[open + 1..];
+                    let mut escaped = false;
+                    let close = rest.char_indices().find(|&(_, c)| {
+                        let is_end = c == '"' && !escaped;
+                        escaped = c == '\\' && !escaped;
+                        is_end
+                    });
+                    close.map(|(idx, _)| &rest[idx + 1..]).unwrap_or("")
+                }
+                None => invocation.as_str(),
+            };
+
+            // 以密钥名结尾的标识符（如 local_api_key）视为密钥变量
+            let mut leaks = false;
+            let mut ident_start = None;
+            for (idx, c) in args.char_indices().chain(std::iter::once((args.len(), ' '))) {
+                if c.is_alphanumeric() || c == '_' {
+                    ident_start.get_or_insert(idx);
+                    continue;
+                }
+                if let Some(begin) = ident_start.take() {
+                    let ident = &args[begin..idx];
+                    if SECRET_IDENTS.iter().any(|secret| ident.ends_with(secret))
+                        && !SAFE_SUFFIXES.iter().any(|suffix| args[idx..].starts_with(suffix))
+                    {
+                        leaks = true;
+                    }
+                }
+            }
+            if leaks {
+                findings.push(first_line + 1);
+            }
+        }
+        findings
+    }
+
+    #[test]
+    fn test_secret_logging_detector() {
+        let source = r#"
+            println!("key: {}", api_key);
+            println!("present: {}", request.local_api_key.is_some());
+            println!("len: {}", request.cloud_api_key.as_ref().map_or(0, |k| k.len()));
+            // println!("commented: {}", api_key);
+            info!("multi line {}",
+                config.local_api_key);
+            println!("api_key in the format string only");
+        "#;
+        assert_eq!(find_secret_logging(source), vec![2, 6]);
+    }
+
+    #[test]
+    fn test_no_secret_logging_in_command_database_and_coordinator_modules() {
+        let sources = [
+            ("commands.rs", include_str!("commands.rs")),
+            ("commands/error.rs", include_str!("commands/error.rs")),
+            ("commands/history_export.rs", include_str!("commands/history_export.rs")),
+            ("database.rs", include_str!("database.rs")),
+            ("voice_assistant/coordinator.rs", include_str!("voice_assistant/coordinator.rs")),
+        ];
+        for (name, source) in sources {
+            // 跳过测试模块自身的示例代码
+            let source = source.split("#[cfg(test)]").next().unwrap();
+            let findings = find_secret_logging(source);
+            assert!(findings.is_empty(), "{} logs secret values at lines {:?}", name, findings);
+        }
+    }
+
+    fn unique_temp_dir(label: &str) -> std::path::PathBuf {
+        let dir = std::env::temp_dir().join(format!("voicetype-{}-{}", label, uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_scan_whisper_models_includes_active_model_dir_once() {
+        let models_dir = unique_temp_dir("models");
+        let external_dir = unique_temp_dir("external");
+        std::fs::write(models_dir.join("ggml-base.bin"), vec![0u8; 2048]).unwrap();
+        std::fs::write(models_dir.join("ggml-tiny.bin"), vec![0u8; 1024]).unwrap();
+        std::fs::write(models_dir.join("ggml-silero-vad.bin"), vec![0u8; 16]).unwrap();
+        std::fs::write(models_dir.join("notes.txt"), b"not a model").unwrap();
+        std::fs::write(external_dir.join("ggml-large-v3-turbo.bin"), vec![0u8; 4096]).unwrap();
+
+        // 激活模型在外部目录：两个目录都被扫描
+        let active = external_dir.join("ggml-large-v3-turbo.bin");
+        let models = scan_whisper_models_in(&models_dir, Some(&active)).unwrap();
+        let names: Vec<&str> = models.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, vec!["ggml-large-v3-turbo.bin", "ggml-base.bin", "ggml-tiny.bin"]);
+        assert!(models[0].is_active);
+        assert!(models[1..].iter().all(|m| !m.is_active));
+
+        // 激活模型就在模型目录中（路径写法不同）：不会重复列出
+        let active = models_dir.join(".").join("ggml-base.bin");
+        let models = scan_whisper_models_in(&models_dir, Some(&active)).unwrap();
+        assert_eq!(models.len(), 2);
+        assert_eq!(models.iter().filter(|m| m.is_active).count(), 1);
+        assert!(models.iter().find(|m| m.name == "ggml-base.bin").unwrap().is_active);
+
+        std::fs::remove_dir_all(&models_dir).ok();
+        std::fs::remove_dir_all(&external_dir).ok();
+    }
+
+    #[test]
+    fn test_resolve_model_in_dir_rejects_paths_outside_models_dir() {
+        let models_dir = unique_temp_dir("models");
+        let outside_dir = unique_temp_dir("outside");
+        std::fs::write(models_dir.join("ggml-base.bin"), vec![0u8; 16]).unwrap();
+        std::fs::write(outside_dir.join("ggml-tiny.bin"), vec![0u8; 16]).unwrap();
+
+        let inside = models_dir.join("ggml-base.bin");
+        assert!(resolve_model_in_dir(&models_dir, inside.to_str().unwrap()).is_ok());
+
+        let outside = outside_dir.join("ggml-tiny.bin");
+        assert!(resolve_model_in_dir(&models_dir, outside.to_str().unwrap()).is_err());
+
+        // 通过 .. 跳出模型目录
+        let traversal = models_dir
+            .join("..")
+            .join(outside_dir.file_name().unwrap())
+            .join("ggml-tiny.bin");
+        let err = resolve_model_in_dir(&models_dir, traversal.to_str().unwrap()).unwrap_err();
+        assert!(err.contains("outside the models directory"));
+
+        // 目录本身不是模型文件
+        assert!(resolve_model_in_dir(&models_dir, models_dir.to_str().unwrap()).is_err());
+
+        std::fs::remove_dir_all(&models_dir).ok();
+        std::fs::remove_dir_all(&outside_dir).ok();
+    }
+
+    /// 按从新到旧的顺序生成记录，每条相隔一分钟
+    fn latency_series(values: &[i64]) -> Vec<crate::database::LatencyRecord> {
+        values
+            .iter()
+            .enumerate()
+            .map(|(i, &ms)| crate::database::LatencyRecord {
+                recorded_at: chrono::Utc::now() - chrono::Duration::minutes(i as i64),
+                ..latency_record(ms, None, None)
+            })
+            .collect()
+    }
+
+    fn latency_record(latency_ms: i64, record_ms: Option<i64>, asr_ms: Option<i64>) -> crate::database::LatencyRecord {
+        crate::database::LatencyRecord {
+            id: uuid::Uuid::new_v4().to_string(),
+            service_name: "local_asr".to_string(),
+            latency_ms,
+            request_type: "asr".to_string(),
+            recorded_at: chrono::Utc::now(),
+            record_ms,
+            wav_ms: None,
+            asr_ms,
+            postprocess_ms: None,
+            translate_ms: None,
+            typing_ms: None,
+            attempts: None,
+            race_local_ms: None,
+            race_cloud_ms: None,
+        }
+    }
+
+    #[test]
+    fn test_latency_trend_normalizes_by_audio_length() {
+        // 总耗时变长了，但每秒音频的 ASR 耗时从 300ms 降到 200ms
+        let mut records = latency_series(&[2500, 2500, 900, 900]);
+        for (i, record) in records.iter_mut().enumerate() {
+            let (record_ms, asr_ms) = if i < 2 { (10_000, 2000) } else { (2000, 600) };
+            record.record_ms = Some(record_ms);
+            record.asr_ms = Some(asr_ms);
+        }
+        assert_eq!(latency_trend(&records, DEFAULT_LATENCY_TREND_WINDOW), ("down", -33));
+
+        // 有记录没有分阶段数据时比较总耗时
+        records[3].asr_ms = None;
+        assert_eq!(latency_trend(&records, DEFAULT_LATENCY_TREND_WINDOW), ("up", 178));
+    }
+
+    #[test]
+    fn test_latency_trend_increasing_decreasing_and_flat() {
+        let window = DEFAULT_LATENCY_TREND_WINDOW;
+        // 最新的在前：最近两条 600ms，之前两条 400ms
+        assert_eq!(latency_trend(&latency_series(&[600, 600, 400, 400]), window), ("up", 50));
+        assert_eq!(latency_trend(&latency_series(&[300, 300, 400, 400]), window), ("down", -25));
+        assert_eq!(latency_trend(&latency_series(&[500, 490, 505, 500]), window), ("neutral", -1));
+
+        // 不依赖输入顺序
+        let mut shuffled = latency_series(&[600, 600, 400, 400]);
+        shuffled.reverse();
+        assert_eq!(latency_trend(&shuffled, window), ("up", 50));
+
+        // 窗口大小可配置：窗口 2 只看最近 4 条
+        let records = latency_series(&[800, 800, 400, 400, 100, 100]);
+        assert_eq!(latency_trend(&records, 2), ("up", 100));
+        assert_eq!(latency_trend(&records, 3), ("up", 233));
+
+        // 少于 4 条不计算
+        assert_eq!(latency_trend(&latency_series(&[900, 100, 100]), window), ("neutral", 0));
+    }
+
+    #[test]
+    fn test_latency_percentiles_known_distribution() {
+        // 1..=100ms，打乱顺序
+        let values: Vec<i64> = (1..=100).rev().collect();
+        assert_eq!(latency_percentiles(values), LatencyPercentiles { p50: 50, p90: 90, p99: 99, max: 100, count: 100 });
+
+        let skewed = vec![100, 100, 100, 100, 100, 100, 100, 100, 100, 5000];
+        assert_eq!(latency_percentiles(skewed), LatencyPercentiles { p50: 100, p90: 100, p99: 5000, max: 5000, count: 10 });
+
+        assert_eq!(latency_percentiles(vec![42]), LatencyPercentiles { p50: 42, p90: 42, p99: 42, max: 42, count: 1 });
+        assert_eq!(latency_percentiles(vec![]), LatencyPercentiles::default());
+    }
+
+    #[test]
+    fn test_scan_whisper_models_missing_dir_is_empty() {
+        let missing = std::env::temp_dir().join(format!("voicetype-missing-{}", uuid::Uuid::new_v4()));
+        assert!(scan_whisper_models_in(&missing, None).unwrap().is_empty());
     }
-}
\ No newline at end of file
+}
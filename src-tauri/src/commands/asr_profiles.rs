@@ -0,0 +1,160 @@
+use tauri::State;
+use tracing::{info, warn};
+use crate::database::{AsrConfig, Database};
+use super::DatabaseState;
+
+fn database_from_state(db_state: &DatabaseState) -> Result<Database, String> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    db.ok_or_else(|| "Database not initialized".to_string())
+}
+
+/// 循环切换时当前方案的下一个（按创建时间），到末尾回到第一个
+pub fn next_profile(profiles: &[AsrConfig]) -> Option<&AsrConfig> {
+    if profiles.len() < 2 {
+        return None;
+    }
+    let next = profiles
+        .iter()
+        .position(|p| p.is_active)
+        .map_or(0, |active| (active + 1) % profiles.len());
+    profiles.get(next)
+}
+
+/// 切换当前方案，然后让运行中的助手重新加载配置，下一次录音就使用新的处理器
+async fn activate(database: &Database, id: &str) -> Result<AsrConfig, String> {
+    let profile = database.activate_asr_profile(id).await
+        .map_err(|e| format!("Failed to activate ASR profile: {}", e))?
+        .ok_or_else(|| format!("ASR profile not found: {}", id))?;
+    info!("🎚️ Active ASR profile: {} ({})", profile.name, profile.service_provider);
+
+    if let Err(e) = crate::voice_assistant::coordinator::refresh_running_voice_assistant().await {
+        warn!("⚠️ Failed to refresh VoiceAssistant after switching ASR profile: {}", e);
+    }
+    crate::voice_assistant::coordinator::emit_asr_profile_changed(&profile);
+    Ok(profile)
+}
+
+#[tauri::command]
+pub async fn list_asr_profiles(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Vec<AsrConfig>, String> {
+    let database = database_from_state(&db_state)?;
+    let profiles = database.list_asr_profiles().await
+        .map_err(|e| format!("Failed to list ASR profiles: {}", e))?;
+    Ok(profiles.into_iter().map(AsrConfig::masked).collect())
+}
+
+/// 以当前方案的设置新建方案，激活后在 ASR 设置中修改
+#[tauri::command]
+pub async fn create_asr_profile(
+    db_state: State<'_, DatabaseState>,
+    name: String,
+) -> Result<AsrConfig, String> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err("Profile name must not be empty".to_string());
+    }
+
+    let database = database_from_state(&db_state)?;
+    if database.asr_profile_name_exists(name).await
+        .map_err(|e| format!("Failed to check ASR profile name: {}", e))?
+    {
+        return Err(format!("An ASR profile named '{}' already exists", name));
+    }
+
+    let profile = database.create_asr_profile(name).await
+        .map_err(|e| format!("Failed to create ASR profile: {}", e))?;
+    Ok(profile.masked())
+}
+
+#[tauri::command]
+pub async fn activate_asr_profile(
+    db_state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<AsrConfig, String> {
+    let database = database_from_state(&db_state)?;
+    activate(&database, &id).await.map(AsrConfig::masked)
+}
+
+/// 删除方案；删除的是当前方案时自动改用最近修改的其他方案
+#[tauri::command]
+pub async fn delete_asr_profile(
+    db_state: State<'_, DatabaseState>,
+    id: String,
+) -> Result<bool, String> {
+    let database = database_from_state(&db_state)?;
+    let was_active = database.get_asr_config().await
+        .map_err(|e| format!("Failed to get ASR config: {}", e))?
+        .is_some_and(|active| active.id == id);
+
+    let deleted = database.delete_asr_profile(&id).await
+        .map_err(|e| format!("Failed to delete ASR profile: {}", e))?;
+
+    if deleted && was_active {
+        if let Some(profile) = database.get_asr_config().await
+            .map_err(|e| format!("Failed to get ASR config: {}", e))?
+        {
+            activate(&database, &profile.id).await?;
+        }
+    }
+    Ok(deleted)
+}
+
+/// 切换到下一个方案（循环热键）；只有一个方案时返回 None
+pub async fn cycle_asr_profile_internal() -> Result<Option<AsrConfig>, String> {
+    let database = Database::from_global_pool().await
+        .map_err(|e| format!("Failed to create database: {}", e))?;
+    let profiles = database.list_asr_profiles().await
+        .map_err(|e| format!("Failed to list ASR profiles: {}", e))?;
+
+    match next_profile(&profiles) {
+        Some(next) => activate(&database, &next.id).await.map(Some),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn profile(id: &str, is_active: bool) -> AsrConfig {
+        AsrConfig {
+            id: id.to_string(),
+            service_provider: "whisper-rs".to_string(),
+            local_endpoint: None,
+            local_api_key: None,
+            cloud_endpoint: None,
+            cloud_api_key: None,
+            whisper_model: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+            enable_vad: false,
+            cloud_provider: "custom".to_string(),
+            cloud_model: None,
+            cloud_language: None,
+            fallback_chain: None,
+            name: id.to_string(),
+            is_active,
+            key_unavailable: false,
+        }
+    }
+
+    #[test]
+    fn test_next_profile_wraps_around() {
+        let profiles = vec![profile("a", false), profile("b", true), profile("c", false)];
+        assert_eq!(next_profile(&profiles).unwrap().id, "c");
+
+        let profiles = vec![profile("a", false), profile("b", true)];
+        assert_eq!(next_profile(&profiles).unwrap().id, "a");
+
+        // 没有激活的方案时从第一个开始
+        let profiles = vec![profile("a", false), profile("b", false)];
+        assert_eq!(next_profile(&profiles).unwrap().id, "a");
+
+        assert!(next_profile(&[profile("a", true)]).is_none());
+    }
+}
@@ -0,0 +1,288 @@
+use crate::database::{BenchmarkResult, Database};
+use crate::voice_assistant::asr::whisper_rs::{WhisperBackend, WhisperRSProcessor};
+use crate::voice_assistant::audio::{decode_to_samples, resample_to_16k_mono};
+use crate::voice_assistant::audio_decoder::encode_wav;
+use crate::voice_assistant::traits::AsrProcessor;
+use crate::voice_assistant::Mode;
+use serde::{Deserialize, Serialize};
+use std::io::Cursor;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use tauri::{AppHandle, Emitter};
+use tracing::{info, warn};
+
+/// 随应用打包的参考音频文件名（约 10 秒的朗读）
+const REFERENCE_WAV_NAME: &str = "benchmark_reference.wav";
+/// 找不到参考音频时合成的测试信号长度
+const SYNTHETIC_AUDIO_SECS: u32 = 10;
+const SAMPLE_RATE: u32 = crate::voice_assistant::audio::TARGET_SAMPLE_RATE;
+
+/// 同一时间只允许一个基准测试
+static BENCHMARK_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+struct BenchmarkGuard;
+
+impl Drop for BenchmarkGuard {
+    fn drop(&mut self) {
+        BENCHMARK_IN_PROGRESS.store(false, Ordering::Release);
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BenchmarkReport {
+    /// "bundled" 表示使用打包的参考录音，"synthetic" 表示合成信号
+    pub audio_source: String,
+    /// 使用合成信号时的提示，前端应与结果一起显示
+    pub audio_note: Option<String>,
+    pub audio_ms: i64,
+    pub app_version: String,
+    pub results: Vec<BenchmarkResult>,
+}
+
+/// 查找打包的参考音频：安装目录或开发时的 resources 目录
+fn find_reference_wav() -> Option<PathBuf> {
+    let mut exe_dir = std::env::current_exe().ok()?;
+    exe_dir.pop();
+
+    [
+        exe_dir.join(REFERENCE_WAV_NAME),
+        exe_dir.join("resources").join(REFERENCE_WAV_NAME),
+        PathBuf::from("resources").join(REFERENCE_WAV_NAME),
+    ]
+    .into_iter()
+    .find(|path| path.is_file())
+}
+
+/// 合成类似语音包络的信号：基频 + 谐波，按音节节奏调幅。
+/// 不能代表识别准确率，但推理耗时只与音频长度有关，足够用于测吞吐
+fn synthetic_reference_audio() -> Vec<f32> {
+    let len = (SAMPLE_RATE * SYNTHETIC_AUDIO_SECS) as usize;
+    (0..len)
+        .map(|i| {
+            let t = i as f32 / SAMPLE_RATE as f32;
+            let pitch = 140.0 + 20.0 * (2.0 * std::f32::consts::PI * 0.5 * t).sin();
+            let voice: f32 = (1..=4)
+                .map(|h| (2.0 * std::f32::consts::PI * pitch * h as f32 * t).sin() / h as f32)
+                .sum();
+            let syllables = (2.0 * std::f32::consts::PI * 4.0 * t).sin().max(0.0);
+            0.2 * voice * syllables
+        })
+        .collect()
+}
+
+/// 返回 16kHz 单声道样本和音频来源
+fn load_reference_audio() -> (Vec<f32>, &'static str) {
+    if let Some(path) = find_reference_wav() {
+        match decode_to_samples(&path) {
+            Ok((samples, sample_rate)) => {
+                info!("🎧 Using bundled benchmark audio: {}", path.display());
+                return (resample_to_16k_mono(&samples, sample_rate, 1), "bundled");
+            }
+            Err(e) => warn!("⚠️ Failed to load benchmark audio {}: {}", path.display(), e),
+        }
+    }
+    warn!("⚠️ Bundled benchmark audio not found, using a synthetic signal (speed only, not accuracy)");
+    (synthetic_reference_audio(), "synthetic")
+}
+
+/// 合成信号不是语音，结果只能比较同一台机器上不同模型/后端的相对速度
+fn audio_source_note(audio_source: &str) -> Option<String> {
+    (audio_source == "synthetic").then(|| {
+        format!(
+            "No bundled speech sample ({}) was found; this run used a synthetic tone. \
+             Timings show relative speed only and say nothing about accuracy or decode time on real speech.",
+            REFERENCE_WAV_NAME
+        )
+    })
+}
+
+fn audio_duration_ms(samples: &[f32]) -> i64 {
+    samples.len() as i64 * 1000 / SAMPLE_RATE as i64
+}
+
+/// 实时因子 = 推理耗时 / 音频时长，小于 1 表示比实时快
+fn realtime_factor(inference_ms: i64, audio_ms: i64) -> Option<f64> {
+    (audio_ms > 0).then(|| inference_ms as f64 / audio_ms as f64)
+}
+
+fn peak_rss_mb() -> Option<f64> {
+    crate::utils::platform::peak_rss_bytes().map(|bytes| bytes as f64 / (1024.0 * 1024.0))
+}
+
+/// 用独立的处理器加载模型并识别一次，返回 (实际后端, 加载耗时, 推理耗时)
+fn benchmark_model(model_path: &str, backend: WhisperBackend, wav_bytes: Vec<u8>) -> Result<(String, i64, i64), String> {
+    let load_start = Instant::now();
+    let processor = WhisperRSProcessor::with_model_path_and_backend(model_path, backend).map_err(|e| e.to_string())?;
+    let load_ms = load_start.elapsed().as_millis() as i64;
+    let effective_backend = processor.effective_backend().to_string();
+
+    let inference_start = Instant::now();
+    match processor.process_audio(Cursor::new(wav_bytes), Mode::Transcriptions, "") {
+        // 合成信号可能识别不出内容，只要推理跑完就算成功
+        Ok(_) | Err(crate::voice_assistant::traits::VoiceError::NoSpeech) => {}
+        Err(e) => return Err(e.to_string()),
+    }
+    let inference_ms = inference_start.elapsed().as_millis() as i64;
+
+    // processor 在这里 drop，释放模型内存后再测下一个
+    Ok((effective_backend, load_ms, inference_ms))
+}
+
+fn emit_progress(app: &AppHandle, index: usize, total: usize, model_name: &str, stage: &str, result: Option<&BenchmarkResult>) {
+    let _ = app.emit("benchmark-progress", serde_json::json!({
+        "index": index,
+        "total": total,
+        "model_name": model_name,
+        "stage": stage,
+        "result": result
+    }));
+}
+
+/// 测试模型的真实识别吞吐：加载耗时、推理耗时、实时因子和峰值内存。
+/// `model_path` 为空或 "all" 时测试所有已下载的模型。
+/// 每个模型使用独立的 WhisperRS 实例，不会替换语音助手正在使用的全局模型；
+/// 测试期间会额外占用一份模型内存，结束后释放
+#[tauri::command]
+pub async fn run_whisper_benchmark(app: AppHandle, model_path: Option<String>) -> Result<BenchmarkReport, String> {
+    let models: Vec<(String, String)> = match model_path.as_deref().map(str::trim) {
+        None | Some("") | Some("all") => crate::commands::scan_whisper_models().map_err(|e| e.to_string())?
+            .into_iter()
+            .map(|m| (m.name, m.path))
+            .collect(),
+        Some(path) => {
+            let path_buf = PathBuf::from(path);
+            if !path_buf.is_file() {
+                return Err(format!("Whisper model file not found: {}", path));
+            }
+            let name = path_buf.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| path.to_string());
+            vec![(name, path.to_string())]
+        }
+    };
+    if models.is_empty() {
+        return Err("No Whisper models found to benchmark".to_string());
+    }
+
+    if crate::voice_assistant::global_whisper::is_inference_in_progress() {
+        return Err("Voice assistant is transcribing, try the benchmark again later".to_string());
+    }
+    if BENCHMARK_IN_PROGRESS
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        return Err("A benchmark is already running".to_string());
+    }
+    let _guard = BenchmarkGuard;
+
+    let (audio, audio_source) = load_reference_audio();
+    let audio_ms = audio_duration_ms(&audio);
+    let wav_bytes = encode_wav(&audio, SAMPLE_RATE).map_err(|e| e.to_string())?;
+    // 与全局处理器使用相同的后端设置，结果能代表语音助手的实际速度
+    let requested_backend = crate::voice_assistant::global_whisper::get_global_whisper_manager()
+        .read()
+        .await
+        .requested_backend()
+        .clone();
+    let app_version = env!("CARGO_PKG_VERSION").to_string();
+    let database = Database::from_global_pool().await.ok();
+
+    info!("🏁 Benchmarking {} Whisper model(s) with {}ms of {} audio", models.len(), audio_ms, audio_source);
+    let total = models.len();
+    let mut results = Vec::with_capacity(total);
+
+    for (index, (model_name, model_path)) in models.into_iter().enumerate() {
+        emit_progress(&app, index, total, &model_name, "running", None);
+
+        let path = model_path.clone();
+        let bytes = wav_bytes.clone();
+        let backend = requested_backend.clone();
+        let outcome = tauri::async_runtime::spawn_blocking(move || benchmark_model(&path, backend, bytes))
+            .await
+            .map_err(|e| format!("Benchmark task failed: {}", e))
+            .and_then(|r| r);
+
+        let (backend, load_ms, inference_ms, error_message) = match outcome {
+            Ok((backend, load_ms, inference_ms)) => (backend, Some(load_ms), Some(inference_ms), None),
+            Err(e) => (requested_backend.to_string(), None, None, Some(e)),
+        };
+        let result = BenchmarkResult {
+            id: uuid::Uuid::new_v4().to_string(),
+            model_name: model_name.clone(),
+            model_path,
+            backend,
+            app_version: app_version.clone(),
+            load_ms,
+            inference_ms,
+            audio_ms,
+            audio_source: audio_source.to_string(),
+            realtime_factor: inference_ms.and_then(|ms| realtime_factor(ms, audio_ms)),
+            // 进程级高水位，按测试顺序单调不减
+            peak_rss_mb: peak_rss_mb(),
+            success: error_message.is_none(),
+            error_message,
+            created_at: chrono::Utc::now(),
+        };
+
+        match &result.error_message {
+            None => info!(
+                "✅ {}: load {}ms, inference {}ms, RTF {:.2}",
+                model_name,
+                load_ms.unwrap_or(0),
+                inference_ms.unwrap_or(0),
+                result.realtime_factor.unwrap_or(0.0)
+            ),
+            Some(e) => warn!("❌ {}: benchmark failed: {}", model_name, e),
+        }
+
+        if let Some(db) = &database {
+            if let Err(e) = db.save_benchmark_result(&result).await {
+                warn!("⚠️ Failed to save benchmark result: {}", e);
+            }
+        }
+
+        emit_progress(&app, index + 1, total, &model_name, if result.success { "done" } else { "error" }, Some(&result));
+        results.push(result);
+    }
+
+    Ok(BenchmarkReport {
+        audio_source: audio_source.to_string(),
+        audio_note: audio_source_note(audio_source),
+        audio_ms,
+        app_version,
+        results,
+    })
+}
+
+/// 历史基准测试结果，用于比较升级前后的性能
+#[tauri::command]
+pub async fn get_benchmark_results(limit: Option<i64>) -> Result<Vec<BenchmarkResult>, String> {
+    let database = Database::from_global_pool().await
+        .map_err(|e| format!("Failed to get database instance: {}", e))?;
+    database.get_benchmark_results(limit.unwrap_or(50).clamp(1, 500)).await
+        .map_err(|e| format!("Failed to get benchmark results: {}", e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_realtime_factor() {
+        assert_eq!(realtime_factor(2500, 10_000), Some(0.25));
+        assert_eq!(realtime_factor(100, 0), None);
+    }
+
+    #[test]
+    fn test_synthetic_audio_is_labelled() {
+        assert!(audio_source_note("synthetic").unwrap().contains("synthetic tone"));
+        assert!(audio_source_note("bundled").is_none());
+    }
+
+    #[test]
+    fn test_synthetic_reference_audio_length() {
+        let audio = synthetic_reference_audio();
+        assert_eq!(audio_duration_ms(&audio), SYNTHETIC_AUDIO_SECS as i64 * 1000);
+        assert!(audio.iter().all(|s| s.abs() <= 1.0));
+        assert!(encode_wav(&audio, SAMPLE_RATE).is_ok());
+    }
+}
@@ -0,0 +1,180 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use thiserror::Error;
+use crate::voice_assistant::VoiceError;
+
+/// Tauri 命令返回给前端的结构化错误，序列化为 `{ code, message }`，另带各变体的结构化字段
+/// （网络错误的 `status`、找不到模型的 `path`、配置错误的 `field`）；日志中仍使用 Display 输出的文字
+#[derive(Debug, Clone, Error)]
+pub enum CommandError {
+    #[error("Database not initialized")]
+    DatabaseNotInitialized,
+    #[error("Database error: {0}")]
+    Database(String),
+    /// path 为模型文件路径或模型名，message 说明如何解决
+    #[error("Model not found: {message}")]
+    ModelNotFound { path: String, message: String },
+    #[error("Network error: {message}")]
+    Network { status: Option<u16>, message: String },
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+    /// field 为出错的配置项或请求字段
+    #[error("Invalid configuration: {message}")]
+    ConfigInvalid { field: String, message: String },
+    #[error("Audio error: {0}")]
+    Audio(String),
+    /// 本地 whisper.cpp 加载模型或推理失败
+    #[error("Whisper error: {0}")]
+    Whisper(String),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl CommandError {
+    pub fn model_not_found(path: &str, message: impl Into<String>) -> Self {
+        CommandError::ModelNotFound { path: path.to_string(), message: message.into() }
+    }
+
+    pub fn config_invalid(field: &str, message: impl Into<String>) -> Self {
+        CommandError::ConfigInvalid { field: field.to_string(), message: message.into() }
+    }
+
+    /// 稳定的机器可读错误码，前端据此分支处理
+    pub fn code(&self) -> &'static str {
+        match self {
+            CommandError::DatabaseNotInitialized => "DATABASE_NOT_INITIALIZED",
+            CommandError::Database(_) => "DATABASE_ERROR",
+            CommandError::ModelNotFound { .. } => "MODEL_NOT_FOUND",
+            CommandError::Network { .. } => "NETWORK_ERROR",
+            CommandError::Unauthorized(_) => "UNAUTHORIZED",
+            CommandError::ConfigInvalid { .. } => "INVALID_CONFIG",
+            CommandError::Audio(_) => "AUDIO_ERROR",
+            CommandError::Whisper(_) => "WHISPER_ERROR",
+            CommandError::Other(_) => "UNKNOWN",
+        }
+    }
+}
+
+impl Serialize for CommandError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let extra = usize::from(matches!(
+            self,
+            CommandError::Network { status: Some(_), .. } | CommandError::ModelNotFound { .. } | CommandError::ConfigInvalid { .. }
+        ));
+        let mut state = serializer.serialize_struct("CommandError", 2 + extra)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        match self {
+            CommandError::Network { status: Some(status), .. } => state.serialize_field("status", status)?,
+            CommandError::ModelNotFound { path, .. } => state.serialize_field("path", path)?,
+            CommandError::ConfigInvalid { field, .. } => state.serialize_field("field", field)?,
+            _ => {}
+        }
+        state.end()
+    }
+}
+
+impl From<VoiceError> for CommandError {
+    fn from(e: VoiceError) -> Self {
+        match e {
+            VoiceError::Network(e) => CommandError::from(e),
+            VoiceError::Timeout(_) => CommandError::Network { status: None, message: e.to_string() },
+            VoiceError::Audio(msg) => CommandError::Audio(msg),
+            VoiceError::TooShort | VoiceError::NoSpeech => CommandError::Audio(e.to_string()),
+            VoiceError::PermissionDenied => CommandError::Unauthorized(e.to_string()),
+            VoiceError::ModelNotFound { ref searched } => CommandError::model_not_found(&searched.join(", "), e.to_string()),
+            other => CommandError::Other(other.to_string()),
+        }
+    }
+}
+
+impl From<sqlx::Error> for CommandError {
+    fn from(e: sqlx::Error) -> Self {
+        CommandError::Database(e.to_string())
+    }
+}
+
+impl From<reqwest::Error> for CommandError {
+    fn from(e: reqwest::Error) -> Self {
+        CommandError::Network {
+            status: e.status().map(|status| status.as_u16()),
+            message: e.to_string(),
+        }
+    }
+}
+
+impl From<String> for CommandError {
+    fn from(s: String) -> Self {
+        CommandError::Other(s)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serialized_shape_is_code_and_message() {
+        let value = serde_json::to_value(CommandError::DatabaseNotInitialized).unwrap();
+        assert_eq!(value, serde_json::json!({
+            "code": "DATABASE_NOT_INITIALIZED",
+            "message": "Database not initialized",
+        }));
+
+        let value = serde_json::to_value(CommandError::config_invalid("endpoint", "missing endpoint")).unwrap();
+        assert_eq!(value, serde_json::json!({
+            "code": "INVALID_CONFIG",
+            "message": "Invalid configuration: missing endpoint",
+            "field": "endpoint",
+        }));
+
+        let value = serde_json::to_value(CommandError::model_not_found("/models/ggml-base.bin", "Model file does not exist")).unwrap();
+        assert_eq!(value["code"], "MODEL_NOT_FOUND");
+        assert_eq!(value["path"], "/models/ggml-base.bin");
+    }
+
+    #[test]
+    fn test_from_voice_error() {
+        assert_eq!(CommandError::from(VoiceError::NoSpeech).code(), "AUDIO_ERROR");
+        assert_eq!(CommandError::from(VoiceError::Other("x".to_string())).code(), "UNKNOWN");
+        let missing = VoiceError::ModelNotFound { searched: vec!["/models".to_string()] };
+        assert!(matches!(CommandError::from(missing), CommandError::ModelNotFound { path, .. } if path == "/models"));
+
+        let timeout = CommandError::from(VoiceError::Timeout("no response within 30s".to_string()));
+        assert_eq!(timeout.code(), "NETWORK_ERROR");
+        assert_eq!(timeout.to_string(), "Network error: Timeout: no response within 30s");
+    }
+
+    #[test]
+    fn test_network_status_is_serialized_when_known() {
+        let value = serde_json::to_value(CommandError::Network { status: Some(401), message: "bad key".to_string() }).unwrap();
+        assert_eq!(value, serde_json::json!({
+            "code": "NETWORK_ERROR",
+            "message": "Network error: bad key",
+            "status": 401,
+        }));
+
+        let value = serde_json::to_value(CommandError::Network { status: None, message: "connection refused".to_string() }).unwrap();
+        assert!(value.get("status").is_none());
+    }
+
+    #[test]
+    fn test_from_sqlx_and_string_errors() {
+        let db_error = CommandError::from(sqlx::Error::RowNotFound);
+        assert_eq!(db_error.code(), "DATABASE_ERROR");
+        assert!(db_error.to_string().starts_with("Database error: "));
+
+        let other = CommandError::from("Something went wrong".to_string());
+        assert_eq!(other.code(), "UNKNOWN");
+        assert_eq!(other.to_string(), "Something went wrong");
+        assert_eq!(CommandError::Whisper("bad model".to_string()).code(), "WHISPER_ERROR");
+    }
+
+    #[test]
+    fn test_from_reqwest_error_has_no_status_without_response() {
+        // 无效 URL 在构建请求时就失败，没有 HTTP 状态码，也不发生网络请求
+        let e = reqwest::Client::new().get("not a url").build().unwrap_err();
+        let error = CommandError::from(e);
+        assert!(matches!(error, CommandError::Network { status: None, .. }), "unexpected error: {:?}", error);
+    }
+}
@@ -2,6 +2,7 @@ use crate::voice_assistant::asr::gpu_detector::get_gpu_detector;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::process::Command;
+use tracing::warn;
 
 #[cfg(target_os = "windows")]
 use std::os::windows::process::CommandExt;
@@ -23,6 +24,37 @@ pub struct GpuBackendStatus {
     pub detection_timestamp: String,
 }
 
+fn nvidia_smi_path() -> &'static str {
+    if cfg!(windows) {
+        "C:\\Windows\\System32\\nvidia-smi.exe"
+    } else {
+        "nvidia-smi"
+    }
+}
+
+/// 解析 `nvidia-smi --query-gpu=memory.free --format=csv,noheader,nounits` 的输出（每块显卡一行，单位 MiB）
+fn parse_free_vram_mb(output: &str, device: usize) -> Option<u64> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .nth(device)
+        .and_then(|line| line.trim().parse::<u64>().ok())
+}
+
+/// 查询指定 NVIDIA 显卡的可用显存（MiB），没有 nvidia-smi 或查询失败时返回 None
+pub fn query_nvidia_free_vram_mb(device: usize) -> Option<u64> {
+    let mut command = Command::new(nvidia_smi_path());
+    command.args(["--query-gpu=memory.free", "--format=csv,noheader,nounits"]);
+    #[cfg(target_os = "windows")]
+    command.creation_flags(0x08000000); // CREATE_NO_WINDOW
+
+    let output = command.output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    parse_free_vram_mb(&String::from_utf8_lossy(&output.stdout), device)
+}
+
 /// NVIDIA 驱动版本检查结果
 #[derive(Debug, Serialize, Deserialize)]
 pub struct NvidiaDriverInfo {
@@ -213,17 +245,50 @@ pub fn get_gpu_backend_status() -> Result<GpuBackendStatus, String> {
     Ok(status)
 }
 
+/// 设置首选后端，已加载的 Whisper 模型和运行中的听写处理器会用新的 GPU 参数重新加载，
+/// 返回信息中包含听写实际使用的后端
 #[tauri::command]
-pub fn set_preferred_gpu_backend(backend: String) -> Result<String, String> {
-    // Validate backend string
-    let valid_backends = ["CUDA", "Vulkan", "Metal", "CPU", "OpenCL"];
-    if !valid_backends.contains(&backend.as_str()) {
-        return Err(format!("Invalid backend '{}'. Valid options: {:?}", backend, valid_backends));
+pub async fn set_preferred_gpu_backend(backend: String) -> Result<String, String> {
+    use crate::voice_assistant::asr::whisper_rs::WhisperBackend;
+
+    let parsed = WhisperBackend::parse(&backend).ok_or_else(|| {
+        format!("Invalid backend '{}'. Valid options: [\"CUDA\", \"Vulkan\", \"Metal\", \"CPU\", \"OpenCL\"]", backend)
+    })?;
+
+    if parsed != WhisperBackend::CPU {
+        let detector = get_gpu_detector();
+        let mut guard = detector.lock().map_err(|e| format!("Failed to acquire GPU detector lock: {}", e))?;
+        // 检测不到也允许设置，加载时会回退到 CPU 并通过 gpu-backend-fallback 事件提示
+        if let Err(e) = guard.set_preferred_backend(parsed.clone()) {
+            warn!("⚠️ {}", e);
+        }
     }
 
-    // TODO: Implement setting preferred backend in GpuDetector
-    // For now, return current status
-    Ok(format!("Preferred backend set to {} (implementation pending)", backend))
+    // 保存到运行时设置，重启后仍使用该后端
+    match crate::database::Database::from_global_pool().await {
+        Ok(database) => {
+            crate::voice_assistant::settings::save(&database, crate::voice_assistant::settings::WHISPER_BACKEND, &parsed.to_string()).await?;
+        }
+        Err(e) => warn!("⚠️ Failed to persist preferred backend: {}", e),
+    }
+
+    let reloaded = crate::voice_assistant::global_whisper::set_whisper_backend(parsed.clone())
+        .await
+        .map_err(|e| format!("Failed to reload Whisper with {} backend: {}", parsed, e))?;
+
+    // 听写的 worker / 进程内处理器在启动时读取后端设置，重建处理器后才会使用新的后端
+    crate::voice_assistant::coordinator::refresh_running_voice_assistant()
+        .await
+        .map_err(|e| format!("Failed to restart dictation with {} backend: {}", parsed, e))?;
+
+    let mut message = format!("Preferred backend set to {}", parsed);
+    if reloaded {
+        message.push_str(", Whisper model reloaded");
+    }
+    if let Some(backend) = crate::voice_assistant::coordinator::running_whisper_backend() {
+        message.push_str(&format!(", dictation is using {}", backend));
+    }
+    Ok(message)
 }
 
 #[tauri::command]
@@ -259,4 +324,17 @@ pub fn test_backend_performance(backend: String) -> Result<HashMap<String, Strin
     results.insert("timestamp".to_string(), chrono::Utc::now().to_rfc3339());
 
     Ok(results)
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_free_vram_mb() {
+        let output = "3712\n10240\n";
+        assert_eq!(parse_free_vram_mb(output, 0), Some(3712));
+        assert_eq!(parse_free_vram_mb(output, 1), Some(10240));
+        assert_eq!(parse_free_vram_mb(output, 2), None);
+        assert_eq!(parse_free_vram_mb("No devices were found", 0), None);
+    }
+}
@@ -0,0 +1,257 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tauri::State;
+use crate::database::HistoryRecord;
+use super::DatabaseState;
+
+/// 每批从数据库读取的记录数，导出时不会一次性加载全部历史
+const EXPORT_BATCH_SIZE: i64 = 500;
+
+const CSV_HEADER: [&str; 10] = [
+    "id",
+    "record_type",
+    "input_text",
+    "output_text",
+    "audio_file_path",
+    "processor_type",
+    "processing_time_ms",
+    "success",
+    "error_message",
+    "created_at",
+];
+
+/// 字段包含逗号、引号或换行时加引号，内部引号加倍（RFC 4180）
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+pub struct HistoryCsvWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> HistoryCsvWriter<W> {
+    pub fn new(mut writer: W) -> std::io::Result<Self> {
+        writeln!(writer, "{}", CSV_HEADER.join(","))?;
+        Ok(Self { writer })
+    }
+
+    pub fn write_record(&mut self, record: &HistoryRecord) -> std::io::Result<()> {
+        let fields = [
+            record.id.clone(),
+            record.record_type.clone(),
+            record.input_text.clone().unwrap_or_default(),
+            record.output_text.clone().unwrap_or_default(),
+            record.audio_file_path.clone().unwrap_or_default(),
+            record.processor_type.clone().unwrap_or_default(),
+            record.processing_time_ms.map(|ms| ms.to_string()).unwrap_or_default(),
+            record.success.to_string(),
+            record.error_message.clone().unwrap_or_default(),
+            record.created_at.to_rfc3339(),
+        ];
+
+        let line = fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(",");
+        writeln!(self.writer, "{}", line)
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+/// 逐条写出格式化的 JSON 数组，输出与 `serde_json::to_string_pretty(&Vec<HistoryRecord>)` 一致
+pub struct HistoryJsonWriter<W: Write> {
+    writer: W,
+    count: u64,
+}
+
+impl<W: Write> HistoryJsonWriter<W> {
+    pub fn new(writer: W) -> Self {
+        Self { writer, count: 0 }
+    }
+
+    pub fn write_record(&mut self, record: &HistoryRecord) -> std::io::Result<()> {
+        let json = serde_json::to_string_pretty(record)?;
+        let separator = if self.count == 0 { "[" } else { "," };
+        write!(self.writer, "{}\n  {}", separator, json.replace('\n', "\n  "))?;
+        self.count += 1;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> std::io::Result<W> {
+        if self.count == 0 {
+            write!(self.writer, "[]")?;
+        } else {
+            write!(self.writer, "\n]")?;
+        }
+        self.writer.flush()?;
+        Ok(self.writer)
+    }
+}
+
+enum HistoryWriter {
+    Csv(HistoryCsvWriter<BufWriter<File>>),
+    Json(HistoryJsonWriter<BufWriter<File>>),
+}
+
+impl HistoryWriter {
+    fn write_record(&mut self, record: &HistoryRecord) -> std::io::Result<()> {
+        match self {
+            HistoryWriter::Csv(w) => w.write_record(record),
+            HistoryWriter::Json(w) => w.write_record(record),
+        }
+    }
+
+    fn finish(self) -> std::io::Result<()> {
+        match self {
+            HistoryWriter::Csv(w) => w.finish().map(|_| ()),
+            HistoryWriter::Json(w) => w.finish().map(|_| ()),
+        }
+    }
+}
+
+/// 导出全部历史记录到 CSV 或 JSON 文件，返回导出的记录数
+#[tauri::command]
+pub async fn export_history(
+    db_state: State<'_, DatabaseState>,
+    format: String,
+    path: String,
+) -> Result<u64, String> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or_else(|| "Database not initialized".to_string())?;
+
+    let file = File::create(&path)
+        .map_err(|e| format!("Failed to create export file {}: {}", path, e))?;
+    let buffered = BufWriter::new(file);
+
+    let mut writer = match format.to_lowercase().as_str() {
+        "csv" => HistoryWriter::Csv(
+            HistoryCsvWriter::new(buffered).map_err(|e| format!("Failed to write CSV header: {}", e))?
+        ),
+        "json" => HistoryWriter::Json(HistoryJsonWriter::new(buffered)),
+        other => return Err(format!("Unsupported export format: {} (expected csv or json)", other)),
+    };
+
+    let mut exported = 0u64;
+    let mut last_rowid = 0i64;
+    loop {
+        let batch = database.get_history_batch(last_rowid, EXPORT_BATCH_SIZE).await
+            .map_err(|e| format!("Failed to read history records: {}", e))?;
+        if batch.is_empty() {
+            break;
+        }
+
+        for (rowid, record) in &batch {
+            writer.write_record(record)
+                .map_err(|e| format!("Failed to write history record: {}", e))?;
+            last_rowid = *rowid;
+            exported += 1;
+        }
+    }
+
+    writer.finish().map_err(|e| format!("Failed to finish export file: {}", e))?;
+
+    tracing::info!("📤 Exported {} history record(s) as {} to {}", exported, format, path);
+    Ok(exported)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+
+    fn record(id: &str, output: &str, error: Option<&str>) -> HistoryRecord {
+        HistoryRecord {
+            id: id.to_string(),
+            record_type: "transcribe".to_string(),
+            input_text: None,
+            output_text: Some(output.to_string()),
+            audio_file_path: None,
+            processor_type: Some("whisper-rs".to_string()),
+            processing_time_ms: Some(1234),
+            audio_duration_ms: Some(3000),
+            success: error.is_none(),
+            error_message: error.map(|e| e.to_string()),
+            created_at: Utc.with_ymd_and_hms(2024, 5, 1, 8, 30, 0).unwrap(),
+            translation_skipped: false,
+            detected_language: None,
+            confidence: None,
+            segments_json: None,
+        }
+    }
+
+    /// 最小的 RFC 4180 解析器，只用于验证写出的内容
+    fn parse_csv(input: &str) -> Vec<Vec<String>> {
+        let mut rows = Vec::new();
+        let mut row = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+        let mut chars = input.chars().peekable();
+
+        while let Some(c) = chars.next() {
+            match (c, in_quotes) {
+                ('"', true) if chars.peek() == Some(&'"') => {
+                    field.push('"');
+                    chars.next();
+                }
+                ('"', true) => in_quotes = false,
+                ('"', false) => in_quotes = true,
+                (',', false) => row.push(std::mem::take(&mut field)),
+                ('\n', false) => {
+                    row.push(std::mem::take(&mut field));
+                    rows.push(std::mem::take(&mut row));
+                }
+                (c, _) => field.push(c),
+            }
+        }
+        rows
+    }
+
+    #[test]
+    fn test_csv_roundtrip_with_commas_quotes_and_newlines() {
+        let records = vec![
+            record("1", "hello, world", None),
+            record("2", "she said \"hi\"\nthen left", None),
+            record("3", "你好，世界", Some("timeout, retry later")),
+        ];
+
+        let mut writer = HistoryCsvWriter::new(Vec::new()).unwrap();
+        for r in &records {
+            writer.write_record(r).unwrap();
+        }
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+
+        let rows = parse_csv(&output);
+        assert_eq!(rows.len(), 4);
+        assert_eq!(rows[0], CSV_HEADER.iter().map(|s| s.to_string()).collect::<Vec<_>>());
+        for (row, r) in rows[1..].iter().zip(&records) {
+            assert_eq!(row.len(), CSV_HEADER.len());
+            assert_eq!(row[0], r.id);
+            assert_eq!(Some(row[3].as_str()), r.output_text.as_deref());
+            assert_eq!(row[7], r.success.to_string());
+            assert_eq!(row[8], r.error_message.clone().unwrap_or_default());
+        }
+    }
+
+    #[test]
+    fn test_json_writer_matches_pretty_array() {
+        let records = vec![record("1", "a", None), record("2", "b", Some("err"))];
+
+        let mut writer = HistoryJsonWriter::new(Vec::new());
+        for r in &records {
+            writer.write_record(r).unwrap();
+        }
+        let output = String::from_utf8(writer.finish().unwrap()).unwrap();
+        assert_eq!(output, serde_json::to_string_pretty(&records).unwrap());
+
+        let empty = HistoryJsonWriter::new(Vec::new()).finish().unwrap();
+        assert_eq!(String::from_utf8(empty).unwrap(), "[]");
+    }
+}
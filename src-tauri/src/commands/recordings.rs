@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::{info, warn};
+use crate::database::{Database, RecordingRetentionConfig};
+use super::DatabaseState;
+
+/// 应用打开期间每天清理一次
+const CLEANUP_INTERVAL: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// 录音文件保存目录，与 recorder 保存 WAV 的位置一致
+pub fn recordings_dir() -> PathBuf {
+    crate::voice_assistant::recorder::audio_directory()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordingFile {
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+}
+
+/// 列出目录中的 WAV 录音，按修改时间从旧到新排序；目录不存在时返回空列表
+pub fn scan_recordings(dir: &Path) -> std::io::Result<Vec<RecordingFile>> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_wav = path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| ext.eq_ignore_ascii_case("wav"));
+        if !is_wav {
+            continue;
+        }
+        if let Ok(metadata) = entry.metadata() {
+            if metadata.is_file() {
+                files.push(RecordingFile {
+                    path,
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                });
+            }
+        }
+    }
+
+    files.sort_by_key(|f| f.modified);
+    Ok(files)
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub max_age: Option<Duration>,
+    pub max_total_bytes: Option<u64>,
+}
+
+impl RetentionPolicy {
+    pub fn is_unlimited(&self) -> bool {
+        self.max_age.is_none() && self.max_total_bytes.is_none()
+    }
+
+    /// 早于该时间的录音按天数限制删除
+    pub fn age_cutoff(&self, now: SystemTime) -> Option<SystemTime> {
+        self.max_age.and_then(|age| now.checked_sub(age))
+    }
+}
+
+impl From<&RecordingRetentionConfig> for RetentionPolicy {
+    fn from(config: &RecordingRetentionConfig) -> Self {
+        Self {
+            max_age: config.max_age_days
+                .filter(|days| *days > 0)
+                .map(|days| Duration::from_secs(days as u64 * 24 * 60 * 60)),
+            max_total_bytes: config.max_total_mb
+                .filter(|mb| *mb > 0)
+                .map(|mb| mb as u64 * 1024 * 1024),
+        }
+    }
+}
+
+/// 选出需要删除的录音（`files` 按从旧到新排序）。
+/// 超过保留天数的录音被删除，之后总大小仍超出上限时从最旧的开始删除；
+/// 历史记录仍引用的文件（`protected`）两种限制都不会删除
+pub fn select_for_deletion(
+    files: &[RecordingFile],
+    policy: &RetentionPolicy,
+    now: SystemTime,
+    protected: &HashSet<PathBuf>,
+) -> Vec<PathBuf> {
+    let cutoff = policy.age_cutoff(now);
+    let mut keep = Vec::new();
+    let mut delete = Vec::new();
+
+    for file in files {
+        let expired = cutoff.is_some_and(|cutoff| file.modified < cutoff);
+        if expired && !protected.contains(&file.path) {
+            delete.push(file.path.clone());
+        } else {
+            keep.push(file);
+        }
+    }
+
+    if let Some(max_total) = policy.max_total_bytes {
+        let mut total: u64 = keep.iter().map(|f| f.size).sum();
+        for file in keep.into_iter().filter(|file| !protected.contains(&file.path)) {
+            if total <= max_total {
+                break;
+            }
+            total -= file.size;
+            delete.push(file.path.clone());
+        }
+    }
+
+    delete
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionCleanupReport {
+    pub deleted_files: u64,
+    pub freed_bytes: u64,
+    pub cleared_references: u64,
+}
+
+/// 按保存的保留策略清理录音目录，删除的文件在历史记录中的引用会被清空
+pub async fn run_retention_cleanup(database: &Database, dir: &Path) -> Result<RetentionCleanupReport, String> {
+    let config = database.get_recording_retention_config().await
+        .map_err(|e| format!("Failed to get recording retention config: {}", e))?;
+    let policy = config.as_ref().map(RetentionPolicy::from).unwrap_or_default();
+    if policy.is_unlimited() {
+        return Ok(RetentionCleanupReport::default());
+    }
+
+    let now = SystemTime::now();
+    // 有保留天数时只保护截止时间之后的历史记录引用的文件，过期记录的录音仍按天数删除
+    let referenced_since = policy.age_cutoff(now).unwrap_or(SystemTime::UNIX_EPOCH);
+    let protected: HashSet<PathBuf> = database
+        .get_audio_paths_referenced_since(chrono::DateTime::<chrono::Utc>::from(referenced_since)).await
+        .map_err(|e| format!("Failed to get referenced recordings: {}", e))?
+        .into_iter()
+        .map(PathBuf::from)
+        .collect();
+
+    let files = scan_recordings(dir)
+        .map_err(|e| format!("Failed to scan recordings directory: {}", e))?;
+    let sizes: std::collections::HashMap<&Path, u64> = files.iter().map(|f| (f.path.as_path(), f.size)).collect();
+
+    let mut report = RetentionCleanupReport::default();
+    for path in select_for_deletion(&files, &policy, now, &protected) {
+        if let Err(e) = std::fs::remove_file(&path) {
+            warn!("⚠️ Failed to delete recording {}: {}", path.display(), e);
+            continue;
+        }
+        report.deleted_files += 1;
+        report.freed_bytes += sizes.get(path.as_path()).copied().unwrap_or(0);
+        report.cleared_references += database
+            .clear_audio_path_references(&path.to_string_lossy()).await
+            .map_err(|e| format!("Failed to clear audio path references: {}", e))?;
+    }
+
+    if report.deleted_files > 0 {
+        info!(
+            "🧹 Recording cleanup: deleted {} files ({} bytes), cleared {} history references",
+            report.deleted_files, report.freed_bytes, report.cleared_references
+        );
+    }
+    Ok(report)
+}
+
+/// 启动时清理一次，之后应用打开期间每天清理一次
+pub fn spawn_retention_task(db_state: DatabaseState) {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let db = {
+                let guard = db_state.lock().unwrap();
+                guard.as_ref().cloned()
+            };
+            if let Some(database) = db {
+                if let Err(e) = run_retention_cleanup(&database, &recordings_dir()).await {
+                    warn!("⚠️ Recording cleanup failed: {}", e);
+                }
+            }
+            tokio::time::sleep(CLEANUP_INTERVAL).await;
+        }
+    });
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingsDiskUsage {
+    pub directory: String,
+    pub file_count: u64,
+    pub total_bytes: u64,
+}
+
+#[tauri::command]
+pub async fn get_recordings_disk_usage() -> Result<RecordingsDiskUsage, String> {
+    let dir = recordings_dir();
+    let files = scan_recordings(&dir)
+        .map_err(|e| format!("Failed to scan recordings directory: {}", e))?;
+
+    Ok(RecordingsDiskUsage {
+        directory: dir.to_string_lossy().to_string(),
+        file_count: files.len() as u64,
+        total_bytes: files.iter().map(|f| f.size).sum(),
+    })
+}
+
+#[tauri::command]
+pub async fn get_recording_retention_config(
+    db_state: State<'_, DatabaseState>,
+) -> Result<Option<RecordingRetentionConfig>, String> {
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or_else(|| "Database not initialized".to_string())?;
+
+    database.get_recording_retention_config().await
+        .map_err(|e| format!("Failed to get recording retention config: {}", e))
+}
+
+/// 保存保留策略并立即按新策略清理一次；限制为空或 0 表示不限制
+#[tauri::command]
+pub async fn save_recording_retention_config(
+    db_state: State<'_, DatabaseState>,
+    max_age_days: Option<i64>,
+    max_total_mb: Option<i64>,
+) -> Result<RecordingRetentionConfig, String> {
+    if max_age_days.is_some_and(|days| days < 0) || max_total_mb.is_some_and(|mb| mb < 0) {
+        return Err("Retention limits must not be negative".to_string());
+    }
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or_else(|| "Database not initialized".to_string())?;
+
+    let config = database.save_recording_retention_config(max_age_days, max_total_mb).await
+        .map_err(|e| format!("Failed to save recording retention config: {}", e))?;
+    if let Err(e) = run_retention_cleanup(&database, &recordings_dir()).await {
+        warn!("⚠️ Recording cleanup failed: {}", e);
+    }
+    Ok(config)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn file(name: &str, size: u64, age_days: u64, now: SystemTime) -> RecordingFile {
+        RecordingFile {
+            path: PathBuf::from(name),
+            size,
+            modified: now - DAY * age_days as u32,
+        }
+    }
+
+    #[test]
+    fn test_age_limit_skips_referenced_files() {
+        let now = SystemTime::now();
+        let files = vec![
+            file("old.wav", 10, 30, now),
+            file("old-referenced.wav", 10, 20, now),
+            file("new.wav", 10, 1, now),
+        ];
+        let policy = RetentionPolicy { max_age: Some(DAY * 7), max_total_bytes: None };
+        let protected = HashSet::from([PathBuf::from("old-referenced.wav")]);
+
+        assert_eq!(select_for_deletion(&files, &policy, now, &protected), vec![PathBuf::from("old.wav")]);
+    }
+
+    #[test]
+    fn test_size_limit_deletes_oldest_first() {
+        let now = SystemTime::now();
+        let files = vec![
+            file("a.wav", 40, 3, now),
+            file("b.wav", 40, 2, now),
+            file("c.wav", 40, 1, now),
+        ];
+        let policy = RetentionPolicy { max_age: None, max_total_bytes: Some(80) };
+        assert_eq!(select_for_deletion(&files, &policy, now, &HashSet::new()), vec![PathBuf::from("a.wav")]);
+
+        let unlimited = RetentionPolicy::default();
+        assert!(unlimited.is_unlimited());
+        assert!(select_for_deletion(&files, &unlimited, now, &HashSet::new()).is_empty());
+    }
+
+    #[test]
+    fn test_size_limit_skips_referenced_files() {
+        let now = SystemTime::now();
+        let files = vec![
+            file("a-referenced.wav", 40, 3, now),
+            file("b.wav", 40, 2, now),
+            file("c.wav", 40, 1, now),
+        ];
+        let policy = RetentionPolicy { max_age: None, max_total_bytes: Some(40) };
+        let protected = HashSet::from([PathBuf::from("a-referenced.wav")]);
+        assert_eq!(
+            select_for_deletion(&files, &policy, now, &protected),
+            vec![PathBuf::from("b.wav"), PathBuf::from("c.wav")]
+        );
+    }
+
+    #[test]
+    fn test_scan_recordings_only_lists_wav_files() {
+        let dir = std::env::temp_dir().join(format!("voicetype-recordings-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("recording_1.wav"), vec![0u8; 128]).unwrap();
+        std::fs::write(dir.join("recording_1.txt"), b"export").unwrap();
+
+        let files = scan_recordings(&dir).unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(files[0].size, 128);
+        assert!(scan_recordings(&dir.join("missing")).unwrap().is_empty());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,271 @@
+//! 仪表盘的服务状态汇总：本地 whisper 直接看全局处理器是否已加载，网络服务使用后台定时健康检查的缓存结果，
+//! 请求次数和最近一次成功/失败的时间来自 service_stats 表（由历史记录写入时更新）
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tauri::State;
+use tracing::debug;
+use crate::database::ServiceStats;
+use super::{CommandError, DatabaseState, HealthCheckRequest};
+
+/// 网络服务健康检查的间隔
+const HEALTH_CHECK_INTERVAL: Duration = Duration::from_secs(5 * 60);
+
+/// 仪表盘展示的服务，顺序即展示顺序；名称与 service_stats.service_name 一致
+pub const KNOWN_SERVICES: &[&str] = &[
+    "local_asr",
+    "local_http_asr",
+    "whisper_asr",
+    "sensevoice_asr",
+    "siliconflow_translation",
+    "ollama_translation",
+];
+
+/// 一次健康检查的结果
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceProbe {
+    /// "online"、"offline"、"loading" 或 "not_configured"
+    pub status: String,
+    pub endpoint: Option<String>,
+    pub message: Option<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ServiceProbe {
+    fn new(status: &str, endpoint: Option<String>, message: impl Into<String>) -> Self {
+        Self {
+            status: status.to_string(),
+            endpoint,
+            message: Some(message.into()).filter(|m: &String| !m.is_empty()),
+            checked_at: Utc::now(),
+        }
+    }
+
+    fn from_result(endpoint: Option<String>, result: Result<String, String>) -> Self {
+        match result {
+            Ok(message) => Self::new("online", endpoint, message),
+            Err(message) => Self::new("offline", endpoint, message),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceStatusEntry {
+    pub service_name: String,
+    pub status: String,
+    pub endpoint: Option<String>,
+    pub message: Option<String>,
+    /// 状态的判断时间；本地 whisper 为本次查询时间，网络服务为最近一次健康检查的时间
+    pub checked_at: Option<DateTime<Utc>>,
+    pub total_requests: i64,
+    pub successful_requests: i64,
+    pub failed_requests: i64,
+    pub last_success_at: Option<DateTime<Utc>>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+}
+
+impl ServiceStatusEntry {
+    /// 合并实时状态和统计数据；还没有检查过的服务按统计表中的状态显示
+    pub fn merge(service_name: &str, probe: Option<&ServiceProbe>, stats: Option<&ServiceStats>) -> Self {
+        Self {
+            service_name: service_name.to_string(),
+            status: probe
+                .map(|p| p.status.clone())
+                .or_else(|| stats.map(|s| s.status.clone()))
+                .unwrap_or_else(|| "offline".to_string()),
+            endpoint: probe.and_then(|p| p.endpoint.clone()).or_else(|| stats.and_then(|s| s.endpoint.clone())),
+            message: probe.and_then(|p| p.message.clone()),
+            checked_at: probe.map(|p| p.checked_at),
+            total_requests: stats.map_or(0, |s| s.total_requests),
+            successful_requests: stats.map_or(0, |s| s.successful_requests),
+            failed_requests: stats.map_or(0, |s| s.failed_requests),
+            last_success_at: stats.and_then(|s| s.last_success_at),
+            last_error_at: stats.and_then(|s| s.last_error_at),
+            last_error: stats.and_then(|s| s.last_error.clone()),
+        }
+    }
+}
+
+fn probe_cache() -> &'static RwLock<HashMap<String, ServiceProbe>> {
+    static CACHE: OnceLock<RwLock<HashMap<String, ServiceProbe>>> = OnceLock::new();
+    CACHE.get_or_init(|| RwLock::new(HashMap::new()))
+}
+
+/// 本地 whisper：全局处理器已加载即为在线，开销很小，每次查询都实时判断
+async fn probe_local_whisper() -> ServiceProbe {
+    match crate::voice_assistant::global_whisper::get_whisper_manager_status().await {
+        Ok(status) if status.has_processor => ServiceProbe::new(
+            "online",
+            status.current_model_path,
+            status.effective_backend.map(|b| format!("Model loaded ({})", b)).unwrap_or_default(),
+        ),
+        Ok(status) if status.init_in_progress => ServiceProbe::new("loading", status.current_model_path, "Model is loading"),
+        Ok(_) => ServiceProbe::new("offline", None, "Model is not loaded"),
+        Err(e) => ServiceProbe::new("offline", None, e),
+    }
+}
+
+/// 复用 test_connection_health：不带 API key 时请求 /health，带 API key 时请求模型列表
+async fn probe_endpoint(endpoint: String, api_key: Option<String>) -> ServiceProbe {
+    let result = match super::test_connection_health(HealthCheckRequest { endpoint: endpoint.clone(), api_key }).await {
+        Ok(response) if response.healthy => Ok(response.message),
+        Ok(response) => Err(response.message),
+        Err(e) => Err(e.to_string()),
+    };
+    ServiceProbe::from_result(Some(endpoint), result)
+}
+
+/// 本地 HTTP 识别服务：请求 /health
+async fn probe_local_http_asr() -> ServiceProbe {
+    let endpoint = super::get_asr_config_internal().await.ok()
+        .and_then(|configs| configs.into_iter().find(|c| c.service_provider == "local"))
+        .and_then(|c| c.local_endpoint)
+        .filter(|e| !e.trim().is_empty());
+    let Some(endpoint) = endpoint else {
+        return ServiceProbe::new("not_configured", None, "No local ASR endpoint configured");
+    };
+    probe_endpoint(endpoint, None).await
+}
+
+/// 云端 OpenAI 兼容服务：用 API key 请求模型列表，不产生识别费用
+async fn probe_cloud(key_env: &str, endpoint: String) -> ServiceProbe {
+    let Some(api_key) = std::env::var(key_env).ok().filter(|k| !k.trim().is_empty()) else {
+        return ServiceProbe::new("not_configured", Some(endpoint), format!("{} is not configured", key_env));
+    };
+    probe_endpoint(endpoint, Some(api_key)).await
+}
+
+/// Ollama：只检查保存过配置的情况，避免没装 Ollama 的用户一直看到离线
+async fn probe_ollama() -> ServiceProbe {
+    let configured = super::get_translation_config_internal().await
+        .is_ok_and(|configs| configs.iter().any(|c| c.provider == "ollama"));
+    if !configured {
+        return ServiceProbe::new("not_configured", None, "Ollama translation is not configured");
+    }
+    let (endpoint, model) = match super::resolve_ollama_settings(None, None).await {
+        Ok(settings) => settings,
+        Err(e) => return ServiceProbe::new("offline", None, e.to_string()),
+    };
+    let result = super::test_ollama_connection(Some(endpoint.clone()), Some(model))
+        .await
+        .map_err(|e| e.to_string());
+    ServiceProbe::from_result(Some(endpoint), result)
+}
+
+async fn probe_service(service_name: &str) -> Option<ServiceProbe> {
+    let groq_base = std::env::var("GROQ_BASE_URL").unwrap_or_else(|_| "https://api.groq.com".to_string());
+    let siliconflow_base = std::env::var("SILICONFLOW_BASE_URL").unwrap_or_else(|_| "https://api.siliconflow.cn".to_string());
+    Some(match service_name {
+        "local_http_asr" => probe_local_http_asr().await,
+        "whisper_asr" => probe_cloud("GROQ_API_KEY", format!("{}/openai/v1", groq_base)).await,
+        "sensevoice_asr" => probe_cloud("SILICONFLOW_API_KEY", format!("{}/v1", siliconflow_base)).await,
+        "siliconflow_translation" => probe_cloud("SILICONFLOW_API_KEY", format!("{}/v1", siliconflow_base)).await,
+        "ollama_translation" => probe_ollama().await,
+        _ => return None,
+    })
+}
+
+/// 检查所有网络服务并更新缓存
+pub async fn refresh_service_health() {
+    for service_name in KNOWN_SERVICES {
+        if let Some(probe) = probe_service(service_name).await {
+            debug!("🩺 {}: {} {:?}", service_name, probe.status, probe.message);
+            probe_cache().write().unwrap().insert(service_name.to_string(), probe);
+        }
+    }
+}
+
+/// 启动时和之后每隔 HEALTH_CHECK_INTERVAL 检查一次网络服务，完成后通知仪表盘刷新
+pub fn spawn_health_task() {
+    tauri::async_runtime::spawn(async {
+        loop {
+            refresh_service_health().await;
+            crate::voice_assistant::coordinator::emit_service_status_updated_event();
+            tokio::time::sleep(HEALTH_CHECK_INTERVAL).await;
+        }
+    });
+}
+
+/// 所有已知服务的状态；refresh 为 true 时先重新检查网络服务
+#[tauri::command]
+pub async fn get_all_services_status(
+    db_state: State<'_, DatabaseState>,
+    refresh: Option<bool>,
+) -> Result<Vec<ServiceStatusEntry>, CommandError> {
+    if refresh.unwrap_or(false) {
+        refresh_service_health().await;
+    }
+
+    let db = {
+        let guard = db_state.lock().unwrap();
+        guard.as_ref().cloned()
+    };
+    let database = db.ok_or(CommandError::DatabaseNotInitialized)?;
+    let stats: HashMap<String, ServiceStats> = database
+        .get_all_service_stats()
+        .await
+        .map_err(|e| CommandError::Database(format!("Failed to get service stats: {}", e)))?
+        .into_iter()
+        .map(|s| (s.service_name.clone(), s))
+        .collect();
+
+    let local_whisper = probe_local_whisper().await;
+    let cache = probe_cache().read().unwrap().clone();
+    Ok(KNOWN_SERVICES
+        .iter()
+        .map(|name| {
+            let probe = if *name == "local_asr" { Some(&local_whisper) } else { cache.get(*name) };
+            ServiceStatusEntry::merge(name, probe, stats.get(*name))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(status: &str) -> ServiceStats {
+        let now = Utc::now();
+        ServiceStats {
+            id: "1".to_string(),
+            service_name: "local_asr".to_string(),
+            status: status.to_string(),
+            endpoint: Some("from-stats".to_string()),
+            last_check: now,
+            uptime_seconds: 0,
+            total_requests: 5,
+            successful_requests: 4,
+            failed_requests: 1,
+            created_at: now,
+            updated_at: now,
+            last_success_at: Some(now),
+            last_error_at: None,
+            last_error: None,
+        }
+    }
+
+    #[test]
+    fn test_live_probe_overrides_stats_status() {
+        let probe = ServiceProbe::new("online", Some("/models/ggml-base.bin".to_string()), "Model loaded (CPU)");
+        let entry = ServiceStatusEntry::merge("local_asr", Some(&probe), Some(&stats("error")));
+        assert_eq!(entry.status, "online");
+        assert_eq!(entry.endpoint.as_deref(), Some("/models/ggml-base.bin"));
+        assert_eq!(entry.total_requests, 5);
+        assert!(entry.last_success_at.is_some());
+    }
+
+    #[test]
+    fn test_unchecked_service_falls_back_to_stats() {
+        let entry = ServiceStatusEntry::merge("local_asr", None, Some(&stats("error")));
+        assert_eq!(entry.status, "error");
+        assert_eq!(entry.endpoint.as_deref(), Some("from-stats"));
+        assert!(entry.checked_at.is_none());
+
+        let entry = ServiceStatusEntry::merge("ollama_translation", None, None);
+        assert_eq!(entry.status, "offline");
+        assert_eq!(entry.total_requests, 0);
+    }
+}
@@ -0,0 +1,32 @@
+use crate::voice_assistant::transcript::{self, TranscriptFormat, TranscriptSession};
+use tracing::info;
+
+/// 开始会话记录；transcript_only 为 true 时听写结果只记录不输入
+#[tauri::command]
+pub async fn start_transcript_session(transcript_only: Option<bool>) -> Result<TranscriptSession, String> {
+    transcript::start(transcript_only.unwrap_or(false))
+}
+
+/// 结束会话记录并保存到历史，返回完整记录；没有进行中的会话时返回 None
+#[tauri::command]
+pub async fn end_transcript_session() -> Result<Option<TranscriptSession>, String> {
+    Ok(transcript::end_and_save())
+}
+
+#[tauri::command]
+pub async fn get_current_transcript() -> Result<Option<TranscriptSession>, String> {
+    Ok(transcript::current())
+}
+
+/// 把进行中的会话记录导出为 txt、md 或 srt 文件
+#[tauri::command]
+pub async fn export_transcript(path: String, format: String) -> Result<(), String> {
+    let format = TranscriptFormat::parse(&format)
+        .ok_or_else(|| format!("Unsupported transcript format: {} (expected txt, md or srt)", format))?;
+    let session = transcript::current().ok_or_else(|| "No transcript session is active".to_string())?;
+
+    std::fs::write(&path, transcript::render(&session, format))
+        .map_err(|e| format!("Failed to write transcript to {}: {}", path, e))?;
+    info!("📤 Exported transcript ({} entries) as {:?} to {}", session.entries.len(), format, path);
+    Ok(())
+}
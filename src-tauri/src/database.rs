@@ -1,12 +1,14 @@
-use sqlx::{SqlitePool, sqlite::SqliteConnectOptions};
+use sqlx::{QueryBuilder, Sqlite, SqliteConnection, SqlitePool, sqlite::SqliteConnectOptions};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
-use tracing::info;
+use tracing::{debug, info, warn};
 use std::str::FromStr;
 use std::sync::{Arc, Mutex};
 use std::sync::OnceLock;
 
+pub mod crypto;
+
 // Database models
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct AsrConfig {
@@ -19,9 +21,34 @@ pub struct AsrConfig {
     pub whisper_model: Option<String>, // 新增：选择的whisper模型
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    pub enable_vad: bool, // 推理前裁剪首尾静音
+    #[serde(default = "default_cloud_provider")]
+    pub cloud_provider: String, // 云端请求格式："custom" 或 "openai"
+    #[serde(default)]
+    pub cloud_model: Option<String>, // OpenAI 兼容接口的 model 字段
+    #[serde(default)]
+    pub cloud_language: Option<String>, // OpenAI 兼容接口的 language 字段
+    #[serde(default)]
+    pub fallback_chain: Option<String>, // 备用处理器的 JSON 数组，例如 ["openai","groq"]；NULL 表示不使用备用处理器
+    #[serde(default = "default_asr_profile_name")]
+    pub name: String, // 配置方案名称
+    #[serde(default)]
+    pub is_active: bool, // 当前使用的方案，同一时间只有一个
+    /// 保存的 API key 无法解密（钥匙串中的密钥丢失等），设置页面据此提示重新填写 key
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub key_unavailable: bool,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+fn default_cloud_provider() -> String {
+    "custom".to_string()
+}
+
+fn default_asr_profile_name() -> String {
+    "Default".to_string()
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TypingDelays {
     pub clipboard_update_ms: i64,
     pub keyboard_events_settle_ms: i64,
@@ -55,32 +82,496 @@ pub struct HotkeyConfig {
     pub typing_complete_ms: i64,
     pub character_interval_ms: i64,
     pub short_operation_ms: i64,
+    /// 文本注入方式："auto"、"direct" 或 "clipboard"
+    #[serde(default = "default_text_injection_method")]
+    pub text_injection_method: String,
+    /// 热键后端："rdev"（全局键盘监听）或 "plugin"（tauri-plugin-global-shortcut）
+    #[serde(default = "default_hotkey_backend")]
+    pub hotkey_backend: String,
+    /// 录音模式："hold"（按住录音）或 "toggle"（按一次开始、再按一次停止）
+    #[serde(default = "default_recording_mode")]
+    pub recording_mode: String,
+    /// 最长录音时长（秒），超过后自动停止并识别
+    #[serde(default = "default_max_recording_secs")]
+    pub max_recording_secs: i64,
+    /// 录音中持续静音时自动停止并识别
+    #[serde(default)]
+    pub silence_auto_stop_enabled: bool,
+    /// 触发自动停止的静音时长（毫秒）
+    #[serde(default = "default_silence_auto_stop_ms")]
+    pub silence_auto_stop_ms: i64,
+    /// 录音开始前预录的时长（毫秒），0 表示关闭。
+    /// 开启后语音助手运行期间麦克风保持打开，最近这段音频只保存在内存中
+    #[serde(default = "default_preroll_ms")]
+    pub preroll_ms: i64,
+    /// 排队等待识别的结果只复制到剪贴板，不输入（避免输入到已经切换的窗口）
+    #[serde(default)]
+    pub queued_to_clipboard: bool,
+    /// 循环切换 ASR 配置方案的热键，空字符串表示未设置
+    #[serde(default)]
+    pub cycle_profile_key: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
 }
 
+fn default_text_injection_method() -> String {
+    "auto".to_string()
+}
+
+fn default_hotkey_backend() -> String {
+    "rdev".to_string()
+}
+
+fn default_recording_mode() -> String {
+    "hold".to_string()
+}
+
+fn default_max_recording_secs() -> i64 {
+    crate::voice_assistant::keyboard::DEFAULT_MAX_RECORDING_SECS
+}
+
+fn default_silence_auto_stop_ms() -> i64 {
+    2000
+}
+
+fn default_preroll_ms() -> i64 {
+    500
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct TranslationConfig {
     pub id: String,
-    pub provider: String, // "siliconflow" or "ollama"
+    pub provider: String, // "siliconflow", "ollama" or "deepl"
     pub api_key: Option<String>,
     pub endpoint: Option<String>,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    #[serde(default = "default_target_language")]
+    pub target_language: String, // 语言代码，如 "en"、"ja"
+    #[serde(default)]
+    pub source_language: Option<String>, // 为空时由翻译服务自动判断
+    #[serde(default)]
+    pub model: Option<String>, // 为空时使用处理器的默认模型
+    #[serde(default = "default_translate_timeout_secs")]
+    pub timeout_secs: i64, // 单次翻译的超时，超时后输入原文
+    /// 保存的 API key 无法解密，设置页面据此提示重新填写 key
+    #[serde(default)]
+    #[sqlx(skip)]
+    pub key_unavailable: bool,
+}
+
+fn default_target_language() -> String {
+    crate::voice_assistant::translate::DEFAULT_TARGET_LANGUAGE.to_string()
+}
+
+fn default_translate_timeout_secs() -> i64 {
+    crate::voice_assistant::translate::DEFAULT_TRANSLATE_TIMEOUT_SECS
+}
+
+impl AsrConfig {
+    /// 解析备用处理器列表，无法解析时使用默认顺序
+    pub fn fallback_chain(&self) -> Option<Vec<crate::voice_assistant::ProcessorType>> {
+        let json = self.fallback_chain.as_deref()?;
+        match serde_json::from_str(json) {
+            Ok(chain) => Some(chain),
+            Err(e) => {
+                warn!("⚠️ Invalid ASR fallback chain {:?}, using default order: {}", json, e);
+                None
+            }
+        }
+    }
+
+    fn decrypted(mut self) -> Self {
+        self.local_api_key = decrypt_field(self.local_api_key, "local_api_key", &mut self.key_unavailable);
+        self.cloud_api_key = decrypt_field(self.cloud_api_key, "cloud_api_key", &mut self.key_unavailable);
+        self
+    }
+
+    /// 返回给前端时隐藏 API key
+    pub fn masked(mut self) -> Self {
+        self.local_api_key = self.local_api_key.map(|k| crypto::mask_secret(&k));
+        self.cloud_api_key = self.cloud_api_key.map(|k| crypto::mask_secret(&k));
+        self
+    }
+}
+
+impl TranslationConfig {
+    fn decrypted(mut self) -> Self {
+        self.api_key = decrypt_field(self.api_key, "api_key", &mut self.key_unavailable);
+        self
+    }
+
+    /// 返回给前端时隐藏 API key
+    pub fn masked(mut self) -> Self {
+        self.api_key = self.api_key.map(|k| crypto::mask_secret(&k));
+        self
+    }
+}
+
+/// 钥匙串中的密钥丢失等情况下不阻塞启动，按没有 key 处理并置位 unavailable，用户需要重新填写 key
+fn decrypt_field(value: Option<String>, column: &str, unavailable: &mut bool) -> Option<String> {
+    value.and_then(|v| match crypto::decrypt_secret(&v) {
+        Ok(plain) => Some(plain),
+        Err(e) => {
+            warn!("⚠️ Database: Cannot decrypt {}: {}", column, e);
+            *unavailable = true;
+            None
+        }
+    })
+}
+
+fn encrypt_field(value: Option<&str>) -> Result<Option<String>, sqlx::Error> {
+    value
+        .map(|v| crypto::encrypt_secret(v).map_err(|e| sqlx::Error::Encode(e.into())))
+        .transpose()
+}
+
+/// app_settings 中需要加密保存、导出备份时去掉的项
+const SECRET_APP_SETTINGS: &[&str] = &[crate::voice_assistant::settings::HTTP_API_TOKEN];
+
+fn is_secret_app_setting(key: &str) -> bool {
+    SECRET_APP_SETTINGS.contains(&key)
+}
+
+impl AppSetting {
+    fn decrypted(mut self) -> Self {
+        if is_secret_app_setting(&self.key) {
+            self.value = decrypt_field(Some(self.value), &self.key, &mut false).unwrap_or_default();
+        }
+        self
+    }
+}
+
+/// 要写入 app_settings 的值，敏感项加密
+fn app_setting_value(key: &str, value: &str) -> Result<String, sqlx::Error> {
+    if is_secret_app_setting(key) {
+        crypto::encrypt_secret(value).map_err(|e| sqlx::Error::Encode(e.into()))
+    } else {
+        Ok(value.to_string())
+    }
+}
+
+/// 前端回传脱敏值时保留已保存的 key。
+/// 已保存的 key 解密失败时拒绝保存脱敏值，否则库里的密文会被清空，用户需要重新填写完整的 key
+fn resolve_masked_key<'a>(incoming: Option<&'a str>, existing: Option<&'a str>) -> Result<Option<&'a str>, sqlx::Error> {
+    match incoming {
+        Some(k) if crypto::is_masked(k) => match existing {
+            Some(existing) => Ok(Some(existing)),
+            None => Err(sqlx::Error::Encode(
+                "Cannot save a masked API key: the stored key is unavailable, please enter the full key".into(),
+            )),
+        },
+        other => Ok(other),
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct PostProcessConfig {
+    pub id: String,
+    pub convert_to_simplified: bool,
+    pub add_symbol: bool,
+    pub optimize_result: bool,
+    /// LLM 优化的时间预算（毫秒），超时后输入原文
+    #[serde(default = "default_optimize_timeout_ms")]
+    pub optimize_timeout_ms: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn default_optimize_timeout_ms() -> i64 {
+    crate::voice_assistant::postprocess::DEFAULT_OPTIMIZE_TIMEOUT_MS as i64
+}
+
+/// app_settings 表中的一项运行时设置，值统一存为文本，由 voice_assistant::settings 解析
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AppSetting {
+    pub key: String,
+    pub value: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 送入 ASR 前的音频预处理开关
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct AudioPrepConfig {
+    pub id: String,
+    pub normalize_enabled: bool,
+    pub high_pass_enabled: bool,
+    pub noise_gate_enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl AudioPrepConfig {
+    pub fn options(&self) -> crate::voice_assistant::audio_prep::AudioPrepOptions {
+        crate::voice_assistant::audio_prep::AudioPrepOptions {
+            normalize: self.normalize_enabled,
+            high_pass: self.high_pass_enabled,
+            noise_gate: self.noise_gate_enabled,
+        }
+    }
+}
+
+/// 录音状态浮窗的开关和位置
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OverlayConfig {
+    pub id: String,
+    pub enabled: bool,
+    pub position: String, // "cursor" 或 "top-left"/"top-right"/"bottom-left"/"bottom-right"
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 录音文件保留策略，两个限制都为 NULL 时不清理
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct RecordingRetentionConfig {
+    pub id: String,
+    /// 保留最近 N 天的录音
+    pub max_age_days: Option<i64>,
+    /// 录音总大小上限（MB），超出时从最旧的开始删除
+    pub max_total_mb: Option<i64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// 口述命令（"句号"、"new line" 等）的开关和命令表
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct DictationCommandConfig {
+    pub id: String,
+    pub enabled: bool,
+    pub commands_json: Option<String>, // DictationCommand 的 JSON 数组，NULL 表示使用默认命令
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl DictationCommandConfig {
+    /// 解析保存的命令表，解析失败时回退到默认命令
+    pub fn commands(&self) -> Option<Vec<crate::voice_assistant::dictation_commands::DictationCommand>> {
+        let json = self.commands_json.as_deref()?;
+        match serde_json::from_str(json) {
+            Ok(commands) => Some(commands),
+            Err(e) => {
+                warn!("⚠️ Invalid dictation command table, using defaults: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// 云端 ASR 和翻译请求的重试设置
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct NetworkRetryConfig {
+    pub id: String,
+    pub max_retries: i64,
+    pub time_budget_ms: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl NetworkRetryConfig {
+    pub fn policy(&self) -> crate::voice_assistant::net::RetryPolicy {
+        crate::voice_assistant::net::RetryPolicy {
+            max_retries: self.max_retries.clamp(0, crate::voice_assistant::net::MAX_RETRIES_LIMIT as i64) as u32,
+            time_budget: std::time::Duration::from_millis(self.time_budget_ms.max(0) as u64),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct TranscriptionReplacement {
+    pub id: String,
+    pub pattern: String,
+    pub replacement: String,
+    pub is_regex: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// 按前台应用选择的输出方式
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct OutputProfileRecord {
+    pub id: String,
+    pub app_pattern: String,      // 窗口类名/应用名/窗口标题包含的文字（不区分大小写），"*" 匹配所有应用
+    pub injection_method: String, // "default"（沿用全局设置）、"auto"、"direct" 或 "clipboard"
+    pub trailing_text: String,
+    pub capitalize_sentences: bool,
+    #[serde(default)]
+    pub typing_delays_json: Option<String>, // TypingDelays 的 JSON，NULL 表示沿用全局的输入延迟
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl OutputProfileRecord {
+    /// 解析该应用专用的输入延迟，解析失败时沿用全局设置
+    pub fn typing_delays(&self) -> Option<TypingDelays> {
+        let json = self.typing_delays_json.as_deref()?;
+        match serde_json::from_str(json) {
+            Ok(delays) => Some(delays),
+            Err(e) => {
+                warn!("⚠️ Invalid typing delays for output profile {}, using global delays: {}", self.id, e);
+                None
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct HistoryRecord {
     pub id: String,
-    pub record_type: String, // "transcribe" or "translate"
+    pub record_type: String, // "asr", "translate", "session" or "skipped"
     pub input_text: Option<String>,
     pub output_text: Option<String>,
     pub audio_file_path: Option<String>,
     pub processor_type: Option<String>,
     pub processing_time_ms: Option<i64>,
+    /// 录音时长，旧记录为 NULL
+    #[serde(default)]
+    pub audio_duration_ms: Option<i64>,
     pub success: bool,
     pub error_message: Option<String>,
     pub created_at: DateTime<Utc>,
+    /// 翻译记录：识别出的语言已经是目标语言，原文直接输入，没有调用翻译服务
+    #[serde(default)]
+    pub translation_skipped: bool,
+    /// whisper 检测到的语言代码（如 "zh"、"en"），云端处理器和旧记录为 NULL
+    #[serde(default)]
+    pub detected_language: Option<String>,
+    /// 本地 whisper 的识别可信度（0.0 - 1.0），云端处理器和旧记录为 NULL
+    #[serde(default)]
+    pub confidence: Option<f64>,
+    /// 会话记录中每次听写的时间（JSON），导出 SRT 时每段一条字幕；其他记录为 NULL
+    #[serde(default)]
+    pub segments_json: Option<String>,
+}
+
+/// History search filters; every field is optional
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoryQuery {
+    pub query: Option<String>,
+    pub record_type: Option<String>,
+    pub date_from: Option<DateTime<Utc>>,
+    pub date_to: Option<DateTime<Utc>>,
+    pub success: Option<bool>,
+    pub detected_language: Option<String>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// history_records.record_type 的合法取值，按类型筛选时只接受这些
+pub const HISTORY_RECORD_TYPES: &[&str] = &["asr", "transcribe", "translate", "session", "skipped"];
+
+impl HistoryQuery {
+    pub fn validate(&self) -> Result<(), String> {
+        match self.record_type.as_deref() {
+            Some(record_type) if !HISTORY_RECORD_TYPES.contains(&record_type) => Err(format!(
+                "Invalid record type '{}'. Valid options: {:?}", record_type, HISTORY_RECORD_TYPES
+            )),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPage {
+    pub records: Vec<HistoryRecord>,
+    pub total_count: i64,
+    pub limit: Option<i64>,
+    pub offset: i64,
+}
+
+/// 旧版本写入历史记录的处理器标识 -> 当前标识
+const LEGACY_PROCESSOR_TYPES: [(&str, &str); 2] = [
+    ("whisper", "cloud-groq"),
+    ("sensevoice", "cloud-sensevoice"),
+];
+
+/// 备份文件格式版本，结构不兼容时递增
+pub const BACKUP_FORMAT_VERSION: u32 = 1;
+
+/// 设置与历史记录的完整备份
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppBackup {
+    pub version: u32,
+    pub app_version: String,
+    pub exported_at: DateTime<Utc>,
+    pub api_keys_included: bool,
+    pub asr_configs: Vec<AsrConfig>,
+    pub translation_configs: Vec<TranslationConfig>,
+    pub hotkey_configs: Vec<HotkeyConfig>,
+    #[serde(default)]
+    pub postprocess_configs: Vec<PostProcessConfig>,
+    #[serde(default)]
+    pub replacements: Vec<TranscriptionReplacement>,
+    #[serde(default)]
+    pub audio_prep_configs: Vec<AudioPrepConfig>,
+    #[serde(default)]
+    pub network_retry_configs: Vec<NetworkRetryConfig>,
+    #[serde(default)]
+    pub overlay_configs: Vec<OverlayConfig>,
+    #[serde(default)]
+    pub dictation_command_configs: Vec<DictationCommandConfig>,
+    #[serde(default)]
+    pub output_profiles: Vec<OutputProfileRecord>,
+    #[serde(default)]
+    pub recording_retention_configs: Vec<RecordingRetentionConfig>,
+    #[serde(default)]
+    pub app_settings: Vec<AppSetting>,
+    #[serde(default)]
+    pub history_records: Option<Vec<HistoryRecord>>,
+}
+
+impl AppBackup {
+    /// 解析并校验备份文件，先检查版本再解析完整结构
+    pub fn parse(content: &str) -> Result<Self, String> {
+        let value: serde_json::Value = serde_json::from_str(content)
+            .map_err(|e| format!("Backup file is corrupted: {}", e))?;
+
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_u64())
+            .ok_or_else(|| "Backup file is corrupted: missing format version".to_string())?;
+
+        if version > BACKUP_FORMAT_VERSION as u64 {
+            return Err(format!(
+                "Backup format version {} is newer than supported version {}; please update the app",
+                version, BACKUP_FORMAT_VERSION
+            ));
+        }
+        if version == 0 {
+            return Err("Backup file is corrupted: invalid format version 0".to_string());
+        }
+
+        serde_json::from_value(value).map_err(|e| format!("Backup file is corrupted: {}", e))
+    }
+
+    /// 去掉所有 API key
+    pub fn redact_api_keys(&mut self) {
+        for config in &mut self.asr_configs {
+            config.local_api_key = None;
+            config.cloud_api_key = None;
+        }
+        for config in &mut self.translation_configs {
+            config.api_key = None;
+        }
+        self.app_settings.retain(|setting| !is_secret_app_setting(&setting.key));
+        self.api_keys_included = false;
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BackupImportSummary {
+    pub asr_configs: u64,
+    pub translation_configs: u64,
+    pub hotkey_configs: u64,
+    pub postprocess_configs: u64,
+    pub replacements: u64,
+    pub audio_prep_configs: u64,
+    pub network_retry_configs: u64,
+    pub overlay_configs: u64,
+    pub dictation_command_configs: u64,
+    pub output_profiles: u64,
+    pub recording_retention_configs: u64,
+    pub app_settings: u64,
+    pub history_imported: u64,
+    pub history_skipped: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -91,8 +582,49 @@ pub struct NewHistoryRecord {
     pub audio_file_path: Option<String>,
     pub processor_type: Option<String>,
     pub processing_time_ms: Option<i64>,
+    pub audio_duration_ms: Option<i64>,
     pub success: bool,
     pub error_message: Option<String>,
+    pub translation_skipped: bool,
+    pub detected_language: Option<String>,
+    pub confidence: Option<f64>,
+    #[serde(default)]
+    pub segments_json: Option<String>,
+}
+
+impl NewHistoryRecord {
+    /// 翻译记录：input_text 为 ASR 原文，output_text 为译文，历史页面两者都会显示
+    pub fn translation(
+        input_text: String,
+        output_text: String,
+        processor_type: &str,
+        processing_time_ms: Option<i64>,
+        audio_file_path: Option<String>,
+    ) -> Self {
+        Self {
+            record_type: "translate".to_string(),
+            input_text: Some(input_text),
+            output_text: Some(output_text),
+            audio_file_path,
+            processor_type: Some(processor_type.to_string()),
+            processing_time_ms,
+            audio_duration_ms: None,
+            success: true,
+            error_message: None,
+            translation_skipped: false,
+            detected_language: None,
+            confidence: None,
+            segments_json: None,
+        }
+    }
+
+    /// 翻译记录必须带原文
+    pub fn validate(&self) -> Result<(), String> {
+        if self.record_type == "translate" && self.input_text.as_deref().map_or(true, |t| t.trim().is_empty()) {
+            return Err("Translation records must include the original transcription (input_text)".to_string());
+        }
+        Ok(())
+    }
 }
 
 // Statistics models
@@ -109,6 +641,14 @@ pub struct ServiceStats {
     pub failed_requests: i64,
     pub created_at: DateTime<Utc>,
     pub updated_at: DateTime<Utc>,
+    /// 最近一次成功请求的时间
+    #[serde(default)]
+    pub last_success_at: Option<DateTime<Utc>>,
+    /// 最近一次失败请求的时间和错误信息
+    #[serde(default)]
+    pub last_error_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub last_error: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
@@ -118,13 +658,84 @@ pub struct LatencyRecord {
     pub latency_ms: i64,
     pub request_type: String, // "transcribe", "translate"
     pub recorded_at: DateTime<Utc>,
+    // 分阶段耗时，旧记录和非热键路径为 NULL
+    pub record_ms: Option<i64>,
+    pub wav_ms: Option<i64>,
+    pub asr_ms: Option<i64>,
+    pub postprocess_ms: Option<i64>,
+    /// 翻译耗时，没有翻译的请求为 NULL
+    #[serde(default)]
+    pub translate_ms: Option<i64>,
+    pub typing_ms: Option<i64>,
+    pub attempts: Option<i64>,
+    // 竞速模式下本地/云端各自的耗时
+    #[serde(default)]
+    pub race_local_ms: Option<i64>,
+    #[serde(default)]
+    pub race_cloud_ms: Option<i64>,
+}
+
+/// 一次热键请求各阶段的耗时（毫秒）
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LatencyBreakdown {
+    /// 录音时长（音频长度）
+    pub record_ms: Option<i64>,
+    /// 转换为 WAV（含音频预处理）
+    pub wav_ms: Option<i64>,
+    /// ASR 推理
+    pub asr_ms: Option<i64>,
+    /// 后处理：LLM 优化和替换规则
+    pub postprocess_ms: Option<i64>,
+    /// 翻译（翻译热键或自动翻译），没有翻译时为 None
+    #[serde(default)]
+    pub translate_ms: Option<i64>,
+    /// 文本输入
+    pub typing_ms: Option<i64>,
+    /// 云端请求次数（含重试），本地推理为 None
+    #[serde(default)]
+    pub attempts: Option<i64>,
+    /// 竞速模式：本地 whisper / 云端各自完成的耗时，被中止的一方为 None
+    #[serde(default)]
+    pub race_local_ms: Option<i64>,
+    #[serde(default)]
+    pub race_cloud_ms: Option<i64>,
+}
+
+impl LatencyBreakdown {
+    pub fn from_record(record: &LatencyRecord) -> Option<Self> {
+        let breakdown = Self {
+            record_ms: record.record_ms,
+            wav_ms: record.wav_ms,
+            asr_ms: record.asr_ms,
+            postprocess_ms: record.postprocess_ms,
+            translate_ms: record.translate_ms,
+            typing_ms: record.typing_ms,
+            attempts: record.attempts,
+            race_local_ms: record.race_local_ms,
+            race_cloud_ms: record.race_cloud_ms,
+        };
+        (breakdown != Self::default()).then_some(breakdown)
+    }
+
+    /// 每秒音频的 ASR 推理耗时，不受录音长短影响，用于趋势比较
+    pub fn asr_ms_per_audio_second(&self) -> Option<f64> {
+        match (self.asr_ms, self.record_ms) {
+            (Some(asr_ms), Some(record_ms)) if record_ms > 0 => {
+                Some(asr_ms as f64 * 1000.0 / record_ms as f64)
+            }
+            _ => None,
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
 pub struct UsageLog {
     pub id: String,
     pub date: String, // YYYY-MM-DD format
+    /// 当天实际录音的秒数（由 total_audio_ms 换算）
     pub total_seconds: i64,
+    #[serde(default)]
+    pub total_audio_ms: i64,
     pub total_requests: i64,
     pub successful_requests: i64,
     pub failed_requests: i64,
@@ -132,6 +743,104 @@ pub struct UsageLog {
     pub updated_at: DateTime<Utc>,
 }
 
+/// 用量报表的分组粒度
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportGranularity {
+    #[default]
+    Day,
+    /// 按周分组，以周一为一周的开始
+    Week,
+}
+
+impl ReportGranularity {
+    /// 把时间列换算成本地日期的分组表达式，`$3` 是时区偏移修饰符（如 "+480 minutes"）
+    fn period_sql(self, column: &str) -> String {
+        match self {
+            ReportGranularity::Day => format!("date({}, $3)", column),
+            ReportGranularity::Week => format!("date({}, $3, 'weekday 0', '-6 days')", column),
+        }
+    }
+}
+
+/// 用量报表的查询条件，日期是本地日期（含首尾）
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UsageReportQuery {
+    pub start_date: chrono::NaiveDate,
+    pub end_date: chrono::NaiveDate,
+    pub granularity: ReportGranularity,
+    /// 本地时区相对 UTC 的偏移（分钟），按本地日期分组
+    pub utc_offset_minutes: i32,
+}
+
+impl UsageReportQuery {
+    /// 本地日期范围对应的 UTC 时间区间 [start, end)
+    fn utc_bounds(&self) -> (DateTime<Utc>, DateTime<Utc>) {
+        let offset = chrono::Duration::minutes(self.utc_offset_minutes as i64);
+        let to_utc = |date: chrono::NaiveDate| date.and_time(chrono::NaiveTime::MIN).and_utc() - offset;
+        (to_utc(self.start_date), to_utc(self.end_date + chrono::Duration::days(1)))
+    }
+
+    fn offset_modifier(&self) -> String {
+        format!("{:+} minutes", self.utc_offset_minutes)
+    }
+}
+
+/// 某个时间段内某个处理器的识别统计
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct UsageReportRow {
+    /// 本地日期 YYYY-MM-DD，按周分组时为该周周一
+    pub period: String,
+    pub processor_type: String,
+    pub total_requests: i64,
+    pub successful_requests: i64,
+    pub success_rate: f64,
+    pub avg_processing_ms: Option<f64>,
+    pub total_audio_ms: i64,
+}
+
+/// 某个时间段内某个服务的延迟统计（来自 latency_records）
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, sqlx::FromRow)]
+pub struct LatencyReportRow {
+    pub period: String,
+    pub service_name: String,
+    pub requests: i64,
+    pub avg_latency_ms: Option<f64>,
+    pub avg_asr_ms: Option<f64>,
+}
+
+/// 仪表盘趋势图用的用量报表
+/// 历史记录只保存处理器类型，模型没有单独记录，因此按 processor_type 区分（whisper-rs / cloud-*）
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageReport {
+    pub start_date: String,
+    pub end_date: String,
+    pub granularity: ReportGranularity,
+    pub utc_offset_minutes: i32,
+    pub usage: Vec<UsageReportRow>,
+    pub latency: Vec<LatencyReportRow>,
+}
+
+/// 一次模型基准测试的结果，用于比较驱动/版本升级前后的性能
+#[derive(Debug, Clone, Serialize, Deserialize, sqlx::FromRow)]
+pub struct BenchmarkResult {
+    pub id: String,
+    pub model_name: String,
+    pub model_path: String,
+    pub backend: String,
+    pub app_version: String,
+    pub load_ms: Option<i64>,
+    pub inference_ms: Option<i64>,
+    pub audio_ms: i64,
+    /// "bundled" 为打包的参考录音，"synthetic" 为合成信号（只反映速度，不反映准确率）
+    pub audio_source: String,
+    pub realtime_factor: Option<f64>,
+    pub peak_rss_mb: Option<f64>,
+    pub success: bool,
+    pub error_message: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct NewServiceStats {
     pub service_name: String,
@@ -262,6 +971,37 @@ impl Database {
         .await
         .ok(); // 忽略错误，如果列已存在
 
+        // 添加 enable_vad 列如果不存在（为现有数据库）
+        sqlx::query(
+            "ALTER TABLE asr_configs ADD COLUMN enable_vad BOOLEAN NOT NULL DEFAULT 0"
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // 忽略错误，如果列已存在
+
+        // 云端 ASR 请求格式和 OpenAI 兼容接口的 model/language
+        for statement in [
+            "ALTER TABLE asr_configs ADD COLUMN cloud_provider TEXT NOT NULL DEFAULT 'custom'",
+            "ALTER TABLE asr_configs ADD COLUMN cloud_model TEXT",
+            "ALTER TABLE asr_configs ADD COLUMN cloud_language TEXT",
+            "ALTER TABLE asr_configs ADD COLUMN fallback_chain TEXT",
+            "ALTER TABLE asr_configs ADD COLUMN name TEXT NOT NULL DEFAULT 'Default'",
+            "ALTER TABLE asr_configs ADD COLUMN is_active BOOLEAN NOT NULL DEFAULT 0",
+        ] {
+            sqlx::query(statement).execute(&*self.pool).await.ok(); // 忽略错误，如果列已存在
+        }
+
+        // 旧版本只使用最新的一行，升级后把它设为当前方案
+        sqlx::query(
+            r#"
+            UPDATE asr_configs SET is_active = 1
+            WHERE id = (SELECT id FROM asr_configs ORDER BY updated_at DESC LIMIT 1)
+              AND NOT EXISTS (SELECT 1 FROM asr_configs WHERE is_active = 1)
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
         // Create translation config table
         sqlx::query(
             r#"
@@ -278,6 +1018,16 @@ impl Database {
         .execute(&*self.pool)
         .await?;
 
+        // 翻译目标语言/源语言
+        for statement in [
+            "ALTER TABLE translation_configs ADD COLUMN target_language TEXT NOT NULL DEFAULT 'en'",
+            "ALTER TABLE translation_configs ADD COLUMN source_language TEXT",
+            "ALTER TABLE translation_configs ADD COLUMN model TEXT",
+            "ALTER TABLE translation_configs ADD COLUMN timeout_secs INTEGER NOT NULL DEFAULT 30",
+        ] {
+            sqlx::query(statement).execute(&*self.pool).await.ok(); // 忽略错误，如果列已存在
+        }
+
         // Create history records table
         sqlx::query(
             r#"
@@ -299,6 +1049,27 @@ impl Database {
         .await?;
 
         // Create indexes for better query performance
+        sqlx::query("ALTER TABLE history_records ADD COLUMN audio_duration_ms INTEGER")
+            .execute(&*self.pool)
+            .await
+            .ok(); // 忽略错误，如果列已存在
+        sqlx::query("ALTER TABLE history_records ADD COLUMN translation_skipped BOOLEAN NOT NULL DEFAULT FALSE")
+            .execute(&*self.pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE history_records ADD COLUMN detected_language TEXT")
+            .execute(&*self.pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE history_records ADD COLUMN confidence REAL")
+            .execute(&*self.pool)
+            .await
+            .ok();
+        sqlx::query("ALTER TABLE history_records ADD COLUMN segments_json TEXT")
+            .execute(&*self.pool)
+            .await
+            .ok();
+
         sqlx::query("CREATE INDEX IF NOT EXISTS idx_history_type ON history_records(record_type)")
             .execute(&*self.pool)
             .await?;
@@ -307,25 +1078,138 @@ impl Database {
             .execute(&*self.pool)
             .await?;
 
-        // Create hotkey configs table
-        sqlx::query(
-            r#"
-            CREATE TABLE IF NOT EXISTS hotkey_configs (
-                id TEXT PRIMARY KEY,
-                transcribe_key TEXT NOT NULL,
-                translate_key TEXT NOT NULL,
-                trigger_delay_ms INTEGER NOT NULL DEFAULT 300,
-                anti_mistouch_enabled BOOLEAN NOT NULL DEFAULT TRUE,
-                save_wav_files BOOLEAN NOT NULL DEFAULT TRUE,
-                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
-                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
-            )
-            "#,
-        )
-        .execute(&*self.pool)
-        .await?;
+        // 用量报表按时间范围聚合，覆盖索引避免回表
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_history_report ON history_records(created_at, processor_type, success, processing_time_ms, audio_duration_ms)")
+            .execute(&*self.pool)
+            .await?;
 
-        // Add the save_wav_files column if it doesn't exist (for existing databases)
+        // 旧版本的云端处理器标识改为统一的 "cloud-*" 形式，按处理器筛选历史时不会分成两类
+        for (legacy, current) in LEGACY_PROCESSOR_TYPES {
+            sqlx::query("UPDATE history_records SET processor_type = $1 WHERE processor_type = $2")
+                .bind(current)
+                .bind(legacy)
+                .execute(&*self.pool)
+                .await
+                .ok();
+        }
+
+        // FTS 索引用显式的 search_id 作为 content rowid：history_records 的主键是 TEXT，
+        // 隐式 rowid 在 VACUUM 时可能被重新编号，索引会指向错误的记录
+        let search_id_added = sqlx::query("ALTER TABLE history_records ADD COLUMN search_id INTEGER")
+            .execute(&*self.pool)
+            .await
+            .is_ok();
+        if search_id_added {
+            sqlx::query("UPDATE history_records SET search_id = rowid WHERE search_id IS NULL")
+                .execute(&*self.pool)
+                .await?;
+            // 旧版本的索引和触发器按隐式 rowid 建立，删除后按 search_id 重建
+            for statement in [
+                "DROP TRIGGER IF EXISTS history_fts_ai",
+                "DROP TRIGGER IF EXISTS history_fts_ad",
+                "DROP TRIGGER IF EXISTS history_fts_au",
+                "DROP TABLE IF EXISTS history_fts",
+            ] {
+                sqlx::query(statement).execute(&*self.pool).await?;
+            }
+        }
+        sqlx::query("CREATE UNIQUE INDEX IF NOT EXISTS idx_history_search_id ON history_records(search_id)")
+            .execute(&*self.pool)
+            .await?;
+
+        // Full-text search index over history text (external content table kept in sync by triggers)
+        let fts_existed = sqlx::query_scalar::<_, bool>(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'history_fts'"
+        )
+        .fetch_one(&*self.pool)
+        .await
+        .unwrap_or(false);
+
+        let fts_created = sqlx::query(
+            r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS history_fts USING fts5(
+                input_text,
+                output_text,
+                content='history_records',
+                content_rowid='search_id'
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await;
+
+        match fts_created {
+            Ok(_) => {
+                sqlx::query(
+                    r#"
+                    CREATE TRIGGER IF NOT EXISTS history_fts_ai AFTER INSERT ON history_records BEGIN
+                        UPDATE history_records
+                        SET search_id = (SELECT COALESCE(MAX(search_id), 0) + 1 FROM history_records)
+                        WHERE id = new.id AND search_id IS NULL;
+                        INSERT INTO history_fts(rowid, input_text, output_text)
+                        SELECT search_id, input_text, output_text FROM history_records WHERE id = new.id;
+                    END
+                    "#
+                )
+                .execute(&*self.pool)
+                .await?;
+
+                sqlx::query(
+                    r#"
+                    CREATE TRIGGER IF NOT EXISTS history_fts_ad AFTER DELETE ON history_records BEGIN
+                        INSERT INTO history_fts(history_fts, rowid, input_text, output_text)
+                        VALUES ('delete', old.search_id, old.input_text, old.output_text);
+                    END
+                    "#
+                )
+                .execute(&*self.pool)
+                .await?;
+
+                sqlx::query(
+                    r#"
+                    CREATE TRIGGER IF NOT EXISTS history_fts_au AFTER UPDATE OF input_text, output_text ON history_records BEGIN
+                        INSERT INTO history_fts(history_fts, rowid, input_text, output_text)
+                        VALUES ('delete', old.search_id, old.input_text, old.output_text);
+                        INSERT INTO history_fts(rowid, input_text, output_text)
+                        VALUES (new.search_id, new.input_text, new.output_text);
+                    END
+                    "#
+                )
+                .execute(&*self.pool)
+                .await?;
+
+                // Backfill the index from rows that existed before FTS was added
+                if !fts_existed {
+                    info!("🔄 Database: Backfilling history full-text index...");
+                    sqlx::query("INSERT INTO history_fts(history_fts) VALUES('rebuild')")
+                        .execute(&*self.pool)
+                        .await?;
+                }
+            }
+            Err(e) => {
+                warn!("⚠️ Database: FTS5 unavailable, history search will use LIKE: {}", e);
+            }
+        }
+
+        // Create hotkey configs table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS hotkey_configs (
+                id TEXT PRIMARY KEY,
+                transcribe_key TEXT NOT NULL,
+                translate_key TEXT NOT NULL,
+                trigger_delay_ms INTEGER NOT NULL DEFAULT 300,
+                anti_mistouch_enabled BOOLEAN NOT NULL DEFAULT TRUE,
+                save_wav_files BOOLEAN NOT NULL DEFAULT TRUE,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#,
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Add the save_wav_files column if it doesn't exist (for existing databases)
         sqlx::query(
             r#"
             ALTER TABLE hotkey_configs ADD COLUMN save_wav_files BOOLEAN NOT NULL DEFAULT TRUE
@@ -412,6 +1296,242 @@ impl Database {
         .await
         .ok(); // Ignore error if column already exists
 
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN text_injection_method TEXT NOT NULL DEFAULT 'auto'
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN hotkey_backend TEXT NOT NULL DEFAULT 'rdev'
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN recording_mode TEXT NOT NULL DEFAULT 'hold'
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN max_recording_secs INTEGER NOT NULL DEFAULT 120
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN silence_auto_stop_enabled BOOLEAN NOT NULL DEFAULT FALSE
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN silence_auto_stop_ms INTEGER NOT NULL DEFAULT 2000
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN preroll_ms INTEGER NOT NULL DEFAULT 500
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query(
+            r#"
+            ALTER TABLE hotkey_configs ADD COLUMN queued_to_clipboard BOOLEAN NOT NULL DEFAULT FALSE
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        sqlx::query("ALTER TABLE hotkey_configs ADD COLUMN cycle_profile_key TEXT")
+            .execute(&*self.pool)
+            .await
+            .ok(); // Ignore error if column already exists
+
+        // Add per-stage latency columns (NULL for older records)
+        for column in ["record_ms", "wav_ms", "asr_ms", "postprocess_ms", "typing_ms", "attempts", "race_local_ms", "race_cloud_ms", "translate_ms"] {
+            sqlx::query(&format!("ALTER TABLE latency_records ADD COLUMN {} INTEGER", column))
+                .execute(&*self.pool)
+                .await
+                .ok(); // Ignore error if column already exists
+        }
+
+        // Create post-processing config table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS postprocess_configs (
+                id TEXT PRIMARY KEY,
+                convert_to_simplified BOOLEAN NOT NULL DEFAULT TRUE,
+                add_symbol BOOLEAN NOT NULL DEFAULT TRUE,
+                optimize_result BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query(
+            r#"
+            ALTER TABLE postprocess_configs ADD COLUMN optimize_timeout_ms INTEGER NOT NULL DEFAULT 3000
+            "#
+        )
+        .execute(&*self.pool)
+        .await
+        .ok(); // Ignore error if column already exists
+
+        // Create audio pre-processing config table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS audio_prep_configs (
+                id TEXT PRIMARY KEY,
+                normalize_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+                high_pass_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+                noise_gate_enabled BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Create network retry config table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS network_retry_configs (
+                id TEXT PRIMARY KEY,
+                max_retries INTEGER NOT NULL DEFAULT 3,
+                time_budget_ms INTEGER NOT NULL DEFAULT 20000,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Create overlay config table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS overlay_configs (
+                id TEXT PRIMARY KEY,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                position TEXT NOT NULL DEFAULT 'cursor',
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Create recording retention config table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS recording_retention_configs (
+                id TEXT PRIMARY KEY,
+                max_age_days INTEGER,
+                max_total_mb INTEGER,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Create runtime settings table (key/value)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS app_settings (
+                key TEXT PRIMARY KEY,
+                value TEXT NOT NULL,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Create dictation command config table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS dictation_command_configs (
+                id TEXT PRIMARY KEY,
+                enabled BOOLEAN NOT NULL DEFAULT 0,
+                commands_json TEXT,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        // Create per-application output profile table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS output_profiles (
+                id TEXT PRIMARY KEY,
+                app_pattern TEXT NOT NULL,
+                injection_method TEXT NOT NULL DEFAULT 'default',
+                trailing_text TEXT NOT NULL DEFAULT '',
+                capitalize_sentences BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
+        sqlx::query("ALTER TABLE output_profiles ADD COLUMN typing_delays_json TEXT")
+            .execute(&*self.pool)
+            .await
+            .ok(); // 忽略错误，如果列已存在
+
+        // Create transcription replacements (glossary) table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS transcription_replacements (
+                id TEXT PRIMARY KEY,
+                pattern TEXT NOT NULL,
+                replacement TEXT NOT NULL,
+                is_regex BOOLEAN NOT NULL DEFAULT FALSE,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
+        )
+        .execute(&*self.pool)
+        .await?;
+
         // Create service stats table
         sqlx::query(
             r#"
@@ -433,6 +1553,14 @@ impl Database {
         .execute(&*self.pool)
         .await?;
 
+        // 最近一次成功/失败的请求时间和错误信息（旧数据为 NULL）
+        for column in ["last_success_at DATETIME", "last_error_at DATETIME", "last_error TEXT"] {
+            sqlx::query(&format!("ALTER TABLE service_stats ADD COLUMN {}", column))
+                .execute(&*self.pool)
+                .await
+                .ok(); // Ignore error if column already exists
+        }
+
         // Create latency records table
         sqlx::query(
             r#"
@@ -475,26 +1603,132 @@ impl Database {
             .execute(&*self.pool)
             .await?;
 
-        sqlx::query("CREATE INDEX IF NOT EXISTS idx_usage_date ON usage_logs(date)")
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_latency_report ON latency_records(recorded_at, service_name, latency_ms, asr_ms)")
             .execute(&*self.pool)
             .await?;
 
-        info!("Database migrations completed successfully");
-        Ok(())
-    }
+        sqlx::query("ALTER TABLE usage_logs ADD COLUMN total_audio_ms INTEGER NOT NULL DEFAULT 0")
+            .execute(&*self.pool)
+            .await
+            .ok(); // 忽略错误，如果列已存在
 
-    // Hotkey Configuration methods
-    pub async fn get_hotkey_config(&self) -> Result<Option<HotkeyConfig>, sqlx::Error> {
-        let config = sqlx::query_as::<_, HotkeyConfig>(
-            "SELECT * FROM hotkey_configs ORDER BY updated_at DESC LIMIT 1"
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_usage_date ON usage_logs(date)")
+            .execute(&*self.pool)
+            .await?;
+
+        // Create benchmark results table
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS benchmark_results (
+                id TEXT PRIMARY KEY,
+                model_name TEXT NOT NULL,
+                model_path TEXT NOT NULL,
+                backend TEXT NOT NULL,
+                app_version TEXT NOT NULL,
+                load_ms INTEGER,
+                inference_ms INTEGER,
+                audio_ms INTEGER NOT NULL,
+                realtime_factor REAL,
+                peak_rss_mb REAL,
+                success BOOLEAN NOT NULL,
+                error_message TEXT,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            )
+            "#
         )
-        .fetch_optional(&*self.pool)
+        .execute(&*self.pool)
         .await?;
 
-        Ok(config)
-    }
+        // 之前没有打包参考录音，旧结果都是用合成信号测的
+        sqlx::query("ALTER TABLE benchmark_results ADD COLUMN audio_source TEXT NOT NULL DEFAULT 'synthetic'")
+            .execute(&*self.pool)
+            .await
+            .ok(); // 忽略错误，如果列已存在
 
-    pub async fn save_hotkey_config(
+        sqlx::query("CREATE INDEX IF NOT EXISTS idx_benchmark_created ON benchmark_results(created_at)")
+            .execute(&*self.pool)
+            .await?;
+
+        // One-time migration: encrypt API keys that were stored in plaintext
+        self.encrypt_plaintext_api_keys().await?;
+
+        info!("Database migrations completed successfully");
+        Ok(())
+    }
+
+    /// 把旧版明文保存的 API key 重新加密，已加密的行会被跳过
+    async fn encrypt_plaintext_api_keys(&self) -> Result<(), sqlx::Error> {
+        let mut migrated = 0;
+
+        let asr_rows = sqlx::query_as::<_, AsrConfig>("SELECT * FROM asr_configs")
+            .fetch_all(&*self.pool)
+            .await?;
+        for row in asr_rows {
+            let needs_local = row.local_api_key.as_deref().map_or(false, |k| !crypto::is_encrypted(k));
+            let needs_cloud = row.cloud_api_key.as_deref().map_or(false, |k| !crypto::is_encrypted(k));
+            if !needs_local && !needs_cloud {
+                continue;
+            }
+
+            sqlx::query("UPDATE asr_configs SET local_api_key = $1, cloud_api_key = $2 WHERE id = $3")
+                .bind(encrypt_field(row.local_api_key.as_deref())?)
+                .bind(encrypt_field(row.cloud_api_key.as_deref())?)
+                .bind(&row.id)
+                .execute(&*self.pool)
+                .await?;
+            migrated += 1;
+        }
+
+        let translation_rows = sqlx::query_as::<_, TranslationConfig>("SELECT * FROM translation_configs")
+            .fetch_all(&*self.pool)
+            .await?;
+        for row in translation_rows {
+            if !row.api_key.as_deref().map_or(false, |k| !crypto::is_encrypted(k)) {
+                continue;
+            }
+
+            sqlx::query("UPDATE translation_configs SET api_key = $1 WHERE id = $2")
+                .bind(encrypt_field(row.api_key.as_deref())?)
+                .bind(&row.id)
+                .execute(&*self.pool)
+                .await?;
+            migrated += 1;
+        }
+
+        let setting_rows = sqlx::query_as::<_, AppSetting>("SELECT * FROM app_settings")
+            .fetch_all(&*self.pool)
+            .await?;
+        for row in setting_rows {
+            if !is_secret_app_setting(&row.key) || crypto::is_encrypted(&row.value) {
+                continue;
+            }
+
+            sqlx::query("UPDATE app_settings SET value = $1 WHERE key = $2")
+                .bind(app_setting_value(&row.key, &row.value)?)
+                .bind(&row.key)
+                .execute(&*self.pool)
+                .await?;
+            migrated += 1;
+        }
+
+        if migrated > 0 {
+            info!("🔐 Database: Encrypted API keys in {} existing config row(s)", migrated);
+        }
+        Ok(())
+    }
+
+    // Hotkey Configuration methods
+    pub async fn get_hotkey_config(&self) -> Result<Option<HotkeyConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, HotkeyConfig>(
+            "SELECT * FROM hotkey_configs ORDER BY updated_at DESC LIMIT 1"
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(config)
+    }
+
+    pub async fn save_hotkey_config(
         &self,
         transcribe_key: &str,
         translate_key: &str,
@@ -502,6 +1736,15 @@ impl Database {
         anti_mistouch_enabled: bool,
         save_wav_files: bool,
         typing_delays: Option<&TypingDelays>,
+        text_injection_method: Option<&str>,
+        hotkey_backend: Option<&str>,
+        recording_mode: Option<&str>,
+        max_recording_secs: Option<i64>,
+        silence_auto_stop_enabled: Option<bool>,
+        silence_auto_stop_ms: Option<i64>,
+        preroll_ms: Option<i64>,
+        queued_to_clipboard: Option<bool>,
+        cycle_profile_key: Option<&str>,
     ) -> Result<HotkeyConfig, sqlx::Error> {
         let now = Utc::now();
 
@@ -530,7 +1773,16 @@ impl Database {
                 typing_complete_ms = $8,
                 character_interval_ms = $9,
                 short_operation_ms = $10,
-                updated_at = $11
+                updated_at = $11,
+                text_injection_method = COALESCE($12, text_injection_method),
+                hotkey_backend = COALESCE($13, hotkey_backend),
+                recording_mode = COALESCE($14, recording_mode),
+                max_recording_secs = COALESCE($15, max_recording_secs),
+                silence_auto_stop_enabled = COALESCE($16, silence_auto_stop_enabled),
+                silence_auto_stop_ms = COALESCE($17, silence_auto_stop_ms),
+                preroll_ms = COALESCE($18, preroll_ms),
+                queued_to_clipboard = COALESCE($19, queued_to_clipboard),
+                cycle_profile_key = COALESCE($20, cycle_profile_key)
             WHERE id = (SELECT id FROM hotkey_configs ORDER BY updated_at DESC LIMIT 1)
             RETURNING *
             "#
@@ -546,6 +1798,15 @@ impl Database {
         .bind(delays.character_interval_ms)
         .bind(delays.short_operation_ms)
         .bind(now)
+        .bind(text_injection_method)
+        .bind(hotkey_backend)
+        .bind(recording_mode)
+        .bind(max_recording_secs)
+        .bind(silence_auto_stop_enabled)
+        .bind(silence_auto_stop_ms)
+        .bind(preroll_ms)
+        .bind(queued_to_clipboard)
+        .bind(cycle_profile_key)
         .fetch_optional(&*self.pool)
         .await?;
 
@@ -567,8 +1828,8 @@ impl Database {
 
             let config = sqlx::query_as::<_, HotkeyConfig>(
                 r#"
-                INSERT INTO hotkey_configs (id, transcribe_key, translate_key, trigger_delay_ms, anti_mistouch_enabled, save_wav_files, clipboard_update_ms, keyboard_events_settle_ms, typing_complete_ms, character_interval_ms, short_operation_ms, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13)
+                INSERT INTO hotkey_configs (id, transcribe_key, translate_key, trigger_delay_ms, anti_mistouch_enabled, save_wav_files, clipboard_update_ms, keyboard_events_settle_ms, typing_complete_ms, character_interval_ms, short_operation_ms, created_at, updated_at, text_injection_method, hotkey_backend, recording_mode, max_recording_secs, silence_auto_stop_enabled, silence_auto_stop_ms, preroll_ms, queued_to_clipboard, cycle_profile_key)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, COALESCE($14, 'auto'), COALESCE($15, 'rdev'), COALESCE($16, 'hold'), $17, COALESCE($18, FALSE), COALESCE($19, 2000), COALESCE($20, 500), COALESCE($21, FALSE), $22)
                 RETURNING *
                 "#
             )
@@ -585,6 +1846,15 @@ impl Database {
             .bind(delays.short_operation_ms)
             .bind(now)
             .bind(now)
+            .bind(text_injection_method)
+            .bind(hotkey_backend)
+            .bind(recording_mode)
+            .bind(max_recording_secs.unwrap_or_else(default_max_recording_secs))
+            .bind(silence_auto_stop_enabled)
+            .bind(silence_auto_stop_ms)
+            .bind(preroll_ms)
+            .bind(queued_to_clipboard)
+            .bind(cycle_profile_key)
             .fetch_one(&*self.pool)
             .await?;
 
@@ -594,320 +1864,1669 @@ impl Database {
         }
     }
 
-    // ASR Configuration methods
-    pub async fn get_asr_config(&self) -> Result<Option<AsrConfig>, sqlx::Error> {
-        println!("🗄️ Database: get_asr_config() called");
-        println!("🔍 Database: Querying asr_configs table...");
-        
-        let config = sqlx::query_as::<_, AsrConfig>(
-            "SELECT * FROM asr_configs ORDER BY updated_at DESC LIMIT 1"
+    // Post-processing Configuration methods
+    pub async fn get_postprocess_config(&self) -> Result<Option<PostProcessConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, PostProcessConfig>(
+            "SELECT * FROM postprocess_configs ORDER BY updated_at DESC LIMIT 1"
         )
         .fetch_optional(&*self.pool)
         .await?;
 
-        if let Some(ref cfg) = config {
-            println!("✅ Database: Query successful, found ASR config:");
-            println!("  - ID: {}", cfg.id);
-            println!("  - Service Provider: {}", cfg.service_provider);
-            println!("  - Local Endpoint: {:?}", cfg.local_endpoint);
-            println!("  - Local API Key: {}", cfg.local_api_key.is_some());
-            println!("  - Cloud Endpoint: {:?}", cfg.cloud_endpoint);
-            println!("  - Cloud API Key: {}", cfg.cloud_api_key.is_some());
-            println!("  - Whisper Model: {:?}", cfg.whisper_model);
-            println!("  - Created At: {}", cfg.created_at);
-            println!("  - Updated At: {}", cfg.updated_at);
-        } else {
-            println!("📥 Database: Query successful, but no ASR config found");
-        }
-
         Ok(config)
     }
 
-    pub async fn save_asr_config(
+    pub async fn save_postprocess_config(
         &self,
-        service_provider: &str,
-        local_endpoint: Option<&str>,
-        local_api_key: Option<&str>,
-        cloud_endpoint: Option<&str>,
-        cloud_api_key: Option<&str>,
-        whisper_model: Option<&str>,
-    ) -> Result<AsrConfig, sqlx::Error> {
+        convert_to_simplified: bool,
+        add_symbol: bool,
+        optimize_result: bool,
+        optimize_timeout_ms: i64,
+    ) -> Result<PostProcessConfig, sqlx::Error> {
         let now = Utc::now();
 
-        // First, try to update existing record
-        let update_result = sqlx::query_as::<_, AsrConfig>(
+        let update_result = sqlx::query_as::<_, PostProcessConfig>(
             r#"
-            UPDATE asr_configs
-            SET service_provider = $1,
-                local_endpoint = $2,
-                local_api_key = $3,
-                cloud_endpoint = $4,
-                cloud_api_key = $5,
-                whisper_model = $6,
-                updated_at = $7
-            WHERE id = (SELECT id FROM asr_configs ORDER BY updated_at DESC LIMIT 1)
+            UPDATE postprocess_configs
+            SET convert_to_simplified = $1,
+                add_symbol = $2,
+                optimize_result = $3,
+                optimize_timeout_ms = $4,
+                updated_at = $5
+            WHERE id = (SELECT id FROM postprocess_configs ORDER BY updated_at DESC LIMIT 1)
             RETURNING *
             "#
         )
-        .bind(service_provider)
-        .bind(local_endpoint)
-        .bind(local_api_key)
-        .bind(cloud_endpoint)
-        .bind(cloud_api_key)
-        .bind(whisper_model)
+        .bind(convert_to_simplified)
+        .bind(add_symbol)
+        .bind(optimize_result)
+        .bind(optimize_timeout_ms)
         .bind(now)
         .fetch_optional(&*self.pool)
         .await?;
 
         if let Some(config) = update_result {
-            info!("Updated ASR config for provider: {}", service_provider);
-            println!("✅ Database: Updated existing ASR config with whisper model: {:?}", whisper_model);
-            Ok(config)
-        } else {
-            // If no existing record, insert new one
-            println!("⚠️ Database: No existing record found, creating new one...");
-            let id = Uuid::new_v4().to_string();
-            println!("🆔 Database: New record ID: {}", id);
-            println!("💾 Database: Inserting API key: {:?}", local_api_key);
+            info!("Updated post-processing config");
+            return Ok(config);
+        }
 
-            let config = sqlx::query_as::<_, AsrConfig>(
-                r#"
-                INSERT INTO asr_configs (id, service_provider, local_endpoint, local_api_key, cloud_endpoint, cloud_api_key, whisper_model, created_at, updated_at)
-                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9)
-                RETURNING *
-                "#
-            )
-            .bind(&id)
-            .bind(service_provider)
-            .bind(local_endpoint)
-            .bind(local_api_key)
-            .bind(cloud_endpoint)
-            .bind(cloud_api_key)
-            .bind(whisper_model)
-            .bind(now)
-            .bind(now)
-            .fetch_one(&*self.pool)
-            .await?;
+        let id = Uuid::new_v4().to_string();
+        let config = sqlx::query_as::<_, PostProcessConfig>(
+            r#"
+            INSERT INTO postprocess_configs (id, convert_to_simplified, add_symbol, optimize_result, optimize_timeout_ms, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(convert_to_simplified)
+        .bind(add_symbol)
+        .bind(optimize_result)
+        .bind(optimize_timeout_ms)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&*self.pool)
+        .await?;
 
-            info!("Created new ASR config for provider: {}", service_provider);
-            Ok(config)
-        }
+        info!("Created new post-processing config");
+        Ok(config)
     }
 
-    // Translation Configuration methods
-    pub async fn get_translation_config(&self, provider: &str) -> Result<Option<TranslationConfig>, sqlx::Error> {
-        let config = sqlx::query_as::<_, TranslationConfig>(
-            "SELECT * FROM translation_configs WHERE provider = $1 ORDER BY updated_at DESC LIMIT 1"
+    // Audio pre-processing configuration methods
+    pub async fn get_audio_prep_config(&self) -> Result<Option<AudioPrepConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, AudioPrepConfig>(
+            "SELECT * FROM audio_prep_configs ORDER BY updated_at DESC LIMIT 1"
         )
-        .bind(provider)
         .fetch_optional(&*self.pool)
         .await?;
 
         Ok(config)
     }
 
-    pub async fn save_translation_config(
+    pub async fn save_audio_prep_config(
         &self,
-        provider: &str,
-        api_key: Option<&str>,
-        endpoint: Option<&str>,
-    ) -> Result<TranslationConfig, sqlx::Error> {
-        let id = Uuid::new_v4().to_string();
+        normalize_enabled: bool,
+        high_pass_enabled: bool,
+        noise_gate_enabled: bool,
+    ) -> Result<AudioPrepConfig, sqlx::Error> {
         let now = Utc::now();
 
-        let config = sqlx::query_as::<_, TranslationConfig>(
+        let update_result = sqlx::query_as::<_, AudioPrepConfig>(
+            r#"
+            UPDATE audio_prep_configs
+            SET normalize_enabled = $1,
+                high_pass_enabled = $2,
+                noise_gate_enabled = $3,
+                updated_at = $4
+            WHERE id = (SELECT id FROM audio_prep_configs ORDER BY updated_at DESC LIMIT 1)
+            RETURNING *
+            "#
+        )
+        .bind(normalize_enabled)
+        .bind(high_pass_enabled)
+        .bind(noise_gate_enabled)
+        .bind(now)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        if let Some(config) = update_result {
+            info!("Updated audio pre-processing config");
+            return Ok(config);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let config = sqlx::query_as::<_, AudioPrepConfig>(
             r#"
-            INSERT INTO translation_configs (id, provider, api_key, endpoint, created_at, updated_at)
+            INSERT INTO audio_prep_configs (id, normalize_enabled, high_pass_enabled, noise_gate_enabled, created_at, updated_at)
             VALUES ($1, $2, $3, $4, $5, $6)
             RETURNING *
             "#
         )
         .bind(&id)
-        .bind(provider)
-        .bind(api_key)
-        .bind(endpoint)
+        .bind(normalize_enabled)
+        .bind(high_pass_enabled)
+        .bind(noise_gate_enabled)
         .bind(now)
         .bind(now)
         .fetch_one(&*self.pool)
         .await?;
 
-        info!("Saved translation config for provider: {}", provider);
+        info!("Created new audio pre-processing config");
         Ok(config)
     }
 
-    // History methods
-    pub async fn add_history_record(&self, record: NewHistoryRecord) -> Result<HistoryRecord, sqlx::Error> {
-        let id = Uuid::new_v4().to_string();
+    // Network retry configuration methods
+    pub async fn get_network_retry_config(&self) -> Result<Option<NetworkRetryConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, NetworkRetryConfig>(
+            "SELECT * FROM network_retry_configs ORDER BY updated_at DESC LIMIT 1"
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(config)
+    }
+
+    pub async fn save_network_retry_config(
+        &self,
+        max_retries: i64,
+        time_budget_ms: i64,
+    ) -> Result<NetworkRetryConfig, sqlx::Error> {
         let now = Utc::now();
 
-        let history = sqlx::query_as::<_, HistoryRecord>(
+        let update_result = sqlx::query_as::<_, NetworkRetryConfig>(
             r#"
-            INSERT INTO history_records (id, record_type, input_text, output_text, audio_file_path, processor_type, processing_time_ms, success, error_message, created_at)
-            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+            UPDATE network_retry_configs
+            SET max_retries = $1,
+                time_budget_ms = $2,
+                updated_at = $3
+            WHERE id = (SELECT id FROM network_retry_configs ORDER BY updated_at DESC LIMIT 1)
             RETURNING *
             "#
         )
-        .bind(&id)
-        .bind(&record.record_type)
-        .bind(&record.input_text)
-        .bind(&record.output_text)
-        .bind(&record.audio_file_path)
-        .bind(&record.processor_type)
-        .bind(record.processing_time_ms)
-        .bind(record.success)
-        .bind(&record.error_message)
+        .bind(max_retries)
+        .bind(time_budget_ms)
         .bind(now)
-        .fetch_one(&*self.pool)
+        .fetch_optional(&*self.pool)
         .await?;
 
-        // Update service statistics after successful history record addition
-        if record.success {
-            self.update_service_stats_from_record(&record, now).await?;
-            self.update_latency_from_record(&record, now).await?;
-            self.update_usage_from_record(&record, now).await?;
+        if let Some(config) = update_result {
+            info!("Updated network retry config");
+            return Ok(config);
         }
 
-        Ok(history)
-    }
-
-    // Helper function to update service stats from a new history record
-    async fn update_service_stats_from_record(&self, record: &NewHistoryRecord, _timestamp: chrono::DateTime<chrono::Utc>) -> Result<(), sqlx::Error> {
-        let service_name = match record.processor_type.as_deref() {
-            Some("whisper") => "whisper_asr",
-            Some("sensevoice") => "sensevoice_asr", 
-            Some("local") => "local_asr",
-            Some("siliconflow") => "siliconflow_translation",
-            Some("ollama") => "ollama_translation",
-            _ => "unknown_service",
-        };
-
-        let status = if record.success { "online" } else { "error" };
-        
-        self.update_service_status(service_name, status, None).await?;
-        Ok(())
-    }
-
-    // Helper function to update latency from a new history record
-    async fn update_latency_from_record(&self, record: &NewHistoryRecord, timestamp: chrono::DateTime<chrono::Utc>) -> Result<(), sqlx::Error> {
-        let service_name = match record.processor_type.as_deref() {
-            Some("whisper") | Some("whisper-rs") => "local_asr",  // whisper-rs maps to local_asr
-            Some("sensevoice") => "sensevoice_asr",
-            Some("local") => "local_asr",
-            Some("cloud") => "cloud_asr",
-            _ => "local_asr",  // Default to local_asr for unknown types
-        };
-
-        // Insert latency record
         let id = Uuid::new_v4().to_string();
-        sqlx::query(
+        let config = sqlx::query_as::<_, NetworkRetryConfig>(
             r#"
-            INSERT INTO latency_records (id, service_name, latency_ms, request_type, recorded_at)
+            INSERT INTO network_retry_configs (id, max_retries, time_budget_ms, created_at, updated_at)
             VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
             "#
         )
         .bind(&id)
-        .bind(service_name)
-        .bind(record.processing_time_ms.unwrap_or(0))
-        .bind(&record.record_type)
-        .bind(timestamp)
-        .execute(&*self.pool)
+        .bind(max_retries)
+        .bind(time_budget_ms)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&*self.pool)
         .await?;
 
-        Ok(())
+        info!("Created new network retry config");
+        Ok(config)
     }
 
-    // Helper function to update usage from a new history record
-    async fn update_usage_from_record(&self, record: &NewHistoryRecord, timestamp: chrono::DateTime<chrono::Utc>) -> Result<(), sqlx::Error> {
-        // Update today's usage (calculate seconds from processing time)
-        let seconds_today = (record.processing_time_ms.unwrap_or(0) / 1000).max(1); // Convert ms to seconds, at least 1 second
-        
-        // Update or insert today's usage record
-        let today = timestamp.format("%Y-%m-%d").to_string();
-        let id = Uuid::new_v4().to_string();
-        
-        sqlx::query(
-            r#"
-            INSERT OR REPLACE INTO usage_logs (id, date, total_seconds, total_requests, successful_requests)
-            VALUES (
-                $1,
-                $2,
-                COALESCE((SELECT total_seconds FROM usage_logs WHERE date = $2), 0) + $3,
-                COALESCE((SELECT total_requests FROM usage_logs WHERE date = $2), 0) + 1,
-                COALESCE((SELECT successful_requests FROM usage_logs WHERE date = $2), 0) + $4
-            )
-            "#
+    // Recording retention methods
+    pub async fn get_recording_retention_config(&self) -> Result<Option<RecordingRetentionConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, RecordingRetentionConfig>(
+            "SELECT * FROM recording_retention_configs ORDER BY updated_at DESC LIMIT 1"
         )
-        .bind(&id)
-        .bind(&today)
-        .bind(seconds_today)
-        .bind(if record.success { 1 } else { 0 })
-        .execute(&*self.pool)
+        .fetch_optional(&*self.pool)
         .await?;
 
-        Ok(())
+        Ok(config)
     }
 
-    pub async fn get_history_records(
+    /// 限制为 None 表示不按该条件清理
+    pub async fn save_recording_retention_config(
         &self,
-        limit: Option<i64>,
-        record_type: Option<&str>,
-    ) -> Result<Vec<HistoryRecord>, sqlx::Error> {
-        let mut query = "SELECT * FROM history_records".to_string();
-        let mut conditions = Vec::new();
+        max_age_days: Option<i64>,
+        max_total_mb: Option<i64>,
+    ) -> Result<RecordingRetentionConfig, sqlx::Error> {
+        let now = Utc::now();
 
-        if let Some(r_type) = record_type {
-            conditions.push(format!("record_type = '{}'", r_type));
-        }
+        let update_result = sqlx::query_as::<_, RecordingRetentionConfig>(
+            r#"
+            UPDATE recording_retention_configs
+            SET max_age_days = $1,
+                max_total_mb = $2,
+                updated_at = $3
+            WHERE id = (SELECT id FROM recording_retention_configs ORDER BY updated_at DESC LIMIT 1)
+            RETURNING *
+            "#
+        )
+        .bind(max_age_days)
+        .bind(max_total_mb)
+        .bind(now)
+        .fetch_optional(&*self.pool)
+        .await?;
 
-        if !conditions.is_empty() {
-            query += " WHERE ";
-            query += &conditions.join(" AND ");
+        if let Some(config) = update_result {
+            info!("Updated recording retention config");
+            return Ok(config);
         }
 
-        query += " ORDER BY created_at DESC";
+        let id = Uuid::new_v4().to_string();
+        let config = sqlx::query_as::<_, RecordingRetentionConfig>(
+            r#"
+            INSERT INTO recording_retention_configs (id, max_age_days, max_total_mb, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(max_age_days)
+        .bind(max_total_mb)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&*self.pool)
+        .await?;
 
-        if let Some(limit_val) = limit {
-            query += &format!(" LIMIT {}", limit_val);
-        }
+        info!("Created new recording retention config");
+        Ok(config)
+    }
 
-        let records = sqlx::query_as::<_, HistoryRecord>(&query)
+    // Runtime settings methods
+    pub async fn get_app_settings(&self) -> Result<Vec<AppSetting>, sqlx::Error> {
+        let settings = sqlx::query_as::<_, AppSetting>("SELECT * FROM app_settings ORDER BY key ASC")
             .fetch_all(&*self.pool)
             .await?;
-
-        Ok(records)
+        Ok(settings.into_iter().map(AppSetting::decrypted).collect())
     }
 
-    pub async fn get_history_stats(&self) -> Result<(i64, i64, i64), sqlx::Error> {
-        let total_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM history_records")
-            .fetch_one(&*self.pool)
-            .await?;
+    pub async fn set_app_setting(&self, key: &str, value: &str) -> Result<AppSetting, sqlx::Error> {
+        let setting = sqlx::query_as::<_, AppSetting>(
+            r#"
+            INSERT INTO app_settings (key, value, updated_at)
+            VALUES ($1, $2, $3)
+            ON CONFLICT(key) DO UPDATE SET
+                value = excluded.value,
+                updated_at = excluded.updated_at
+            RETURNING *
+            "#
+        )
+        .bind(key)
+        .bind(app_setting_value(key, value)?)
+        .bind(Utc::now())
+        .fetch_one(&*self.pool)
+        .await?;
 
-        let success_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM history_records WHERE success = true")
-            .fetch_one(&*self.pool)
-            .await?;
+        info!("Saved app setting {}", key);
+        Ok(setting.decrypted())
+    }
 
-        let transcribe_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM history_records WHERE record_type = 'transcribe'")
-            .fetch_one(&*self.pool)
+    /// 删除已保存的设置，恢复为默认值。返回是否存在该项
+    pub async fn delete_app_setting(&self, key: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM app_settings WHERE key = $1")
+            .bind(key)
+            .execute(&*self.pool)
             .await?;
-
-        Ok((total_count, success_count, transcribe_count))
+        Ok(result.rows_affected() > 0)
     }
 
-    // Utility methods
+    /// 在 `since` 之后创建的历史记录仍引用的录音文件
+    pub async fn get_audio_paths_referenced_since(&self, since: DateTime<Utc>) -> Result<Vec<String>, sqlx::Error> {
+        sqlx::query_scalar(
+            "SELECT DISTINCT audio_file_path FROM history_records WHERE audio_file_path IS NOT NULL AND created_at >= $1"
+        )
+        .bind(since)
+        .fetch_all(&*self.pool)
+        .await
+    }
+
+    /// 录音文件被删除后清空引用它的历史记录，返回受影响的记录数
+    pub async fn clear_audio_path_references(&self, audio_file_path: &str) -> Result<u64, sqlx::Error> {
+        let result = sqlx::query("UPDATE history_records SET audio_file_path = NULL WHERE audio_file_path = $1")
+            .bind(audio_file_path)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    // Overlay configuration methods
+    pub async fn get_overlay_config(&self) -> Result<Option<OverlayConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, OverlayConfig>(
+            "SELECT * FROM overlay_configs ORDER BY updated_at DESC LIMIT 1"
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(config)
+    }
+
+    /// 参数为 None 时保留原值，首次保存时使用默认值
+    pub async fn save_overlay_config(
+        &self,
+        enabled: Option<bool>,
+        position: Option<&str>,
+    ) -> Result<OverlayConfig, sqlx::Error> {
+        let now = Utc::now();
+
+        let update_result = sqlx::query_as::<_, OverlayConfig>(
+            r#"
+            UPDATE overlay_configs
+            SET enabled = COALESCE($1, enabled),
+                position = COALESCE($2, position),
+                updated_at = $3
+            WHERE id = (SELECT id FROM overlay_configs ORDER BY updated_at DESC LIMIT 1)
+            RETURNING *
+            "#
+        )
+        .bind(enabled)
+        .bind(position)
+        .bind(now)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        if let Some(config) = update_result {
+            info!("Updated overlay config");
+            return Ok(config);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let config = sqlx::query_as::<_, OverlayConfig>(
+            r#"
+            INSERT INTO overlay_configs (id, enabled, position, created_at, updated_at)
+            VALUES ($1, COALESCE($2, 1), COALESCE($3, 'cursor'), $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(enabled)
+        .bind(position)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        info!("Created new overlay config");
+        Ok(config)
+    }
+
+    // Dictation command configuration methods
+    pub async fn get_dictation_command_config(&self) -> Result<Option<DictationCommandConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, DictationCommandConfig>(
+            "SELECT * FROM dictation_command_configs ORDER BY updated_at DESC LIMIT 1"
+        )
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(config)
+    }
+
+    /// commands_json 为 None 时恢复默认命令表
+    pub async fn save_dictation_command_config(
+        &self,
+        enabled: bool,
+        commands_json: Option<&str>,
+    ) -> Result<DictationCommandConfig, sqlx::Error> {
+        let now = Utc::now();
+
+        let update_result = sqlx::query_as::<_, DictationCommandConfig>(
+            r#"
+            UPDATE dictation_command_configs
+            SET enabled = $1, commands_json = $2, updated_at = $3
+            WHERE id = (SELECT id FROM dictation_command_configs ORDER BY updated_at DESC LIMIT 1)
+            RETURNING *
+            "#
+        )
+        .bind(enabled)
+        .bind(commands_json)
+        .bind(now)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        if let Some(config) = update_result {
+            info!("Updated dictation command config");
+            return Ok(config);
+        }
+
+        let id = Uuid::new_v4().to_string();
+        let config = sqlx::query_as::<_, DictationCommandConfig>(
+            r#"
+            INSERT INTO dictation_command_configs (id, enabled, commands_json, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(enabled)
+        .bind(commands_json)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        info!("Created new dictation command config");
+        Ok(config)
+    }
+
+    // Transcription replacement methods
+    pub async fn get_replacements(&self) -> Result<Vec<TranscriptionReplacement>, sqlx::Error> {
+        let replacements = sqlx::query_as::<_, TranscriptionReplacement>(
+            "SELECT * FROM transcription_replacements ORDER BY created_at ASC"
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+
+        Ok(replacements)
+    }
+
+    pub async fn add_replacement(
+        &self,
+        pattern: &str,
+        replacement: &str,
+        is_regex: bool,
+    ) -> Result<TranscriptionReplacement, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let record = sqlx::query_as::<_, TranscriptionReplacement>(
+            r#"
+            INSERT INTO transcription_replacements (id, pattern, replacement, is_regex, created_at)
+            VALUES ($1, $2, $3, $4, $5)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(pattern)
+        .bind(replacement)
+        .bind(is_regex)
+        .bind(now)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        info!("Added transcription replacement: {}", id);
+        Ok(record)
+    }
+
+    pub async fn delete_replacement(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM transcription_replacements WHERE id = $1")
+            .bind(id)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Output profile methods
+    pub async fn get_output_profiles(&self) -> Result<Vec<OutputProfileRecord>, sqlx::Error> {
+        let profiles = sqlx::query_as::<_, OutputProfileRecord>(
+            "SELECT * FROM output_profiles ORDER BY created_at ASC"
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+
+        Ok(profiles)
+    }
+
+    pub async fn add_output_profile(
+        &self,
+        app_pattern: &str,
+        injection_method: &str,
+        trailing_text: &str,
+        capitalize_sentences: bool,
+        typing_delays_json: Option<&str>,
+    ) -> Result<OutputProfileRecord, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let record = sqlx::query_as::<_, OutputProfileRecord>(
+            r#"
+            INSERT INTO output_profiles (id, app_pattern, injection_method, trailing_text, capitalize_sentences, typing_delays_json, created_at, updated_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(app_pattern)
+        .bind(injection_method)
+        .bind(trailing_text)
+        .bind(capitalize_sentences)
+        .bind(typing_delays_json)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        info!("Added output profile: {}", id);
+        Ok(record)
+    }
+
+    /// 返回 None 表示 ID 不存在
+    pub async fn update_output_profile(
+        &self,
+        id: &str,
+        app_pattern: &str,
+        injection_method: &str,
+        trailing_text: &str,
+        capitalize_sentences: bool,
+        typing_delays_json: Option<&str>,
+    ) -> Result<Option<OutputProfileRecord>, sqlx::Error> {
+        let record = sqlx::query_as::<_, OutputProfileRecord>(
+            r#"
+            UPDATE output_profiles
+            SET app_pattern = $1, injection_method = $2, trailing_text = $3, capitalize_sentences = $4, typing_delays_json = $5, updated_at = $6
+            WHERE id = $7
+            RETURNING *
+            "#
+        )
+        .bind(app_pattern)
+        .bind(injection_method)
+        .bind(trailing_text)
+        .bind(capitalize_sentences)
+        .bind(typing_delays_json)
+        .bind(Utc::now())
+        .bind(id)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    pub async fn delete_output_profile(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let result = sqlx::query("DELETE FROM output_profiles WHERE id = $1")
+            .bind(id)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // ASR Configuration methods
+    /// 当前使用的 ASR 配置方案
+    pub async fn get_asr_config(&self) -> Result<Option<AsrConfig>, sqlx::Error> {
+        println!("🗄️ Database: get_asr_config() called");
+        println!("🔍 Database: Querying asr_configs table...");
+        
+        let config = sqlx::query_as::<_, AsrConfig>(
+            "SELECT * FROM asr_configs ORDER BY is_active DESC, updated_at DESC LIMIT 1"
+        )
+        .fetch_optional(&*self.pool)
+        .await?
+        .map(AsrConfig::decrypted);
+
+        if let Some(ref cfg) = config {
+            debug!("✅ Database: Query successful, found ASR config:");
+            debug!("  - ID: {}", cfg.id);
+            debug!("  - Service Provider: {}", cfg.service_provider);
+            debug!("  - Local Endpoint: {:?}", cfg.local_endpoint);
+            debug!("  - Local API Key: {}", cfg.local_api_key.is_some());
+            debug!("  - Cloud Endpoint: {:?}", cfg.cloud_endpoint);
+            debug!("  - Cloud API Key: {}", cfg.cloud_api_key.is_some());
+            debug!("  - Whisper Model: {:?}", cfg.whisper_model);
+            debug!("  - Enable VAD: {}", cfg.enable_vad);
+            debug!("  - Cloud provider: {}", cfg.cloud_provider);
+            debug!("  - Created At: {}", cfg.created_at);
+            debug!("  - Updated At: {}", cfg.updated_at);
+        } else {
+            debug!("📥 Database: Query successful, but no ASR config found");
+        }
+
+        Ok(config)
+    }
+
+    pub async fn save_asr_config(
+        &self,
+        service_provider: &str,
+        local_endpoint: Option<&str>,
+        local_api_key: Option<&str>,
+        cloud_endpoint: Option<&str>,
+        cloud_api_key: Option<&str>,
+        whisper_model: Option<&str>,
+        enable_vad: Option<bool>,
+        cloud_provider: Option<&str>,
+        cloud_model: Option<&str>,
+        cloud_language: Option<&str>,
+    ) -> Result<AsrConfig, sqlx::Error> {
+        let now = Utc::now();
+
+        // 前端拿到的是脱敏 key，原样回传时沿用已保存的值
+        let existing = self.get_asr_config().await?;
+        let local_api_key = resolve_masked_key(local_api_key, existing.as_ref().and_then(|c| c.local_api_key.as_deref()))?;
+        let cloud_api_key = resolve_masked_key(cloud_api_key, existing.as_ref().and_then(|c| c.cloud_api_key.as_deref()))?;
+        let encrypted_local_key = encrypt_field(local_api_key)?;
+        let encrypted_cloud_key = encrypt_field(cloud_api_key)?;
+
+        // First, try to update existing record
+        let update_result = sqlx::query_as::<_, AsrConfig>(
+            r#"
+            UPDATE asr_configs
+            SET service_provider = $1,
+                local_endpoint = $2,
+                local_api_key = $3,
+                cloud_endpoint = $4,
+                cloud_api_key = $5,
+                whisper_model = $6,
+                updated_at = $7,
+                enable_vad = COALESCE($8, enable_vad),
+                cloud_provider = COALESCE($9, cloud_provider),
+                cloud_model = COALESCE($10, cloud_model),
+                cloud_language = COALESCE($11, cloud_language)
+            WHERE id = (SELECT id FROM asr_configs ORDER BY is_active DESC, updated_at DESC LIMIT 1)
+            RETURNING *
+            "#
+        )
+        .bind(service_provider)
+        .bind(local_endpoint)
+        .bind(&encrypted_local_key)
+        .bind(cloud_endpoint)
+        .bind(&encrypted_cloud_key)
+        .bind(whisper_model)
+        .bind(now)
+        .bind(enable_vad)
+        .bind(cloud_provider)
+        .bind(cloud_model)
+        .bind(cloud_language)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        if let Some(config) = update_result {
+            info!("Updated ASR config for provider: {}", service_provider);
+            println!("✅ Database: Updated existing ASR config with whisper model: {:?}", whisper_model);
+            Ok(config.decrypted())
+        } else {
+            // If no existing record, insert new one
+            println!("⚠️ Database: No existing record found, creating new one...");
+            let id = Uuid::new_v4().to_string();
+            println!("🆔 Database: New record ID: {}", id);
+
+            let config = sqlx::query_as::<_, AsrConfig>(
+                r#"
+                INSERT INTO asr_configs (id, service_provider, local_endpoint, local_api_key, cloud_endpoint, cloud_api_key, whisper_model, created_at, updated_at, enable_vad, cloud_provider, cloud_model, cloud_language, is_active)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, COALESCE($11, 'custom'), $12, $13, 1)
+                RETURNING *
+                "#
+            )
+            .bind(&id)
+            .bind(service_provider)
+            .bind(local_endpoint)
+            .bind(&encrypted_local_key)
+            .bind(cloud_endpoint)
+            .bind(&encrypted_cloud_key)
+            .bind(whisper_model)
+            .bind(now)
+            .bind(now)
+            .bind(enable_vad.unwrap_or(false))
+            .bind(cloud_provider)
+            .bind(cloud_model)
+            .bind(cloud_language)
+            .fetch_one(&*self.pool)
+            .await?;
+
+            info!("Created new ASR config for provider: {}", service_provider);
+            Ok(config.decrypted())
+        }
+    }
+
+    /// 保存 ASR 备用处理器顺序（JSON 数组），None 恢复默认顺序；没有 ASR 配置时返回 None
+    pub async fn save_asr_fallback_chain(&self, chain_json: Option<&str>) -> Result<Option<AsrConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, AsrConfig>(
+            r#"
+            UPDATE asr_configs
+            SET fallback_chain = $1,
+                updated_at = $2
+            WHERE id = (SELECT id FROM asr_configs ORDER BY is_active DESC, updated_at DESC LIMIT 1)
+            RETURNING *
+            "#
+        )
+        .bind(chain_json)
+        .bind(Utc::now())
+        .fetch_optional(&*self.pool)
+        .await?
+        .map(AsrConfig::decrypted);
+
+        if config.is_some() {
+            info!("Saved ASR fallback chain: {:?}", chain_json);
+        }
+        Ok(config)
+    }
+
+    /// 所有 ASR 配置方案，按创建时间排序
+    pub async fn list_asr_profiles(&self) -> Result<Vec<AsrConfig>, sqlx::Error> {
+        let profiles = sqlx::query_as::<_, AsrConfig>(
+            "SELECT * FROM asr_configs ORDER BY created_at ASC, id ASC"
+        )
+        .fetch_all(&*self.pool)
+        .await?
+        .into_iter()
+        .map(AsrConfig::decrypted)
+        .collect();
+
+        Ok(profiles)
+    }
+
+    pub async fn asr_profile_name_exists(&self, name: &str) -> Result<bool, sqlx::Error> {
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM asr_configs WHERE name = $1 COLLATE NOCASE"
+        )
+        .bind(name)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        Ok(count > 0)
+    }
+
+    /// 以当前方案为模板新建一个（未激活的）方案；还没有任何配置时使用本地 Whisper 的默认设置
+    pub async fn create_asr_profile(&self, name: &str) -> Result<AsrConfig, sqlx::Error> {
+        let now = Utc::now();
+        let id = Uuid::new_v4().to_string();
+
+        let copied = sqlx::query_as::<_, AsrConfig>(
+            r#"
+            INSERT INTO asr_configs (id, service_provider, local_endpoint, local_api_key, cloud_endpoint, cloud_api_key, whisper_model, created_at, updated_at, enable_vad, cloud_provider, cloud_model, cloud_language, fallback_chain, name, is_active)
+            SELECT $1, service_provider, local_endpoint, local_api_key, cloud_endpoint, cloud_api_key, whisper_model, $2, $2, enable_vad, cloud_provider, cloud_model, cloud_language, fallback_chain, $3, 0
+            FROM asr_configs
+            ORDER BY is_active DESC, updated_at DESC
+            LIMIT 1
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(now)
+        .bind(name)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        let profile = match copied {
+            Some(profile) => profile,
+            None => {
+                // 第一个方案直接设为当前方案
+                sqlx::query_as::<_, AsrConfig>(
+                    r#"
+                    INSERT INTO asr_configs (id, service_provider, created_at, updated_at, name, is_active)
+                    VALUES ($1, 'whisper-rs', $2, $2, $3, 1)
+                    RETURNING *
+                    "#
+                )
+                .bind(&id)
+                .bind(now)
+                .bind(name)
+                .fetch_one(&*self.pool)
+                .await?
+            }
+        };
+
+        info!("Created ASR profile: {}", name);
+        Ok(profile.decrypted())
+    }
+
+    /// 切换当前方案；方案不存在时返回 None，原来的方案保持不变
+    pub async fn activate_asr_profile(&self, id: &str) -> Result<Option<AsrConfig>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let exists: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM asr_configs WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        if exists == 0 {
+            return Ok(None);
+        }
+
+        sqlx::query("UPDATE asr_configs SET is_active = (id = $1)")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        let profile = sqlx::query_as::<_, AsrConfig>("SELECT * FROM asr_configs WHERE id = $1")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+        tx.commit().await?;
+
+        info!("Activated ASR profile: {}", profile.name);
+        Ok(Some(profile.decrypted()))
+    }
+
+    /// 删除方案；删除的是当前方案时改用最近修改的其他方案
+    pub async fn delete_asr_profile(&self, id: &str) -> Result<bool, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+
+        let result = sqlx::query("DELETE FROM asr_configs WHERE id = $1")
+            .bind(id)
+            .execute(&mut *tx)
+            .await?;
+        sqlx::query(
+            r#"
+            UPDATE asr_configs SET is_active = 1
+            WHERE id = (SELECT id FROM asr_configs ORDER BY updated_at DESC LIMIT 1)
+              AND NOT EXISTS (SELECT 1 FROM asr_configs WHERE is_active = 1)
+            "#
+        )
+        .execute(&mut *tx)
+        .await?;
+        tx.commit().await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // Translation Configuration methods
+    pub async fn get_translation_config(&self, provider: &str) -> Result<Option<TranslationConfig>, sqlx::Error> {
+        let config = sqlx::query_as::<_, TranslationConfig>(
+            "SELECT * FROM translation_configs WHERE provider = $1 ORDER BY updated_at DESC LIMIT 1"
+        )
+        .bind(provider)
+        .fetch_optional(&*self.pool)
+        .await?
+        .map(TranslationConfig::decrypted);
+
+        Ok(config)
+    }
+
+    pub async fn save_translation_config(
+        &self,
+        provider: &str,
+        api_key: Option<&str>,
+        endpoint: Option<&str>,
+        target_language: Option<&str>,
+        source_language: Option<&str>,
+        model: Option<&str>,
+        timeout_secs: Option<i64>,
+    ) -> Result<TranslationConfig, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let existing = self.get_translation_config(provider).await?;
+        let api_key = resolve_masked_key(api_key, existing.as_ref().and_then(|c| c.api_key.as_deref()))?;
+        let encrypted_api_key = encrypt_field(api_key)?;
+        // 没有指定超时时沿用之前保存的值
+        let timeout_secs = timeout_secs
+            .or_else(|| existing.as_ref().map(|c| c.timeout_secs))
+            .unwrap_or_else(default_translate_timeout_secs);
+
+        let config = sqlx::query_as::<_, TranslationConfig>(
+            r#"
+            INSERT INTO translation_configs (id, provider, api_key, endpoint, created_at, updated_at, target_language, source_language, model, timeout_secs)
+            VALUES ($1, $2, $3, $4, $5, $6, COALESCE($7, 'en'), $8, $9, $10)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(provider)
+        .bind(&encrypted_api_key)
+        .bind(endpoint)
+        .bind(now)
+        .bind(now)
+        .bind(target_language)
+        .bind(source_language)
+        .bind(model)
+        .bind(timeout_secs)
+        .fetch_one(&*self.pool)
+        .await?;
+
+        info!("Saved translation config for provider: {}", provider);
+        Ok(config.decrypted())
+    }
+
+    // History methods
+    pub async fn add_history_record(&self, record: NewHistoryRecord) -> Result<HistoryRecord, sqlx::Error> {
+        self.add_history_record_with_latency(record, None).await
+    }
+
+    /// 同 `add_history_record`，额外记录分阶段耗时
+    pub async fn add_history_record_with_latency(&self, record: NewHistoryRecord, breakdown: Option<LatencyBreakdown>) -> Result<HistoryRecord, sqlx::Error> {
+        let mut saved = self.add_history_records(vec![(record, breakdown)]).await?;
+        Ok(saved.remove(0))
+    }
+
+    /// 在一个事务中写入多条历史记录及其统计；任意一条失败时整批回滚，调用方可以原样重试
+    pub async fn add_history_records(&self, records: Vec<(NewHistoryRecord, Option<LatencyBreakdown>)>) -> Result<Vec<HistoryRecord>, sqlx::Error> {
+        let mut tx = self.pool.begin().await?;
+        let mut saved = Vec::with_capacity(records.len());
+        for (record, breakdown) in records {
+            saved.push(Self::insert_history_record(&mut tx, record, breakdown).await?);
+        }
+        tx.commit().await?;
+        Ok(saved)
+    }
+
+    async fn insert_history_record(conn: &mut SqliteConnection, record: NewHistoryRecord, breakdown: Option<LatencyBreakdown>) -> Result<HistoryRecord, sqlx::Error> {
+        let id = Uuid::new_v4().to_string();
+        let now = Utc::now();
+
+        let history = sqlx::query_as::<_, HistoryRecord>(
+            r#"
+            INSERT INTO history_records (id, record_type, input_text, output_text, audio_file_path, processor_type, processing_time_ms, success, error_message, created_at, audio_duration_ms, translation_skipped, detected_language, confidence, segments_json)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+            RETURNING *
+            "#
+        )
+        .bind(&id)
+        .bind(&record.record_type)
+        .bind(&record.input_text)
+        .bind(&record.output_text)
+        .bind(&record.audio_file_path)
+        .bind(&record.processor_type)
+        .bind(record.processing_time_ms)
+        .bind(record.success)
+        .bind(&record.error_message)
+        .bind(now)
+        .bind(record.audio_duration_ms)
+        .bind(record.translation_skipped)
+        .bind(&record.detected_language)
+        .bind(record.confidence)
+        .bind(&record.segments_json)
+        .fetch_one(&mut *conn)
+        .await?;
+
+        // 跳过的空录音没有调用识别服务，不计入服务统计、延迟和用量
+        if record.record_type == "skipped" {
+            return Ok(history);
+        }
+
+        // 成功和失败都计入统计，失败时延迟为出错前的耗时
+        Self::update_service_stats_from_record(conn, &record, now).await?;
+        Self::update_latency_from_record(conn, &record, breakdown.unwrap_or_default(), now).await?;
+        Self::update_usage_from_record(conn, &record, now).await?;
+
+        Ok(history)
+    }
+
+    // Helper function to update service stats from a new history record
+    async fn update_service_stats_from_record(conn: &mut SqliteConnection, record: &NewHistoryRecord, timestamp: chrono::DateTime<chrono::Utc>) -> Result<(), sqlx::Error> {
+        let service_name = service_name_for_processor(record.processor_type.as_deref()).unwrap_or("unknown_service");
+
+        let status = if record.success { "online" } else { "error" };
+        
+        Self::upsert_service_status(conn, service_name, status, None).await?;
+
+        let (successful, failed) = if record.success { (1i64, 0i64) } else { (0, 1) };
+        sqlx::query(
+            r#"
+            UPDATE service_stats SET
+                total_requests = total_requests + 1,
+                successful_requests = successful_requests + ?1,
+                failed_requests = failed_requests + ?2,
+                last_success_at = CASE WHEN ?1 = 1 THEN ?4 ELSE last_success_at END,
+                last_error_at = CASE WHEN ?2 = 1 THEN ?4 ELSE last_error_at END,
+                last_error = CASE WHEN ?2 = 1 THEN ?5 ELSE last_error END
+            WHERE service_name = ?3
+            "#
+        )
+        .bind(successful)
+        .bind(failed)
+        .bind(service_name)
+        .bind(timestamp)
+        .bind(&record.error_message)
+        .execute(&mut *conn)
+        .await?;
+        Ok(())
+    }
+
+    // Helper function to update latency from a new history record
+    async fn update_latency_from_record(conn: &mut SqliteConnection, record: &NewHistoryRecord, breakdown: LatencyBreakdown, timestamp: chrono::DateTime<chrono::Utc>) -> Result<(), sqlx::Error> {
+        // 与服务状态使用同一份映射，延迟页面和状态页面的服务名才能对上
+        let service_name = service_name_for_processor(record.processor_type.as_deref()).unwrap_or("local_asr");
+
+        // Insert latency record
+        let id = Uuid::new_v4().to_string();
+        sqlx::query(
+            r#"
+            INSERT INTO latency_records (id, service_name, latency_ms, request_type, recorded_at, record_ms, wav_ms, asr_ms, postprocess_ms, typing_ms, attempts, race_local_ms, race_cloud_ms, translate_ms)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#
+        )
+        .bind(&id)
+        .bind(service_name)
+        .bind(record.processing_time_ms.unwrap_or(0))
+        .bind(&record.record_type)
+        .bind(timestamp)
+        .bind(breakdown.record_ms)
+        .bind(breakdown.wav_ms)
+        .bind(breakdown.asr_ms)
+        .bind(breakdown.postprocess_ms)
+        .bind(breakdown.typing_ms)
+        .bind(breakdown.attempts)
+        .bind(breakdown.race_local_ms)
+        .bind(breakdown.race_cloud_ms)
+        .bind(breakdown.translate_ms)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    // Helper function to update usage from a new history record
+    async fn update_usage_from_record(conn: &mut SqliteConnection, record: &NewHistoryRecord, timestamp: chrono::DateTime<chrono::Utc>) -> Result<(), sqlx::Error> {
+        // 按实际录音时长统计，处理耗时只计入延迟统计；没有录音时长的记录（如文件转录）只计次数
+        let audio_ms = record.audio_duration_ms.unwrap_or(0).max(0);
+
+        // Update or insert today's usage record
+        // ON CONFLICT 只累加计数，保留当天首条记录的 id 和 created_at
+        let today = timestamp.format("%Y-%m-%d").to_string();
+        let id = Uuid::new_v4().to_string();
+        let (successful, failed) = if record.success { (1, 0) } else { (0, 1) };
+
+        sqlx::query(
+            r#"
+            INSERT INTO usage_logs (id, date, total_seconds, total_audio_ms, total_requests, successful_requests, failed_requests, created_at, updated_at)
+            VALUES ($1, $2, $3 / 1000, $3, 1, $4, $5, $6, $6)
+            ON CONFLICT(date) DO UPDATE SET
+                total_audio_ms = total_audio_ms + excluded.total_audio_ms,
+                total_seconds = (total_audio_ms + excluded.total_audio_ms) / 1000,
+                total_requests = total_requests + 1,
+                successful_requests = successful_requests + excluded.successful_requests,
+                failed_requests = failed_requests + excluded.failed_requests,
+                updated_at = excluded.updated_at
+            "#
+        )
+        .bind(&id)
+        .bind(&today)
+        .bind(audio_ms)
+        .bind(successful)
+        .bind(failed)
+        .bind(timestamp)
+        .execute(&mut *conn)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn get_history_records(
+        &self,
+        limit: Option<i64>,
+        record_type: Option<&str>,
+    ) -> Result<Vec<HistoryRecord>, sqlx::Error> {
+        let filter = HistoryQuery {
+            record_type: record_type.map(|t| t.to_string()),
+            limit,
+            ..Default::default()
+        };
+
+        Ok(self.search_history_records(&filter).await?.records)
+    }
+
+    /// Search history with optional full-text query, filters and pagination.
+    /// All user input goes through bound parameters.
+    pub async fn search_history_records(&self, filter: &HistoryQuery) -> Result<HistoryPage, sqlx::Error> {
+        filter.validate().map_err(sqlx::Error::Protocol)?;
+
+        let use_fts = sqlx::query_scalar::<_, bool>(
+            "SELECT COUNT(*) > 0 FROM sqlite_master WHERE type = 'table' AND name = 'history_fts'"
+        )
+        .fetch_one(&*self.pool)
+        .await
+        .unwrap_or(false);
+
+        let mut count_builder = QueryBuilder::<Sqlite>::new("SELECT COUNT(*) FROM history_records");
+        push_history_filters(&mut count_builder, filter, use_fts);
+        let total_count: i64 = count_builder
+            .build_query_scalar()
+            .fetch_one(&*self.pool)
+            .await?;
+
+        let offset = filter.offset.unwrap_or(0).max(0);
+        let mut builder = QueryBuilder::<Sqlite>::new("SELECT * FROM history_records");
+        push_history_filters(&mut builder, filter, use_fts);
+        builder.push(" ORDER BY created_at DESC LIMIT ");
+        // SQLite treats a negative LIMIT as "no limit"
+        builder.push_bind(filter.limit.unwrap_or(-1));
+        builder.push(" OFFSET ");
+        builder.push_bind(offset);
+
+        let records = builder
+            .build_query_as::<HistoryRecord>()
+            .fetch_all(&*self.pool)
+            .await?;
+
+        Ok(HistoryPage {
+            records,
+            total_count,
+            limit: filter.limit,
+            offset,
+        })
+    }
+
+    /// 按 rowid 顺序分批读取历史记录，供导出时流式写入，返回 (rowid, record)
+    pub async fn get_history_batch(&self, after_rowid: i64, batch_size: i64) -> Result<Vec<(i64, HistoryRecord)>, sqlx::Error> {
+        use sqlx::{FromRow, Row};
+
+        let rows = sqlx::query(
+            "SELECT rowid AS row_id, * FROM history_records WHERE rowid > $1 ORDER BY rowid ASC LIMIT $2"
+        )
+        .bind(after_rowid)
+        .bind(batch_size)
+        .fetch_all(&*self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| Ok((row.try_get::<i64, _>("row_id")?, HistoryRecord::from_row(row)?)))
+            .collect()
+    }
+
+    pub async fn get_history_record(&self, id: &str) -> Result<Option<HistoryRecord>, sqlx::Error> {
+        let record = sqlx::query_as::<_, HistoryRecord>(
+            "SELECT * FROM history_records WHERE id = $1"
+        )
+        .bind(id)
+        .fetch_optional(&*self.pool)
+        .await?;
+
+        Ok(record)
+    }
+
+    pub async fn clear_history_audio_path(&self, id: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE history_records SET audio_file_path = NULL WHERE id = $1")
+            .bind(id)
+            .execute(&*self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Null out audio_file_path for records whose WAV file no longer exists
+    pub async fn cleanup_missing_audio_paths(&self) -> Result<u64, sqlx::Error> {
+        let records = sqlx::query_as::<_, HistoryRecord>(
+            "SELECT * FROM history_records WHERE audio_file_path IS NOT NULL"
+        )
+        .fetch_all(&*self.pool)
+        .await?;
+
+        let mut cleared = 0;
+        for record in records {
+            if let Some(ref path) = record.audio_file_path {
+                if !std::path::Path::new(path).exists() {
+                    self.clear_history_audio_path(&record.id).await?;
+                    cleared += 1;
+                }
+            }
+        }
+
+        info!("Cleared {} dangling audio paths", cleared);
+        Ok(cleared)
+    }
+
+    pub async fn get_history_stats(&self) -> Result<(i64, i64, i64), sqlx::Error> {
+        let total_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM history_records")
+            .fetch_one(&*self.pool)
+            .await?;
+
+        let success_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM history_records WHERE success = true")
+            .fetch_one(&*self.pool)
+            .await?;
+
+        let transcribe_count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM history_records WHERE record_type = 'transcribe'")
+            .fetch_one(&*self.pool)
+            .await?;
+
+        Ok((total_count, success_count, transcribe_count))
+    }
+
+    // Utility methods
     pub async fn cleanup_old_records(&self, days: i64) -> Result<u64, sqlx::Error> {
         let cutoff_date = Utc::now() - chrono::Duration::days(days);
 
-        let result = sqlx::query(
-            "DELETE FROM history_records WHERE created_at < $1"
-        )
-        .bind(cutoff_date)
-        .execute(&*self.pool)
-        .await?;
+        let result = sqlx::query(
+            "DELETE FROM history_records WHERE created_at < $1"
+        )
+        .bind(cutoff_date)
+        .execute(&*self.pool)
+        .await?;
+
+        let deleted_count = result.rows_affected();
+        info!("Cleaned up {} old records older than {} days", deleted_count, days);
+
+        Ok(deleted_count)
+    }
+
+    // Backup methods
+    pub async fn export_backup(&self, include_history: bool, include_api_keys: bool) -> Result<AppBackup, sqlx::Error> {
+        // 备份文件可在其他机器上恢复，导出明文 key（或按选项脱敏）
+        let asr_configs = sqlx::query_as::<_, AsrConfig>("SELECT * FROM asr_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?
+            .into_iter()
+            .map(AsrConfig::decrypted)
+            .collect();
+        let translation_configs = sqlx::query_as::<_, TranslationConfig>("SELECT * FROM translation_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?
+            .into_iter()
+            .map(TranslationConfig::decrypted)
+            .collect();
+        let hotkey_configs = sqlx::query_as::<_, HotkeyConfig>("SELECT * FROM hotkey_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?;
+        let postprocess_configs = sqlx::query_as::<_, PostProcessConfig>("SELECT * FROM postprocess_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?;
+        let replacements = self.get_replacements().await?;
+        let audio_prep_configs = sqlx::query_as::<_, AudioPrepConfig>("SELECT * FROM audio_prep_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?;
+        let network_retry_configs = sqlx::query_as::<_, NetworkRetryConfig>("SELECT * FROM network_retry_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?;
+        let overlay_configs = sqlx::query_as::<_, OverlayConfig>("SELECT * FROM overlay_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?;
+        let dictation_command_configs = sqlx::query_as::<_, DictationCommandConfig>("SELECT * FROM dictation_command_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?;
+        let output_profiles = self.get_output_profiles().await?;
+        let recording_retention_configs = sqlx::query_as::<_, RecordingRetentionConfig>("SELECT * FROM recording_retention_configs ORDER BY updated_at ASC")
+            .fetch_all(&*self.pool)
+            .await?;
+        let app_settings = self.get_app_settings().await?;
+
+        let history_records = if include_history {
+            Some(
+                sqlx::query_as::<_, HistoryRecord>("SELECT * FROM history_records ORDER BY created_at ASC")
+                    .fetch_all(&*self.pool)
+                    .await?
+            )
+        } else {
+            None
+        };
+
+        let mut backup = AppBackup {
+            version: BACKUP_FORMAT_VERSION,
+            app_version: env!("CARGO_PKG_VERSION").to_string(),
+            exported_at: Utc::now(),
+            api_keys_included: true,
+            asr_configs,
+            translation_configs,
+            hotkey_configs,
+            postprocess_configs,
+            replacements,
+            audio_prep_configs,
+            network_retry_configs,
+            overlay_configs,
+            dictation_command_configs,
+            output_profiles,
+            recording_retention_configs,
+            app_settings,
+            history_records,
+        };
+
+        if !include_api_keys {
+            backup.redact_api_keys();
+        }
+
+        Ok(backup)
+    }
+
+    /// 在单个事务中导入备份：配置按 ID upsert，历史记录跳过已存在的 ID。
+    /// 任何一步失败都会回滚，不会留下部分导入的数据。
+    pub async fn import_backup(&self, backup: &AppBackup) -> Result<BackupImportSummary, sqlx::Error> {
+        let mut summary = BackupImportSummary::default();
+        let now = Utc::now();
+        let mut tx = self.pool.begin().await?;
+
+        // 配置都是"取最新 updated_at"，整体平移时间戳让导入的配置生效，同时保留相对顺序
+        let asr_shift = restore_time_shift(backup.asr_configs.iter().map(|c| c.updated_at), now);
+        let translation_shift = restore_time_shift(backup.translation_configs.iter().map(|c| c.updated_at), now);
+        let hotkey_shift = restore_time_shift(backup.hotkey_configs.iter().map(|c| c.updated_at), now);
+        let postprocess_shift = restore_time_shift(backup.postprocess_configs.iter().map(|c| c.updated_at), now);
+        let audio_prep_shift = restore_time_shift(backup.audio_prep_configs.iter().map(|c| c.updated_at), now);
+        let network_retry_shift = restore_time_shift(backup.network_retry_configs.iter().map(|c| c.updated_at), now);
+        let overlay_shift = restore_time_shift(backup.overlay_configs.iter().map(|c| c.updated_at), now);
+        let dictation_shift = restore_time_shift(backup.dictation_command_configs.iter().map(|c| c.updated_at), now);
+        let retention_shift = restore_time_shift(backup.recording_retention_configs.iter().map(|c| c.updated_at), now);
+
+        // 备份中的当前方案成为当前方案；旧版备份没有方案信息，使用其中最新的一行
+        let active_asr_id = backup.asr_configs.iter()
+            .find(|c| c.is_active)
+            .or_else(|| backup.asr_configs.iter().max_by_key(|c| c.updated_at))
+            .map(|c| c.id.clone());
+        if active_asr_id.is_some() {
+            sqlx::query("UPDATE asr_configs SET is_active = 0")
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        // 脱敏的备份不会覆盖本地已有的 API key
+        for config in &backup.asr_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO asr_configs (id, service_provider, local_endpoint, local_api_key, cloud_endpoint, cloud_api_key, whisper_model, created_at, updated_at, enable_vad, cloud_provider, cloud_model, cloud_language, fallback_chain, name, is_active)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16)
+                ON CONFLICT(id) DO UPDATE SET
+                    service_provider = excluded.service_provider,
+                    local_endpoint = excluded.local_endpoint,
+                    local_api_key = COALESCE(excluded.local_api_key, asr_configs.local_api_key),
+                    cloud_endpoint = excluded.cloud_endpoint,
+                    cloud_api_key = COALESCE(excluded.cloud_api_key, asr_configs.cloud_api_key),
+                    whisper_model = excluded.whisper_model,
+                    updated_at = excluded.updated_at,
+                    enable_vad = excluded.enable_vad,
+                    cloud_provider = excluded.cloud_provider,
+                    cloud_model = excluded.cloud_model,
+                    cloud_language = excluded.cloud_language,
+                    fallback_chain = excluded.fallback_chain,
+                    name = excluded.name,
+                    is_active = excluded.is_active
+                "#
+            )
+            .bind(&config.id)
+            .bind(&config.service_provider)
+            .bind(&config.local_endpoint)
+            .bind(encrypt_field(config.local_api_key.as_deref())?)
+            .bind(&config.cloud_endpoint)
+            .bind(encrypt_field(config.cloud_api_key.as_deref())?)
+            .bind(&config.whisper_model)
+            .bind(config.created_at)
+            .bind(config.updated_at + asr_shift)
+            .bind(config.enable_vad)
+            .bind(&config.cloud_provider)
+            .bind(&config.cloud_model)
+            .bind(&config.cloud_language)
+            .bind(&config.fallback_chain)
+            .bind(&config.name)
+            .bind(active_asr_id.as_deref() == Some(config.id.as_str()))
+            .execute(&mut *tx)
+            .await?;
+            summary.asr_configs += 1;
+        }
+
+        for config in &backup.translation_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO translation_configs (id, provider, api_key, endpoint, created_at, updated_at, target_language, source_language, model, timeout_secs)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10)
+                ON CONFLICT(id) DO UPDATE SET
+                    provider = excluded.provider,
+                    api_key = COALESCE(excluded.api_key, translation_configs.api_key),
+                    endpoint = excluded.endpoint,
+                    updated_at = excluded.updated_at,
+                    target_language = excluded.target_language,
+                    source_language = excluded.source_language,
+                    model = excluded.model,
+                    timeout_secs = excluded.timeout_secs
+                "#
+            )
+            .bind(&config.id)
+            .bind(&config.provider)
+            .bind(encrypt_field(config.api_key.as_deref())?)
+            .bind(&config.endpoint)
+            .bind(config.created_at)
+            .bind(config.updated_at + translation_shift)
+            .bind(&config.target_language)
+            .bind(&config.source_language)
+            .bind(&config.model)
+            .bind(config.timeout_secs)
+            .execute(&mut *tx)
+            .await?;
+            summary.translation_configs += 1;
+        }
+
+        for config in &backup.hotkey_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO hotkey_configs (id, transcribe_key, translate_key, trigger_delay_ms, anti_mistouch_enabled, save_wav_files, clipboard_update_ms, keyboard_events_settle_ms, typing_complete_ms, character_interval_ms, short_operation_ms, created_at, updated_at, text_injection_method, hotkey_backend, recording_mode, max_recording_secs, silence_auto_stop_enabled, silence_auto_stop_ms, preroll_ms, queued_to_clipboard, cycle_profile_key)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18, $19, $20, $21, $22)
+                ON CONFLICT(id) DO UPDATE SET
+                    transcribe_key = excluded.transcribe_key,
+                    translate_key = excluded.translate_key,
+                    trigger_delay_ms = excluded.trigger_delay_ms,
+                    anti_mistouch_enabled = excluded.anti_mistouch_enabled,
+                    save_wav_files = excluded.save_wav_files,
+                    clipboard_update_ms = excluded.clipboard_update_ms,
+                    keyboard_events_settle_ms = excluded.keyboard_events_settle_ms,
+                    typing_complete_ms = excluded.typing_complete_ms,
+                    character_interval_ms = excluded.character_interval_ms,
+                    short_operation_ms = excluded.short_operation_ms,
+                    updated_at = excluded.updated_at,
+                    text_injection_method = excluded.text_injection_method,
+                    hotkey_backend = excluded.hotkey_backend,
+                    recording_mode = excluded.recording_mode,
+                    max_recording_secs = excluded.max_recording_secs,
+                    silence_auto_stop_enabled = excluded.silence_auto_stop_enabled,
+                    silence_auto_stop_ms = excluded.silence_auto_stop_ms,
+                    preroll_ms = excluded.preroll_ms,
+                    queued_to_clipboard = excluded.queued_to_clipboard,
+                    cycle_profile_key = excluded.cycle_profile_key
+                "#
+            )
+            .bind(&config.id)
+            .bind(&config.transcribe_key)
+            .bind(&config.translate_key)
+            .bind(config.trigger_delay_ms)
+            .bind(config.anti_mistouch_enabled)
+            .bind(config.save_wav_files)
+            .bind(config.clipboard_update_ms)
+            .bind(config.keyboard_events_settle_ms)
+            .bind(config.typing_complete_ms)
+            .bind(config.character_interval_ms)
+            .bind(config.short_operation_ms)
+            .bind(config.created_at)
+            .bind(config.updated_at + hotkey_shift)
+            .bind(&config.text_injection_method)
+            .bind(&config.hotkey_backend)
+            .bind(&config.recording_mode)
+            .bind(config.max_recording_secs)
+            .bind(config.silence_auto_stop_enabled)
+            .bind(config.silence_auto_stop_ms)
+            .bind(config.preroll_ms)
+            .bind(config.queued_to_clipboard)
+            .bind(&config.cycle_profile_key)
+            .execute(&mut *tx)
+            .await?;
+            summary.hotkey_configs += 1;
+        }
+
+        for config in &backup.postprocess_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO postprocess_configs (id, convert_to_simplified, add_symbol, optimize_result, optimize_timeout_ms, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7)
+                ON CONFLICT(id) DO UPDATE SET
+                    convert_to_simplified = excluded.convert_to_simplified,
+                    add_symbol = excluded.add_symbol,
+                    optimize_result = excluded.optimize_result,
+                    optimize_timeout_ms = excluded.optimize_timeout_ms,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&config.id)
+            .bind(config.convert_to_simplified)
+            .bind(config.add_symbol)
+            .bind(config.optimize_result)
+            .bind(config.optimize_timeout_ms)
+            .bind(config.created_at)
+            .bind(config.updated_at + postprocess_shift)
+            .execute(&mut *tx)
+            .await?;
+            summary.postprocess_configs += 1;
+        }
+
+        for rule in &backup.replacements {
+            sqlx::query(
+                r#"
+                INSERT INTO transcription_replacements (id, pattern, replacement, is_regex, created_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT(id) DO UPDATE SET
+                    pattern = excluded.pattern,
+                    replacement = excluded.replacement,
+                    is_regex = excluded.is_regex
+                "#
+            )
+            .bind(&rule.id)
+            .bind(&rule.pattern)
+            .bind(&rule.replacement)
+            .bind(rule.is_regex)
+            .bind(rule.created_at)
+            .execute(&mut *tx)
+            .await?;
+            summary.replacements += 1;
+        }
 
-        let deleted_count = result.rows_affected();
-        info!("Cleaned up {} old records older than {} days", deleted_count, days);
+        for config in &backup.audio_prep_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO audio_prep_configs (id, normalize_enabled, high_pass_enabled, noise_gate_enabled, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6)
+                ON CONFLICT(id) DO UPDATE SET
+                    normalize_enabled = excluded.normalize_enabled,
+                    high_pass_enabled = excluded.high_pass_enabled,
+                    noise_gate_enabled = excluded.noise_gate_enabled,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&config.id)
+            .bind(config.normalize_enabled)
+            .bind(config.high_pass_enabled)
+            .bind(config.noise_gate_enabled)
+            .bind(config.created_at)
+            .bind(config.updated_at + audio_prep_shift)
+            .execute(&mut *tx)
+            .await?;
+            summary.audio_prep_configs += 1;
+        }
 
-        Ok(deleted_count)
+        for config in &backup.network_retry_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO network_retry_configs (id, max_retries, time_budget_ms, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT(id) DO UPDATE SET
+                    max_retries = excluded.max_retries,
+                    time_budget_ms = excluded.time_budget_ms,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&config.id)
+            .bind(config.max_retries)
+            .bind(config.time_budget_ms)
+            .bind(config.created_at)
+            .bind(config.updated_at + network_retry_shift)
+            .execute(&mut *tx)
+            .await?;
+            summary.network_retry_configs += 1;
+        }
+
+        for config in &backup.overlay_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO overlay_configs (id, enabled, position, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT(id) DO UPDATE SET
+                    enabled = excluded.enabled,
+                    position = excluded.position,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&config.id)
+            .bind(config.enabled)
+            .bind(&config.position)
+            .bind(config.created_at)
+            .bind(config.updated_at + overlay_shift)
+            .execute(&mut *tx)
+            .await?;
+            summary.overlay_configs += 1;
+        }
+
+        for config in &backup.recording_retention_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO recording_retention_configs (id, max_age_days, max_total_mb, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT(id) DO UPDATE SET
+                    max_age_days = excluded.max_age_days,
+                    max_total_mb = excluded.max_total_mb,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&config.id)
+            .bind(config.max_age_days)
+            .bind(config.max_total_mb)
+            .bind(config.created_at)
+            .bind(config.updated_at + retention_shift)
+            .execute(&mut *tx)
+            .await?;
+            summary.recording_retention_configs += 1;
+        }
+
+        for setting in &backup.app_settings {
+            sqlx::query(
+                r#"
+                INSERT INTO app_settings (key, value, updated_at)
+                VALUES ($1, $2, $3)
+                ON CONFLICT(key) DO UPDATE SET
+                    value = excluded.value,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&setting.key)
+            .bind(app_setting_value(&setting.key, &setting.value)?)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+            summary.app_settings += 1;
+        }
+
+        for config in &backup.dictation_command_configs {
+            sqlx::query(
+                r#"
+                INSERT INTO dictation_command_configs (id, enabled, commands_json, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5)
+                ON CONFLICT(id) DO UPDATE SET
+                    enabled = excluded.enabled,
+                    commands_json = excluded.commands_json,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&config.id)
+            .bind(config.enabled)
+            .bind(&config.commands_json)
+            .bind(config.created_at)
+            .bind(config.updated_at + dictation_shift)
+            .execute(&mut *tx)
+            .await?;
+            summary.dictation_command_configs += 1;
+        }
+
+        for profile in &backup.output_profiles {
+            sqlx::query(
+                r#"
+                INSERT INTO output_profiles (id, app_pattern, injection_method, trailing_text, capitalize_sentences, typing_delays_json, created_at, updated_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT(id) DO UPDATE SET
+                    app_pattern = excluded.app_pattern,
+                    injection_method = excluded.injection_method,
+                    trailing_text = excluded.trailing_text,
+                    capitalize_sentences = excluded.capitalize_sentences,
+                    typing_delays_json = excluded.typing_delays_json,
+                    updated_at = excluded.updated_at
+                "#
+            )
+            .bind(&profile.id)
+            .bind(&profile.app_pattern)
+            .bind(&profile.injection_method)
+            .bind(&profile.trailing_text)
+            .bind(profile.capitalize_sentences)
+            .bind(&profile.typing_delays_json)
+            .bind(profile.created_at)
+            .bind(profile.updated_at)
+            .execute(&mut *tx)
+            .await?;
+            summary.output_profiles += 1;
+        }
+
+        if let Some(ref records) = backup.history_records {
+            for record in records {
+                let result = sqlx::query(
+                    r#"
+                    INSERT OR IGNORE INTO history_records (id, record_type, input_text, output_text, audio_file_path, processor_type, processing_time_ms, success, error_message, created_at, audio_duration_ms, translation_skipped, detected_language, confidence, segments_json)
+                    VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14, $15)
+                    "#
+                )
+                .bind(&record.id)
+                .bind(&record.record_type)
+                .bind(&record.input_text)
+                .bind(&record.output_text)
+                .bind(&record.audio_file_path)
+                .bind(&record.processor_type)
+                .bind(record.processing_time_ms)
+                .bind(record.success)
+                .bind(&record.error_message)
+                .bind(record.created_at)
+                .bind(record.audio_duration_ms)
+                .bind(record.translation_skipped)
+                .bind(&record.detected_language)
+                .bind(record.confidence)
+                .bind(&record.segments_json)
+                .execute(&mut *tx)
+                .await?;
+
+                if result.rows_affected() > 0 {
+                    summary.history_imported += 1;
+                } else {
+                    summary.history_skipped += 1;
+                }
+            }
+        }
+
+        tx.commit().await?;
+        info!("Imported backup: {:?}", summary);
+        Ok(summary)
     }
 
     /// Create or get a global database pool instance
@@ -916,6 +3535,20 @@ impl Database {
         Self::new().await
     }
 
+    /// 应用退出时关闭全局连接池：先把 WAL 写回主库，再等待连接归还后关闭
+    pub async fn close_global_pool() {
+        let pool = GLOBAL_DB_POOL.get().and_then(|guard| guard.lock().unwrap().take());
+        let Some(pool) = pool else {
+            return;
+        };
+
+        if let Err(e) = sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&pool).await {
+            warn!("⚠️ Database: WAL checkpoint failed before close: {}", e);
+        }
+        pool.close().await;
+        info!("🔒 Database: Global database pool closed");
+    }
+
     // Statistics methods for frontend
     pub async fn get_service_status(&self, service_name: &str) -> Result<Option<ServiceStats>, sqlx::Error> {
         let stats = sqlx::query_as::<_, ServiceStats>(
@@ -939,6 +3572,11 @@ impl Database {
     }
 
     pub async fn update_service_status(&self, service_name: &str, status: &str, endpoint: Option<String>) -> Result<(), sqlx::Error> {
+        let mut conn = self.pool.acquire().await?;
+        Self::upsert_service_status(&mut conn, service_name, status, endpoint).await
+    }
+
+    async fn upsert_service_status(conn: &mut SqliteConnection, service_name: &str, status: &str, endpoint: Option<String>) -> Result<(), sqlx::Error> {
         let now = Utc::now();
 
         let result = sqlx::query(
@@ -955,7 +3593,7 @@ impl Database {
         .bind(&endpoint)
         .bind(now)
         .bind(service_name)
-        .execute(&*self.pool)
+        .execute(&mut *conn)
         .await?;
 
         // If no rows were affected, create a new service stats record
@@ -981,7 +3619,7 @@ impl Database {
             .bind(0i64)
             .bind(now)
             .bind(now)
-            .execute(&*self.pool)
+            .execute(&mut *conn)
             .await?;
         }
 
@@ -1006,6 +3644,43 @@ impl Database {
         Ok(records)
     }
 
+    pub async fn save_benchmark_result(&self, result: &BenchmarkResult) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"
+            INSERT INTO benchmark_results (id, model_name, model_path, backend, app_version, load_ms, inference_ms, audio_ms, audio_source, realtime_factor, peak_rss_mb, success, error_message, created_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+            "#
+        )
+        .bind(&result.id)
+        .bind(&result.model_name)
+        .bind(&result.model_path)
+        .bind(&result.backend)
+        .bind(&result.app_version)
+        .bind(result.load_ms)
+        .bind(result.inference_ms)
+        .bind(result.audio_ms)
+        .bind(&result.audio_source)
+        .bind(result.realtime_factor)
+        .bind(result.peak_rss_mb)
+        .bind(result.success)
+        .bind(&result.error_message)
+        .bind(result.created_at)
+        .execute(&*self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// 最近的基准测试结果，新的在前
+    pub async fn get_benchmark_results(&self, limit: i64) -> Result<Vec<BenchmarkResult>, sqlx::Error> {
+        sqlx::query_as::<_, BenchmarkResult>(
+            "SELECT * FROM benchmark_results ORDER BY created_at DESC LIMIT ?"
+        )
+        .bind(limit)
+        .fetch_all(&*self.pool)
+        .await
+    }
+
     pub async fn get_usage_data(&self, date: &str) -> Result<Option<UsageLog>, sqlx::Error> {
         let usage = sqlx::query_as::<_, UsageLog>(
             "SELECT * FROM usage_logs WHERE date = ?"
@@ -1021,6 +3696,164 @@ impl Database {
         let today = Utc::now().format("%Y-%m-%d").to_string();
         self.get_usage_data(&today).await
     }
+
+    /// 按本地日期/周、处理器聚合历史记录和延迟记录
+    pub async fn get_usage_report(&self, query: &UsageReportQuery) -> Result<UsageReport, sqlx::Error> {
+        let (start, end) = query.utc_bounds();
+        let offset = query.offset_modifier();
+
+        let usage_sql = format!(
+            r#"
+            SELECT {period} AS period,
+                   COALESCE(processor_type, 'unknown') AS processor_type,
+                   COUNT(*) AS total_requests,
+                   COALESCE(SUM(success), 0) AS successful_requests,
+                   100.0 * COALESCE(SUM(success), 0) / COUNT(*) AS success_rate,
+                   AVG(processing_time_ms) AS avg_processing_ms,
+                   COALESCE(SUM(audio_duration_ms), 0) AS total_audio_ms
+            FROM history_records
+            WHERE created_at >= $1 AND created_at < $2
+            GROUP BY period, processor_type
+            ORDER BY period ASC, processor_type ASC
+            "#,
+            period = query.granularity.period_sql("created_at"),
+        );
+        let usage = sqlx::query_as::<_, UsageReportRow>(&usage_sql)
+            .bind(start)
+            .bind(end)
+            .bind(&offset)
+            .fetch_all(&*self.pool)
+            .await?;
+
+        let latency_sql = format!(
+            r#"
+            SELECT {period} AS period,
+                   service_name,
+                   COUNT(*) AS requests,
+                   AVG(latency_ms) AS avg_latency_ms,
+                   AVG(asr_ms) AS avg_asr_ms
+            FROM latency_records
+            WHERE recorded_at >= $1 AND recorded_at < $2
+            GROUP BY period, service_name
+            ORDER BY period ASC, service_name ASC
+            "#,
+            period = query.granularity.period_sql("recorded_at"),
+        );
+        let latency = sqlx::query_as::<_, LatencyReportRow>(&latency_sql)
+            .bind(start)
+            .bind(end)
+            .bind(&offset)
+            .fetch_all(&*self.pool)
+            .await?;
+
+        Ok(UsageReport {
+            start_date: query.start_date.format("%Y-%m-%d").to_string(),
+            end_date: query.end_date.format("%Y-%m-%d").to_string(),
+            granularity: query.granularity,
+            utc_offset_minutes: query.utc_offset_minutes,
+            usage,
+            latency,
+        })
+    }
+}
+
+fn push_history_filters(builder: &mut QueryBuilder<'_, Sqlite>, filter: &HistoryQuery, use_fts: bool) {
+    builder.push(" WHERE 1 = 1");
+
+    if let Some(ref record_type) = filter.record_type {
+        builder.push(" AND record_type = ");
+        builder.push_bind(record_type.clone());
+    }
+    if let Some(date_from) = filter.date_from {
+        builder.push(" AND created_at >= ");
+        builder.push_bind(date_from);
+    }
+    if let Some(date_to) = filter.date_to {
+        builder.push(" AND created_at <= ");
+        builder.push_bind(date_to);
+    }
+    if let Some(success) = filter.success {
+        builder.push(" AND success = ");
+        builder.push_bind(success);
+    }
+    if let Some(ref language) = filter.detected_language {
+        builder.push(" AND detected_language = ");
+        builder.push_bind(language.trim().to_lowercase());
+    }
+
+    if let Some(query) = filter.query.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        // 只用其中一种：MATCH 和 LIKE 用 OR 连接时每次都要全表扫描，索引就没有意义了
+        match fts_match_expression(query).filter(|_| use_fts) {
+            Some(match_expr) => {
+                builder.push(" AND search_id IN (SELECT rowid FROM history_fts WHERE history_fts MATCH ");
+                builder.push_bind(match_expr);
+                builder.push(")");
+            }
+            None => {
+                let like_pattern = format!("%{}%", escape_like(query));
+                builder.push(" AND (output_text LIKE ");
+                builder.push_bind(like_pattern.clone());
+                builder.push(" ESCAPE '\\' OR input_text LIKE ");
+                builder.push_bind(like_pattern);
+                builder.push(" ESCAPE '\\')");
+            }
+        }
+    }
+}
+
+/// 历史记录中的处理器标识对应的服务名，服务状态和延迟统计共用
+fn service_name_for_processor(processor_type: Option<&str>) -> Option<&'static str> {
+    match processor_type? {
+        "cloud-groq" | "whisper" => Some("whisper_asr"),
+        "cloud-sensevoice" | "sensevoice" => Some("sensevoice_asr"),
+        "cloud-openai" => Some("openai_asr"),
+        "whisper-rs" => Some("local_asr"),
+        "local" => Some("local_http_asr"),
+        "cloud" => Some("cloud_asr"),
+        "siliconflow" => Some("siliconflow_translation"),
+        "ollama" => Some("ollama_translation"),
+        _ => None,
+    }
+}
+
+/// 把备份中最新的时间戳对齐到当前时间所需的偏移量
+fn restore_time_shift(timestamps: impl Iterator<Item = DateTime<Utc>>, now: DateTime<Utc>) -> chrono::Duration {
+    timestamps
+        .max()
+        .map(|latest| (now - latest).max(chrono::Duration::zero()))
+        .unwrap_or_else(chrono::Duration::zero)
+}
+
+/// Quote each search term so user input can't inject FTS5 query syntax; each term matches as a prefix.
+/// Returns None for queries the index can't answer, which then use LIKE instead:
+/// terms without any word characters (e.g. "%"), and Chinese/Japanese text, which the default tokenizer doesn't segment.
+fn fts_match_expression(query: &str) -> Option<String> {
+    if query.chars().any(is_unsegmented_script) {
+        return None;
+    }
+    let terms: Vec<String> = query
+        .split_whitespace()
+        .filter(|term| term.chars().any(char::is_alphanumeric))
+        .map(|term| format!("\"{}\"*", term.replace('"', "\"\"")))
+        .collect();
+
+    if terms.is_empty() {
+        None
+    } else {
+        Some(terms.join(" "))
+    }
+}
+
+/// 汉字和假名：词之间没有空格，unicode61 分词器会把整句当成一个词
+fn is_unsegmented_script(c: char) -> bool {
+    matches!(c as u32, 0x3040..=0x30FF | 0x3400..=0x4DBF | 0x4E00..=0x9FFF | 0xF900..=0xFAFF | 0x20000..=0x2A6DF)
+}
+
+fn escape_like(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
 }
 
 // 移除 Drop trait，因为使用全局连接池，不需要在 drop 时关闭连接
@@ -1029,4 +3862,610 @@ impl Database {
 //         // 不再输出 "Database connection dropped" 消息
 //         // 因为使用全局连接池，连接会一直保持
 //     }
-// }
\ No newline at end of file
+// }
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_service_name_for_processor() {
+        assert_eq!(service_name_for_processor(Some("local")), Some("local_http_asr"));
+        assert_eq!(service_name_for_processor(Some("whisper-rs")), Some("local_asr"));
+        assert_eq!(service_name_for_processor(Some("cloud-groq")), Some("whisper_asr"));
+        assert_eq!(service_name_for_processor(Some("mystery")), None);
+        assert_eq!(service_name_for_processor(None), None);
+    }
+
+    #[test]
+    fn test_fts_match_expression_quotes_terms() {
+        assert_eq!(fts_match_expression("quarterly numbers").as_deref(), Some("\"quarterly\"* \"numbers\"*"));
+        assert_eq!(fts_match_expression("say \"hi\" OR x").as_deref(), Some("\"say\"* \"\"\"hi\"\"\"* \"OR\"* \"x\"*"));
+        assert_eq!(fts_match_expression("% --"), None);
+        assert_eq!(fts_match_expression("会议 notes"), None);
+    }
+
+    #[test]
+    fn test_backup_parse_rejects_future_and_corrupt_files() {
+        let future = format!(r#"{{"version": {}}}"#, BACKUP_FORMAT_VERSION + 1);
+        assert!(AppBackup::parse(&future).unwrap_err().contains("newer than supported"));
+        assert!(AppBackup::parse("{not json").unwrap_err().contains("corrupted"));
+        assert!(AppBackup::parse(r#"{"asr_configs": []}"#).unwrap_err().contains("missing format version"));
+        // Right version but truncated body must not parse partially
+        assert!(AppBackup::parse(r#"{"version": 1, "asr_configs": []}"#).unwrap_err().contains("corrupted"));
+    }
+
+    #[test]
+    fn test_backup_roundtrip_with_redacted_keys() {
+        let now = Utc::now();
+        let mut backup = AppBackup {
+            version: BACKUP_FORMAT_VERSION,
+            app_version: "test".to_string(),
+            exported_at: now,
+            api_keys_included: true,
+            asr_configs: vec![],
+            translation_configs: vec![TranslationConfig {
+                id: "t1".to_string(),
+                provider: "deepl".to_string(),
+                api_key: Some("secret:fx".to_string()),
+                endpoint: None,
+                created_at: now,
+                updated_at: now,
+                target_language: "ja".to_string(),
+                source_language: None,
+                model: None,
+                timeout_secs: 30,
+                key_unavailable: false,
+            }],
+            hotkey_configs: vec![],
+            postprocess_configs: vec![],
+            replacements: vec![],
+            audio_prep_configs: vec![],
+            network_retry_configs: vec![],
+            overlay_configs: vec![],
+            dictation_command_configs: vec![],
+            output_profiles: vec![],
+            recording_retention_configs: vec![],
+            app_settings: vec![
+                AppSetting { key: "http_api_token".to_string(), value: "token".to_string(), updated_at: now },
+                AppSetting { key: "add_symbol".to_string(), value: "false".to_string(), updated_at: now },
+            ],
+            history_records: None,
+        };
+        backup.redact_api_keys();
+
+        let parsed = AppBackup::parse(&serde_json::to_string(&backup).unwrap()).unwrap();
+        assert!(!parsed.api_keys_included);
+        assert_eq!(parsed.translation_configs[0].api_key, None);
+        let keys: Vec<&str> = parsed.app_settings.iter().map(|s| s.key.as_str()).collect();
+        assert_eq!(keys, vec!["add_symbol"]);
+    }
+
+    async fn memory_database() -> Database {
+        // 单连接，保证所有查询落在同一个内存数据库上
+        let pool = sqlx::sqlite::SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:")
+            .await
+            .unwrap();
+        let db = Database { pool: Arc::new(pool) };
+        db.migrate().await.unwrap();
+        db
+    }
+
+    fn transcript(text: &str) -> NewHistoryRecord {
+        NewHistoryRecord {
+            record_type: "transcribe".to_string(),
+            input_text: None,
+            output_text: Some(text.to_string()),
+            audio_file_path: None,
+            processor_type: Some("whisper-rs".to_string()),
+            processing_time_ms: Some(100),
+            audio_duration_ms: Some(1500),
+            success: true,
+            error_message: None,
+            translation_skipped: false,
+            detected_language: None,
+            confidence: None,
+            segments_json: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_history_search_handles_special_characters() {
+        let db = memory_database().await;
+        for text in ["progress is 100% done", "it's fine", "100 percent", "plain text"] {
+            db.add_history_record(transcript(text)).await.unwrap();
+        }
+
+        let search = |q: &str| HistoryQuery { query: Some(q.to_string()), ..Default::default() };
+
+        let page = db.search_history_records(&search("%")).await.unwrap();
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.records[0].output_text.as_deref(), Some("progress is 100% done"));
+
+        let page = db.search_history_records(&search("it's")).await.unwrap();
+        assert_eq!(page.total_count, 1);
+
+        let page = db.search_history_records(&search("'; DROP TABLE history_records; --")).await.unwrap();
+        assert_eq!(page.total_count, 0);
+        assert_eq!(db.get_history_records(None, None).await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_history_search_survives_vacuum() {
+        let db = memory_database().await;
+        let first = db.add_history_record(transcript("alpha meeting")).await.unwrap();
+        db.add_history_record(transcript("beta notes")).await.unwrap();
+        db.add_history_record(transcript("gamma 会议纪要")).await.unwrap();
+        sqlx::query("DELETE FROM history_records WHERE id = $1").bind(&first.id).execute(&*db.pool).await.unwrap();
+        sqlx::query("VACUUM").execute(&*db.pool).await.unwrap();
+        db.add_history_record(transcript("delta reminder")).await.unwrap();
+
+        let search = |q: &str| HistoryQuery { query: Some(q.to_string()), ..Default::default() };
+        for (query, expected) in [("beta", "beta notes"), ("remind", "delta reminder"), ("会议", "gamma 会议纪要")] {
+            let page = db.search_history_records(&search(query)).await.unwrap();
+            assert_eq!(page.total_count, 1, "query {}", query);
+            assert_eq!(page.records[0].output_text.as_deref(), Some(expected));
+        }
+        assert_eq!(db.search_history_records(&search("alpha")).await.unwrap().total_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_history_pagination_reports_total_count() {
+        let db = memory_database().await;
+        for i in 0..5 {
+            db.add_history_record(transcript(&format!("record {}", i))).await.unwrap();
+        }
+
+        let page = db.search_history_records(&HistoryQuery {
+            limit: Some(2),
+            offset: Some(4),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(page.total_count, 5);
+        assert_eq!(page.records.len(), 1);
+
+        let page = db.search_history_records(&HistoryQuery {
+            record_type: Some("transcribe".to_string()),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(page.total_count, 5);
+    }
+
+    #[tokio::test]
+    async fn test_backup_covers_every_settings_table() {
+        // 历史、统计和基准测试结果不是设置，单独处理
+        const NOT_SETTINGS: &[&str] = &["history_records", "latency_records", "service_stats", "usage_logs", "benchmark_results"];
+        let db = memory_database().await;
+        let tables: Vec<String> = sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'")
+            .fetch_all(&*db.pool)
+            .await
+            .unwrap();
+        let backup = serde_json::to_value(db.export_backup(false, false).await.unwrap()).unwrap();
+
+        for table in tables.iter().filter(|t| !NOT_SETTINGS.contains(&t.as_str()) && !t.contains("_fts")) {
+            let field = if table == "transcription_replacements" { "replacements" } else { table.as_str() };
+            assert!(backup.get(field).is_some(), "settings table {} is missing from the backup", table);
+        }
+    }
+
+    #[test]
+    fn test_masked_key_is_not_saved_over_unreadable_key() {
+        assert_eq!(resolve_masked_key(Some("sk-****1234"), Some("sk-real-key-1234")).unwrap(), Some("sk-real-key-1234"));
+        assert_eq!(resolve_masked_key(Some("sk-new"), None).unwrap(), Some("sk-new"));
+        assert_eq!(resolve_masked_key(None, Some("sk-real")).unwrap(), None);
+        assert!(resolve_masked_key(Some("sk-****1234"), None).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_history_batch_is_saved_with_stats() {
+        let db = memory_database().await;
+        let saved = db
+            .add_history_records(vec![(transcript("first"), None), (transcript("second"), Some(LatencyBreakdown::default()))])
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 2);
+        assert_eq!(db.get_history_records(None, None).await.unwrap().len(), 2);
+        let stats = db.get_service_status("local_asr").await.unwrap().unwrap();
+        assert_eq!(stats.total_requests, 2);
+        assert_eq!(db.get_latency_data("local_asr", 1).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_history_record_type_filter_rejects_hostile_values() {
+        let db = memory_database().await;
+        db.add_history_record(transcript("kept")).await.unwrap();
+
+        for hostile in ["translate' OR '1'='1", "'; DROP TABLE history_records; --", "asr\0", "ASR"] {
+            assert!(db.get_history_records(None, Some(hostile)).await.is_err(), "{}", hostile);
+        }
+        assert!(db.get_history_records(Some(-1), Some("translate")).await.unwrap().is_empty());
+        assert_eq!(db.get_history_records(Some(10), Some("transcribe")).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_usage_upsert_counts_failures() {
+        let db = memory_database().await;
+        let first = chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 3, 1, 8, 0, 0).unwrap();
+        let later = first + chrono::Duration::hours(2);
+
+        let mut failed = transcript("failed");
+        failed.success = false;
+        // 旧版本/文件转录没有录音时长，只计次数
+        failed.audio_duration_ms = None;
+        db.update_usage_from_record(&transcript("a"), first).await.unwrap();
+        db.update_usage_from_record(&failed, first + chrono::Duration::hours(1)).await.unwrap();
+        db.update_usage_from_record(&transcript("b"), later).await.unwrap();
+
+        let usage = db.get_usage_data("2024-03-01").await.unwrap().unwrap();
+        assert_eq!(usage.total_requests, 3);
+        assert_eq!(usage.successful_requests, 2);
+        assert_eq!(usage.failed_requests, 1);
+        // 按录音时长累加（1.5s + 1.5s），而不是处理耗时
+        assert_eq!(usage.total_audio_ms, 3000);
+        assert_eq!(usage.total_seconds, 3);
+        assert_eq!(usage.created_at, first);
+        assert_eq!(usage.updated_at, later);
+    }
+
+    #[tokio::test]
+    async fn test_failed_records_update_stats() {
+        let db = memory_database().await;
+        let mut ok = transcript("ok");
+        ok.processor_type = Some("cloud-sensevoice".to_string());
+        let mut failed = NewHistoryRecord {
+            success: false,
+            output_text: None,
+            error_message: Some("HTTP 503".to_string()),
+            processing_time_ms: Some(2500),
+            ..ok.clone()
+        };
+        db.add_history_record(ok).await.unwrap();
+        db.add_history_record(failed.clone()).await.unwrap();
+
+        let stats = db.get_service_status("sensevoice_asr").await.unwrap().unwrap();
+        assert_eq!(stats.status, "error");
+        assert_eq!(stats.total_requests, 2);
+        assert_eq!(stats.successful_requests, 1);
+        assert_eq!(stats.failed_requests, 1);
+        assert!(stats.last_success_at.is_some());
+        assert!(stats.last_error_at.is_some());
+        assert_eq!(stats.last_error.as_deref(), Some("HTTP 503"));
+
+        // 失败也记录出错前的耗时
+        let latencies = db.get_latency_data("sensevoice_asr", 1).await.unwrap();
+        assert_eq!(latencies.len(), 2);
+        assert!(latencies.iter().any(|r| r.latency_ms == 2500));
+
+        let usage = db.get_today_usage().await.unwrap().unwrap();
+        assert_eq!(usage.total_requests, 2);
+        assert_eq!(usage.failed_requests, 1);
+
+        // 恢复后状态回到 online
+        failed.success = true;
+        db.add_history_record(failed).await.unwrap();
+        let stats = db.get_service_status("sensevoice_asr").await.unwrap().unwrap();
+        assert_eq!(stats.status, "online");
+        assert_eq!(stats.failed_requests, 1);
+    }
+
+    #[tokio::test]
+    async fn test_failed_translation_counts_as_failed_request() {
+        use crate::voice_assistant::coordinator::{RecognitionInfo, RecordedAudio};
+        use crate::voice_assistant::result_sink::PendingResult;
+
+        let db = memory_database().await;
+        let failed = PendingResult::failed_translation(
+            "ASR Error: HTTP 503".to_string(),
+            "HTTP 503".to_string(),
+            "cloud-sensevoice".to_string(),
+            Some(1800),
+            RecordedAudio { file_path: None, duration_ms: Some(2000) },
+            Some(LatencyBreakdown { asr_ms: Some(1700), ..Default::default() }),
+            RecognitionInfo::default(),
+        );
+        db.add_history_records(vec![failed.into_record()]).await.unwrap();
+
+        let stats = db.get_service_status("sensevoice_asr").await.unwrap().unwrap();
+        assert_eq!(stats.status, "error");
+        assert_eq!(stats.failed_requests, 1);
+        assert_eq!(stats.last_error.as_deref(), Some("HTTP 503"));
+
+        let latencies = db.get_latency_data("sensevoice_asr", 1).await.unwrap();
+        assert_eq!(latencies[0].latency_ms, 1800);
+        assert_eq!(latencies[0].asr_ms, Some(1700));
+
+        let usage = db.get_today_usage().await.unwrap().unwrap();
+        assert_eq!(usage.failed_requests, 1);
+        assert_eq!(usage.total_audio_ms, 2000);
+    }
+
+    #[tokio::test]
+    async fn test_usage_report_groups_by_local_date() {
+        let db = memory_database().await;
+        let at = |d: u32, h: u32| chrono::TimeZone::with_ymd_and_hms(&Utc, 2024, 3, d, h, 30, 0).unwrap();
+
+        // UTC+8：3 月 4 日 20:30 UTC 已经是本地 3 月 5 日
+        let mut cloud = transcript("cloud");
+        cloud.processor_type = Some("cloud-groq".to_string());
+        let mut failed = transcript("failed");
+        failed.success = false;
+        failed.audio_duration_ms = None;
+        for (record, created_at) in [
+            (transcript("a"), at(4, 2)),
+            (failed, at(4, 3)),
+            (transcript("b"), at(4, 20)),
+            (cloud, at(5, 1)),
+            (transcript("out of range"), at(10, 1)),
+        ] {
+            let saved = db.add_history_record_with_latency(record, None).await.unwrap();
+            sqlx::query("UPDATE history_records SET created_at = $1 WHERE id = $2")
+                .bind(created_at)
+                .bind(&saved.id)
+                .execute(&*db.pool)
+                .await
+                .unwrap();
+        }
+        sqlx::query("UPDATE latency_records SET recorded_at = $1")
+            .bind(at(4, 20))
+            .execute(&*db.pool)
+            .await
+            .unwrap();
+
+        let date = |d: u32| chrono::NaiveDate::from_ymd_opt(2024, 3, d).unwrap();
+        let mut query = UsageReportQuery {
+            start_date: date(4),
+            end_date: date(5),
+            granularity: ReportGranularity::Day,
+            utc_offset_minutes: 8 * 60,
+        };
+        let report = db.get_usage_report(&query).await.unwrap();
+        let summary: Vec<_> = report.usage.iter()
+            .map(|r| (r.period.as_str(), r.processor_type.as_str(), r.total_requests, r.successful_requests))
+            .collect();
+        assert_eq!(summary, vec![
+            ("2024-03-04", "whisper-rs", 2, 1),
+            ("2024-03-05", "cloud-groq", 1, 1),
+            ("2024-03-05", "whisper-rs", 1, 1),
+        ]);
+        assert_eq!(report.usage[0].success_rate, 50.0);
+        assert_eq!(report.usage[0].avg_processing_ms, Some(100.0));
+        assert_eq!(report.usage[0].total_audio_ms, 1500);
+        assert!(report.latency.iter().all(|r| r.period == "2024-03-05"));
+        assert_eq!(report.latency.iter().map(|r| r.requests).sum::<i64>(), 5);
+
+        // 2024-03-04 是周一，两天合并到同一周
+        query.granularity = ReportGranularity::Week;
+        let report = db.get_usage_report(&query).await.unwrap();
+        assert!(report.usage.iter().all(|r| r.period == "2024-03-04"));
+        assert_eq!(report.usage.iter().map(|r| r.total_requests).sum::<i64>(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_recording_references_are_cleared() {
+        let db = memory_database().await;
+        let mut record = transcript("with audio");
+        record.audio_file_path = Some("/tmp/recording_1.wav".to_string());
+        let saved = db.add_history_record(record).await.unwrap();
+
+        let hour_ago = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(db.get_audio_paths_referenced_since(hour_ago).await.unwrap(), vec!["/tmp/recording_1.wav".to_string()]);
+        assert!(db.get_audio_paths_referenced_since(Utc::now() + chrono::Duration::hours(1)).await.unwrap().is_empty());
+
+        assert_eq!(db.clear_audio_path_references("/tmp/recording_1.wav").await.unwrap(), 1);
+        let record = db.get_history_record(&saved.id).await.unwrap().unwrap();
+        assert_eq!(record.audio_file_path, None);
+
+        let config = db.save_recording_retention_config(Some(30), None).await.unwrap();
+        assert_eq!((config.max_age_days, config.max_total_mb), (Some(30), None));
+        let config = db.save_recording_retention_config(None, Some(500)).await.unwrap();
+        assert_eq!((config.max_age_days, config.max_total_mb), (None, Some(500)));
+    }
+
+    #[tokio::test]
+    async fn test_app_settings_upsert_and_delete() {
+        let db = memory_database().await;
+        db.set_app_setting("add_symbol", "true").await.unwrap();
+        let updated = db.set_app_setting("add_symbol", "false").await.unwrap();
+        assert_eq!(updated.value, "false");
+        db.set_app_setting("service_platform", "groq").await.unwrap();
+
+        let settings = db.get_app_settings().await.unwrap();
+        let pairs: Vec<(&str, &str)> = settings.iter().map(|s| (s.key.as_str(), s.value.as_str())).collect();
+        assert_eq!(pairs, vec![("add_symbol", "false"), ("service_platform", "groq")]);
+
+        assert!(db.delete_app_setting("add_symbol").await.unwrap());
+        assert!(!db.delete_app_setting("add_symbol").await.unwrap());
+        assert_eq!(db.get_app_settings().await.unwrap().len(), 1);
+
+        // HTTP API token 加密保存，读取时解密
+        let saved = db.set_app_setting("http_api_token", "abc123").await.unwrap();
+        assert_eq!(saved.value, "abc123");
+        let stored: String = sqlx::query_scalar("SELECT value FROM app_settings WHERE key = 'http_api_token'")
+            .fetch_one(&*db.pool)
+            .await
+            .unwrap();
+        assert!(crypto::is_encrypted(&stored));
+        let token = db.get_app_settings().await.unwrap().into_iter().find(|s| s.key == "http_api_token").unwrap();
+        assert_eq!(token.value, "abc123");
+    }
+
+    #[tokio::test]
+    async fn test_translation_config_languages() {
+        let db = memory_database().await;
+        let config = db.save_translation_config("ollama", None, None, None, None, None, None).await.unwrap();
+        assert_eq!(config.target_language, "en");
+        assert_eq!(config.source_language, None);
+
+        db.save_translation_config("ollama", None, None, Some("ja"), Some("zh"), Some("qwen2.5"), Some(45)).await.unwrap();
+        let config = db.get_translation_config("ollama").await.unwrap().unwrap();
+        assert_eq!(config.target_language, "ja");
+        assert_eq!(config.source_language.as_deref(), Some("zh"));
+        assert_eq!(config.model.as_deref(), Some("qwen2.5"));
+        assert_eq!(config.timeout_secs, 45);
+    }
+
+    #[tokio::test]
+    async fn test_dictation_command_config_roundtrip() {
+        let db = memory_database().await;
+        assert!(db.get_dictation_command_config().await.unwrap().is_none());
+
+        let config = db.save_dictation_command_config(true, None).await.unwrap();
+        assert!(config.enabled);
+        assert!(config.commands().is_none());
+
+        let json = r#"[{"phrase":"scratch that","action":{"type":"delete_last"}}]"#;
+        db.save_dictation_command_config(false, Some(json)).await.unwrap();
+        let config = db.get_dictation_command_config().await.unwrap().unwrap();
+        assert!(!config.enabled);
+        assert_eq!(config.commands().unwrap()[0].phrase, "scratch that");
+    }
+
+    #[tokio::test]
+    async fn test_asr_fallback_chain_roundtrip() {
+        use crate::voice_assistant::ProcessorType;
+
+        let db = memory_database().await;
+        assert!(db.save_asr_fallback_chain(Some("[]")).await.unwrap().is_none());
+
+        db.save_asr_config("whisper-rs", None, None, None, None, None, None, None, None, None).await.unwrap();
+        let config = db.save_asr_fallback_chain(Some(r#"["groq","local"]"#)).await.unwrap().unwrap();
+        assert_eq!(config.fallback_chain(), Some(vec![ProcessorType::Groq, ProcessorType::LocalASR]));
+
+        // 保存其他 ASR 设置不会清掉备用链
+        db.save_asr_config("whisper-rs", None, None, None, None, Some("ggml-base.bin"), None, None, None, None).await.unwrap();
+        let config = db.get_asr_config().await.unwrap().unwrap();
+        assert_eq!(config.fallback_chain(), Some(vec![ProcessorType::Groq, ProcessorType::LocalASR]));
+
+        let config = db.save_asr_fallback_chain(None).await.unwrap().unwrap();
+        assert_eq!(config.fallback_chain(), None);
+    }
+
+    #[tokio::test]
+    async fn test_asr_profiles_switch_active_config() {
+        let db = memory_database().await;
+        let local = db.save_asr_config("whisper-rs", None, None, None, None, Some("ggml-base.bin"), None, None, None, None).await.unwrap();
+        assert!(local.is_active);
+
+        // 新方案复制当前方案的设置，但不会自动激活
+        let cloud = db.create_asr_profile("Cloud").await.unwrap();
+        assert!(!cloud.is_active);
+        assert_eq!(cloud.whisper_model.as_deref(), Some("ggml-base.bin"));
+        assert!(db.asr_profile_name_exists("cloud").await.unwrap());
+
+        db.activate_asr_profile(&cloud.id).await.unwrap().unwrap();
+        db.save_asr_config("cloud", None, None, Some("https://asr.example.com"), None, None, None, None, None, None).await.unwrap();
+        let active = db.get_asr_config().await.unwrap().unwrap();
+        assert_eq!(active.id, cloud.id);
+        assert_eq!(active.service_provider, "cloud");
+
+        // 保存只修改当前方案
+        db.activate_asr_profile(&local.id).await.unwrap().unwrap();
+        assert_eq!(db.get_asr_config().await.unwrap().unwrap().service_provider, "whisper-rs");
+        assert!(db.activate_asr_profile("missing").await.unwrap().is_none());
+        assert_eq!(db.get_asr_config().await.unwrap().unwrap().id, local.id);
+
+        // 删除当前方案后改用剩下的方案
+        assert!(db.delete_asr_profile(&local.id).await.unwrap());
+        let profiles = db.list_asr_profiles().await.unwrap();
+        assert_eq!(profiles.len(), 1);
+        assert!(profiles[0].is_active);
+        assert_eq!(profiles[0].name, "Cloud");
+    }
+
+    #[tokio::test]
+    async fn test_output_profile_crud() {
+        let db = memory_database().await;
+        let profile = db.add_output_profile("terminal", "direct", "", false, None).await.unwrap();
+        assert!(profile.typing_delays().is_none());
+
+        let delays = r#"{"clipboard_update_ms":20,"keyboard_events_settle_ms":50,"typing_complete_ms":80,"character_interval_ms":5,"short_operation_ms":20}"#;
+        let updated = db.update_output_profile(&profile.id, "Terminal", "direct", " ", true, Some(delays)).await.unwrap().unwrap();
+        assert_eq!(updated.app_pattern, "Terminal");
+        assert_eq!(updated.trailing_text, " ");
+        assert!(updated.capitalize_sentences);
+        assert_eq!(updated.typing_delays().unwrap().character_interval_ms, 5);
+        assert!(db.update_output_profile("missing", "x", "auto", "", false, None).await.unwrap().is_none());
+
+        assert_eq!(db.get_output_profiles().await.unwrap().len(), 1);
+        assert!(db.delete_output_profile(&profile.id).await.unwrap());
+        assert!(db.get_output_profiles().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_latency_breakdown_roundtrip() {
+        let db = memory_database().await;
+        let breakdown = LatencyBreakdown {
+            record_ms: Some(4000),
+            wav_ms: Some(5),
+            asr_ms: Some(800),
+            postprocess_ms: None,
+            translate_ms: Some(300),
+            typing_ms: Some(120),
+            attempts: Some(2),
+            race_local_ms: Some(950),
+            race_cloud_ms: None,
+        };
+        db.add_history_record_with_latency(transcript("with breakdown"), Some(breakdown)).await.unwrap();
+        db.add_history_record(transcript("without breakdown")).await.unwrap();
+
+        let records = db.get_latency_data("local_asr", 1).await.unwrap();
+        assert_eq!(records.len(), 2);
+        let stored: Vec<_> = records.iter().filter_map(LatencyBreakdown::from_record).collect();
+        assert_eq!(stored, vec![breakdown]);
+        assert_eq!(stored[0].asr_ms_per_audio_second(), Some(200.0));
+    }
+
+    #[tokio::test]
+    async fn test_translation_record_keeps_original_and_translation() {
+        let record = NewHistoryRecord::translation(
+            "你好世界".to_string(),
+            "Hello world".to_string(),
+            "whisper-rs",
+            Some(900),
+            None,
+        );
+        assert_eq!(record.record_type, "translate");
+        assert_eq!(record.input_text.as_deref(), Some("你好世界"));
+        assert_eq!(record.output_text.as_deref(), Some("Hello world"));
+        assert!(record.validate().is_ok());
+
+        let db = memory_database().await;
+        let saved = db.add_history_record(record).await.unwrap();
+        assert_eq!(saved.input_text.as_deref(), Some("你好世界"));
+        assert_eq!(saved.output_text.as_deref(), Some("Hello world"));
+        assert!(!saved.translation_skipped);
+
+        let skipped = NewHistoryRecord {
+            translation_skipped: true,
+            ..NewHistoryRecord::translation("Hello".to_string(), "Hello".to_string(), "whisper-rs", None, None)
+        };
+        let saved = db.add_history_record(skipped).await.unwrap();
+        assert!(db.get_history_record(&saved.id).await.unwrap().unwrap().translation_skipped);
+
+        let detected = NewHistoryRecord {
+            detected_language: Some("ja".to_string()),
+            ..NewHistoryRecord::translation("こんにちは".to_string(), "你好".to_string(), "whisper-rs", None, None)
+        };
+        db.add_history_record(detected).await.unwrap();
+        let page = db.search_history_records(&HistoryQuery {
+            detected_language: Some("JA".to_string()),
+            ..Default::default()
+        }).await.unwrap();
+        assert_eq!(page.total_count, 1);
+        assert_eq!(page.records[0].detected_language.as_deref(), Some("ja"));
+
+        let scored = NewHistoryRecord { confidence: Some(0.82), ..transcript("scored") };
+        let saved = db.add_history_record(scored).await.unwrap();
+        assert_eq!(db.get_history_record(&saved.id).await.unwrap().unwrap().confidence, Some(0.82));
+
+        let missing_input = NewHistoryRecord { input_text: None, ..NewHistoryRecord::translation(String::new(), "Hi".to_string(), "whisper-rs", None, None) };
+        assert!(missing_input.validate().is_err());
+        assert!(transcript("plain").validate().is_ok());
+    }
+
+    #[test]
+    fn test_escape_like() {
+        assert_eq!(escape_like("100%_done"), "100\\%\\_done");
+    }
+}
@@ -0,0 +1,244 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing::{info, warn};
+
+/// 加密后的值都带这个前缀，没有前缀的视为旧版明文
+const ENCRYPTED_PREFIX: &str = "enc:v1:";
+const NONCE_LEN: usize = 12;
+const KEY_LEN: usize = 32;
+/// 系统钥匙串中的条目
+const KEYCHAIN_SERVICE: &str = "com.voicetype.app";
+const KEYCHAIN_USER: &str = "api-key-encryption";
+
+static CIPHER: OnceLock<Aes256Gcm> = OnceLock::new();
+/// 串行化密钥的读取和生成，避免两个首次调用各自生成不同的密钥
+static KEY_LOCK: Mutex<()> = Mutex::new(());
+
+/// 机器密钥的存放位置，测试时用内存实现
+trait KeyStore {
+    fn load(&self) -> Result<Option<Vec<u8>>, String>;
+    fn store(&self, key: &[u8]) -> Result<(), String>;
+}
+
+/// 系统钥匙串（macOS Keychain、Windows 凭据管理器、Linux Secret Service），
+/// 密钥不落在数据目录里，复制或备份数据目录不会连带密钥
+struct KeychainStore;
+
+impl KeychainStore {
+    fn entry() -> Result<keyring::Entry, String> {
+        keyring::Entry::new(KEYCHAIN_SERVICE, KEYCHAIN_USER)
+            .map_err(|e| format!("Failed to open system keychain: {}", e))
+    }
+}
+
+impl KeyStore for KeychainStore {
+    fn load(&self) -> Result<Option<Vec<u8>>, String> {
+        match Self::entry()?.get_password() {
+            Ok(encoded) => STANDARD
+                .decode(encoded.trim())
+                .map(Some)
+                .map_err(|e| format!("Secret key in system keychain is corrupted: {}", e)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(e) => Err(format!("Failed to read secret key from system keychain: {}", e)),
+        }
+    }
+
+    fn store(&self, key: &[u8]) -> Result<(), String> {
+        Self::entry()?
+            .set_password(&STANDARD.encode(key))
+            .map_err(|e| format!("Failed to save secret key to system keychain: {}", e))
+    }
+}
+
+/// 旧版本放在数据目录里的密钥文件，首次启动时迁移到钥匙串后删除
+fn legacy_key_path() -> PathBuf {
+    std::env::current_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(".tauri-data")
+        .join("secret.key")
+}
+
+fn load_or_create_key(store: &dyn KeyStore, legacy_path: &Path) -> Result<Vec<u8>, String> {
+    let _guard = KEY_LOCK.lock().unwrap_or_else(|e| e.into_inner());
+
+    if let Some(key) = store.load()? {
+        if key.len() == KEY_LEN {
+            return Ok(key);
+        }
+        return Err("Secret key in system keychain has the wrong length".to_string());
+    }
+
+    if let Ok(key) = std::fs::read(legacy_path) {
+        if key.len() != KEY_LEN {
+            return Err(format!("Secret key file {:?} is corrupted", legacy_path));
+        }
+        store.store(&key)?;
+        if let Err(e) = std::fs::remove_file(legacy_path) {
+            warn!("⚠️ Failed to remove migrated secret key file {:?}: {}", legacy_path, e);
+        }
+        info!("🔐 Moved API key encryption secret into the system keychain");
+        return Ok(key);
+    }
+
+    info!("🔐 Generating new machine secret for API key encryption");
+    let key = Aes256Gcm::generate_key(&mut OsRng);
+    store.store(key.as_slice())?;
+    Ok(key.to_vec())
+}
+
+fn cipher() -> Result<&'static Aes256Gcm, String> {
+    if let Some(cipher) = CIPHER.get() {
+        return Ok(cipher);
+    }
+    let key = load_or_create_key(&KeychainStore, &legacy_key_path())?;
+    let cipher = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key));
+    Ok(CIPHER.get_or_init(|| cipher))
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(ENCRYPTED_PREFIX)
+}
+
+fn encrypt_with(cipher: &Aes256Gcm, plaintext: &str) -> Result<String, String> {
+    let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+    let ciphertext = cipher
+        .encrypt(&nonce, plaintext.as_bytes())
+        .map_err(|e| format!("Failed to encrypt secret: {}", e))?;
+
+    let mut payload = nonce.to_vec();
+    payload.extend_from_slice(&ciphertext);
+    Ok(format!("{}{}", ENCRYPTED_PREFIX, STANDARD.encode(payload)))
+}
+
+fn decrypt_with(cipher: &Aes256Gcm, stored: &str) -> Result<String, String> {
+    let Some(encoded) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+        // 旧版明文，迁移前原样返回
+        return Ok(stored.to_string());
+    };
+
+    let payload = STANDARD
+        .decode(encoded)
+        .map_err(|e| format!("Encrypted secret is corrupted: {}", e))?;
+    if payload.len() <= NONCE_LEN {
+        return Err("Encrypted secret is corrupted: payload too short".to_string());
+    }
+
+    let (nonce, ciphertext) = payload.split_at(NONCE_LEN);
+    let plaintext = cipher
+        .decrypt(Nonce::from_slice(nonce), ciphertext)
+        .map_err(|_| "Failed to decrypt secret: the machine key may have changed".to_string())?;
+
+    String::from_utf8(plaintext).map_err(|e| format!("Decrypted secret is not UTF-8: {}", e))
+}
+
+/// 写入数据库前加密；已加密的值不会重复加密
+pub fn encrypt_secret(plaintext: &str) -> Result<String, String> {
+    if is_encrypted(plaintext) {
+        return Ok(plaintext.to_string());
+    }
+    encrypt_with(cipher()?, plaintext)
+}
+
+/// 从数据库读取后解密
+pub fn decrypt_secret(stored: &str) -> Result<String, String> {
+    if !is_encrypted(stored) {
+        return Ok(stored.to_string());
+    }
+    decrypt_with(cipher()?, stored)
+}
+
+/// 返回给前端的脱敏形式，例如 "sk-****abcd"
+pub fn mask_secret(secret: &str) -> String {
+    let chars: Vec<char> = secret.chars().collect();
+    if chars.len() <= 8 {
+        return "****".to_string();
+    }
+    let prefix: String = chars[..3].iter().collect();
+    let suffix: String = chars[chars.len() - 4..].iter().collect();
+    format!("{}****{}", prefix, suffix)
+}
+
+/// 前端回传的脱敏值不能覆盖真实 key
+pub fn is_masked(value: &str) -> bool {
+    value.contains("****")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_cipher() -> Aes256Gcm {
+        Aes256Gcm::new(&Aes256Gcm::generate_key(&mut OsRng))
+    }
+
+    #[test]
+    fn test_encrypt_decrypt_roundtrip() {
+        let cipher = test_cipher();
+        let encrypted = encrypt_with(&cipher, "sk-test-1234567890").unwrap();
+        assert!(is_encrypted(&encrypted));
+        assert!(!encrypted.contains("sk-test"));
+        assert_eq!(decrypt_with(&cipher, &encrypted).unwrap(), "sk-test-1234567890");
+    }
+
+    #[test]
+    fn test_plaintext_passes_through_and_wrong_key_fails() {
+        let cipher = test_cipher();
+        assert_eq!(decrypt_with(&cipher, "legacy-plain-key").unwrap(), "legacy-plain-key");
+
+        let encrypted = encrypt_with(&cipher, "secret").unwrap();
+        assert!(decrypt_with(&test_cipher(), &encrypted).is_err());
+    }
+
+    #[derive(Default)]
+    struct MemoryStore(Mutex<Option<Vec<u8>>>);
+
+    impl KeyStore for MemoryStore {
+        fn load(&self) -> Result<Option<Vec<u8>>, String> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn store(&self, key: &[u8]) -> Result<(), String> {
+            *self.0.lock().unwrap() = Some(key.to_vec());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_concurrent_first_calls_share_one_key() {
+        let store = std::sync::Arc::new(MemoryStore::default());
+        let missing = std::env::temp_dir().join(format!("missing-{}.key", uuid::Uuid::new_v4()));
+        let keys: Vec<Vec<u8>> = (0..8)
+            .map(|_| {
+                let store = store.clone();
+                let missing = missing.clone();
+                std::thread::spawn(move || load_or_create_key(store.as_ref(), &missing).unwrap())
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|handle| handle.join().unwrap())
+            .collect();
+        assert!(keys.iter().all(|key| key == &keys[0]));
+        assert_eq!(store.load().unwrap().as_ref(), Some(&keys[0]));
+    }
+
+    #[test]
+    fn test_legacy_key_file_is_moved_to_store() {
+        let path = std::env::temp_dir().join(format!("legacy-{}.key", uuid::Uuid::new_v4()));
+        std::fs::write(&path, [7u8; KEY_LEN]).unwrap();
+
+        let store = MemoryStore::default();
+        assert_eq!(load_or_create_key(&store, &path).unwrap(), vec![7u8; KEY_LEN]);
+        assert_eq!(store.load().unwrap(), Some(vec![7u8; KEY_LEN]));
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn test_mask_secret() {
+        assert_eq!(mask_secret("sk-abcdefghijkl1234"), "sk-****1234");
+        assert_eq!(mask_secret("short"), "****");
+        assert!(is_masked(&mask_secret("sk-abcdefghijkl1234")));
+    }
+}
@@ -90,11 +90,14 @@ fn add(a: i32, b: i32) -> i32 {
     a + b
 }
 
+#[cfg(desktop)]
+use voice_assistant::SystemTrayManager;
+
 // Re-export VoiceAssistant commands
 use voice_assistant::{
     start_voice_assistant, stop_voice_assistant, get_voice_assistant_state,
-    get_voice_assistant_config, test_asr, test_translation, get_system_info,
-    // SystemTrayManager, GlobalHotkeyManager, ensure_dependencies,
+    get_voice_assistant_config, test_asr, test_translation, get_system_info, check_asr_health,
+    transcribe_file, run_pipeline_self_test,
     GlobalHotkeyManager, ensure_dependencies,
     // Model management commands
     get_available_models, download_model, delete_model, set_active_model,
@@ -106,19 +109,40 @@ use voice_assistant::{
 // Import commands module
 use commands::{
     test_frontend_backend_connection, test_connection_health,
-    init_database, get_asr_config, save_asr_config,
-    get_translation_config, save_translation_config,
+    init_database, get_asr_config, save_asr_config, get_asr_fallback_chain, save_asr_fallback_chain,
+    get_translation_config, save_translation_config, reveal_api_key,
+    list_ollama_models, test_ollama_connection,
+    get_app_settings, set_app_setting, set_whisper_decoding, set_whisper_prompt, set_whisper_min_segment_confidence,
+    get_postprocess_config, save_postprocess_config,
+    get_audio_prep_config, save_audio_prep_config,
+    get_network_retry_config, save_network_retry_config,
+    get_overlay_config, save_overlay_config, set_overlay_enabled,
+    get_dictation_command_config, save_dictation_command_config, get_default_dictation_commands,
+    get_replacements, add_replacement, delete_replacement,
+    get_output_profiles, add_output_profile, update_output_profile, delete_output_profile, get_focused_app_name,
     add_history_record, get_history_records, get_history_stats, cleanup_old_records,
-    get_hotkey_config, save_hotkey_config,
+    get_history_audio, export_history_record, cleanup_missing_audio_paths,
+    export_app_data, import_app_data,
+    get_hotkey_config, save_hotkey_config, validate_hotkey_string, capture_hotkey, cancel_hotkey_capture,
     start_test_recording, get_audio_devices, test_microphone,
     test_asr_transcription,
-    get_service_status, get_latency_data, get_usage_data,
+    get_service_status, get_latency_data, get_latency_percentiles, get_usage_data, get_usage_report,
     handle_asr_result,
-    scan_whisper_models, set_active_whisper_model, get_active_whisper_model
+    scan_whisper_models, set_active_whisper_model, get_active_whisper_model, set_models_dir,
+    delete_whisper_model
 };
 
 // Import global whisper manager commands
-use voice_assistant::global_whisper::{get_whisper_manager_status, reload_whisper_processor, clear_whisper_processor};
+use voice_assistant::global_whisper::{get_whisper_manager_status, reload_whisper_processor, clear_whisper_processor, preload_whisper_model};
+
+// Import local HTTP API commands
+use voice_assistant::http_api::{get_http_api_status, regenerate_http_api_token};
+
+// Import logging commands
+use voice_assistant::logger::{get_log_level, set_log_level, get_recent_logs, get_log_file_path};
+
+// Import history export command
+use commands::history_export::export_history;
 
 // Import GPU backend commands
 use commands::gpu_backend::{
@@ -126,12 +150,32 @@ use commands::gpu_backend::{
     get_backend_details, test_backend_performance, check_nvidia_driver
 };
 
+// Import benchmark commands
+use commands::benchmark::{run_whisper_benchmark, get_benchmark_results};
+use commands::recordings::{get_recordings_disk_usage, get_recording_retention_config, save_recording_retention_config};
+use commands::asr_profiles::{list_asr_profiles, create_asr_profile, activate_asr_profile, delete_asr_profile};
+use commands::transcript::{start_transcript_session, end_transcript_session, get_current_transcript, export_transcript};
+use commands::service_status::get_all_services_status;
+
 use std::sync::{Arc, Mutex};
 use commands::DatabaseState;
 
 
+/// 命令行带 `--whisper-worker <config_json>` 时作为 whisper 推理 worker 运行，返回进程退出码
+pub fn run_whisper_worker_if_requested() -> Option<i32> {
+    use voice_assistant::asr::whisper_worker;
+
+    let config = whisper_worker::worker_config(std::env::args())?;
+    Some(whisper_worker::run_worker(config))
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Initialize tracing first so startup logs reach the ring buffer
+    if let Err(e) = voice_assistant::init_logger() {
+        eprintln!("Failed to initialize logger: {}", e);
+    }
+
     // Load CUDA DLLs from resources if available
     if let Err(e) = load_cuda_dlls() {
         println!("ℹ️  CUDA DLL loading: {} (falling back to CPU mode)", e);
@@ -154,6 +198,10 @@ pub fn run() {
         match commands::init_database_direct().await {
             Ok(db) => {
                 println!("✅ Database initialization successful");
+                // 其他模块读取运行时设置前先加载一次
+                if let Err(e) = voice_assistant::settings::load_from(&db).await {
+                    tracing::warn!("⚠️ Failed to load app settings: {}", e);
+                }
                 *db_for_init.lock().unwrap() = Some(db);
             }
             Err(e) => eprintln!("❌ Failed to initialize database on startup: {}", e),
@@ -185,42 +233,49 @@ pub fn run() {
             crate::voice_assistant::coordinator::set_app_handle(app.handle().clone());
             println!("✅ Global app handle set for event emission");
 
-            // Initialize system tray manager - DISABLED DUE TO COMPILATION ISSUES
-            // let system_tray_manager = Arc::new(Mutex::new(
-            //     SystemTrayManager::new(app.handle().clone())
-            // ));
-            // app.manage(system_tray_manager.clone());
-
-            // // Create system tray icon with menu items
-            // match SystemTrayManager::create_tray_icon() {
-            //     Ok(tray) => {
-            //         if let Err(e) = tray.build(app) {
-            //             eprintln!("⚠️  Failed to build system tray: {}", e);
-            //         } else {
-            //             println!("✅ System tray created successfully");
-            //         }
-            //     }
-            //     Err(e) => eprintln!("⚠️  Failed to create system tray: {}", e),
-            // }
-
-            // Create overlay window (initially hidden) - TEMPORARILY DISABLED
-            // let tray_manager_ref = app.state::<Arc<Mutex<SystemTrayManager>>>();
-            // if let Ok(tray_manager) = tray_manager_ref.try_lock() {
-            //     if let Err(e) = tray_manager.create_overlay_window() {
-            //         eprintln!("⚠️  Failed to create overlay window: {}", e);
-            //     } else {
-            //         println!("✅ Overlay window created successfully");
-            //     }
-            // }
-            println!("ℹ️  Overlay window creation disabled for evaluation");
+            // System tray: state-dependent icon, start/stop, model switching, settings and quit
+            #[cfg(desktop)]
+            {
+                use tauri::Manager;
+                match SystemTrayManager::init(app.handle()) {
+                    Ok(tray_manager) => {
+                        app.manage(tray_manager);
+                        tracing::info!("✅ System tray created successfully");
+                    }
+                    Err(e) => tracing::warn!("⚠️  Failed to create system tray: {}", e),
+                }
+            }
+
+            // Recording overlay: follows the cursor while recording, spinner while processing
+            #[cfg(desktop)]
+            {
+                if let Err(e) = crate::voice_assistant::overlay::init_overlay(app.handle()) {
+                    tracing::warn!("⚠️  Failed to create overlay window: {}", e);
+                }
+            }
+
+            // Recording retention: clean up old WAV files at startup and once a day
+            {
+                use tauri::Manager;
+                let db_state = app.state::<DatabaseState>().inner().clone();
+                commands::recordings::spawn_retention_task(db_state);
+            }
+
+            // Dashboard service status: ping network services at startup and every few minutes
+            commands::service_status::spawn_health_task();
+
+            // Optional local HTTP API (disabled by default, 127.0.0.1 only)
+            tauri::async_runtime::spawn(async {
+                if let Err(e) = crate::voice_assistant::http_api::apply_settings().await {
+                    tracing::warn!("⚠️  Failed to start local HTTP API: {}", e);
+                }
+            });
 
             // Initialize and register global hotkeys
             let hotkey_manager = GlobalHotkeyManager::new(app.handle().clone());
 
             if let Err(e) = hotkey_manager.register_global_hotkeys() {
                 eprintln!("❌ Failed to register global hotkeys: {}", e);
-            } else {
-                println!("ℹ️  Global hotkey registration skipped (feature disabled)");
             }
 
             Ok(())
@@ -235,6 +290,9 @@ pub fn run() {
             get_voice_assistant_config,
             test_asr,
             test_translation,
+            check_asr_health,
+            transcribe_file,
+            run_pipeline_self_test,
             get_system_info,
             test_frontend_backend_connection,
             test_connection_health,
@@ -242,14 +300,66 @@ pub fn run() {
             init_database,
             get_asr_config,
             save_asr_config,
+            get_asr_fallback_chain,
+            save_asr_fallback_chain,
+            list_asr_profiles,
+            create_asr_profile,
+            activate_asr_profile,
+            delete_asr_profile,
             get_translation_config,
             save_translation_config,
+            list_ollama_models,
+            test_ollama_connection,
+            reveal_api_key,
+            get_postprocess_config,
+            save_postprocess_config,
+            get_app_settings,
+            set_app_setting,
+            set_whisper_decoding,
+            set_whisper_min_segment_confidence,
+            set_whisper_prompt,
+            get_http_api_status,
+            regenerate_http_api_token,
+            get_audio_prep_config,
+            save_audio_prep_config,
+            get_network_retry_config,
+            save_network_retry_config,
+            get_overlay_config,
+            save_overlay_config,
+            set_overlay_enabled,
+            get_dictation_command_config,
+            save_dictation_command_config,
+            get_default_dictation_commands,
+            get_replacements,
+            add_replacement,
+            delete_replacement,
+            get_output_profiles,
+            add_output_profile,
+            update_output_profile,
+            delete_output_profile,
+            get_focused_app_name,
             add_history_record,
             get_history_records,
             get_history_stats,
             cleanup_old_records,
+            get_history_audio,
+            export_history_record,
+            cleanup_missing_audio_paths,
+            export_app_data,
+            import_app_data,
+            export_history,
+            start_transcript_session,
+            end_transcript_session,
+            get_current_transcript,
+            export_transcript,
+            get_recordings_disk_usage,
+            get_recording_retention_config,
+            save_recording_retention_config,
             get_hotkey_config,
             save_hotkey_config,
+            validate_hotkey_string,
+            capture_hotkey,
+            cancel_hotkey_capture,
             // Audio and testing commands
             start_test_recording,
             get_audio_devices,
@@ -257,13 +367,16 @@ pub fn run() {
             test_asr_transcription,
             // Live data commands
             get_service_status,
+            get_all_services_status,
             get_latency_data,
+            get_latency_percentiles,
             get_usage_data,
+            get_usage_report,
             handle_asr_result,
             // Model management commands - ONLY use file-based scanning commands
             // scan_whisper_models,      // ⭐️ ACTIVE - Scans actual model files
-            // set_active_whisper_model, // ⭐️ ACTIVE - Sets model via environment
-            // get_active_whisper_model, // ⭐️ ACTIVE - Gets active model from env
+            // set_active_whisper_model, // ⭐️ ACTIVE - Saves model to app settings
+            // get_active_whisper_model, // ⭐️ ACTIVE - Gets active model from app settings
             
             // ❌ DISABLED - Redundant hardcoded model management
             // get_available_models,     // Conflicts with scan_whisper_models
@@ -275,6 +388,8 @@ pub fn run() {
             
             // 🎯 TEMP: Keep both for now during transition
             scan_whisper_models,
+            set_models_dir,
+            delete_whisper_model,
             set_active_whisper_model,
             get_active_whisper_model,
             get_available_models,
@@ -287,18 +402,59 @@ pub fn run() {
             // Download site commands
             get_download_sites,
             test_download_sites,
+            // Logging commands
+            get_log_level,
+            set_log_level,
+            get_recent_logs,
+            get_log_file_path,
             // Global WhisperRS manager commands
             get_whisper_manager_status,
             reload_whisper_processor,
             clear_whisper_processor,
+            preload_whisper_model,
             // GPU backend management commands
             check_nvidia_driver,
             get_gpu_backend_status,
             set_preferred_gpu_backend,
             redetect_gpu_backends,
             get_backend_details,
-            test_backend_performance
+            test_backend_performance,
+            // Benchmark commands
+            run_whisper_benchmark,
+            get_benchmark_results
         ])
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            if let tauri::RunEvent::ExitRequested { code, api, .. } = event {
+                handle_exit_requested(app_handle, code, &api);
+            }
+        });
+}
+
+/// 退出前最多等待正在进行的识别完成的时间
+const SHUTDOWN_TRANSCRIPTION_WAIT: std::time::Duration = std::time::Duration::from_secs(5);
+/// 整个退出清理的上限，超时后直接退出
+const SHUTDOWN_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(8);
+
+/// 第一次退出请求先阻止退出，清理完成（或超时）后再退出；
+/// 清理期间再次请求退出（如再次点击托盘的退出）不再阻止，直接退出
+fn handle_exit_requested(app_handle: &tauri::AppHandle, code: Option<i32>, api: &tauri::ExitRequestApi) {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    static SHUTDOWN_STARTED: AtomicBool = AtomicBool::new(false);
+
+    if SHUTDOWN_STARTED.swap(true, Ordering::SeqCst) {
+        tracing::info!("👋 Exiting");
+        return;
+    }
+
+    api.prevent_exit();
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        let cleanup = voice_assistant::coordinator::shutdown_for_exit(SHUTDOWN_TRANSCRIPTION_WAIT);
+        if tokio::time::timeout(SHUTDOWN_TIMEOUT, cleanup).await.is_err() {
+            tracing::warn!("⚠️ Shutdown cleanup timed out, exiting anyway");
+        }
+        app_handle.exit(code.unwrap_or(0));
+    });
 }
@@ -9,5 +9,10 @@ fn main() {
     //  Ported from Python to Rust with Tauri v2
     // ==========================================
 
+    // 以 --whisper-worker 启动时只作为隔离的 whisper 推理进程运行
+    if let Some(code) = voicetype_lib::run_whisper_worker_if_requested() {
+        std::process::exit(code);
+    }
+
     voicetype_lib::run()
 }
@@ -1 +1,2 @@
-pub mod platform;
\ No newline at end of file
+pub mod platform;
+pub mod thread;
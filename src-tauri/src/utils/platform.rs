@@ -51,16 +51,143 @@ pub fn get_home_dir() -> PathBuf {
     }
 }
 
-/// 获取模型存储目录
+/// 覆盖模型目录的环境变量，优先级高于已保存的设置
+pub const MODELS_DIR_ENV: &str = "VOICETYPE_MODELS_DIR";
+
+/// 保存用户自定义模型目录的文件
+fn models_dir_setting_file() -> PathBuf {
+    get_user_data_dir().join("models_dir.txt")
+}
+
+/// 获取模型存储目录：VOICETYPE_MODELS_DIR > set_models_dir 保存的目录 > 平台默认目录
 pub fn get_models_dir() -> PathBuf {
+    let env_dir = std::env::var(MODELS_DIR_ENV).ok();
+    let saved = std::fs::read_to_string(models_dir_setting_file()).ok();
+    match resolve_models_dir(env_dir.as_deref(), saved.as_deref()) {
+        Some(dir) => dir,
+        None => {
+            let default_dir = get_default_models_dir();
+            migrate_legacy_models_once(&default_dir);
+            default_dir
+        }
+    }
+}
+
+/// 按优先级选出自定义目录，都没有设置时返回 None（使用默认目录）
+fn resolve_models_dir(env_dir: Option<&str>, saved_dir: Option<&str>) -> Option<PathBuf> {
+    [env_dir, saved_dir]
+        .into_iter()
+        .flatten()
+        .map(str::trim)
+        .find(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+}
+
+/// 平台默认模型目录
+pub fn get_default_models_dir() -> PathBuf {
     get_user_data_dir().join("models")
 }
 
+/// 旧版本模型管理器使用的 Tauri 应用数据目录（按应用标识 com.voicetype.app），
+/// 之前下载的模型可能还在这里
+fn legacy_models_dir() -> Option<PathBuf> {
+    const IDENTIFIER: &str = "com.voicetype.app";
+    #[cfg(target_os = "windows")]
+    let data_dir = std::env::var("APPDATA").ok().map(PathBuf::from);
+    #[cfg(target_os = "macos")]
+    let data_dir = std::env::var("HOME").ok().map(|home| PathBuf::from(home).join("Library").join("Application Support"));
+    #[cfg(target_os = "linux")]
+    let data_dir = std::env::var("XDG_DATA_HOME")
+        .ok()
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".local").join("share")));
+    #[cfg(not(any(target_os = "windows", target_os = "macos", target_os = "linux")))]
+    let data_dir: Option<PathBuf> = None;
+
+    data_dir.map(|dir| dir.join(IDENTIFIER).join("models"))
+}
+
+fn migrate_legacy_models_once(models_dir: &std::path::Path) {
+    static MIGRATED: std::sync::OnceLock<()> = std::sync::OnceLock::new();
+    MIGRATED.get_or_init(|| {
+        if let Some(legacy_dir) = legacy_models_dir() {
+            let moved = migrate_legacy_models(&legacy_dir, models_dir);
+            if moved > 0 {
+                tracing::info!("📦 Moved {} model file(s) from {:?} to {:?}", moved, legacy_dir, models_dir);
+            }
+        }
+    });
+}
+
+/// 把旧目录中的模型文件（.bin）移到新目录，新目录已有同名文件时保留旧文件不动。返回移动的文件数
+pub fn migrate_legacy_models(legacy_dir: &std::path::Path, models_dir: &std::path::Path) -> usize {
+    if legacy_dir == models_dir {
+        return 0;
+    }
+    let Ok(entries) = std::fs::read_dir(legacy_dir) else {
+        return 0;
+    };
+
+    let mut moved = 0;
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if !path.is_file() || path.extension().and_then(|ext| ext.to_str()) != Some("bin") {
+            continue;
+        }
+        let Some(file_name) = path.file_name() else { continue };
+        let target = models_dir.join(file_name);
+        if target.exists() {
+            continue;
+        }
+        if let Err(e) = std::fs::create_dir_all(models_dir) {
+            tracing::warn!("⚠️ Failed to create models directory {:?}: {}", models_dir, e);
+            return moved;
+        }
+        // 跨文件系统时 rename 会失败，改为复制后删除
+        let result = std::fs::rename(&path, &target).or_else(|_| {
+            std::fs::copy(&path, &target).and_then(|_| std::fs::remove_file(&path))
+        });
+        match result {
+            Ok(()) => moved += 1,
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to move model {:?} to {:?}: {}", path, target, e);
+                // 复制到一半的文件会被当作已下载的模型，删掉
+                if path.exists() {
+                    std::fs::remove_file(&target).ok();
+                }
+            }
+        }
+    }
+    moved
+}
+
+/// 保存自定义模型目录；传入 None 恢复默认目录
+pub fn save_models_dir(dir: Option<&std::path::Path>) -> std::io::Result<()> {
+    let setting_file = models_dir_setting_file();
+    match dir {
+        Some(dir) => {
+            if let Some(parent) = setting_file.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(&setting_file, dir.to_string_lossy().as_bytes())
+        }
+        None => match std::fs::remove_file(&setting_file) {
+            Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        },
+    }
+}
+
 /// 获取数据库存储目录
 pub fn get_database_dir() -> PathBuf {
     get_user_data_dir().join("databases")
 }
 
+/// 获取日志文件目录
+pub fn get_logs_dir() -> PathBuf {
+    get_user_data_dir().join("logs")
+}
+
 /// 获取平台信息
 pub fn get_platform_info() -> (String, String) {
     let os = std::env::consts::OS.to_string();
@@ -102,6 +229,45 @@ pub fn get_cuda_env_vars() -> Vec<String> {
     }
 }
 
+/// 系统当前可用内存（字节），不支持的平台返回 None
+pub fn available_memory_bytes() -> Option<u64> {
+    // sysinfo 在 Linux 上读 MemAvailable（包含可回收的缓存），macOS/Windows 上使用系统 API
+    let mut system = sysinfo::System::new();
+    system.refresh_memory();
+    Some(system.available_memory()).filter(|&bytes| bytes > 0)
+}
+
+/// 当前进程的峰值常驻内存（字节），不支持的平台返回 None
+pub fn peak_rss_bytes() -> Option<u64> {
+    #[cfg(target_os = "linux")]
+    {
+        // VmHWM 是进程的常驻内存高水位，单位 kB
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        status
+            .lines()
+            .find(|line| line.starts_with("VmHWM:"))
+            .and_then(|line| line.split_whitespace().nth(1))
+            .and_then(|kb| kb.parse::<u64>().ok())
+            .map(|kb| kb * 1024)
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        use winapi::um::processthreadsapi::GetCurrentProcess;
+        use winapi::um::psapi::{GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS};
+
+        let mut counters: PROCESS_MEMORY_COUNTERS = unsafe { std::mem::zeroed() };
+        let size = std::mem::size_of::<PROCESS_MEMORY_COUNTERS>() as u32;
+        let ok = unsafe { GetProcessMemoryInfo(GetCurrentProcess(), &mut counters, size) };
+        (ok != 0).then_some(counters.PeakWorkingSetSize as u64)
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "windows")))]
+    {
+        None
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -125,6 +291,37 @@ mod tests {
         println!("Database dir: {:?}", db_dir);
     }
 
+    #[test]
+    fn test_models_dir_priority() {
+        assert_eq!(resolve_models_dir(Some("/mnt/external/models"), Some("/saved")), Some(PathBuf::from("/mnt/external/models")));
+        assert_eq!(resolve_models_dir(Some("  "), Some(" /saved\n")), Some(PathBuf::from("/saved")));
+        assert_eq!(resolve_models_dir(None, Some("")), None);
+        assert_eq!(resolve_models_dir(None, None), None);
+    }
+
+    #[test]
+    fn test_migrate_legacy_models() {
+        let root = std::env::temp_dir().join(format!("models-migrate-{}", std::process::id()));
+        let legacy = root.join("legacy");
+        let current = root.join("current");
+        std::fs::create_dir_all(&legacy).unwrap();
+        std::fs::create_dir_all(&current).unwrap();
+        std::fs::write(legacy.join("ggml-base.bin"), b"base").unwrap();
+        std::fs::write(legacy.join("ggml-small.bin"), b"old small").unwrap();
+        std::fs::write(legacy.join("notes.txt"), b"not a model").unwrap();
+        std::fs::write(current.join("ggml-small.bin"), b"new small").unwrap();
+
+        assert_eq!(migrate_legacy_models(&legacy, &current), 1);
+        assert_eq!(std::fs::read(current.join("ggml-base.bin")).unwrap(), b"base");
+        assert!(!legacy.join("ggml-base.bin").exists());
+        // 新目录已有的文件不覆盖
+        assert_eq!(std::fs::read(current.join("ggml-small.bin")).unwrap(), b"new small");
+        assert!(legacy.join("notes.txt").exists());
+        assert_eq!(migrate_legacy_models(&root.join("missing"), &current), 0);
+
+        std::fs::remove_dir_all(&root).ok();
+    }
+
     #[test]
     fn test_cuda_env_vars() {
         let cuda_vars = get_cuda_env_vars();
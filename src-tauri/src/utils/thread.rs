@@ -0,0 +1,71 @@
+/// 在独立线程中执行可能卡死或 panic 的初始化（如 whisper.cpp 加载模型）
+use std::any::Any;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::time::Duration;
+use crate::voice_assistant::VoiceError;
+
+fn panic_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "unknown panic payload".to_string()
+    }
+}
+
+/// 在一个工作线程中执行 `f`，最多等待 `timeout`。
+/// 超时返回 `VoiceError::Timeout`（工作线程无法被终止，会在后台继续运行直到结束）；
+/// `f` panic 时返回带 panic 信息的 `VoiceError::Other`
+pub fn create_with_timeout<T, F>(timeout: Duration, f: F) -> Result<T, VoiceError>
+where
+    T: Send + 'static,
+    F: FnOnce() -> Result<T, VoiceError> + Send + 'static,
+{
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        let result = panic::catch_unwind(AssertUnwindSafe(f));
+        // 超时后接收端已丢弃，发送失败可以忽略
+        let _ = tx.send(result);
+    });
+
+    match rx.recv_timeout(timeout) {
+        Ok(Ok(result)) => result,
+        Ok(Err(payload)) => Err(VoiceError::Other(format!("Worker thread panicked: {}", panic_message(payload.as_ref())))),
+        Err(RecvTimeoutError::Timeout) => Err(VoiceError::Timeout(format!("Operation did not finish within {:?}", timeout))),
+        Err(RecvTimeoutError::Disconnected) => Err(VoiceError::Other("Worker thread exited without a result".to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fast_closure_returns_value() {
+        let result = create_with_timeout(Duration::from_secs(5), || Ok(42));
+        assert_eq!(result.unwrap(), 42);
+
+        let result: Result<(), _> = create_with_timeout(Duration::from_secs(5), || Err(VoiceError::Other("load failed".to_string())));
+        assert!(matches!(result, Err(VoiceError::Other(ref e)) if e == "load failed"));
+    }
+
+    #[test]
+    fn test_slow_closure_times_out() {
+        let result = create_with_timeout(Duration::from_millis(20), || {
+            std::thread::sleep(Duration::from_millis(500));
+            Ok(())
+        });
+        assert!(matches!(result, Err(VoiceError::Timeout(_))), "unexpected result: {:?}", result);
+    }
+
+    #[test]
+    fn test_panic_is_reported_with_message() {
+        let result: Result<(), _> = create_with_timeout(Duration::from_secs(5), || panic!("model file corrupted"));
+        match result {
+            Err(VoiceError::Other(e)) => assert!(e.contains("model file corrupted"), "unexpected message: {}", e),
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
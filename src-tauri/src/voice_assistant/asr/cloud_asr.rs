@@ -0,0 +1,172 @@
+use std::io::Cursor;
+use crate::voice_assistant::{AsrProcessor, AsrTranscript, Mode, VoiceError};
+use crate::voice_assistant::asr::cloud_provider::{verify_api_key, CloudAsrOptions, CloudAsrProvider};
+use crate::voice_assistant::asr::race::CancelToken;
+use std::time::Duration;
+
+/// 未配置云端地址时 OpenAI 格式使用的服务地址
+pub const DEFAULT_OPENAI_ENDPOINT: &str = "https://api.openai.com/v1";
+
+/// 设置中保存的云端 ASR 服务：地址、key，以及请求格式、模型和语言
+#[derive(Debug, Clone)]
+pub struct CloudAsrConfig {
+    pub endpoint: String,
+    pub api_key: Option<String>,
+    pub options: CloudAsrOptions,
+}
+
+impl CloudAsrConfig {
+    /// 从保存的 ASR 配置读取；没有保存云端地址时返回 None
+    pub fn from_config(config: &crate::database::AsrConfig) -> Option<Self> {
+        let endpoint = config.cloud_endpoint.clone().filter(|e| !e.trim().is_empty())?;
+        Some(Self {
+            endpoint,
+            api_key: config.cloud_api_key.clone(),
+            options: CloudAsrOptions::from_config(config),
+        })
+    }
+
+    /// OpenAI 兼容服务：沿用保存的地址、key、模型和语言，请求格式固定为 OpenAI，没有地址时使用 OpenAI 官方接口
+    pub fn openai(config: &crate::database::AsrConfig) -> Self {
+        let mut cloud = Self::from_config(config).unwrap_or_else(|| Self {
+            endpoint: DEFAULT_OPENAI_ENDPOINT.to_string(),
+            api_key: config.cloud_api_key.clone(),
+            options: CloudAsrOptions::from_config(config),
+        });
+        cloud.options.provider = CloudAsrProvider::OpenAi;
+        cloud
+    }
+}
+
+/// 按保存的云端配置识别，运行时与测试命令使用同一套请求格式
+pub struct CloudAsrProcessor {
+    client: reqwest::Client,
+    config: CloudAsrConfig,
+}
+
+impl CloudAsrProcessor {
+    /// 自建服务器格式，与 service_provider "cloud" 一致
+    pub const CUSTOM_PROCESSOR_TYPE: &'static str = "cloud";
+    pub const OPENAI_PROCESSOR_TYPE: &'static str = "cloud-openai";
+
+    pub fn with_config(config: CloudAsrConfig) -> Result<Self, VoiceError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(VoiceError::Network)?;
+
+        Ok(Self {
+            client,
+            config,
+        })
+    }
+
+    fn options(&self, mode: Mode, prompt: &str) -> CloudAsrOptions {
+        CloudAsrOptions {
+            prompt: Some(prompt.to_string()),
+            translate: mode == Mode::Translations,
+            ..self.config.options.clone()
+        }
+    }
+}
+
+impl AsrProcessor for CloudAsrProcessor {
+    fn process_audio(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.process_audio_cancellable(audio_buffer, mode, prompt, &CancelToken::default())
+    }
+
+    fn process_audio_cancellable(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+        cancel: &CancelToken,
+    ) -> Result<AsrTranscript, VoiceError> {
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
+
+        let options = self.options(mode, prompt);
+        let request = options.transcribe(&self.client, &self.config.endpoint, self.config.api_key.as_deref(), audio_buffer.into_inner());
+        rt.block_on(cancel.run(request)).map(|transcript| AsrTranscript {
+            // 配置了语言时服务按该语言识别
+            language: self.config.options.language.clone(),
+            ..transcript
+        })
+    }
+
+    fn get_processor_type(&self) -> &str {
+        match self.config.options.provider {
+            CloudAsrProvider::Custom => Self::CUSTOM_PROCESSOR_TYPE,
+            CloudAsrProvider::OpenAi => Self::OPENAI_PROCESSOR_TYPE,
+        }
+    }
+
+    fn health_check(&self) -> Result<(), VoiceError> {
+        let api_key = self.config.api_key.as_deref().unwrap_or_default();
+        match self.config.options.provider {
+            CloudAsrProvider::OpenAi if api_key.trim().is_empty() => {
+                Err(VoiceError::Other("Cloud ASR API key is not configured".to_string()))
+            }
+            CloudAsrProvider::OpenAi => {
+                tauri::async_runtime::block_on(verify_api_key(&self.client, &self.config.endpoint, api_key))
+            }
+            // 自建服务器没有不产生识别请求的检查接口
+            CloudAsrProvider::Custom => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn saved(provider: &str, endpoint: Option<&str>) -> crate::database::AsrConfig {
+        crate::database::AsrConfig {
+            id: "test".to_string(),
+            service_provider: "cloud".to_string(),
+            local_endpoint: None,
+            local_api_key: None,
+            cloud_endpoint: endpoint.map(str::to_string),
+            cloud_api_key: Some("sk-test".to_string()),
+            whisper_model: None,
+            created_at: chrono::Utc::now(),
+            updated_at: chrono::Utc::now(),
+            enable_vad: false,
+            cloud_provider: provider.to_string(),
+            cloud_model: Some("whisper-large-v3".to_string()),
+            cloud_language: Some("zh".to_string()),
+            fallback_chain: None,
+            name: "Default".to_string(),
+            is_active: true,
+            key_unavailable: false,
+        }
+    }
+
+    #[test]
+    fn test_saved_provider_model_and_language_reach_the_processor() {
+        let config = CloudAsrConfig::from_config(&saved("openai", Some("https://asr.example.com/v1"))).unwrap();
+        let processor = CloudAsrProcessor::with_config(config).unwrap();
+        let options = processor.options(Mode::Transcriptions, "");
+        assert_eq!(options.provider, CloudAsrProvider::OpenAi);
+        assert_eq!(options.model.as_deref(), Some("whisper-large-v3"));
+        assert_eq!(options.language.as_deref(), Some("zh"));
+        assert_eq!(processor.get_processor_type(), "cloud-openai");
+
+        let custom = CloudAsrConfig::from_config(&saved("custom", Some("http://127.0.0.1:5001/inference"))).unwrap();
+        assert_eq!(CloudAsrProcessor::with_config(custom).unwrap().get_processor_type(), "cloud");
+        assert!(CloudAsrConfig::from_config(&saved("custom", Some("  "))).is_none());
+    }
+
+    #[test]
+    fn test_openai_processor_defaults_to_openai_endpoint() {
+        let config = CloudAsrConfig::openai(&saved("custom", None));
+        assert_eq!(config.endpoint, DEFAULT_OPENAI_ENDPOINT);
+        assert_eq!(config.options.provider, CloudAsrProvider::OpenAi);
+        assert_eq!(config.options.model.as_deref(), Some("whisper-large-v3"));
+    }
+}
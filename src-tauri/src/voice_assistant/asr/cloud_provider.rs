@@ -0,0 +1,312 @@
+use crate::voice_assistant::{AsrTranscript, VoiceError};
+use crate::voice_assistant::net::{send_with_retry, RetryBudget};
+use reqwest::multipart;
+use serde_json::Value;
+
+/// OpenAI 兼容接口未指定模型时使用的 model 字段
+pub const DEFAULT_OPENAI_ASR_MODEL: &str = "whisper-1";
+
+/// 云端 ASR 的请求格式
+/// - Custom: 自建服务器格式，multipart "audio" 字段 + X-API-Key
+/// - OpenAi: /v1/audio/transcriptions，multipart "file" + "model"，Bearer 认证（OpenAI、Groq、faster-whisper-server 等）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CloudAsrProvider {
+    #[default]
+    Custom,
+    OpenAi,
+}
+
+impl CloudAsrProvider {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "custom" => Some(Self::Custom),
+            "openai" => Some(Self::OpenAi),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Custom => "custom",
+            Self::OpenAi => "openai",
+        }
+    }
+}
+
+/// 一次云端识别请求的参数
+#[derive(Debug, Clone, Default)]
+pub struct CloudAsrOptions {
+    pub provider: CloudAsrProvider,
+    pub model: Option<String>,
+    pub language: Option<String>,
+    pub prompt: Option<String>,
+    /// OpenAI 格式下改用 /audio/translations（翻译成英文）
+    pub translate: bool,
+}
+
+impl CloudAsrOptions {
+    pub fn new(provider: CloudAsrProvider) -> Self {
+        Self { provider, ..Default::default() }
+    }
+
+    /// 从保存的 ASR 配置读取请求格式、模型和语言
+    pub fn from_config(config: &crate::database::AsrConfig) -> Self {
+        Self {
+            provider: CloudAsrProvider::parse(&config.cloud_provider).unwrap_or_default(),
+            model: non_empty(config.cloud_model.as_deref()),
+            language: non_empty(config.cloud_language.as_deref()),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    /// 实际请求的 URL；OpenAI 格式可以只填服务地址或 /v1 前缀
+    pub fn request_url(&self, endpoint: &str) -> String {
+        let endpoint = endpoint.trim().trim_end_matches('/');
+        match self.provider {
+            CloudAsrProvider::Custom => endpoint.to_string(),
+            CloudAsrProvider::OpenAi => {
+                if endpoint.ends_with("/audio/transcriptions") || endpoint.ends_with("/audio/translations") {
+                    return endpoint.to_string();
+                }
+                let path = if self.translate { "audio/translations" } else { "audio/transcriptions" };
+                if endpoint.ends_with("/v1") {
+                    format!("{}/{}", endpoint, path)
+                } else {
+                    format!("{}/v1/{}", endpoint, path)
+                }
+            }
+        }
+    }
+
+    /// 构造请求；multipart 发送后会被消耗，重试时需要重新构造
+    pub fn build_request(
+        &self,
+        client: &reqwest::Client,
+        endpoint: &str,
+        api_key: Option<&str>,
+        audio_data: Vec<u8>,
+    ) -> Result<reqwest::RequestBuilder, reqwest::Error> {
+        let api_key = api_key.map(clean_api_key).filter(|k| !k.is_empty());
+
+        let request = match self.provider {
+            CloudAsrProvider::Custom => {
+                let form = multipart::Form::new()
+                    .part("audio", multipart::Part::bytes(audio_data)
+                        .file_name("audio.wav")
+                        .mime_str("audio/wav")?);
+                let request = client.post(self.request_url(endpoint)).multipart(form);
+                match api_key {
+                    Some(key) => request.header("X-API-Key", key),
+                    None => request,
+                }
+            }
+            CloudAsrProvider::OpenAi => {
+                let mut form = multipart::Form::new()
+                    .part("file", multipart::Part::bytes(audio_data)
+                        .file_name("audio.wav")
+                        .mime_str("audio/wav")?)
+                    .text("model", self.model.clone().unwrap_or_else(|| DEFAULT_OPENAI_ASR_MODEL.to_string()))
+                    .text("response_format", "json");
+                // 翻译接口固定输出英文，不接受 language
+                if let Some(language) = self.language.clone().filter(|_| !self.translate) {
+                    form = form.text("language", language);
+                }
+                if let Some(prompt) = self.prompt.clone().filter(|p| !p.is_empty()) {
+                    form = form.text("prompt", prompt);
+                }
+                let request = client.post(self.request_url(endpoint)).multipart(form);
+                match api_key {
+                    Some(key) => request.bearer_auth(key),
+                    None => request,
+                }
+            }
+        };
+        Ok(request)
+    }
+
+    /// 从响应中取出识别文本；OpenAI 格式找不到文本时返回 None
+    pub fn parse_response(&self, body: &str) -> Option<String> {
+        match self.provider {
+            CloudAsrProvider::Custom => Some(parse_custom_response(body)),
+            CloudAsrProvider::OpenAi => parse_openai_response(body),
+        }
+    }
+
+    /// 发送识别请求，按全局重试设置处理 5xx/429 和连接错误，供运行时的云端处理器使用。
+    /// 请求次数随结果返回
+    pub async fn transcribe(
+        &self,
+        client: &reqwest::Client,
+        endpoint: &str,
+        api_key: Option<&str>,
+        audio_data: Vec<u8>,
+    ) -> Result<AsrTranscript, VoiceError> {
+        let sent = send_with_retry("Cloud ASR", &RetryBudget::start(), || {
+            Ok(self.build_request(client, endpoint, api_key, audio_data.clone())?)
+        })
+        .await?;
+        let response = sent.response;
+
+        let status = response.status();
+        let body = response.text().await?;
+        if !status.is_success() {
+            return Err(VoiceError::Other(format!("Cloud ASR error: {} - {}", status, body)));
+        }
+
+        let text = self.parse_response(&body)
+            .ok_or_else(|| VoiceError::Other("No text in cloud ASR response".to_string()))?;
+        Ok(AsrTranscript {
+            request_attempts: Some(sent.attempts),
+            ..AsrTranscript::from(text)
+        })
+    }
+}
+
+/// OpenAI 兼容服务的模型列表地址，用于不产生识别费用的连通性和 key 检查
+pub fn models_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    if endpoint.ends_with("/v1") {
+        format!("{}/models", endpoint)
+    } else {
+        format!("{}/v1/models", endpoint)
+    }
+}
+
+/// 请求模型列表，检查服务是否可达、API key 是否有效
+pub async fn verify_api_key(client: &reqwest::Client, endpoint: &str, api_key: &str) -> Result<(), VoiceError> {
+    let url = models_url(endpoint);
+    let response = client
+        .get(&url)
+        .bearer_auth(clean_api_key(api_key))
+        .timeout(std::time::Duration::from_secs(5))
+        .send()
+        .await
+        .map_err(|e| VoiceError::Other(format!("Cloud ASR endpoint unreachable ({}): {}", url, e)))?;
+
+    let status = response.status();
+    if status.is_success() {
+        Ok(())
+    } else if status == reqwest::StatusCode::UNAUTHORIZED || status == reqwest::StatusCode::FORBIDDEN {
+        Err(VoiceError::Other(format!("API key rejected by {} ({})", url, status)))
+    } else {
+        Err(VoiceError::Other(format!("Cloud ASR health check failed: {} ({})", status, url)))
+    }
+}
+
+fn non_empty(value: Option<&str>) -> Option<String> {
+    value.map(str::trim).filter(|v| !v.is_empty()).map(str::to_string)
+}
+
+/// 去掉误粘贴的 "API_KEY=" 前缀和首尾空白
+pub fn clean_api_key(key: &str) -> String {
+    let trimmed = key.trim();
+    trimmed.strip_prefix("API_KEY=").unwrap_or(trimmed).to_string()
+}
+
+/// 自建服务器的响应格式不统一，按常见字段依次尝试，都没有时返回原始内容
+fn parse_custom_response(body: &str) -> String {
+    let Ok(json_data) = serde_json::from_str::<Value>(body) else {
+        tracing::warn!("⚠️ Response is not JSON, using raw text");
+        return body.to_string();
+    };
+
+    // Structured response format: {"code":0,"msg":"ok","data":"transcription_text"}
+    for field in ["data", "text", "transcription", "result"] {
+        if let Some(text) = json_data.get(field).and_then(|v| v.as_str()) {
+            return text.to_string();
+        }
+    }
+
+    if let Some(result) = json_data.get("result") {
+        if let Some(text) = result.get("text").and_then(|v| v.as_str()) {
+            return text.to_string();
+        }
+        tracing::warn!("⚠️ Could not extract transcription from result");
+        tracing::trace!("Unrecognized result payload: {}", crate::voice_assistant::redact(&result.to_string()));
+        return format!("Complex response: {}", json_data);
+    }
+
+    tracing::warn!("⚠️ Unknown JSON format, using raw response");
+    body.to_string()
+}
+
+/// json 和 verbose_json 都有顶层 "text"；个别兼容服务只返回 segments
+fn parse_openai_response(body: &str) -> Option<String> {
+    let json_data = serde_json::from_str::<Value>(body).ok()?;
+
+    if let Some(text) = json_data.get("text").and_then(|v| v.as_str()) {
+        return Some(text.trim().to_string());
+    }
+
+    let segments = json_data.get("segments")?.as_array()?;
+    let text = segments
+        .iter()
+        .filter_map(|segment| segment.get("text").and_then(|v| v.as_str()))
+        .map(str::trim)
+        .filter(|t| !t.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ");
+    Some(text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openai_request_url() {
+        let options = CloudAsrOptions::new(CloudAsrProvider::OpenAi);
+        assert_eq!(options.request_url("https://api.openai.com"), "https://api.openai.com/v1/audio/transcriptions");
+        assert_eq!(options.request_url("https://api.groq.com/openai/v1/"), "https://api.groq.com/openai/v1/audio/transcriptions");
+        assert_eq!(options.request_url("http://localhost:8000/v1/audio/transcriptions"), "http://localhost:8000/v1/audio/transcriptions");
+
+        let translate = CloudAsrOptions { translate: true, ..options };
+        assert_eq!(translate.request_url("https://api.openai.com/v1"), "https://api.openai.com/v1/audio/translations");
+
+        let custom = CloudAsrOptions::new(CloudAsrProvider::Custom);
+        assert_eq!(custom.request_url("http://localhost:5000/asr"), "http://localhost:5000/asr");
+    }
+
+    #[test]
+    fn test_models_url() {
+        assert_eq!(models_url("https://api.groq.com/openai/v1/"), "https://api.groq.com/openai/v1/models");
+        assert_eq!(models_url("https://api.siliconflow.cn"), "https://api.siliconflow.cn/v1/models");
+    }
+
+    #[test]
+    fn test_parse_openai_json_and_verbose_json() {
+        let options = CloudAsrOptions::new(CloudAsrProvider::OpenAi);
+        assert_eq!(options.parse_response(r#"{"text":" hello world "}"#).as_deref(), Some("hello world"));
+
+        let verbose = r#"{"task":"transcribe","language":"english","duration":1.5,"text":"hello world","segments":[{"id":0,"text":" hello world"}]}"#;
+        assert_eq!(options.parse_response(verbose).as_deref(), Some("hello world"));
+
+        let segments_only = r#"{"segments":[{"text":" hello"},{"text":" world"}]}"#;
+        assert_eq!(options.parse_response(segments_only).as_deref(), Some("hello world"));
+
+        assert_eq!(options.parse_response(r#"{"error":{"message":"bad"}}"#), None);
+        assert_eq!(options.parse_response("not json"), None);
+    }
+
+    #[test]
+    fn test_parse_custom_response_formats() {
+        let options = CloudAsrOptions::new(CloudAsrProvider::Custom);
+        assert_eq!(options.parse_response(r#"{"code":0,"msg":"ok","data":"你好"}"#).as_deref(), Some("你好"));
+        assert_eq!(options.parse_response(r#"{"result":{"text":"nested"}}"#).as_deref(), Some("nested"));
+        assert_eq!(options.parse_response("plain text").as_deref(), Some("plain text"));
+    }
+
+    #[test]
+    fn test_provider_parse_and_clean_key() {
+        assert_eq!(CloudAsrProvider::parse(" OpenAI "), Some(CloudAsrProvider::OpenAi));
+        assert_eq!(CloudAsrProvider::parse("custom"), Some(CloudAsrProvider::Custom));
+        assert_eq!(CloudAsrProvider::parse("azure"), None);
+        assert_eq!(clean_api_key(" API_KEY=sk-123 "), "sk-123");
+        assert_eq!(clean_api_key("sk-456"), "sk-456");
+    }
+}
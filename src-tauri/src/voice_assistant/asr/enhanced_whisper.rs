@@ -2,7 +2,7 @@ use std::io::Cursor;
 use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use whisper_rs::{WhisperContext, FullParams, SamplingStrategy, WhisperContextParameters};
-use crate::voice_assistant::{AsrProcessor, Mode, VoiceError};
+use crate::voice_assistant::{AsrProcessor, AsrTranscript, Mode, VoiceError};
 use super::{WhisperRSConfig, WhisperVadProcessor, VadSegment, filter_silence};
 
 pub struct EnhancedWhisperProcessor {
@@ -289,7 +289,7 @@ impl AsrProcessor for EnhancedWhisperProcessor {
         audio_buffer: Cursor<Vec<u8>>,
         _mode: Mode,
         _prompt: &str,
-    ) -> Result<String, VoiceError> {
+    ) -> Result<AsrTranscript, VoiceError> {
         // Convert byte buffer to f32 audio samples
         let audio_data = self.convert_bytes_to_f32(audio_buffer.into_inner())?;
 
@@ -301,15 +301,16 @@ impl AsrProcessor for EnhancedWhisperProcessor {
         };
 
         // Process with VAD if available
-        if self.vad_processor.is_some() {
-            self.process_with_vad(&audio_data, mode)
+        let text = if self.vad_processor.is_some() {
+            self.process_with_vad(&audio_data, mode)?
         } else {
-            self.process_audio_data(&audio_data, mode)
-        }
+            self.process_audio_data(&audio_data, mode)?
+        };
+        Ok(AsrTranscript::from(text))
     }
 
-    fn get_processor_type(&self) -> Option<&str> {
-        Some("enhanced-whisper-rs")
+    fn get_processor_type(&self) -> &str {
+        "enhanced-whisper-rs"
     }
 }
 
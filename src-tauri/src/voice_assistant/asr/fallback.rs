@@ -0,0 +1,212 @@
+use std::io::Cursor;
+use std::sync::Arc;
+use tracing::{info, warn};
+use crate::voice_assistant::{AsrProcessor, AsrTranscript, Mode, VoiceError};
+use crate::voice_assistant::asr::race::CancelToken;
+
+/// 按顺序尝试多个 ASR 处理器，第一个成功的结果被采用
+pub struct FallbackAsrProcessor {
+    processors: Vec<Arc<dyn AsrProcessor + Send + Sync>>,
+}
+
+impl FallbackAsrProcessor {
+    pub fn new(processors: Vec<Arc<dyn AsrProcessor + Send + Sync>>) -> Result<Self, VoiceError> {
+        if processors.is_empty() {
+            return Err(VoiceError::Other("ASR fallback chain is empty".to_string()));
+        }
+        Ok(Self { processors })
+    }
+
+    pub fn processor_types(&self) -> Vec<String> {
+        self.processors.iter().map(|p| p.get_processor_type().to_string()).collect()
+    }
+}
+
+impl AsrProcessor for FallbackAsrProcessor {
+    fn process_audio(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.process_audio_cancellable(audio_buffer, mode, prompt, &CancelToken::default())
+    }
+
+    /// 取消标记传给当前尝试的处理器，取消后不再尝试后面的处理器
+    fn process_audio_cancellable(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+        cancel: &CancelToken,
+    ) -> Result<AsrTranscript, VoiceError> {
+        let audio = audio_buffer.into_inner();
+        let mut errors = Vec::new();
+
+        for (index, processor) in self.processors.iter().enumerate() {
+            if cancel.is_cancelled() {
+                return Err(VoiceError::Other("ASR request aborted".to_string()));
+            }
+            match processor.process_audio_cancellable(Cursor::new(audio.clone()), mode, prompt, cancel) {
+                Ok(transcript) => {
+                    if index > 0 {
+                        info!("🔄 ASR fallback: {} succeeded after {} failure(s)", processor.get_processor_type(), index);
+                    }
+                    // 实际使用的处理器随结果返回，并发识别时不会互相覆盖
+                    return Ok(transcript.attributed_to(processor.as_ref()));
+                }
+                // 没有说话不是处理器故障，换一个处理器结果也一样
+                Err(VoiceError::NoSpeech) => return Err(VoiceError::NoSpeech),
+                Err(e) => {
+                    warn!("⚠️ ASR processor {} failed: {}", processor.get_processor_type(), e);
+                    errors.push(format!("{}: {}", processor.get_processor_type(), e));
+                }
+            }
+        }
+
+        Err(VoiceError::Other(format!("All ASR processors failed ({})", errors.join("; "))))
+    }
+
+    /// 链的标识取主处理器；某次识别实际使用的处理器见 AsrTranscript::processor_type
+    fn get_processor_type(&self) -> &str {
+        self.processors[0].get_processor_type()
+    }
+
+    fn chain_members(&self) -> Vec<Arc<dyn AsrProcessor + Send + Sync>> {
+        self.processors.clone()
+    }
+
+    /// 链中第一个本地 whisper 的后端
+    fn whisper_backend(&self) -> Option<crate::voice_assistant::asr::whisper_rs::WhisperBackend> {
+        self.processors.iter().find_map(|processor| processor.whisper_backend())
+    }
+
+    /// 链中任意一个处理器可用即可
+    fn health_check(&self) -> Result<(), VoiceError> {
+        let mut errors = Vec::new();
+        for processor in &self.processors {
+            match processor.health_check() {
+                Ok(()) => return Ok(()),
+                Err(e) => errors.push(format!("{}: {}", processor.get_processor_type(), e)),
+            }
+        }
+        Err(VoiceError::Other(errors.join("; ")))
+    }
+
+    fn unload(&mut self) {
+        for processor in &mut self.processors {
+            match Arc::get_mut(processor) {
+                Some(processor) => processor.unload(),
+                // 其他地方仍持有引用，最后一个引用释放时由处理器的 Drop 卸载
+                None => warn!("⚠️ ASR processor {} is still shared, unloading when released", processor.get_processor_type()),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    struct MockProcessor {
+        name: &'static str,
+        result: Result<&'static str, &'static str>,
+        calls: AtomicU32,
+        unloads: Arc<AtomicU32>,
+    }
+
+    impl MockProcessor {
+        fn new(name: &'static str, result: Result<&'static str, &'static str>) -> Arc<Self> {
+            Arc::new(Self { name, result, calls: AtomicU32::new(0), unloads: Arc::default() })
+        }
+    }
+
+    impl AsrProcessor for MockProcessor {
+        fn process_audio(&self, _audio: Cursor<Vec<u8>>, _mode: Mode, _prompt: &str) -> Result<AsrTranscript, VoiceError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            self.result
+                .map(|text| AsrTranscript::new(text.to_string(), None))
+                .map_err(|e| if e == "no-speech" { VoiceError::NoSpeech } else { VoiceError::Other(e.to_string()) })
+        }
+
+        fn get_processor_type(&self) -> &str {
+            self.name
+        }
+
+        fn unload(&mut self) {
+            self.unloads.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn chain(processors: &[&Arc<MockProcessor>]) -> FallbackAsrProcessor {
+        let processors = processors
+            .iter()
+            .map(|p| Arc::clone(*p) as Arc<dyn AsrProcessor + Send + Sync>)
+            .collect();
+        FallbackAsrProcessor::new(processors).unwrap()
+    }
+
+    #[test]
+    fn test_second_processor_used_when_first_fails() {
+        let first = MockProcessor::new("whisper-rs", Err("model crashed"));
+        let second = MockProcessor::new("cloud-groq", Ok("hello"));
+        let chain = chain(&[&first, &second]);
+
+        let transcript = chain.process_audio(Cursor::new(vec![1, 2, 3]), Mode::Transcriptions, "").unwrap();
+        assert_eq!(transcript.text, "hello");
+        assert_eq!(first.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(second.calls.load(Ordering::SeqCst), 1);
+        assert_eq!(transcript.processor_type.as_deref(), Some("cloud-groq"));
+        // 链本身的标识不随某次识别变化
+        assert_eq!(chain.get_processor_type(), "whisper-rs");
+    }
+
+    #[test]
+    fn test_first_success_stops_the_chain() {
+        let first = MockProcessor::new("local", Ok("hi"));
+        let second = MockProcessor::new("cloud-groq", Ok("unused"));
+        let chain = chain(&[&first, &second]);
+
+        let transcript = chain.process_audio(Cursor::new(vec![]), Mode::Transcriptions, "").unwrap();
+        assert_eq!(transcript.text, "hi");
+        assert_eq!(transcript.processor_type.as_deref(), Some("local"));
+        assert_eq!(second.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_no_speech_does_not_fall_back() {
+        let first = MockProcessor::new("whisper-rs", Err("no-speech"));
+        let second = MockProcessor::new("cloud-groq", Ok("unused"));
+        let chain = chain(&[&first, &second]);
+
+        let result = chain.process_audio(Cursor::new(vec![]), Mode::Transcriptions, "");
+        assert!(matches!(result, Err(VoiceError::NoSpeech)));
+        assert_eq!(second.calls.load(Ordering::SeqCst), 0);
+    }
+
+    #[test]
+    fn test_all_failures_are_reported() {
+        let chain = chain(&[
+            &MockProcessor::new("whisper-rs", Err("model crashed")),
+            &MockProcessor::new("cloud-groq", Err("HTTP 503")),
+        ]);
+
+        let err = chain.process_audio(Cursor::new(vec![]), Mode::Transcriptions, "").unwrap_err().to_string();
+        assert!(err.contains("whisper-rs: model crashed"));
+        assert!(err.contains("cloud-groq: HTTP 503"));
+        assert!(FallbackAsrProcessor::new(vec![]).is_err());
+    }
+
+    #[test]
+    fn test_unload_reaches_inner_processors() {
+        let first = MockProcessor::new("whisper-rs", Ok("hi"));
+        let second = MockProcessor::new("cloud-groq", Ok("unused"));
+        let (first_unloads, second_unloads) = (Arc::clone(&first.unloads), Arc::clone(&second.unloads));
+        let mut chain = FallbackAsrProcessor::new(vec![first as Arc<dyn AsrProcessor + Send + Sync>, second]).unwrap();
+
+        chain.unload();
+        assert_eq!(first_unloads.load(Ordering::SeqCst), 1);
+        assert_eq!(second_unloads.load(Ordering::SeqCst), 1);
+    }
+}
@@ -84,15 +84,17 @@ impl GpuDetector {
                 return false;
             }
         } else {
-            // Linux/macOS CUDA检测 - 只检查nvidia-smi可执行文件存在性
-            if std::path::Path::new("/usr/bin/nvidia-smi").exists() ||
-               std::path::Path::new("/usr/local/bin/nvidia-smi").exists() {
-                println!("🚀 NVIDIA nvidia-smi binary found");
-                println!("⚠️ Skipping nvidia-smi execution to avoid potential hangs");
+            // Linux CUDA检测 - 驱动加载并识别到显卡后才有 /proc/driver/nvidia/gpus 下的条目和 /dev/nvidia0，
+            // 只装了 nvidia-smi 不代表有可用设备
+            let has_gpu_entry = std::fs::read_dir("/proc/driver/nvidia/gpus")
+                .map(|mut entries| entries.next().is_some())
+                .unwrap_or(false);
+            if has_gpu_entry || std::path::Path::new("/dev/nvidia0").exists() {
+                tracing::info!("🚀 NVIDIA GPU device found");
                 return true;
             }
 
-            println!("❌ NVIDIA nvidia-smi not found");
+            tracing::info!("❌ No NVIDIA GPU device found");
             false
         }
     }
@@ -1,6 +1,7 @@
 use std::io::Cursor;
 use reqwest::multipart;
-use crate::voice_assistant::{AsrProcessor, Mode, VoiceError};
+use crate::voice_assistant::{AsrProcessor, AsrTranscript, Mode, VoiceError};
+use crate::voice_assistant::net::{send_with_retry, RetryBudget};
 use serde_json::Value;
 use std::time::Duration;
 
@@ -17,6 +18,8 @@ pub struct LocalASRProcessor {
 }
 
 impl LocalASRProcessor {
+    pub const PROCESSOR_TYPE: &'static str = "local";
+
     pub fn new() -> Result<Self, VoiceError> {
         let api_url = std::env::var("LOCAL_ASR_URL")
             .unwrap_or_else(|_| "http://192.168.8.107:5001/inference".to_string());
@@ -49,23 +52,26 @@ impl LocalASRProcessor {
         })
     }
 
-    async fn call_api_with_format(&self, audio_data: &[u8], lang: &str, format: String) -> Result<String, VoiceError> {
-        let form = multipart::Form::new()
-            .part("file", multipart::Part::bytes(audio_data.to_vec())
-                .file_name("audio.wav")
-                .mime_str("audio/wav")?)
-            .text("response_format", format.clone())
-            .text("language", lang.to_string());
-
+    /// 返回响应内容和这次的请求次数（含重试）
+    async fn call_api_with_format(&self, audio_data: &[u8], lang: &str, format: String, budget: &RetryBudget) -> Result<(String, u32), VoiceError> {
         println!("🔍 Sending ASR request with format={}, language={}", format, lang);
 
-        let response = self.client
-            .post(&self.api_url)
-            .header("X-API-KEY", &self.api_key)
-            .multipart(form)
-            .send()
-            .await
-            .map_err(|e| VoiceError::Network(e))?;
+        // multipart 表单发送后会被消耗，每次尝试重新构造
+        let sent = send_with_retry("Local ASR", budget, || {
+            let form = multipart::Form::new()
+                .part("file", multipart::Part::bytes(audio_data.to_vec())
+                    .file_name("audio.wav")
+                    .mime_str("audio/wav")?)
+                .text("response_format", format.clone())
+                .text("language", lang.to_string());
+            Ok(self.client
+                .post(&self.api_url)
+                .header("X-API-KEY", &self.api_key)
+                .multipart(form))
+        })
+        .await?;
+        let attempts = sent.attempts;
+        let response = sent.response;
 
         let status = response.status();
         if !status.is_success() {
@@ -79,24 +85,32 @@ impl LocalASRProcessor {
         println!("🔍 ASR response ({} format): {} chars", format, response_text.len());
         println!("📄 Response preview: {}", &response_text[..response_text.len().min(200)]);
 
-        Ok(response_text)
+        Ok((response_text, attempts))
     }
 
-    async fn call_api(&self, audio_data: &[u8], lang: &str) -> Result<String, VoiceError> {
+    /// 依次尝试各个响应格式，请求次数累计所有格式的尝试
+    async fn call_api(&self, audio_data: &[u8], lang: &str) -> Result<AsrTranscript, VoiceError> {
         // 🔥 根据API错误信息，优化格式尝试顺序：SRT → JSON → Text
         let formats = vec!["srt", "json", "text"];
+        // 所有格式共用一个重试预算，避免逐个格式各自重试拖太久
+        let budget = RetryBudget::start();
+        let mut attempts = 0;
 
         for format in formats {
             println!("🔄 Trying response format: {}", format);
 
-            match self.call_api_with_format(audio_data, lang, format.to_string()).await {
-                Ok(response_text) => {
+            match self.call_api_with_format(audio_data, lang, format.to_string(), &budget).await {
+                Ok((response_text, format_attempts)) => {
+                    attempts += format_attempts;
                     // 处理响应
                     let processed_text = self.process_response(&response_text, format)?;
 
                     if !processed_text.is_empty() {
                         println!("✅ Successfully processed response with {} format", format);
-                        return Ok(processed_text);
+                        return Ok(AsrTranscript {
+                            request_attempts: Some(attempts),
+                            ..AsrTranscript::from(processed_text)
+                        });
                     } else {
                         println!("⚠️ Empty result with {} format, trying next...", format);
                     }
@@ -214,7 +228,7 @@ impl AsrProcessor for LocalASRProcessor {
         audio_buffer: Cursor<Vec<u8>>,
         mode: Mode,
         _prompt: &str,
-    ) -> Result<String, VoiceError> {
+    ) -> Result<AsrTranscript, VoiceError> {
         let rt = tokio::runtime::Runtime::new()
             .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
 
@@ -229,7 +243,53 @@ impl AsrProcessor for LocalASRProcessor {
         })
     }
 
-    fn get_processor_type(&self) -> Option<&str> {
-        Some("local")
+    fn get_processor_type(&self) -> &str {
+        Self::PROCESSOR_TYPE
+    }
+
+    fn health_check(&self) -> Result<(), VoiceError> {
+        let url = health_url(&self.api_url)?;
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
+
+        rt.block_on(async {
+            let response = self.client
+                .get(&url)
+                .timeout(HEALTH_CHECK_TIMEOUT)
+                .send()
+                .await
+                .map_err(|e| VoiceError::Other(format!("Local ASR endpoint unreachable ({}): {}", url, e)))?;
+
+            let status = response.status();
+            // 没有实现 /health 的服务返回 404，也说明服务在线
+            if status.is_success() || status == reqwest::StatusCode::NOT_FOUND {
+                Ok(())
+            } else {
+                Err(VoiceError::Other(format!("Local ASR health check failed: {} ({})", status, url)))
+            }
+        })
+    }
+}
+
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// 识别接口所在服务的 /health 地址
+fn health_url(api_url: &str) -> Result<String, VoiceError> {
+    let mut url = reqwest::Url::parse(api_url.trim())
+        .map_err(|e| VoiceError::Other(format!("Invalid local ASR endpoint {}: {}", api_url, e)))?;
+    url.set_path("/health");
+    url.set_query(None);
+    Ok(url.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_url_uses_service_root() {
+        assert_eq!(health_url("http://192.168.8.107:5001/inference").unwrap(), "http://192.168.8.107:5001/health");
+        assert_eq!(health_url("https://asr.example.com/v1/asr?x=1").unwrap(), "https://asr.example.com/health");
+        assert!(health_url("not a url").is_err());
     }
 }
\ No newline at end of file
@@ -0,0 +1,112 @@
+use crate::voice_assistant::asr::whisper_rs::WhisperBackend;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use tracing::warn;
+
+/// 推理时的工作缓冲（KV cache、计算图）大约是模型文件大小的 30%
+const WORKING_BUFFER_OVERHEAD: f64 = 0.3;
+/// 设为 true 时跳过内存预检，由用户自行承担风险
+pub const SKIP_MEMORY_CHECK_ENV: &str = "WHISPER_SKIP_MEMORY_CHECK";
+
+const MB: u64 = 1024 * 1024;
+
+/// 加载模型的内存估算
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MemoryEstimate {
+    pub required_mb: u64,
+    /// 模型加载到显存（CUDA）时为 true，此时比较的是可用显存
+    pub uses_vram: bool,
+    /// 可用内存/显存，无法查询时为 None（不阻止加载）
+    pub available_mb: Option<u64>,
+}
+
+impl MemoryEstimate {
+    pub fn fits(&self) -> bool {
+        self.available_mb.map_or(true, |available| self.required_mb <= available)
+    }
+}
+
+/// 根据模型文件大小估算需要的内存（MB）
+pub fn estimate_required_mb(file_size_bytes: u64) -> u64 {
+    ((file_size_bytes as f64 * (1.0 + WORKING_BUFFER_OVERHEAD)) / MB as f64).ceil() as u64
+}
+
+pub fn skip_memory_check() -> bool {
+    std::env::var(SKIP_MEMORY_CHECK_ENV)
+        .map(|v| v.trim().eq_ignore_ascii_case("true") || v.trim() == "1")
+        .unwrap_or(false)
+}
+
+/// 估算模型在指定后端上的内存需求和当前可用量。
+/// CUDA 后端查询 nvidia-smi 的可用显存，其他后端（包括共享内存的 Metal）比较系统可用内存
+pub fn estimate_for_backend(file_size_bytes: u64, backend: &WhisperBackend) -> MemoryEstimate {
+    let required_mb = estimate_required_mb(file_size_bytes);
+    let vram_mb = match backend {
+        WhisperBackend::CUDA => {
+            let device = crate::voice_assistant::settings::current().whisper_gpu_device_id.unwrap_or(0) as usize;
+            crate::commands::gpu_backend::query_nvidia_free_vram_mb(device)
+        }
+        _ => None,
+    };
+
+    match vram_mb {
+        Some(available) => MemoryEstimate { required_mb, uses_vram: true, available_mb: Some(available) },
+        None => MemoryEstimate {
+            required_mb,
+            uses_vram: false,
+            available_mb: crate::utils::platform::available_memory_bytes().map(|bytes| bytes / MB),
+        },
+    }
+}
+
+/// 加载前的内存预检，放不下时返回带建议的错误。可通过 WHISPER_SKIP_MEMORY_CHECK=true 跳过
+pub fn preflight(model_path: &str, backend: &WhisperBackend) -> Result<MemoryEstimate, String> {
+    let file_size = std::fs::metadata(Path::new(model_path))
+        .map_err(|e| format!("Failed to read model file {}: {}", model_path, e))?
+        .len();
+    let estimate = estimate_for_backend(file_size, backend);
+
+    if estimate.fits() {
+        return Ok(estimate);
+    }
+    if skip_memory_check() {
+        warn!(
+            "⚠️ Model needs ~{}MB but only {}MB is available; loading anyway ({} is set)",
+            estimate.required_mb,
+            estimate.available_mb.unwrap_or(0),
+            SKIP_MEMORY_CHECK_ENV
+        );
+        return Ok(estimate);
+    }
+
+    Err(format!(
+        "Not enough {} to load this model: needs ~{}MB, {}MB available. \
+         Try a smaller or quantized model (e.g. ggml-base or a q5/q8 variant), \
+         or set {}=true to load it anyway",
+        if estimate.uses_vram { "GPU memory" } else { "system memory" },
+        estimate.required_mb,
+        estimate.available_mb.unwrap_or(0),
+        SKIP_MEMORY_CHECK_ENV
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_estimate_required_mb_adds_working_buffers() {
+        // ggml-large-v3 约 3.1GB
+        assert_eq!(estimate_required_mb(3_100 * MB), 4_030);
+        assert_eq!(estimate_required_mb(0), 0);
+    }
+
+    #[test]
+    fn test_estimate_fits() {
+        let estimate = |available_mb| MemoryEstimate { required_mb: 4_030, uses_vram: true, available_mb };
+        assert!(!estimate(Some(3_800)).fits());
+        assert!(estimate(Some(8_000)).fits());
+        // 查询不到可用量时不阻止加载
+        assert!(estimate(None).fits());
+    }
+}
@@ -2,8 +2,16 @@ pub mod whisper;
 pub mod sensevoice;
 pub mod local_asr;
 pub mod whisper_rs;
+pub mod whisper_worker;
 pub mod vad_processor;
 pub mod gpu_detector;
+pub mod memory_check;
+pub mod model_file;
+pub mod models;
+pub mod cloud_provider;
+pub mod cloud_asr;
+pub mod fallback;
+pub mod race;
 // pub mod enhanced_whisper;
 
 pub use whisper::*;
@@ -12,4 +20,41 @@ pub use local_asr::*;
 pub use whisper_rs::*;
 pub use vad_processor::*;
 pub use gpu_detector::*;
-// pub use enhanced_whisper::*;
\ No newline at end of file
+pub use cloud_provider::*;
+pub use cloud_asr::*;
+pub use fallback::*;
+pub use race::*;
+// pub use enhanced_whisper::*;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice_assistant::AsrProcessor;
+
+    #[test]
+    fn test_processor_types_are_stable() {
+        let local = LocalASRProcessor::with_config(LocalASRConfig {
+            endpoint: "http://127.0.0.1:5001/inference".to_string(),
+            api_key: "test".to_string(),
+        })
+        .unwrap();
+        assert_eq!(local.get_processor_type(), "local");
+
+        let groq = WhisperProcessor::with_config("test-key".to_string(), "https://api.groq.com".to_string()).unwrap();
+        assert_eq!(groq.get_processor_type(), "cloud-groq");
+
+        let sensevoice = SenseVoiceProcessor::with_config("test-key".to_string(), "https://api.siliconflow.cn".to_string()).unwrap();
+        assert_eq!(sensevoice.get_processor_type(), "cloud-sensevoice");
+
+        let cloud = CloudAsrProcessor::with_config(CloudAsrConfig {
+            endpoint: DEFAULT_OPENAI_ENDPOINT.to_string(),
+            api_key: Some("test-key".to_string()),
+            options: CloudAsrOptions::new(CloudAsrProvider::OpenAi),
+        })
+        .unwrap();
+        assert_eq!(cloud.get_processor_type(), "cloud-openai");
+
+        // whisper-rs 需要真实模型才能创建，这里只检查常量
+        assert_eq!(WhisperRSProcessor::PROCESSOR_TYPE, "whisper-rs");
+    }
+}
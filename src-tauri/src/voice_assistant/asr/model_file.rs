@@ -0,0 +1,417 @@
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+use std::path::Path;
+
+/// whisper.cpp 模型文件开头的魔数（"ggml"，小端 u32）
+const GGML_MAGIC: u32 = 0x6767_6d6c;
+/// gguf 格式的魔数（"GGUF"）
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+/// ftype 中包含量化版本号：ftype = version * 1000 + 类型
+const GGML_QNT_VERSION_FACTOR: i32 = 1000;
+
+/// 模型的权重精度/量化类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantization {
+    F32,
+    F16,
+    Q4_0,
+    Q4_1,
+    Q5_0,
+    Q5_1,
+    Q8_0,
+}
+
+impl Quantization {
+    /// 文件名后缀（"-q5_0"）中的写法，F16 是官方模型的默认精度，文件名中没有后缀
+    pub fn parse(suffix: &str) -> Option<Self> {
+        match suffix.trim().to_ascii_lowercase().as_str() {
+            "f32" => Some(Self::F32),
+            "f16" => Some(Self::F16),
+            "q4_0" => Some(Self::Q4_0),
+            "q4_1" => Some(Self::Q4_1),
+            "q5_0" => Some(Self::Q5_0),
+            "q5_1" => Some(Self::Q5_1),
+            "q8_0" => Some(Self::Q8_0),
+            _ => None,
+        }
+    }
+
+    /// ggml 头中的 ftype（去掉量化版本号后）
+    fn from_ftype(ftype: i32) -> Option<Self> {
+        match ftype % GGML_QNT_VERSION_FACTOR {
+            0 => Some(Self::F32),
+            1 => Some(Self::F16),
+            2 => Some(Self::Q4_0),
+            3 | 4 => Some(Self::Q4_1),
+            7 => Some(Self::Q8_0),
+            8 => Some(Self::Q5_0),
+            9 => Some(Self::Q5_1),
+            _ => None,
+        }
+    }
+
+    pub fn is_quantized(&self) -> bool {
+        !matches!(self, Self::F32 | Self::F16)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::F32 => "f32",
+            Self::F16 => "f16",
+            Self::Q4_0 => "q4_0",
+            Self::Q4_1 => "q4_1",
+            Self::Q5_0 => "q5_0",
+            Self::Q5_1 => "q5_1",
+            Self::Q8_0 => "q8_0",
+        }
+    }
+
+    /// 相对 F16 模型的文件大小比例（按官方模型实际大小估算）
+    fn size_ratio(&self) -> f64 {
+        match self {
+            Self::F32 => 2.0,
+            Self::F16 => 1.0,
+            Self::Q8_0 => 0.54,
+            Self::Q5_0 | Self::Q5_1 => 0.37,
+            Self::Q4_0 | Self::Q4_1 => 0.31,
+        }
+    }
+
+    pub fn quality_hint(&self) -> &'static str {
+        match self {
+            Self::F32 | Self::F16 => "full accuracy",
+            Self::Q8_0 => "near full accuracy",
+            Self::Q5_0 | Self::Q5_1 => "slightly lower accuracy",
+            Self::Q4_0 | Self::Q4_1 => "lower accuracy",
+        }
+    }
+
+    /// 同一尺寸的量化模型中优先选择精度更高的
+    fn preference(&self) -> u8 {
+        match self {
+            Self::F32 => 6,
+            Self::F16 => 5,
+            Self::Q8_0 => 4,
+            Self::Q5_1 => 3,
+            Self::Q5_0 => 2,
+            Self::Q4_1 => 1,
+            Self::Q4_0 => 0,
+        }
+    }
+}
+
+impl std::fmt::Display for Quantization {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.as_str().to_ascii_uppercase())
+    }
+}
+
+/// 模型尺寸档位
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelSize {
+    Tiny,
+    Base,
+    Small,
+    Medium,
+    Large,
+    LargeTurbo,
+}
+
+impl ModelSize {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Self::Tiny => "Tiny",
+            Self::Base => "Base",
+            Self::Small => "Small",
+            Self::Medium => "Medium",
+            Self::Large => "Large",
+            Self::LargeTurbo => "Large V3 Turbo",
+        }
+    }
+
+    /// F16 模型文件的大致大小（MB）
+    fn f16_size_mb(&self) -> f64 {
+        match self {
+            Self::Tiny => 75.0,
+            Self::Base => 142.0,
+            Self::Small => 466.0,
+            Self::Medium => 1500.0,
+            Self::Large => 2900.0,
+            Self::LargeTurbo => 1550.0,
+        }
+    }
+
+    /// 编码器/解码器层数对应的尺寸，用于校验文件头
+    fn from_layers(audio_layers: i32, text_layers: i32) -> Option<Self> {
+        match (audio_layers, text_layers) {
+            (4, _) => Some(Self::Tiny),
+            (6, _) => Some(Self::Base),
+            (12, _) => Some(Self::Small),
+            (24, _) => Some(Self::Medium),
+            (32, 4) => Some(Self::LargeTurbo),
+            (32, _) => Some(Self::Large),
+            _ => None,
+        }
+    }
+}
+
+/// 从文件名（和文件头）解析出的模型信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelFileInfo {
+    pub size: Option<ModelSize>,
+    pub quantization: Quantization,
+    pub english_only: bool,
+}
+
+impl ModelFileInfo {
+    /// 按尺寸和量化类型估算的文件大小（MB），未知尺寸时为 None
+    pub fn expected_size_mb(&self) -> Option<f64> {
+        self.size.map(|size| size.f16_size_mb() * self.quantization.size_ratio())
+    }
+
+    /// 模型列表中显示的类型，如 "Medium Q5_0 (~555MB, slightly lower accuracy)"
+    pub fn label(&self, actual_size_mb: f64) -> String {
+        let Some(size) = self.size else {
+            return match self.quantization {
+                Quantization::F16 => format!("Custom ({:.1}MB)", actual_size_mb),
+                quantization => format!("Custom {} ({:.1}MB)", quantization, actual_size_mb),
+            };
+        };
+
+        let mut name = size.label().to_string();
+        if self.english_only {
+            name.push_str(" (English)");
+        }
+        if self.quantization.is_quantized() {
+            name = format!("{} {}", name, self.quantization);
+        }
+        // 文件看起来完整时显示实际大小，否则显示该档位的参考大小
+        let size_mb = if actual_size_mb > 1.0 { actual_size_mb } else { self.expected_size_mb().unwrap_or(0.0) };
+        format!("{} (~{}, {})", name, format_size(size_mb), self.quantization.quality_hint())
+    }
+
+    /// 同一尺寸档位的另一种精度
+    pub fn is_variant_of(&self, other: &ModelFileInfo) -> bool {
+        self.size.is_some() && self.size == other.size && self.english_only == other.english_only
+    }
+}
+
+fn format_size(size_mb: f64) -> String {
+    if size_mb >= 1024.0 {
+        format!("{:.1}GB", size_mb / 1024.0)
+    } else {
+        format!("{:.0}MB", size_mb)
+    }
+}
+
+/// 按官方命名（ggml-<size>[.en][-q5_0].bin）解析文件名；没有量化后缀的视为 F16
+pub fn parse_model_file_name(file_name: &str) -> ModelFileInfo {
+    let name = file_name.to_ascii_lowercase();
+    let stem = name.strip_suffix(".bin").unwrap_or(&name);
+    let stem = stem.strip_prefix("ggml-").unwrap_or(stem);
+
+    let quantization = stem
+        .rsplit_once('-')
+        .and_then(|(_, suffix)| Quantization::parse(suffix))
+        .unwrap_or(Quantization::F16);
+
+    let size = if stem.contains("large") {
+        Some(if stem.contains("turbo") { ModelSize::LargeTurbo } else { ModelSize::Large })
+    } else if stem.contains("medium") {
+        Some(ModelSize::Medium)
+    } else if stem.contains("small") {
+        Some(ModelSize::Small)
+    } else if stem.contains("base") {
+        Some(ModelSize::Base)
+    } else if stem.contains("tiny") {
+        Some(ModelSize::Tiny)
+    } else {
+        None
+    };
+
+    ModelFileInfo {
+        size,
+        quantization,
+        english_only: stem.contains(".en"),
+    }
+}
+
+/// 从 whisper.cpp 模型文件头读到的信息
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelHeader {
+    pub size: Option<ModelSize>,
+    pub quantization: Option<Quantization>,
+}
+
+/// 读取 ggml 文件头（魔数 + 11 个 i32 超参数）。gguf 文件只确认格式，不解析元数据；
+/// 不是模型文件时返回 None
+pub fn read_model_header(path: &Path) -> Option<ModelHeader> {
+    let mut buf = [0u8; 48];
+    let mut file = std::fs::File::open(path).ok()?;
+    let read = file.read(&mut buf).ok()?;
+    parse_model_header(&buf[..read])
+}
+
+fn parse_model_header(bytes: &[u8]) -> Option<ModelHeader> {
+    if bytes.len() >= 4 && &bytes[..4] == GGUF_MAGIC {
+        return Some(ModelHeader { size: None, quantization: None });
+    }
+
+    let int_at = |index: usize| {
+        let offset = 4 + index * 4;
+        bytes.get(offset..offset + 4).map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    };
+    let magic = bytes.get(..4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))?;
+    if magic != GGML_MAGIC {
+        return None;
+    }
+
+    // n_vocab, n_audio_ctx, n_audio_state, n_audio_head, n_audio_layer,
+    // n_text_ctx, n_text_state, n_text_head, n_text_layer, n_mels, ftype
+    let size = match (int_at(4), int_at(8)) {
+        (Some(audio_layers), Some(text_layers)) => ModelSize::from_layers(audio_layers, text_layers),
+        _ => None,
+    };
+    Some(ModelHeader {
+        size,
+        quantization: int_at(10).and_then(Quantization::from_ftype),
+    })
+}
+
+/// 文件名解析结果，文件头可读时以文件头为准
+pub fn inspect_model_file(path: &Path) -> ModelFileInfo {
+    let file_name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+    let mut info = parse_model_file_name(file_name);
+    if let Some(header) = read_model_header(path) {
+        if let Some(quantization) = header.quantization {
+            info.quantization = quantization;
+        }
+        if header.size.is_some() {
+            info.size = header.size;
+        }
+    }
+    info
+}
+
+/// 在候选文件中找与 `model` 同尺寸的其他精度版本，按精度从高到低排列
+pub fn variants_of<'a>(model: &Path, candidates: &'a [std::path::PathBuf]) -> Vec<&'a std::path::PathBuf> {
+    let info = inspect_model_file(model);
+    let mut variants: Vec<(&std::path::PathBuf, ModelFileInfo)> = candidates
+        .iter()
+        .filter(|path| path.as_path() != model)
+        .map(|path| (path, inspect_model_file(path)))
+        .filter(|(_, candidate)| candidate.is_variant_of(&info) && candidate.quantization != info.quantization)
+        .collect();
+    variants.sort_by_key(|(_, candidate)| std::cmp::Reverse(candidate.quantization.preference()));
+    variants.into_iter().map(|(path, _)| path).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_official_model_names() {
+        use ModelSize::*;
+        use Quantization::*;
+
+        let cases = [
+            ("ggml-tiny.bin", Tiny, F16, false),
+            ("ggml-tiny.en.bin", Tiny, F16, true),
+            ("ggml-tiny-q5_1.bin", Tiny, Q5_1, false),
+            ("ggml-tiny.en-q5_1.bin", Tiny, Q5_1, true),
+            ("ggml-tiny-q8_0.bin", Tiny, Q8_0, false),
+            ("ggml-tiny.en-q8_0.bin", Tiny, Q8_0, true),
+            ("ggml-base.bin", Base, F16, false),
+            ("ggml-base.en.bin", Base, F16, true),
+            ("ggml-base-q5_1.bin", Base, Q5_1, false),
+            ("ggml-base.en-q5_1.bin", Base, Q5_1, true),
+            ("ggml-base-q8_0.bin", Base, Q8_0, false),
+            ("ggml-base.en-q8_0.bin", Base, Q8_0, true),
+            ("ggml-small.bin", Small, F16, false),
+            ("ggml-small.en.bin", Small, F16, true),
+            ("ggml-small.en-tdrz.bin", Small, F16, true),
+            ("ggml-small-q5_1.bin", Small, Q5_1, false),
+            ("ggml-small.en-q5_1.bin", Small, Q5_1, true),
+            ("ggml-small-q8_0.bin", Small, Q8_0, false),
+            ("ggml-small.en-q8_0.bin", Small, Q8_0, true),
+            ("ggml-medium.bin", Medium, F16, false),
+            ("ggml-medium.en.bin", Medium, F16, true),
+            ("ggml-medium-q5_0.bin", Medium, Q5_0, false),
+            ("ggml-medium.en-q5_0.bin", Medium, Q5_0, true),
+            ("ggml-medium-q8_0.bin", Medium, Q8_0, false),
+            ("ggml-medium.en-q8_0.bin", Medium, Q8_0, true),
+            ("ggml-large-v1.bin", Large, F16, false),
+            ("ggml-large-v2.bin", Large, F16, false),
+            ("ggml-large-v2-q5_0.bin", Large, Q5_0, false),
+            ("ggml-large-v2-q8_0.bin", Large, Q8_0, false),
+            ("ggml-large-v3.bin", Large, F16, false),
+            ("ggml-large-v3-q5_0.bin", Large, Q5_0, false),
+            ("ggml-large-v3-turbo.bin", LargeTurbo, F16, false),
+            ("ggml-large-v3-turbo-q5_0.bin", LargeTurbo, Q5_0, false),
+            ("ggml-large-v3-turbo-q8_0.bin", LargeTurbo, Q8_0, false),
+        ];
+
+        for (name, size, quantization, english_only) in cases {
+            let info = parse_model_file_name(name);
+            assert_eq!(info, ModelFileInfo { size: Some(size), quantization, english_only }, "{}", name);
+        }
+
+        let custom = parse_model_file_name("my-finetune-Q4_0.bin");
+        assert_eq!((custom.size, custom.quantization), (None, Q4_0));
+    }
+
+    #[test]
+    fn test_labels_show_quantization_and_realistic_size() {
+        let medium_q5 = parse_model_file_name("ggml-medium-q5_0.bin");
+        assert_eq!(medium_q5.label(514.0), "Medium Q5_0 (~514MB, slightly lower accuracy)");
+        assert_eq!(parse_model_file_name("ggml-medium.bin").label(1463.0), "Medium (~1.4GB, full accuracy)");
+        assert_eq!(parse_model_file_name("ggml-base.en.bin").label(0.0), "Base (English) (~142MB, full accuracy)");
+        assert_eq!(parse_model_file_name("custom.bin").label(12.5), "Custom (12.5MB)");
+    }
+
+    fn ggml_header(audio_layers: i32, text_layers: i32, ftype: i32) -> Vec<u8> {
+        let mut bytes = GGML_MAGIC.to_le_bytes().to_vec();
+        for value in [51865, 1500, 1024, 16, audio_layers, 448, 1024, 16, text_layers, 80, ftype] {
+            bytes.extend_from_slice(&value.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn test_parse_model_header() {
+        // 量化版本 2 的 q5_0 medium 模型
+        let header = parse_model_header(&ggml_header(24, 24, 2008)).unwrap();
+        assert_eq!(header, ModelHeader { size: Some(ModelSize::Medium), quantization: Some(Quantization::Q5_0) });
+
+        let header = parse_model_header(&ggml_header(32, 4, 1)).unwrap();
+        assert_eq!(header, ModelHeader { size: Some(ModelSize::LargeTurbo), quantization: Some(Quantization::F16) });
+
+        assert!(parse_model_header(b"GGUF\x03\x00\x00\x00").unwrap().quantization.is_none());
+        assert!(parse_model_header(b"not a model").is_none());
+    }
+
+    #[test]
+    fn test_header_overrides_misleading_file_name() {
+        let dir = std::env::temp_dir().join(format!("voicetype-model-file-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let renamed = dir.join("ggml-medium.bin");
+        std::fs::write(&renamed, ggml_header(24, 24, 1007)).unwrap();
+        assert_eq!(inspect_model_file(&renamed).quantization, Quantization::Q8_0);
+
+        let full = dir.join("ggml-small.bin");
+        let q5 = dir.join("ggml-small-q5_1.bin");
+        let q8 = dir.join("ggml-small-q8_0.bin");
+        let other = dir.join("ggml-base-q8_0.bin");
+        for path in [&full, &q5, &q8, &other] {
+            std::fs::write(path, b"").unwrap();
+        }
+        let candidates = vec![full.clone(), q5.clone(), q8.clone(), other];
+        assert_eq!(variants_of(&full, &candidates), vec![&q8, &q5]);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
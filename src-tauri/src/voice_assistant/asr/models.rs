@@ -0,0 +1,256 @@
+//! 查找要加载的 Whisper 模型文件。热键识别、设置页的识别测试和 CPU 处理器共用同一套顺序和模型目录，
+//! 避免测试页和热键加载不同的模型
+use std::path::{Path, PathBuf};
+use tracing::{debug, info, warn};
+use crate::voice_assistant::asr::whisper_rs::WhisperBackend;
+use crate::voice_assistant::VoiceError;
+
+/// 设置中没有激活模型时，模型目录里按这个顺序选第一个存在的（精度优先，同尺寸的量化版本在后）
+pub const MODEL_PREFERENCES: &[&str] = &[
+    "ggml-large-v3-turbo-q5_0.bin",
+    "ggml-large-v3-turbo.bin",
+    "ggml-large-v3-turbo-q8_0.bin",
+    "ggml-large-v2.bin",
+    "ggml-large-v2-q5_0.bin",
+    "ggml-large-v3.bin",
+    "ggml-large-v3-q5_0.bin",
+    "ggml-medium.bin",
+    "ggml-medium-q5_0.bin",
+    "ggml-small.bin",
+    "ggml-small-q5_1.bin",
+    "ggml-base.bin",
+    "ggml-base-q5_1.bin",
+];
+
+/// 强制 CPU 推理时的顺序：大模型在 CPU 上太慢，small/base 优先，其余按尺寸从小到大
+pub const CPU_MODEL_PREFERENCES: &[&str] = &[
+    "ggml-small.bin",
+    "ggml-small-q5_1.bin",
+    "ggml-base.bin",
+    "ggml-base-q5_1.bin",
+    "ggml-medium-q5_0.bin",
+    "ggml-medium.bin",
+    "ggml-large-v3-turbo-q5_0.bin",
+    "ggml-large-v3-turbo-q8_0.bin",
+    "ggml-large-v3-turbo.bin",
+    "ggml-large-v3-q5_0.bin",
+    "ggml-large-v2-q5_0.bin",
+    "ggml-large-v3.bin",
+    "ggml-large-v2.bin",
+];
+
+/// 模型的用途，决定内存预检按哪个后端估算
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelPurpose {
+    /// 热键听写和设置页的识别测试，使用配置的（或自动选择的）后端
+    Dictation,
+    /// 强制在 CPU 上推理
+    Cpu,
+}
+
+impl ModelPurpose {
+    fn backend(&self) -> WhisperBackend {
+        match self {
+            ModelPurpose::Dictation => crate::voice_assistant::global_whisper::default_backend(),
+            ModelPurpose::Cpu => WhisperBackend::CPU,
+        }
+    }
+
+    fn preferences(&self) -> &'static [&'static str] {
+        match self {
+            ModelPurpose::Dictation => MODEL_PREFERENCES,
+            ModelPurpose::Cpu => CPU_MODEL_PREFERENCES,
+        }
+    }
+}
+
+/// 当前 ASR 配置方案中选择的模型（asr_configs.whisper_model），没有选择时为 None
+pub async fn configured_whisper_model() -> Option<String> {
+    crate::commands::get_asr_config_internal().await.ok()?
+        .into_iter()
+        .next()?
+        .whisper_model
+        .filter(|model| !model.trim().is_empty())
+}
+
+/// 配置方案中的模型可以是完整路径、模型目录中的文件名，或模型名（"base" 对应 ggml-base.bin）
+fn configured_model_path(model: &str, models_dir: &Path) -> Option<PathBuf> {
+    let model = model.trim();
+    let path = Path::new(model);
+    let candidates = if path.is_absolute() {
+        vec![path.to_path_buf()]
+    } else {
+        vec![models_dir.join(model), models_dir.join(format!("ggml-{}.bin", model))]
+    };
+    candidates.into_iter().find(|candidate| candidate.is_file())
+}
+
+/// 查找顺序：设置中激活的模型（存在时）、`preferences`、模型目录中按文件名排序的任意 .bin
+pub fn find_model(active_model: Option<&str>, models_dir: &Path, preferences: &[&str]) -> Option<PathBuf> {
+    if let Some(active) = active_model {
+        if Path::new(active).exists() {
+            info!("✅ Using active model from settings: {}", active);
+            return Some(PathBuf::from(active));
+        }
+        warn!("⚠️ Active model doesn't exist: {}", active);
+    }
+
+    debug!("🔍 Searching for models in: {}", models_dir.display());
+    if let Some(preferred) = preferences.iter().map(|model| models_dir.join(model)).find(|path| path.exists()) {
+        info!("✅ Found model: {}", preferred.display());
+        return Some(preferred);
+    }
+
+    warn!("⚠️ No preferred models found, searching for any .bin files...");
+    let mut others: Vec<PathBuf> = std::fs::read_dir(models_dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    others.retain(|path| path.is_file() && path.extension().and_then(|s| s.to_str()) == Some("bin"));
+    others.sort();
+    let found = others.into_iter().next();
+    if let Some(ref path) = found {
+        info!("✅ Found alternative model: {}", path.display());
+    }
+    found
+}
+
+/// 内存预检显示选中的模型放不下时，改用模型目录中同尺寸、能放下的量化版本（精度高的优先）；
+/// 没有合适的版本时保持原选择，由加载时的预检报错
+fn prefer_variant_that_fits(path: PathBuf, models_dir: &Path, backend: &WhisperBackend) -> PathBuf {
+    use crate::voice_assistant::asr::{memory_check, model_file};
+
+    let Err(reason) = memory_check::preflight(&path.to_string_lossy(), backend) else {
+        return path;
+    };
+
+    let candidates: Vec<PathBuf> = std::fs::read_dir(models_dir)
+        .map(|entries| entries.flatten().map(|entry| entry.path()).collect())
+        .unwrap_or_default();
+    let fitting = model_file::variants_of(&path, &candidates)
+        .into_iter()
+        .find(|variant| memory_check::preflight(&variant.to_string_lossy(), backend).is_ok())
+        .cloned();
+
+    match fitting {
+        Some(variant) => {
+            warn!("⚠️ {}", reason);
+            info!("🪶 Using quantized variant that fits in memory: {}", variant.display());
+            variant
+        }
+        None => path,
+    }
+}
+
+/// 配置方案中选择的模型优先，其次按 find_model 的顺序。
+/// 找不到模型时返回 ModelNotFound，列出查找过的位置，前端据此提示下载模型
+fn locate_model(
+    configured_model: Option<&str>,
+    active_model: Option<&str>,
+    models_dir: &Path,
+    preferences: &[&str],
+) -> Result<PathBuf, VoiceError> {
+    if let Some(model) = configured_model {
+        if let Some(path) = configured_model_path(model, models_dir) {
+            info!("✅ Using model from ASR config: {}", path.display());
+            return Ok(path);
+        }
+        warn!("⚠️ Model from ASR config doesn't exist: {}", model);
+    }
+
+    find_model(active_model, models_dir, preferences).ok_or_else(|| {
+        warn!("⚠️ Whisper model not found. Please download a model to {}/", models_dir.display());
+        info!("💡 Recommended models for CPU: ggml-base.bin (fastest) or ggml-small.bin (balanced)");
+        info!("📥 Download from: https://huggingface.co/ggerganov/whisper.cpp/tree/main");
+        let mut searched: Vec<String> = configured_model.into_iter().chain(active_model).map(str::to_string).collect();
+        searched.push(models_dir.display().to_string());
+        VoiceError::ModelNotFound { searched }
+    })
+}
+
+/// 加载模型前的检查：按设置中激活的模型和模型目录确定模型文件，不存在时直接报错，
+/// 不要把不存在的路径交给 whisper 加载
+pub fn resolve_active_model() -> Result<PathBuf, VoiceError> {
+    let models_dir = crate::utils::platform::get_models_dir();
+    let active = crate::voice_assistant::settings::current().whisper_model_path;
+    locate_model(None, active.as_deref(), &models_dir, MODEL_PREFERENCES)
+}
+
+/// 配置方案中的模型（见 configured_whisper_model）优先，其次是设置中激活的模型和按用途排序的模型目录；
+/// 内存放不下时换用同尺寸的量化版本
+pub fn resolve_whisper_model(purpose: ModelPurpose, configured_model: Option<&str>) -> Result<String, VoiceError> {
+    let models_dir = crate::utils::platform::get_models_dir();
+    let active = crate::voice_assistant::settings::current().whisper_model_path;
+    let path = locate_model(configured_model, active.as_deref(), &models_dir, purpose.preferences())?;
+    let path = prefer_variant_that_fits(path, &models_dir, &purpose.backend());
+    Ok(path.to_string_lossy().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn models_dir(files: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("voicetype-models-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for file in files {
+            std::fs::write(dir.join(file), b"").unwrap();
+        }
+        dir
+    }
+
+    #[test]
+    fn test_active_model_wins_when_it_exists() {
+        let dir = models_dir(&["ggml-large-v3-turbo.bin", "custom.bin"]);
+        let active = dir.join("custom.bin");
+        assert_eq!(find_model(Some(&active.to_string_lossy()), &dir, MODEL_PREFERENCES), Some(active));
+
+        // 激活的模型已被删除时回退到偏好列表
+        let missing = dir.join("ggml-tiny.bin");
+        assert_eq!(find_model(Some(&missing.to_string_lossy()), &dir, MODEL_PREFERENCES), Some(dir.join("ggml-large-v3-turbo.bin")));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_preference_order_then_any_bin() {
+        let dir = models_dir(&["ggml-base.bin", "ggml-medium-q5_0.bin", "ggml-small.bin"]);
+        assert_eq!(find_model(None, &dir, MODEL_PREFERENCES), Some(dir.join("ggml-medium-q5_0.bin")));
+        // CPU 上 small/base 优先
+        assert_eq!(find_model(None, &dir, ModelPurpose::Cpu.preferences()), Some(dir.join("ggml-small.bin")));
+        std::fs::remove_dir_all(&dir).ok();
+
+        let dir = models_dir(&["zz-custom.bin", "my-finetune.bin", "notes.txt"]);
+        assert_eq!(find_model(None, &dir, MODEL_PREFERENCES), Some(dir.join("my-finetune.bin")));
+        std::fs::remove_dir_all(&dir).ok();
+
+        let dir = models_dir(&["notes.txt"]);
+        assert_eq!(find_model(None, &dir, MODEL_PREFERENCES), None);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_configured_model_wins_over_active_model() {
+        let dir = models_dir(&["ggml-base.bin", "ggml-small.bin", "custom.bin"]);
+        let active = dir.join("ggml-small.bin").to_string_lossy().to_string();
+        let locate = |configured: &str| locate_model(Some(configured), Some(&active), &dir, MODEL_PREFERENCES).unwrap();
+
+        assert_eq!(locate("base"), dir.join("ggml-base.bin"));
+        assert_eq!(locate("custom.bin"), dir.join("custom.bin"));
+        assert_eq!(locate(&dir.join("custom.bin").to_string_lossy()), dir.join("custom.bin"));
+        // 配置的模型不存在时使用激活的模型
+        assert_eq!(locate("large-v3"), dir.join("ggml-small.bin"));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_empty_models_dir_reports_model_not_found() {
+        let dir = models_dir(&[]);
+        let missing = dir.join("ggml-base.bin").to_string_lossy().to_string();
+        match locate_model(None, Some(&missing), &dir, MODEL_PREFERENCES) {
+            Err(VoiceError::ModelNotFound { searched }) => {
+                assert_eq!(searched, vec![missing, dir.display().to_string()]);
+            }
+            other => panic!("expected ModelNotFound, got {:?}", other),
+        }
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
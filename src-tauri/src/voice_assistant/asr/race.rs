@@ -0,0 +1,289 @@
+//! 竞速模式：同一段录音同时交给本地 whisper 和云端识别，先成功返回的结果被采用，较慢的一方被中止。
+//! 短句通常本地更快，长句通常云端更快；会额外产生云端费用，默认关闭（设置项 asr_race_mode）。
+//! 每次调用使用单独的 CancelToken：进程内 whisper 通过 abort 回调停止推理，worker 进程中的推理通过取消命令停止，云端请求直接取消
+use std::future::Future;
+use std::io::Cursor;
+use std::sync::mpsc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use tokio::sync::Notify;
+use tracing::{info, warn};
+use crate::voice_assistant::{AsrProcessor, AsrTranscript, Mode, VoiceError};
+
+/// 一次竞速的结果，随识别结果返回（AsrTranscript::race），写入延迟记录供统计页面比较两边的耗时
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RaceOutcome {
+    /// 胜出的处理器标识
+    pub winner: Option<String>,
+    /// 本地/云端各自完成（成功或失败）的耗时；被中止的一方为 None
+    pub local_ms: Option<i64>,
+    pub cloud_ms: Option<i64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RaceSide {
+    Local,
+    Cloud,
+}
+
+pub struct RaceAsrProcessor {
+    local: Arc<dyn AsrProcessor + Send + Sync>,
+    cloud: Arc<dyn AsrProcessor + Send + Sync>,
+}
+
+impl RaceAsrProcessor {
+    pub fn new(local: Arc<dyn AsrProcessor + Send + Sync>, cloud: Arc<dyn AsrProcessor + Send + Sync>) -> Self {
+        Self { local, cloud }
+    }
+
+    fn processor(&self, side: RaceSide) -> &Arc<dyn AsrProcessor + Send + Sync> {
+        match side {
+            RaceSide::Local => &self.local,
+            RaceSide::Cloud => &self.cloud,
+        }
+    }
+}
+
+impl AsrProcessor for RaceAsrProcessor {
+    fn process_audio(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+    ) -> Result<AsrTranscript, VoiceError> {
+        let audio = audio_buffer.into_inner();
+        let started = Instant::now();
+        let (sender, receiver) = mpsc::channel();
+        // 每一边单独的取消标记，只中止这次竞速中输掉的调用
+        let local_cancel = CancelToken::default();
+        let cloud_cancel = CancelToken::default();
+        for (side, cancel) in [(RaceSide::Local, &local_cancel), (RaceSide::Cloud, &cloud_cancel)] {
+            let processor = Arc::clone(self.processor(side));
+            let sender = sender.clone();
+            let audio = audio.clone();
+            let prompt = prompt.to_string();
+            let cancel = cancel.clone();
+            // 输掉的一方被中止后很快返回，结果发到已关闭的通道里被丢弃
+            std::thread::spawn(move || {
+                let result = processor.process_audio_cancellable(Cursor::new(audio), mode, &prompt, &cancel);
+                let _ = sender.send((side, result, started.elapsed().as_millis() as i64));
+            });
+        }
+        drop(sender);
+
+        let mut outcome = RaceOutcome::default();
+        let mut errors = Vec::new();
+        while let Ok((side, result, elapsed_ms)) = receiver.recv() {
+            match side {
+                RaceSide::Local => outcome.local_ms = Some(elapsed_ms),
+                RaceSide::Cloud => outcome.cloud_ms = Some(elapsed_ms),
+            }
+            let processor = self.processor(side);
+            match result {
+                // 没有说话也是有效结果，不用再等另一边
+                Ok(_) | Err(VoiceError::NoSpeech) => {
+                    let (loser, loser_cancel) = match side {
+                        RaceSide::Local => (self.processor(RaceSide::Cloud), &cloud_cancel),
+                        RaceSide::Cloud => (self.processor(RaceSide::Local), &local_cancel),
+                    };
+                    if outcome.local_ms.is_none() || outcome.cloud_ms.is_none() {
+                        info!("🏁 ASR race won by {} in {}ms, aborting {}", processor.get_processor_type(), elapsed_ms, loser.get_processor_type());
+                        loser_cancel.cancel();
+                    }
+                    outcome.winner = Some(processor.get_processor_type().to_string());
+                    // 胜出方和耗时随结果返回，并发识别时不会互相覆盖
+                    return result.map(|transcript| AsrTranscript {
+                        race: Some(outcome),
+                        ..transcript.attributed_to(processor.as_ref())
+                    });
+                }
+                Err(e) => {
+                    warn!("⚠️ ASR race: {} failed after {}ms: {}", processor.get_processor_type(), elapsed_ms, e);
+                    errors.push(format!("{}: {}", processor.get_processor_type(), e));
+                }
+            }
+        }
+
+        Err(VoiceError::Other(format!("All ASR processors failed ({})", errors.join("; "))))
+    }
+
+    /// 竞速的标识取本地处理器；某次识别实际胜出的处理器见 AsrTranscript::processor_type
+    fn get_processor_type(&self) -> &str {
+        self.local.get_processor_type()
+    }
+
+    fn whisper_backend(&self) -> Option<crate::voice_assistant::asr::whisper_rs::WhisperBackend> {
+        self.local.whisper_backend()
+    }
+
+    /// 任意一边可用即可参与竞速
+    fn health_check(&self) -> Result<(), VoiceError> {
+        match (self.local.health_check(), self.cloud.health_check()) {
+            (Err(local), Err(cloud)) => Err(VoiceError::Other(format!(
+                "{}: {}; {}: {}",
+                self.local.get_processor_type(), local, self.cloud.get_processor_type(), cloud
+            ))),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// 一次识别调用的取消标记，每次调用单独创建：中止只影响持有它的那次调用，
+/// 同一处理器上之后或并发的识别不受影响
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(Arc<CancelState>);
+
+#[derive(Debug, Default)]
+struct CancelState {
+    cancelled: AtomicBool,
+    notify: Notify,
+}
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.cancelled.store(true, Ordering::SeqCst);
+        self.0.notify.notify_waiters();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.cancelled.load(Ordering::SeqCst)
+    }
+
+    /// 运行云端请求，取消时直接丢弃请求 future，reqwest 随之断开连接
+    pub async fn run<T>(&self, request: impl Future<Output = Result<T, VoiceError>>) -> Result<T, VoiceError> {
+        // 先注册等待再检查标记，两者之间到达的取消不会丢失
+        let cancelled = self.0.notify.notified();
+        if self.is_cancelled() {
+            return Err(VoiceError::Other("Request aborted".to_string()));
+        }
+        tokio::select! {
+            result = request => result,
+            () = cancelled => Err(VoiceError::Other("Request aborted".to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+    use std::time::Duration;
+
+    struct MockProcessor {
+        name: &'static str,
+        delay: Duration,
+        result: Result<&'static str, &'static str>,
+        /// 每次调用收到的取消标记
+        cancels: Mutex<Vec<CancelToken>>,
+    }
+
+    impl MockProcessor {
+        fn new(name: &'static str, delay_ms: u64, result: Result<&'static str, &'static str>) -> Arc<Self> {
+            Arc::new(Self { name, delay: Duration::from_millis(delay_ms), result, cancels: Mutex::new(Vec::new()) })
+        }
+
+        fn aborted(&self) -> bool {
+            self.cancels.lock().unwrap().iter().any(CancelToken::is_cancelled)
+        }
+    }
+
+    impl AsrProcessor for MockProcessor {
+        fn process_audio(&self, audio: Cursor<Vec<u8>>, mode: Mode, prompt: &str) -> Result<AsrTranscript, VoiceError> {
+            self.process_audio_cancellable(audio, mode, prompt, &CancelToken::default())
+        }
+
+        fn process_audio_cancellable(&self, _audio: Cursor<Vec<u8>>, _mode: Mode, _prompt: &str, cancel: &CancelToken) -> Result<AsrTranscript, VoiceError> {
+            self.cancels.lock().unwrap().push(cancel.clone());
+            std::thread::sleep(self.delay);
+            self.result.map(|text| AsrTranscript::new(text.to_string(), None)).map_err(|e| VoiceError::Other(e.to_string()))
+        }
+
+        fn get_processor_type(&self) -> &str {
+            self.name
+        }
+    }
+
+    fn race(local: &Arc<MockProcessor>, cloud: &Arc<MockProcessor>) -> RaceAsrProcessor {
+        RaceAsrProcessor::new(Arc::clone(local) as _, Arc::clone(cloud) as _)
+    }
+
+    fn transcribe(processor: &RaceAsrProcessor) -> Result<AsrTranscript, VoiceError> {
+        processor.process_audio(Cursor::new(vec![0u8; 44]), Mode::Transcriptions, "")
+    }
+
+    #[test]
+    fn test_faster_result_wins_and_slower_is_aborted() {
+        let local = MockProcessor::new("whisper-rs", 300, Ok("slow local"));
+        let cloud = MockProcessor::new("cloud-groq", 10, Ok("fast cloud"));
+        let processor = race(&local, &cloud);
+
+        let transcript = transcribe(&processor).unwrap();
+        assert_eq!(transcript.text, "fast cloud");
+        assert_eq!(transcript.processor_type.as_deref(), Some("cloud-groq"));
+        assert!(local.aborted());
+        assert!(!cloud.aborted());
+
+        let outcome = transcript.race.unwrap();
+        assert_eq!(outcome.winner.as_deref(), Some("cloud-groq"));
+        assert!(outcome.cloud_ms.is_some());
+        assert_eq!(outcome.local_ms, None);
+    }
+
+    #[test]
+    fn test_failure_on_one_side_does_not_fail_dictation() {
+        let local = MockProcessor::new("whisper-rs", 5, Err("model crashed"));
+        let cloud = MockProcessor::new("cloud-groq", 50, Ok("hello"));
+        let processor = race(&local, &cloud);
+
+        let transcript = transcribe(&processor).unwrap();
+        assert_eq!(transcript.text, "hello");
+        let outcome = transcript.race.unwrap();
+        assert_eq!(outcome.winner.as_deref(), Some("cloud-groq"));
+        // 两边都完成了，都有耗时，不需要中止
+        assert!(outcome.local_ms.is_some() && outcome.cloud_ms.is_some());
+        assert!(!local.aborted());
+    }
+
+    #[test]
+    fn test_both_failures_are_reported() {
+        let processor = race(
+            &MockProcessor::new("whisper-rs", 0, Err("model crashed")),
+            &MockProcessor::new("cloud-groq", 0, Err("HTTP 503")),
+        );
+        let err = transcribe(&processor).unwrap_err().to_string();
+        assert!(err.contains("whisper-rs: model crashed"));
+        assert!(err.contains("cloud-groq: HTTP 503"));
+    }
+
+    #[test]
+    fn test_abort_does_not_carry_over_to_next_call() {
+        let local = MockProcessor::new("whisper-rs", 300, Ok("slow local"));
+        let cloud = MockProcessor::new("cloud-groq", 10, Ok("fast cloud"));
+        let processor = race(&local, &cloud);
+        assert_eq!(transcribe(&processor).unwrap().text, "fast cloud");
+
+        // 输掉的调用可能还在运行，下一次竞速使用新的标记，不会被上一次的中止影响，也不会清除它
+        assert_eq!(transcribe(&processor).unwrap().text, "fast cloud");
+        let cancels = local.cancels.lock().unwrap();
+        assert_eq!(cancels.len(), 2);
+        assert!(cancels.iter().all(CancelToken::is_cancelled));
+        assert!(!Arc::ptr_eq(&cancels[0].0, &cancels[1].0));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_token_cancels_pending_request() {
+        let cancel = CancelToken::default();
+        let canceller = cancel.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            canceller.cancel();
+        });
+        let result = cancel.run(std::future::pending::<Result<(), VoiceError>>()).await;
+        assert!(result.unwrap_err().to_string().contains("aborted"));
+
+        // 已取消的标记不再发出请求；新的标记不受影响
+        assert!(cancel.run(async { Ok(1) }).await.is_err());
+        assert_eq!(CancelToken::default().run(async { Ok(1) }).await.unwrap(), 1);
+    }
+}
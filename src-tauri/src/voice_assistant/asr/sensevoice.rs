@@ -1,22 +1,32 @@
 use std::io::Cursor;
-use reqwest::multipart;
-use crate::voice_assistant::{AsrProcessor, Mode, VoiceError, TranslateProcessor};
-use serde_json::Value;
+use crate::voice_assistant::{AsrProcessor, AsrTranscript, Mode, VoiceError, TranslateProcessor};
+use crate::voice_assistant::asr::cloud_provider::{verify_api_key, CloudAsrOptions, CloudAsrProvider};
+use crate::voice_assistant::asr::race::CancelToken;
 use std::time::Duration;
 use std::sync::Arc;
 
 pub struct SenseVoiceProcessor {
     client: reqwest::Client,
     api_key: String,
+    base_url: String,
     convert_to_simplified: bool,
     translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>>,
 }
 
 impl SenseVoiceProcessor {
+    pub const PROCESSOR_TYPE: &'static str = "cloud-sensevoice";
+
     pub fn new() -> Result<Self, VoiceError> {
         let api_key = std::env::var("SILICONFLOW_API_KEY")
             .map_err(|_| VoiceError::Other("SILICONFLOW_API_KEY environment variable not set".to_string()))?;
 
+        let base_url = std::env::var("SILICONFLOW_BASE_URL")
+            .unwrap_or_else(|_| "https://api.siliconflow.cn".to_string());
+
+        Self::with_config(api_key, base_url)
+    }
+
+    pub fn with_config(api_key: String, base_url: String) -> Result<Self, VoiceError> {
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(20))
             .build()
@@ -25,10 +35,8 @@ impl SenseVoiceProcessor {
         Ok(Self {
             client,
             api_key,
-            convert_to_simplified: std::env::var("CONVERT_TO_SIMPLIFIED")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
+            base_url,
+            convert_to_simplified: crate::voice_assistant::settings::current().convert_to_simplified,
             translate_processor: None,
         })
     }
@@ -38,67 +46,57 @@ impl SenseVoiceProcessor {
         self
     }
 
-    async fn call_api(&self, audio_data: &[u8]) -> Result<String, VoiceError> {
-        let form = multipart::Form::new()
-            .part("file", multipart::Part::bytes(audio_data.to_vec())
-                .file_name("audio.wav")
-                .mime_str("audio/wav")?)
-            .text("model", "FunAudioLLM/SenseVoiceSmall");
-
-        let response = self.client
-            .post("https://api.siliconflow.cn/v1/audio/transcriptions")
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
-            .await
-            .map_err(|e| VoiceError::Network(e))?;
+    async fn call_api(&self, audio_data: &[u8]) -> Result<AsrTranscript, VoiceError> {
+        let mut transcript = CloudAsrOptions::new(CloudAsrProvider::OpenAi)
+            .with_model("FunAudioLLM/SenseVoiceSmall")
+            .transcribe(&self.client, &self.api_url(), Some(&self.api_key), audio_data.to_vec())
+            .await?;
 
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(VoiceError::Other(format!("SenseVoice API error: {} - {}", status, error_text)));
+        if self.convert_to_simplified {
+            transcript.text = self.convert_traditional_to_simplified(&transcript.text);
         }
+        Ok(transcript)
+    }
 
-        let result: Value = response.json().await
-            .map_err(|e| VoiceError::Network(e))?;
-
-        if let Some(text) = result.get("text").and_then(|v| v.as_str()) {
-            let processed_text = if self.convert_to_simplified {
-                self.convert_traditional_to_simplified(text)
-            } else {
-                text.to_string()
-            };
-            Ok(processed_text)
-        } else {
-            Err(VoiceError::Other("No text in SenseVoice response".to_string()))
-        }
+    fn api_url(&self) -> String {
+        format!("{}/v1", self.base_url)
     }
 
     fn convert_traditional_to_simplified(&self, text: &str) -> String {
-        // Placeholder for Chinese text conversion
-        text.to_string()
+        crate::voice_assistant::postprocess::simplify_if_chinese(text)
     }
 }
 
 impl AsrProcessor for SenseVoiceProcessor {
     fn process_audio(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.process_audio_cancellable(audio_buffer, mode, prompt, &CancelToken::default())
+    }
+
+    fn process_audio_cancellable(
         &self,
         audio_buffer: Cursor<Vec<u8>>,
         mode: Mode,
         _prompt: &str,
-    ) -> Result<String, VoiceError> {
+        cancel: &CancelToken,
+    ) -> Result<AsrTranscript, VoiceError> {
         let rt = tokio::runtime::Runtime::new()
             .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
 
         rt.block_on(async {
             let audio_data = audio_buffer.into_inner();
-            let transcription = self.call_api(&audio_data).await?;
+            let transcription = cancel.run(self.call_api(&audio_data)).await?;
 
             match mode {
                 Mode::Transcriptions => Ok(transcription),
                 Mode::Translations => {
                     if let Some(ref translate_processor) = self.translate_processor {
-                        translate_processor.translate(&transcription)
+                        let translated = translate_processor.translate(&transcription.text)?;
+                        Ok(AsrTranscript { text: translated, ..transcription })
                     } else {
                         Err(VoiceError::Other("No translate processor available for translation mode".to_string()))
                     }
@@ -107,7 +105,15 @@ impl AsrProcessor for SenseVoiceProcessor {
         })
     }
     
-    fn get_processor_type(&self) -> Option<&str> {
-        Some("sensevoice")
+    fn get_processor_type(&self) -> &str {
+        Self::PROCESSOR_TYPE
+    }
+
+    fn health_check(&self) -> Result<(), VoiceError> {
+        if self.api_key.trim().is_empty() {
+            return Err(VoiceError::Other("SILICONFLOW_API_KEY is not configured".to_string()));
+        }
+        // 在 Tauri 的运行时上执行，不为每次检查新建运行时；调用方在 spawn_blocking 中调用
+        tauri::async_runtime::block_on(verify_api_key(&self.client, &self.api_url(), &self.api_key))
     }
 }
\ No newline at end of file
@@ -18,4 +18,180 @@ impl WhisperVadProcessor {
     pub fn process(&mut self, _audio_data: &[f32], _sample_rate: u32) -> Result<Vec<VadSegment>, VoiceError> {
         Err(VoiceError::Other("WhisperVad disabled for Windows migration".to_string()))
     }
-}
\ No newline at end of file
+}
+/// 判定为语音的帧 RMS 阈值
+pub const VAD_ENERGY_THRESHOLD: f32 = 0.01;
+/// 能量分析的帧长（毫秒）
+const VAD_FRAME_MS: u32 = 30;
+/// 语音前后保留的余量（毫秒），避免切掉首尾的轻辅音
+const VAD_PADDING_MS: u32 = 200;
+
+/// 能量 VAD 的单帧判断，裁剪静音、录音前检查和静音自动停止共用同一个阈值
+fn is_speech_frame(frame: &[f32]) -> bool {
+    crate::voice_assistant::recorder::rms(frame) > VAD_ENERGY_THRESHOLD
+}
+
+/// 基于能量裁剪首尾静音，只保留第一个到最后一个语音帧之间的音频。
+/// 中间的停顿不做处理，避免破坏 whisper 的上下文。
+/// 整段都是静音时返回 None。
+pub fn trim_silence(audio_data: &[f32], sample_rate: u32) -> Option<std::ops::Range<usize>> {
+    if audio_data.is_empty() || sample_rate == 0 {
+        return None;
+    }
+
+    let frame_len = ((sample_rate * VAD_FRAME_MS / 1000) as usize).max(1);
+    let frames: Vec<&[f32]> = audio_data.chunks(frame_len).collect();
+    let first = frames.iter().position(|f| is_speech_frame(f))?;
+    let last = frames.iter().rposition(|f| is_speech_frame(f))?;
+
+    let padding = (sample_rate * VAD_PADDING_MS / 1000) as usize;
+    let start = (first * frame_len).saturating_sub(padding);
+    let end = ((last + 1) * frame_len + padding).min(audio_data.len());
+
+    Some(start..end)
+}
+
+/// 能量超过阈值的帧的总时长，用于识别前判断录音里有没有说话。
+/// 热键录音还没有经过预处理，先做峰值归一化，否则音量小的麦克风录到的语音会被当成静音
+pub fn speech_duration(audio_data: &[f32], sample_rate: u32) -> std::time::Duration {
+    if sample_rate == 0 {
+        return std::time::Duration::ZERO;
+    }
+    let audio = crate::voice_assistant::audio_prep::peak_normalized(audio_data);
+    let frame_len = ((sample_rate * VAD_FRAME_MS / 1000) as usize).max(1);
+    let speech_samples: usize = audio
+        .chunks(frame_len)
+        .filter(|frame| is_speech_frame(frame))
+        .map(|frame| frame.len())
+        .sum();
+    std::time::Duration::from_secs_f64(speech_samples as f64 / sample_rate as f64)
+}
+
+/// 录音中的静音检测：按帧累计连续静音时长，用于静音自动停止录音。
+/// 录音开始后的宽限期内不会触发，避免用户还没开口就被停掉。
+pub struct SilenceDetector {
+    frame_len: usize,
+    silence_frames_needed: usize,
+    grace_samples: usize,
+    samples_seen: usize,
+    silent_frames: usize,
+    // 跨调用时凑不满一帧的剩余采样
+    pending: Vec<f32>,
+}
+
+impl SilenceDetector {
+    pub fn new(sample_rate: u32, silence: std::time::Duration, grace: std::time::Duration) -> Self {
+        let frame_len = ((sample_rate * VAD_FRAME_MS / 1000) as usize).max(1);
+        let silence_samples = (sample_rate as f64 * silence.as_secs_f64()) as usize;
+        Self {
+            frame_len,
+            silence_frames_needed: silence_samples.div_ceil(frame_len).max(1),
+            grace_samples: (sample_rate as f64 * grace.as_secs_f64()) as usize,
+            samples_seen: 0,
+            silent_frames: 0,
+            pending: Vec::with_capacity(frame_len),
+        }
+    }
+
+    /// 送入新录到的采样，连续静音达到阈值且已过宽限期时返回 true
+    pub fn feed(&mut self, samples: &[f32]) -> bool {
+        self.samples_seen += samples.len();
+        self.pending.extend_from_slice(samples);
+
+        let full_frames = self.pending.len() / self.frame_len;
+        for frame in self.pending[..full_frames * self.frame_len].chunks(self.frame_len) {
+            if is_speech_frame(frame) {
+                self.silent_frames = 0;
+            } else {
+                self.silent_frames += 1;
+            }
+        }
+        self.pending.drain(..full_frames * self.frame_len);
+
+        self.samples_seen >= self.grace_samples && self.silent_frames >= self.silence_frames_needed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tone(len: usize, amplitude: f32) -> Vec<f32> {
+        (0..len).map(|i| if i % 2 == 0 { amplitude } else { -amplitude }).collect()
+    }
+
+    #[test]
+    fn test_trim_leading_and_trailing_silence() {
+        let mut audio = vec![0.0; 16000];
+        audio.extend(tone(8000, 0.3));
+        audio.extend(vec![0.0; 16000]);
+
+        let range = trim_silence(&audio, 16000).unwrap();
+        // 200ms padding on each side of the 0.5s tone
+        assert!(range.start <= 16000 - 3200 && range.start >= 16000 - 3200 - 480);
+        assert!(range.end >= 24000 && range.end <= 24000 + 3200 + 480);
+    }
+
+    #[test]
+    fn test_keeps_pauses_between_speech() {
+        let mut audio = tone(4800, 0.3);
+        audio.extend(vec![0.0; 16000]);
+        audio.extend(tone(4800, 0.3));
+
+        let range = trim_silence(&audio, 16000).unwrap();
+        assert_eq!(range, 0..audio.len());
+    }
+
+    #[test]
+    fn test_all_silence_returns_none() {
+        assert!(trim_silence(&vec![0.001; 32000], 16000).is_none());
+        assert!(trim_silence(&[], 16000).is_none());
+    }
+
+    #[test]
+    fn test_speech_duration_counts_loud_frames_only() {
+        let mut audio = vec![0.002; 16000];
+        audio.extend(tone(4800, 0.3));
+        audio.extend(vec![0.0; 8000]);
+        let speech = speech_duration(&audio, 16000);
+        assert!(speech >= std::time::Duration::from_millis(270) && speech <= std::time::Duration::from_millis(360));
+
+        assert_eq!(speech_duration(&vec![0.0; 32000], 16000), std::time::Duration::ZERO);
+        assert_eq!(speech_duration(&[], 16000), std::time::Duration::ZERO);
+    }
+
+    #[test]
+    fn test_speech_duration_normalizes_quiet_recordings() {
+        // 峰值 0.008 低于阈值，归一化后才能检测到
+        let mut audio = vec![0.0; 16000];
+        audio.extend(tone(8000, 0.008));
+        let speech = speech_duration(&audio, 16000);
+        assert!(speech >= std::time::Duration::from_millis(450), "{:?}", speech);
+    }
+
+    #[test]
+    fn test_silence_detector_triggers_after_threshold() {
+        let mut detector = SilenceDetector::new(16000, std::time::Duration::from_millis(1000), std::time::Duration::from_secs(1));
+        assert!(!detector.feed(&tone(16000, 0.3)));
+        // 不按帧长对齐地送入静音
+        assert!(!detector.feed(&vec![0.0; 3333]));
+        assert!(!detector.feed(&vec![0.0; 8000]));
+        assert!(detector.feed(&vec![0.0; 6000]));
+    }
+
+    #[test]
+    fn test_silence_detector_resets_on_speech() {
+        let mut detector = SilenceDetector::new(16000, std::time::Duration::from_millis(500), std::time::Duration::ZERO);
+        assert!(!detector.feed(&vec![0.0; 6400]));
+        assert!(!detector.feed(&tone(480, 0.3)));
+        assert!(!detector.feed(&vec![0.0; 6400]));
+        assert!(detector.feed(&vec![0.0; 3200]));
+    }
+
+    #[test]
+    fn test_silence_detector_respects_grace_period() {
+        let mut detector = SilenceDetector::new(16000, std::time::Duration::from_millis(200), std::time::Duration::from_secs(1));
+        assert!(!detector.feed(&vec![0.0; 12000]));
+        assert!(detector.feed(&vec![0.0; 4000]));
+    }
+}
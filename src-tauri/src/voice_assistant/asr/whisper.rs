@@ -1,7 +1,7 @@
 use std::io::Cursor;
-use reqwest::multipart;
-use crate::voice_assistant::{AsrProcessor, Mode, VoiceError};
-use serde_json::Value;
+use crate::voice_assistant::{AsrProcessor, AsrTranscript, Mode, VoiceError};
+use crate::voice_assistant::asr::cloud_provider::{verify_api_key, CloudAsrOptions, CloudAsrProvider};
+use crate::voice_assistant::asr::race::CancelToken;
 use std::time::Duration;
 
 pub struct WhisperProcessor {
@@ -14,6 +14,8 @@ pub struct WhisperProcessor {
 }
 
 impl WhisperProcessor {
+    pub const PROCESSOR_TYPE: &'static str = "cloud-groq";
+
     pub fn new() -> Result<Self, VoiceError> {
         let api_key = std::env::var("GROQ_API_KEY")
             .map_err(|_| VoiceError::Other("GROQ_API_KEY environment variable not set".to_string()))?;
@@ -21,27 +23,23 @@ impl WhisperProcessor {
         let base_url = std::env::var("GROQ_BASE_URL")
             .unwrap_or_else(|_| "https://api.groq.com".to_string());
 
+        Self::with_config(api_key, base_url)
+    }
+
+    pub fn with_config(api_key: String, base_url: String) -> Result<Self, VoiceError> {
         let client = reqwest::Client::builder()
             .timeout(Duration::from_secs(30))
             .build()
             .map_err(|e| VoiceError::Network(e))?;
 
+        let settings = crate::voice_assistant::settings::current();
         Ok(Self {
             client,
             api_key,
             base_url,
-            convert_to_simplified: std::env::var("CONVERT_TO_SIMPLIFIED")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-            add_symbol: std::env::var("ADD_SYMBOL")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-            optimize_result: std::env::var("OPTIMIZE_RESULT")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
+            convert_to_simplified: settings.convert_to_simplified,
+            add_symbol: settings.add_symbol,
+            optimize_result: settings.optimize_result,
         })
     }
 
@@ -50,64 +48,35 @@ impl WhisperProcessor {
         mode: Mode,
         audio_data: &[u8],
         prompt: &str,
-    ) -> Result<String, VoiceError> {
+    ) -> Result<AsrTranscript, VoiceError> {
         let model = match mode {
             Mode::Transcriptions => "whisper-large-v3-turbo",
             Mode::Translations => "whisper-large-v3",
         };
 
-        let form = multipart::Form::new()
-            .part("file", multipart::Part::bytes(audio_data.to_vec())
-                .file_name("audio.wav")
-                .mime_str("audio/wav")?)
-            .text("model", model)
-            .text("response_format", "json");
-
-        let form = if !prompt.is_empty() {
-            form.text("prompt", prompt.to_string())
-        } else {
-            form
+        let options = CloudAsrOptions {
+            prompt: Some(prompt.to_string()),
+            translate: mode == Mode::Translations,
+            ..CloudAsrOptions::new(CloudAsrProvider::OpenAi).with_model(model)
         };
+        let mut transcript = options
+            .transcribe(&self.client, &format!("{}/openai/v1", self.base_url), Some(&self.api_key), audio_data.to_vec())
+            .await?;
+        let mut processed_text = std::mem::take(&mut transcript.text);
 
-        let request = self.client
-            .post(&format!("{}/openai/v1/audio/{}", self.base_url, 
-                if mode == Mode::Translations { "translations" } else { "transcriptions" }))
-            .header("Authorization", format!("Bearer {}", self.api_key))
-            .multipart(form);
-
-        let response = request
-            .send()
-            .await
-            .map_err(|e| VoiceError::Network(e))?;
-
-        let status = response.status();
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_default();
-            return Err(VoiceError::Other(format!("Whisper API error: {} - {}", status, error_text)));
+        if self.add_symbol {
+            processed_text = self.add_punctuation(&processed_text);
         }
 
-        let result: Value = response.json().await
-            .map_err(|e| VoiceError::Network(e))?;
+        if self.optimize_result {
+            processed_text = self.optimize_text(&processed_text);
+        }
 
-        if let Some(text) = result.get("text").and_then(|v| v.as_str()) {
-            let mut processed_text = text.to_string();
-            
-            if self.add_symbol {
-                processed_text = self.add_punctuation(&processed_text);
-            }
-            
-            if self.optimize_result {
-                processed_text = self.optimize_text(&processed_text);
-            }
-            
-            if self.convert_to_simplified {
-                processed_text = self.convert_traditional_to_simplified(&processed_text);
-            }
-            
-            Ok(processed_text)
-        } else {
-            Err(VoiceError::Other("No text in Whisper response".to_string()))
+        if self.convert_to_simplified {
+            processed_text = self.convert_traditional_to_simplified(&processed_text);
         }
+
+        Ok(AsrTranscript { text: processed_text, ..transcript })
     }
 
     fn add_punctuation(&self, text: &str) -> String {
@@ -122,9 +91,7 @@ impl WhisperProcessor {
     }
 
     fn convert_traditional_to_simplified(&self, text: &str) -> String {
-        // Placeholder for Chinese text conversion
-        // In a real implementation, you'd use a library like chinese-conversion
-        text.to_string()
+        crate::voice_assistant::postprocess::simplify_if_chinese(text)
     }
 }
 
@@ -134,18 +101,36 @@ impl AsrProcessor for WhisperProcessor {
         audio_buffer: Cursor<Vec<u8>>,
         mode: Mode,
         prompt: &str,
-    ) -> Result<String, VoiceError> {
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.process_audio_cancellable(audio_buffer, mode, prompt, &CancelToken::default())
+    }
+
+    fn process_audio_cancellable(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+        cancel: &CancelToken,
+    ) -> Result<AsrTranscript, VoiceError> {
         // Since we need async, but the trait is sync, we'll use a blocking runtime
         let rt = tokio::runtime::Runtime::new()
             .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
 
-        rt.block_on(async {
+        rt.block_on(cancel.run(async {
             let audio_data = audio_buffer.into_inner();
             self.call_whisper_api(mode, &audio_data, prompt).await
-        })
+        }))
     }
     
-    fn get_processor_type(&self) -> Option<&str> {
-        Some("whisper")
+    fn get_processor_type(&self) -> &str {
+        Self::PROCESSOR_TYPE
+    }
+
+    fn health_check(&self) -> Result<(), VoiceError> {
+        if self.api_key.trim().is_empty() {
+            return Err(VoiceError::Other("GROQ_API_KEY is not configured".to_string()));
+        }
+        // 在 Tauri 的运行时上执行，不为每次检查新建运行时；调用方在 spawn_blocking 中调用
+        tauri::async_runtime::block_on(verify_api_key(&self.client, &format!("{}/openai/v1", self.base_url), &self.api_key))
     }
 }
\ No newline at end of file
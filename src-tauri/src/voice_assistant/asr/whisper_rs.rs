@@ -1,18 +1,119 @@
 use std::io::Cursor;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
-use whisper_rs::{WhisperContext, FullParams, SamplingStrategy, WhisperContextParameters};
-use crate::voice_assistant::{AsrProcessor, Mode, VoiceError};
+use whisper_rs::{WhisperContext, WhisperState, FullParams, SamplingStrategy, WhisperContextParameters};
+use crate::voice_assistant::{AsrConfidence, AsrProcessor, AsrTranscript, Mode, VoiceError};
+use crate::voice_assistant::asr::race::CancelToken;
 use std::time::Instant;
 use serde_json;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum SamplingStrategyConfig {
     Greedy { best_of: u32 },
     Beam { beam_size: u32, patience: f32 },
 }
 
-#[derive(Debug, Clone)]
+/// 运行时可调的解码方式（持久化在设置中），每次推理时覆盖处理器的默认值
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DecodingStrategy {
+    Greedy,
+    Beam,
+}
+
+impl DecodingStrategy {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "greedy" => Some(DecodingStrategy::Greedy),
+            "beam" | "beam_search" => Some(DecodingStrategy::Beam),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DecodingStrategy::Greedy => "greedy",
+            DecodingStrategy::Beam => "beam",
+        }
+    }
+}
+
+pub const DEFAULT_BEAM_SIZE: u32 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct WhisperDecoding {
+    /// None 时沿用处理器创建时的采样方式
+    pub strategy: Option<DecodingStrategy>,
+    pub beam_size: u32,
+    /// None 时转写用 0.0、翻译用 0.2
+    pub temperature: Option<f32>,
+    /// None 时使用 whisper.cpp 的默认值
+    pub no_speech_threshold: Option<f32>,
+}
+
+impl Default for WhisperDecoding {
+    fn default() -> Self {
+        Self {
+            strategy: None,
+            beam_size: DEFAULT_BEAM_SIZE,
+            temperature: None,
+            no_speech_threshold: None,
+        }
+    }
+}
+
+impl WhisperDecoding {
+    pub fn sampling_strategy(&self, configured: &SamplingStrategyConfig) -> SamplingStrategyConfig {
+        match self.strategy {
+            Some(DecodingStrategy::Greedy) => SamplingStrategyConfig::Greedy { best_of: 1 },
+            // patience -1.0 表示使用 whisper.cpp 的默认值
+            Some(DecodingStrategy::Beam) => SamplingStrategyConfig::Beam { beam_size: self.beam_size, patience: -1.0 },
+            None => configured.clone(),
+        }
+    }
+
+    pub fn temperature_for(&self, mode: Mode) -> f32 {
+        self.temperature.unwrap_or(if matches!(mode, Mode::Translations) {
+            0.2 // Higher temperature for translation to prevent repetition
+        } else {
+            0.0 // Greedy decoding for transcription accuracy
+        })
+    }
+}
+
+/// 把提示词写入解码参数；FullParams 之外，测试中可以换成记录调用的实现
+pub trait PromptParams {
+    fn set_initial_prompt(&mut self, prompt: &str);
+}
+
+impl PromptParams for FullParams<'_, '_> {
+    fn set_initial_prompt(&mut self, prompt: &str) {
+        FullParams::set_initial_prompt(self, prompt);
+    }
+}
+
+/// 设置 initial_prompt，用人名、术语等引导 whisper 的用词；转写和翻译模式都会设置。
+/// 空提示词不设置，NUL 字符（whisper.cpp 不接受）会被去掉。返回是否设置了提示词
+pub fn apply_initial_prompt(params: &mut impl PromptParams, prompt: &str) -> bool {
+    let prompt = prompt.replace('\0', "");
+    let prompt = prompt.trim();
+    if prompt.is_empty() {
+        return false;
+    }
+    params.set_initial_prompt(prompt);
+    true
+}
+
+/// 调用方传入的提示词优先，为空时使用设置中的默认提示词
+pub fn effective_prompt(prompt: &str) -> String {
+    if !prompt.trim().is_empty() {
+        return prompt.to_string();
+    }
+    crate::voice_assistant::settings::current().default_prompt.unwrap_or_default()
+}
+
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub enum OutputFormat {
     Text,    // 纯文本
     Json,    // JSON格式
@@ -28,9 +129,68 @@ pub struct SegmentData {
     pub start_ms: u64,
     pub end_ms: u64,
     pub index: i32,
+    /// whisper 判断这一段没有语音的概率
+    pub no_speech_prob: f32,
+    /// 文本 token 的概率（不含时间戳等特殊 token）
+    pub token_probs: Vec<f32>,
 }
 
-#[derive(Debug, Clone, PartialEq)]
+impl SegmentData {
+    /// 这一段的可信度：文本 token 概率的几何平均（即平均对数概率取 exp），没有文本 token 时为 None
+    pub fn confidence(&self) -> Option<f32> {
+        if self.token_probs.is_empty() {
+            return None;
+        }
+        summarize_confidence(std::slice::from_ref(self)).map(|summary| summary.confidence)
+    }
+}
+
+/// 丢弃可信度低于阈值的段落，用于过滤静音上"谢谢观看"之类的幻觉；没有文本 token 的段落保留
+pub fn filter_low_confidence_segments(segments: Vec<SegmentData>, min_confidence: f32) -> Vec<SegmentData> {
+    if min_confidence <= 0.0 {
+        return segments;
+    }
+    segments
+        .into_iter()
+        .filter(|seg| match seg.confidence() {
+            Some(confidence) if confidence < min_confidence => {
+                tracing::debug!("🧹 Dropping low-confidence segment {} ({:.2} < {:.2})", seg.index, confidence, min_confidence);
+                false
+            }
+            _ => true,
+        })
+        .collect()
+}
+
+/// 设置中的段落可信度阈值；worker 进程中为默认值，实际阈值随每个请求传入
+fn configured_min_segment_confidence() -> f32 {
+    crate::voice_assistant::settings::current().whisper_min_segment_confidence
+}
+
+/// 汇总各段的概率：confidence 取所有文本 token 概率的几何平均，
+/// no_speech_prob 取各段的最小值（只要有一段听到了语音就不算空录音）
+pub fn summarize_confidence(segments: &[SegmentData]) -> Option<AsrConfidence> {
+    if segments.is_empty() {
+        return None;
+    }
+    let no_speech_prob = segments
+        .iter()
+        .map(|seg| seg.no_speech_prob)
+        .fold(f32::INFINITY, f32::min);
+    let probs: Vec<f32> = segments.iter().flat_map(|seg| seg.token_probs.iter().copied()).collect();
+    let confidence = if probs.is_empty() {
+        0.0
+    } else {
+        let mean_log = probs.iter().map(|p| p.clamp(1e-6, 1.0).ln()).sum::<f32>() / probs.len() as f32;
+        mean_log.exp()
+    };
+    Some(AsrConfidence {
+        confidence,
+        no_speech_prob: no_speech_prob.clamp(0.0, 1.0),
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
 pub enum WhisperBackend {
     CPU,
     CUDA,
@@ -45,6 +205,49 @@ impl Default for WhisperBackend {
     }
 }
 
+impl WhisperBackend {
+    /// 解析后端名称（不区分大小写），用于设置页面和配置
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "cpu" => Some(Self::CPU),
+            "cuda" => Some(Self::CUDA),
+            "vulkan" => Some(Self::Vulkan),
+            "metal" => Some(Self::Metal),
+            "opencl" => Some(Self::OpenCL),
+            _ => None,
+        }
+    }
+}
+
+/// 从 whisper.cpp 的 system info 中解析编译进来的 GPU 后端。
+/// 旧格式为 `CUDA = 1 | METAL = 0 | ...`，新格式为 `CUDA : ARCHS = 890 | ...`（出现即表示已注册）
+fn parse_system_info_backends(info: &str) -> Vec<WhisperBackend> {
+    let mut backends = Vec::new();
+    for segment in info.split('|') {
+        let segment = segment.trim();
+        let enabled_name = if let Some((name, value)) = segment.split_once(" = ") {
+            (value.trim() == "1").then_some(name)
+        } else {
+            None
+        };
+        let registered_name = segment.split_once(':').map(|(name, _)| name);
+
+        for name in [enabled_name, registered_name].into_iter().flatten() {
+            if let Some(backend) = WhisperBackend::parse(name.trim()) {
+                if backend != WhisperBackend::CPU && !backends.contains(&backend) {
+                    backends.push(backend);
+                }
+            }
+        }
+    }
+    backends
+}
+
+/// 当前二进制中 whisper.cpp 实际编译进来的 GPU 后端
+pub fn compiled_gpu_backends() -> Vec<WhisperBackend> {
+    parse_system_info_backends(whisper_rs::print_system_info())
+}
+
 impl std::fmt::Display for WhisperBackend {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -57,7 +260,7 @@ impl std::fmt::Display for WhisperBackend {
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct WhisperRSConfig {
     pub model_path: String,
     pub sampling_strategy: SamplingStrategyConfig,
@@ -68,6 +271,10 @@ pub struct WhisperRSConfig {
     pub use_gpu_if_available: bool,
     pub gpu_device_id: Option<u32>,
     pub output_format: OutputFormat, // 🔥 NEW: 输出格式控制
+    /// 复用同一个 WhisperState，省去每次识别重新分配 state 的开销
+    pub reuse_state: bool,
+    /// 平均 token 概率低于该值的段落不输出（静音上的幻觉），0.0 表示不过滤
+    pub min_segment_confidence: f32,
 }
 
 pub struct WhisperRSProcessor {
@@ -75,11 +282,16 @@ pub struct WhisperRSProcessor {
     config: WhisperRSConfig,
     // VAD flag for basic energy-based VAD (thread-safe alternative)
     enable_basic_vad: bool,
-    // For thread-safe access if needed
-    _state_guard: Mutex<()>,
+    // reuse_state 开启时缓存的 WhisperState，同时保证同一时间只有一次推理使用它
+    _state_guard: Mutex<Option<WhisperState>>,
+    // 实际生效的后端；请求的 GPU 后端不可用时回退为 CPU
+    effective_backend: WhisperBackend,
+    gpu_fallback_reason: Option<String>,
 }
 
 impl WhisperRSProcessor {
+    pub const PROCESSOR_TYPE: &'static str = "whisper-rs";
+
     pub fn new(config: WhisperRSConfig) -> Result<Self, VoiceError> {
         println!("📍 [DEBUG] Step A: new() called with model: {}", config.model_path);
 
@@ -96,70 +308,67 @@ impl WhisperRSProcessor {
         // 设置GPU后端参数
         println!("🔧 Initializing Whisper with backend: {:?}", config.backend);
 
-        println!("📍 [DEBUG] Step D: Creating WhisperContextParameters...");
-        let params = WhisperContextParameters::default();
-        println!("📍 [DEBUG] Step E: Parameters created");
-
-        // 根据配置的后端设置参数
-        match config.backend {
-            WhisperBackend::CUDA => {
-                println!("🚀 Initializing CUDA backend for GPU acceleration");
-
-                // 设置CUDA设备ID（如果指定）
-                if let Some(device_id) = config.gpu_device_id {
-                    // whisper-rs通过环境变量设置CUDA设备
-                    std::env::set_var("CUDA_VISIBLE_DEVICES", device_id.to_string());
-                    println!("📱 Using CUDA device ID: {}", device_id);
+        // 请求的 GPU 后端必须编译进 whisper.cpp 才能使用，否则直接走 CPU
+        let mut gpu_fallback_reason = None;
+        let use_gpu = match config.backend {
+            WhisperBackend::CPU => false,
+            ref requested => {
+                let compiled = compiled_gpu_backends();
+                if compiled.contains(requested) {
+                    true
+                } else {
+                    gpu_fallback_reason = Some(format!(
+                        "{} backend is not compiled into this build (available: [{}])",
+                        requested,
+                        compiled.iter().map(|b| b.to_string()).collect::<Vec<_>>().join(", ")
+                    ));
+                    false
                 }
-
-                // 注意：当前版本使用CPU后端，CUDA支持需要重新编译
-                println!("⚠️ CUDA backend requested but running in CPU mode");
-                println!("💡 To enable CUDA, recompile with: cargo build --features cuda");
-            }
-            WhisperBackend::Vulkan => {
-                println!("⚠️ Vulkan backend requested but running in CPU mode");
-                println!("💡 To enable Vulkan, recompile with: cargo build --features vulkan");
-            }
-            WhisperBackend::Metal => {
-                println!("⚠️ Metal backend requested but running in CPU mode");
-                println!("💡 To enable Metal, recompile with: cargo build --features metal");
             }
-            WhisperBackend::OpenCL => {
-                println!("⚠️ OpenCL backend requested but running in CPU mode");
-                println!("💡 OpenCL support not available in current build");
-            }
-            WhisperBackend::CPU => {
-                println!("💻 Using CPU backend");
-            }
-        }
+        };
 
-        // Create whisper context
         println!("📍 [DEBUG] Step F: About to call WhisperContext::new_with_params...");
         println!("📍 [DEBUG] Step F-1: Model path: {}", config.model_path);
-        println!("📍 [DEBUG] Step F-2: This is where it likely hangs...");
 
-        let ctx = WhisperContext::new_with_params(
-            &config.model_path,
-            params,
-        ).map_err(|e| {
-            VoiceError::Other(format!("Failed to load whisper model: {}", e))
-        })?;
+        let create_context = |use_gpu: bool| {
+            let mut params = WhisperContextParameters::default();
+            // 默认参数在 GPU 构建下会开启 GPU，这里总是显式设置
+            params.use_gpu(use_gpu);
+            if use_gpu {
+                let device = config.gpu_device_id.unwrap_or(0) as i32;
+                params.gpu_device(device);
+                tracing::info!("🚀 Initializing {} backend on GPU device {}", config.backend, device);
+            } else {
+                println!("💻 Using CPU backend");
+            }
+            WhisperContext::new_with_params(&config.model_path, params)
+        };
 
-        println!("📍 [DEBUG] Step G: WhisperContext created successfully");
+        let ctx = match create_context(use_gpu) {
+            Ok(ctx) => ctx,
+            Err(e) if use_gpu => {
+                // GPU 初始化失败时回退到 CPU，而不是让整个识别不可用
+                gpu_fallback_reason = Some(format!("{} initialization failed: {}", config.backend, e));
+                create_context(false).map_err(|e| {
+                    VoiceError::Other(format!("Failed to load whisper model: {}", e))
+                })?
+            }
+            Err(e) => {
+                return Err(VoiceError::Other(format!("Failed to load whisper model: {}", e)));
+            }
+        };
 
-        // 验证实际使用的后端
-        println!("✅ Whisper context created successfully");
+        println!("📍 [DEBUG] Step G: WhisperContext created successfully");
 
-        // 如果GPU后端初始化失败但请求了GPU，提供fallback建议
-        if config.use_gpu_if_available && config.backend != WhisperBackend::CPU {
-            println!("⚠️ Requested GPU backend but currently using CPU backend");
-            println!("💡 To enable GPU acceleration:");
-            println!("   1. Install NVIDIA GPU drivers");
-            println!("   2. Install CUDA Toolkit (for CUDA support)");
-            println!("   3. Recompile with GPU features:");
-            println!("      cargo build --release --features cuda");
-            println!("   4. Check CUDA installation guide");
+        let effective_backend = if gpu_fallback_reason.is_none() {
+            config.backend.clone()
+        } else {
+            WhisperBackend::CPU
+        };
+        if let Some(reason) = &gpu_fallback_reason {
+            tracing::warn!("⚠️ Falling back to CPU backend: {}", reason);
         }
+        tracing::info!("✅ Whisper context created successfully (effective backend: {})", effective_backend);
 
         // Initialize VAD functionality
         println!("📍 [DEBUG] Step H: Initializing VAD...");
@@ -175,14 +384,30 @@ impl WhisperRSProcessor {
             ctx: Some(Arc::new(ctx)),
             config,
             enable_basic_vad,
-            _state_guard: Mutex::new(()),
+            _state_guard: Mutex::new(None),
+            effective_backend,
+            gpu_fallback_reason,
         })
     }
 
+    /// 实际使用的后端（可能与配置中请求的不同）
+    pub fn effective_backend(&self) -> &WhisperBackend {
+        &self.effective_backend
+    }
+
+    /// 请求了 GPU 但回退到 CPU 时的原因
+    pub fn gpu_fallback_reason(&self) -> Option<&str> {
+        self.gpu_fallback_reason.as_deref()
+    }
+
     /// 显式卸载模型并释放GPU内存
     pub fn unload(&mut self) {
         if self.ctx.is_some() {
             println!("🗑️ WhisperRS: Explicitly unloading model...");
+            // 缓存的 state 也持有模型引用，需要一起释放
+            if let Ok(mut cached) = self._state_guard.lock() {
+                *cached = None;
+            }
             // Drop the context - this will trigger whisper_free
             self.ctx = None;
             println!("✅ WhisperRS: Model unloaded, GPU memory should be released");
@@ -192,11 +417,9 @@ impl WhisperRSProcessor {
     }
 
     pub fn from_env() -> Result<Self, VoiceError> {
-        let model_path = std::env::var("WHISPER_MODEL_PATH")
-            .unwrap_or_else(|_| {
-                // Default model path - user should set this environment variable
-                "./models/ggml-base.bin".to_string()
-            });
+        let model_path = crate::voice_assistant::asr::models::resolve_active_model()?
+            .to_string_lossy()
+            .to_string();
 
         // 🔥 简化：直接使用CPU后端，避免GPU detector死锁
         let config = WhisperRSConfig {
@@ -209,21 +432,24 @@ impl WhisperRSProcessor {
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
 
         Self::new(config)
     }
 
-    fn create_params(&self, mode: Mode) -> FullParams<'_, '_> {
-        let sampling_strategy = match &self.config.sampling_strategy {
+    fn create_params(&self, mode: Mode, prompt: &str, cancel: &CancelToken) -> FullParams<'_, '_> {
+        let decoding = crate::voice_assistant::settings::current().whisper_decoding;
+        let sampling_strategy = match decoding.sampling_strategy(&self.config.sampling_strategy) {
             SamplingStrategyConfig::Greedy { best_of } => {
-                SamplingStrategy::Greedy { best_of: *best_of as i32 }
+                SamplingStrategy::Greedy { best_of: best_of as i32 }
             }
             SamplingStrategyConfig::Beam { beam_size, patience } => {
                 println!("🎯 Using Beam Search with beam_size: {}, patience: {}", beam_size, patience);
                 SamplingStrategy::BeamSearch {
-                    beam_size: *beam_size as i32,
-                    patience: *patience,
+                    beam_size: beam_size as i32,
+                    patience,
                 }
             }
         };
@@ -272,22 +498,38 @@ impl WhisperRSProcessor {
         // Performance settings
         // Translation requires higher temperature to avoid repetition loops
         // Transcription uses 0.0 for accuracy, translation uses 0.2 for better results
-        let temperature = if matches!(mode, Mode::Translations) {
-            0.2f32  // Higher temperature for translation to prevent repetition
-        } else {
-            0.0f32  // Greedy decoding for transcription accuracy
-        };
+        let temperature = decoding.temperature_for(mode);
         params.set_temperature(temperature);
         println!("🌡️ Temperature set to: {} (mode: {:?})", temperature, mode);
+        if let Some(threshold) = decoding.no_speech_threshold {
+            params.set_no_speech_thold(threshold);
+        }
+        if apply_initial_prompt(&mut params, prompt) {
+            tracing::debug!("💬 Initial prompt set ({} chars)", prompt.chars().count());
+        }
 
         params.set_max_initial_ts(1_000_000.0); // Set to large value to disable
 
         // Enable prompt caching for better performance on subsequent runs
-        params.set_no_context(false);
+        // 复用 state 时上一次的识别结果会留在 state 里，关闭上下文避免串到这次的结果中
+        params.set_no_context(self.config.reuse_state);
+
+        // 只读这次调用的取消标记，中止竞速中输掉的调用不会影响之后的识别
+        let cancel = cancel.clone();
+        params.set_abort_callback_safe(move || cancel.is_cancelled());
 
         params
     }
 
+    /// 识别 WAV 数据，cancel 被置位时中止推理（竞速输掉或 worker 收到取消命令）
+    pub fn transcribe_wav(&self, audio_buffer: Cursor<Vec<u8>>, mode: Mode, prompt: &str, cancel: &CancelToken) -> Result<AsrTranscript, VoiceError> {
+        let audio_data = self.convert_bytes_to_f32(audio_buffer.into_inner())?;
+
+        // 🔥 关键修复：使用传入的mode参数，而不是config.translate
+        println!("🔍 [ASR] process_audio called with mode: {:?}", mode);
+        self.process_audio_data_with_mode(&audio_data, mode, &effective_prompt(prompt), cancel)
+    }
+
     #[allow(dead_code)]
     fn process_audio_data(&self, audio_data: &[f32]) -> Result<String, VoiceError> {
         // 🔥 使用配置的翻译模式
@@ -296,21 +538,36 @@ impl WhisperRSProcessor {
         } else {
             Mode::Transcriptions
         };
-        self.process_audio_data_with_mode(audio_data, mode)
+        self.process_audio_data_with_mode(audio_data, mode, "", &CancelToken::default()).map(|transcript| transcript.text)
     }
 
     /// 🔥 使用指定的mode处理音频
-    fn process_audio_data_with_mode(&self, audio_data: &[f32], mode: Mode) -> Result<String, VoiceError> {
+    fn process_audio_data_with_mode(&self, audio_data: &[f32], mode: Mode, prompt: &str, cancel: &CancelToken) -> Result<AsrTranscript, VoiceError> {
         let start_time = Instant::now();
+        // 重采样期间到达的中止同样生效
+        if cancel.is_cancelled() {
+            return Err(VoiceError::Other("Whisper inference aborted".to_string()));
+        }
 
-        // Create a new state for each processing request
         let ctx = self.ctx.as_ref().ok_or_else(|| VoiceError::Other("WhisperContext not loaded".to_string()))?;
-        let mut state = ctx.create_state()
-            .map_err(|e| VoiceError::Other(format!("Failed to create whisper state: {}", e)))?;
+        let mut cached_state = self._state_guard.lock()
+            .map_err(|_| VoiceError::Other("Whisper state lock poisoned".to_string()))?;
+        let mut fresh_state = None;
+        let state = if self.config.reuse_state {
+            if cached_state.is_none() {
+                tracing::debug!("🧠 Creating reusable whisper state");
+                *cached_state = Some(ctx.create_state()
+                    .map_err(|e| VoiceError::Other(format!("Failed to create whisper state: {}", e)))?);
+            }
+            cached_state.as_mut().unwrap()
+        } else {
+            // Create a new state for each processing request
+            fresh_state.insert(ctx.create_state()
+                .map_err(|e| VoiceError::Other(format!("Failed to create whisper state: {}", e)))?)
+        };
 
-        // Resample audio if needed (assuming input is 16kHz mono)
-        // whisper.cpp expects 16kHz mono f32 audio
-        let processed_audio = self.preprocess_audio(audio_data);
+        // convert_bytes_to_f32 已经转成 whisper.cpp 需要的 16kHz 单声道
+        let processed_audio = audio_data.to_vec();
 
         // Apply VAD filtering if enabled
         let final_audio = if self.config.enable_vad {
@@ -324,6 +581,11 @@ impl WhisperRSProcessor {
                              original_len, filtered_len, reduction);
                     filtered_audio
                 }
+                // 全是静音时不要把空音频送给 whisper
+                Err(VoiceError::NoSpeech) => {
+                    tracing::info!("🔇 VAD found no speech in {} samples, skipping inference", processed_audio.len());
+                    return Err(VoiceError::NoSpeech);
+                }
                 Err(e) => {
                     println!("⚠️ VAD filtering failed: {}, using original audio", e);
                     processed_audio.clone()
@@ -339,7 +601,7 @@ impl WhisperRSProcessor {
         }
 
         // 🔥 关键：使用传入的mode参数，而不是config.translate
-        let params = self.create_params(mode);
+        let params = self.create_params(mode, prompt, cancel);
 
         // 🔥 DEBUG: 打印参数设置
         println!("🔍 [DEBUG] About to run whisper inference:");
@@ -348,11 +610,25 @@ impl WhisperRSProcessor {
         println!("   Audio length: {} samples", final_audio.len());
 
         // Run inference
-        state.full(params, &final_audio)
-            .map_err(|e| VoiceError::Other(format!("Whisper inference failed: {}", e)))?;
+        state.full(params, &final_audio).map_err(|e| {
+            if cancel.is_cancelled() {
+                VoiceError::Other("Whisper inference aborted".to_string())
+            } else {
+                VoiceError::Other(format!("Whisper inference failed: {}", e))
+            }
+        })?;
+
+        // 指定了识别语言时这里就是该语言，否则是 whisper 自动检测的结果
+        let language = state.full_lang_id_from_state().ok()
+            .and_then(whisper_rs::get_lang_str)
+            .map(str::to_string);
+        tracing::debug!("🌍 Detected language: {:?}", language);
 
         // 🔥 根据配置的输出格式处理结果
-        let formatted_result = self.format_transcription(&state, &self.config.output_format)?;
+        let transcript = AsrTranscript {
+            language,
+            ..self.format_transcription(state, &self.config.output_format)?
+        };
 
         let processing_time = start_time.elapsed();
         let audio_duration = final_audio.len() as f32 / 16000.0;
@@ -362,20 +638,21 @@ impl WhisperRSProcessor {
         println!("⏱️ Audio duration: {:.2}s, Real-time factor: {:.2}x", audio_duration, real_time_factor);
         println!("📄 Output format: {:?}", self.config.output_format);
 
-        Ok(formatted_result)
+        Ok(transcript)
     }
 
-    /// 🔥 NEW: 根据指定格式格式化转录结果
+    /// 🔥 NEW: 根据指定格式格式化转录结果，同时汇总整段录音的可信度
     fn format_transcription(
         &self,
         state: &whisper_rs::WhisperState,
         output_format: &OutputFormat,
-    ) -> Result<String, VoiceError> {
+    ) -> Result<AsrTranscript, VoiceError> {
         // 获取所有段落数据
         let num_segments = state
             .full_n_segments()
             .map_err(|e| VoiceError::Other(format!("Failed to get number of segments: {}", e)))?;
 
+        let token_eot = self.ctx.as_ref().map(|ctx| ctx.token_eot());
         let mut segments = Vec::with_capacity(num_segments as usize);
 
         // 收集所有段落信息
@@ -392,22 +669,51 @@ impl WhisperRSProcessor {
                 .full_get_segment_t1(i)
                 .map_err(|e| VoiceError::Other(format!("Failed to get segment end time: {}", e)))?;
 
+            let num_tokens = state
+                .full_n_tokens(i)
+                .map_err(|e| VoiceError::Other(format!("Failed to get number of tokens: {}", e)))?;
+            let mut token_probs = Vec::with_capacity(num_tokens as usize);
+            for j in 0..num_tokens {
+                // eot 及之后的 id 都是特殊 token（sot、时间戳等），不计入可信度
+                let is_special = match (state.full_get_token_id(i, j), token_eot) {
+                    (Ok(id), Some(eot)) => id >= eot,
+                    _ => false,
+                };
+                if is_special {
+                    continue;
+                }
+                if let Ok(prob) = state.full_get_token_prob(i, j) {
+                    token_probs.push(prob);
+                }
+            }
+
             segments.push(SegmentData {
                 text: segment_text.trim().to_string(),
                 start_ms: (segment_start as u64) * 10, // whisper uses 100ms units
                 end_ms: (segment_end as u64) * 10,
                 index: i,
+                no_speech_prob: state.full_get_segment_no_speech_prob(i),
+                token_probs,
             });
         }
 
-        // 根据格式生成输出
-        match output_format {
-            OutputFormat::Text => Ok(self.format_as_text(&segments)),
-            OutputFormat::Json => Ok(self.format_as_json(&segments)),
-            OutputFormat::Srt => Ok(self.format_as_srt(&segments)),
-            OutputFormat::Vtt => Ok(self.format_as_vtt(&segments)),
-            OutputFormat::Csv => Ok(self.format_as_csv(&segments)),
+        let confidence = summarize_confidence(&segments);
+        if let Some(c) = confidence {
+            tracing::debug!("📊 Confidence: {:.2}, no-speech probability: {:.2}", c.confidence, c.no_speech_prob);
         }
+
+        // 可信度按整段录音统计，过滤只影响输出的文本
+        let segments = filter_low_confidence_segments(segments, self.config.min_segment_confidence);
+
+        // 根据格式生成输出
+        let text = match output_format {
+            OutputFormat::Text => self.format_as_text(&segments),
+            OutputFormat::Json => self.format_as_json(&segments),
+            OutputFormat::Srt => self.format_as_srt(&segments),
+            OutputFormat::Vtt => self.format_as_vtt(&segments),
+            OutputFormat::Csv => self.format_as_csv(&segments),
+        };
+        Ok(AsrTranscript::new(text, confidence))
     }
 
     
@@ -527,25 +833,6 @@ impl WhisperRSProcessor {
             hours, minutes, seconds, milliseconds
         )
     }
-
-    fn preprocess_audio(&self, audio_data: &[f32]) -> Vec<f32> {
-        // Check if we need to convert stereo to mono
-        // If the audio length is even, we assume it might be stereo
-        if audio_data.len() % 2 == 0 {
-            // Try to convert from stereo to mono by averaging pairs
-            let mut mono_audio = Vec::with_capacity(audio_data.len() / 2);
-            for chunk in audio_data.chunks_exact(2) {
-                let mono_sample = (chunk[0] + chunk[1]) / 2.0;
-                mono_audio.push(mono_sample);
-            }
-            println!("🔄 Converted stereo audio to mono: {} -> {} samples", audio_data.len(), mono_audio.len());
-            mono_audio
-        } else {
-            // Already mono
-            println!("📊 Audio is already mono: {} samples", audio_data.len());
-            audio_data.to_vec()
-        }
-    }
 }
 
 impl AsrProcessor for WhisperRSProcessor {
@@ -553,18 +840,37 @@ impl AsrProcessor for WhisperRSProcessor {
         &self,
         audio_buffer: Cursor<Vec<u8>>,
         mode: Mode,  // 🔥 使用传入的mode参数
-        _prompt: &str,
-    ) -> Result<String, VoiceError> {
-        // Convert byte buffer to f32 audio samples
-        let audio_data = self.convert_bytes_to_f32(audio_buffer.into_inner())?;
+        prompt: &str,
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.transcribe_wav(audio_buffer, mode, prompt, &CancelToken::default())
+    }
 
-        // 🔥 关键修复：使用传入的mode参数，而不是config.translate
-        println!("🔍 [ASR] process_audio called with mode: {:?}", mode);
-        self.process_audio_data_with_mode(&audio_data, mode)
+    fn process_audio_cancellable(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+        cancel: &CancelToken,
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.transcribe_wav(audio_buffer, mode, prompt, cancel)
+    }
+
+    fn get_processor_type(&self) -> &str {
+        Self::PROCESSOR_TYPE
     }
 
-    fn get_processor_type(&self) -> Option<&str> {
-        Some("whisper-rs")
+    fn health_check(&self) -> Result<(), VoiceError> {
+        if !std::path::Path::new(&self.config.model_path).exists() {
+            return Err(VoiceError::Other(format!("Whisper model file not found: {}", self.config.model_path)));
+        }
+        if self.ctx.is_none() {
+            return Err(VoiceError::Other("Whisper model is not loaded".to_string()));
+        }
+        Ok(())
+    }
+
+    fn whisper_backend(&self) -> Option<WhisperBackend> {
+        Some(self.effective_backend.clone())
     }
 
     fn unload(&mut self) {
@@ -573,169 +879,97 @@ impl AsrProcessor for WhisperRSProcessor {
 }
 
 impl WhisperRSProcessor {
+    /// 解析 WAV 并转成 whisper 需要的 16kHz 单声道 f32（任意采样率、声道数和位深）
     fn convert_bytes_to_f32(&self, audio_bytes: Vec<u8>) -> Result<Vec<f32>, VoiceError> {
-        // Try to parse as WAV file using hound
         let cursor = std::io::Cursor::new(audio_bytes);
-        match hound::WavReader::new(cursor) {
-            Ok(mut reader) => {
-                let spec = reader.spec();
-
-                match spec.sample_format {
-                    hound::SampleFormat::Int => {
-                        // Convert integer samples to f32
-                        let samples: Result<Vec<f32>, _> = reader.samples::<i16>()
-                            .map(|s| s.map(|sample| sample as f32 / 32768.0))
-                            .collect();
-
-                        let mut float_samples = samples.map_err(|e|
-                            VoiceError::Other(format!("Failed to parse WAV samples: {}", e))
-                        )?;
-
-                        // Convert stereo to mono if needed
-                        if spec.channels == 2 {
-                            let mut mono_samples = Vec::with_capacity(float_samples.len() / 2);
-                            for chunk in float_samples.chunks_exact(2) {
-                                let mono_sample = (chunk[0] + chunk[1]) / 2.0;
-                                mono_samples.push(mono_sample);
-                            }
-                            float_samples = mono_samples;
-                            println!("🔄 Converted stereo WAV to mono: {} -> {} samples",
-                                    float_samples.len() * 2, float_samples.len());
-                        }
-
-                        Ok(float_samples)
-                    }
-                    hound::SampleFormat::Float => {
-                        // Already float samples
-                        let samples: Result<Vec<f32>, _> = reader.samples::<f32>()
-                            .map(|s| s.map(|sample| sample))
-                            .collect();
-
-                        let mut float_samples = samples.map_err(|e|
-                            VoiceError::Other(format!("Failed to parse WAV samples: {}", e))
-                        )?;
-
-                        // Convert stereo to mono if needed
-                        if spec.channels == 2 {
-                            let mut mono_samples = Vec::with_capacity(float_samples.len() / 2);
-                            for chunk in float_samples.chunks_exact(2) {
-                                let mono_sample = (chunk[0] + chunk[1]) / 2.0;
-                                mono_samples.push(mono_sample);
-                            }
-                            float_samples = mono_samples;
-                            println!("🔄 Converted stereo WAV to mono: {} -> {} samples",
-                                    float_samples.len() * 2, float_samples.len());
-                        }
-
-                        Ok(float_samples)
-                    }
-                }
-            }
-            Err(e) => {
-                // If it's not a valid WAV file, assume raw f32 data
-                Err(VoiceError::Other(format!("Failed to parse WAV file: {}. Expected valid WAV format.", e)))
+        let mut reader = hound::WavReader::new(cursor)
+            .map_err(|e| VoiceError::Other(format!("Failed to parse WAV file: {}. Expected valid WAV format.", e)))?;
+        let spec = reader.spec();
+
+        let samples: Result<Vec<f32>, _> = match spec.sample_format {
+            hound::SampleFormat::Int => {
+                // 按位深缩放到 [-1, 1)
+                let scale = (1i64 << (spec.bits_per_sample.max(1) - 1)) as f32;
+                reader.samples::<i32>().map(|s| s.map(|sample| sample as f32 / scale)).collect()
             }
+            hound::SampleFormat::Float => reader.samples::<f32>().collect(),
+        };
+        let samples = samples.map_err(|e| VoiceError::Other(format!("Failed to parse WAV samples: {}", e)))?;
+
+        if spec.sample_rate != crate::voice_assistant::audio::TARGET_SAMPLE_RATE || spec.channels > 1 {
+            tracing::debug!("🔄 Resampling {}Hz {}-channel audio to 16kHz mono", spec.sample_rate, spec.channels);
         }
+        Ok(crate::voice_assistant::audio::resample_to_16k_mono(&samples, spec.sample_rate, spec.channels))
     }
 
     fn apply_vad_filtering(&self, audio_data: &[f32]) -> Result<Vec<f32>, VoiceError> {
         if self.enable_basic_vad {
-            println!("🎯 Applying basic energy-based VAD filtering to {} audio samples", audio_data.len());
-            
-            let filtered_audio = self.basic_energy_vad(audio_data);
-            
-            println!("✅ Basic VAD filtered: {} -> {} samples (removed {:.1}% non-speech audio)", 
-                     audio_data.len(), filtered_audio.len(), 
-                     (1.0 - filtered_audio.len() as f64 / audio_data.len() as f64) * 100.0);
-            
-            Ok(filtered_audio)
+            tracing::debug!("🎯 Trimming leading/trailing silence from {} audio samples", audio_data.len());
+
+            let range = super::trim_silence(audio_data, 16000).ok_or(VoiceError::NoSpeech)?;
+            let trimmed = audio_data[range.clone()].to_vec();
+
+            tracing::debug!("✅ VAD trimmed {:.2}s head / {:.2}s tail",
+                     range.start as f32 / 16000.0,
+                     (audio_data.len() - range.end) as f32 / 16000.0);
+
+            Ok(trimmed)
         } else {
             println!("⚠️ VAD not enabled, returning original audio");
             Ok(audio_data.to_vec())
         }
     }
 
-    // Basic energy-based VAD implementation (thread-safe)
-    fn basic_energy_vad(&self, audio_data: &[f32]) -> Vec<f32> {
-        let window_size = 1024; // 64ms windows at 16kHz
-        let overlap = 512; // 32ms overlap
-        let energy_threshold = 0.01; // Energy threshold for speech detection
-        
-        if audio_data.len() < window_size {
-            return audio_data.to_vec();
+    /// 运行时修改段落可信度阈值，无需重新加载模型
+    pub fn set_min_segment_confidence(&mut self, min_confidence: f32) {
+        if self.config.min_segment_confidence != min_confidence {
+            tracing::info!("🧹 WhisperRS minimum segment confidence: {:.2}", min_confidence);
         }
-        
-        let mut speech_segments = Vec::new();
-        let mut in_speech = false;
-        let mut speech_start = 0;
-        
-        // Process audio in windows
-        for i in (0..audio_data.len() - window_size + 1).step_by(overlap) {
-            let window = &audio_data[i..i + window_size];
-            
-            // Calculate RMS energy
-            let energy: f32 = (window.iter().map(|&x| x * x).sum::<f32>() / window_size as f32).sqrt();
-            
-            if energy > energy_threshold {
-                if !in_speech {
-                    // Start of speech segment
-                    speech_start = i;
-                    in_speech = true;
-                }
-            } else {
-                if in_speech {
-                    // End of speech segment
-                    speech_segments.push((speech_start, i));
-                    in_speech = false;
-                }
-            }
+        self.config.min_segment_confidence = min_confidence;
+    }
+
+    /// 运行时切换 VAD，无需重新加载模型
+    pub fn set_vad_enabled(&mut self, enabled: bool) {
+        if self.config.enable_vad != enabled {
+            tracing::info!("🎯 WhisperRS VAD {}", if enabled { "enabled" } else { "disabled" });
         }
-        
-        // Handle case where speech extends to end
-        if in_speech {
-            speech_segments.push((speech_start, audio_data.len()));
+        self.config.enable_vad = enabled;
+        self.enable_basic_vad = enabled;
+    }
+
+    /// 开关 WhisperState 复用；关闭时释放缓存的 state
+    pub fn set_reuse_state(&mut self, enabled: bool) {
+        if self.config.reuse_state != enabled {
+            tracing::info!("🧠 WhisperRS state reuse {}", if enabled { "enabled" } else { "disabled" });
         }
-        
-        // Merge speech segments into continuous audio
-        let total_samples: usize = speech_segments.iter()
-            .map(|(start, end)| end - start)
-            .sum();
-        
-        let mut filtered_audio = Vec::with_capacity(total_samples);
-        for (start, end) in speech_segments {
-            filtered_audio.extend_from_slice(&audio_data[start..end]);
+        self.config.reuse_state = enabled;
+        if !enabled {
+            if let Ok(mut cached) = self._state_guard.lock() {
+                *cached = None;
+            }
         }
-        
-        filtered_audio
     }
 }
 
 // Factory functions for easy creation
 impl WhisperRSProcessor {
+    /// 听写使用的处理器：会被反复调用，复用 state 降低短语音延迟
     pub fn with_model_path(model_path: &str) -> Result<Self, VoiceError> {
-        println!("📍 [DEBUG] Step 1: with_model_path called with: {}", model_path);
-
         // 🔥 简化：直接使用CPU后端，避免GPU detector死锁
-        println!("📍 [DEBUG] Step 2: Using CPU backend (skipping GPU detection)");
-        let backend = WhisperBackend::CPU;
-        println!("📍 [DEBUG] Step 3: Backend: {:?}", backend);
-
         let config = WhisperRSConfig {
             model_path: model_path.to_string(),
             sampling_strategy: SamplingStrategyConfig::Greedy { best_of: 1 },
             language: None,
             translate: false,
             enable_vad: false,
-            backend,
+            backend: WhisperBackend::CPU,
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: true,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
-
-        println!("📍 [DEBUG] Step 4: Config created, calling Self::new...");
-        let result = Self::new(config);
-        println!("📍 [DEBUG] Step 5: Self::new returned: {:?}", result.is_ok());
-        result
+        Self::new(config)
     }
 
     pub fn with_model_path_and_backend(model_path: &str, backend: WhisperBackend) -> Result<Self, VoiceError> {
@@ -749,6 +983,8 @@ impl WhisperRSProcessor {
             use_gpu_if_available: true,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
         Self::new(config)
     }
@@ -765,6 +1001,8 @@ impl WhisperRSProcessor {
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
         Self::new(config)
     }
@@ -785,6 +1023,8 @@ impl WhisperRSProcessor {
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
         Self::new(config)
     }
@@ -802,6 +1042,8 @@ impl WhisperRSProcessor {
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
         Self::new(config)
     }
@@ -818,6 +1060,8 @@ impl WhisperRSProcessor {
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
         Self::new(config)
     }
@@ -839,6 +1083,8 @@ impl WhisperRSProcessor {
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
+            min_segment_confidence: configured_min_segment_confidence(),
         };
         Self::new(config)
     }
@@ -848,6 +1094,32 @@ impl WhisperRSProcessor {
 mod tests {
     use super::*;
 
+    #[derive(Default)]
+    struct RecordedPrompt(Option<String>);
+
+    impl PromptParams for RecordedPrompt {
+        fn set_initial_prompt(&mut self, prompt: &str) {
+            self.0 = Some(prompt.to_string());
+        }
+    }
+
+    #[test]
+    fn test_initial_prompt_is_applied() {
+        let mut params = RecordedPrompt::default();
+        assert!(apply_initial_prompt(&mut params, " Kubernetes, kubectl, Grafana "));
+        assert_eq!(params.0.as_deref(), Some("Kubernetes, kubectl, Grafana"));
+
+        let mut params = RecordedPrompt::default();
+        assert!(apply_initial_prompt(&mut params, "Tauri\0 whisper"));
+        assert_eq!(params.0.as_deref(), Some("Tauri whisper"));
+
+        let mut params = RecordedPrompt::default();
+        assert!(!apply_initial_prompt(&mut params, "  "));
+        assert_eq!(params.0, None);
+
+        assert_eq!(effective_prompt("Grafana"), "Grafana");
+    }
+
     #[test]
     fn test_config_creation() {
         let config = WhisperRSConfig {
@@ -859,12 +1131,122 @@ mod tests {
             use_gpu_if_available: false,
             gpu_device_id: None,
             output_format: OutputFormat::Text,
+            reuse_state: false,
             enable_vad: false,
+            min_segment_confidence: 0.0,
         };
         
         assert_eq!(config.model_path, "test.bin");
+        assert_eq!(config.min_segment_confidence, 0.0);
         assert!(matches!(config.sampling_strategy, SamplingStrategyConfig::Greedy { best_of: 1 }));
         assert_eq!(config.language, Some("en".to_string()));
         assert!(!config.translate);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_parse_system_info_backends() {
+        // 旧版 whisper.cpp：标志位格式
+        let legacy = "AVX = 1 | AVX2 = 1 | METAL = 0 | CUDA = 1 | COREML = 0 | OPENVINO = 0";
+        assert_eq!(parse_system_info_backends(legacy), vec![WhisperBackend::CUDA]);
+
+        // 新版：每个已注册的 ggml 后端一段
+        let current = "WHISPER : COREML = 0 | OPENVINO = 0 | CUDA : ARCHS = 890 | USE_GRAPHS = 1 | Vulkan : | CPU : SSE3 = 1 | AVX = 1 |";
+        assert_eq!(parse_system_info_backends(current), vec![WhisperBackend::CUDA, WhisperBackend::Vulkan]);
+
+        let cpu_only = "WHISPER : COREML = 0 | OPENVINO = 0 | CPU : SSE3 = 1 | AVX2 = 1 |";
+        assert!(parse_system_info_backends(cpu_only).is_empty());
+    }
+
+    #[test]
+    fn test_summarize_confidence() {
+        let segment = |no_speech_prob: f32, token_probs: Vec<f32>| SegmentData {
+            text: String::new(),
+            start_ms: 0,
+            end_ms: 0,
+            index: 0,
+            no_speech_prob,
+            token_probs,
+        };
+
+        assert_eq!(summarize_confidence(&[]), None);
+
+        let summary = summarize_confidence(&[segment(0.9, vec![0.5, 0.5]), segment(0.1, vec![0.125])]).unwrap();
+        // (0.5 * 0.5 * 0.125) 的几何平均
+        assert!((summary.confidence - 0.3150).abs() < 1e-3);
+        assert_eq!(summary.no_speech_prob, 0.1);
+
+        // 只有特殊 token 的段落（如空录音）可信度为 0
+        let summary = summarize_confidence(&[segment(0.95, vec![])]).unwrap();
+        assert_eq!(summary.confidence, 0.0);
+        assert_eq!(summary.no_speech_prob, 0.95);
+    }
+
+    #[test]
+    fn test_filter_low_confidence_segments() {
+        let segment = |index: i32, text: &str, token_probs: Vec<f32>| SegmentData {
+            text: text.to_string(),
+            start_ms: 0,
+            end_ms: 0,
+            index,
+            no_speech_prob: 0.1,
+            token_probs,
+        };
+        let segments = vec![
+            segment(0, "Turn on the lights.", vec![0.9, 0.8, 0.95]),
+            segment(1, "Thanks for watching!", vec![0.2, 0.1, 0.15]),
+            segment(2, "", vec![]),
+        ];
+        assert!((segments[0].confidence().unwrap() - 0.881).abs() < 1e-3);
+        assert_eq!(segments[2].confidence(), None);
+
+        let kept = filter_low_confidence_segments(segments.clone(), 0.4);
+        let indexes: Vec<i32> = kept.iter().map(|seg| seg.index).collect();
+        assert_eq!(indexes, [0, 2]);
+
+        // 阈值为 0 时不过滤
+        assert_eq!(filter_low_confidence_segments(segments, 0.0).len(), 3);
+    }
+
+    /// 对比复用 WhisperState 前后的识别延迟：同一段短音频各识别 10 次。
+    /// 需要本地模型，运行方式：
+    /// `WHISPER_MODEL_PATH=./models/ggml-base.bin cargo test --release state_reuse_latency -- --ignored --nocapture`
+    /// 复用 state 省掉的是每次 create_state 分配 KV cache/计算缓冲的时间，模型越大差距越明显
+    #[test]
+    #[ignore]
+    fn bench_state_reuse_latency() {
+        let model_path = std::env::var("WHISPER_MODEL_PATH").unwrap_or_else(|_| "./models/ggml-base.bin".to_string());
+        if !Path::new(&model_path).exists() {
+            println!("⏭️ Model not found at {}, skipping benchmark", model_path);
+            return;
+        }
+
+        // 1.5 秒带包络的 220Hz 音频，足够走完整个推理流程
+        let clip: Vec<f32> = (0..24000)
+            .map(|i| {
+                let t = i as f32 / 16000.0;
+                0.3 * (2.0 * std::f32::consts::PI * 220.0 * t).sin() * (std::f32::consts::PI * t / 1.5).sin()
+            })
+            .collect();
+
+        let mut processor = WhisperRSProcessor::with_model_path(&model_path).unwrap();
+        let mut results = Vec::new();
+        for reuse in [false, true] {
+            processor.set_reuse_state(reuse);
+            // 预热一次，不计入统计
+            let _ = processor.process_audio_data_with_mode(&clip, Mode::Transcriptions, "", &CancelToken::default());
+
+            let start = Instant::now();
+            for _ in 0..10 {
+                let _ = processor.process_audio_data_with_mode(&clip, Mode::Transcriptions, "", &CancelToken::default());
+            }
+            let average = start.elapsed() / 10;
+            println!("📊 reuse_state={}: average {:?} per transcription", reuse, average);
+            results.push(average);
+        }
+
+        println!(
+            "📊 State reuse saved {:?} per transcription",
+            results[0].saturating_sub(results[1])
+        );
+    }
+}
@@ -0,0 +1,683 @@
+use std::io::{BufRead, BufReader, Cursor, Write};
+use std::path::PathBuf;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+use base64::{Engine as _, engine::general_purpose::STANDARD};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+use crate::voice_assistant::asr::race::CancelToken;
+use crate::voice_assistant::asr::whisper_rs::{effective_prompt, WhisperBackend, WhisperDecoding, WhisperRSConfig, WhisperRSProcessor};
+use crate::voice_assistant::{AsrConfidence, AsrProcessor, AsrTranscript, Mode, VoiceError};
+
+/// 以 worker 模式启动应用自身时的命令行参数：`--whisper-worker <config_json>`。
+/// worker 进程不读数据库，后端、GPU 设备、VAD 等设置由应用进程序列化到配置中
+pub const WORKER_FLAG: &str = "--whisper-worker";
+/// 设置为 1 时在应用进程内直接推理，不启动 worker（调试用）
+pub const IN_PROCESS_ENV: &str = "VOICETYPE_WHISPER_IN_PROCESS";
+/// worker 的 stdout 上还有 whisper-rs 的调试输出，协议消息用这个前缀区分
+const MESSAGE_PREFIX: &str = "@@voicetype-whisper@@ ";
+/// 推理进行中写入 worker stdin 的取消命令，worker 的读线程收到后取消正在处理的请求
+const CANCEL_COMMAND: &str = "cancel";
+/// 等待 worker 回复期间检查取消标记的间隔
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(20);
+/// 单次推理超时的默认值（秒），可通过 WHISPER_INFERENCE_TIMEOUT_SECS 覆盖
+const DEFAULT_INFERENCE_TIMEOUT_SECS: u64 = 120;
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WorkerRequest {
+    translate: bool,
+    /// WAV 文件内容
+    audio_base64: String,
+    /// worker 进程不读数据库，应用进程把当前的解码设置随请求带过来
+    #[serde(default)]
+    decoding: WhisperDecoding,
+    /// 已经合并了默认提示词的 initial_prompt
+    #[serde(default)]
+    prompt: String,
+    /// 低于该可信度的段落不输出，同样随请求带过来
+    #[serde(default)]
+    min_segment_confidence: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum WorkerMessage {
+    /// 模型加载完成，可以接收请求
+    Ready {
+        /// 实际使用的后端，请求的 GPU 后端不可用时为 CPU
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        backend: Option<WhisperBackend>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        gpu_fallback_reason: Option<String>,
+    },
+    Text {
+        text: String,
+        /// whisper 检测到的语言，旧版本 worker 不发送
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        language: Option<String>,
+        /// 识别可信度，旧版本 worker 不发送
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        confidence: Option<AsrConfidence>,
+    },
+    NoSpeech,
+    Error { message: String },
+}
+
+fn encode_message(message: &WorkerMessage) -> String {
+    format!("{}{}", MESSAGE_PREFIX, serde_json::to_string(message).unwrap_or_default())
+}
+
+fn parse_message(line: &str) -> Option<WorkerMessage> {
+    line.strip_prefix(MESSAGE_PREFIX)
+        .and_then(|json| serde_json::from_str(json.trim_end()).ok())
+}
+
+/// 默认在独立进程中推理，whisper.cpp abort() 时只会结束 worker
+pub fn worker_enabled() -> bool {
+    !std::env::var(IN_PROCESS_ENV)
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+fn inference_timeout() -> Duration {
+    let secs = std::env::var("WHISPER_INFERENCE_TIMEOUT_SECS")
+        .ok()
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_INFERENCE_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// worker 进程的健康状态，在 get_whisper_manager_status 中展示
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WhisperWorkerHealth {
+    pub running: bool,
+    pub pid: Option<u32>,
+    pub model_path: Option<String>,
+    /// worker 加载模型时实际使用的后端
+    pub backend: Option<WhisperBackend>,
+    /// 启动次数，包括崩溃/超时后的重启
+    pub starts: u64,
+    pub restarts: u64,
+    pub crashes: u64,
+    pub timeouts: u64,
+    pub last_error: Option<String>,
+}
+
+static WORKER_HEALTH: OnceLock<Arc<Mutex<WhisperWorkerHealth>>> = OnceLock::new();
+
+fn shared_health() -> Arc<Mutex<WhisperWorkerHealth>> {
+    WORKER_HEALTH
+        .get_or_init(|| Arc::new(Mutex::new(WhisperWorkerHealth::default())))
+        .clone()
+}
+
+pub fn worker_health() -> WhisperWorkerHealth {
+    shared_health().lock().unwrap().clone()
+}
+
+/// 启动 worker 的命令，默认是应用自身加上 `--whisper-worker <config_json>`
+#[derive(Debug, Clone)]
+struct WorkerCommand {
+    program: PathBuf,
+    args: Vec<String>,
+}
+
+impl WorkerCommand {
+    fn for_config(config: &WhisperRSConfig) -> Result<Self, VoiceError> {
+        let program = std::env::current_exe()
+            .map_err(|e| VoiceError::Other(format!("Failed to locate app executable for whisper worker: {}", e)))?;
+        let config = serde_json::to_string(config)
+            .map_err(|e| VoiceError::Other(format!("Failed to serialize whisper worker config: {}", e)))?;
+        Ok(Self {
+            program,
+            args: vec![WORKER_FLAG.to_string(), config],
+        })
+    }
+}
+
+struct WorkerProcess {
+    child: Child,
+    /// 请求和取消命令都写入这里
+    stdin: Arc<Mutex<ChildStdin>>,
+    /// 读线程在 worker 退出（stdout 关闭）时结束，接收端随之得到 Disconnected
+    messages: Receiver<WorkerMessage>,
+}
+
+impl WorkerProcess {
+    fn spawn(command: &WorkerCommand) -> Result<Self, VoiceError> {
+        let mut child = Command::new(&command.program)
+            .args(&command.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::inherit())
+            .spawn()
+            .map_err(|e| VoiceError::Other(format!("Failed to start whisper worker: {}", e)))?;
+
+        let (Some(stdin), Some(stdout)) = (child.stdin.take(), child.stdout.take()) else {
+            let _ = child.kill();
+            return Err(VoiceError::Other("Whisper worker pipes are not available".to_string()));
+        };
+
+        let (tx, rx) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else { break };
+                match parse_message(&line) {
+                    Some(message) => {
+                        if tx.send(message).is_err() {
+                            break;
+                        }
+                    }
+                    None => debug!("[whisper-worker] {}", line),
+                }
+            }
+        });
+
+        Ok(Self { child, stdin: Arc::new(Mutex::new(stdin)), messages: rx })
+    }
+
+    fn send(&mut self, request: &WorkerRequest) -> std::io::Result<()> {
+        let line = serde_json::to_string(request)?;
+        write_line(&self.stdin, &line)
+    }
+
+    fn kill(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+
+    /// 等待已退出的 worker，返回退出状态（如 "signal: 6 (SIGABRT)"）
+    fn exit_status(&mut self) -> String {
+        match self.child.wait() {
+            Ok(status) => status.to_string(),
+            Err(e) => format!("unknown exit status: {}", e),
+        }
+    }
+}
+
+fn write_line(stdin: &Mutex<ChildStdin>, line: &str) -> std::io::Result<()> {
+    let mut stdin = stdin.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    writeln!(stdin, "{}", line)?;
+    stdin.flush()
+}
+
+impl Drop for WorkerProcess {
+    fn drop(&mut self) {
+        self.kill();
+    }
+}
+
+/// 在独立进程中运行 whisper.cpp 推理：worker 崩溃或超时时返回 VoiceError
+/// （由 ASR 回退链切换到下一个处理器），下一次请求时自动重启 worker
+pub struct SupervisedWhisperProcessor {
+    command: WorkerCommand,
+    model_path: String,
+    /// 设置中选择的后端，worker 回退到 CPU 时用于提示
+    requested_backend: WhisperBackend,
+    worker: Mutex<Option<WorkerProcess>>,
+    health: Arc<Mutex<WhisperWorkerHealth>>,
+    load_timeout: Duration,
+    inference_timeout: Duration,
+}
+
+impl SupervisedWhisperProcessor {
+    /// 按给定配置启动 worker 并等待模型加载完成
+    pub fn start(config: WhisperRSConfig) -> Result<Self, VoiceError> {
+        let mut processor = Self::with_command(
+            WorkerCommand::for_config(&config)?,
+            &config.model_path,
+            shared_health(),
+            crate::voice_assistant::global_whisper::model_load_timeout(),
+            inference_timeout(),
+        );
+        processor.requested_backend = config.backend;
+        processor.ensure_started(&mut processor.lock_worker())?;
+        Ok(processor)
+    }
+
+    fn with_command(
+        command: WorkerCommand,
+        model_path: &str,
+        health: Arc<Mutex<WhisperWorkerHealth>>,
+        load_timeout: Duration,
+        inference_timeout: Duration,
+    ) -> Self {
+        Self {
+            command,
+            model_path: model_path.to_string(),
+            requested_backend: WhisperBackend::default(),
+            worker: Mutex::new(None),
+            health,
+            load_timeout,
+            inference_timeout,
+        }
+    }
+
+    pub fn health(&self) -> WhisperWorkerHealth {
+        self.health.lock().unwrap().clone()
+    }
+
+    fn lock_worker(&self) -> MutexGuard<'_, Option<WorkerProcess>> {
+        // 推理线程 panic 不影响后续请求，worker 状态由下面的逻辑自行恢复
+        self.worker.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// 等待 worker 回复，超过推理超时返回 Timeout。cancel 被置位时（竞速输掉）向 worker 发送一次取消命令，
+    /// worker 随即返回错误并释放锁，不必等推理跑完
+    fn wait_for_response(&self, worker: &WorkerProcess, cancel: &CancelToken) -> Result<WorkerMessage, RecvTimeoutError> {
+        let deadline = Instant::now() + self.inference_timeout;
+        let mut cancel_sent = false;
+        loop {
+            if !cancel_sent && cancel.is_cancelled() {
+                cancel_sent = true;
+                info!("🛑 Cancelling whisper inference in worker");
+                if let Err(e) = write_line(&worker.stdin, CANCEL_COMMAND) {
+                    warn!("Failed to send cancel to whisper worker: {}", e);
+                }
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match worker.messages.recv_timeout(remaining.min(CANCEL_POLL_INTERVAL)) {
+                Err(RecvTimeoutError::Timeout) if remaining > CANCEL_POLL_INTERVAL => continue,
+                response => return response,
+            }
+        }
+    }
+
+    /// 记录 worker 异常退出，返回给调用方的错误
+    fn record_failure(&self, crashed: bool, timed_out: bool, message: String) -> VoiceError {
+        warn!("💥 {}", message);
+        let mut health = self.health.lock().unwrap();
+        health.running = false;
+        health.pid = None;
+        if crashed {
+            health.crashes += 1;
+        }
+        if timed_out {
+            health.timeouts += 1;
+        }
+        health.last_error = Some(message.clone());
+        VoiceError::Other(message)
+    }
+
+    fn ensure_started<'a>(&self, slot: &'a mut Option<WorkerProcess>) -> Result<&'a mut WorkerProcess, VoiceError> {
+        if slot.is_none() {
+            *slot = Some(self.spawn_worker()?);
+        }
+        slot.as_mut().ok_or_else(|| VoiceError::Other("Whisper worker is not running".to_string()))
+    }
+
+    fn spawn_worker(&self) -> Result<WorkerProcess, VoiceError> {
+        let mut process = WorkerProcess::spawn(&self.command)
+            .map_err(|e| self.record_failure(false, false, e.to_string()))?;
+        {
+            let mut health = self.health.lock().unwrap();
+            if health.starts > 0 {
+                health.restarts += 1;
+            }
+            health.starts += 1;
+            health.pid = Some(process.child.id());
+            health.model_path = Some(self.model_path.clone());
+        }
+        info!("🛡️ Whisper worker started (pid {}) for model: {}", process.child.id(), self.model_path);
+
+        match process.messages.recv_timeout(self.load_timeout) {
+            Ok(WorkerMessage::Ready { backend, gpu_fallback_reason }) => {
+                if let Some(reason) = &gpu_fallback_reason {
+                    crate::voice_assistant::coordinator::emit_gpu_backend_fallback(&self.requested_backend.to_string(), reason);
+                }
+                let mut health = self.health.lock().unwrap();
+                health.running = true;
+                health.backend = backend;
+                Ok(process)
+            }
+            Ok(WorkerMessage::Error { message }) => {
+                Err(self.record_failure(false, false, format!("Whisper worker failed to load model: {}", message)))
+            }
+            Ok(other) => {
+                Err(self.record_failure(false, false, format!("Unexpected message from whisper worker: {:?}", other)))
+            }
+            Err(RecvTimeoutError::Timeout) => Err(self.record_failure(
+                false,
+                true,
+                format!("Whisper worker timed out loading model after {}s", self.load_timeout.as_secs()),
+            )),
+            Err(RecvTimeoutError::Disconnected) => {
+                let status = process.exit_status();
+                Err(self.record_failure(true, false, format!("Whisper worker exited while loading model ({})", status)))
+            }
+        }
+    }
+}
+
+impl AsrProcessor for SupervisedWhisperProcessor {
+    fn process_audio(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.process_audio_cancellable(audio_buffer, mode, prompt, &CancelToken::default())
+    }
+
+    fn process_audio_cancellable(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+        cancel: &CancelToken,
+    ) -> Result<AsrTranscript, VoiceError> {
+        let request = WorkerRequest {
+            translate: mode == Mode::Translations,
+            audio_base64: STANDARD.encode(audio_buffer.into_inner()),
+            decoding: crate::voice_assistant::settings::current().whisper_decoding,
+            prompt: effective_prompt(prompt),
+            min_segment_confidence: crate::voice_assistant::settings::current().whisper_min_segment_confidence,
+        };
+
+        let mut slot = self.lock_worker();
+        // 等待 worker 期间已被取消（竞速中另一边先返回）时不再发送请求
+        if cancel.is_cancelled() {
+            return Err(VoiceError::Other("Whisper inference aborted".to_string()));
+        }
+        let worker = self.ensure_started(&mut slot)?;
+
+        if let Err(e) = worker.send(&request) {
+            // 写入失败说明 worker 已经退出
+            let status = worker.exit_status();
+            *slot = None;
+            return Err(self.record_failure(true, false, format!("Whisper worker is not accepting requests ({}; {})", e, status)));
+        }
+
+        let response = self.wait_for_response(worker, cancel);
+
+        match response {
+            Ok(WorkerMessage::Text { text, language, confidence }) => {
+                Ok(AsrTranscript { language, ..AsrTranscript::new(text, confidence) })
+            }
+            Ok(WorkerMessage::NoSpeech) => Err(VoiceError::NoSpeech),
+            Ok(WorkerMessage::Error { message }) => Err(VoiceError::Other(message)),
+            Ok(WorkerMessage::Ready { .. }) => Err(VoiceError::Other("Unexpected ready message from whisper worker".to_string())),
+            Err(RecvTimeoutError::Timeout) => {
+                // 卡住的 worker 直接结束，下一次请求重新启动
+                worker.kill();
+                *slot = None;
+                Err(self.record_failure(
+                    false,
+                    true,
+                    format!("Whisper inference timed out after {}s, worker will be restarted", self.inference_timeout.as_secs()),
+                ))
+            }
+            Err(RecvTimeoutError::Disconnected) => {
+                let status = worker.exit_status();
+                *slot = None;
+                Err(self.record_failure(true, false, format!("Whisper worker crashed during inference ({})", status)))
+            }
+        }
+    }
+
+    fn get_processor_type(&self) -> &str {
+        WhisperRSProcessor::PROCESSOR_TYPE
+    }
+
+    /// worker 未运行时会在下一次请求时重启，这里只检查模型文件
+    fn health_check(&self) -> Result<(), VoiceError> {
+        if !std::path::Path::new(&self.model_path).exists() {
+            return Err(VoiceError::Other(format!("Whisper model file not found: {}", self.model_path)));
+        }
+        Ok(())
+    }
+
+    /// worker 上一次加载模型时报告的后端
+    fn whisper_backend(&self) -> Option<WhisperBackend> {
+        self.health.lock().unwrap().backend.clone()
+    }
+
+    fn unload(&mut self) {
+        if self.lock_worker().take().is_some() {
+            info!("🗑️ Whisper worker stopped");
+            let mut health = self.health.lock().unwrap();
+            health.running = false;
+            health.pid = None;
+        }
+    }
+}
+
+impl Drop for SupervisedWhisperProcessor {
+    fn drop(&mut self) {
+        self.unload();
+    }
+}
+
+/// 解析 `--whisper-worker <config_json>`，不是 worker 模式时返回 None，配置缺失或无效时返回错误
+pub fn worker_config(mut args: impl Iterator<Item = String>) -> Option<Result<WhisperRSConfig, VoiceError>> {
+    args.by_ref().find(|arg| arg == WORKER_FLAG)?;
+    Some(
+        args.next()
+            .ok_or_else(|| VoiceError::Other("Missing whisper worker config".to_string()))
+            .and_then(|json| {
+                serde_json::from_str(&json)
+                    .map_err(|e| VoiceError::Other(format!("Invalid whisper worker config: {}", e)))
+            }),
+    )
+}
+
+fn emit(message: &WorkerMessage) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{}", encode_message(message));
+    let _ = stdout.flush();
+}
+
+fn handle_request(processor: &mut WhisperRSProcessor, line: &str, cancel: &CancelToken) -> WorkerMessage {
+    let request = match serde_json::from_str::<WorkerRequest>(line) {
+        Ok(request) => request,
+        Err(e) => return WorkerMessage::Error { message: format!("Invalid worker request: {}", e) },
+    };
+    let audio = match STANDARD.decode(&request.audio_base64) {
+        Ok(audio) => audio,
+        Err(e) => return WorkerMessage::Error { message: format!("Invalid audio payload: {}", e) },
+    };
+    let mode = if request.translate { Mode::Translations } else { Mode::Transcriptions };
+    crate::voice_assistant::settings::update(|s| s.whisper_decoding = request.decoding);
+    processor.set_min_segment_confidence(request.min_segment_confidence);
+
+    match processor.transcribe_wav(Cursor::new(audio), mode, &request.prompt, cancel) {
+        Ok(transcript) => WorkerMessage::Text {
+            text: transcript.text,
+            language: transcript.language,
+            confidence: transcript.confidence,
+        },
+        Err(VoiceError::NoSpeech) => WorkerMessage::NoSpeech,
+        Err(e) => WorkerMessage::Error { message: e.to_string() },
+    }
+}
+
+/// worker 进程入口：加载模型后逐行处理 stdin 中的请求，stdin 关闭时退出。返回进程退出码
+pub fn run_worker(config: Result<WhisperRSConfig, VoiceError>) -> i32 {
+    let mut processor = match config.and_then(WhisperRSProcessor::new) {
+        Ok(processor) => processor,
+        Err(e) => {
+            emit(&WorkerMessage::Error { message: e.to_string() });
+            return 1;
+        }
+    };
+    emit(&WorkerMessage::Ready {
+        backend: Some(processor.effective_backend().clone()),
+        gpu_fallback_reason: processor.gpu_fallback_reason().map(str::to_string),
+    });
+
+    // 推理期间主线程被占用，stdin 由单独的线程读取，取消命令才能及时生效
+    let (tx, rx) = mpsc::channel();
+    spawn_stdin_reader(tx);
+    for (line, cancel) in rx {
+        emit(&handle_request(&mut processor, &line, &cancel));
+    }
+    0
+}
+
+/// 逐行读取 stdin：每个请求带着自己的取消标记转交主线程，取消命令只取消最近收到的请求。
+/// stdin 关闭时结束，主线程随之退出
+fn spawn_stdin_reader(requests: mpsc::Sender<(String, CancelToken)>) {
+    std::thread::spawn(move || {
+        let mut current = CancelToken::default();
+        for line in std::io::stdin().lock().lines() {
+            let Ok(line) = line else { break };
+            match line.trim() {
+                "" => continue,
+                CANCEL_COMMAND => current.cancel(),
+                _ => {
+                    current = CancelToken::default();
+                    if requests.send((line, current.clone())).is_err() {
+                        break;
+                    }
+                }
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_roundtrip_ignores_debug_output() {
+        let message = WorkerMessage::Text {
+            text: "你好 world".to_string(),
+            language: Some("zh".to_string()),
+            confidence: Some(AsrConfidence { confidence: 0.5, no_speech_prob: 0.25 }),
+        };
+        assert_eq!(parse_message(&encode_message(&message)), Some(message));
+        // 旧版本 worker 不带 language 和 confidence
+        assert_eq!(
+            parse_message(&format!("{}{{\"type\":\"text\",\"text\":\"hi\"}}", MESSAGE_PREFIX)),
+            Some(WorkerMessage::Text { text: "hi".to_string(), language: None, confidence: None })
+        );
+        assert_eq!(parse_message("📍 [DEBUG] Step 1: with_model_path called"), None);
+        // 旧版本 worker 的 ready 不带后端
+        assert_eq!(
+            parse_message(&format!("{}{{\"type\":\"ready\"}}", MESSAGE_PREFIX)),
+            Some(WorkerMessage::Ready { backend: None, gpu_fallback_reason: None })
+        );
+        assert_eq!(parse_message(&encode_message(&WorkerMessage::NoSpeech)), Some(WorkerMessage::NoSpeech));
+    }
+
+    #[test]
+    fn test_worker_config_from_args() {
+        use crate::voice_assistant::asr::whisper_rs::WhisperBackend;
+
+        let mut config = crate::voice_assistant::global_whisper::whisper_config("/m/ggml-base.bin", WhisperBackend::CUDA);
+        config.gpu_device_id = Some(1);
+        config.enable_vad = true;
+        let command = WorkerCommand::for_config(&config).unwrap();
+        let parsed = worker_config(["voicetype".to_string()].into_iter().chain(command.args)).unwrap().unwrap();
+        assert_eq!(parsed.model_path, "/m/ggml-base.bin");
+        assert_eq!(parsed.backend, WhisperBackend::CUDA);
+        assert_eq!(parsed.gpu_device_id, Some(1));
+        assert!(parsed.enable_vad);
+        assert!(parsed.reuse_state);
+
+        let args = |list: &[&str]| list.iter().map(|s| s.to_string()).collect::<Vec<_>>().into_iter();
+        assert!(worker_config(args(&["voicetype"])).is_none());
+        assert!(worker_config(args(&["voicetype", "--whisper-worker"])).unwrap().is_err());
+        assert!(worker_config(args(&["voicetype", "--whisper-worker", "/m/ggml-base.bin"])).unwrap().is_err());
+    }
+
+    /// 用 shell 脚本模拟 worker：先发送 ready，再按脚本处理请求
+    #[cfg(unix)]
+    fn fake_worker(script: &str, inference_timeout: Duration) -> SupervisedWhisperProcessor {
+        let ready = encode_message(&WorkerMessage::Ready { backend: Some(WhisperBackend::CPU), gpu_fallback_reason: None });
+        let command = WorkerCommand {
+            program: PathBuf::from("sh"),
+            args: vec!["-c".to_string(), format!("echo 'debug noise'; echo '{}'; {}", ready, script)],
+        };
+        SupervisedWhisperProcessor::with_command(
+            command,
+            "/tmp/ggml-test.bin",
+            Arc::new(Mutex::new(WhisperWorkerHealth::default())),
+            Duration::from_secs(5),
+            inference_timeout,
+        )
+    }
+
+    #[cfg(unix)]
+    fn transcribe(processor: &SupervisedWhisperProcessor) -> Result<String, VoiceError> {
+        processor.process_audio(Cursor::new(vec![0u8; 44]), Mode::Transcriptions, "").map(|transcript| transcript.text)
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_worker_result_is_returned() {
+        let text = encode_message(&WorkerMessage::Text {
+            text: "hello".to_string(),
+            language: Some("en".to_string()),
+            confidence: Some(AsrConfidence { confidence: 0.75, no_speech_prob: 0.0 }),
+        });
+        let processor = fake_worker(&format!("while read line; do echo '{}'; done", text), Duration::from_secs(5));
+        let transcript = processor.process_audio(Cursor::new(vec![0u8; 44]), Mode::Transcriptions, "").unwrap();
+        assert_eq!(transcript.text, "hello");
+        assert_eq!(transcript.confidence.map(|c| c.confidence), Some(0.75));
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        assert_eq!(transcribe(&processor).unwrap(), "hello");
+        assert_eq!(processor.whisper_backend(), Some(WhisperBackend::CPU));
+        let health = processor.health();
+        assert!(health.running);
+        assert_eq!((health.starts, health.restarts), (1, 0));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_worker_abort_becomes_error_and_restarts() {
+        let processor = fake_worker("read line; kill -ABRT $$", Duration::from_secs(5));
+
+        let err = transcribe(&processor).unwrap_err().to_string();
+        assert!(err.contains("crashed"), "{}", err);
+        let health = processor.health();
+        assert!(!health.running);
+        assert_eq!(health.crashes, 1);
+
+        // 下一次请求重新启动 worker
+        assert!(transcribe(&processor).is_err());
+        let health = processor.health();
+        assert_eq!((health.starts, health.restarts, health.crashes), (2, 1, 2));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_cancel_is_sent_to_worker() {
+        let aborted = encode_message(&WorkerMessage::Error { message: "Whisper inference aborted".to_string() });
+        // 收到请求后不回复，直到读到取消命令
+        let processor = fake_worker(
+            &format!("while read line; do if [ \"$line\" = {} ]; then echo '{}'; fi; done", CANCEL_COMMAND, aborted),
+            Duration::from_secs(5),
+        );
+
+        let cancel = CancelToken::default();
+        let canceller = cancel.clone();
+        let handle = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            canceller.cancel();
+        });
+        let err = processor
+            .process_audio_cancellable(Cursor::new(vec![0u8; 44]), Mode::Transcriptions, "", &cancel)
+            .unwrap_err()
+            .to_string();
+        handle.join().unwrap();
+        assert!(err.contains("aborted"), "{}", err);
+        // worker 没有被结束，下一次请求继续使用
+        assert!(processor.lock_worker().is_some());
+        assert_eq!(processor.health().restarts, 0);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_hung_worker_is_killed_after_timeout() {
+        let processor = fake_worker("read line; exec sleep 30", Duration::from_millis(200));
+        let err = transcribe(&processor).unwrap_err().to_string();
+        assert!(err.contains("timed out"), "{}", err);
+        assert_eq!(processor.health().timeouts, 1);
+        assert!(processor.lock_worker().is_none());
+    }
+}
@@ -0,0 +1,2 @@
+//! 音频输入的公共入口：把文件解码成样本，再重采样到 whisper 需要的 16kHz 单声道。实现在 `audio_decoder`
+pub use super::audio_decoder::{decode_bytes_to_samples, decode_to_samples, downmix_to_mono, resample, resample_to_16k_mono, TARGET_SAMPLE_RATE};
@@ -0,0 +1,515 @@
+use hound::{WavSpec, WavWriter};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::{Arc, Mutex, OnceLock};
+use symphonia::core::audio::SampleBuffer;
+use symphonia::core::codecs::{DecoderOptions, CODEC_TYPE_NULL};
+use symphonia::core::errors::Error as SymphoniaError;
+use symphonia::core::formats::FormatOptions;
+use symphonia::core::io::MediaSourceStream;
+use symphonia::core::meta::MetadataOptions;
+use symphonia::core::probe::Hint;
+use tracing::{debug, warn};
+use crate::voice_assistant::VoiceError;
+
+/// Whisper 及云端 ASR 期望的采样率
+pub const TARGET_SAMPLE_RATE: u32 = 16000;
+
+/// 根据文件头（magic bytes）识别出的音频容器格式
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    Mp3,
+    /// ADTS 封装的 AAC 裸流
+    Aac,
+    Ogg,
+    Flac,
+    Mp4,
+    WebM,
+    Unknown,
+}
+
+impl AudioFormat {
+    /// 通过文件头识别格式，不信任上传的文件名
+    pub fn detect(data: &[u8]) -> Self {
+        if data.len() >= 12 && &data[0..4] == b"RIFF" && &data[8..12] == b"WAVE" {
+            AudioFormat::Wav
+        } else if data.starts_with(b"OggS") {
+            AudioFormat::Ogg
+        } else if data.starts_with(b"fLaC") {
+            AudioFormat::Flac
+        } else if data.starts_with(b"ID3") {
+            AudioFormat::Mp3
+        } else if data.len() >= 2 && data[0] == 0xFF && (data[1] & 0xF6) == 0xF0 {
+            // ADTS 同步字 0xFFF，layer 固定为 00；MPEG 音频的 layer 00 是保留值，不会与 MP3 混淆
+            AudioFormat::Aac
+        } else if data.len() >= 2 && data[0] == 0xFF && (data[1] & 0xE0) == 0xE0 {
+            // MPEG 音频帧同步字
+            AudioFormat::Mp3
+        } else if data.len() >= 8 && &data[4..8] == b"ftyp" {
+            AudioFormat::Mp4
+        } else if data.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            AudioFormat::WebM
+        } else {
+            AudioFormat::Unknown
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "WAV",
+            AudioFormat::Mp3 => "MP3",
+            AudioFormat::Aac => "AAC",
+            AudioFormat::Ogg => "OGG",
+            AudioFormat::Flac => "FLAC",
+            AudioFormat::Mp4 => "MP4/M4A",
+            AudioFormat::WebM => "WebM/Matroska",
+            AudioFormat::Unknown => "unknown",
+        }
+    }
+
+    fn extension_hint(&self) -> Option<&'static str> {
+        match self {
+            AudioFormat::Wav => Some("wav"),
+            AudioFormat::Mp3 => Some("mp3"),
+            AudioFormat::Aac => Some("aac"),
+            AudioFormat::Ogg => Some("ogg"),
+            AudioFormat::Flac => Some("flac"),
+            AudioFormat::Mp4 => Some("m4a"),
+            _ => None,
+        }
+    }
+
+    /// 当前构建中 symphonia 启用了解码器的格式
+    pub fn is_supported(&self) -> bool {
+        matches!(self, AudioFormat::Wav | AudioFormat::Mp3 | AudioFormat::Aac | AudioFormat::Ogg | AudioFormat::Flac | AudioFormat::Mp4)
+    }
+}
+
+/// 单声道 f32 样本编码为 16-bit PCM WAV，供现有的本地/云端 ASR 管道使用
+pub fn encode_wav(samples: &[f32], sample_rate: u32) -> Result<Vec<u8>, VoiceError> {
+    let spec = WavSpec {
+        channels: 1,
+        sample_rate,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+
+    let mut cursor = Cursor::new(Vec::new());
+    {
+        let mut writer = WavWriter::new(&mut cursor, spec)
+            .map_err(|e| VoiceError::Audio(format!("Failed to create WAV writer: {}", e)))?;
+        for &sample in samples {
+            let value = (sample.clamp(-1.0, 1.0) * i16::MAX as f32) as i16;
+            writer.write_sample(value)
+                .map_err(|e| VoiceError::Audio(format!("Failed to write WAV sample: {}", e)))?;
+        }
+        writer.finalize()
+            .map_err(|e| VoiceError::Audio(format!("Failed to finalize WAV: {}", e)))?;
+    }
+
+    Ok(cursor.into_inner())
+}
+
+/// 解码音频文件（WAV/MP3/M4A/OGG/FLAC）为原始采样率的单声道 f32 样本，返回样本和采样率，
+/// 之后交给 `audio::resample_to_16k_mono` 转成 16kHz
+pub fn decode_to_samples(path: &std::path::Path) -> Result<(Vec<f32>, u32), VoiceError> {
+    let data = std::fs::read(path)
+        .map_err(|e| VoiceError::Audio(format!("Failed to read {}: {}", path.display(), e)))?;
+    decode_bytes_to_samples(&data, None)
+}
+
+/// 同 `decode_to_samples`，解码内存中的音频（如前端上传的文件）。设置 max_secs 时超长的音频直接拒绝：
+/// 容器记录了时长时在解码前检查，否则解码到上限即停止，不会先把整个大文件解码完
+pub fn decode_bytes_to_samples(data: &[u8], max_secs: Option<f64>) -> Result<(Vec<f32>, u32), VoiceError> {
+    decode_mono(AudioFormat::detect(data), data, max_secs)
+}
+
+/// 用 symphonia 解码，多声道取平均，返回（样本、采样率）
+fn decode_mono(format: AudioFormat, data: &[u8], max_secs: Option<f64>) -> Result<(Vec<f32>, u32), VoiceError> {
+    debug!("🔍 Detected audio format: {}", format.name());
+
+    if !format.is_supported() {
+        return Err(VoiceError::Audio(format!(
+            "Unsupported audio format: {} (supported: WAV, MP3, AAC, M4A, OGG, FLAC)",
+            format.name()
+        )));
+    }
+
+    let mut hint = Hint::new();
+    if let Some(ext) = format.extension_hint() {
+        hint.with_extension(ext);
+    }
+
+    let mss = MediaSourceStream::new(Box::new(Cursor::new(data.to_vec())), Default::default());
+    let probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| VoiceError::Audio(format!("Failed to read {} container: {}", format.name(), e)))?;
+
+    let mut reader = probed.format;
+    let track = reader
+        .tracks()
+        .iter()
+        .find(|t| t.codec_params.codec != CODEC_TYPE_NULL)
+        .ok_or_else(|| VoiceError::Audio(format!("No audio track found in {} file", format.name())))?;
+
+    let track_id = track.id;
+    if let (Some(max_secs), Some(frames), Some(rate)) = (max_secs, track.codec_params.n_frames, track.codec_params.sample_rate) {
+        let duration_secs = frames as f64 / rate.max(1) as f64;
+        if duration_secs > max_secs {
+            return Err(VoiceError::Audio(format!("Audio too long: {:.1}s (max: {:.0}s)", duration_secs, max_secs)));
+        }
+    }
+    let mut decoder = symphonia::default::get_codecs()
+        .make(&track.codec_params, &DecoderOptions::default())
+        .map_err(|e| VoiceError::Audio(format!("Unsupported codec in {} file: {}", format.name(), e)))?;
+
+    let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+    let mut channels = track.codec_params.channels.map(|c| c.count()).unwrap_or(0);
+    let mut mono: Vec<f32> = Vec::new();
+
+    loop {
+        let packet = match reader.next_packet() {
+            Ok(packet) => packet,
+            Err(SymphoniaError::IoError(e)) if e.kind() == std::io::ErrorKind::UnexpectedEof => break,
+            Err(SymphoniaError::ResetRequired) => break,
+            Err(e) => return Err(VoiceError::Audio(format!("Failed to read {} packet: {}", format.name(), e))),
+        };
+
+        if packet.track_id() != track_id {
+            continue;
+        }
+
+        let decoded = match decoder.decode(&packet) {
+            Ok(decoded) => decoded,
+            // 单个损坏的帧不影响整体解码
+            Err(SymphoniaError::DecodeError(e)) => {
+                warn!("⚠️ Skipping corrupt {} frame: {}", format.name(), e);
+                continue;
+            }
+            Err(e) => return Err(VoiceError::Audio(format!("Failed to decode {} audio: {}", format.name(), e))),
+        };
+
+        let spec = *decoded.spec();
+        sample_rate = spec.rate;
+        channels = spec.channels.count().max(1);
+
+        let mut buffer = SampleBuffer::<f32>::new(decoded.capacity() as u64, spec);
+        buffer.copy_interleaved_ref(decoded);
+
+        // 多声道取平均转为单声道
+        for frame in buffer.samples().chunks(channels) {
+            mono.push(frame.iter().sum::<f32>() / frame.len() as f32);
+        }
+
+        // 容器没有记录时长（如 MP3/ADTS 裸流）时，解码到上限就停止
+        if let Some(max_secs) = max_secs {
+            if mono.len() as f64 > max_secs * sample_rate as f64 {
+                return Err(VoiceError::Audio(format!("Audio too long: more than {:.0}s", max_secs)));
+            }
+        }
+    }
+
+    if sample_rate == 0 || mono.is_empty() {
+        return Err(VoiceError::Audio(format!("{} file contains no decodable audio", format.name())));
+    }
+
+    debug!("🎵 Decoded {} samples at {}Hz ({} channel(s))", mono.len(), sample_rate, channels);
+    Ok((mono, sample_rate))
+}
+
+/// sinc 插值每侧使用的过零点数，越大过渡带越窄
+const SINC_ZERO_CROSSINGS: f64 = 16.0;
+/// 截止频率相对奈奎斯特频率的比例，留出过渡带
+const SINC_ROLLOFF: f64 = 0.95;
+
+/// 转成 whisper 需要的 16kHz 单声道：交错排列的多声道先取平均，采样率不同时再重采样
+pub fn resample_to_16k_mono(samples: &[f32], src_rate: u32, channels: u16) -> Vec<f32> {
+    let mono = downmix_to_mono(samples, channels);
+    resample(&mono, src_rate, TARGET_SAMPLE_RATE)
+}
+
+/// 交错排列的多声道取平均转为单声道
+pub fn downmix_to_mono(samples: &[f32], channels: u16) -> Vec<f32> {
+    if channels <= 1 {
+        return samples.to_vec();
+    }
+    samples
+        .chunks(channels as usize)
+        .map(|frame| frame.iter().sum::<f32>() / frame.len() as f32)
+        .collect()
+}
+
+/// 带限（Blackman 窗 sinc）重采样。降采样时截止频率取目标采样率的奈奎斯特频率，
+/// 高于它的成分被滤掉而不是混叠进语音频段。滤波器系数按采样率组合缓存，逐样本只做乘加
+pub fn resample(samples: &[f32], from_rate: u32, to_rate: u32) -> Vec<f32> {
+    if from_rate == to_rate || from_rate == 0 || to_rate == 0 || samples.is_empty() {
+        return samples.to_vec();
+    }
+
+    let filter = PolyphaseFilter::cached(from_rate, to_rate);
+    let out_len = (samples.len() as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let last = samples.len() as i64 - 1;
+
+    let mut output = Vec::with_capacity(out_len);
+    for i in 0..out_len {
+        // 输出样本 i 在输入中的位置 = i * from / to，拆成整数部分和相位
+        let position = i as u64 * filter.step as u64;
+        let (carry, phase) = filter.phase_index(position % filter.upsample as u64);
+        let base = (position / filter.upsample as u64 + carry) as i64;
+        let taps = filter.taps(phase);
+
+        let mut sum = 0.0;
+        let mut weight_sum = 0.0;
+        for (k, weight) in taps.iter().enumerate() {
+            let j = base + k as i64 - filter.half_taps as i64;
+            if j < 0 || j > last {
+                continue;
+            }
+            sum += samples[j as usize] as f64 * weight;
+            weight_sum += weight;
+        }
+        // 按权重和归一化，边缘处核被截断时增益不变
+        output.push(if weight_sum.abs() > f64::EPSILON { (sum / weight_sum) as f32 } else { 0.0 });
+    }
+
+    output
+}
+
+/// 相位数上限；采样率比值约分后分母超过它时（如 44101Hz）相位就近取整
+const MAX_PHASES: u32 = 1024;
+
+/// 多相滤波器表：每个相位一组预先算好的窗 sinc 系数
+struct PolyphaseFilter {
+    /// 约分后的 to_rate（输入的每个样本被分成的相位数）
+    upsample: u32,
+    /// 约分后的 from_rate（相邻输出样本在相位单位下的间隔）
+    step: u32,
+    phases: u32,
+    half_taps: usize,
+    coefficients: Vec<f64>,
+}
+
+impl PolyphaseFilter {
+    fn new(from_rate: u32, to_rate: u32) -> Self {
+        let divisor = gcd(from_rate, to_rate);
+        let upsample = to_rate / divisor;
+        let step = from_rate / divisor;
+        let phases = upsample.min(MAX_PHASES);
+
+        let ratio = to_rate as f64 / from_rate as f64;
+        let cutoff = ratio.min(1.0) * SINC_ROLLOFF;
+        let half_width = SINC_ZERO_CROSSINGS / cutoff;
+        let half_taps = half_width.ceil() as usize + 1;
+        let tap_count = 2 * half_taps + 1;
+
+        let mut coefficients = Vec::with_capacity(phases as usize * tap_count);
+        for phase in 0..phases {
+            let fraction = phase as f64 / phases as f64;
+            for k in 0..tap_count {
+                let distance = fraction - (k as f64 - half_taps as f64);
+                coefficients.push(cutoff * sinc(cutoff * distance) * blackman(distance / half_width));
+            }
+        }
+
+        Self { upsample, step, phases, half_taps, coefficients }
+    }
+
+    fn cached(from_rate: u32, to_rate: u32) -> Arc<Self> {
+        static FILTERS: OnceLock<Mutex<HashMap<(u32, u32), Arc<PolyphaseFilter>>>> = OnceLock::new();
+        FILTERS
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .unwrap()
+            .entry((from_rate, to_rate))
+            .or_insert_with(|| Arc::new(Self::new(from_rate, to_rate)))
+            .clone()
+    }
+
+    /// offset ∈ [0, upsample)，返回 (进位, 相位)：相位就近取整到 phases 时等于下一个输入样本的第 0 相位
+    fn phase_index(&self, offset: u64) -> (u64, usize) {
+        if self.phases == self.upsample {
+            return (0, offset as usize);
+        }
+        let phase = (offset * self.phases as u64 + self.upsample as u64 / 2) / self.upsample as u64;
+        if phase == self.phases as u64 {
+            (1, 0)
+        } else {
+            (0, phase as usize)
+        }
+    }
+
+    fn taps(&self, phase: usize) -> &[f64] {
+        let tap_count = 2 * self.half_taps + 1;
+        &self.coefficients[phase * tap_count..(phase + 1) * tap_count]
+    }
+}
+
+fn gcd(mut a: u32, mut b: u32) -> u32 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+fn sinc(x: f64) -> f64 {
+    if x.abs() < 1e-9 {
+        1.0
+    } else {
+        let x = std::f64::consts::PI * x;
+        x.sin() / x
+    }
+}
+
+/// x ∈ [-1, 1]
+fn blackman(x: f64) -> f64 {
+    if x.abs() >= 1.0 {
+        return 0.0;
+    }
+    let x = std::f64::consts::PI * x;
+    0.42 + 0.5 * x.cos() + 0.08 * (2.0 * x).cos()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_format_from_magic_bytes() {
+        assert_eq!(AudioFormat::detect(b"RIFF\0\0\0\0WAVEfmt "), AudioFormat::Wav);
+        assert_eq!(AudioFormat::detect(b"ID3\x04\0\0"), AudioFormat::Mp3);
+        assert_eq!(AudioFormat::detect(&[0xFF, 0xFB, 0x90, 0x00]), AudioFormat::Mp3);
+        assert_eq!(AudioFormat::detect(&[0xFF, 0xF1, 0x50, 0x80]), AudioFormat::Aac);
+        assert_eq!(AudioFormat::detect(&[0xFF, 0xF9, 0x50, 0x80]), AudioFormat::Aac);
+        assert_eq!(AudioFormat::detect(b"OggS\0\x02"), AudioFormat::Ogg);
+        assert_eq!(AudioFormat::detect(b"fLaC\0\0\0\x22"), AudioFormat::Flac);
+        assert_eq!(AudioFormat::detect(b"\0\0\0\x20ftypM4A "), AudioFormat::Mp4);
+        assert_eq!(AudioFormat::detect(b"hello"), AudioFormat::Unknown);
+    }
+
+    #[test]
+    fn test_unsupported_format_error_names_format() {
+        let err = decode_bytes_to_samples(&[0x1A, 0x45, 0xDF, 0xA3, 0x00], None).unwrap_err();
+        assert!(err.to_string().contains("WebM"));
+    }
+
+    /// MPEG-1 Layer III，128kbps、44.1kHz、单声道的静音帧：side info 全为 0（不含 Huffman 数据）
+    fn silent_mp3(frames: usize) -> Vec<u8> {
+        const FRAME_LEN: usize = 144 * 128_000 / 44_100;
+        let mut data = Vec::with_capacity(frames * FRAME_LEN);
+        for _ in 0..frames {
+            let mut frame = vec![0u8; FRAME_LEN];
+            frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0xC4]);
+            data.extend_from_slice(&frame);
+        }
+        data
+    }
+
+    #[test]
+    fn test_decode_mp3_fixture_to_samples() {
+        let path = std::env::temp_dir().join(format!("voicetype-decode-{}.mp3", uuid::Uuid::new_v4()));
+        std::fs::write(&path, silent_mp3(20)).unwrap();
+
+        let result = decode_to_samples(&path);
+        std::fs::remove_file(&path).ok();
+        let (samples, sample_rate) = result.unwrap();
+        assert_eq!(sample_rate, 44100);
+        assert!(!samples.is_empty());
+        assert!(samples.iter().all(|s| s.abs() < 1e-3));
+
+        assert_eq!(decode_bytes_to_samples(&silent_mp3(20), None).unwrap(), (samples, sample_rate));
+    }
+
+    #[test]
+    fn test_duration_limit_stops_decoding() {
+        // 每帧 1152 个样本，100 帧约 2.6 秒；MP3 裸流没有时长信息，只能在解码过程中停止
+        let err = decode_bytes_to_samples(&silent_mp3(100), Some(1.0)).unwrap_err().to_string();
+        assert!(err.contains("Audio too long"), "{}", err);
+        assert!(decode_bytes_to_samples(&silent_mp3(20), Some(1.0)).is_ok());
+
+        // WAV 头里有样本数，解码前就能拒绝
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let spec = WavSpec { channels: 1, sample_rate: 16000, bits_per_sample: 16, sample_format: hound::SampleFormat::Int };
+            let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+            for _ in 0..32000 {
+                writer.write_sample(0i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+        let err = decode_bytes_to_samples(&cursor.into_inner(), Some(1.0)).unwrap_err().to_string();
+        assert!(err.contains("2.0s"), "{}", err);
+    }
+
+    #[test]
+    fn test_wav_roundtrip_resamples_to_16k() {
+        let spec = WavSpec {
+            channels: 2,
+            sample_rate: 44100,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut cursor = Cursor::new(Vec::new());
+        {
+            let mut writer = WavWriter::new(&mut cursor, spec).unwrap();
+            for _ in 0..44100 {
+                writer.write_sample(1000i16).unwrap();
+                writer.write_sample(1000i16).unwrap();
+            }
+            writer.finalize().unwrap();
+        }
+
+        // 解码时已经取平均转为单声道，采样率保持原样
+        let (samples, sample_rate) = decode_bytes_to_samples(&cursor.into_inner(), None).unwrap();
+        assert_eq!((samples.len(), sample_rate), (44100, 44100));
+        assert_eq!(resample_to_16k_mono(&samples, sample_rate, 1).len(), TARGET_SAMPLE_RATE as usize);
+    }
+
+    fn sine(freq: f64, rate: u32, secs: f64) -> Vec<f32> {
+        (0..(rate as f64 * secs) as usize)
+            .map(|i| (2.0 * std::f64::consts::PI * freq * i as f64 / rate as f64).sin() as f32)
+            .collect()
+    }
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn test_resample_output_length_scales_with_rate() {
+        assert_eq!(resample_to_16k_mono(&vec![0.0; 48_000], 48_000, 1).len(), 16_000);
+        assert_eq!(resample_to_16k_mono(&vec![0.0; 44_100], 44_100, 1).len(), 16_000);
+        assert_eq!(resample_to_16k_mono(&vec![0.0; 8_000], 8_000, 1).len(), 16_000);
+        // 约分后分母很大的采样率：相位数受限，直流信号的增益不变
+        let resampled = resample_to_16k_mono(&vec![0.5; 44_101], 44_101, 1);
+        assert_eq!(resampled.len(), 16_000);
+        assert!(resampled.iter().all(|s| (s - 0.5).abs() < 1e-4));
+        assert_eq!(PolyphaseFilter::cached(44_101, 16_000).phases, MAX_PHASES);
+        // 已经是 16kHz 单声道时原样返回
+        let input = sine(440.0, 16_000, 0.1);
+        assert_eq!(resample_to_16k_mono(&input, 16_000, 1), input);
+    }
+
+    #[test]
+    fn test_stereo_is_downmixed() {
+        let stereo: Vec<f32> = (0..48_000).flat_map(|_| [0.5, -0.1]).collect();
+        let output = resample_to_16k_mono(&stereo, 48_000, 2);
+        assert_eq!(output.len(), 16_000);
+        assert!(output.iter().all(|s| (s - 0.2).abs() < 1e-3));
+
+        assert_eq!(downmix_to_mono(&[1.0, 0.0, 0.5, 0.5], 2), vec![0.5, 0.5]);
+    }
+
+    #[test]
+    fn test_resample_keeps_speech_band_and_filters_aliases() {
+        // 1kHz 在语音频段内，幅度基本不变
+        let tone = resample_to_16k_mono(&sine(1_000.0, 48_000, 0.5), 48_000, 1);
+        let middle = &tone[1_000..tone.len() - 1_000];
+        assert!((rms(middle) - std::f32::consts::FRAC_1_SQRT_2).abs() < 0.02, "rms {}", rms(middle));
+
+        // 12kHz 高于 8kHz 奈奎斯特频率，线性插值会混叠成 4kHz，这里应被滤掉
+        let high = resample_to_16k_mono(&sine(12_000.0, 48_000, 0.5), 48_000, 1);
+        let middle = &high[1_000..high.len() - 1_000];
+        assert!(rms(middle) < 0.02, "rms {}", rms(middle));
+    }
+}
@@ -0,0 +1,198 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+use tracing::info;
+
+/// 峰值归一化的目标电平
+const TARGET_PEAK: f32 = 0.9;
+/// 归一化的最大增益，避免把几乎静音的录音放大成噪声
+const MAX_GAIN: f32 = 10.0;
+/// 低于此峰值视为静音，不做归一化
+const MIN_PEAK: f32 = 1e-4;
+/// 高通滤波截止频率（Hz），去掉直流偏移和低频嗡嗡声
+pub const HIGH_PASS_CUTOFF_HZ: f32 = 80.0;
+/// 噪声门的帧长（毫秒）和 RMS 阈值（归一化之后的电平）
+const NOISE_GATE_FRAME_MS: u32 = 10;
+const NOISE_GATE_THRESHOLD: f32 = 0.01;
+
+/// 送入 ASR 之前的音频预处理开关，默认全部关闭（与之前行为一致）
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub struct AudioPrepOptions {
+    pub normalize: bool,
+    pub high_pass: bool,
+    pub noise_gate: bool,
+}
+
+impl AudioPrepOptions {
+    pub fn is_enabled(&self) -> bool {
+        self.normalize || self.high_pass || self.noise_gate
+    }
+}
+
+/// 按 高通 → 归一化 → 噪声门 的顺序处理。
+/// 先去掉直流再算峰值；噪声门放在归一化之后，阈值不受麦克风音量影响
+pub fn apply(samples: &[f32], sample_rate: u32, options: &AudioPrepOptions) -> Vec<f32> {
+    let mut output = samples.to_vec();
+    if options.high_pass {
+        high_pass(&mut output, sample_rate, HIGH_PASS_CUTOFF_HZ);
+    }
+    if options.normalize {
+        normalize(&mut output, TARGET_PEAK, MAX_GAIN);
+    }
+    if options.noise_gate {
+        noise_gate(&mut output, sample_rate, NOISE_GATE_THRESHOLD);
+    }
+    output
+}
+
+/// 一阶 RC 高通滤波
+pub fn high_pass(samples: &mut [f32], sample_rate: u32, cutoff_hz: f32) {
+    if samples.is_empty() || sample_rate == 0 || cutoff_hz <= 0.0 {
+        return;
+    }
+
+    let rc = 1.0 / (2.0 * std::f32::consts::PI * cutoff_hz);
+    let dt = 1.0 / sample_rate as f32;
+    let alpha = rc / (rc + dt);
+
+    // 以第一个采样为初始输入，开头的直流不会产生阶跃
+    let mut prev_input = samples[0];
+    let mut prev_output = 0.0;
+    for sample in samples.iter_mut() {
+        let input = *sample;
+        prev_output = alpha * (prev_output + input - prev_input);
+        prev_input = input;
+        *sample = prev_output;
+    }
+}
+
+/// 峰值归一化到 `target_peak`，增益不超过 `max_gain`；静音不处理
+pub fn normalize(samples: &mut [f32], target_peak: f32, max_gain: f32) {
+    let peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+    if peak < MIN_PEAK {
+        return;
+    }
+
+    let gain = (target_peak / peak).min(max_gain);
+    for sample in samples.iter_mut() {
+        *sample = (*sample * gain).clamp(-1.0, 1.0);
+    }
+}
+
+/// 按预处理相同的目标电平和最大增益归一化后的副本，供能量检测使用
+pub fn peak_normalized(samples: &[f32]) -> Vec<f32> {
+    let mut samples = samples.to_vec();
+    normalize(&mut samples, TARGET_PEAK, MAX_GAIN);
+    samples
+}
+
+/// 按帧计算 RMS，低于阈值的帧置零
+pub fn noise_gate(samples: &mut [f32], sample_rate: u32, threshold: f32) {
+    let frame_len = ((sample_rate * NOISE_GATE_FRAME_MS / 1000) as usize).max(1);
+    for frame in samples.chunks_mut(frame_len) {
+        if crate::voice_assistant::recorder::rms(frame) < threshold {
+            frame.fill(0.0);
+        }
+    }
+}
+
+// 全局设置，热键录音和 test_asr_transcription 共用
+static AUDIO_PREP_OPTIONS: OnceLock<RwLock<AudioPrepOptions>> = OnceLock::new();
+
+fn options() -> &'static RwLock<AudioPrepOptions> {
+    AUDIO_PREP_OPTIONS.get_or_init(|| RwLock::new(AudioPrepOptions::default()))
+}
+
+pub fn current_options() -> AudioPrepOptions {
+    *options().read().unwrap()
+}
+
+pub fn set_options(new_options: AudioPrepOptions) {
+    info!(
+        "🎚️ Audio prep: normalize={}, high_pass={}, noise_gate={}",
+        new_options.normalize, new_options.high_pass, new_options.noise_gate
+    );
+    *options().write().unwrap() = new_options;
+}
+
+/// 按当前全局设置处理音频
+pub fn prepare(samples: &[f32], sample_rate: u32) -> Vec<f32> {
+    let options = current_options();
+    if !options.is_enabled() {
+        return samples.to_vec();
+    }
+    apply(samples, sample_rate, &options)
+}
+
+/// 从数据库重新加载预处理设置
+pub async fn reload_audio_prep_config() -> Result<(), String> {
+    let config = crate::commands::get_audio_prep_config_internal().await?;
+    set_options(config.map(|c| c.options()).unwrap_or_default());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sine(freq: f32, amplitude: f32, sample_rate: u32, len: usize) -> Vec<f32> {
+        (0..len)
+            .map(|i| amplitude * (2.0 * std::f32::consts::PI * freq * i as f32 / sample_rate as f32).sin())
+            .collect()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples.iter().fold(0.0f32, |p, s| p.max(s.abs()))
+    }
+
+    #[test]
+    fn test_high_pass_removes_dc_and_keeps_speech_band() {
+        let mut audio: Vec<f32> = sine(1000.0, 0.3, 16000, 16000).iter().map(|s| s + 0.5).collect();
+        high_pass(&mut audio, 16000, HIGH_PASS_CUTOFF_HZ);
+
+        let tail = &audio[8000..];
+        let mean = tail.iter().sum::<f32>() / tail.len() as f32;
+        assert!(mean.abs() < 0.01, "DC not removed: mean {}", mean);
+        assert!(peak(tail) > 0.27, "1kHz attenuated too much: {}", peak(tail));
+    }
+
+    #[test]
+    fn test_high_pass_attenuates_rumble() {
+        let mut rumble = sine(20.0, 0.5, 16000, 16000);
+        high_pass(&mut rumble, 16000, HIGH_PASS_CUTOFF_HZ);
+        // 一阶滤波在截止频率以下两个倍频程约衰减到 1/4
+        assert!(peak(&rumble[8000..]) < 0.2);
+    }
+
+    #[test]
+    fn test_normalize_gain() {
+        let mut quiet = sine(440.0, 0.1, 16000, 1600);
+        normalize(&mut quiet, TARGET_PEAK, MAX_GAIN);
+        assert!((peak(&quiet) - TARGET_PEAK).abs() < 1e-3);
+
+        // 增益上限
+        let mut very_quiet = sine(440.0, 0.01, 16000, 1600);
+        normalize(&mut very_quiet, TARGET_PEAK, MAX_GAIN);
+        assert!((peak(&very_quiet) - 0.1).abs() < 1e-3);
+
+        // 静音保持不变
+        let mut silence = vec![0.0; 1600];
+        normalize(&mut silence, TARGET_PEAK, MAX_GAIN);
+        assert_eq!(peak(&silence), 0.0);
+    }
+
+    #[test]
+    fn test_noise_gate_silences_quiet_frames() {
+        let mut audio = sine(440.0, 0.002, 16000, 1600);
+        audio.extend(sine(440.0, 0.5, 16000, 1600));
+        noise_gate(&mut audio, 16000, NOISE_GATE_THRESHOLD);
+
+        assert_eq!(peak(&audio[..1600]), 0.0);
+        assert!(peak(&audio[1600..]) > 0.49);
+    }
+
+    #[test]
+    fn test_disabled_options_leave_audio_unchanged() {
+        let audio = sine(440.0, 0.05, 16000, 1600);
+        assert_eq!(apply(&audio, 16000, &AudioPrepOptions::default()), audio);
+    }
+}
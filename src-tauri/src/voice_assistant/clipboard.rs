@@ -0,0 +1,336 @@
+use std::sync::mpsc::{self, Sender};
+use std::sync::OnceLock;
+use arboard::{Clipboard, ImageData};
+use tracing::{info, warn};
+use crate::voice_assistant::VoiceError;
+
+/// 输入前保存的剪贴板内容，输入完成后原样恢复（文本或图片）
+#[derive(Debug, Clone)]
+pub enum ClipboardSnapshot {
+    Text(String),
+    Image(ImageData<'static>),
+}
+
+type ClipboardJob = Box<dyn FnOnce(&mut Clipboard) + Send>;
+
+/// arboard 句柄只在专用线程上创建、使用和释放（Windows 的剪贴板归属于打开它的线程），
+/// 其他线程把操作通过 channel 发给它。句柄需要长期持有：Linux 下剪贴板内容由进程自身提供，句柄释放后内容可能丢失。
+/// None 表示 arboard 初始化失败，退回到命令行工具
+static CLIPBOARD: OnceLock<Option<Sender<ClipboardJob>>> = OnceLock::new();
+
+fn clipboard_thread() -> Option<&'static Sender<ClipboardJob>> {
+    CLIPBOARD.get_or_init(spawn_clipboard_thread).as_ref()
+}
+
+fn spawn_clipboard_thread() -> Option<Sender<ClipboardJob>> {
+    let (jobs_tx, jobs_rx) = mpsc::channel::<ClipboardJob>();
+    let (ready_tx, ready_rx) = mpsc::channel();
+    let spawned = std::thread::Builder::new()
+        .name("clipboard".to_string())
+        .spawn(move || {
+            let mut clipboard = match Clipboard::new() {
+                Ok(clipboard) => clipboard,
+                Err(e) => {
+                    let _ = ready_tx.send(Err(e.to_string()));
+                    return;
+                }
+            };
+            let _ = ready_tx.send(Ok(()));
+            for job in jobs_rx {
+                job(&mut clipboard);
+            }
+        });
+    if let Err(e) = spawned {
+        warn!("⚠️ Failed to start clipboard thread ({}), falling back to command-line tools", e);
+        return None;
+    }
+
+    match ready_rx.recv() {
+        Ok(Ok(())) => {
+            info!("📋 Clipboard backend: arboard");
+            Some(jobs_tx)
+        }
+        Ok(Err(e)) => {
+            warn!("⚠️ arboard clipboard unavailable ({}), falling back to command-line tools", e);
+            None
+        }
+        Err(_) => {
+            warn!("⚠️ Clipboard thread exited during startup, falling back to command-line tools");
+            None
+        }
+    }
+}
+
+/// 在剪贴板线程上执行操作并等待结果；arboard 不可用时返回 None
+fn with_clipboard<T: Send + 'static>(f: impl FnOnce(&mut Clipboard) -> T + Send + 'static) -> Option<T> {
+    let jobs = clipboard_thread()?;
+    let (result_tx, result_rx) = mpsc::channel();
+    let job: ClipboardJob = Box::new(move |clipboard| {
+        let _ = result_tx.send(f(clipboard));
+    });
+    jobs.send(job).ok()?;
+    result_rx.recv().ok()
+}
+
+/// 读取剪贴板文本
+pub fn get_text() -> Result<String, VoiceError> {
+    with_clipboard(|clipboard| clipboard.get_text())
+        .map(|result| result.map_err(|e| VoiceError::Other(format!("Failed to get clipboard: {}", e))))
+        .unwrap_or_else(fallback::get_clipboard_content)
+}
+
+/// 写入剪贴板文本
+pub fn set_text(text: &str) {
+    let owned = text.to_string();
+    match with_clipboard(move |clipboard| clipboard.set_text(owned)) {
+        Some(Ok(())) => {}
+        Some(Err(e)) => warn!("❌ Failed to set clipboard text: {}", e),
+        None => fallback::set_clipboard_content(text),
+    }
+}
+
+/// 保存当前剪贴板内容；剪贴板为空或内容格式不支持时返回 None
+pub fn snapshot() -> Option<ClipboardSnapshot> {
+    let snapshot = with_clipboard(|clipboard| {
+        if let Ok(text) = clipboard.get_text() {
+            return Some(ClipboardSnapshot::Text(text));
+        }
+        clipboard.get_image().ok().map(|image| ClipboardSnapshot::Image(image.to_owned_img()))
+    });
+    match snapshot {
+        Some(snapshot) => snapshot,
+        None => fallback::get_clipboard_content().ok().map(ClipboardSnapshot::Text),
+    }
+}
+
+/// 恢复之前保存的剪贴板内容
+pub fn restore(snapshot: ClipboardSnapshot) {
+    match snapshot {
+        ClipboardSnapshot::Text(text) => set_text(&text),
+        ClipboardSnapshot::Image(image) => {
+            // 命令行回退方式不支持图片
+            if let Some(Err(e)) = with_clipboard(move |clipboard| clipboard.set_image(image)) {
+                warn!("❌ Failed to restore clipboard image: {}", e);
+            }
+        }
+    }
+}
+
+/// arboard 不可用时使用的命令行/系统 API 实现
+mod fallback {
+    use crate::voice_assistant::VoiceError;
+
+    pub fn get_clipboard_content() -> Result<String, VoiceError> {
+        #[cfg(target_os = "macos")]
+        {
+            use std::process::Command;
+            let output = Command::new("pbpaste").output()
+                .map_err(|e| VoiceError::Other(format!("Failed to get clipboard: {}", e)))?;
+
+            Ok(String::from_utf8(output.stdout)?)
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            // Use Windows API directly instead of PowerShell
+            unsafe {
+                use winapi::um::winuser::{OpenClipboard, CloseClipboard, GetClipboardData, CF_UNICODETEXT};
+                use winapi::um::winnt::WCHAR;
+
+                if OpenClipboard(std::ptr::null_mut()) == 0 {
+                    return Err(VoiceError::Other("Failed to open clipboard".to_string()));
+                }
+
+                let clipboard_data = GetClipboardData(CF_UNICODETEXT);
+                if clipboard_data.is_null() {
+                    CloseClipboard();
+                    return Err(VoiceError::Other("Failed to get clipboard data".to_string()));
+                }
+
+                let text_ptr = clipboard_data as *const WCHAR;
+                let mut len = 0;
+                while *text_ptr.offset(len) != 0 {
+                    len += 1;
+                }
+
+                let slice = std::slice::from_raw_parts(text_ptr, len as usize);
+                let text = String::from_utf16(slice)
+                    .map_err(|e| VoiceError::Other(format!("Failed to parse clipboard text: {}", e)))?;
+
+                CloseClipboard();
+                Ok(text)
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::process::Command;
+            if let Ok(output) = Command::new("xclip").arg("-selection").arg("clipboard").arg("-o").output() {
+                Ok(String::from_utf8(output.stdout)?)
+            } else {
+                Err(VoiceError::Other("xclip not found".to_string()))
+            }
+        }
+
+        #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
+        {
+            Err(VoiceError::Other("Platform not supported".to_string()))
+        }
+    }
+
+    pub fn set_clipboard_content(text: &str) {
+        #[cfg(target_os = "macos")]
+        {
+            use std::io::Write;
+            use std::process::Command;
+
+            match Command::new("pbcopy")
+                .stdin(std::process::Stdio::piped())
+                .spawn()
+            {
+                Ok(mut child) => {
+                    if let Some(mut stdin) = child.stdin.take() {
+                        if let Err(e) = stdin.write_all(text.as_bytes()) {
+                            tracing::warn!("❌ Failed to write to pbcopy: {}", e);
+                        }
+                        // 关闭 stdin，pbcopy 读到 EOF 后才会写入剪贴板并退出
+                    }
+                    let _ = child.wait();
+                }
+                Err(e) => tracing::warn!("❌ Failed to spawn pbcopy: {}", e),
+            }
+        }
+
+        #[cfg(target_os = "windows")]
+        {
+            // Use Windows API directly instead of PowerShell
+            unsafe {
+                use winapi::um::winuser::{OpenClipboard, CloseClipboard, EmptyClipboard, SetClipboardData, CF_UNICODETEXT};
+                use winapi::um::winbase::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
+                use winapi::um::winnt::WCHAR;
+
+                if OpenClipboard(std::ptr::null_mut()) == 0 {
+                    return;
+                }
+
+                EmptyClipboard();
+
+                // Convert string to UTF-16
+                let utf16_text: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+                let byte_len = utf16_text.len() * std::mem::size_of::<WCHAR>();
+
+                // Allocate global memory
+                let handle = GlobalAlloc(GMEM_MOVEABLE, byte_len);
+                if handle.is_null() {
+                    CloseClipboard();
+                    return;
+                }
+
+                // Lock and copy data
+                let ptr = GlobalLock(handle);
+                if ptr.is_null() {
+                    GlobalUnlock(handle);
+                    CloseClipboard();
+                    return;
+                }
+
+                std::ptr::copy_nonoverlapping(utf16_text.as_ptr(), ptr as *mut WCHAR, utf16_text.len());
+                GlobalUnlock(handle);
+
+                // Set clipboard data
+                SetClipboardData(CF_UNICODETEXT, handle);
+                CloseClipboard();
+            }
+        }
+
+        #[cfg(target_os = "linux")]
+        {
+            use std::io::Write;
+            use std::process::Command;
+
+            // Try multiple clipboard methods
+            let mut success = false;
+
+            // Method 1: Try xclip (most common)
+            if let Ok(output) = Command::new("which").arg("xclip").output() {
+                if output.status.success() {
+                    if let Ok(mut child) = Command::new("xclip")
+                        .args(&["-selection", "clipboard"])
+                        .stdin(std::process::Stdio::piped())
+                        .spawn()
+                    {
+                        if let Some(stdin) = child.stdin.as_mut() {
+                            if let Ok(_) = stdin.write_all(text.as_bytes()) {
+                                let _ = child.wait();
+                                success = true;
+                                tracing::debug!("✅ Text set to clipboard via xclip");
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Method 2: Try xsel if xclip fails
+            if !success {
+                if let Ok(output) = Command::new("which").arg("xsel").output() {
+                    if output.status.success() {
+                        if let Ok(mut child) = Command::new("xsel")
+                            .args(&["--clipboard", "--input"])
+                            .stdin(std::process::Stdio::piped())
+                            .spawn()
+                        {
+                            if let Some(stdin) = child.stdin.as_mut() {
+                                if let Ok(_) = stdin.write_all(text.as_bytes()) {
+                                    let _ = child.wait();
+                                    success = true;
+                                    tracing::debug!("✅ Text set to clipboard via xsel");
+                                }
+                            }
+                        }
+                    }
+                }
+            }
+
+            // Method 3: Try wl-copy (Wayland)
+            if !success {
+                if let Ok(output) = Command::new("which").arg("wl-copy").output() {
+                    if output.status.success() {
+                        if let Ok(_) = Command::new("wl-copy")
+                            .arg(text)
+                            .output()
+                        {
+                            success = true;
+                            tracing::debug!("✅ Text set to clipboard via wl-copy");
+                        }
+                    }
+                }
+            }
+
+            if !success {
+                tracing::warn!("❌ No clipboard utility found (xclip, xsel, wl-copy)");
+                tracing::warn!("💡 Install one of: sudo apt install xclip");
+
+                // 原文只在 trace 级别输出，且默认脱敏
+                tracing::trace!("Text to copy manually: {}", crate::voice_assistant::redact(text));
+            }
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "macos"))]
+mod tests {
+    use super::fallback::{get_clipboard_content, set_clipboard_content};
+
+    #[test]
+    fn test_pbcopy_pbpaste_roundtrip() {
+        let original = get_clipboard_content().ok();
+        let text = "voicetype \"clipboard\" test\n第二行 🎤";
+
+        set_clipboard_content(text);
+        assert_eq!(get_clipboard_content().unwrap(), text);
+
+        if let Some(original) = original {
+            set_clipboard_content(&original);
+        }
+    }
+}
@@ -1,15 +1,17 @@
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use std::sync::OnceLock;
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter};
 use crate::voice_assistant::{
-    AsrProcessor, TranslateProcessor,
-    AudioRecorder, KeyboardManager, Mode, InputState, VoiceError,
-    WhisperProcessor, SenseVoiceProcessor, LocalASRProcessor,
-    SiliconFlowTranslateProcessor, OllamaTranslateProcessor,
-    WhisperRSProcessor // , EnhancedWhisperProcessor
+    AsrProcessor, AsrTranscript, TranslateProcessor,
+    AudioRecorder, KeyboardManager, Mode, InputState, VoiceError, TextInjectionMethod, HotkeyBackend, RecordingMode,
+    WhisperProcessor, SenseVoiceProcessor, LocalASRProcessor, CloudAsrProcessor, CloudAsrConfig,
+    SiliconFlowTranslateProcessor, OllamaTranslateProcessor, DeepLTranslateProcessor,
+    WhisperRSProcessor, WhisperRSConfig, WhisperBackend, FallbackAsrProcessor, RaceAsrProcessor // , EnhancedWhisperProcessor
 };
-use tracing::{info, error};
+use crate::voice_assistant::asr::whisper_worker;
+use crate::commands::error::CommandError;
+use tracing::{debug, info, warn, error, trace};
 
 // Global VoiceAssistant instance
 static VOICE_ASSISTANT: OnceLock<Arc<Mutex<Option<VoiceAssistant>>>> = OnceLock::new();
@@ -66,6 +68,294 @@ pub fn emit_new_history_record_event() {
     }
 }
 
+// Helper function to emit live recording level events (RMS 0.0-1.0 and elapsed time) for the overlay
+pub fn emit_recording_level(level: f32, elapsed_ms: u64) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({ "level": level, "elapsed_ms": elapsed_ms });
+                if let Err(e) = handle.emit("recording-level", payload) {
+                    error!("Failed to emit recording level event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+// Helper function to tell the UI that recording was stopped without the hotkey (max duration / silence)
+pub fn emit_recording_auto_stopped(reason: &str, elapsed_ms: u64) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({ "reason": reason, "elapsed_ms": elapsed_ms });
+                if let Err(e) = handle.emit("recording-auto-stopped", payload) {
+                    error!("Failed to emit recording auto-stopped event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 识别结果的无语音概率超过阈值、被丢弃时通知前端"什么都没听到"
+pub fn emit_nothing_heard(no_speech_prob: f32) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({ "no_speech_prob": no_speech_prob });
+                if let Err(e) = handle.emit("nothing-heard", payload) {
+                    error!("Failed to emit nothing heard event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 流式翻译收到的一段译文
+pub fn emit_translation_chunk(chunk: &str) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({ "chunk": chunk });
+                if let Err(e) = handle.emit("translation-chunk", payload) {
+                    error!("Failed to emit translation chunk event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 识别结果写入历史记录失败时通知前端：本批失败的条数和最后一个错误
+pub fn emit_history_save_failed(failed: usize, error_message: &str) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({ "failed": failed, "error": error_message });
+                if let Err(e) = handle.emit("history-save-failed", payload) {
+                    error!("Failed to emit history save failed event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 识别前检查发现录音里没有语音、跳过识别时通知前端（悬浮窗提示"未检测到语音"）
+pub fn emit_empty_recording(speech_ms: u64, min_speech_ms: u64) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({ "speech_ms": speech_ms, "min_speech_ms": min_speech_ms });
+                if let Err(e) = handle.emit("empty-recording", payload) {
+                    error!("Failed to emit empty recording event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 识别结果超过输出长度限制时通知前端：已输入的字符数和放到剪贴板的字符数
+pub fn emit_output_truncated(typed_chars: usize, clipboard_chars: usize) {
+    warn!("Output capped: typed {} chars, {} chars left on the clipboard", typed_chars, clipboard_chars);
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({
+                    "typed_chars": typed_chars,
+                    "clipboard_chars": clipboard_chars
+                });
+                if let Err(e) = handle.emit("output-truncated", payload) {
+                    error!("Failed to emit output truncated event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 通知前端转写队列深度：排队中的录音数、是否正在识别、因队列满被丢弃的录音数
+pub fn emit_transcription_queue_depth(pending: usize, processing: bool, dropped: u64) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({
+                    "pending": pending,
+                    "processing": processing,
+                    "dropped": dropped
+                });
+                if let Err(e) = handle.emit("transcription-queue-depth", payload) {
+                    error!("Failed to emit transcription queue depth event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 没有可用的 Whisper 模型时通知前端，提示用户去模型管理下载，而不是一直等待加载
+pub fn emit_whisper_model_missing(searched: &[String]) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({ "searched": searched });
+                if let Err(e) = handle.emit("whisper-model-missing", payload) {
+                    error!("Failed to emit whisper model missing event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 会话记录开始/结束时通知前端和托盘；session 为 None 表示已结束
+pub fn emit_transcript_session_changed(session: Option<&crate::voice_assistant::transcript::TranscriptSession>) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({
+                    "active": session.is_some(),
+                    "transcript_only": session.map(|s| s.transcript_only).unwrap_or(false)
+                });
+                if let Err(e) = handle.emit("transcript-session-changed", payload) {
+                    error!("Failed to emit transcript session changed event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+// Warn the frontend once per model load that the requested GPU backend fell back to CPU
+pub fn emit_gpu_backend_fallback(requested_backend: &str, reason: &str) {
+    warn!("GPU backend {} unavailable, using CPU: {}", requested_backend, reason);
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({
+                    "requested_backend": requested_backend,
+                    "effective_backend": "CPU",
+                    "reason": reason
+                });
+                if let Err(e) = handle.emit("gpu-backend-fallback", payload) {
+                    error!("Failed to emit GPU backend fallback event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+// Register the transcribe/translate hotkeys through tauri-plugin-global-shortcut
+fn register_plugin_hotkeys(transcribe_key: &str, translate_key: &str, cycle_profile_key: Option<&str>) -> Result<(), String> {
+    let handle = APP_HANDLE.get()
+        .and_then(|guard| guard.lock().ok().and_then(|handle| handle.clone()))
+        .ok_or_else(|| "App handle not set, cannot register global shortcuts".to_string())?;
+    crate::voice_assistant::GlobalHotkeyManager::new(handle).register_hotkeys(transcribe_key, translate_key, cycle_profile_key)
+}
+
+fn unregister_plugin_hotkeys() {
+    if let Some(handle) = APP_HANDLE.get().and_then(|guard| guard.lock().ok().and_then(|handle| handle.clone())) {
+        if let Err(e) = crate::voice_assistant::GlobalHotkeyManager::new(handle).unregister_all_hotkeys() {
+            error!("Failed to unregister global shortcuts: {}", e);
+        }
+    }
+}
+
+/// 通知前端和托盘当前使用的 ASR 配置方案
+pub fn emit_asr_profile_changed(profile: &crate::database::AsrConfig) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({
+                    "id": profile.id,
+                    "name": profile.name,
+                    "service_provider": profile.service_provider
+                });
+                if let Err(e) = handle.emit("asr-profile-changed", payload) {
+                    error!("Failed to emit ASR profile changed event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 循环热键：在后台切换到下一个 ASR 配置方案（热键回调中不能等待数据库）。
+/// 只在空闲时切换，避免录音或识别中途更换处理器
+pub fn cycle_asr_profile_from_hotkey() {
+    let busy = get_voice_assistant_instance().lock().unwrap()
+        .as_ref()
+        .is_some_and(|assistant| assistant.get_state() != InputState::Idle);
+    if busy {
+        info!("⏭️ Ignoring ASR profile cycle hotkey while recording or processing");
+        return;
+    }
+
+    tauri::async_runtime::spawn(async {
+        match crate::commands::asr_profiles::cycle_asr_profile_internal().await {
+            Ok(Some(profile)) => info!("🎚️ Switched ASR profile to {}", profile.name),
+            Ok(None) => info!("ℹ️ Only one ASR profile configured, nothing to cycle"),
+            Err(e) => error!("❌ Failed to cycle ASR profile: {}", e),
+        }
+    });
+}
+
+// Apply a saved hotkey config to the running voice assistant; no-op when it is stopped
+pub fn apply_hotkey_config(config: &crate::database::HotkeyConfig) -> Result<bool, VoiceError> {
+    let instance = get_voice_assistant_instance();
+    let guard = instance.lock().unwrap();
+    match guard.as_ref() {
+        Some(assistant) => assistant.apply_hotkey_config(config).map(|_| true),
+        None => Ok(false),
+    }
+}
+
+/// 通知前端退出进度，前端据此显示“正在完成识别…”等提示
+/// stage: stopping_listeners / finishing_transcription / closing_database / done
+fn emit_shutdown_progress(stage: &str, pending: usize) {
+    if let Some(handle_guard) = APP_HANDLE.get() {
+        if let Ok(app_handle) = handle_guard.lock() {
+            if let Some(ref handle) = *app_handle {
+                let payload = serde_json::json!({
+                    "stage": stage,
+                    "pending": pending
+                });
+                if let Err(e) = handle.emit("app-shutdown-progress", payload) {
+                    error!("Failed to emit shutdown progress event: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// 应用退出前的清理：停止热键监听，最多等待 `transcription_wait` 让正在进行的识别完成、结果写入历史，
+/// 最后关闭全局数据库连接池
+pub async fn shutdown_for_exit(transcription_wait: std::time::Duration) {
+    info!("👋 Shutting down voice assistant before exit");
+    emit_shutdown_progress("stopping_listeners", 0);
+    crate::voice_assistant::keyboard::begin_shutdown();
+    crate::voice_assistant::http_api::stop();
+    if let Some(assistant) = get_voice_assistant_instance().lock().unwrap().as_ref() {
+        assistant.stop_hotkeys();
+    }
+
+    let deadline = std::time::Instant::now() + transcription_wait;
+    let mut pending = crate::voice_assistant::keyboard::transcriptions_in_flight();
+    while pending > 0 {
+        if std::time::Instant::now() >= deadline {
+            warn!("⚠️ Exiting with {} transcription(s) still in progress", pending);
+            break;
+        }
+        emit_shutdown_progress("finishing_transcription", pending);
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        pending = crate::voice_assistant::keyboard::transcriptions_in_flight();
+    }
+
+    // 未结束的会话记录也交给写库任务保存
+    crate::voice_assistant::transcript::end_and_save();
+
+    // 识别完成后结果还要经过写库任务，等它写完再关闭数据库
+    while crate::voice_assistant::result_sink::pending() > 0 && std::time::Instant::now() < deadline {
+        tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    }
+
+    emit_shutdown_progress("closing_database", 0);
+    crate::database::Database::close_global_pool().await;
+    emit_shutdown_progress("done", 0);
+}
+
 // Helper function to emit service status update events
 pub fn emit_service_status_updated_event() {
     if let Some(handle_guard) = APP_HANDLE.get() {
@@ -81,81 +371,72 @@ pub fn emit_service_status_updated_event() {
     }
 }
 
-// Directly save ASR result to database and emit update events
-pub async fn save_asr_result_directly(
-    output_text: String,
-    processor_type: &str,
-    processing_time_ms: Option<i64>,
-    success: bool,
-    error_message: Option<String>,
-) {
-    println!("📊 [Coordinator] Directly saving ASR result to database...");
-    
-    // Create history record
-    let record = crate::database::NewHistoryRecord {
-        record_type: "asr".to_string(),
-        input_text: None,
-        output_text: Some(output_text),
-        audio_file_path: None,
-        processor_type: Some(processor_type.to_string()),
-        processing_time_ms,
-        success,
-        error_message,
-    };
+/// 一次录音的文件路径和时长，写入历史记录用于用量统计
+#[derive(Debug, Clone, Default)]
+pub struct RecordedAudio {
+    pub file_path: Option<String>,
+    pub duration_ms: Option<i64>,
+}
 
-    // Use global database pool
-    match crate::database::Database::from_global_pool().await {
-        Ok(database) => {
-            match database.add_history_record(record).await {
-                Ok(_) => {
-                    println!("✅ [Coordinator] ASR result saved to database successfully");
-                    // Emit update events for frontend refresh
-                    emit_new_history_record_event();
-                    emit_service_status_updated_event();
-                }
-                Err(e) => {
-                    println!("❌ [Coordinator] Failed to save ASR result to database: {}", e);
-                }
-            }
-        }
-        Err(e) => {
-            println!("❌ [Coordinator] Failed to get database instance: {}", e);
+/// 识别器报告的语言和可信度，云端处理器都为 None
+#[derive(Debug, Clone, Default)]
+pub struct RecognitionInfo {
+    pub detected_language: Option<String>,
+    pub confidence: Option<f64>,
+    /// 实际给出结果的处理器（回退链中可能不是主处理器）
+    pub processor_type: Option<String>,
+}
+
+impl RecognitionInfo {
+    /// 可信度、语言和处理器随识别结果一起返回，不从处理器上读取，避免被并发的识别覆盖
+    pub fn from_transcript(processor: &dyn AsrProcessor, transcript: &AsrTranscript) -> Self {
+        Self {
+            detected_language: transcript.language.clone(),
+            confidence: transcript.confidence.map(|c| f64::from(c.confidence)),
+            processor_type: Some(transcript.processor_type_or(processor).to_string()),
         }
     }
+
+    /// 写入历史记录的处理器标识；没有识别结果时取配置的处理器
+    pub fn processor_type_or(&self, processor: &dyn AsrProcessor) -> String {
+        self.processor_type.clone().unwrap_or_else(|| processor.get_processor_type().to_string())
+    }
 }
 
 // Helper function to emit ASR result events
 pub fn emit_asr_result_event(result: &AsrResult) {
-    println!("🚀 [Backend] Attempting to emit ASR result event...");
+    debug!("🚀 [Backend] Attempting to emit ASR result event...");
     if let Some(handle_guard) = APP_HANDLE.get() {
-        println!("🔍 [Backend] Got app handle guard");
+        debug!("🔍 [Backend] Got app handle guard");
         if let Ok(app_handle) = handle_guard.lock() {
-            println!("🔍 [Backend] Got app handle lock");
+            debug!("🔍 [Backend] Got app handle lock");
             if let Some(ref handle) = *app_handle {
-                println!("🔍 [Backend] Got app handle reference");
+                debug!("🔍 [Backend] Got app handle reference");
                 match handle.emit("asr-result-complete", result) {
                     Ok(_) => {
                         info!("✅ Emitted ASR result event: {} chars", result.output_text.chars().count());
-                        println!("✅ [Backend] ASR result event emitted successfully");
+                        debug!("✅ [Backend] ASR result event emitted successfully");
                     }
                     Err(e) => {
                         error!("Failed to emit ASR result event: {}", e);
-                        println!("❌ [Backend] Failed to emit ASR result event: {}", e);
                     }
                 }
             } else {
-                println!("❌ [Backend] No app handle reference");
+                warn!("⚠️ [Backend] No app handle reference");
             }
         } else {
-            println!("❌ [Backend] Failed to get app handle lock");
+            warn!("⚠️ [Backend] Failed to get app handle lock");
         }
     } else {
-        println!("❌ [Backend] No app handle guard available");
+        warn!("⚠️ [Backend] No app handle guard available");
     }
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 pub struct AsrResult {
+    /// "asr"（默认）或 "translate"；翻译结果的 input_text 为原文
+    #[serde(default)]
+    pub record_type: Option<String>,
     pub success: bool,
     pub input_text: Option<String>,
     pub output_text: String,
@@ -165,24 +446,70 @@ pub struct AsrResult {
     pub error_message: Option<String>,
 }
 
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+impl AsrResult {
+    pub fn from_record(record: &crate::database::NewHistoryRecord) -> Self {
+        Self {
+            record_type: Some(record.record_type.clone()),
+            success: record.success,
+            input_text: record.input_text.clone(),
+            output_text: record.output_text.clone().unwrap_or_default(),
+            processor_type: record.processor_type.clone().unwrap_or_default(),
+            processing_time_ms: record.processing_time_ms,
+            audio_file_path: record.audio_file_path.clone(),
+            error_message: record.error_message.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum ProcessorType {
+    /// 保存了云端地址时按保存的请求格式、模型和语言识别，否则按 service_platform 选择 Groq 或 SenseVoice
     #[serde(rename = "cloud")]
     CloudASR,
     #[serde(rename = "local")]
     LocalASR,
     #[serde(rename = "whisper-rs")]
     WhisperRS,
+    #[serde(rename = "groq")]
+    Groq,
+    #[serde(rename = "sensevoice")]
+    SenseVoice,
+    /// OpenAI 兼容的 /v1/audio/transcriptions 服务，使用保存的云端地址、key、模型和语言
+    #[serde(rename = "openai")]
+    OpenAI,
     // #[serde(rename = "enhanced-whisper")]
     // EnhancedWhisper,
 }
 
+impl ProcessorType {
+    /// "cloud" 解析成具体的云端后端，方便备用链去重；
+    /// service_platform 为 "cloud"（设置中保存的云端服务）时保留，创建时读取保存的配置
+    pub fn resolve(&self, service_platform: &str) -> ProcessorType {
+        match self {
+            ProcessorType::CloudASR if service_platform == "groq" => ProcessorType::Groq,
+            ProcessorType::CloudASR if service_platform == "cloud" => ProcessorType::CloudASR,
+            ProcessorType::CloudASR => ProcessorType::SenseVoice,
+            other => other.clone(),
+        }
+    }
+}
+
+/// 竞速模式参与比较的本地 whisper 和第一个云端处理器在链中的位置
+pub fn race_pair(chain: &[ProcessorType]) -> Option<(usize, usize)> {
+    let local = chain.iter().position(|kind| *kind == ProcessorType::WhisperRS)?;
+    let cloud = chain
+        .iter()
+        .position(|kind| matches!(kind, ProcessorType::Groq | ProcessorType::SenseVoice | ProcessorType::OpenAI | ProcessorType::CloudASR))?;
+    Some((local, cloud))
+}
+
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TranslateType {
     SiliconFlow,
     Ollama,
+    DeepL,
 }
 
 #[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
@@ -193,30 +520,115 @@ pub struct VoiceAssistantConfig {
     pub convert_to_simplified: bool,
     pub add_symbol: bool,
     pub optimize_result: bool,
+    /// LLM 优化结果的时间预算（毫秒），超时输入原文
+    #[serde(default = "default_optimize_timeout_ms")]
+    pub optimize_timeout_ms: u64,
+    /// 翻译热键的目标语言（语言代码）
+    pub translate_target_language: String,
+    /// 翻译的源语言，None 表示自动判断
+    pub translate_source_language: Option<String>,
+    /// 翻译服务地址和模型（Ollama、DeepL 使用），None 时回退到环境变量
+    #[serde(default)]
+    pub translate_endpoint: Option<String>,
+    #[serde(default)]
+    pub translate_model: Option<String>,
+    /// 翻译服务的 key（目前只有 DeepL 使用），不发给前端
+    #[serde(skip)]
+    pub translate_api_key: Option<String>,
+    /// 单次翻译请求的超时（秒），超时后输入未翻译的原文
+    #[serde(default = "default_translate_timeout_secs")]
+    pub translate_timeout_secs: u64,
+    /// 主处理器失败时按顺序尝试的处理器，None 表示使用默认顺序，空列表表示不回退
+    #[serde(default)]
+    pub asr_fallback_chain: Option<Vec<ProcessorType>>,
+}
+
+fn default_optimize_timeout_ms() -> u64 {
+    crate::voice_assistant::postprocess::DEFAULT_OPTIMIZE_TIMEOUT_MS
+}
+
+fn default_translate_timeout_secs() -> u64 {
+    crate::voice_assistant::translate::DEFAULT_TRANSLATE_TIMEOUT_SECS as u64
 }
 
 impl Default for VoiceAssistantConfig {
     fn default() -> Self {
+        let settings = crate::voice_assistant::settings::current();
         Self {
-            service_platform: std::env::var("SERVICE_PLATFORM").unwrap_or_else(|_| "siliconflow".to_string()),
+            service_platform: settings.service_platform,
             asr_processor: ProcessorType::WhisperRS, // 🔥 改为默认使用本地WhisperRS
             translate_processor: TranslateType::Ollama,
-            convert_to_simplified: std::env::var("CONVERT_TO_SIMPLIFIED")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-            add_symbol: std::env::var("ADD_SYMBOL")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-            optimize_result: std::env::var("OPTIMIZE_RESULT")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
+            convert_to_simplified: settings.convert_to_simplified,
+            add_symbol: settings.add_symbol,
+            optimize_result: settings.optimize_result,
+            optimize_timeout_ms: settings.optimize_timeout_ms,
+            translate_target_language: crate::voice_assistant::translate::DEFAULT_TARGET_LANGUAGE.to_string(),
+            translate_source_language: None,
+            translate_endpoint: None,
+            translate_model: None,
+            translate_api_key: None,
+            translate_timeout_secs: default_translate_timeout_secs(),
+            asr_fallback_chain: None,
         }
     }
 }
 
+impl VoiceAssistantConfig {
+    /// 主处理器 + 备用处理器，"cloud" 已解析为具体后端并去重。
+    /// 没有保存备用链时只使用主处理器：本地识别的录音不会在用户不知情时发到云端
+    pub fn asr_processor_chain(&self) -> Vec<ProcessorType> {
+        let fallbacks = self.asr_fallback_chain.clone().unwrap_or_default();
+
+        let mut chain: Vec<ProcessorType> = Vec::new();
+        for kind in std::iter::once(&self.asr_processor).chain(fallbacks.iter()) {
+            let kind = kind.resolve(&self.service_platform);
+            if !chain.contains(&kind) {
+                chain.push(kind);
+            }
+        }
+        chain
+    }
+
+    pub fn postprocess_options(&self) -> crate::voice_assistant::postprocess::PostProcessOptions {
+        crate::voice_assistant::postprocess::PostProcessOptions {
+            convert_to_simplified: self.convert_to_simplified,
+            add_symbol: self.add_symbol,
+            optimize_result: self.optimize_result,
+            optimize_timeout_ms: self.optimize_timeout_ms,
+        }
+    }
+
+    /// 按配置的目标/源语言创建翻译处理器
+    pub fn create_translate_processor(&self) -> Result<Arc<dyn TranslateProcessor + Send + Sync>, VoiceError> {
+        let target = self.translate_target_language.as_str();
+        let source = self.translate_source_language.as_deref();
+        let timeout = std::time::Duration::from_secs(self.translate_timeout_secs.max(1));
+        Ok(match self.translate_processor {
+            TranslateType::SiliconFlow => Arc::new(
+                SiliconFlowTranslateProcessor::new()?
+                    .with_languages(target, source)
+                    .with_timeout(timeout),
+            ),
+            TranslateType::Ollama => Arc::new(
+                OllamaTranslateProcessor::with_settings(self.translate_endpoint.as_deref(), self.translate_model.as_deref())?
+                    .with_languages(target, source)
+                    .with_timeout(timeout),
+            ),
+            TranslateType::DeepL => Arc::new(
+                DeepLTranslateProcessor::with_settings(self.translate_api_key.as_deref(), self.translate_endpoint.as_deref())?
+                    .with_languages(target, source),
+            ),
+        })
+    }
+}
+
+/// 刷新时从数据库重新创建的配置和处理器
+struct RefreshedConfigs {
+    config: VoiceAssistantConfig,
+    asr_processor: Arc<dyn AsrProcessor + Send + Sync>,
+    translate_processor: Arc<dyn TranslateProcessor + Send + Sync>,
+}
+
 pub struct VoiceAssistant {
     config: VoiceAssistantConfig,
     app_handle: Option<AppHandle>,
@@ -224,7 +636,6 @@ pub struct VoiceAssistant {
     translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>>,
     keyboard_manager: Arc<Mutex<KeyboardManager>>,
     recorder: Arc<Mutex<AudioRecorder>>,
-    state: Arc<Mutex<InputState>>,
     logger_initialized: bool,
 }
 
@@ -236,267 +647,25 @@ impl VoiceAssistant {
     pub async fn new_with_handle(app_handle: Option<AppHandle>) -> Result<Self, VoiceError> {
         // Initialize logger first
         if let Err(e) = crate::voice_assistant::init_logger() {
-            eprintln!("Failed to initialize logger: {}", e);
+            warn!("Failed to initialize logger: {}", e);
         }
 
         // Load configuration from database during initialization
         let config = Self::load_config_from_database().await.unwrap_or_else(|e| {
-            println!("⚠️ Failed to load config from database: {}, using default", e);
+            warn!("⚠️ Failed to load config from database: {}, using default", e);
             VoiceAssistantConfig::default()
         });
         info!("Initializing VoiceAssistant");
 
-        // Create ASR processor based on configuration
-        let asr_processor: Arc<dyn AsrProcessor + Send + Sync> = match config.asr_processor {
-            ProcessorType::CloudASR => {
-                // Choose between Whisper and SenseVoice based on service platform
-                if config.service_platform == "groq" {
-                    info!("Creating Cloud ASR processor (Whisper backend)");
-                    Arc::new(WhisperProcessor::new()?)
-                } else {
-                    info!("Creating Cloud ASR processor (SenseVoice backend)");
-                    Arc::new(SenseVoiceProcessor::new()?)
-                }
-            },
-            ProcessorType::LocalASR => {
-                info!("Creating Local ASR processor (HTTP API)");
-                // Load ASR configuration from database for Local ASR
-                let local_asr_config = Self::load_local_asr_config().await.unwrap_or_else(|e| {
-                    println!("⚠️ Failed to load local ASR config from database: {}, using default", e);
-                    crate::voice_assistant::asr::local_asr::LocalASRConfig {
-                        endpoint: "http://192.168.8.107:5001/inference".to_string(),
-                        api_key: "default-key".to_string(),
-                    }
-                });
-
-                Arc::new(LocalASRProcessor::with_config(local_asr_config)?)
-            },
-            ProcessorType::WhisperRS => {
-                info!("Creating WhisperRS processor (Local whisper.cpp)");
-
-                // Get the correct models directory using Tauri API if available
-                let models_dir = if let Some(ref handle) = app_handle {
-                    handle.path()
-                        .app_data_dir()
-                        .unwrap_or_else(|_| std::env::current_dir().unwrap().join("data"))
-                        .join("models")
-                } else {
-                    crate::utils::platform::get_models_dir()
-                };
-
-                // Load WhisperRS configuration from environment or use default location
-                let model_path = std::env::var("WHISPER_MODEL_PATH")
-                    .ok()
-                    .and_then(|path| {
-                        if std::path::Path::new(&path).exists() {
-                            println!("✅ Using active model from environment: {}", path);
-                            Some(path)
-                        } else {
-                            println!("⚠️ Environment model doesn't exist: {}", path);
-                            None
-                        }
-                    })
-                    .or_else(|| {
-                        // Try to find the model in the data directory
-                        let model_file = models_dir.join("ggml-large-v3-turbo-q5_0.bin");
-                        if model_file.exists() {
-                            Some(model_file.to_string_lossy().to_string())
-                        } else {
-                            None
-                        }
-                    })
-                    .or_else(|| {
-                        // Try to find models in the data directory
-                        println!("🔍 Searching for models in: {}", models_dir.display());
-
-                        // Try different models in order of preference
-                        let model_preferences = [
-                            "ggml-large-v3-turbo-q5_0.bin",
-                            "ggml-large-v3-turbo.bin",
-                            "ggml-large-v2.bin",
-                            "ggml-large-v3.bin",
-                            "ggml-small.bin",
-                            "ggml-base.bin",
-                        ];
-
-                        for model in model_preferences {
-                            let model_file = models_dir.join(model);
-                            if model_file.exists() {
-                                println!("✅ Found model: {}", model);
-                                return Some(model_file.to_string_lossy().to_string());
-                            }
-                        }
-
-                        // If no preferred models found, scan all .bin files
-                        println!("⚠️ No preferred models found, searching for any .bin files...");
-                        if let Ok(entries) = std::fs::read_dir(&models_dir) {
-                            for entry in entries.flatten() {
-                                let path = entry.path();
-                                if path.extension().and_then(|s| s.to_str()) == Some("bin") {
-                                    println!("✅ Found alternative model: {}", path.display());
-                                    return Some(path.to_string_lossy().to_string());
-                                }
-                            }
-                        }
-
-                        None
-                    })
-                    .ok_or_else(|| {
-                        println!("⚠️ Whisper model not found. Please download a model to {}/", models_dir.display());
-                        println!("💡 Recommended models for CPU: ggml-base.bin (fastest) or ggml-small.bin (balanced)");
-                        println!("📥 Download from: https://huggingface.co/ggerganov/whisper.cpp/tree/main");
-                        VoiceError::Other("Whisper model not found".to_string())
-                    })?;
-
-                println!("🎯 Using Whisper model: {}", model_path);
-
-                {
-                    println!("🔧 Creating WhisperRSProcessor with model: {}", model_path);
-                    
-                    // Try to create WhisperRSProcessor with timeout to prevent indefinite hangs
-                    println!("⏱️ Creating WhisperRSProcessor with safety timeout...");
-                    
-                    let processor_result = std::thread::spawn(move || {
-                        // Use a simple timeout mechanism
-                        let (tx, rx) = std::sync::mpsc::channel();
-                        
-                        // Spawn the processor creation in a separate thread
-                        std::thread::spawn(move || {
-                            let result = WhisperRSProcessor::with_model_path(&model_path);
-                            let _ = tx.send(result);
-                        });
-                        
-                        // Wait for up to 180 seconds (3 minutes) for processor creation - large models need more time
-                        match rx.recv_timeout(std::time::Duration::from_secs(180)) {
-                            Ok(processor_result) => processor_result,
-                            Err(_) => {
-                                eprintln!("⏰ WhisperRSProcessor creation timed out after 180 seconds");
-                                eprintln!("💡 This indicates a deadlock or infinite loop in whisper.cpp");
-                                eprintln!("💡 Or the model is very large and needs even more time to load");
-                                Err(crate::voice_assistant::VoiceError::Other(
-                                    "WhisperRSProcessor creation timeout".to_string()
-                                ))
-                            }
-                        }
-                    }).join().unwrap_or_else(|_| {
-                        eprintln!("💥 WhisperRSProcessor creation thread panicked!");
-                        Err(crate::voice_assistant::VoiceError::Other(
-                            "WhisperRSProcessor creation thread panicked".to_string()
-                        ))
-                    });
-                    
-                    match processor_result {
-                        Ok(processor) => {
-                            println!("✅ WhisperRSProcessor created successfully");
-                            Arc::new(processor)
-                        }
-                        Err(e) => {
-                            eprintln!("❌ Failed to create WhisperRSProcessor: {}", e);
-                            eprintln!("💡 Falling back to Cloud ASR processor...");
-
-                            // Fallback to Cloud ASR
-                            if config.service_platform == "groq" {
-                                println!("🔄 Fallback: Creating Whisper Cloud processor...");
-                                match WhisperProcessor::new() {
-                                    Ok(processor) => {
-                                        println!("✅ Cloud ASR fallback processor created successfully");
-                                        Arc::new(processor)
-                                    }
-                                    Err(e) => {
-                                        eprintln!("❌ Failed to create Cloud ASR fallback: {}", e);
-                                        eprintln!("💡 Trying SenseVoice as final fallback...");
-                                        match SenseVoiceProcessor::new() {
-                                            Ok(processor) => {
-                                                println!("✅ SenseVoice fallback processor created successfully");
-                                                Arc::new(processor)
-                                            }
-                                            Err(e) => {
-                                                eprintln!("❌ All ASR processors failed: {}", e);
-                                                return Err(crate::voice_assistant::VoiceError::Other(
-                                                    format!("All ASR processors failed. Primary error: {}, Cloud fallback failed: {}", e, e)
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
-                            } else {
-                                println!("🔄 Fallback: Creating SenseVoice Cloud processor...");
-                                match SenseVoiceProcessor::new() {
-                                    Ok(processor) => {
-                                        println!("✅ SenseVoice fallback processor created successfully");
-                                        Arc::new(processor)
-                                    }
-                                    Err(e) => {
-                                        eprintln!("❌ Failed to create SenseVoice fallback: {}", e);
-                                        eprintln!("💡 Trying Whisper Cloud as final fallback...");
-                                        match WhisperProcessor::new() {
-                                            Ok(processor) => {
-                                                println!("✅ Cloud ASR fallback processor created successfully");
-                                                Arc::new(processor)
-                                            }
-                                            Err(e) => {
-                                                eprintln!("❌ All ASR processors failed: {}", e);
-                                                return Err(crate::voice_assistant::VoiceError::Other(
-                                                    format!("All ASR processors failed. Primary error: {}, Cloud fallback failed: {}", e, e)
-                                                ));
-                                            }
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    }
-                }
-            },
-            // ProcessorType::EnhancedWhisper => {
-            //     info!("Creating Enhanced Whisper processor (with VAD support)");
-            //     // Load model path with intelligent detection
-            //     let model_path = std::env::var("WHISPER_MODEL_PATH")
-            //         .ok()
-            //         .and_then(|path| {
-            //             if std::path::Path::new(&path).exists() {
-            //                 Some(path)
-            //             } else {
-            //                 None
-            //             }
-            //         })
-            //         .or_else(|| {
-            //             // Try to find the model in the default data directory
-            //             let home = std::env::var("HOME").ok()?;
-            //             let model_file = format!("{}/.local/share/com.martin.flash-input/models/ggml-large-v3-turbo.bin", home);
-            //             if std::path::Path::new(&model_file).exists() {
-            //                 Some(model_file)
-            //             } else {
-            //                 None
-            //             }
-            //         })
-            //         .unwrap_or_else(|| {
-            //             println!("⚠️ Whisper model not found. Please download ggml-large-v3-turbo.bin to ~/.local/share/com.martin.flash-input/models/ or set WHISPER_MODEL_PATH");
-            //             "./models/ggml-large-v3-turbo.bin".to_string()
-            //         });
-
-            //     println!("🚀 Using Enhanced Whisper with VAD model: {}", model_path);
-
-            //     // Use beam search with VAD for better accuracy
-            //     Arc::new(EnhancedWhisperProcessor::with_beam_search_and_vad(
-            //         &model_path,
-            //         5,  // beam_size
-            //         -1.0, // patience (default)
-            //     )?)
-            // },
-        };
+        // Create ASR processor chain based on configuration
+        let asr_processor = Self::create_asr_processor_chain(&config).await?;
 
         // Create translation processor
-        let translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>> = match config.translate_processor {
-            TranslateType::SiliconFlow => {
-                info!("Creating SiliconFlow translation processor");
-                Some(Arc::new(SiliconFlowTranslateProcessor::new()?))
-            },
-            TranslateType::Ollama => {
-                info!("Creating Ollama translation processor");
-                Some(Arc::new(OllamaTranslateProcessor::new()?))
-            },
-        };
+        info!(
+            "Creating {:?} translation processor (target: {}, source: {:?})",
+            config.translate_processor, config.translate_target_language, config.translate_source_language
+        );
+        let translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>> = Some(config.create_translate_processor()?);
 
         // Create audio recorder
         let recorder = Arc::new(Mutex::new(AudioRecorder::new()?));
@@ -514,121 +683,51 @@ impl VoiceAssistant {
             translate_processor,
             keyboard_manager,
             recorder,
-            state: Arc::new(Mutex::new(InputState::Idle)),
             logger_initialized: true,
         })
     }
 
-    pub async fn with_config(config: VoiceAssistantConfig) -> Result<Self, VoiceError> {
-        // Similar to new() but uses provided config
-        let mut assistant = Self::new().await?;
-        assistant.config = config;
-        Ok(assistant)
-    }
-
-    /// 🔥 刷新所有配置 - 确保从数据库获取最新设置
-    pub async fn refresh_all_configs(&mut self) -> Result<(), VoiceError> {
-        println!("🔄 Refreshing all configurations from database...");
-        
-        // 1. 刷新核心配置
-        let fresh_config = Self::load_config_from_database().await?;
-        self.config = fresh_config;
-        println!("✅ Core configuration refreshed");
-        
-        // 2. 刷新ASR处理器（如果类型发生变化）
-        let new_asr_processor: Arc<dyn AsrProcessor + Send + Sync> = match self.config.asr_processor {
-            ProcessorType::CloudASR => {
-                // 根据service_platform选择不同的云ASR后端
-                if self.config.service_platform == "groq" {
-                    println!("🔄 Creating Cloud ASR processor (Whisper backend)");
-                    Arc::new(crate::voice_assistant::asr::whisper::WhisperProcessor::new()?)
-                } else {
-                    println!("🔄 Creating Cloud ASR processor (SenseVoice backend)");
-                    Arc::new(crate::voice_assistant::asr::sensevoice::SenseVoiceProcessor::new()?)
-                }
-            },
-            ProcessorType::LocalASR => {
-                println!("🔄 Creating Local ASR processor (HTTP API)");
-                let local_asr_config = Self::load_local_asr_config().await?;
-                Arc::new(crate::voice_assistant::asr::local_asr::LocalASRProcessor::with_config(local_asr_config)?)
-            },
-            ProcessorType::WhisperRS => {
-                println!("🔄 Creating WhisperRS processor (Local whisper.cpp)");
-                // Load WhisperRS configuration from environment or use default location
-                let model_path = std::env::var("WHISPER_MODEL_PATH")
-                    .ok()
-                    .and_then(|path| {
-                        if std::path::Path::new(&path).exists() {
-                            println!("✅ Using active model from environment: {}", path);
-                            Some(path)
-                        } else {
-                            println!("⚠️ Environment model doesn't exist: {}", path);
-                            None
-                        }
-                    })
-                    .or_else(|| {
-                        // 🔥 搜索模型目录，按优先级查找
-                        let models_dir = crate::utils::platform::get_models_dir();
-                        println!("🔍 Searching for models in: {}", models_dir.display());
-
-                        // Try different models in order of preference
-                        let model_preferences = [
-                            "ggml-large-v3-turbo-q5_0.bin", // ~990MB - Q5_0 quantized
-                            "ggml-large-v3-turbo.bin",     // ~1570MB
-                            "ggml-large-v2.bin",           // ~1550MB
-                            "ggml-large-v3.bin",           // ~2950MB
-                            "ggml-small.bin",              // ~467MB
-                            "ggml-base.bin",               // ~148MB
-                        ];
-
-                        for model in model_preferences {
-                            let model_file = models_dir.join(model);
-                            if model_file.exists() {
-                                println!("✅ Found model: {}", model);
-                                return Some(model_file.to_string_lossy().to_string());
-                            }
-                        }
+    pub async fn with_config(config: VoiceAssistantConfig) -> Result<Self, VoiceError> {
+        // Similar to new() but uses provided config
+        let mut assistant = Self::new().await?;
+        assistant.config = config;
+        Ok(assistant)
+    }
 
-                        // 如果上述模型都没找到，扫描所有.bin文件
-                        println!("⚠️ No preferred models found, searching for any .bin files...");
-                        if let Ok(entries) = std::fs::read_dir(&models_dir) {
-                            for entry in entries.flatten() {
-                                let path = entry.path();
-                                if path.extension().and_then(|s| s.to_str()) == Some("bin") {
-                                    println!("✅ Found alternative model: {}", path.display());
-                                    return Some(path.to_string_lossy().to_string());
-                                }
-                            }
-                        }
+    /// 🔥 刷新所有配置 - 确保从数据库获取最新设置
+    pub async fn refresh_all_configs(&mut self) -> Result<(), VoiceError> {
+        let refreshed = Self::load_refreshed_configs().await?;
+        self.apply_refreshed_configs(refreshed)
+    }
 
-                        None
-                    })
-                    .ok_or_else(|| {
-                        println!("⚠️ Whisper model not found. Please download a model to {}/", crate::utils::platform::get_models_dir().display());
-                        VoiceError::Other("Whisper model not found".to_string())
-                    })?;
+    /// 从数据库读取配置并创建新的处理器，不需要持有运行中的实例
+    async fn load_refreshed_configs() -> Result<RefreshedConfigs, VoiceError> {
+        info!("🔄 Refreshing all configurations from database...");
 
-                println!("🎯 Using Whisper model: {}", model_path);
+        // 1. 刷新核心配置
+        let config = Self::load_config_from_database().await?;
+        info!("✅ Core configuration refreshed");
 
-                Arc::new(crate::voice_assistant::asr::whisper_rs::WhisperRSProcessor::with_model_path(&model_path)?)
-            },
-        };
-        self.asr_processor = Some(new_asr_processor);
-        println!("✅ ASR processor refreshed");
+        // 2. 刷新ASR处理器（如果类型发生变化）
+        let asr_processor = Self::create_asr_processor_chain(&config).await?;
+        info!("✅ ASR processor refreshed");
 
         // 3. 刷新翻译处理器
-        let new_translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>> = match self.config.translate_processor {
-            TranslateType::SiliconFlow => {
-                println!("🔄 Creating SiliconFlow translation processor");
-                Some(Arc::new(crate::voice_assistant::translate::siliconflow::SiliconFlowTranslateProcessor::new()?))
-            },
-            TranslateType::Ollama => {
-                println!("🔄 Creating Ollama translation processor");
-                Some(Arc::new(crate::voice_assistant::translate::ollama::OllamaTranslateProcessor::new()?))
-            },
-        };
-        self.translate_processor = new_translate_processor;
-        println!("✅ Translation processor refreshed");
+        info!(
+            "🔄 Creating {:?} translation processor (target: {})",
+            config.translate_processor, config.translate_target_language
+        );
+        let translate_processor = config.create_translate_processor()?;
+        info!("✅ Translation processor refreshed");
+
+        Ok(RefreshedConfigs { config, asr_processor, translate_processor })
+    }
+
+    /// 换上新的配置和处理器，不含 await，可以在持有全局实例锁时调用
+    fn apply_refreshed_configs(&mut self, refreshed: RefreshedConfigs) -> Result<(), VoiceError> {
+        self.config = refreshed.config;
+        self.asr_processor = Some(refreshed.asr_processor);
+        self.translate_processor = Some(refreshed.translate_processor);
 
         // 4. 更新键盘管理器的处理器引用
         if let Ok(mut keyboard_manager) = self.keyboard_manager.lock() {
@@ -636,60 +735,104 @@ impl VoiceAssistant {
                 self.asr_processor.clone(),
                 self.translate_processor.clone()
             )?;
-            println!("✅ Keyboard manager processors updated");
+            keyboard_manager.set_postprocess_options(self.config.postprocess_options());
+            info!("✅ Keyboard manager processors updated");
         }
         
-        println!("🎉 All configurations successfully refreshed from database");
+        info!("🎉 All configurations successfully refreshed from database");
         Ok(())
     }
 
     pub async fn start(&mut self) -> Result<(), VoiceError> {
-        println!("🚀 === VoiceAssistant Starting ===");
+        info!("🚀 === VoiceAssistant Starting ===");
         info!("Starting VoiceAssistant");
         
         // STEP 0: Skip refresh - config already loaded during initialization
-        println!("🔄 Step 0: Configuration already loaded during initialization");
+        info!("🔄 Step 0: Configuration already loaded during initialization");
         
+        // Step 0.5: Load glossary replacements so they're compiled once before dictation starts
+        if let Err(e) = crate::voice_assistant::replacements::reload_replacements().await {
+            warn!("⚠️ Failed to load transcription replacements: {}", e);
+        }
+        if let Err(e) = crate::voice_assistant::audio_prep::reload_audio_prep_config().await {
+            warn!("⚠️ Failed to load audio pre-processing config: {}", e);
+        }
+        if let Err(e) = crate::voice_assistant::net::reload_retry_config().await {
+            warn!("⚠️ Failed to load network retry config: {}", e);
+        }
+        if let Err(e) = crate::voice_assistant::dictation_commands::reload_dictation_commands().await {
+            warn!("⚠️ Failed to load dictation commands: {}", e);
+        }
+        if let Err(e) = crate::voice_assistant::output_profiles::reload_output_profiles().await {
+            warn!("⚠️ Failed to load output profiles: {}", e);
+        }
+        // 识别结果通过通道交给写库任务保存
+        crate::voice_assistant::result_sink::ResultSink::start();
+
         // Step 1: Load hotkey configuration from database
-        println!("📊 Step 1: Loading hotkey configuration...");
+        debug!("📊 Step 1: Loading hotkey configuration...");
         let db_config = crate::commands::get_hotkey_config_from_database().await?;
         if let Some(config) = db_config {
-            println!("✅ Database config found:");
-            println!("  - Transcribe: {}", config.transcribe_key);
-            println!("  - Translate: {}", config.translate_key);
-            println!("  - Trigger delay: {}ms", config.trigger_delay_ms);
-            println!("  - Anti-mistouch enabled: {}", config.anti_mistouch_enabled);
-            println!("  - Save WAV files: {}", config.save_wav_files);
+            info!("✅ Database config found:");
+            debug!("  - Transcribe: {}", config.transcribe_key);
+            debug!("  - Translate: {}", config.translate_key);
+            debug!("  - Trigger delay: {}ms", config.trigger_delay_ms);
+            debug!("  - Anti-mistouch enabled: {}", config.anti_mistouch_enabled);
+            debug!("  - Save WAV files: {}", config.save_wav_files);
             
             // Step 2: Set hotkeys on keyboard manager and start listening
-            println!("📝 Step 2: Setting hotkeys on keyboard manager...");
+            debug!("📝 Step 2: Setting hotkeys on keyboard manager...");
             if let Ok(mut keyboard_manager) = self.keyboard_manager.lock() {
-                println!("🔓 Keyboard manager lock acquired");
+                debug!("🔓 Keyboard manager lock acquired");
                 if let Err(e) = keyboard_manager.set_hotkeys(&config.transcribe_key, &config.translate_key) {
-                    println!("❌ Failed to set hotkeys: {}", e);
+                    error!("❌ Failed to set hotkeys: {}", e);
                     return Err(VoiceError::Audio(format!("Failed to set hotkeys: {}", e)));
                 }
-                println!("✅ Hotkeys set successfully");
+                info!("✅ Hotkeys set successfully");
 
                 // Step 2.5: Set save_wav_files configuration
-                println!("📁 Step 2.5: Setting save_wav_files configuration...");
+                debug!("📁 Step 2.5: Setting save_wav_files configuration...");
                 keyboard_manager.set_save_wav_files(config.save_wav_files);
+                keyboard_manager.set_text_injection_method(
+                    TextInjectionMethod::parse(&config.text_injection_method).unwrap_or_default()
+                );
+                keyboard_manager.set_postprocess_options(self.config.postprocess_options());
+                keyboard_manager.set_recording_mode(RecordingMode::parse(&config.recording_mode).unwrap_or_default());
+                keyboard_manager.set_max_recording_secs(config.max_recording_secs);
+                keyboard_manager.set_silence_auto_stop(config.silence_auto_stop_enabled, config.silence_auto_stop_ms);
+                keyboard_manager.set_preroll_ms(config.preroll_ms);
+                keyboard_manager.set_queued_to_clipboard(config.queued_to_clipboard);
+                keyboard_manager.set_cycle_profile_hotkey(config.cycle_profile_key.as_deref());
+                keyboard_manager.set_anti_mistouch_enabled(config.anti_mistouch_enabled);
+                keyboard_manager.set_trigger_delay_ms(config.trigger_delay_ms);
+                let backend = HotkeyBackend::parse(&config.hotkey_backend).unwrap_or_default();
+                keyboard_manager.set_hotkey_backend(backend);
 
                 // Step 3: Start keyboard listening
-                println!("👂 Step 3: Starting keyboard listening...");
+                info!("👂 Step 3: Starting keyboard listening...");
                 keyboard_manager.start_listening();
-                println!("✅ Keyboard listening started");
+                if backend == HotkeyBackend::Plugin {
+                    if let Err(e) = register_plugin_hotkeys(&config.transcribe_key, &config.translate_key, config.cycle_profile_key.as_deref()) {
+                        // 插件注册失败（如快捷键被其他程序占用）时退回 rdev，保证热键可用
+                        warn!("⚠️ {}, falling back to rdev keyboard listener", e);
+                        keyboard_manager.stop_listening();
+                        keyboard_manager.set_hotkey_backend(HotkeyBackend::Rdev);
+                        keyboard_manager.start_listening();
+                    }
+                }
+                info!("✅ Keyboard listening started");
             } else {
-                println!("❌ Failed to acquire keyboard manager lock");
+                error!("❌ Failed to acquire keyboard manager lock");
                 return Err(VoiceError::Audio("Failed to acquire keyboard manager lock".to_string()));
             }
         } else {
-            println!("⚠️ No hotkey configuration found in database, using defaults");
+            warn!("⚠️ No hotkey configuration found in database, using defaults");
             if let Ok(mut keyboard_manager) = self.keyboard_manager.lock() {
                 // 使用默认热键 (F4 和 Shift + F4)
                 if let Err(e) = keyboard_manager.set_hotkeys("F4", "Shift + F4") {
                     return Err(VoiceError::Audio(format!("Failed to set default hotkeys: {}", e)));
                 }
+                keyboard_manager.set_postprocess_options(self.config.postprocess_options());
                 keyboard_manager.start_listening();
             }
         }
@@ -721,9 +864,21 @@ impl VoiceAssistant {
         Ok(())
     }
 
+    /// 注销插件热键并停止信号线程，已在队列中的录音仍会识别完
+    fn stop_hotkeys(&self) {
+        if let Ok(keyboard_manager) = self.keyboard_manager.lock() {
+            if keyboard_manager.hotkey_backend() == HotkeyBackend::Plugin {
+                unregister_plugin_hotkeys();
+            }
+            keyboard_manager.stop_listening();
+        }
+    }
+
     pub fn stop(&mut self) -> Result<(), VoiceError> {
         info!("Stopping VoiceAssistant");
 
+        self.stop_hotkeys();
+
         // Reset keyboard manager state
         if let Ok(mut keyboard_manager) = self.keyboard_manager.lock() {
             keyboard_manager.reset_state();
@@ -753,19 +908,219 @@ impl VoiceAssistant {
             info!("✅ Translation processor unloaded successfully");
         }
 
-        *self.state.lock().unwrap() = InputState::Idle;
         info!("VoiceAssistant stopped");
         Ok(())
     }
 
+    /// 录音/识别状态由 KeyboardManager 的状态机维护
     pub fn get_state(&self) -> InputState {
-        *self.state.lock().unwrap()
+        self.keyboard_manager.lock().unwrap().state()
+    }
+
+    pub fn recording_mode(&self) -> RecordingMode {
+        self.keyboard_manager.lock().unwrap().recording_mode()
     }
 
     pub fn get_config(&self) -> VoiceAssistantConfig {
         self.config.clone()
     }
 
+    /// 按配置的顺序创建主处理器和备用处理器；多个处理器可用时包装成回退链
+    async fn create_asr_processor_chain(config: &VoiceAssistantConfig) -> Result<Arc<dyn AsrProcessor + Send + Sync>, VoiceError> {
+        let chain = config.asr_processor_chain();
+        info!("🔗 ASR processor order: {:?}", chain);
+
+        let mut kinds = Vec::new();
+        let mut processors: Vec<Arc<dyn AsrProcessor + Send + Sync>> = Vec::new();
+        let mut errors = Vec::new();
+        for kind in &chain {
+            match Self::create_asr_processor(kind).await {
+                Ok(processor) => {
+                    info!("✅ {:?} ASR processor created", kind);
+                    kinds.push(kind.clone());
+                    processors.push(processor);
+                }
+                Err(e) => {
+                    error!("❌ Failed to create {:?} ASR processor: {}", kind, e);
+                    errors.push((kind, e));
+                }
+            }
+        }
+
+        // 竞速模式：本地和云端合并成一个处理器，放在两者中靠前的位置，其余处理器仍作为备用
+        if crate::voice_assistant::settings::current().asr_race_mode {
+            match race_pair(&kinds) {
+                Some((local, cloud)) => {
+                    let first = local.min(cloud);
+                    let cloud_processor = processors.remove(cloud);
+                    let local_processor = processors.remove(if cloud < local { local - 1 } else { local });
+                    info!("🏁 ASR race mode: {:?} vs {:?}", kinds[local], kinds[cloud]);
+                    processors.insert(first, Arc::new(RaceAsrProcessor::new(local_processor, cloud_processor)));
+                }
+                None => warn!("⚠️ ASR race mode needs both a local whisper model and a cloud processor, using the normal chain"),
+            }
+        }
+
+        match processors.len() {
+            // 只有一个处理器时保留原始错误（如 ModelNotFound），前端据此提示下载模型
+            0 if errors.len() == 1 => Err(errors.remove(0).1),
+            0 => {
+                let messages: Vec<String> = errors.iter().map(|(kind, e)| format!("{:?}: {}", kind, e)).collect();
+                Err(VoiceError::Other(format!("All ASR processors failed: {}", messages.join("; "))))
+            }
+            1 => Ok(processors.remove(0)),
+            _ => {
+                let fallback = FallbackAsrProcessor::new(processors)?;
+                info!("Using ASR fallback chain: {}", fallback.processor_types().join(" -> "));
+                Ok(Arc::new(fallback))
+            }
+        }
+    }
+
+    /// 创建单个 ASR 处理器；"cloud" 需要先用 ProcessorType::resolve 解析
+    async fn create_asr_processor(kind: &ProcessorType) -> Result<Arc<dyn AsrProcessor + Send + Sync>, VoiceError> {
+        Ok(match kind {
+            ProcessorType::Groq => {
+                info!("Creating Cloud ASR processor (Whisper backend)");
+                Arc::new(WhisperProcessor::new()?)
+            }
+            ProcessorType::CloudASR => match Self::load_saved_asr_config().await?.as_ref().and_then(CloudAsrConfig::from_config) {
+                // 与测试命令相同：保存的地址、key、请求格式、模型和语言
+                Some(cloud) => {
+                    info!("Creating Cloud ASR processor ({} format, {})", cloud.options.provider.as_str(), cloud.endpoint);
+                    Arc::new(CloudAsrProcessor::with_config(cloud)?)
+                }
+                None => {
+                    info!("Creating Cloud ASR processor (SenseVoice backend, no saved cloud endpoint)");
+                    Arc::new(SenseVoiceProcessor::new()?)
+                }
+            },
+            ProcessorType::SenseVoice => {
+                info!("Creating Cloud ASR processor (SenseVoice backend)");
+                Arc::new(SenseVoiceProcessor::new()?)
+            }
+            ProcessorType::OpenAI => {
+                info!("Creating Cloud ASR processor (OpenAI-compatible backend)");
+                let saved = Self::load_saved_asr_config().await?.ok_or_else(|| {
+                    VoiceError::Other("Save a cloud ASR endpoint and API key before using the OpenAI-compatible processor".to_string())
+                })?;
+                Arc::new(CloudAsrProcessor::with_config(CloudAsrConfig::openai(&saved))?)
+            }
+            ProcessorType::LocalASR => {
+                info!("Creating Local ASR processor (HTTP API)");
+                // Load ASR configuration from database for Local ASR
+                let local_asr_config = Self::load_local_asr_config().await.unwrap_or_else(|e| {
+                    warn!("⚠️ Failed to load local ASR config from database: {}, using default", e);
+                    crate::voice_assistant::asr::local_asr::LocalASRConfig {
+                        endpoint: "http://192.168.8.107:5001/inference".to_string(),
+                        api_key: "default-key".to_string(),
+                    }
+                });
+
+                Arc::new(LocalASRProcessor::with_config(local_asr_config)?)
+            }
+            ProcessorType::WhisperRS => {
+                let configured_model = crate::voice_assistant::asr::models::configured_whisper_model().await;
+                Self::create_whisper_rs_processor(configured_model.as_deref())?
+            }
+            // ProcessorType::EnhancedWhisper => {
+            //     info!("Creating Enhanced Whisper processor (with VAD support)");
+            //     // Load model path with intelligent detection
+            //     let model_path = std::env::var("WHISPER_MODEL_PATH")
+            //         .ok()
+            //         .and_then(|path| {
+            //             if std::path::Path::new(&path).exists() {
+            //                 Some(path)
+            //             } else {
+            //                 None
+            //             }
+            //         })
+            //         .or_else(|| {
+            //             // Try to find the model in the default data directory
+            //             let home = std::env::var("HOME").ok()?;
+            //             let model_file = format!("{}/.local/share/com.martin.flash-input/models/ggml-large-v3-turbo.bin", home);
+            //             if std::path::Path::new(&model_file).exists() {
+            //                 Some(model_file)
+            //             } else {
+            //                 None
+            //             }
+            //         })
+            //         .unwrap_or_else(|| {
+            //             println!("⚠️ Whisper model not found. Please download ggml-large-v3-turbo.bin to ~/.local/share/com.martin.flash-input/models/ or set WHISPER_MODEL_PATH");
+            //             "./models/ggml-large-v3-turbo.bin".to_string()
+            //         });
+
+            //     println!("🚀 Using Enhanced Whisper with VAD model: {}", model_path);
+
+            //     // Use beam search with VAD for better accuracy
+            //     Arc::new(EnhancedWhisperProcessor::with_beam_search_and_vad(
+            //         &model_path,
+            //         5,  // beam_size
+            //         -1.0, // patience (default)
+            //     )?)
+            // },
+        })
+    }
+
+    /// 查找模型并创建 WhisperRS 处理器。默认在受监督的 worker 进程中推理，
+    /// whisper.cpp 崩溃时只影响 worker，错误交给回退链处理
+    fn create_whisper_rs_processor(configured_model: Option<&str>) -> Result<Arc<dyn AsrProcessor + Send + Sync>, VoiceError> {
+        info!("Creating WhisperRS processor (Local whisper.cpp)");
+
+        let model_path = crate::voice_assistant::asr::models::resolve_whisper_model(
+            crate::voice_assistant::asr::models::ModelPurpose::Dictation,
+            configured_model,
+        )
+        .inspect_err(|e| {
+            if let VoiceError::ModelNotFound { searched } = e {
+                emit_whisper_model_missing(searched);
+            }
+        })?;
+        info!("🎯 Using Whisper model: {}", model_path);
+
+        // 后端、GPU 设备、VAD 等取自设置，worker 和进程内推理使用同一份配置
+        let config = crate::voice_assistant::global_whisper::dictation_config(&model_path);
+
+        if whisper_worker::worker_enabled() {
+            info!("🛡️ Running whisper inference in a supervised worker process");
+            return Ok(Arc::new(whisper_worker::SupervisedWhisperProcessor::start(config)?));
+        }
+
+        info!("⚠️ {} is set, running whisper inference in-process", whisper_worker::IN_PROCESS_ENV);
+        Ok(Arc::new(Self::create_in_process_whisper_rs_processor(config)?))
+    }
+
+    /// 在应用进程内加载模型，加载超时视为失败
+    fn create_in_process_whisper_rs_processor(config: WhisperRSConfig) -> Result<WhisperRSProcessor, VoiceError> {
+        debug!("🔧 Creating WhisperRSProcessor with model: {} (backend: {})", config.model_path, config.backend);
+        
+        // Try to create WhisperRSProcessor with timeout to prevent indefinite hangs
+        info!("⏱️ Creating WhisperRSProcessor with safety timeout...");
+        
+        // 大模型冷加载需要较长时间，超时默认 180 秒，可通过 WHISPER_MODEL_LOAD_TIMEOUT_SECS 调整
+        let timeout = crate::voice_assistant::global_whisper::model_load_timeout();
+        let requested_backend = config.backend.to_string();
+        crate::utils::thread::create_with_timeout(timeout, move || WhisperRSProcessor::new(config))
+            .inspect(|processor| {
+                if let Some(reason) = processor.gpu_fallback_reason() {
+                    emit_gpu_backend_fallback(&requested_backend, reason);
+                }
+            })
+            .inspect_err(|e| match e {
+                VoiceError::Timeout(_) => {
+                    warn!("⏰ WhisperRSProcessor creation timed out after {} seconds", timeout.as_secs());
+                    warn!("💡 This indicates a deadlock or infinite loop in whisper.cpp");
+                    warn!("💡 Or the model is very large and needs even more time to load");
+                }
+                _ => error!("💥 WhisperRSProcessor creation failed: {}", e),
+            })
+    }
+
+    /// 当前使用的 ASR 配置方案，云端处理器从中读取地址、key、请求格式、模型和语言
+    async fn load_saved_asr_config() -> Result<Option<crate::database::AsrConfig>, VoiceError> {
+        Ok(crate::commands::get_asr_config_internal().await?.into_iter().next())
+    }
+
     async fn load_local_asr_config() -> Result<crate::voice_assistant::asr::local_asr::LocalASRConfig, VoiceError> {
         // Get ASR configs from database
         let asr_configs = crate::commands::get_asr_config_internal().await?;
@@ -786,30 +1141,42 @@ impl VoiceAssistant {
     }
 
     async fn load_config_from_database() -> Result<VoiceAssistantConfig, VoiceError> {
-        println!("📊 Loading configuration from database...");
+        debug!("📊 Loading configuration from database...");
+
+        // 运行时设置也从数据库重新读取，修改后无需重启
+        if let Err(e) = crate::voice_assistant::settings::reload_settings().await {
+            warn!("⚠️ Failed to reload app settings: {}", e);
+        }
         
         // Get ASR config from database
         let asr_configs = crate::commands::get_asr_config_internal().await?;
         if !asr_configs.is_empty() {
-            println!("✅ Found {} ASR config(s) in database", asr_configs.len());
+            info!("✅ Found {} ASR config(s) in database", asr_configs.len());
             for (i, config) in asr_configs.iter().enumerate() {
-                println!("  ASR Config {}: service={}, local_endpoint={:?}", 
+                debug!("  ASR Config {}: service={}, local_endpoint={:?}", 
                     i+1, config.service_provider, config.local_endpoint);
             }
         } else {
-            println!("⚠️ No ASR configs found in database");
+            warn!("⚠️ No ASR configs found in database");
         }
 
         // Get translation config from database
         let translation_configs = crate::commands::get_translation_config_internal().await?;
         if !translation_configs.is_empty() {
-            println!("✅ Found {} translation config(s) in database", translation_configs.len());
+            info!("✅ Found {} translation config(s) in database", translation_configs.len());
             for (i, config) in translation_configs.iter().enumerate() {
-                println!("  Translation Config {}: provider={}, endpoint={:?}", 
+                debug!("  Translation Config {}: provider={}, endpoint={:?}", 
                     i+1, config.provider, config.endpoint);
             }
         } else {
-            println!("⚠️ No translation configs found in database");
+            warn!("⚠️ No translation configs found in database");
+        }
+
+        // ASR 配置中的 VAD 开关同步到运行时设置，WhisperRS 加载时读取
+        if let Some(asr_config) = asr_configs.first() {
+            let enable_vad = asr_config.enable_vad;
+            crate::voice_assistant::settings::update(|s| s.whisper_enable_vad = enable_vad);
+            debug!("  - Whisper VAD trimming: {}", asr_config.enable_vad);
         }
 
         // Determine ASR processor type from database config
@@ -829,6 +1196,7 @@ impl VoiceAssistant {
             match translation_config.provider.as_str() {
                 "siliconflow" => TranslateType::SiliconFlow,
                 "ollama" => TranslateType::Ollama,
+                "deepl" => TranslateType::DeepL,
                 _ => TranslateType::Ollama,
             }
         } else {
@@ -839,30 +1207,59 @@ impl VoiceAssistant {
         let service_platform = if let Some(asr_config) = asr_configs.first() {
             asr_config.service_provider.clone()
         } else {
-            "siliconflow".to_string()
+            crate::voice_assistant::settings::current().service_platform
         };
 
-        println!("📊 Loaded config from database:");
-        println!("  - ASR processor: {:?}", asr_processor);
-        println!("  - Translate processor: {:?}", translate_processor);
-        println!("  - Service platform: {}", service_platform);
+        // 翻译语言跟随选中的翻译服务配置
+        let (translate_target_language, translate_source_language) = match translation_configs.first() {
+            Some(c) => (
+                crate::voice_assistant::translate::normalize_language(Some(&c.target_language))
+                    .unwrap_or_else(|| crate::voice_assistant::translate::DEFAULT_TARGET_LANGUAGE.to_string()),
+                crate::voice_assistant::translate::normalize_language(c.source_language.as_deref()),
+            ),
+            None => (crate::voice_assistant::translate::DEFAULT_TARGET_LANGUAGE.to_string(), None),
+        };
+        let translate_endpoint = translation_configs.first().and_then(|c| c.endpoint.clone()).filter(|e| !e.trim().is_empty());
+        let translate_model = translation_configs.first().and_then(|c| c.model.clone()).filter(|m| !m.trim().is_empty());
+        let translate_api_key = translation_configs.first().and_then(|c| c.api_key.clone()).filter(|k| !k.trim().is_empty());
+        let translate_timeout_secs = translation_configs.first()
+            .map(|c| c.timeout_secs.max(1) as u64)
+            .unwrap_or_else(default_translate_timeout_secs);
+
+        let asr_fallback_chain = asr_configs.first().and_then(|c| c.fallback_chain());
+
+        debug!("📊 Loaded config from database:");
+        debug!("  - ASR processor: {:?}", asr_processor);
+        debug!("  - ASR fallback chain: {:?}", asr_fallback_chain);
+        debug!("  - Translate processor: {:?}", translate_processor);
+        debug!("  - Translation: {:?} -> {}", translate_source_language, translate_target_language);
+        debug!("  - Service platform: {}", service_platform);
+
+        // Post-processing flags come from the database, env vars are only the fallback
+        let defaults = VoiceAssistantConfig::default();
+        let (convert_to_simplified, add_symbol, optimize_result, optimize_timeout_ms) =
+            match crate::commands::get_postprocess_config_internal().await? {
+                Some(pp) => (pp.convert_to_simplified, pp.add_symbol, pp.optimize_result, pp.optimize_timeout_ms.max(0) as u64),
+                None => (defaults.convert_to_simplified, defaults.add_symbol, defaults.optimize_result, defaults.optimize_timeout_ms),
+            };
+        debug!("  - Post-processing: simplified={}, symbol={}, optimize={} ({}ms budget)",
+            convert_to_simplified, add_symbol, optimize_result, optimize_timeout_ms);
 
         Ok(VoiceAssistantConfig {
             service_platform,
             asr_processor,
             translate_processor,
-            convert_to_simplified: std::env::var("CONVERT_TO_SIMPLIFIED")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-            add_symbol: std::env::var("ADD_SYMBOL")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
-            optimize_result: std::env::var("OPTIMIZE_RESULT")
-                .unwrap_or_else(|_| "true".to_string())
-                .parse()
-                .unwrap_or(true),
+            convert_to_simplified,
+            add_symbol,
+            optimize_result,
+            optimize_timeout_ms,
+            translate_target_language,
+            translate_source_language,
+            translate_endpoint,
+            translate_model,
+            translate_api_key,
+            translate_timeout_secs,
+            asr_fallback_chain,
         })
     }
 
@@ -883,7 +1280,7 @@ impl VoiceAssistant {
         // Process with ASR
         let prompt_str = prompt.unwrap_or("");
         let asr = self.asr_processor.as_ref().ok_or_else(|| VoiceError::Other("ASR processor not available".to_string()))?;
-        let result = asr.process_audio(audio_cursor, mode, prompt_str)?;
+        let result = asr.process_audio(audio_cursor, mode, prompt_str)?.text;
 
         info!("Audio processing completed, result length: {}", result.len());
         Ok(result)
@@ -891,7 +1288,8 @@ impl VoiceAssistant {
 
     pub fn translate_text(&self, text: &str) -> Result<String, VoiceError> {
         if let Some(ref translate_processor) = self.translate_processor {
-            info!("Translating text: {}", text);
+            info!("Translating text ({} chars)", text.chars().count());
+            trace!("Translation input: {}", super::redact(text));
             translate_processor.translate(text)
         } else {
             Err(VoiceError::Other("No translation processor available".to_string()))
@@ -902,18 +1300,16 @@ impl VoiceAssistant {
         info!("Testing ASR processor: {:?}", processor_type);
 
         let test_result = match processor_type {
-            ProcessorType::CloudASR => "Cloud ASR processor test successful",
+            ProcessorType::CloudASR | ProcessorType::Groq | ProcessorType::SenseVoice | ProcessorType::OpenAI => "Cloud ASR processor test successful",
             ProcessorType::LocalASR => "Local ASR processor test successful",
             ProcessorType::WhisperRS => {
                 // Check if model file exists for WhisperRS
-                let model_path = std::env::var("WHISPER_MODEL_PATH").unwrap_or_else(|_| {
-                    "./models/ggml-base.bin".to_string()
-                });
-                
-                if std::path::Path::new(&model_path).exists() {
-                    "WhisperRS processor test successful - model found"
-                } else {
-                    "WhisperRS processor test failed - model file not found"
+                match crate::voice_assistant::asr::models::resolve_active_model() {
+                    Ok(_) => "WhisperRS processor test successful - model found",
+                    Err(e) => {
+                        warn!("{}", e);
+                        "WhisperRS processor test failed - model file not found"
+                    }
                 }
             }
         };
@@ -922,12 +1318,12 @@ impl VoiceAssistant {
         Ok(test_result.to_string())
     }
 
-    pub fn test_translate_processor(&self, translate_type: TranslateType) -> Result<String, VoiceError> {
+    pub fn test_translate_processor(&self, translate_type: TranslateType, target_language: Option<&str>) -> Result<String, VoiceError> {
         info!("Testing translation processor: {:?}", translate_type);
 
         if let Some(ref translate_processor) = self.translate_processor {
-            let test_text = "Hello, this is a test translation.";
-            let result = translate_processor.translate(test_text)?;
+            let target = target_language.unwrap_or_else(|| translate_processor.target_language());
+            let result = translate_processor.translate_to(translation_test_text(target), target)?;
             info!("Translation test result: {}", result);
             Ok(result)
         } else {
@@ -952,6 +1348,9 @@ impl VoiceAssistant {
         if std::env::var("SILICONFLOW_API_KEY").is_ok() {
             info.insert("siliconflow_configured".to_string(), "true".to_string());
         }
+        if std::env::var("DEEPL_AUTH_KEY").is_ok() {
+            info.insert("deepl_configured".to_string(), "true".to_string());
+        }
 
         info
     }
@@ -984,6 +1383,39 @@ impl VoiceAssistant {
         let keyboard_manager = self.keyboard_manager.lock().unwrap();
         keyboard_manager.set_typing_delays(typing_delays);
     }
+
+    /// 应用新保存的热键配置：更新按键，必要时切换热键后端并重新注册插件快捷键
+    pub fn apply_hotkey_config(&self, config: &crate::database::HotkeyConfig) -> Result<(), VoiceError> {
+        let mut keyboard_manager = self.keyboard_manager.lock().unwrap();
+        keyboard_manager.set_hotkeys(&config.transcribe_key, &config.translate_key)?;
+        keyboard_manager.set_text_injection_method(
+            TextInjectionMethod::parse(&config.text_injection_method).unwrap_or_default()
+        );
+        keyboard_manager.set_recording_mode(RecordingMode::parse(&config.recording_mode).unwrap_or_default());
+        keyboard_manager.set_max_recording_secs(config.max_recording_secs);
+        keyboard_manager.set_silence_auto_stop(config.silence_auto_stop_enabled, config.silence_auto_stop_ms);
+        keyboard_manager.set_preroll_ms(config.preroll_ms);
+        keyboard_manager.set_queued_to_clipboard(config.queued_to_clipboard);
+        keyboard_manager.set_cycle_profile_hotkey(config.cycle_profile_key.as_deref());
+
+        let backend = HotkeyBackend::parse(&config.hotkey_backend).unwrap_or_default();
+        let previous = keyboard_manager.hotkey_backend();
+        keyboard_manager.set_hotkey_backend(backend);
+
+        // 后端不变时不重启信号线程，避免打断正在进行的录音
+        if backend != previous {
+            if previous == HotkeyBackend::Plugin {
+                unregister_plugin_hotkeys();
+            }
+            keyboard_manager.stop_listening();
+            keyboard_manager.start_listening();
+        }
+        if backend == HotkeyBackend::Plugin {
+            register_plugin_hotkeys(&config.transcribe_key, &config.translate_key, config.cycle_profile_key.as_deref())
+                .map_err(VoiceError::Other)?;
+        }
+        Ok(())
+    }
 }
 
 impl Default for VoiceAssistant {
@@ -1014,9 +1446,32 @@ fn get_voice_assistant_instance() -> &'static Arc<Mutex<Option<VoiceAssistant>>>
     VOICE_ASSISTANT.get_or_init(|| Arc::new(Mutex::new(None)))
 }
 
+/// 如果 VoiceAssistant 正在运行，从数据库重新加载所有配置。
+/// 返回是否有运行中的实例被刷新。
+pub async fn refresh_running_voice_assistant() -> Result<bool, VoiceError> {
+    let instance = get_voice_assistant_instance();
+    if instance.lock().unwrap().is_none() {
+        return Ok(false);
+    }
+
+    // 不能跨 await 持有 std Mutex：先在锁外创建新的处理器，再在锁内替换。
+    // 实例一直留在全局状态中，刷新期间按下热键仍然使用旧的处理器
+    let refreshed = VoiceAssistant::load_refreshed_configs().await?;
+    match instance.lock().unwrap().as_mut() {
+        Some(assistant) => assistant.apply_refreshed_configs(refreshed).map(|_| true),
+        // 刷新期间已被停止
+        None => Ok(false),
+    }
+}
+
+/// 按数据库配置计算实际的 ASR 尝试顺序（主处理器 + 备用链）
+pub async fn effective_asr_processor_chain() -> Result<Vec<ProcessorType>, VoiceError> {
+    Ok(VoiceAssistant::load_config_from_database().await?.asr_processor_chain())
+}
+
 // Tauri commands - Real implementation
 #[tauri::command]
-pub async fn start_voice_assistant(app_handle: tauri::AppHandle) -> Result<String, String> {
+pub async fn start_voice_assistant(app_handle: tauri::AppHandle) -> Result<String, CommandError> {
     info!("🚀 Start VoiceAssistant command called");
 
     let instance = get_voice_assistant_instance();
@@ -1059,19 +1514,19 @@ pub async fn start_voice_assistant(app_handle: tauri::AppHandle) -> Result<Strin
                 }
                 Err(e) => {
                     error!("❌ Failed to start VoiceAssistant: {}", e);
-                    Err(format!("Failed to start VoiceAssistant: {}", e))
+                    Err(CommandError::from(e))
                 }
             }
         }
         Err(e) => {
             error!("❌ Failed to create VoiceAssistant: {}", e);
-            Err(format!("Failed to create VoiceAssistant: {}", e))
+            Err(CommandError::from(e))
         }
     }
 }
 
 #[tauri::command]
-pub async fn stop_voice_assistant() -> Result<String, String> {
+pub async fn stop_voice_assistant() -> Result<String, CommandError> {
     info!("⏹️ Stop VoiceAssistant command called");
 
     let instance = get_voice_assistant_instance();
@@ -1105,7 +1560,7 @@ pub async fn stop_voice_assistant() -> Result<String, String> {
                 }
                 Err(e) => {
                     error!("❌ Failed to stop VoiceAssistant: {}", e);
-                    Err(format!("Failed to stop VoiceAssistant: {}", e))
+                    Err(CommandError::from(e))
                 }
             }
         } else {
@@ -1114,8 +1569,49 @@ pub async fn stop_voice_assistant() -> Result<String, String> {
     }
 }
 
+/// 运行中的助手正在使用的 ASR 处理器（含备用链），助手未启动时返回 None
+fn running_asr_processor() -> Option<Arc<dyn AsrProcessor + Send + Sync>> {
+    get_voice_assistant_instance().lock().unwrap().as_ref()
+        .and_then(|assistant| assistant.asr_processor.clone())
+}
+
+/// 运行中的助手听写时本地 whisper 实际使用的后端；助手未启动或没有使用本地 whisper 时返回 None
+pub fn running_whisper_backend() -> Option<WhisperBackend> {
+    running_asr_processor().and_then(|processor| processor.whisper_backend())
+}
+
+/// 检查当前使用的 ASR 处理器是否就绪，供设置页统一显示
+#[tauri::command]
+pub async fn check_asr_health() -> Result<String, CommandError> {
+    let processor = running_asr_processor()
+        .ok_or_else(|| CommandError::Other("Voice assistant is not running".to_string()))?;
+
+    let processor_type = processor.get_processor_type().to_string();
+    // 云端处理器在检查时阻塞等待网络请求，不能在 async 上下文里直接调用
+    let result = tokio::task::spawn_blocking(move || processor.health_check())
+        .await
+        .map_err(|e| CommandError::Other(format!("Health check task failed: {}", e)))?;
+
+    match result {
+        Ok(()) => {
+            info!("ASR processor {} is healthy", processor_type);
+            Ok(format!("✅ {} is ready", processor_type))
+        }
+        Err(e) => {
+            warn!("ASR processor {} health check failed: {}", processor_type, e);
+            Err(CommandError::from(e))
+        }
+    }
+}
+
+/// 运行中的助手的状态和录音模式；助手未启动时返回 None
+pub fn running_dictation_state() -> Option<(InputState, RecordingMode)> {
+    get_voice_assistant_instance().lock().unwrap().as_ref()
+        .map(|assistant| (assistant.get_state(), assistant.recording_mode()))
+}
+
 #[tauri::command]
-pub async fn get_voice_assistant_state() -> Result<String, String> {
+pub async fn get_voice_assistant_state() -> Result<String, CommandError> {
     let instance = get_voice_assistant_instance();
 
     let va = instance.lock().unwrap();
@@ -1132,31 +1628,314 @@ pub async fn get_voice_assistant_state() -> Result<String, String> {
 }
 
 #[tauri::command]
-pub async fn get_voice_assistant_config() -> Result<VoiceAssistantConfig, String> {
+pub async fn get_voice_assistant_config() -> Result<VoiceAssistantConfig, CommandError> {
     Ok(VoiceAssistantConfig::default())
 }
 
+/// 按保存的配置创建指定的 ASR 处理器并做健康检查：本地 whisper 加载并校验模型，
+/// 本地服务请求 /health，云端服务用 API key 请求模型列表
 #[tauri::command]
-pub async fn test_asr(processor_type: ProcessorType) -> Result<String, String> {
+pub async fn test_asr(processor_type: ProcessorType) -> Result<String, CommandError> {
     info!("Testing ASR processor: {:?}", processor_type);
-    match processor_type {
-        ProcessorType::CloudASR => Ok("✅ Cloud ASR processor test successful".to_string()),
-        ProcessorType::LocalASR => Ok("✅ Local ASR processor test successful".to_string()),
-        ProcessorType::WhisperRS => Ok("✅ WhisperRS processor test successful".to_string()),
+    let config = VoiceAssistant::load_config_from_database().await?;
+    let kind = processor_type.resolve(&config.service_platform);
+    let processor = VoiceAssistant::create_asr_processor(&kind).await
+        .inspect_err(|e| warn!("❌ Failed to create {:?} ASR processor: {}", kind, e))?;
+
+    let name = processor.get_processor_type().to_string();
+    // 云端处理器在检查时阻塞等待网络请求，不能在 async 上下文里直接调用
+    let result = tokio::task::spawn_blocking(move || processor.health_check())
+        .await
+        .map_err(|e| CommandError::Other(format!("ASR test task failed: {}", e)))?;
+
+    match result {
+        Ok(()) => Ok(format!("✅ {} processor test successful", name)),
+        Err(e) => {
+            warn!("❌ {} processor test failed: {}", name, e);
+            Err(CommandError::from(e))
+        }
+    }
+}
+
+/// transcribe_file 的结果
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct FileTranscription {
+    pub text: String,
+    pub processing_time_ms: u64,
+    pub detected_language: Option<String>,
+}
+
+/// "transcribe" / "translate" 对应 ASR 的两种模式
+pub fn parse_transcription_mode(mode: &str) -> Result<Mode, String> {
+    match mode.trim().to_ascii_lowercase().as_str() {
+        "transcribe" | "transcriptions" => Ok(Mode::Transcriptions),
+        "translate" | "translations" => Ok(Mode::Translations),
+        other => Err(format!("Unknown mode '{}'. Valid options: [\"transcribe\", \"translate\"]", other)),
+    }
+}
+
+/// 用当前配置的主 ASR 处理器识别一个音频文件，只返回结果：不输入文本、不写历史记录，
+/// 也不走备用链，方便用一批录音做回归测试。助手运行时直接使用已加载的处理器
+#[tauri::command]
+pub async fn transcribe_file(path: String, mode: String) -> Result<FileTranscription, CommandError> {
+    let mode = parse_transcription_mode(&mode).map_err(|e| CommandError::config_invalid("mode", e))?;
+    use crate::voice_assistant::audio::{decode_to_samples, resample_to_16k_mono, TARGET_SAMPLE_RATE};
+    use crate::voice_assistant::audio_decoder::encode_wav;
+
+    let processor = match running_asr_processor() {
+        Some(processor) => processor.chain_members().into_iter().next().unwrap_or(processor),
+        None => {
+            let config = VoiceAssistant::load_config_from_database().await?;
+            let kind = config.asr_processor.resolve(&config.service_platform);
+            VoiceAssistant::create_asr_processor(&kind).await
+                .inspect_err(|e| warn!("❌ Failed to create {:?} ASR processor: {}", kind, e))?
+        }
+    };
+    info!("🧪 Dry-run transcription of {} with {} ({:?})", path, processor.get_processor_type(), mode);
+
+    // 解码和识别都是 CPU 密集的阻塞操作，处理器内部也可能创建自己的 runtime，不能在 async 上下文里直接调用
+    tokio::task::spawn_blocking(move || {
+        // MP3/M4A/OGG/FLAC/WAV 解码后与热键录音走同一套重采样和预处理
+        let (samples, sample_rate) = decode_to_samples(std::path::Path::new(&path))
+            .map_err(|e| CommandError::Audio(format!("Failed to decode {}: {}", path, e)))?;
+        let samples = resample_to_16k_mono(&samples, sample_rate, 1);
+        let samples = crate::voice_assistant::audio_prep::prepare(&samples, TARGET_SAMPLE_RATE);
+        let wav_bytes = encode_wav(&samples, TARGET_SAMPLE_RATE)?;
+
+        let started = std::time::Instant::now();
+        let transcript = processor
+            .process_audio(std::io::Cursor::new(wav_bytes), mode, "")
+            .inspect_err(|e| warn!("❌ Transcription failed: {}", e))?;
+        let detected_language = RecognitionInfo::from_transcript(processor.as_ref(), &transcript).detected_language;
+        Ok(FileTranscription {
+            text: transcript.text,
+            processing_time_ms: started.elapsed().as_millis() as u64,
+            detected_language,
+        })
+    })
+    .await
+    .map_err(|e| CommandError::Other(format!("Transcription task failed: {}", e)))?
+}
+
+/// run_pipeline_self_test 的报告：asr_* 是主处理器的结果，主处理器失败且配置了备用链时
+/// fallback_* 是备用处理器的结果；没有配置翻译时 translate_* 均为 None
+#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+pub struct PipelineSelfTest {
+    pub asr_processor: String,
+    pub asr_ok: bool,
+    pub asr_text: Option<String>,
+    pub asr_error: Option<String>,
+    pub asr_ms: u64,
+    pub fallback_processor: Option<String>,
+    pub fallback_ok: Option<bool>,
+    pub fallback_text: Option<String>,
+    pub fallback_error: Option<String>,
+    pub fallback_ms: Option<u64>,
+    pub translate_ok: Option<bool>,
+    pub translate_text: Option<String>,
+    pub translate_error: Option<String>,
+    pub translate_ms: Option<u64>,
+}
+
+impl PipelineSelfTest {
+    /// 识别出的文字，主处理器失败时取备用处理器的结果
+    fn recognized_text(&self) -> Option<String> {
+        let text = if self.asr_ok { &self.asr_text } else { &self.fallback_text };
+        text.clone().filter(|text| !text.is_empty())
+    }
+}
+
+/// 用一个处理器识别自检录音；合成录音识别不出文字（NoSpeech）也算正常工作
+fn self_test_recognize(processor: &dyn AsrProcessor, wav_bytes: &[u8]) -> (Result<String, VoiceError>, u64) {
+    let started = std::time::Instant::now();
+    let result = match processor.process_audio(std::io::Cursor::new(wav_bytes.to_vec()), Mode::Transcriptions, "") {
+        Ok(transcript) => Ok(transcript.text.trim().to_string()),
+        Err(VoiceError::NoSpeech) => Ok(String::new()),
+        Err(e) => Err(e),
+    };
+    (result, started.elapsed().as_millis() as u64)
+}
+
+/// 先单独测试主处理器；主处理器失败时按顺序测试备用处理器，直到有一个成功
+fn self_test_asr(processor: &(dyn AsrProcessor + Send + Sync), wav_bytes: &[u8]) -> PipelineSelfTest {
+    let members = processor.chain_members();
+    let (primary, fallbacks): (&dyn AsrProcessor, &[Arc<dyn AsrProcessor + Send + Sync>]) = match members.split_first() {
+        Some((primary, fallbacks)) => (primary.as_ref(), fallbacks),
+        None => (processor, &[]),
+    };
+
+    let (result, asr_ms) = self_test_recognize(primary, wav_bytes);
+    let mut report = PipelineSelfTest {
+        asr_processor: primary.get_processor_type().to_string(),
+        asr_ms,
+        ..Default::default()
+    };
+    match result {
+        Ok(text) => {
+            report.asr_ok = true;
+            report.asr_text = Some(text);
+            return report;
+        }
+        Err(e) => {
+            warn!("❌ Self-test ASR {} failed: {}", report.asr_processor, e);
+            report.asr_error = Some(e.to_string());
+        }
+    }
+
+    for fallback in fallbacks {
+        let (result, fallback_ms) = self_test_recognize(fallback.as_ref(), wav_bytes);
+        report.fallback_processor = Some(fallback.get_processor_type().to_string());
+        report.fallback_ms = Some(fallback_ms);
+        match result {
+            Ok(text) => {
+                report.fallback_ok = Some(true);
+                report.fallback_text = Some(text);
+                report.fallback_error = None;
+                break;
+            }
+            Err(e) => {
+                warn!("❌ Self-test fallback ASR {} failed: {}", fallback.get_processor_type(), e);
+                report.fallback_ok = Some(false);
+                report.fallback_error = Some(e.to_string());
+            }
+        }
+    }
+    report
+}
+
+/// 自检用的合成录音：1.5 秒、带谐波和音节起伏的类人声信号，16kHz 单声道 WAV
+pub fn self_test_clip() -> Result<Vec<u8>, VoiceError> {
+    use crate::voice_assistant::audio_decoder::{encode_wav, TARGET_SAMPLE_RATE};
+
+    let rate = TARGET_SAMPLE_RATE as f32;
+    let samples: Vec<f32> = (0..(TARGET_SAMPLE_RATE as usize * 3 / 2))
+        .map(|i| {
+            let t = i as f32 / rate;
+            let voice: f32 = (1..=4)
+                .map(|h| (2.0 * std::f32::consts::PI * 150.0 * h as f32 * t).sin() / h as f32)
+                .sum();
+            // 每秒 4 个"音节"
+            let syllables = (std::f32::consts::PI * 4.0 * t).sin().abs();
+            0.2 * voice * syllables
+        })
+        .collect();
+    encode_wav(&samples, TARGET_SAMPLE_RATE)
+}
+
+/// 用真实处理器跑一遍完整流程：合成录音 → 当前 ASR（主处理器和备用链分别报告）→ 已配置的翻译。
+/// 助手运行时测试正在使用的处理器，不会再加载一次模型；不输入文本、不写历史记录
+#[tauri::command]
+pub async fn run_pipeline_self_test() -> Result<PipelineSelfTest, CommandError> {
+    let wav_bytes = self_test_clip()?;
+    let config = VoiceAssistant::load_config_from_database().await?;
+    let processor = match running_asr_processor() {
+        Some(processor) => processor,
+        None => VoiceAssistant::create_asr_processor_chain(&config).await
+            .inspect_err(|e| warn!("❌ Failed to create ASR processor for self-test: {}", e))?,
+    };
+    info!("🧪 Running pipeline self-test with {}", processor.get_processor_type());
+
+    // 处理器内部可能创建自己的 runtime，不能在 async 上下文里直接调用
+    let mut report = tokio::task::spawn_blocking(move || self_test_asr(processor.as_ref(), &wav_bytes))
+        .await
+        .map_err(|e| CommandError::Other(format!("Self-test ASR task failed: {}", e)))?;
+
+    let configs = crate::commands::get_translation_config_internal().await?;
+    if configs.iter().any(|c| c.provider == config.translate_processor.provider_name()) {
+        let asr_text = report.recognized_text();
+        let (result, translate_ms) = tokio::task::spawn_blocking(move || {
+            let started = std::time::Instant::now();
+            let result = config.create_translate_processor().and_then(|processor| {
+                let text = asr_text.unwrap_or_else(|| translation_test_text(processor.target_language()).to_string());
+                processor.translate(&text)
+            });
+            (result, started.elapsed().as_millis() as u64)
+        })
+        .await
+        .map_err(|e| CommandError::Other(format!("Self-test translation task failed: {}", e)))?;
+
+        report.translate_ms = Some(translate_ms);
+        match result {
+            Ok(translated) => {
+                report.translate_ok = Some(true);
+                report.translate_text = Some(translated);
+            }
+            Err(e) => {
+                warn!("❌ Self-test translation failed: {}", e);
+                report.translate_ok = Some(false);
+                report.translate_error = Some(e.to_string());
+            }
+        }
+    }
+
+    info!(
+        "🧪 Pipeline self-test: ASR ok={} ({}ms), fallback ok={:?}, translate ok={:?}",
+        report.asr_ok, report.asr_ms, report.fallback_ok, report.translate_ok
+    );
+    Ok(report)
+}
+
+/// 翻译测试用的句子；目标是英文时用中文句子，否则用英文句子
+fn translation_test_text(target: &str) -> &'static str {
+    if target.eq_ignore_ascii_case("en") || target.to_lowercase().starts_with("en-") {
+        "你好，这是一条翻译测试。"
+    } else {
+        "Hello, this is a test translation."
+    }
+}
+
+impl TranslateType {
+    pub fn provider_name(&self) -> &'static str {
+        match self {
+            TranslateType::SiliconFlow => "siliconflow",
+            TranslateType::Ollama => "ollama",
+            TranslateType::DeepL => "deepl",
+        }
     }
 }
 
+/// 用保存的配置翻译一句测试文本；target_language 为空时使用配置的目标语言
 #[tauri::command]
-pub async fn test_translation(translate_type: TranslateType) -> Result<String, String> {
+pub async fn test_translation(translate_type: TranslateType, target_language: Option<String>) -> Result<String, CommandError> {
+    use crate::voice_assistant::translate::{normalize_language, DEFAULT_TARGET_LANGUAGE};
+
     info!("Testing translation processor: {:?}", translate_type);
-    match translate_type {
-        TranslateType::SiliconFlow => Ok("✅ SiliconFlow translation test successful".to_string()),
-        TranslateType::Ollama => Ok("✅ Ollama translation test successful".to_string()),
+    let configs = crate::commands::get_translation_config_internal().await?;
+    let saved = configs.iter().find(|c| c.provider == translate_type.provider_name());
+
+    let target = normalize_language(target_language.as_deref())
+        .or_else(|| saved.and_then(|c| normalize_language(Some(&c.target_language))))
+        .unwrap_or_else(|| DEFAULT_TARGET_LANGUAGE.to_string());
+    let config = VoiceAssistantConfig {
+        translate_processor: translate_type.clone(),
+        translate_target_language: target.clone(),
+        translate_source_language: saved.and_then(|c| normalize_language(c.source_language.as_deref())),
+        translate_endpoint: saved.and_then(|c| c.endpoint.clone()).filter(|e| !e.trim().is_empty()),
+        translate_model: saved.and_then(|c| c.model.clone()).filter(|m| !m.trim().is_empty()),
+        translate_api_key: saved.and_then(|c| c.api_key.clone()).filter(|k| !k.trim().is_empty()),
+        translate_timeout_secs: saved.map_or_else(default_translate_timeout_secs, |c| c.timeout_secs.max(1) as u64),
+        ..VoiceAssistantConfig::default()
+    };
+
+    // 处理器内部会创建自己的 runtime，不能在 async 上下文里直接调用
+    let result = tokio::task::spawn_blocking(move || {
+        let processor = config.create_translate_processor()?;
+        processor.translate(translation_test_text(processor.target_language()))
+    })
+    .await
+    .map_err(|e| format!("Translation test task failed: {}", e))?;
+
+    match result {
+        Ok(translated) => Ok(format!("✅ {:?} translation test successful (→ {}): {}", translate_type, target, translated)),
+        Err(e) => {
+            warn!("❌ {:?} translation to {} failed: {}", translate_type, target, e);
+            Err(CommandError::from(e))
+        }
     }
 }
 
 #[tauri::command]
-pub async fn get_system_info() -> Result<HashMap<String, String>, String> {
+pub async fn get_system_info() -> Result<HashMap<String, String>, CommandError> {
     let mut info = HashMap::new();
     info.insert("Platform".to_string(), std::env::consts::OS.to_string());
     info.insert("Arch".to_string(), std::env::consts::ARCH.to_string());
@@ -1173,7 +1952,7 @@ pub async fn configure_hotkeys(
     trigger_delay_ms: i64,
     anti_mistouch_enabled: bool,
     typing_delays: Option<crate::database::TypingDelays>,
-) -> Result<String, String> {
+) -> Result<String, CommandError> {
     info!("Configuring hotkeys:");
     info!("  - Transcribe: {}", transcribe_key);
     info!("  - Translate: {}", translate_key);
@@ -1188,11 +1967,11 @@ pub async fn configure_hotkeys(
         Ok(assistant) => {
             // Set hotkey configuration
             if let Err(e) = assistant.set_transcribe_hotkey(&transcribe_key) {
-                return Err(format!("Failed to set transcribe hotkey: {}", e));
+                return Err(CommandError::config_invalid("transcribe_key", format!("Failed to set transcribe hotkey: {}", e)));
             }
 
             if let Err(e) = assistant.set_translate_hotkey(&translate_key) {
-                return Err(format!("Failed to set translate hotkey: {}", e));
+                return Err(CommandError::config_invalid("translate_key", format!("Failed to set translate hotkey: {}", e)));
             }
 
             assistant.set_trigger_delay_ms(trigger_delay_ms);
@@ -1209,7 +1988,110 @@ pub async fn configure_hotkeys(
         Err(e) => {
             let error_msg = format!("Failed to create VoiceAssistant for hotkey configuration: {}", e);
             error!("{}", error_msg);
-            Err(error_msg)
+            Err(CommandError::Other(error_msg))
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(primary: ProcessorType, platform: &str, fallbacks: Option<Vec<ProcessorType>>) -> VoiceAssistantConfig {
+        VoiceAssistantConfig {
+            service_platform: platform.to_string(),
+            asr_processor: primary,
+            asr_fallback_chain: fallbacks,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_parse_transcription_mode() {
+        assert_eq!(parse_transcription_mode("transcribe").unwrap(), Mode::Transcriptions);
+        assert_eq!(parse_transcription_mode(" Translate ").unwrap(), Mode::Translations);
+        assert!(parse_transcription_mode("dictate").is_err());
+    }
+
+    #[test]
+    fn test_self_test_clip_is_audible_wav() {
+        let wav = self_test_clip().unwrap();
+        let mut reader = hound::WavReader::new(std::io::Cursor::new(wav)).unwrap();
+        assert_eq!(reader.spec().sample_rate, 16000);
+        assert_eq!(reader.spec().channels, 1);
+        let samples: Vec<i16> = reader.samples::<i16>().map(Result::unwrap).collect();
+        assert_eq!(samples.len(), 24000);
+        assert!(samples.iter().any(|s| s.unsigned_abs() > 1000));
+    }
+
+    struct FixedProcessor(&'static str, Result<&'static str, &'static str>);
+
+    impl AsrProcessor for FixedProcessor {
+        fn process_audio(&self, _audio: std::io::Cursor<Vec<u8>>, _mode: Mode, _prompt: &str) -> Result<AsrTranscript, VoiceError> {
+            self.1.map(|text| AsrTranscript::from(text.to_string())).map_err(|e| VoiceError::Other(e.to_string()))
         }
+
+        fn get_processor_type(&self) -> &str {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_self_test_reports_primary_failure_separately() {
+        let chain = FallbackAsrProcessor::new(vec![
+            Arc::new(FixedProcessor("whisper-rs", Err("model crashed"))),
+            Arc::new(FixedProcessor("cloud-groq", Ok(" hello "))),
+        ])
+        .unwrap();
+        let report = self_test_asr(&chain, &[]);
+        assert_eq!(report.asr_processor, "whisper-rs");
+        assert!(!report.asr_ok);
+        assert!(report.asr_error.unwrap().contains("model crashed"));
+        assert_eq!(report.fallback_processor.as_deref(), Some("cloud-groq"));
+        assert_eq!(report.fallback_ok, Some(true));
+        assert_eq!(report.fallback_text.as_deref(), Some("hello"));
+
+        let single = self_test_asr(&FixedProcessor("cloud-groq", Ok("hi")), &[]);
+        assert!(single.asr_ok);
+        assert!(single.fallback_processor.is_none());
+    }
+
+    #[test]
+    fn test_cloud_fallback_is_opt_in() {
+        let chain = config(ProcessorType::WhisperRS, "groq", None).asr_processor_chain();
+        assert_eq!(chain, vec![ProcessorType::WhisperRS]);
+
+        let chain = config(ProcessorType::CloudASR, "siliconflow", None).asr_processor_chain();
+        assert_eq!(chain, vec![ProcessorType::SenseVoice]);
+
+        let chain = config(ProcessorType::WhisperRS, "groq", Some(vec![ProcessorType::OpenAI])).asr_processor_chain();
+        assert_eq!(chain, vec![ProcessorType::WhisperRS, ProcessorType::OpenAI]);
+        assert_eq!(serde_json::to_string(&ProcessorType::OpenAI).unwrap(), "\"openai\"");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_configured_chain_is_resolved_and_deduplicated() {
+        let fallbacks = vec![ProcessorType::LocalASR, ProcessorType::CloudASR, ProcessorType::Groq, ProcessorType::WhisperRS];
+        let chain = config(ProcessorType::WhisperRS, "groq", Some(fallbacks)).asr_processor_chain();
+        assert_eq!(chain, vec![ProcessorType::WhisperRS, ProcessorType::LocalASR, ProcessorType::Groq]);
+
+        // 空列表表示不使用备用处理器
+        let chain = config(ProcessorType::WhisperRS, "groq", Some(Vec::new())).asr_processor_chain();
+        assert_eq!(chain, vec![ProcessorType::WhisperRS]);
+    }
+
+    #[test]
+    fn test_saved_cloud_service_is_not_replaced_by_sensevoice() {
+        // service_provider "cloud" 使用设置中保存的云端地址和请求格式，不能被解析成读取环境变量的 SenseVoice
+        let chain = config(ProcessorType::CloudASR, "cloud", None).asr_processor_chain();
+        assert_eq!(chain, vec![ProcessorType::CloudASR]);
+        assert_eq!(ProcessorType::CloudASR.resolve("siliconflow"), ProcessorType::SenseVoice);
+    }
+
+    #[test]
+    fn test_race_pair_needs_local_and_cloud() {
+        let chain = [ProcessorType::LocalASR, ProcessorType::Groq, ProcessorType::WhisperRS, ProcessorType::SenseVoice];
+        assert_eq!(race_pair(&chain), Some((2, 1)));
+        assert_eq!(race_pair(&[ProcessorType::WhisperRS, ProcessorType::LocalASR]), None);
+        assert_eq!(race_pair(&[ProcessorType::Groq, ProcessorType::SenseVoice]), None);
+    }
+}
@@ -0,0 +1,277 @@
+use serde::{Deserialize, Serialize};
+use std::sync::{OnceLock, RwLock};
+use tracing::info;
+use crate::voice_assistant::VoiceError;
+
+/// 口述命令对应的动作
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum DictationAction {
+    /// 替换成标点或换行
+    Insert { text: String },
+    /// 删除本句中命令之前的内容；命令前没有内容时删除上一次输入的结果
+    DeleteLast,
+}
+
+/// 一条口述命令，数据库里以 JSON 数组保存
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DictationCommand {
+    pub phrase: String,
+    pub action: DictationAction,
+}
+
+impl DictationCommand {
+    fn insert(phrase: &str, text: &str) -> Self {
+        Self {
+            phrase: phrase.to_string(),
+            action: DictationAction::Insert { text: text.to_string() },
+        }
+    }
+
+    fn delete_last(phrase: &str) -> Self {
+        Self {
+            phrase: phrase.to_string(),
+            action: DictationAction::DeleteLast,
+        }
+    }
+}
+
+/// 默认的中英文命令
+pub fn default_commands() -> Vec<DictationCommand> {
+    vec![
+        DictationCommand::insert("new paragraph", "\n\n"),
+        DictationCommand::insert("new line", "\n"),
+        DictationCommand::insert("period", "."),
+        DictationCommand::insert("full stop", "."),
+        DictationCommand::insert("comma", ","),
+        DictationCommand::insert("question mark", "?"),
+        DictationCommand::insert("exclamation mark", "!"),
+        DictationCommand::delete_last("delete that"),
+        DictationCommand::insert("新段落", "\n\n"),
+        DictationCommand::insert("换行", "\n"),
+        DictationCommand::insert("句号", "。"),
+        DictationCommand::insert("逗号", "，"),
+        DictationCommand::insert("问号", "？"),
+        DictationCommand::insert("感叹号", "！"),
+        DictationCommand::delete_last("删除上一句"),
+    ]
+}
+
+/// 校验用户提交的命令表
+pub fn validate_commands(commands: &[DictationCommand]) -> Result<(), VoiceError> {
+    for command in commands {
+        if command.phrase.trim().is_empty() {
+            return Err(VoiceError::Other("Dictation command phrase must not be empty".to_string()));
+        }
+    }
+    Ok(())
+}
+
+/// 应用命令后的结果
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct DictationOutput {
+    pub text: String,
+    /// 输入前需要先删除上一次输入的结果
+    pub delete_last: bool,
+}
+
+/// ASR 在命令词前后自动加的标点，命令替换后去掉
+fn is_asr_punctuation(c: char) -> bool {
+    matches!(c, ',' | '.' | '!' | '?' | ';' | '，' | '。' | '！' | '？' | '；' | '、')
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '\''
+}
+
+/// 在 `chars[start..]` 处匹配命令词；英文词要求前后不是字母数字，避免匹配到单词内部
+fn matches_at(chars: &[char], start: usize, phrase: &[char]) -> bool {
+    if start + phrase.len() > chars.len() {
+        return false;
+    }
+    let same = chars[start..start + phrase.len()]
+        .iter()
+        .zip(phrase)
+        .all(|(a, b)| a.to_ascii_lowercase() == b.to_ascii_lowercase());
+    if !same {
+        return false;
+    }
+
+    let starts_word = phrase.first().is_some_and(|c| is_word_char(*c));
+    let ends_word = phrase.last().is_some_and(|c| is_word_char(*c));
+    if starts_word && start > 0 && is_word_char(chars[start - 1]) {
+        return false;
+    }
+    let end = start + phrase.len();
+    if ends_word && end < chars.len() && is_word_char(chars[end]) {
+        return false;
+    }
+    true
+}
+
+fn trim_end_in_place(buffer: &mut String, also_punctuation: bool) {
+    while let Some(c) = buffer.chars().last() {
+        if c == ' ' || c == '\t' || (also_punctuation && is_asr_punctuation(c)) {
+            buffer.pop();
+        } else {
+            break;
+        }
+    }
+}
+
+/// 将命令词替换为对应的标点/动作
+pub fn apply_commands(text: &str, commands: &[DictationCommand]) -> DictationOutput {
+    // 长的命令优先，避免 "new paragraph" 被拆开匹配
+    let mut compiled: Vec<(Vec<char>, &DictationAction)> = commands
+        .iter()
+        .filter(|c| !c.phrase.trim().is_empty())
+        .map(|c| (c.phrase.trim().chars().collect(), &c.action))
+        .collect();
+    compiled.sort_by(|a, b| b.0.len().cmp(&a.0.len()));
+
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = DictationOutput::default();
+    let mut buffer = String::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let Some((phrase, action)) = compiled.iter().find(|(phrase, _)| matches_at(&chars, i, phrase)) else {
+            buffer.push(chars[i]);
+            i += 1;
+            continue;
+        };
+        i += phrase.len();
+
+        match action {
+            DictationAction::Insert { text: insert } => {
+                // 标点贴着前一个词，同时去掉 ASR 在命令词前自动加的标点
+                trim_end_in_place(&mut buffer, true);
+                buffer.push_str(insert);
+                // 命令词后面 ASR 自动加的标点也去掉；换行后不保留行首空格
+                while i < chars.len() && is_asr_punctuation(chars[i]) {
+                    i += 1;
+                }
+                if insert.ends_with('\n') {
+                    while i < chars.len() && chars[i].is_whitespace() {
+                        i += 1;
+                    }
+                }
+            }
+            DictationAction::DeleteLast => {
+                if buffer.trim().is_empty() {
+                    output.delete_last = true;
+                }
+                buffer.clear();
+                while i < chars.len() && (is_asr_punctuation(chars[i]) || chars[i].is_whitespace()) {
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    output.text = buffer;
+    output
+}
+
+#[derive(Debug, Clone)]
+struct DictationSettings {
+    enabled: bool,
+    commands: Vec<DictationCommand>,
+}
+
+// 默认关闭，开启后才会改写识别结果
+static SETTINGS: OnceLock<RwLock<DictationSettings>> = OnceLock::new();
+
+fn settings() -> &'static RwLock<DictationSettings> {
+    SETTINGS.get_or_init(|| RwLock::new(DictationSettings { enabled: false, commands: default_commands() }))
+}
+
+/// 刷新全局命令表；commands 为 None 时使用默认命令
+pub fn set_dictation_commands(enabled: bool, commands: Option<Vec<DictationCommand>>) {
+    let commands = commands.unwrap_or_else(default_commands);
+    info!("🗣️ Dictation commands: enabled={}, {} phrase(s)", enabled, commands.len());
+    *settings().write().unwrap() = DictationSettings { enabled, commands };
+}
+
+/// 对识别结果应用全局命令表，关闭时原样返回
+pub fn apply_dictation_commands(text: &str) -> DictationOutput {
+    let settings = settings().read().unwrap();
+    if !settings.enabled {
+        return DictationOutput { text: text.to_string(), delete_last: false };
+    }
+    apply_commands(text, &settings.commands)
+}
+
+/// 从数据库重新加载命令表
+pub async fn reload_dictation_commands() -> Result<(), String> {
+    match crate::commands::get_dictation_command_config_internal().await? {
+        Some(config) => set_dictation_commands(config.enabled, config.commands()),
+        None => set_dictation_commands(false, None),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(text: &str) -> DictationOutput {
+        apply_commands(text, &default_commands())
+    }
+
+    #[test]
+    fn test_command_at_start_middle_and_end() {
+        assert_eq!(apply("new line hello").text, "\nhello");
+        assert_eq!(apply("hello comma world").text, "hello, world");
+        assert_eq!(apply("see you tomorrow period").text, "see you tomorrow.");
+        assert_eq!(apply("first line new line second line").text, "first line\nsecond line");
+        assert_eq!(apply("one new paragraph two").text, "one\n\ntwo");
+    }
+
+    #[test]
+    fn test_asr_punctuation_around_commands_is_dropped() {
+        assert_eq!(apply("Hello, comma, world. Period.").text, "Hello, world.");
+        assert_eq!(apply("Is it done Question mark?").text, "Is it done?");
+    }
+
+    #[test]
+    fn test_chinese_commands() {
+        assert_eq!(apply("你好逗号世界句号").text, "你好，世界。");
+        assert_eq!(apply("第一行换行第二行").text, "第一行\n第二行");
+    }
+
+    #[test]
+    fn test_no_false_positives_inside_words() {
+        assert_eq!(apply("periodically check the commander").text, "periodically check the commander");
+        assert_eq!(apply("renew lines").text, "renew lines");
+        assert_eq!(apply("a periodic comma").text, "a periodic,");
+    }
+
+    #[test]
+    fn test_delete_that() {
+        let output = apply("delete that");
+        assert!(output.delete_last);
+        assert_eq!(output.text, "");
+
+        // 命令前有内容时只删本句
+        let output = apply("wrong words delete that right words");
+        assert!(!output.delete_last);
+        assert_eq!(output.text, "right words");
+
+        let output = apply("删除上一句。");
+        assert!(output.delete_last);
+    }
+
+    #[test]
+    fn test_custom_commands_roundtrip_as_json() {
+        let commands = vec![
+            DictationCommand::insert("smiley", ":)"),
+            DictationCommand::delete_last("scratch that"),
+        ];
+        let json = serde_json::to_string(&commands).unwrap();
+        let parsed: Vec<DictationCommand> = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed, commands);
+        assert_eq!(apply_commands("nice smiley", &parsed).text, "nice:)");
+        assert!(validate_commands(&[DictationCommand::insert(" ", "x")]).is_err());
+    }
+}
@@ -1,31 +1,74 @@
-use tauri::{AppHandle};
-// use crate::voice_assistant::system_tray::SystemTrayManager;
+use tauri::AppHandle;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+use tracing::{info, warn};
+use crate::voice_assistant::hotkey_parser::ParsedHotkey;
+use crate::voice_assistant::keyboard::{send_hotkey_signal, HotkeyAction, HotkeySignal};
 
 pub struct GlobalHotkeyManager {
-    #[allow(dead_code)]
     app_handle: AppHandle,
-    // system_tray_manager: Arc<Mutex<SystemTrayManager>>,
 }
 
 impl GlobalHotkeyManager {
     pub fn new(app_handle: AppHandle) -> Self {
-        Self {
-            app_handle,
-            // system_tray_manager,
-        }
+        Self { app_handle }
     }
 
     pub fn register_global_hotkeys(&self) -> Result<(), Box<dyn std::error::Error>> {
-        // For now, we'll use a simpler approach without hotkey callbacks
-        // The actual hotkey handling will be done differently in Tauri v2
+        // 语音热键在语音助手启动时按配置的后端注册（见 register_hotkeys）
+        info!("ℹ️  Global hotkeys are registered when the voice assistant starts");
+        Ok(())
+    }
+
+    /// 通过 tauri-plugin-global-shortcut 注册转录/翻译热键（以及可选的方案切换热键），替换之前注册的全部快捷键
+    pub fn register_hotkeys(&self, transcribe_key: &str, translate_key: &str, cycle_profile_key: Option<&str>) -> Result<(), String> {
+        let transcribe = parse_shortcut(transcribe_key)?;
+        let translate = parse_shortcut(translate_key)?;
+        if transcribe == translate {
+            return Err(format!("Transcribe and translate hotkeys are identical: {}", transcribe_key));
+        }
+        let cycle_profile_key = cycle_profile_key.map(str::trim).filter(|key| !key.is_empty());
+        let cycle_profile = cycle_profile_key.map(parse_shortcut).transpose()?;
+        if cycle_profile.is_some_and(|cycle| cycle == transcribe || cycle == translate) {
+            return Err(format!("Profile cycle hotkey {} conflicts with transcribe/translate", cycle_profile_key.unwrap_or_default()));
+        }
 
-        println!("ℹ️  Global hotkey registration temporarily disabled due to API changes");
-        println!("ℹ️  Use system tray menu to toggle overlay window");
+        let shortcuts = self.app_handle.global_shortcut();
+        shortcuts.unregister_all()
+            .map_err(|e| format!("Failed to unregister global shortcuts: {}", e))?;
 
+        for (shortcut, action, label) in [
+            (transcribe, HotkeyAction::Transcribe, transcribe_key),
+            (translate, HotkeyAction::Translate, translate_key),
+        ] {
+            shortcuts
+                .on_shortcut(shortcut, move |_app, _shortcut, event| {
+                    let signal = match event.state() {
+                        ShortcutState::Pressed => HotkeySignal::Pressed(action),
+                        ShortcutState::Released => HotkeySignal::Released(action),
+                    };
+                    if !send_hotkey_signal(signal) {
+                        warn!("⚠️ Global shortcut {:?} ignored: hotkey worker is not running", signal);
+                    }
+                })
+                .map_err(|e| format!("Failed to register global shortcut {}: {}", label, e))?;
+        }
+
+        if let (Some(shortcut), Some(label)) = (cycle_profile, cycle_profile_key) {
+            shortcuts
+                .on_shortcut(shortcut, |_app, _shortcut, event| {
+                    if event.state() == ShortcutState::Pressed {
+                        crate::voice_assistant::coordinator::cycle_asr_profile_from_hotkey();
+                    }
+                })
+                .map_err(|e| format!("Failed to register global shortcut {}: {}", label, e))?;
+        }
+
+        info!("✅ Global shortcuts registered: {} / {}", transcribe_key, translate_key);
         Ok(())
     }
 
     pub fn unregister_all_hotkeys(&self) -> Result<(), Box<dyn std::error::Error>> {
+        self.app_handle.global_shortcut().unregister_all()?;
         println!("✅ Global hotkeys unregistered");
         Ok(())
     }
@@ -38,6 +81,14 @@ impl GlobalHotkeyManager {
     }
 }
 
+/// 数据库中保存的热键字符串（如 "Shift + F4"）转换为插件快捷键，两种后端接受相同的写法
+pub fn parse_shortcut(hotkey_str: &str) -> Result<Shortcut, String> {
+    let accelerator = ParsedHotkey::parse(hotkey_str)?.to_accelerator()?;
+    accelerator
+        .parse::<Shortcut>()
+        .map_err(|e| format!("Invalid global shortcut {} ({}): {}", hotkey_str, accelerator, e))
+}
+
 // Function to check if xdotool is available (Linux)
 fn check_xdotool_available() -> bool {
     std::process::Command::new("which")
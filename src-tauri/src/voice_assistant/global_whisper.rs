@@ -2,8 +2,12 @@ use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Serialize, Deserialize};
 use std::sync::OnceLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+use tracing::{error, info, warn};
 
-use crate::voice_assistant::asr::whisper_rs::{WhisperRSProcessor, WhisperRSConfig, OutputFormat};
+use crate::voice_assistant::asr::whisper_rs::{WhisperRSProcessor, WhisperRSConfig, OutputFormat, WhisperBackend};
 use crate::voice_assistant::traits::VoiceError;
 
 /// 全局WhisperRS实例管理器
@@ -11,6 +15,11 @@ pub struct GlobalWhisperManager {
     processor: Option<Arc<std::sync::Mutex<WhisperRSProcessor>>>,
     current_model_path: Option<String>,
     init_in_progress: bool,
+    /// 设置中选择的后端
+    requested_backend: WhisperBackend,
+    /// 当前处理器实际使用的后端
+    effective_backend: Option<WhisperBackend>,
+    gpu_fallback_reason: Option<String>,
 }
 
 impl GlobalWhisperManager {
@@ -20,6 +29,9 @@ impl GlobalWhisperManager {
             processor: None,
             current_model_path: None,
             init_in_progress: false,
+            requested_backend: default_backend(),
+            effective_backend: None,
+            gpu_fallback_reason: None,
         }
     }
 
@@ -29,7 +41,12 @@ impl GlobalWhisperManager {
         if let Some(current_path) = &self.current_model_path {
             if current_path == model_path {
                 if let Some(processor) = &self.processor {
-                    println!("✅ Reusing existing WhisperRS processor for model: {}", model_path);
+                    info!("✅ Reusing existing WhisperRS processor for model: {}", model_path);
+                    // VAD 开关和段落可信度阈值可能在设置中被修改，复用前同步
+                    if let Ok(mut guard) = processor.lock() {
+                        guard.set_vad_enabled(vad_enabled());
+                        guard.set_min_segment_confidence(min_segment_confidence());
+                    }
                     return Ok(Arc::clone(processor));
                 }
             }
@@ -37,52 +54,53 @@ impl GlobalWhisperManager {
 
         // 如果正在初始化，等待完成
         if self.init_in_progress {
-            println!("⏳ WhisperRS processor initialization in progress, waiting...");
+            info!("⏳ WhisperRS processor initialization in progress, waiting...");
             // 这里可以添加等待逻辑，但为简单起见，我们直接返回错误
             return Err(VoiceError::Other("WhisperRS processor initialization in progress".to_string()));
         }
 
         // 需要创建新的处理器
-        println!("🔧 Initializing new WhisperRS processor for model: {}", model_path);
+        info!("🔧 Initializing new WhisperRS processor for model: {}", model_path);
         self.init_in_progress = true;
 
-        // 🔥 简化：直接使用CPU后端，避免GPU detector死锁
-        let config = WhisperRSConfig {
-            model_path: model_path.to_string(),
-            language: None, // Auto-detect
-            sampling_strategy: crate::voice_assistant::asr::whisper_rs::SamplingStrategyConfig::Greedy { best_of: 1 },
-            translate: false,
-            enable_vad: std::env::var("WHISPER_ENABLE_VAD")
-                .unwrap_or_else(|_| "false".to_string())
-                .parse::<bool>()
-                .unwrap_or(false),
-            backend: crate::voice_assistant::asr::whisper_rs::WhisperBackend::CPU,
-            use_gpu_if_available: false,
-            gpu_device_id: None,
-            output_format: OutputFormat::Text, // 🔥 默认使用纯文本格式
-        };
-
-        match WhisperRSProcessor::new(config) {
+        match WhisperRSProcessor::new(whisper_config(model_path, self.requested_backend.clone())) {
             Ok(processor) => {
+                self.effective_backend = Some(processor.effective_backend().clone());
+                self.gpu_fallback_reason = processor.gpu_fallback_reason().map(str::to_string);
+                if let Some(reason) = &self.gpu_fallback_reason {
+                    crate::voice_assistant::coordinator::emit_gpu_backend_fallback(&self.requested_backend.to_string(), reason);
+                }
+
                 let arc_processor = Arc::new(std::sync::Mutex::new(processor));
                 self.processor = Some(Arc::clone(&arc_processor));
                 self.current_model_path = Some(model_path.to_string());
                 self.init_in_progress = false;
 
-                // 设置环境变量以保持兼容性
-                std::env::set_var("WHISPER_MODEL_PATH", model_path);
+                // 已加载的模型即当前激活的模型，保存后重新加载设置时不会回退
+                if crate::voice_assistant::settings::current().whisper_model_path.as_deref() != Some(model_path) {
+                    if let Err(e) = crate::voice_assistant::settings::persist(crate::voice_assistant::settings::WHISPER_MODEL_PATH, model_path).await {
+                        warn!("⚠️ Failed to save active model: {}", e);
+                        let active_path = model_path.to_string();
+                        crate::voice_assistant::settings::update(|s| s.whisper_model_path = Some(active_path));
+                    }
+                }
 
-                println!("✅ WhisperRS processor initialized successfully for model: {}", model_path);
+                info!("✅ WhisperRS processor initialized successfully for model: {}", model_path);
                 Ok(arc_processor)
             }
             Err(e) => {
                 self.init_in_progress = false;
-                println!("❌ Failed to initialize WhisperRS processor: {}", e);
+                error!("❌ Failed to initialize WhisperRS processor: {}", e);
                 Err(e)
             }
         }
     }
 
+    /// 指定的模型是否已经加载
+    fn is_loaded(&self, model_path: &str) -> bool {
+        self.processor.is_some() && self.current_model_path.as_deref() == Some(model_path)
+    }
+
     /// 检查是否有可用的处理器
     pub fn has_processor(&self) -> bool {
         self.processor.is_some()
@@ -95,20 +113,130 @@ impl GlobalWhisperManager {
 
     /// 清除当前处理器（用于错误恢复或模型卸载）
     pub fn clear_processor(&mut self) {
-        println!("🗑️ Clearing global WhisperRS processor");
+        info!("🗑️ Clearing global WhisperRS processor");
         self.processor = None;
         self.current_model_path = None;
         self.init_in_progress = false;
+        self.effective_backend = None;
+        self.gpu_fallback_reason = None;
+    }
+
+    pub fn requested_backend(&self) -> &WhisperBackend {
+        &self.requested_backend
+    }
+
+    pub fn effective_backend(&self) -> Option<&WhisperBackend> {
+        self.effective_backend.as_ref()
+    }
+
+    pub fn gpu_fallback_reason(&self) -> Option<&str> {
+        self.gpu_fallback_reason.as_deref()
+    }
+
+    /// 切换后端；已加载模型时用新参数重新创建 context。返回是否重新加载
+    pub async fn set_backend(&mut self, backend: WhisperBackend) -> Result<bool, VoiceError> {
+        if self.requested_backend == backend {
+            return Ok(false);
+        }
+        info!("🎯 Whisper backend changed: {} -> {}", self.requested_backend, backend);
+        self.requested_backend = backend;
+
+        match self.current_model_path.clone() {
+            Some(model_path) if self.processor.is_some() => {
+                self.force_reload(&model_path).await?;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
     }
 
     /// 强制重新加载处理器
     pub async fn force_reload(&mut self, model_path: &str) -> Result<Arc<std::sync::Mutex<WhisperRSProcessor>>, VoiceError> {
-        println!("🔄 Force reloading WhisperRS processor for model: {}", model_path);
+        info!("🔄 Force reloading WhisperRS processor for model: {}", model_path);
         self.clear_processor();
         self.get_or_create_processor(model_path).await
     }
 }
 
+/// 按当前设置（VAD、GPU 设备、段落可信度）创建指定后端的配置。
+/// 不经过 GPU detector（避免死锁），后端由调用方决定
+pub fn whisper_config(model_path: &str, backend: WhisperBackend) -> WhisperRSConfig {
+    WhisperRSConfig {
+        model_path: model_path.to_string(),
+        language: None, // Auto-detect
+        sampling_strategy: crate::voice_assistant::asr::whisper_rs::SamplingStrategyConfig::Greedy { best_of: 1 },
+        translate: false,
+        enable_vad: vad_enabled(),
+        use_gpu_if_available: backend != WhisperBackend::CPU,
+        backend,
+        gpu_device_id: gpu_device_id(),
+        output_format: OutputFormat::Text, // 🔥 默认使用纯文本格式
+        reuse_state: true, // 处理器会被反复调用，复用 state 降低短语音延迟
+        min_segment_confidence: min_segment_confidence(),
+    }
+}
+
+/// 听写（worker 或进程内）使用的配置，后端取自设置
+pub fn dictation_config(model_path: &str) -> WhisperRSConfig {
+    whisper_config(model_path, default_backend())
+}
+
+/// VAD 开关（由数据库中的 ASR 配置同步到运行时设置）
+fn vad_enabled() -> bool {
+    crate::voice_assistant::settings::current().whisper_enable_vad
+}
+
+fn min_segment_confidence() -> f32 {
+    crate::voice_assistant::settings::current().whisper_min_segment_confidence
+}
+
+/// 编译进来、并且运行时检测到设备的第一个 GPU 后端；没有检测到可用设备时用 CPU
+pub fn auto_backend() -> WhisperBackend {
+    let compiled = crate::voice_assistant::asr::whisper_rs::compiled_gpu_backends();
+    if compiled.is_empty() {
+        return WhisperBackend::CPU;
+    }
+    let detector = crate::voice_assistant::asr::gpu_detector::get_gpu_detector().lock().unwrap();
+    select_auto_backend(&compiled, detector.get_available_backends())
+}
+
+fn select_auto_backend(compiled: &[WhisperBackend], detected: &[WhisperBackend]) -> WhisperBackend {
+    compiled
+        .iter()
+        .find(|backend| detected.contains(backend))
+        .cloned()
+        .unwrap_or(WhisperBackend::CPU)
+}
+
+/// 默认后端：设置中指定的后端，否则自动选择
+pub fn default_backend() -> WhisperBackend {
+    crate::voice_assistant::settings::current()
+        .whisper_backend()
+        .unwrap_or_else(auto_backend)
+}
+
+/// 多显卡时选择的设备
+fn gpu_device_id() -> Option<u32> {
+    crate::voice_assistant::settings::current().whisper_gpu_device_id
+}
+
+/// 模型加载超时的默认值（秒），大模型冷加载可能需要较长时间
+const DEFAULT_MODEL_LOAD_TIMEOUT_SECS: u64 = 180;
+
+/// 解析 WHISPER_MODEL_LOAD_TIMEOUT_SECS，未设置或无效时使用默认值
+fn parse_model_load_timeout(value: Option<&str>) -> Duration {
+    let secs = value
+        .and_then(|v| v.trim().parse::<u64>().ok())
+        .filter(|&secs| secs > 0)
+        .unwrap_or(DEFAULT_MODEL_LOAD_TIMEOUT_SECS);
+    Duration::from_secs(secs)
+}
+
+/// 模型加载超时，可通过 WHISPER_MODEL_LOAD_TIMEOUT_SECS 覆盖
+pub fn model_load_timeout() -> Duration {
+    parse_model_load_timeout(std::env::var("WHISPER_MODEL_LOAD_TIMEOUT_SECS").ok().as_deref())
+}
+
 /// 全局WhisperRS管理器实例
 static GLOBAL_WHISPER_MANAGER: OnceLock<RwLock<GlobalWhisperManager>> = OnceLock::new();
 
@@ -117,20 +245,91 @@ pub fn get_global_whisper_manager() -> &'static RwLock<GlobalWhisperManager> {
     GLOBAL_WHISPER_MANAGER.get_or_init(|| RwLock::new(GlobalWhisperManager::new()))
 }
 
-/// 便利函数：获取或创建WhisperRS处理器
+/// 是否有 whisper 推理正在进行
+static INFERENCE_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// 推理占用标记，drop 时释放
+pub struct InferenceGuard {
+    _private: (),
+}
+
+impl Drop for InferenceGuard {
+    fn drop(&mut self) {
+        INFERENCE_IN_PROGRESS.store(false, Ordering::Release);
+    }
+}
+
+/// whisper.cpp 的状态不可重入，同一时间只允许一个推理。
+/// 已有推理进行中时立即返回 None，调用方应提示"忙"而不是阻塞等待
+pub fn try_begin_inference() -> Option<InferenceGuard> {
+    INFERENCE_IN_PROGRESS
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .ok()
+        .map(|_| InferenceGuard { _private: () })
+}
+
+/// 热键识别使用：等待正在进行的推理（如模型测试）结束后再开始，录音不能因为忙被丢弃
+pub fn begin_inference() -> InferenceGuard {
+    loop {
+        if let Some(guard) = try_begin_inference() {
+            return guard;
+        }
+        std::thread::sleep(Duration::from_millis(20));
+    }
+}
+
+pub fn is_inference_in_progress() -> bool {
+    INFERENCE_IN_PROGRESS.load(Ordering::Acquire)
+}
+
+/// 加载前检查内存/显存，避免大模型把机器拖进交换或直接崩溃。
+/// 查询显存会调用 nvidia-smi，在阻塞线程上执行，调用方不能持有管理器的锁
+async fn check_memory_before_load(model_path: &str, backend: WhisperBackend) -> Result<(), VoiceError> {
+    let path = model_path.to_string();
+    let estimate = tauri::async_runtime::spawn_blocking(move || {
+        crate::voice_assistant::asr::memory_check::preflight(&path, &backend)
+    })
+    .await
+    .map_err(|e| VoiceError::Other(format!("Memory check task failed: {}", e)))?
+    .map_err(VoiceError::Other)?;
+    info!(
+        "🧮 Model memory estimate: ~{}MB ({} available: {})",
+        estimate.required_mb,
+        if estimate.uses_vram { "VRAM" } else { "RAM" },
+        estimate.available_mb.map(|mb| format!("{}MB", mb)).unwrap_or_else(|| "unknown".to_string())
+    );
+    Ok(())
+}
+
+/// 便利函数：获取或创建WhisperRS处理器；需要加载新模型时先在锁外做内存预检
 pub async fn get_or_create_whisper_processor(model_path: &str) -> Result<Arc<std::sync::Mutex<WhisperRSProcessor>>, VoiceError> {
     let manager = get_global_whisper_manager();
+    let backend_to_check = {
+        let guard = manager.read().await;
+        (!guard.is_loaded(model_path)).then(|| guard.requested_backend().clone())
+    };
+    if let Some(backend) = backend_to_check {
+        check_memory_before_load(model_path, backend).await?;
+    }
+
     let mut manager_guard = manager.write().await;
     manager_guard.get_or_create_processor(model_path).await
 }
 
-/// 便利函数：强制重新加载处理器
+/// 便利函数：强制重新加载处理器。不做内存预检：旧模型还占着内存，预检会误判放不下
 pub async fn force_reload_whisper_processor(model_path: &str) -> Result<Arc<std::sync::Mutex<WhisperRSProcessor>>, VoiceError> {
     let manager = get_global_whisper_manager();
     let mut manager_guard = manager.write().await;
     manager_guard.force_reload(model_path).await
 }
 
+/// 便利函数：切换全局处理器的后端
+pub async fn set_whisper_backend(backend: WhisperBackend) -> Result<bool, VoiceError> {
+    let manager = get_global_whisper_manager();
+    let mut manager_guard = manager.write().await;
+    manager_guard.set_backend(backend).await
+}
+
 /// 便利函数：清除全局处理器
 pub async fn clear_global_whisper_processor() {
     let manager = get_global_whisper_manager();
@@ -146,7 +345,11 @@ pub async fn get_global_whisper_status() -> serde_json::Value {
     serde_json::json!({
         "has_processor": manager_guard.has_processor(),
         "current_model_path": manager_guard.get_current_model_path(),
-        "init_in_progress": false // 由于函数作用域限制，这里返回固定值
+        "init_in_progress": false, // 由于函数作用域限制，这里返回固定值
+        "requested_backend": manager_guard.requested_backend().to_string(),
+        "effective_backend": manager_guard.effective_backend().map(|b| b.to_string()),
+        "gpu_fallback_reason": manager_guard.gpu_fallback_reason(),
+        "worker": crate::voice_assistant::asr::whisper_worker::worker_health()
     })
 }
 
@@ -155,6 +358,13 @@ pub struct WhisperManagerStatus {
     pub has_processor: bool,
     pub current_model_path: Option<String>,
     pub init_in_progress: bool,
+    pub requested_backend: String,
+    /// 模型加载后实际使用的后端，未加载时为 None
+    pub effective_backend: Option<String>,
+    pub gpu_fallback_reason: Option<String>,
+    /// 受监督 worker 进程的运行状态与重启/崩溃次数
+    #[serde(default)]
+    pub worker: crate::voice_assistant::asr::whisper_worker::WhisperWorkerHealth,
 }
 
 /// Tauri命令：获取全局WhisperRS状态
@@ -169,20 +379,160 @@ pub async fn get_whisper_manager_status() -> Result<WhisperManagerStatus, String
 pub async fn reload_whisper_processor(model_path: String) -> Result<String, String> {
     match force_reload_whisper_processor(&model_path).await {
         Ok(_) => {
-            println!("✅ WhisperRS processor reloaded successfully");
+            info!("✅ WhisperRS processor reloaded successfully");
             Ok(format!("Successfully reloaded WhisperRS processor for model: {}", model_path))
         }
         Err(e) => {
-            println!("❌ Failed to reload WhisperRS processor: {}", e);
+            error!("❌ Failed to reload WhisperRS processor: {}", e);
             Err(format!("Failed to reload WhisperRS processor: {}", e))
         }
     }
 }
 
+/// 是否有预加载任务正在进行
+static PRELOAD_IN_PROGRESS: AtomicBool = AtomicBool::new(false);
+
+/// Tauri命令：在后台预加载当前激活的模型，避免第一次按热键时卡住。
+/// 加载开始时发送 `model-load-progress`，结束时发送带加载耗时的 `model-loaded`。
+/// 模型已加载或已有预加载进行中时不重复加载，返回 false
+#[tauri::command]
+pub async fn preload_whisper_model(app: AppHandle) -> Result<bool, String> {
+    let model_path = crate::voice_assistant::settings::current()
+        .whisper_model_path
+        .ok_or_else(|| "No active Whisper model selected".to_string())?;
+    if !std::path::Path::new(&model_path).is_file() {
+        return Err(format!("Whisper model file not found: {}", model_path));
+    }
+
+    {
+        let manager = get_global_whisper_manager().read().await;
+        if manager.has_processor() && manager.get_current_model_path() == Some(model_path.as_str()) {
+            info!("✅ Whisper model already loaded, skipping preload: {}", model_path);
+            let _ = app.emit("model-loaded", serde_json::json!({
+                "model_path": model_path,
+                "status": "success",
+                "load_ms": 0,
+                "already_loaded": true
+            }));
+            return Ok(false);
+        }
+    }
+
+    if PRELOAD_IN_PROGRESS
+        .compare_exchange(false, true, Ordering::AcqRel, Ordering::Acquire)
+        .is_err()
+    {
+        info!("⏳ Whisper model preload already in progress");
+        return Ok(false);
+    }
+
+    tauri::async_runtime::spawn(async move {
+        info!("🚀 Preloading Whisper model: {}", model_path);
+        let _ = app.emit("model-load-progress", serde_json::json!({
+            "model_path": model_path,
+            "status": "loading"
+        }));
+
+        let start = Instant::now();
+        let timeout = model_load_timeout();
+        // 模型加载是同步的，放到阻塞线程里才能让超时生效
+        let path = model_path.clone();
+        let load = tauri::async_runtime::spawn_blocking(move || {
+            tauri::async_runtime::block_on(get_or_create_whisper_processor(&path))
+        });
+        let result = match tokio::time::timeout(timeout, load).await {
+            Ok(Ok(Ok(_))) => Ok(()),
+            Ok(Ok(Err(e))) => Err(e.to_string()),
+            Ok(Err(e)) => Err(format!("Model load task failed: {}", e)),
+            Err(_) => Err(format!("Model load timed out after {}s", timeout.as_secs())),
+        };
+        let load_ms = start.elapsed().as_millis() as u64;
+
+        let payload = match &result {
+            Ok(()) => {
+                info!("✅ Whisper model preloaded in {}ms", load_ms);
+                serde_json::json!({
+                    "model_path": model_path,
+                    "status": "success",
+                    "load_ms": load_ms,
+                    "already_loaded": false
+                })
+            }
+            Err(e) => {
+                error!("❌ Failed to preload Whisper model: {}", e);
+                serde_json::json!({
+                    "model_path": model_path,
+                    "status": "error",
+                    "load_ms": load_ms,
+                    "error": e
+                })
+            }
+        };
+        let _ = app.emit("model-loaded", payload);
+        PRELOAD_IN_PROGRESS.store(false, Ordering::Release);
+    });
+
+    Ok(true)
+}
+
 /// Tauri命令：清除全局WhisperRS处理器
 #[tauri::command]
 pub async fn clear_whisper_processor() -> Result<String, String> {
     clear_global_whisper_processor().await;
-    println!("✅ Global WhisperRS processor cleared");
+    info!("✅ Global WhisperRS processor cleared");
     Ok("Global WhisperRS processor cleared successfully".to_string())
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Barrier;
+
+    #[test]
+    fn test_auto_backend_needs_detected_device() {
+        let compiled = [WhisperBackend::CUDA, WhisperBackend::Vulkan];
+        assert_eq!(select_auto_backend(&compiled, &[WhisperBackend::CPU]), WhisperBackend::CPU);
+        assert_eq!(select_auto_backend(&compiled, &[WhisperBackend::Vulkan, WhisperBackend::CPU]), WhisperBackend::Vulkan);
+        assert_eq!(select_auto_backend(&compiled, &[WhisperBackend::CUDA, WhisperBackend::Vulkan]), WhisperBackend::CUDA);
+        assert_eq!(select_auto_backend(&[], &[WhisperBackend::CUDA]), WhisperBackend::CPU);
+    }
+
+    #[test]
+    fn test_parse_model_load_timeout() {
+        assert_eq!(parse_model_load_timeout(None), Duration::from_secs(180));
+        assert_eq!(parse_model_load_timeout(Some(" 600 ")), Duration::from_secs(600));
+        assert_eq!(parse_model_load_timeout(Some("0")), Duration::from_secs(180));
+        assert_eq!(parse_model_load_timeout(Some("3m")), Duration::from_secs(180));
+    }
+
+    #[test]
+    fn test_only_one_thread_gets_inference_guard() {
+        let acquired = Arc::new(Barrier::new(2));
+        let release = Arc::new(Barrier::new(2));
+
+        let handles: Vec<_> = (0..2)
+            .map(|_| {
+                let acquired = acquired.clone();
+                let release = release.clone();
+                std::thread::spawn(move || {
+                    let guard = try_begin_inference();
+                    let got_guard = guard.is_some();
+                    // 两个线程都尝试过之后才释放
+                    acquired.wait();
+                    release.wait();
+                    drop(guard);
+                    got_guard
+                })
+            })
+            .collect();
+
+        let results: Vec<bool> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(results.iter().filter(|got| **got).count(), 1);
+
+        // 释放后可以再次获取
+        assert!(!is_inference_in_progress());
+        let guard = try_begin_inference();
+        assert!(guard.is_some());
+        assert!(try_begin_inference().is_none());
+    }
+}
@@ -0,0 +1,176 @@
+//! 设置页的"按下新热键"录制：临时接管 rdev 键盘事件，记录下一次完整的按键组合（全部松开时结束），
+//! 录制期间热键不会触发听写
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use rdev::{EventType, Key};
+use tracing::{error, info};
+use crate::voice_assistant::hotkey_parser::ParsedHotkey;
+
+/// 等待用户按键的最长时间
+pub const CAPTURE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 一次录制：按顺序记录按下过的键，全部松开时结束
+#[derive(Debug, Default)]
+pub struct KeyCapture {
+    pressed: HashSet<Key>,
+    combination: Vec<Key>,
+}
+
+impl KeyCapture {
+    /// 处理一个键盘事件；组合完成时返回规范的热键字符串或错误
+    pub fn handle(&mut self, event: &EventType) -> Option<Result<String, String>> {
+        match event {
+            EventType::KeyPress(key) => {
+                self.pressed.insert(*key);
+                if !self.combination.contains(key) {
+                    self.combination.push(*key);
+                }
+                None
+            }
+            EventType::KeyRelease(key) => {
+                self.pressed.remove(key);
+                if !self.pressed.is_empty() || self.combination.is_empty() {
+                    return None;
+                }
+                let combination = std::mem::take(&mut self.combination);
+                match ParsedHotkey::from_keys(&combination) {
+                    Ok(hotkey) => Some(Ok(hotkey.get_display_name())),
+                    // 只按了修饰键时继续等待
+                    Err(crate::voice_assistant::hotkey_parser::HotkeyParseError::MissingMainKey) => None,
+                    Err(e) => Some(Err(e.to_string())),
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+struct ActiveCapture {
+    capture: KeyCapture,
+    sender: Sender<Result<String, String>>,
+}
+
+static CAPTURE: OnceLock<Mutex<Option<ActiveCapture>>> = OnceLock::new();
+// 进程中是否已有 rdev 监听线程（热键监听或录制专用），rdev::listen 无法停止，只启动一次
+static RDEV_LISTENER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+fn capture() -> &'static Mutex<Option<ActiveCapture>> {
+    CAPTURE.get_or_init(|| Mutex::new(None))
+}
+
+pub fn is_capturing() -> bool {
+    capture().lock().unwrap().is_some()
+}
+
+/// 热键监听线程启动 rdev::listen 时调用，录制时复用它的事件
+pub fn mark_rdev_listener_running() {
+    RDEV_LISTENER_RUNNING.store(true, Ordering::SeqCst);
+}
+
+/// rdev 监听回调中最先调用：录制期间事件交给录制处理并返回 true，监听线程不再匹配热键
+pub fn handle_event(event: &EventType) -> bool {
+    let mut guard = capture().lock().unwrap();
+    let Some(active) = guard.as_mut() else {
+        return false;
+    };
+    if let Some(result) = active.capture.handle(event) {
+        let _ = active.sender.send(result);
+        *guard = None;
+    }
+    true
+}
+
+/// 热键监听没有运行（插件后端或服务未启动）时，启动一个只服务于录制的 rdev 监听线程
+fn ensure_listener() {
+    if RDEV_LISTENER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+    info!("🎹 Starting rdev listener for hotkey capture");
+    std::thread::spawn(|| {
+        if let Err(e) = rdev::listen(|event| {
+            handle_event(&event.event_type);
+        }) {
+            error!("❌ Hotkey capture listener failed: {:?}", e);
+            RDEV_LISTENER_RUNNING.store(false, Ordering::SeqCst);
+        }
+    });
+}
+
+/// 开始录制；已有录制时先取消
+pub fn begin() -> Receiver<Result<String, String>> {
+    let (sender, receiver) = mpsc::channel();
+    let previous = capture().lock().unwrap().replace(ActiveCapture {
+        capture: KeyCapture::default(),
+        sender,
+    });
+    if let Some(previous) = previous {
+        let _ = previous.sender.send(Err("Hotkey capture was restarted".to_string()));
+    }
+    ensure_listener();
+    info!("🎹 Waiting for hotkey combination...");
+    receiver
+}
+
+/// 取消进行中的录制，返回是否有录制被取消
+pub fn cancel() -> bool {
+    match capture().lock().unwrap().take() {
+        Some(active) => {
+            let _ = active.sender.send(Err("Hotkey capture cancelled".to_string()));
+            info!("🎹 Hotkey capture cancelled");
+            true
+        }
+        None => false,
+    }
+}
+
+/// 录制下一次按键组合，超时或取消时返回错误
+pub fn capture_next(timeout: Duration) -> Result<String, String> {
+    let receiver = begin();
+    match receiver.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => {
+            capture().lock().unwrap().take();
+            Err(format!("No hotkey pressed within {} seconds", timeout.as_secs()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed(capture: &mut KeyCapture, events: &[EventType]) -> Option<Result<String, String>> {
+        events.iter().find_map(|event| capture.handle(event))
+    }
+
+    #[test]
+    fn test_capture_completes_when_all_keys_released() {
+        let mut capture = KeyCapture::default();
+        let result = feed(&mut capture, &[
+            EventType::KeyPress(Key::ControlRight),
+            EventType::KeyPress(Key::Alt),
+            EventType::KeyPress(Key::KeyT),
+            // 按住时的自动重复
+            EventType::KeyPress(Key::KeyT),
+            EventType::KeyRelease(Key::KeyT),
+            EventType::KeyRelease(Key::Alt),
+            EventType::KeyRelease(Key::ControlRight),
+        ]);
+        assert_eq!(result, Some(Ok("Ctrl + Alt + T".to_string())));
+    }
+
+    #[test]
+    fn test_modifier_only_keeps_waiting() {
+        let mut capture = KeyCapture::default();
+        assert_eq!(feed(&mut capture, &[EventType::KeyPress(Key::ShiftLeft), EventType::KeyRelease(Key::ShiftLeft)]), None);
+        let result = feed(&mut capture, &[EventType::KeyPress(Key::F9), EventType::KeyRelease(Key::F9)]);
+        assert_eq!(result, Some(Ok("F9".to_string())));
+
+        let mut capture = KeyCapture::default();
+        let result = feed(&mut capture, &[EventType::KeyPress(Key::CapsLock), EventType::KeyRelease(Key::CapsLock)]);
+        assert!(matches!(result, Some(Err(_))));
+    }
+}
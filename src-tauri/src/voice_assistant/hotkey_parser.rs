@@ -1,6 +1,147 @@
 use rdev::Key;
 use std::collections::HashSet;
 
+/// 热键字符串解析失败的原因，设置页据此提示用户
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum HotkeyParseError {
+    #[error("Hotkey string is empty")]
+    Empty,
+    #[error("Unknown key name: '{0}'")]
+    UnknownKey(String),
+    #[error("Key {0} is not supported for hotkeys")]
+    UnsupportedKey(String),
+    #[error("Hotkey must contain at least one main key (modifier-only shortcuts not supported)")]
+    MissingMainKey,
+    #[error("Modifier {0} appears more than once")]
+    DuplicateModifier(String),
+    #[error("Hotkey can only have one main key, got {0} and {1}")]
+    MultipleMainKeys(String, String),
+}
+
+/// 修饰键按显示顺序排列，左右两侧的同名键视为同一个
+const MODIFIERS: &[(Key, &str)] = &[
+    (Key::ControlLeft, "Ctrl"),
+    (Key::ShiftLeft, "Shift"),
+    (Key::Alt, "Alt"),
+    (Key::MetaLeft, "Meta"),
+];
+
+/// 支持作为主键的按键及其规范名称
+const MAIN_KEYS: &[(Key, &str)] = &[
+    (Key::Space, "Space"),
+    (Key::Return, "Enter"),
+    (Key::Escape, "Esc"),
+    (Key::Tab, "Tab"),
+    (Key::Backspace, "Backspace"),
+    (Key::Delete, "Delete"),
+    (Key::Insert, "Insert"),
+    (Key::UpArrow, "Up"),
+    (Key::DownArrow, "Down"),
+    (Key::LeftArrow, "Left"),
+    (Key::RightArrow, "Right"),
+    (Key::Home, "Home"),
+    (Key::End, "End"),
+    (Key::PageUp, "PageUp"),
+    (Key::PageDown, "PageDown"),
+    (Key::F1, "F1"),
+    (Key::F2, "F2"),
+    (Key::F3, "F3"),
+    (Key::F4, "F4"),
+    (Key::F5, "F5"),
+    (Key::F6, "F6"),
+    (Key::F7, "F7"),
+    (Key::F8, "F8"),
+    (Key::F9, "F9"),
+    (Key::F10, "F10"),
+    (Key::F11, "F11"),
+    (Key::F12, "F12"),
+    (Key::KeyA, "A"),
+    (Key::KeyB, "B"),
+    (Key::KeyC, "C"),
+    (Key::KeyD, "D"),
+    (Key::KeyE, "E"),
+    (Key::KeyF, "F"),
+    (Key::KeyG, "G"),
+    (Key::KeyH, "H"),
+    (Key::KeyI, "I"),
+    (Key::KeyJ, "J"),
+    (Key::KeyK, "K"),
+    (Key::KeyL, "L"),
+    (Key::KeyM, "M"),
+    (Key::KeyN, "N"),
+    (Key::KeyO, "O"),
+    (Key::KeyP, "P"),
+    (Key::KeyQ, "Q"),
+    (Key::KeyR, "R"),
+    (Key::KeyS, "S"),
+    (Key::KeyT, "T"),
+    (Key::KeyU, "U"),
+    (Key::KeyV, "V"),
+    (Key::KeyW, "W"),
+    (Key::KeyX, "X"),
+    (Key::KeyY, "Y"),
+    (Key::KeyZ, "Z"),
+    (Key::Num0, "0"),
+    (Key::Num1, "1"),
+    (Key::Num2, "2"),
+    (Key::Num3, "3"),
+    (Key::Num4, "4"),
+    (Key::Num5, "5"),
+    (Key::Num6, "6"),
+    (Key::Num7, "7"),
+    (Key::Num8, "8"),
+    (Key::Num9, "9"),
+];
+
+/// 修饰键的各种写法（"ctrl"、"LeftCtrl"、"Control_R"、"cmd" 等），传入的名称已去掉空白并转为小写
+fn modifier_for(name: &str) -> Option<(Key, &'static str)> {
+    let base = name
+        .strip_prefix("left")
+        .or_else(|| name.strip_prefix("right"))
+        .or_else(|| name.strip_suffix("left"))
+        .or_else(|| name.strip_suffix("right"))
+        .or_else(|| name.strip_suffix("_l"))
+        .or_else(|| name.strip_suffix("_r"))
+        .unwrap_or(name);
+    let key = match base {
+        "ctrl" | "control" | "lctrl" | "rctrl" => Key::ControlLeft,
+        "shift" | "lshift" | "rshift" => Key::ShiftLeft,
+        "alt" | "option" | "opt" | "altgr" | "lalt" | "ralt" => Key::Alt,
+        "meta" | "cmd" | "command" | "win" | "windows" | "super" | "lmeta" | "rmeta" => Key::MetaLeft,
+        _ => return None,
+    };
+    MODIFIERS.iter().find(|(k, _)| *k == key).map(|&(key, name)| (key, name))
+}
+
+/// 主键的规范名称和常见别名
+fn main_key_for(name: &str) -> Result<(Key, &'static str), HotkeyParseError> {
+    let canonical = match name {
+        "return" => "enter",
+        "escape" => "esc",
+        "del" => "delete",
+        "ins" => "insert",
+        "arrowup" | "uparrow" => "up",
+        "arrowdown" | "downarrow" => "down",
+        "arrowleft" | "leftarrow" => "left",
+        "arrowright" | "rightarrow" => "right",
+        "pgup" => "pageup",
+        "pgdn" | "pgdown" => "pagedown",
+        other => other,
+    };
+    if let Some(&(key, display)) = MAIN_KEYS.iter().find(|(_, display)| display.eq_ignore_ascii_case(canonical)) {
+        return Ok((key, display));
+    }
+    // rdev 不支持 F13-F24
+    let is_high_function_key = canonical
+        .strip_prefix('f')
+        .and_then(|n| n.parse::<u32>().ok())
+        .is_some_and(|n| (13..=24).contains(&n));
+    if is_high_function_key {
+        return Err(HotkeyParseError::UnsupportedKey(canonical.to_uppercase()));
+    }
+    Err(HotkeyParseError::UnknownKey(name.to_string()))
+}
+
 #[derive(Debug, Clone)]
 pub struct ParsedHotkey {
     pub modifiers: HashSet<Key>,
@@ -11,194 +152,81 @@ pub struct ParsedHotkey {
 impl ParsedHotkey {
     /// 解析热键字符串（如 "Ctrl + F4", "Shift + Alt + T"）为按键组合
     pub fn parse(hotkey_str: &str) -> Result<Self, String> {
-        let mut modifiers = HashSet::new();
-        let mut main_key: Option<Key> = None;
-        let mut key_combination = Vec::new();
+        Self::try_parse(hotkey_str).map_err(|e| e.to_string())
+    }
 
+    /// 同 parse，返回具体的错误类型。按键之间可以用 "+" 或 "-" 分隔，不区分大小写
+    pub fn try_parse(hotkey_str: &str) -> Result<Self, HotkeyParseError> {
         if hotkey_str.trim().is_empty() {
-            return Err("Hotkey string is empty".to_string());
+            return Err(HotkeyParseError::Empty);
         }
 
-        let parts: Vec<&str> = hotkey_str.split('+').map(|s| s.trim()).collect();
-
-        for part in parts {
-            match part.to_lowercase().as_str() {
-                "ctrl" | "control" => {
-                    modifiers.insert(Key::ControlLeft);
-                    key_combination.push(Key::ControlLeft);
-                },
-                "alt" => {
-                    modifiers.insert(Key::Alt);
-                    key_combination.push(Key::Alt);
-                },
-                "shift" => {
-                    modifiers.insert(Key::ShiftLeft); // 使用Left Shift
-                    key_combination.push(Key::ShiftLeft);
-                },
-                "meta" | "cmd" | "command" => {
-                    modifiers.insert(Key::MetaLeft);
-                    key_combination.push(Key::MetaLeft);
-                },
-                "win" | "windows" => {
-                    modifiers.insert(Key::MetaLeft); // Windows键映射为Meta
-                    key_combination.push(Key::MetaLeft);
-                },
-                "space" => {
-                    main_key = Some(Key::Space);
-                    key_combination.push(Key::Space);
-                },
-                "enter" | "return" => {
-                    main_key = Some(Key::Return);
-                    key_combination.push(Key::Return);
-                },
-                "escape" | "esc" => {
-                    main_key = Some(Key::Escape);
-                    key_combination.push(Key::Escape);
-                },
-                "tab" => {
-                    main_key = Some(Key::Tab);
-                    key_combination.push(Key::Tab);
-                },
-                "backspace" => {
-                    main_key = Some(Key::Backspace);
-                    key_combination.push(Key::Backspace);
-                },
-                "delete" => {
-                    main_key = Some(Key::Delete);
-                    key_combination.push(Key::Delete);
-                },
-                "up" => {
-                    main_key = Some(Key::UpArrow);
-                    key_combination.push(Key::UpArrow);
-                },
-                "down" => {
-                    main_key = Some(Key::DownArrow);
-                    key_combination.push(Key::DownArrow);
-                },
-                "left" => {
-                    main_key = Some(Key::LeftArrow);
-                    key_combination.push(Key::LeftArrow);
-                },
-                "right" => {
-                    main_key = Some(Key::RightArrow);
-                    key_combination.push(Key::RightArrow);
-                },
-                "home" => {
-                    main_key = Some(Key::Home);
-                    key_combination.push(Key::Home);
-                },
-                "end" => {
-                    main_key = Some(Key::End);
-                    key_combination.push(Key::End);
-                },
-                "pageup" | "page up" => {
-                    main_key = Some(Key::PageUp);
-                    key_combination.push(Key::PageUp);
-                },
-                "pagedown" | "page down" => {
-                    main_key = Some(Key::PageDown);
-                    key_combination.push(Key::PageDown);
-                },
-                // 字母键
-                s if s.len() == 1 && s.chars().next().unwrap().is_ascii_alphabetic() => {
-                    let key_str = s.to_uppercase();
-                    let key = match key_str.as_str() {
-                        "A" => Key::KeyA,
-                        "B" => Key::KeyB,
-                        "C" => Key::KeyC,
-                        "D" => Key::KeyD,
-                        "E" => Key::KeyE,
-                        "F" => Key::KeyF,
-                        "G" => Key::KeyG,
-                        "H" => Key::KeyH,
-                        "I" => Key::KeyI,
-                        "J" => Key::KeyJ,
-                        "K" => Key::KeyK,
-                        "L" => Key::KeyL,
-                        "M" => Key::KeyM,
-                        "N" => Key::KeyN,
-                        "O" => Key::KeyO,
-                        "P" => Key::KeyP,
-                        "Q" => Key::KeyQ,
-                        "R" => Key::KeyR,
-                        "S" => Key::KeyS,
-                        "T" => Key::KeyT,
-                        "U" => Key::KeyU,
-                        "V" => Key::KeyV,
-                        "W" => Key::KeyW,
-                        "X" => Key::KeyX,
-                        "Y" => Key::KeyY,
-                        "Z" => Key::KeyZ,
-                        _ => return Err(format!("Unsupported alphabetic key: {}", s)),
-                    };
-                    main_key = Some(key);
-                    key_combination.push(key);
-                },
-                // 数字键
-                s if s.len() == 1 && s.chars().next().unwrap().is_ascii_digit() => {
-                    let key = match s {
-                        "0" => Key::Num0,
-                        "1" => Key::Num1,
-                        "2" => Key::Num2,
-                        "3" => Key::Num3,
-                        "4" => Key::Num4,
-                        "5" => Key::Num5,
-                        "6" => Key::Num6,
-                        "7" => Key::Num7,
-                        "8" => Key::Num8,
-                        "9" => Key::Num9,
-                        _ => return Err(format!("Unsupported numeric key: {}", s)),
-                    };
-                    main_key = Some(key);
-                    key_combination.push(key);
-                },
-                // F键
-                s if s.to_uppercase().starts_with('F') && s.len() > 1 => {
-                    if let Ok(num) = s[1..].parse::<u32>() {
-                        if num >= 1 && num <= 24 {
-                            let key = match num {
-                                1 => Key::F1,
-                                2 => Key::F2,
-                                3 => Key::F3,
-                                4 => Key::F4,
-                                5 => Key::F5,
-                                6 => Key::F6,
-                                7 => Key::F7,
-                                8 => Key::F8,
-                                9 => Key::F9,
-                                10 => Key::F10,
-                                11 => Key::F11,
-                                12 => Key::F12,
-                                13..=24 => Key::Unknown(0), // rdev不支持F13-F24，使用0作为占位符
-                                _ => return Err(format!("Invalid F-key number: {}", num)),
-                            };
-                            main_key = Some(key);
-                            key_combination.push(key);
-                        } else {
-                            return Err(format!("F-key number out of range: {}", num));
-                        }
-                    } else {
-                        return Err(format!("Invalid F-key format: {}", s));
-                    }
-                },
-                _ => {
-                    return Err(format!("Unsupported key: {}", part));
+        let mut modifiers = HashSet::new();
+        let mut main_key: Option<(Key, &str)> = None;
+        let mut key_combination = Vec::new();
+
+        for part in hotkey_str.split(['+', '-']) {
+            let name: String = part
+                .chars()
+                .filter(|c| !c.is_whitespace())
+                .collect::<String>()
+                .to_lowercase();
+            if name.is_empty() {
+                return Err(HotkeyParseError::UnknownKey(part.trim().to_string()));
+            }
+
+            if let Some((key, display)) = modifier_for(&name) {
+                if !modifiers.insert(key) {
+                    return Err(HotkeyParseError::DuplicateModifier(display.to_string()));
                 }
+                key_combination.push(key);
+                continue;
             }
+
+            let (key, display) = main_key_for(&name)?;
+            if let Some((_, previous)) = main_key {
+                return Err(HotkeyParseError::MultipleMainKeys(previous.to_string(), display.to_string()));
+            }
+            main_key = Some((key, display));
+            key_combination.push(key);
         }
 
         // 确保至少有一个主键（除了修饰键外）
-        if main_key.is_none() {
-            return Err("Hotkey must contain at least one main key (modifier-only shortcuts not supported)".to_string());
-        }
+        let Some((main_key, _)) = main_key else {
+            return Err(HotkeyParseError::MissingMainKey);
+        };
 
         Ok(ParsedHotkey {
             modifiers,
-            main_key,
+            main_key: Some(main_key),
             key_combination,
         })
     }
 
+    /// 由实际按下的按键（如录制热键时捕获的组合）构造，左右两侧的修饰键视为同一个
+    pub fn from_keys(keys: &[Key]) -> Result<Self, HotkeyParseError> {
+        let mut names = Vec::new();
+        for key in keys {
+            let modifier = match key {
+                Key::ControlLeft | Key::ControlRight => Some("Ctrl"),
+                Key::ShiftLeft | Key::ShiftRight => Some("Shift"),
+                Key::Alt | Key::AltGr => Some("Alt"),
+                Key::MetaLeft | Key::MetaRight => Some("Meta"),
+                _ => None,
+            };
+            let name = match modifier {
+                Some(name) => name,
+                None => MAIN_KEYS
+                    .iter()
+                    .find(|(k, _)| k == key)
+                    .map(|(_, name)| *name)
+                    .ok_or_else(|| HotkeyParseError::UnsupportedKey(format!("{:?}", key)))?,
+            };
+            names.push(name);
+        }
+        Self::try_parse(&names.join(" + "))
+    }
+
     /// 检查当前按键状态是否匹配此热键
     pub fn matches(&self, pressed_keys: &HashSet<Key>) -> bool {
         // 1. 检查所有必需的按键是否都被按下
@@ -218,92 +246,70 @@ impl ParsedHotkey {
         true
     }
 
-    /// 获取热键的显示名称
-    pub fn get_display_name(&self) -> String {
-        let mut parts = Vec::new();
+    /// 转换为 tauri-plugin-global-shortcut 可解析的快捷键字符串（如 "Control+Shift+F4"）
+    pub fn to_accelerator(&self) -> Result<String, String> {
+        let mut parts: Vec<String> = Vec::new();
 
-        // 添加修饰键
-        if self.modifiers.contains(&Key::ControlLeft) {
-            parts.push("Ctrl");
+        if self.modifiers.contains(&Key::ControlLeft) || self.modifiers.contains(&Key::ControlRight) {
+            parts.push("Control".to_string());
         }
-        if self.modifiers.contains(&Key::Alt) {
-            parts.push("Alt");
+        if self.modifiers.contains(&Key::Alt) || self.modifiers.contains(&Key::AltGr) {
+            parts.push("Alt".to_string());
         }
         if self.modifiers.contains(&Key::ShiftLeft) || self.modifiers.contains(&Key::ShiftRight) {
-            parts.push("Shift");
+            parts.push("Shift".to_string());
         }
-        if self.modifiers.contains(&Key::MetaLeft) {
-            parts.push("Meta");
+        if self.modifiers.contains(&Key::MetaLeft) || self.modifiers.contains(&Key::MetaRight) {
+            parts.push("Super".to_string());
         }
 
-        // 添加主键
+        let main_key = self.main_key.ok_or_else(|| "Hotkey has no main key".to_string())?;
+        let code = match main_key {
+            Key::Space => "Space".to_string(),
+            Key::Return => "Enter".to_string(),
+            Key::Escape => "Escape".to_string(),
+            Key::Tab => "Tab".to_string(),
+            Key::Backspace => "Backspace".to_string(),
+            Key::Delete => "Delete".to_string(),
+            Key::UpArrow => "ArrowUp".to_string(),
+            Key::DownArrow => "ArrowDown".to_string(),
+            Key::LeftArrow => "ArrowLeft".to_string(),
+            Key::RightArrow => "ArrowRight".to_string(),
+            Key::Home => "Home".to_string(),
+            Key::End => "End".to_string(),
+            Key::PageUp => "PageUp".to_string(),
+            Key::PageDown => "PageDown".to_string(),
+            other => {
+                // KeyA..KeyZ、F1..F12 与插件的按键码同名，Num0..Num9 对应 Digit0..Digit9
+                let name = format!("{:?}", other);
+                if let Some(digit) = name.strip_prefix("Num") {
+                    format!("Digit{}", digit)
+                } else if name.starts_with("Key") || name.starts_with('F') {
+                    name
+                } else {
+                    return Err(format!("Key {:?} is not supported by the global shortcut backend", other));
+                }
+            }
+        };
+        parts.push(code);
+
+        Ok(parts.join("+"))
+    }
+
+    /// 获取热键的显示名称，也是保存到设置中的规范写法（修饰键按 Ctrl、Shift、Alt、Meta 排序）
+    pub fn get_display_name(&self) -> String {
+        let mut parts: Vec<&str> = MODIFIERS
+            .iter()
+            .filter(|(key, _)| self.modifiers.contains(key))
+            .map(|(_, name)| *name)
+            .collect();
+
         if let Some(main_key) = &self.main_key {
-            parts.push(&match main_key {
-                Key::Space => "Space",
-                Key::Return => "Enter",
-                Key::Escape => "Esc",
-                Key::Tab => "Tab",
-                Key::Backspace => "Backspace",
-                Key::Delete => "Delete",
-                Key::UpArrow => "Up",
-                Key::DownArrow => "Down",
-                Key::LeftArrow => "Left",
-                Key::RightArrow => "Right",
-                Key::Home => "Home",
-                Key::End => "End",
-                Key::PageUp => "PageUp",
-                Key::PageDown => "PageDown",
-                Key::F1 => "F1",
-                Key::F2 => "F2",
-                Key::F3 => "F3",
-                Key::F4 => "F4",
-                Key::F5 => "F5",
-                Key::F6 => "F6",
-                Key::F7 => "F7",
-                Key::F8 => "F8",
-                Key::F9 => "F9",
-                Key::F10 => "F10",
-                Key::F11 => "F11",
-                Key::F12 => "F12",
-                Key::KeyA => "A",
-                Key::KeyB => "B",
-                Key::KeyC => "C",
-                Key::KeyD => "D",
-                Key::KeyE => "E",
-                Key::KeyF => "F",
-                Key::KeyG => "G",
-                Key::KeyH => "H",
-                Key::KeyI => "I",
-                Key::KeyJ => "J",
-                Key::KeyK => "K",
-                Key::KeyL => "L",
-                Key::KeyM => "M",
-                Key::KeyN => "N",
-                Key::KeyO => "O",
-                Key::KeyP => "P",
-                Key::KeyQ => "Q",
-                Key::KeyR => "R",
-                Key::KeyS => "S",
-                Key::KeyT => "T",
-                Key::KeyU => "U",
-                Key::KeyV => "V",
-                Key::KeyW => "W",
-                Key::KeyX => "X",
-                Key::KeyY => "Y",
-                Key::KeyZ => "Z",
-                Key::Num0 => "0",
-                Key::Num1 => "1",
-                Key::Num2 => "2",
-                Key::Num3 => "3",
-                Key::Num4 => "4",
-                Key::Num5 => "5",
-                Key::Num6 => "6",
-                Key::Num7 => "7",
-                Key::Num8 => "8",
-                Key::Num9 => "9",
-                Key::Unknown(_) => "Unknown",
-                _ => "Unknown",
-            });
+            let name = MAIN_KEYS
+                .iter()
+                .find(|(key, _)| key == main_key)
+                .map_or("Unknown", |(_, name)| *name);
+            parts.push(name);
         }
 
         parts.join(" + ")
@@ -339,9 +345,60 @@ mod tests {
         assert_eq!(hotkey1.get_display_name(), hotkey2.get_display_name());
     }
 
+    #[test]
+    fn test_to_accelerator() {
+        assert_eq!(ParsedHotkey::parse("F4").unwrap().to_accelerator().unwrap(), "F4");
+        assert_eq!(ParsedHotkey::parse("Shift + F4").unwrap().to_accelerator().unwrap(), "Shift+F4");
+        assert_eq!(
+            ParsedHotkey::parse("shift + ctrl + a").unwrap().to_accelerator().unwrap(),
+            "Control+Shift+KeyA"
+        );
+        assert_eq!(ParsedHotkey::parse("Win + Space").unwrap().to_accelerator().unwrap(), "Super+Space");
+        assert_eq!(ParsedHotkey::parse("Alt + 1").unwrap().to_accelerator().unwrap(), "Alt+Digit1");
+        assert_eq!(ParsedHotkey::parse("Ctrl + Up").unwrap().to_accelerator().unwrap(), "Control+ArrowUp");
+    }
+
     #[test]
     fn test_parse_invalid_hotkey() {
         assert!(ParsedHotkey::parse("").is_err());
         assert!(ParsedHotkey::parse("Ctrl").is_err()); // 只有修饰键，没有主键
     }
+
+    #[test]
+    fn test_alternative_spellings_normalize() {
+        for spelling in ["CTRL-ALT-T", "ctrl+alt+t", "Control + Option + T", "LeftCtrl+Alt_R+t", "alt - ctrl - T"] {
+            let hotkey = ParsedHotkey::try_parse(spelling).unwrap();
+            assert_eq!(hotkey.get_display_name(), "Ctrl + Alt + T", "{}", spelling);
+        }
+        assert_eq!(ParsedHotkey::try_parse("cmd+shift+page up").unwrap().get_display_name(), "Shift + Meta + PageUp");
+        assert_eq!(ParsedHotkey::try_parse("Escape").unwrap().get_display_name(), "Esc");
+    }
+
+    #[test]
+    fn test_parse_errors_are_precise() {
+        assert_eq!(ParsedHotkey::try_parse("  ").unwrap_err(), HotkeyParseError::Empty);
+        assert_eq!(ParsedHotkey::try_parse("Ctrl + Foo").unwrap_err(), HotkeyParseError::UnknownKey("foo".to_string()));
+        assert_eq!(ParsedHotkey::try_parse("Ctrl + Shift").unwrap_err(), HotkeyParseError::MissingMainKey);
+        assert_eq!(
+            ParsedHotkey::try_parse("Ctrl + LeftCtrl + T").unwrap_err(),
+            HotkeyParseError::DuplicateModifier("Ctrl".to_string())
+        );
+        assert_eq!(
+            ParsedHotkey::try_parse("Ctrl + A + B").unwrap_err(),
+            HotkeyParseError::MultipleMainKeys("A".to_string(), "B".to_string())
+        );
+        assert_eq!(ParsedHotkey::try_parse("F13").unwrap_err(), HotkeyParseError::UnsupportedKey("F13".to_string()));
+    }
+
+    #[test]
+    fn test_from_captured_keys() {
+        let hotkey = ParsedHotkey::from_keys(&[Key::ShiftRight, Key::ControlLeft, Key::F4]).unwrap();
+        assert_eq!(hotkey.get_display_name(), "Ctrl + Shift + F4");
+        // 规范写法能被再次解析，并匹配左侧修饰键
+        let reparsed = ParsedHotkey::try_parse(&hotkey.get_display_name()).unwrap();
+        assert!(reparsed.matches(&[Key::ControlLeft, Key::ShiftLeft, Key::F4].into_iter().collect()));
+
+        assert_eq!(ParsedHotkey::from_keys(&[Key::ControlLeft]).unwrap_err(), HotkeyParseError::MissingMainKey);
+        assert!(matches!(ParsedHotkey::from_keys(&[Key::CapsLock]), Err(HotkeyParseError::UnsupportedKey(_))));
+    }
 }
\ No newline at end of file
@@ -0,0 +1,353 @@
+//! 可选的本地 HTTP 接口，供脚本和外部工具触发听写。
+//!
+//! 默认关闭，只监听 127.0.0.1，所有请求都要带 `Authorization: Bearer <token>`：
+//! - `POST /dictate/start`、`POST /dictate/stop`：与按下/松开转写热键相同的状态转换
+//! - `POST /transcribe`：请求体是音频文件（WAV 等），返回 `{"text": ...}`
+//! - `GET /state`：当前的 InputState
+use std::io::Read;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread::JoinHandle;
+use base64::{engine::general_purpose::STANDARD, Engine};
+use serde_json::json;
+use tiny_http::{Header, Method, Request, Response, Server};
+use tracing::{error, info, warn};
+use crate::voice_assistant::keyboard::{send_hotkey_signal, HotkeyAction, HotkeySignal, RecordingMode};
+use crate::voice_assistant::{settings, InputState};
+
+/// /transcribe 请求体上限，和 test_asr_transcription 的 10 分钟时长限制大致对应
+const MAX_BODY_BYTES: u64 = 64 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Route {
+    DictateStart,
+    DictateStop,
+    Transcribe,
+    State,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum RouteMatch {
+    Found(Route),
+    MethodNotAllowed,
+    NotFound,
+}
+
+fn match_route(method: &Method, url: &str) -> RouteMatch {
+    let path = url.split('?').next().unwrap_or(url).trim_end_matches('/');
+    let (route, expected) = match path {
+        "/dictate/start" => (Route::DictateStart, Method::Post),
+        "/dictate/stop" => (Route::DictateStop, Method::Post),
+        "/transcribe" => (Route::Transcribe, Method::Post),
+        "/state" => (Route::State, Method::Get),
+        _ => return RouteMatch::NotFound,
+    };
+    if *method == expected {
+        RouteMatch::Found(route)
+    } else {
+        RouteMatch::MethodNotAllowed
+    }
+}
+
+/// 取出 Authorization 头里的 Bearer token
+pub fn bearer_token(header: &str) -> Option<&str> {
+    let (scheme, token) = header.trim().split_once(' ')?;
+    let token = token.trim();
+    (scheme.eq_ignore_ascii_case("bearer") && !token.is_empty()).then_some(token)
+}
+
+/// 逐字节比较全部内容，耗时不随第一个不同字节的位置变化
+fn token_matches(provided: &str, expected: &str) -> bool {
+    provided.len() == expected.len()
+        && provided.bytes().zip(expected.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+/// /dictate/start 和 /dictate/stop 对应的热键信号；当前状态下没有可做的转换时返回 None。
+/// stop 在按住模式下相当于松开热键，在切换模式下相当于再按一次
+pub fn dictation_signal(route: Route, state: InputState, mode: RecordingMode) -> Option<HotkeySignal> {
+    match route {
+        Route::DictateStart if state.can_start_recording() => Some(HotkeySignal::Pressed(HotkeyAction::Transcribe)),
+        Route::DictateStop => {
+            let action = match state {
+                InputState::Recording => HotkeyAction::Transcribe,
+                InputState::RecordingTranslate => HotkeyAction::Translate,
+                _ => return None,
+            };
+            Some(match mode {
+                RecordingMode::Hold => HotkeySignal::Released(action),
+                RecordingMode::Toggle => HotkeySignal::Pressed(action),
+            })
+        }
+        _ => None,
+    }
+}
+
+fn json_response(status: u16, body: serde_json::Value) -> Response<std::io::Cursor<Vec<u8>>> {
+    let content_type = Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+    Response::from_string(body.to_string())
+        .with_status_code(status)
+        .with_header(content_type)
+}
+
+fn error_response(status: u16, message: &str) -> Response<std::io::Cursor<Vec<u8>>> {
+    json_response(status, json!({ "error": message }))
+}
+
+fn state_name(state: InputState) -> String {
+    format!("{:?}", state)
+}
+
+fn handle_dictation(route: Route) -> Response<std::io::Cursor<Vec<u8>>> {
+    let Some((state, mode)) = crate::voice_assistant::coordinator::running_dictation_state() else {
+        return error_response(409, "Voice assistant is not running");
+    };
+    match dictation_signal(route, state, mode) {
+        Some(signal) if send_hotkey_signal(signal) => {
+            info!("🌐 HTTP API: {:?} in state {:?}", signal, state);
+            json_response(202, json!({ "previous_state": state_name(state) }))
+        }
+        Some(_) => error_response(503, "Hotkey listener is not running"),
+        None => error_response(409, &format!("Cannot {} while {:?}",
+            if route == Route::DictateStart { "start dictation" } else { "stop dictation" }, state)),
+    }
+}
+
+/// 与设置页的“测试识别”走同一条流程（解码、预处理、本地失败时回退到云端），使用已保存的 ASR 配置
+async fn transcribe(audio: Vec<u8>) -> Result<crate::commands::AsrTestResponse, crate::commands::CommandError> {
+    let saved = crate::commands::get_asr_config_internal().await
+        .map_err(crate::commands::CommandError::Database)?
+        .into_iter()
+        .next();
+    let service_provider = match saved.as_ref().map(|c| c.service_provider.as_str()) {
+        Some("cloud") => "cloud",
+        _ => "local",
+    };
+
+    let request = crate::commands::AsrTestRequest {
+        audio_file_data: STANDARD.encode(&audio),
+        file_name: "http-api.wav".to_string(),
+        service_provider: service_provider.to_string(),
+        endpoint: saved.as_ref().and_then(|c| c.cloud_endpoint.clone()),
+        api_key: saved.as_ref().and_then(|c| c.cloud_api_key.clone()),
+        max_retries: None,
+        cloud_provider: saved.as_ref().map(|c| c.cloud_provider.clone()),
+        cloud_model: saved.as_ref().and_then(|c| c.cloud_model.clone()),
+        cloud_language: saved.as_ref().and_then(|c| c.cloud_language.clone()),
+    };
+    crate::commands::test_asr_transcription(request).await
+}
+
+fn handle_transcribe(request: &mut Request) -> Response<std::io::Cursor<Vec<u8>>> {
+    if request.body_length().is_some_and(|len| len as u64 > MAX_BODY_BYTES) {
+        return error_response(413, "Audio body too large");
+    }
+    let mut audio = Vec::new();
+    if let Err(e) = request.as_reader().take(MAX_BODY_BYTES + 1).read_to_end(&mut audio) {
+        return error_response(400, &format!("Failed to read request body: {}", e));
+    }
+    if audio.len() as u64 > MAX_BODY_BYTES {
+        return error_response(413, "Audio body too large");
+    }
+    if audio.is_empty() {
+        return error_response(400, "Request body must contain audio data");
+    }
+
+    match tauri::async_runtime::block_on(transcribe(audio)) {
+        Ok(response) if response.success => json_response(200, json!({
+            "text": response.transcription.unwrap_or_default(),
+            "processing_time_ms": response.processing_time_ms,
+        })),
+        Ok(response) => error_response(502, &response.message),
+        Err(e) => json_response(500, json!({ "error": e.to_string(), "code": e.code() })),
+    }
+}
+
+fn handle_state() -> Response<std::io::Cursor<Vec<u8>>> {
+    match crate::voice_assistant::coordinator::running_dictation_state() {
+        Some((state, mode)) => json_response(200, json!({
+            "running": true,
+            "state": state_name(state),
+            "recording_mode": mode.as_str(),
+        })),
+        None => json_response(200, json!({ "running": false, "state": state_name(InputState::Idle) })),
+    }
+}
+
+fn is_authorized(request: &Request) -> bool {
+    let Some(expected) = settings::current().http_api_token else {
+        return false;
+    };
+    request.headers().iter()
+        .find(|h| h.field.equiv("Authorization"))
+        .and_then(|h| bearer_token(h.value.as_str()))
+        .is_some_and(|token| token_matches(token, &expected))
+}
+
+fn handle(mut request: Request) {
+    let route = match_route(request.method(), request.url());
+    let response = if !is_authorized(&request) {
+        warn!("🚫 HTTP API: rejected unauthenticated {} {}", request.method(), request.url());
+        error_response(401, "Missing or invalid bearer token")
+            .with_header(Header::from_bytes(&b"WWW-Authenticate"[..], &b"Bearer"[..]).unwrap())
+    } else {
+        match route {
+            RouteMatch::Found(Route::Transcribe) => handle_transcribe(&mut request),
+            RouteMatch::Found(Route::State) => handle_state(),
+            RouteMatch::Found(route) => handle_dictation(route),
+            RouteMatch::MethodNotAllowed => error_response(405, "Method not allowed"),
+            RouteMatch::NotFound => error_response(404, "Not found"),
+        }
+    };
+    if let Err(e) = request.respond(response) {
+        warn!("⚠️ HTTP API: failed to send response: {}", e);
+    }
+}
+
+struct RunningServer {
+    port: u16,
+    server: Arc<Server>,
+    thread: JoinHandle<()>,
+}
+
+static SERVER: OnceLock<Mutex<Option<RunningServer>>> = OnceLock::new();
+
+fn running_server() -> &'static Mutex<Option<RunningServer>> {
+    SERVER.get_or_init(|| Mutex::new(None))
+}
+
+fn start(port: u16) -> Result<RunningServer, String> {
+    let server = Server::http(("127.0.0.1", port))
+        .map_err(|e| format!("Failed to bind 127.0.0.1:{}: {}", port, e))?;
+    let server = Arc::new(server);
+    let listener = server.clone();
+    let thread = std::thread::Builder::new()
+        .name("http-api".to_string())
+        .spawn(move || {
+            // unblock() 之后迭代结束；识别可能要几秒，每个请求单独一个线程，不挡住 /state
+            for request in listener.incoming_requests() {
+                if let Err(e) = std::thread::Builder::new().name("http-api-request".to_string()).spawn(move || handle(request)) {
+                    error!("❌ HTTP API: failed to spawn request handler: {}", e);
+                }
+            }
+        })
+        .map_err(|e| format!("Failed to spawn HTTP API thread: {}", e))?;
+    info!("🌐 Local HTTP API listening on http://127.0.0.1:{}", port);
+    Ok(RunningServer { port, server, thread })
+}
+
+/// 停止监听；正在处理的请求会继续完成
+pub fn stop() {
+    if let Some(running) = running_server().lock().unwrap().take() {
+        running.server.unblock();
+        if running.thread.join().is_err() {
+            warn!("⚠️ HTTP API thread panicked");
+        }
+        info!("🌐 Local HTTP API on port {} stopped", running.port);
+    }
+}
+
+/// 还没有 token 时生成一个并保存，设置页可以看到并复制
+pub async fn ensure_token() -> Result<String, String> {
+    if let Some(token) = settings::current().http_api_token {
+        return Ok(token);
+    }
+    regenerate_token().await
+}
+
+pub async fn regenerate_token() -> Result<String, String> {
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    let database = crate::database::Database::from_global_pool().await
+        .map_err(|e| format!("Failed to create database: {}", e))?;
+    settings::save(&database, settings::HTTP_API_TOKEN, &token).await?;
+    Ok(token)
+}
+
+/// 按当前设置启动、停止或换端口重启监听；启动时和 http_api_* 设置变化后调用
+pub async fn apply_settings() -> Result<(), String> {
+    let current = settings::current();
+    let running_port = running_server().lock().unwrap().as_ref().map(|r| r.port);
+
+    if !current.http_api_enabled {
+        stop();
+        return Ok(());
+    }
+    ensure_token().await?;
+    if running_port == Some(current.http_api_port) {
+        return Ok(());
+    }
+
+    stop();
+    let server = start(current.http_api_port)?;
+    *running_server().lock().unwrap() = Some(server);
+    Ok(())
+}
+
+/// 设置页显示的监听状态
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct HttpApiStatus {
+    pub enabled: bool,
+    pub listening: bool,
+    pub port: u16,
+    pub token: Option<String>,
+}
+
+#[tauri::command]
+pub async fn get_http_api_status() -> Result<HttpApiStatus, String> {
+    let current = settings::current();
+    Ok(HttpApiStatus {
+        enabled: current.http_api_enabled,
+        listening: running_server().lock().unwrap().is_some(),
+        port: current.http_api_port,
+        token: current.http_api_token,
+    })
+}
+
+/// 生成新的 token，旧 token 立即失效
+#[tauri::command]
+pub async fn regenerate_http_api_token() -> Result<HttpApiStatus, String> {
+    regenerate_token().await?;
+    info!("🔑 Regenerated local HTTP API token");
+    get_http_api_status().await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice_assistant::keyboard::next_state_for_signal;
+
+    #[test]
+    fn test_bearer_token_parsing() {
+        assert_eq!(bearer_token("Bearer abc123"), Some("abc123"));
+        assert_eq!(bearer_token("bearer  abc123 "), Some("abc123"));
+        assert_eq!(bearer_token("Basic abc123"), None);
+        assert_eq!(bearer_token("Bearer "), None);
+        assert_eq!(bearer_token("abc123"), None);
+
+        assert!(token_matches("abc123", "abc123"));
+        assert!(!token_matches("abc124", "abc123"));
+        assert!(!token_matches("abc", "abc123"));
+    }
+
+    #[test]
+    fn test_routes() {
+        assert_eq!(match_route(&Method::Post, "/dictate/start"), RouteMatch::Found(Route::DictateStart));
+        assert_eq!(match_route(&Method::Post, "/dictate/stop/"), RouteMatch::Found(Route::DictateStop));
+        assert_eq!(match_route(&Method::Get, "/state?verbose=1"), RouteMatch::Found(Route::State));
+        assert_eq!(match_route(&Method::Get, "/transcribe"), RouteMatch::MethodNotAllowed);
+        assert_eq!(match_route(&Method::Post, "/unknown"), RouteMatch::NotFound);
+    }
+
+    #[test]
+    fn test_dictation_signals_mirror_hotkey_transitions() {
+        for mode in [RecordingMode::Hold, RecordingMode::Toggle] {
+            let start = dictation_signal(Route::DictateStart, InputState::Idle, mode).unwrap();
+            assert_eq!(next_state_for_signal(InputState::Idle, start, mode), Some(InputState::Recording));
+            assert_eq!(dictation_signal(Route::DictateStart, InputState::Recording, mode), None);
+
+            let stop = dictation_signal(Route::DictateStop, InputState::Recording, mode).unwrap();
+            assert_eq!(next_state_for_signal(InputState::Recording, stop, mode), Some(InputState::Processing));
+            let stop = dictation_signal(Route::DictateStop, InputState::RecordingTranslate, mode).unwrap();
+            assert_eq!(next_state_for_signal(InputState::RecordingTranslate, stop, mode), Some(InputState::Translating));
+            assert_eq!(dictation_signal(Route::DictateStop, InputState::Idle, mode), None);
+        }
+    }
+}
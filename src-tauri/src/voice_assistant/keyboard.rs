@@ -1,14 +1,24 @@
 use rdev::{listen, EventType, Key};
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
 use std::time::{Duration, Instant};
 use std::process::Command;
-use crate::voice_assistant::{KeyboardManagerTrait, AsrProcessor, TranslateProcessor, InputState, VoiceError};
+use crate::voice_assistant::{KeyboardManagerTrait, AsrProcessor, AsrTranscript, TranslateProcessor, InputState, VoiceError};
 use crate::voice_assistant::hotkey_parser::ParsedHotkey;
 use std::collections::HashSet;
+use enigo::{Direction, Enigo, Key as EnigoKey, Keyboard, Settings};
 use crate::database::TypingDelays;
+use crate::voice_assistant::postprocess::{self, PostProcessOptions};
+use crate::voice_assistant::logger::{redact, keystroke_logging_enabled};
+use crate::voice_assistant::clipboard::{self, ClipboardSnapshot};
+use crate::voice_assistant::state_machine::{StateMachine, Transition};
+use crate::voice_assistant::coordinator::RecordedAudio;
+use crate::voice_assistant::coordinator::RecognitionInfo;
+use crate::voice_assistant::result_sink::{PendingResult, ResultSink};
+use tracing::{debug, error, info, warn};
 
 pub struct KeyboardManager {
-    state: Arc<Mutex<InputState>>,
+    state: Arc<Mutex<StateMachine>>,
     asr_processor: Arc<dyn AsrProcessor + Send + Sync>,
     translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>>,
     // 热键配置
@@ -16,13 +26,167 @@ pub struct KeyboardManager {
     translate_hotkey: Arc<Mutex<Option<ParsedHotkey>>>,
     // 按键状态跟踪
     pressed_keys: Arc<Mutex<HashSet<Key>>>,
-    hotkey_start_time: Arc<Mutex<Option<Instant>>>,
     temp_text_length: Arc<Mutex<usize>>,
-    original_clipboard: Arc<Mutex<Option<String>>>,
     // WAV文件保存配置
     save_wav_files: Arc<Mutex<bool>>,
     // 延迟配置
     typing_delays: Arc<Mutex<TypingDelays>>,
+    // 文本注入方式
+    text_injection_method: Arc<Mutex<TextInjectionMethod>>,
+    // ASR结果后处理配置
+    postprocess_options: Arc<Mutex<PostProcessOptions>>,
+    // 热键后端
+    hotkey_backend: Arc<Mutex<HotkeyBackend>>,
+    rdev_listening: bool,
+    rdev_active: Arc<AtomicBool>,
+    // 录音模式（按住/切换）和最长录音时长
+    recording_mode: Arc<Mutex<RecordingMode>>,
+    max_recording_duration: Arc<Mutex<Duration>>,
+    // 静音自动停止的静音时长，None 表示关闭
+    silence_auto_stop: Arc<Mutex<Option<Duration>>>,
+    // 录音前预录的时长，0 表示关闭
+    preroll_duration: Arc<Mutex<Duration>>,
+    // 识别期间排队的录音只复制到剪贴板，不直接输入
+    queued_to_clipboard: Arc<Mutex<bool>>,
+    // 循环切换 ASR 配置方案的热键
+    cycle_profile_hotkey: Arc<Mutex<Option<ParsedHotkey>>>,
+}
+
+/// 默认最长录音时长，超过后自动停止并识别
+pub const DEFAULT_MAX_RECORDING_SECS: i64 = 120;
+
+/// 静音自动停止的默认静音时长（毫秒）
+pub const DEFAULT_SILENCE_AUTO_STOP_MS: i64 = 2000;
+/// 录音开始后的宽限期，期间不因静音自动停止
+const SILENCE_GRACE_PERIOD: Duration = Duration::from_secs(1);
+/// 静音检测的轮询间隔
+const SILENCE_CHECK_INTERVAL: Duration = Duration::from_millis(200);
+
+/// 默认预录时长（毫秒），录音开始时拼到开头，避免切掉第一个字
+pub const DEFAULT_PREROLL_MS: i64 = 500;
+/// 信号线程空闲时检查预录设置变化的间隔
+const PREROLL_SYNC_INTERVAL: Duration = Duration::from_millis(500);
+
+/// 识别期间最多排队等待的录音数，超出后丢弃并提示
+const TRANSCRIPTION_QUEUE_CAPACITY: usize = 3;
+
+/// rdev 和插件热键回调发往信号线程的通道，信号线程未运行时为 None
+static HOTKEY_SIGNALS: Mutex<Option<std::sync::mpsc::Sender<HotkeySignal>>> = Mutex::new(None);
+
+/// 当前识别线程的队列统计，退出时用来判断是否还有识别在进行
+static TRANSCRIPTION_STATS: Mutex<Option<Arc<TranscriptionQueueStats>>> = Mutex::new(None);
+
+/// 应用正在退出：rdev 回调不再处理按键，也不再接受新的热键信号
+static SHUTTING_DOWN: AtomicBool = AtomicBool::new(false);
+
+/// 热键监听线程处理录音和识别所需的共享状态
+#[derive(Clone)]
+struct ListenerContext {
+    state: Arc<Mutex<StateMachine>>,
+    asr_processor: Arc<dyn AsrProcessor + Send + Sync>,
+    translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>>,
+    temp_text_length: Arc<Mutex<usize>>,
+    typing_delays: Arc<Mutex<TypingDelays>>,
+    text_injection_method: Arc<Mutex<TextInjectionMethod>>,
+    postprocess_options: Arc<Mutex<PostProcessOptions>>,
+    hotkey_backend: Arc<Mutex<HotkeyBackend>>,
+    recording_mode: Arc<Mutex<RecordingMode>>,
+    max_recording_duration: Arc<Mutex<Duration>>,
+    silence_auto_stop: Arc<Mutex<Option<Duration>>>,
+    preroll_duration: Arc<Mutex<Duration>>,
+    queued_to_clipboard: Arc<Mutex<bool>>,
+    save_wav_files: bool,
+    result_sink: Option<ResultSink>,
+}
+
+impl ListenerContext {
+    /// 结果交给写库任务保存；写库任务由 VoiceAssistant::start 启动，还没启动时只记录日志
+    fn save_result(&self, result: PendingResult) {
+        match &self.result_sink {
+            Some(sink) => sink.send(result),
+            None => tracing::error!(
+                "❌ Result sink is not started, dropping {} result from {}",
+                result.kind(),
+                result.processor_type().unwrap_or("-")
+            ),
+        }
+    }
+}
+
+/// 停止录音后交给识别线程的任务
+struct TranscriptionJob {
+    translate: bool,
+    audio_data: Vec<f32>,
+    sample_rate: u32,
+    audio_path: Option<String>,
+    /// 按下热键的时间，处理耗时从这里算起
+    started_at: Option<Instant>,
+    recording_error: Option<String>,
+    /// 入队时前面还有录音在识别
+    queued: bool,
+}
+
+impl TranscriptionJob {
+    /// 录音文件和按采样数计算的实际录音时长
+    fn recorded_audio(&self) -> RecordedAudio {
+        let duration_ms = (self.sample_rate > 0 && !self.audio_data.is_empty())
+            .then(|| self.audio_data.len() as i64 * 1000 / self.sample_rate as i64);
+        RecordedAudio {
+            file_path: self.audio_path.clone(),
+            duration_ms,
+        }
+    }
+}
+
+/// 队列深度统计，信号线程和识别线程共享
+#[derive(Default)]
+struct TranscriptionQueueStats {
+    pending: AtomicUsize,
+    busy: AtomicBool,
+    dropped: AtomicU64,
+}
+
+impl TranscriptionQueueStats {
+    fn is_working(&self) -> bool {
+        self.busy.load(Ordering::SeqCst) || self.pending.load(Ordering::SeqCst) > 0
+    }
+
+    fn emit(&self) {
+        crate::voice_assistant::coordinator::emit_transcription_queue_depth(
+            self.pending.load(Ordering::SeqCst),
+            self.busy.load(Ordering::SeqCst),
+            self.dropped.load(Ordering::SeqCst),
+        );
+    }
+}
+
+/// 信号线程持有的入队端
+struct TranscriptionQueue {
+    sender: std::sync::mpsc::SyncSender<TranscriptionJob>,
+    stats: Arc<TranscriptionQueueStats>,
+}
+
+impl TranscriptionQueue {
+    /// 入队；队列已满时丢弃这次录音并提示，不阻塞信号线程
+    fn submit(&self, job: TranscriptionJob) {
+        self.stats.pending.fetch_add(1, Ordering::SeqCst);
+        match self.sender.try_send(job) {
+            Ok(()) => {}
+            Err(std::sync::mpsc::TrySendError::Full(_)) => {
+                self.stats.pending.fetch_sub(1, Ordering::SeqCst);
+                self.stats.dropped.fetch_add(1, Ordering::SeqCst);
+                tracing::warn!(
+                    "Transcription queue full ({} waiting), dropping recording",
+                    TRANSCRIPTION_QUEUE_CAPACITY
+                );
+            }
+            Err(std::sync::mpsc::TrySendError::Disconnected(_)) => {
+                self.stats.pending.fetch_sub(1, Ordering::SeqCst);
+                error!("❌ Transcription worker is not running, recording discarded");
+            }
+        }
+        self.stats.emit();
+    }
 }
 
 impl KeyboardManager {
@@ -31,17 +195,26 @@ impl KeyboardManager {
         translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>>,
     ) -> Result<Self, VoiceError> {
         Ok(Self {
-            state: Arc::new(Mutex::new(InputState::Idle)),
+            state: Arc::new(Mutex::new(StateMachine::new())),
             asr_processor,
             translate_processor,
             transcribe_hotkey: Arc::new(Mutex::new(None)),
             translate_hotkey: Arc::new(Mutex::new(None)),
             pressed_keys: Arc::new(Mutex::new(HashSet::new())),
-            hotkey_start_time: Arc::new(Mutex::new(None)),
             temp_text_length: Arc::new(Mutex::new(0)),
-            original_clipboard: Arc::new(Mutex::new(None)),
             save_wav_files: Arc::new(Mutex::new(false)), // Default to false
             typing_delays: Arc::new(Mutex::new(TypingDelays::default())),
+            text_injection_method: Arc::new(Mutex::new(TextInjectionMethod::default())),
+            postprocess_options: Arc::new(Mutex::new(PostProcessOptions::default())),
+            hotkey_backend: Arc::new(Mutex::new(HotkeyBackend::default())),
+            rdev_listening: false,
+            rdev_active: Arc::new(AtomicBool::new(false)),
+            recording_mode: Arc::new(Mutex::new(RecordingMode::default())),
+            max_recording_duration: Arc::new(Mutex::new(Duration::from_secs(DEFAULT_MAX_RECORDING_SECS as u64))),
+            silence_auto_stop: Arc::new(Mutex::new(None)),
+            preroll_duration: Arc::new(Mutex::new(Duration::from_millis(DEFAULT_PREROLL_MS as u64))),
+            queued_to_clipboard: Arc::new(Mutex::new(false)),
+            cycle_profile_hotkey: Arc::new(Mutex::new(None)),
         })
     }
 
@@ -51,7 +224,7 @@ impl KeyboardManager {
         new_asr_processor: Option<Arc<dyn AsrProcessor + Send + Sync>>,
         new_translate_processor: Option<Arc<dyn TranslateProcessor + Send + Sync>>,
     ) -> Result<(), VoiceError> {
-        println!("🔄 Updating KeyboardManager processors...");
+        debug!("🔄 Updating KeyboardManager processors...");
 
         // 更新处理器引用
         if let Some(asr) = new_asr_processor {
@@ -59,25 +232,25 @@ impl KeyboardManager {
         }
         self.translate_processor = new_translate_processor;
 
-        println!("✅ KeyboardManager processors updated successfully");
+        info!("✅ KeyboardManager processors updated successfully");
         Ok(())
     }
 
     /// 🔥 清除处理器引用 - 用于停止服务时释放模型内存
     pub fn clear_processors(&mut self) {
-        println!("🗑️ KeyboardManager: Clearing processor references to free memory...");
+        debug!("🗑️ KeyboardManager: Clearing processor references to free memory...");
         // 将 ASR 处理器替换为一个空的默认实现
         // 这样可以释放原有的 Arc 引用
         self.asr_processor = Arc::new(DefaultAsrProcessor);
         self.translate_processor = None;
-        println!("✅ KeyboardManager: Processor references cleared");
+        info!("✅ KeyboardManager: Processor references cleared");
     }
 
     /// 设置热键配置
     pub fn set_hotkeys(&mut self, transcribe_key: &str, translate_key: &str) -> Result<(), VoiceError> {
-        println!("🔧 Setting hotkeys:");
-        println!("  - Transcribe: {}", transcribe_key);
-        println!("  - Translate: {}", translate_key);
+        debug!("🔧 Setting hotkeys:");
+        debug!("  - Transcribe: {}", transcribe_key);
+        debug!("  - Translate: {}", translate_key);
 
         // 解析热键
         let transcribe_parsed = ParsedHotkey::parse(transcribe_key)
@@ -86,7 +259,7 @@ impl KeyboardManager {
         let translate_parsed = ParsedHotkey::parse(translate_key)
             .map_err(|e| VoiceError::Audio(format!("Failed to parse translate hotkey: {}", e)))?;
 
-        println!("✅ Parsed hotkeys successfully");
+        info!("✅ Parsed hotkeys successfully");
         
         *self.transcribe_hotkey.lock().unwrap() = Some(transcribe_parsed);
         *self.translate_hotkey.lock().unwrap() = Some(translate_parsed);
@@ -95,41 +268,267 @@ impl KeyboardManager {
     }
 
     pub fn start_listening(&mut self) {
+        let backend = *self.hotkey_backend.lock().unwrap();
+        info!("⌨️ Hotkey backend: {}, recording mode: {}", backend.as_str(), self.recording_mode.lock().unwrap().as_str());
+
+        // 两种后端都只负责产生按下/松开信号，录音和识别统一在信号线程中处理
+        self.start_signal_worker();
+        if backend == HotkeyBackend::Rdev {
+            self.start_rdev_listener();
+        }
+    }
+
+    /// 停止信号线程；rdev::listen 无法中途停止，只能通过 rdev_active 标记忽略事件
+    pub fn stop_listening(&self) {
+        self.rdev_active.store(false, Ordering::SeqCst);
+        if HOTKEY_SIGNALS.lock().unwrap().take().is_some() {
+            info!("🛑 Hotkey signal worker stopped");
+        }
+    }
+
+    fn listener_context(&self) -> ListenerContext {
+        ListenerContext {
+            state: self.state.clone(),
+            asr_processor: self.asr_processor.clone(),
+            translate_processor: self.translate_processor.clone(),
+            temp_text_length: self.temp_text_length.clone(),
+            typing_delays: self.typing_delays.clone(),
+            text_injection_method: self.text_injection_method.clone(),
+            postprocess_options: self.postprocess_options.clone(),
+            hotkey_backend: self.hotkey_backend.clone(),
+            recording_mode: self.recording_mode.clone(),
+            max_recording_duration: self.max_recording_duration.clone(),
+            silence_auto_stop: self.silence_auto_stop.clone(),
+            preroll_duration: self.preroll_duration.clone(),
+            queued_to_clipboard: self.queued_to_clipboard.clone(),
+            save_wav_files: *self.save_wav_files.lock().unwrap(),
+            result_sink: ResultSink::global(),
+        }
+    }
+
+    /// 信号线程：按 rdev 或插件发来的按下/松开事件驱动状态转换、录音和识别
+    fn start_signal_worker(&mut self) {
+        let ctx = self.listener_context();
+        let (sender, receiver) = std::sync::mpsc::channel::<HotkeySignal>();
+        // 替换旧的 sender 后，旧线程的 recv 返回错误并退出
+        *HOTKEY_SIGNALS.lock().unwrap() = Some(sender);
+
+        // 识别在单独的线程中进行，识别期间的录音排队等待，不会被丢弃
+        let (job_sender, job_receiver) = std::sync::mpsc::sync_channel::<TranscriptionJob>(TRANSCRIPTION_QUEUE_CAPACITY);
+        let queue = TranscriptionQueue {
+            sender: job_sender,
+            stats: Arc::new(TranscriptionQueueStats::default()),
+        };
+        *TRANSCRIPTION_STATS.lock().unwrap() = Some(queue.stats.clone());
+        Self::spawn_transcription_worker(ctx.clone(), job_receiver, queue.stats.clone());
+
+        tokio::task::spawn_blocking(move || {
+            let mut recorder: Option<crate::voice_assistant::AudioRecorder> = None;
+            // 空闲时保持预录的 recorder，开始录音时直接接管
+            let mut preroll_recorder: Option<crate::voice_assistant::AudioRecorder> = None;
+            let mut preroll_failed = false;
+            // 每次录音递增，超时/静音信号只对发出它的那次录音有效
+            let mut recording_session: u64 = 0;
+            debug!("📁 Save WAV Files setting in hotkey worker: {}", ctx.save_wav_files);
+
+            loop {
+                if recorder.is_none() {
+                    Self::sync_preroll(&ctx, &mut preroll_recorder, &mut preroll_failed);
+                }
+                let signal = match receiver.recv_timeout(PREROLL_SYNC_INTERVAL) {
+                    Ok(signal) => signal,
+                    Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+                    Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => break,
+                };
+
+                if let HotkeySignal::MaxDuration(session) | HotkeySignal::Silence(session) = signal {
+                    if session != recording_session {
+                        continue;
+                    }
+                }
+
+                let mode = *ctx.recording_mode.lock().unwrap();
+                let now = Instant::now();
+                let (current_state, next_state, started_at) = {
+                    let machine = ctx.state.lock().unwrap();
+                    let current_state = machine.state();
+                    let Some(next_state) = next_state_for_signal(current_state, signal, mode) else {
+                        continue;
+                    };
+                    (current_state, next_state, machine.recording_started_at())
+                };
+
+                if next_state.is_recording() {
+                    recording_session += 1;
+                    if recorder.is_none() {
+                        recorder = preroll_recorder.take();
+                    }
+                    // 录音结束后允许重新尝试打开预录
+                    preroll_failed = false;
+                    Self::spawn_max_duration_timer(recording_session, *ctx.max_recording_duration.lock().unwrap());
+                } else if mode == RecordingMode::Hold
+                    && *ctx.hotkey_backend.lock().unwrap() == HotkeyBackend::Plugin
+                {
+                    // 插件只在按下时通知一次，防误触改为松开时检查按住时长（rdev 在按下时已检查）
+                    if let Some(held) = ctx.state.lock().unwrap().released_too_early(now) {
+                        info!("⏭️ Hotkey released after {:?}, discarding recording (anti-mistouch)", held);
+                        Self::discard_recording(&ctx, &mut recorder);
+                        continue;
+                    }
+                }
+
+                if let Some(reason) = signal.auto_stop_reason() {
+                    match signal {
+                        HotkeySignal::MaxDuration(_) => {
+                            info!("⏱️ Maximum recording duration reached, stopping recording automatically")
+                        }
+                        _ => info!("🔇 Silence detected, stopping recording automatically"),
+                    }
+                    let elapsed_ms = started_at.map_or(0, |start| start.elapsed().as_millis() as u64);
+                    crate::voice_assistant::coordinator::emit_recording_auto_stopped(reason, elapsed_ms);
+                }
+
+                let transition = {
+                    let mut machine = ctx.state.lock().unwrap();
+                    match next_state {
+                        InputState::Recording => machine.start_recording(false, now),
+                        InputState::RecordingTranslate => machine.start_recording(true, now),
+                        _ => machine.stop_to_processing(),
+                    }
+                };
+                let transition = match transition {
+                    Ok(transition) => transition,
+                    Err(e) => {
+                        warn!("⚠️ Hotkey {:?} ignored: {}", signal, e);
+                        continue;
+                    }
+                };
+                debug!("⌨️ Hotkey {:?}: {:?} → {:?}", signal, current_state, next_state);
+                Self::emit_transition(transition);
+                Self::handle_state_change(&ctx, &mut recorder, next_state, started_at, &queue);
+
+                if next_state.is_recording() {
+                    let silence = *ctx.silence_auto_stop.lock().unwrap();
+                    if let (Some(silence), Some(rec)) = (silence, recorder.as_ref()) {
+                        let session = recording_session;
+                        let detector = crate::voice_assistant::asr::SilenceDetector::new(
+                            rec.sample_rate(),
+                            silence,
+                            SILENCE_GRACE_PERIOD,
+                        );
+                        rec.start_silence_monitor(SILENCE_CHECK_INTERVAL, detector, move || {
+                            send_hotkey_signal(HotkeySignal::Silence(session));
+                        });
+                    }
+                }
+            }
+            info!("👋 Hotkey signal worker exited");
+        });
+    }
+
+    /// 按当前设置打开/关闭/重建预录；打开失败后直到下一次录音前不再重试，避免刷屏
+    fn sync_preroll(
+        ctx: &ListenerContext,
+        preroll_recorder: &mut Option<crate::voice_assistant::AudioRecorder>,
+        preroll_failed: &mut bool,
+    ) {
+        let duration = *ctx.preroll_duration.lock().unwrap();
+        if duration.is_zero() {
+            if preroll_recorder.take().is_some() {
+                info!("⏪ Pre-roll disabled, microphone released");
+            }
+            return;
+        }
+        if preroll_recorder.as_ref().is_some_and(|r| r.preroll_duration() == duration) || *preroll_failed {
+            return;
+        }
+
+        let result = crate::voice_assistant::AudioRecorder::new().and_then(|mut r| {
+            r.set_save_wav_files(ctx.save_wav_files);
+            r.start_preroll(duration).map(|_| r)
+        });
+        match result {
+            Ok(r) => *preroll_recorder = Some(r),
+            Err(e) => {
+                warn!("⚠️ Failed to start pre-roll, recording will start without it: {}", e);
+                *preroll_recorder = None;
+                *preroll_failed = true;
+            }
+        }
+    }
+
+    /// 录音达到最长时长后发送停止信号，作为切换模式下忘记再按一次热键的保护
+    fn spawn_max_duration_timer(session: u64, max_duration: Duration) {
+        std::thread::spawn(move || {
+            std::thread::sleep(max_duration);
+            send_hotkey_signal(HotkeySignal::MaxDuration(session));
+        });
+    }
+
+    /// 按键时间过短：停止录音并回到 Idle，不做识别
+    fn discard_recording(ctx: &ListenerContext, recorder: &mut Option<crate::voice_assistant::AudioRecorder>) {
+        if let Some(mut rec) = recorder.take() {
+            let _ = rec.stop_recording_with_option(false);
+        }
+        if let Ok(transition) = ctx.state.lock().unwrap().cancel() {
+            Self::emit_transition(transition);
+        }
+    }
+
+    /// 把状态变化通知前端；状态没变时不发送
+    fn emit_transition(transition: Transition) {
+        if transition.changed() {
+            crate::voice_assistant::coordinator::emit_voice_assistant_state_from_keyboard(&transition.to);
+        }
+    }
+
+    /// rdev 后端：匹配热键后向信号线程发送按下/松开事件
+    fn start_rdev_listener(&mut self) {
+        self.rdev_active.store(true, Ordering::SeqCst);
+        // rdev::listen 会一直阻塞，同一个 KeyboardManager 只启动一次
+        if self.rdev_listening {
+            debug!("ℹ️ rdev keyboard listener already running");
+            return;
+        }
+        self.rdev_listening = true;
+
         let state = self.state.clone();
-        let _asr_processor = self.asr_processor.clone();
-        let _translate_processor = self.translate_processor.clone();
         let transcribe_hotkey = self.transcribe_hotkey.clone();
         let translate_hotkey = self.translate_hotkey.clone();
         let pressed_keys = self.pressed_keys.clone();
-        let hotkey_start_time = self.hotkey_start_time.clone();
-        let temp_text_length = self.temp_text_length.clone();
-        let original_clipboard = self.original_clipboard.clone();
+        let recording_mode = self.recording_mode.clone();
+        let rdev_active = self.rdev_active.clone();
+        let cycle_profile_hotkey = self.cycle_profile_hotkey.clone();
 
         // Use tokio::task::spawn_blocking to avoid runtime conflicts with rdev
-        // 获取save_wav_files配置传递到回调中
-        let save_wav_files_config = *self.save_wav_files.lock().unwrap();
-        println!("📁 Save WAV Files setting from config: {}", save_wav_files_config);
+        tokio::task::spawn_blocking(move || {
+            // 已发送按下信号、尚未物理松开的热键
+            let mut active_hotkey: Option<HotkeyAction> = None;
+            // 切换方案的热键已触发、尚未松开，按住时的自动重复不再切换
+            let mut cycle_pressed = false;
 
-        // 克隆延迟配置以便在闭包中使用
-        let typing_delays_for_callback = self.typing_delays.clone();
+            crate::voice_assistant::hotkey_capture::mark_rdev_listener_running();
+            if let Err(e) = listen(move |event| {
+                // 设置页正在录制新热键：按键交给录制处理，不触发听写
+                if crate::voice_assistant::hotkey_capture::handle_event(&event.event_type) {
+                    pressed_keys.lock().unwrap().clear();
+                    return;
+                }
 
-        tokio::task::spawn_blocking(move || {
-            let mut recorder: Option<crate::voice_assistant::AudioRecorder> = None;
+                // 停止服务、切换到插件后端或应用退出后，rdev 线程仍在运行但不再处理按键
+                if !rdev_active.load(Ordering::SeqCst) || is_shutting_down() {
+                    return;
+                }
 
-            // 使用传递过来的save_wav_files配置
-            let save_wav_files = save_wav_files_config;
-            println!("📁 Save WAV Files setting in callback: {}", save_wav_files);
-            let mut last_state = InputState::Idle;
-            let mut recording_started = false;
-            let mut hotkey_press_time: Option<Instant> = None;
-            const HOTKEY_DELAY_THRESHOLD: Duration = Duration::from_millis(300); // 防误触阈值
+                let current_state = state.lock().unwrap().state();
+                let recording = current_state.is_recording();
+                let mode = *recording_mode.lock().unwrap();
 
-            if let Err(e) = listen(move |event| {
                 match event.event_type {
                     EventType::KeyPress(key) => {
-                        // 🔥 关键优化：在非Idle状态下，提前返回忽略所有按键
-                        let current_state = *state.lock().unwrap();
-                        if current_state != InputState::Idle {
+                        // 🔥 关键优化：Idle 和识别中（排队下一次录音）之外，只有切换模式的录音状态需要处理按键（第二次按下停止录音）
+                        let idle_or_processing = matches!(current_state, InputState::Idle | InputState::Processing | InputState::Translating);
+                        if !(idle_or_processing || (recording && mode == RecordingMode::Toggle)) {
                             // 不打印日志，完全静默忽略所有按键事件
                             return;
                         }
@@ -137,340 +536,572 @@ impl KeyboardManager {
                         let mut keys = pressed_keys.lock().unwrap();
                         // 只有当按键是新的时候才记录日志和插入
                         let is_new_key = !keys.contains(&key);
-                        if is_new_key {
-                            println!("⌨️  KeyPress detected: {:?}", key);
+                        // 按键日志默认关闭，需 VOICETYPE_DEBUG_KEYS=1 显式开启
+                        if is_new_key && keystroke_logging_enabled() {
+                            tracing::trace!("KeyPress detected: {:?}", key);
                         }
                         keys.insert(key);
-                        
-                        // 检查是否应该开始录音
-                        let transcribe_hotkey_guard = transcribe_hotkey.lock().unwrap();
-                        let translate_hotkey_guard = translate_hotkey.lock().unwrap();
-                        let current_state = *state.lock().unwrap();
-                        
-                        // 只在有按键变化时输出详细日志
-                        if is_new_key {
-                            println!("🔑 Current state: {:?}, Recording started: {}", current_state, recording_started);
-                            println!("🔑 Pressed keys: {:?}", keys);
-                        }
-                        
-                        // 检查转录热键
-                        if let Some(ref transcribe_hotkey) = *transcribe_hotkey_guard {
-                            // 🔥 只在Idle状态下响应热键，避免enigo模拟输入触发死循环
-                            if transcribe_hotkey.matches(&*keys) && current_state.can_start_recording() && !recording_started && current_state == InputState::Idle {
-                                // 检查按键持续时间（防误触）
-                                let current_time = Instant::now();
-                                let should_trigger = if let Some(press_time) = hotkey_press_time {
-                                    current_time.duration_since(press_time) >= HOTKEY_DELAY_THRESHOLD
-                                } else {
-                                    // 首次按下，记录时间但不触发
-                                    hotkey_press_time = Some(current_time);
-                                    false
-                                };
-
-                                if should_trigger {
-                                    println!("🎤 Transcribe hotkey pressed - starting recording state...");
-
-                                    // IMPORTANT: Clear keys immediately to prevent repeated triggers
-                                    keys.clear();
-
-                                    *hotkey_start_time.lock().unwrap() = Some(Instant::now());
-                                    *state.lock().unwrap() = InputState::Recording; // Start recording state
-                                    // Emit state change event
-                                    crate::voice_assistant::coordinator::emit_voice_assistant_state_from_keyboard(&InputState::Recording);
-                                    recording_started = true;
-                                    hotkey_press_time = None; // 重置按键时间
-                                }
 
-                                // 保存原始剪贴板
-                                let mut clipboard = original_clipboard.lock().unwrap();
-                                if clipboard.is_none() {
-                                    if let Ok(content) = get_clipboard_content() {
-                                        *clipboard = Some(content);
-                                    }
-                                }
-                            }
+                        // 热键按住期间的自动重复不算新的按下
+                        if active_hotkey.is_some() {
+                            return;
                         }
-                        
-                        // 检查翻译热键
-                        if let Some(ref translate_hotkey) = *translate_hotkey_guard {
-                            // 🔥 只在Idle状态下响应热键，避免enigo模拟输入触发死循环
-                            if translate_hotkey.matches(&*keys) && current_state.can_start_recording() && !recording_started && current_state == InputState::Idle {
-                                // 检查按键持续时间（防误触）
-                                let current_time = Instant::now();
-                                let should_trigger = if let Some(press_time) = hotkey_press_time {
-                                    current_time.duration_since(press_time) >= HOTKEY_DELAY_THRESHOLD
-                                } else {
-                                    // 首次按下，记录时间但不触发
-                                    hotkey_press_time = Some(current_time);
-                                    false
-                                };
-
-                                if should_trigger {
-                                    println!("🌐 Translate hotkey pressed - starting recording translate state...");
-
-                                    // IMPORTANT: Clear keys immediately to prevent repeated triggers
-                                    keys.clear();
-
-                                    *hotkey_start_time.lock().unwrap() = Some(Instant::now());
-                                    *state.lock().unwrap() = InputState::RecordingTranslate; // Start recording translate state
-                                    // Emit state change event
-                                    crate::voice_assistant::coordinator::emit_voice_assistant_state_from_keyboard(&InputState::RecordingTranslate);
-                                    recording_started = true;
-                                    hotkey_press_time = None; // 重置按键时间
-                                }
 
-                                // 保存原始剪贴板
-                                let mut clipboard = original_clipboard.lock().unwrap();
-                                if clipboard.is_none() {
-                                    if let Ok(content) = get_clipboard_content() {
-                                        *clipboard = Some(content);
-                                    }
-                                }
+                        let matched = [
+                            (HotkeyAction::Transcribe, &transcribe_hotkey),
+                            (HotkeyAction::Translate, &translate_hotkey),
+                        ]
+                        .into_iter()
+                        .find(|(_, hotkey)| {
+                            hotkey.lock().unwrap().as_ref().map_or(false, |hotkey| hotkey.matches(&*keys))
+                        })
+                        .map(|(action, _)| action);
+
+                        let Some(action) = matched else {
+                            let cycle = cycle_profile_hotkey.lock().unwrap().as_ref().map_or(false, |hotkey| hotkey.matches(&*keys));
+                            if cycle && !cycle_pressed {
+                                debug!("🎹 ASR profile cycle hotkey pressed");
+                                keys.clear();
+                                cycle_pressed = true;
+                                crate::voice_assistant::coordinator::cycle_asr_profile_from_hotkey();
                             }
-                        }
-                    }
-                    
-                    EventType::KeyRelease(key) => {
-                        // 🔥 优化：在非录音状态下，提前返回忽略所有按键释放事件
-                        let current_state = *state.lock().unwrap();
-                        if !matches!(current_state, InputState::Recording | InputState::RecordingTranslate | InputState::Idle) {
-                            // 在Processing/Translating等状态下，完全忽略按键释放
                             return;
+                        };
+
+                        // 按住模式需要持续按住一段时间才触发（防误触）；切换模式按下即触发
+                        let should_trigger = mode == RecordingMode::Toggle
+                            || state.lock().unwrap().hotkey_down(Instant::now());
+
+                        if should_trigger {
+                            debug!("🎹 {:?} hotkey pressed", action);
+                            // IMPORTANT: Clear keys immediately to prevent repeated triggers
+                            keys.clear();
+                            active_hotkey = Some(action);
+                            send_hotkey_signal(HotkeySignal::Pressed(action));
                         }
+                    }
 
+                    EventType::KeyRelease(key) => {
+                        // 识别期间也要处理松开，否则 active_hotkey 不会被清除
                         let mut keys = pressed_keys.lock().unwrap();
-                        println!("🔓 KeyRelease detected: {:?}", key);
                         keys.remove(&key);
-                        println!("🔑 Remaining keys after release: {:?}", keys);
+                        if keystroke_logging_enabled() {
+                            tracing::trace!("KeyRelease detected: {:?}, remaining keys: {:?}", key, keys);
+                        }
 
-                        // 重置按键时间戳（当所有按键都释放时）
+                        // 所有按键都释放时重置按键时间戳，并通知热键已松开
+                        // 按住模式下松开进入识别；切换模式下松开只是允许下一次按下
                         if keys.is_empty() {
-                            hotkey_press_time = None;
-
-                            // 检查是否在录音状态，如果是，则转换到处理状态
-                            match current_state {
-                                InputState::Recording => {
-                                    println!("🎤 Transcribe hotkey released - switching to Processing state...");
-                                    *state.lock().unwrap() = InputState::Processing;
-                                    // Emit state change event
-                                    crate::voice_assistant::coordinator::emit_voice_assistant_state_from_keyboard(&InputState::Processing);
-                                }
-                                InputState::RecordingTranslate => {
-                                    println!("🌐 Translate hotkey released - switching to Translating state...");
-                                    *state.lock().unwrap() = InputState::Translating;
-                                    // Emit state change event
-                                    crate::voice_assistant::coordinator::emit_voice_assistant_state_from_keyboard(&InputState::Translating);
-                                }
-                                _ => {}
+                            cycle_pressed = false;
+                            state.lock().unwrap().hotkey_up();
+                            if let Some(action) = active_hotkey.take() {
+                                debug!("🎹 {:?} hotkey released", action);
+                                send_hotkey_signal(HotkeySignal::Released(action));
                             }
                         }
-
-                        // For direct processing, state reset happens in the processing handlers
-                        // We don't need to reset state here anymore
                     }
                     _ => {}
                 }
+            }) {
+                error!("Error listening for keyboard events: {:?}", e);
+            }
+        });
+    }
 
-                // 状态变化检测和处理
-                let current_state = *state.lock().unwrap();
-                if current_state != last_state {
-                    last_state = current_state;
+    /// 录音/识别状态变化时执行对应动作，rdev 监听和插件热键信号共用
+    fn handle_state_change(
+        ctx: &ListenerContext,
+        recorder: &mut Option<crate::voice_assistant::AudioRecorder>,
+        current_state: InputState,
+        started_at: Option<Instant>,
+        queue: &TranscriptionQueue,
+    ) {
+        let save_wav_files = ctx.save_wav_files;
+
+        match current_state {
+            InputState::Recording => {
+                // 开始转录录音
+                info!("🎤 Recording state - starting real audio recording...");
+                Self::start_recording_internal(recorder, save_wav_files);
+            }
+            InputState::RecordingTranslate => {
+                // 开始翻译录音
+                info!("🌐 Recording Translate state - starting real audio recording...");
+                Self::start_recording_internal(recorder, save_wav_files);
+            }
+            InputState::Processing | InputState::Translating => {
+                // 停止录音后交给识别线程，信号线程马上可以开始下一次录音
+                let translate = current_state == InputState::Translating;
+                debug!("🔄 Entering {:?} state...", current_state);
+                let job = Self::take_transcription_job(ctx, recorder, translate, started_at, queue.stats.is_working());
+                queue.submit(job);
+            }
+            _ => {}
+        }
+    }
 
-                    match current_state {
-                        InputState::Recording => {
-                            // 开始转录录音
-                            println!("🎤 Recording state - starting real audio recording...");
-                            Self::start_recording_internal(&mut recorder, save_wav_files);
+    /// 停止录音并取出音频，生成交给识别线程的任务；不做任何识别，信号线程可以立即接受下一次录音
+    fn take_transcription_job(
+        ctx: &ListenerContext,
+        recorder: &mut Option<crate::voice_assistant::AudioRecorder>,
+        translate: bool,
+        started_at: Option<Instant>,
+        queued: bool,
+    ) -> TranscriptionJob {
+        let mut job = TranscriptionJob {
+            translate,
+            audio_data: Vec::new(),
+            sample_rate: 0,
+            audio_path: None,
+            started_at,
+            recording_error: None,
+            queued,
+        };
+
+        match recorder.take() {
+            Some(mut rec) => {
+                info!("🛑 Stopping recording...");
+                // Get audio data and sample rate BEFORE stopping recording (to avoid data loss)
+                job.audio_data = rec.get_audio_data();
+                job.sample_rate = rec.get_sample_rate();
+                debug!("📊 Got audio data: {} samples", job.audio_data.len());
+
+                match rec.stop_recording_with_option(ctx.save_wav_files) {
+                    Ok(path) => {
+                        info!("✅ Recording stopped successfully");
+                        // "memory://" means the WAV was not written to disk
+                        if !path.starts_with("memory://") {
+                            job.audio_path = Some(path);
                         }
-                        InputState::RecordingTranslate => {
-                            // 开始翻译录音
-                            println!("🌐 Recording Translate state - starting real audio recording...");
-                            Self::start_recording_internal(&mut recorder, save_wav_files);
+                    }
+                    Err(e) => {
+                        error!("❌ Failed to stop recording: {}", e);
+                        // 翻译时仍尝试使用已取到的音频
+                        if !translate {
+                            job.recording_error = Some(format!("Recording error: {}", e));
                         }
-                        InputState::Processing => {
-                            // Process recorded audio with real ASR
-                            println!("🔄 Entering Processing state...");
-                            println!("🎙️ Processing audio with real ASR...");
-
-                            // Stop recording and get audio data
-                            // Process ASR - can now be done synchronously since we use spawn_blocking internally
-                            let asr_result = if let Some(ref mut rec) = recorder {
-                                println!("🛑 Stopping recording...");
-
-                                // Get audio data BEFORE stopping recording (to avoid data loss)
-                                let audio_data = rec.get_audio_data();
-                                println!("📊 Got audio data: {} samples", audio_data.len());
-
-                                match rec.stop_recording_with_option(save_wav_files) {
-                                    Ok(_) => {
-                                        println!("✅ Recording stopped successfully");
-
-                                        if audio_data.is_empty() {
-                                            println!("⚠️ No audio data recorded, using mock text");
-                                            Some("No audio recorded - please check microphone".to_string())
-                                        } else {
-                                            // Convert to WAV format for ASR processing
-                                            match Self::convert_to_wav_bytes(&audio_data, rec.get_sample_rate()) {
-                                                Ok(wav_bytes) => {
-                                                    println!("🔄 Converting {} audio samples to WAV format ({} bytes)", audio_data.len(), wav_bytes.len());
-
-                                                    // Process with ASR - this now uses spawn_blocking internally
-                                                    use std::io::Cursor;
-                                                    match _asr_processor.process_audio(Cursor::new(wav_bytes), crate::voice_assistant::Mode::Transcriptions, "") {
-                                                        Ok(result) => {
-                                                            println!("✅ ASR processing successful");
-                                                            Some(result)
-                                                        }
-                                                        Err(e) => {
-                                                            println!("❌ ASR processing failed: {}", e);
-                                                            Some(format!("ASR Error: {}", e))
-                                                        }
-                                                    }
-                                                }
-                                                Err(e) => {
-                                                    println!("❌ Failed to convert audio to WAV: {}", e);
-                                                    Some(format!("Audio conversion error: {}", e))
-                                                }
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        println!("❌ Failed to stop recording: {}", e);
-                                        Some(format!("Recording error: {}", e))
-                                    }
-                                }
+                    }
+                }
+            }
+            None => {
+                error!("❌ No recorder available");
+                job.recording_error = Some("No recorder available".to_string());
+            }
+        }
+        job
+    }
+
+    /// 识别线程：按录音顺序逐个识别，识别期间新的录音在队列中等待
+    fn spawn_transcription_worker(
+        ctx: ListenerContext,
+        receiver: std::sync::mpsc::Receiver<TranscriptionJob>,
+        stats: Arc<TranscriptionQueueStats>,
+    ) {
+        std::thread::spawn(move || {
+            // 信号线程退出后 sender 被释放，队列处理完即退出
+            for job in receiver {
+                // 先标记 busy 再减 pending，中间不会出现两者都为 0 的瞬间
+                stats.busy.store(true, Ordering::SeqCst);
+                stats.pending.fetch_sub(1, Ordering::SeqCst);
+                stats.emit();
+
+                // 用户已开始下一次录音时不覆盖录音状态
+                if let Ok(transition) = ctx.state.lock().unwrap().begin_processing(job.translate) {
+                    Self::emit_transition(transition);
+                }
+
+                if let Some(speech) = Self::empty_recording_speech(&job) {
+                    ctx.save_result(PendingResult::Skipped {
+                        translate: job.translate,
+                        speech_ms: speech.as_millis() as i64,
+                        audio: job.recorded_audio(),
+                    });
+                } else if job.translate {
+                    Self::process_translation_job(&ctx, job);
+                } else {
+                    Self::process_transcription_job(&ctx, job);
+                }
+
+                stats.busy.store(false, Ordering::SeqCst);
+                if stats.pending.load(Ordering::SeqCst) == 0 {
+                    if let Ok(transition) = ctx.state.lock().unwrap().complete() {
+                        debug!("🔄 Resetting state after processing completion...");
+                        Self::emit_transition(transition);
+                    }
+                }
+                stats.emit();
+            }
+            info!("👋 Transcription worker exited");
+        });
+    }
+
+    /// 输出识别结果：排队的录音在开启 queued_to_clipboard 时只复制到剪贴板，其余直接输入
+    fn deliver_text(ctx: &ListenerContext, text: &str, queued: bool) {
+        if queued && *ctx.queued_to_clipboard.lock().unwrap() {
+            clipboard::set_text(text);
+            info!("📋 Queued result copied to clipboard ({} chars)", text.chars().count());
+            return;
+        }
+        Self::type_text_internal(
+            &ctx.state,
+            &ctx.temp_text_length,
+            text,
+            None,
+            &ctx.typing_delays.lock().unwrap(),
+            *ctx.text_injection_method.lock().unwrap(),
+        );
+    }
+
+    /// 用退格删除上一次输入的结果
+    fn delete_last_result(ctx: &ListenerContext) {
+        let count = std::mem::take(&mut *ctx.temp_text_length.lock().unwrap());
+        if count == 0 {
+            info!("🗣️ Nothing to delete for dictation command");
+            return;
+        }
+        info!("🗣️ Deleting last result ({} chars)", count);
+        if let Err(e) = delete_chars_with(&SystemInjectionBackend, count) {
+            error!("❌ Failed to delete last result: {}", e);
+        }
+    }
+
+    fn process_transcription_job(ctx: &ListenerContext, job: TranscriptionJob) {
+        info!("🎙️ Processing audio with real ASR...");
+        let mut latency = crate::database::LatencyBreakdown::default();
+        let audio = job.recorded_audio();
+        // 失败的识别也写入历史，计入服务状态和失败统计
+        let mut failure: Option<String> = None;
+        let mut recognition = RecognitionInfo::default();
+        // 自动翻译时的识别原文
+        let mut translated_from: Option<String> = None;
+
+        let asr_result = if let Some(error) = job.recording_error {
+            failure = Some(error.clone());
+            Some(error)
+        } else if job.audio_data.is_empty() {
+            warn!("⚠️ No audio data recorded, using mock text");
+            Some("No audio recorded - please check microphone".to_string())
+        } else {
+            latency.record_ms = audio.duration_ms;
+
+            // Convert to WAV format for ASR processing
+            let wav_start = Instant::now();
+            let wav_result = Self::convert_to_wav_bytes(&job.audio_data, job.sample_rate);
+            latency.wav_ms = Some(wav_start.elapsed().as_millis() as i64);
+            match wav_result {
+                Ok(wav_bytes) => {
+                    debug!("🔄 Converting {} audio samples to WAV format ({} bytes)", job.audio_data.len(), wav_bytes.len());
+
+                    // Process with ASR - this now uses spawn_blocking internally
+                    use std::io::Cursor;
+                    let asr_start = Instant::now();
+                    let asr_output = Self::run_asr(ctx, Cursor::new(wav_bytes));
+                    latency.asr_ms = Some(asr_start.elapsed().as_millis() as i64);
+                    latency.attempts = asr_output.as_ref().ok().and_then(|t| t.request_attempts).map(i64::from);
+                    if let Some(race) = asr_output.as_ref().ok().and_then(|t| t.race.as_ref()) {
+                        latency.race_local_ms = race.local_ms;
+                        latency.race_cloud_ms = race.cloud_ms;
+                    }
+                    match asr_output {
+                        Ok(transcript) if Self::heard_nothing(&transcript) => None,
+                        Ok(transcript) => {
+                            info!("✅ ASR processing successful");
+                            recognition = RecognitionInfo::from_transcript(ctx.asr_processor.as_ref(), &transcript);
+                            let result = transcript.text;
+                            let postprocess_start = Instant::now();
+                            let options = *ctx.postprocess_options.lock().unwrap();
+                            let processed = postprocess::apply(&result, &options, ctx.translate_processor.as_ref());
+                            let processed = crate::voice_assistant::replacements::apply_replacements(&processed);
+                            let dictated = crate::voice_assistant::dictation_commands::apply_dictation_commands(&processed);
+                            if dictated.text != result {
+                                tracing::trace!("Post-processed result: {}", redact(&dictated.text));
+                            }
+                            if dictated.delete_last {
+                                Self::delete_last_result(ctx);
+                            }
+                            latency.postprocess_ms = Some(postprocess_start.elapsed().as_millis() as i64);
+                            if dictated.delete_last && dictated.text.trim().is_empty() {
+                                // 只有删除命令，没有需要输入的内容
+                                None
                             } else {
-                                println!("❌ No recorder available");
-                                Some("No recorder available".to_string())
-                            };
-
-                            // Use the ASR result
-                            if let Some(result_text) = asr_result {
-                                println!("⌨️ Typing ASR result: \"{}\"", result_text);
-                                
-                                // Calculate processing time
-                                let processing_time = if let Some(start_time) = hotkey_start_time.lock().unwrap().as_ref() {
-                                    Some(start_time.elapsed().as_millis() as i64)
-                                } else {
-                                    None
-                                };
-                                
-                                // Use tokio runtime to save to database
-                                if let Ok(tokio_rt) = tokio::runtime::Runtime::new() {
-                                    let result_text_clone = result_text.clone();
-                                    let processor_type = _asr_processor.get_processor_type().unwrap_or("unknown").to_string();
-                                    tokio_rt.block_on(async move {
-                                        crate::voice_assistant::coordinator::save_asr_result_directly(
-                                            result_text_clone,
-                                            &processor_type,
-                                            processing_time,
-                                            true,
-                                            None
-                                        ).await;
-                                    });
-                                    
-                                    println!("✅ Database save operation completed");
+                                let translate_start = Instant::now();
+                                match Self::auto_translate(ctx, &dictated.text, recognition.detected_language.as_deref()) {
+                                    Some(translated) => {
+                                        latency.translate_ms = Some(translate_start.elapsed().as_millis() as i64);
+                                        translated_from = Some(dictated.text);
+                                        Some(translated)
+                                    }
+                                    None => Some(dictated.text),
                                 }
-                                
-                                Self::type_text_internal(&state, &temp_text_length, &original_clipboard, &result_text, None, &typing_delays_for_callback.lock().unwrap());
-                                println!("✅ ASR result typing completed");
                             }
+                        }
+                        Err(VoiceError::NoSpeech) => {
+                            info!("🔇 No speech detected, nothing to type");
+                            None
+                        }
+                        Err(e) => {
+                            error!("❌ ASR processing failed: {}", e);
+                            failure = Some(e.to_string());
+                            Some(format!("ASR Error: {}", e))
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("❌ Failed to convert audio to WAV: {}", e);
+                    failure = Some(e.to_string());
+                    Some(format!("Audio conversion error: {}", e))
+                }
+            }
+        };
 
-                            // Reset recorder for next use
-                            recorder = None;
+        // Use the ASR result
+        if let Some(result_text) = asr_result {
+            tracing::debug!("Typing ASR result ({} chars)", result_text.chars().count());
+            tracing::trace!("ASR result text: {}", redact(&result_text));
+
+            // 从按下热键算起，包含排队等待的时间
+            let processing_time = job.started_at.map(|start_time| start_time.elapsed().as_millis() as i64);
+
+            // 会话记录开启时追加到记录中，只记录模式下不输入
+            let recorded_at = chrono::Utc::now()
+                - job.started_at
+                    .and_then(|start| chrono::Duration::from_std(start.elapsed()).ok())
+                    .unwrap_or_else(chrono::Duration::zero);
+            let transcript_only = failure.is_none()
+                && crate::voice_assistant::transcript::record(&result_text, recorded_at, audio.duration_ms);
+
+            // 先输入文本再写数据库，这样输入耗时也能计入延迟分解
+            let typing_start = Instant::now();
+            if transcript_only {
+                info!("📝 Result added to transcript only, not typing");
+            } else {
+                Self::deliver_text(ctx, &result_text, job.queued);
+            }
+            latency.typing_ms = Some(typing_start.elapsed().as_millis() as i64);
+            info!("✅ ASR result typing completed");
+            info!(
+                "⏱️ Latency breakdown: record={:?}ms wav={:?}ms asr={:?}ms postprocess={:?}ms translate={:?}ms typing={:?}ms attempts={:?}",
+                latency.record_ms, latency.wav_ms, latency.asr_ms, latency.postprocess_ms, latency.translate_ms, latency.typing_ms, latency.attempts
+            );
+
+            // 交给写库任务保存，识别线程不等待数据库
+            let processor_type = recognition.processor_type_or(ctx.asr_processor.as_ref());
+            ctx.save_result(match translated_from {
+                // 自动翻译的结果按翻译记录保存，原文和译文都能在历史中看到
+                Some(source_text) => PendingResult::Translation {
+                    source_text,
+                    translated_text: result_text,
+                    processor_type,
+                    processing_time_ms: processing_time,
+                    audio,
+                    translation_skipped: false,
+                    latency: Some(latency),
+                    recognition,
+                },
+                None => PendingResult::Transcription {
+                    text: result_text,
+                    processor_type,
+                    processing_time_ms: processing_time,
+                    error_message: failure,
+                    audio,
+                    latency: Some(latency),
+                    recognition,
+                },
+            });
+        }
+    }
 
-                            // IMPORTANT: Reset state and flags after processing
-                            println!("🔄 Resetting state after processing completion...");
-                            recording_started = false;
-                            *hotkey_start_time.lock().unwrap() = None;
-                            *state.lock().unwrap() = InputState::Idle;
-                        // Emit state change event
-                        crate::voice_assistant::coordinator::emit_voice_assistant_state_from_keyboard(&InputState::Idle);
-                        }
-                        InputState::Translating => {
-                            // 🔥 使用whisper.cpp内置的翻译功能
-                            println!("🔄 Entering Translating state...");
-                            println!("🌐 Using whisper.cpp built-in translation (speech → English text)...");
-
-                            let final_result = if let Some(ref mut rec) = recorder {
-                                println!("🛑 Stopping recording for translation...");
-
-                                // Get audio data and sample rate BEFORE stopping recording
-                                let audio_data = rec.get_audio_data();
-                                let sample_rate = rec.get_sample_rate();
-                                println!("📊 Got audio data: {} samples", audio_data.len());
-
-                                // Stop recording
-                                let _ = rec.stop_recording();
-
-                                // Convert to WAV bytes (after we're done with rec)
-                                let wav_bytes_result = Self::convert_to_wav_bytes(&audio_data, sample_rate);
-                                let _ = rec; // Explicitly drop the borrow
-                                recorder = None; // Now we can assign
-
-                                match wav_bytes_result {
-                                    Ok(wav_bytes) => {
-                                        let audio_cursor = std::io::Cursor::new(wav_bytes);
-                                        println!("🎵 Converted audio to WAV format");
-
-                                        // 🔥 关键：使用 Mode::Translations 让whisper直接翻译成英文
-                                        let start = std::time::Instant::now();
-                                        let translation = _asr_processor.process_audio(
-                                            audio_cursor,
-                                            crate::voice_assistant::Mode::Translations,  // 🔥 翻译模式
-                                            ""
-                                        );
-                                        let processing_time = start.elapsed().as_millis() as i64;
-
-                                        match translation {
-                                            Ok(translated_text) => {
-                                                println!("✅ Whisper translation result: \"{}\"", translated_text);
-                                                println!("⏱️ Processing time: {}ms", processing_time);
-                                                Some(translated_text)
-                                            }
-                                            Err(e) => {
-                                                println!("❌ Whisper translation error: {}", e);
-                                                Some(format!("❌ Translation failed: {}", e))
-                                            }
+    /// 识别前的能量检查：语音时长不足设置的最小值时跳过 ASR，不输入，只通知前端。
+    /// 返回检测到的语音时长；录音出错或没有音频时按原来的流程报告错误
+    fn empty_recording_speech(job: &TranscriptionJob) -> Option<Duration> {
+        if job.recording_error.is_some() || job.audio_data.is_empty() {
+            return None;
+        }
+        let settings = crate::voice_assistant::settings::current();
+        let speech = crate::voice_assistant::asr::speech_duration(&job.audio_data, job.sample_rate);
+        if !settings.is_empty_recording(speech) {
+            return None;
+        }
+        info!("🔇 Only {}ms of speech in {} samples, skipping ASR", speech.as_millis(), job.audio_data.len());
+        crate::voice_assistant::coordinator::emit_empty_recording(speech.as_millis() as u64, settings.speech_precheck_min_ms);
+        Some(speech)
+    }
+
+    /// 识别链中可能有本地 whisper（直接、竞速或兜底），与模型测试命令共用推理占用标记，避免同时推理
+    fn run_asr(ctx: &ListenerContext, wav: std::io::Cursor<Vec<u8>>) -> Result<AsrTranscript, VoiceError> {
+        let _inference = crate::voice_assistant::global_whisper::begin_inference();
+        ctx.asr_processor.process_audio(wav, crate::voice_assistant::Mode::Transcriptions, "")
+    }
+
+    /// 本地 Whisper 认为这段录音大概率没有语音时丢弃结果，不输入也不写历史，只通知前端
+    fn heard_nothing(transcript: &AsrTranscript) -> bool {
+        let confidence = transcript.confidence;
+        if !crate::voice_assistant::settings::current().is_no_speech(confidence) {
+            return false;
+        }
+        let no_speech_prob = confidence.map(|c| c.no_speech_prob).unwrap_or_default();
+        info!("🔇 No-speech probability {:.2} is above the threshold, discarding result", no_speech_prob);
+        crate::voice_assistant::coordinator::emit_nothing_heard(no_speech_prob);
+        true
+    }
+
+    /// 自动翻译模式：识别出的语言不是主语言时翻译成主语言。不需要翻译或翻译失败时返回 None，输入原文
+    fn auto_translate(ctx: &ListenerContext, text: &str, detected_language: Option<&str>) -> Option<String> {
+        let target = crate::voice_assistant::settings::current().auto_translate_target(detected_language)?;
+        let Some(translator) = ctx.translate_processor.as_deref() else {
+            warn!("⚠️ Auto-translate is enabled but no translation processor is configured");
+            return None;
+        };
+
+        info!("🌐 Detected language {:?}, translating to primary language {}", detected_language.unwrap_or_default(), target);
+        match translator.translate_to(text, &target) {
+            Ok(translated) => Some(translated),
+            Err(VoiceError::Timeout(e)) => {
+                warn!("⏱️ Translation timed out, typing transcription instead: {}", e);
+                None
+            }
+            Err(e) => {
+                warn!("❌ Translation failed, typing transcription instead: {}", e);
+                None
+            }
+        }
+    }
+
+    /// 先转录，再交给翻译处理器（SiliconFlow / Ollama）翻译
+    fn process_translation_job(ctx: &ListenerContext, job: TranscriptionJob) {
+        info!("🌐 Processing audio for translation...");
+        let mut latency = crate::database::LatencyBreakdown::default();
+        let audio = job.recorded_audio();
+        let mut recognition = RecognitionInfo::default();
+        // 拿到原文前的失败（录音、WAV 转换、ASR）也写入历史，计入服务状态和失败统计
+        let mut failure: Option<String> = None;
+
+        // 识别出的语言已经是目标语言时不调用翻译服务
+        let mut translation_skipped = false;
+        // (原文, 要输入的文本)；原文为 None 表示出错信息
+        let final_result: Option<(Option<String>, String)> = if let Some(error) = job.recording_error {
+            failure = Some(error.clone());
+            Some((None, error))
+        } else if job.audio_data.is_empty() {
+            warn!("⚠️ No audio data recorded, nothing to translate");
+            None
+        } else {
+            latency.record_ms = audio.duration_ms;
+
+            let wav_start = Instant::now();
+            let wav_result = Self::convert_to_wav_bytes(&job.audio_data, job.sample_rate);
+            latency.wav_ms = Some(wav_start.elapsed().as_millis() as i64);
+            match wav_result {
+                Ok(wav_bytes) => {
+                    let asr_start = Instant::now();
+                    let asr_output = Self::run_asr(ctx, std::io::Cursor::new(wav_bytes));
+                    latency.asr_ms = Some(asr_start.elapsed().as_millis() as i64);
+                    latency.attempts = asr_output.as_ref().ok().and_then(|t| t.request_attempts).map(i64::from);
+                    if let Some(race) = asr_output.as_ref().ok().and_then(|t| t.race.as_ref()) {
+                        latency.race_local_ms = race.local_ms;
+                        latency.race_cloud_ms = race.cloud_ms;
+                    }
+                    match asr_output {
+                        Ok(transcript) if Self::heard_nothing(&transcript) => None,
+                        Ok(transcript) => {
+                            let transcription = crate::voice_assistant::replacements::apply_replacements(&transcript.text);
+                            tracing::trace!("Transcription for translation: {}", redact(&transcription));
+
+                            recognition = RecognitionInfo::from_transcript(ctx.asr_processor.as_ref(), &transcript);
+                            let detected_language = recognition.detected_language.as_deref();
+                            match ctx.translate_processor.as_deref() {
+                                Some(translator) if detected_language.is_some_and(|lang| {
+                                    crate::voice_assistant::translate::is_same_language(lang, translator.target_language())
+                                }) => {
+                                    info!(
+                                        "⏭️ Detected language {:?} is already the target language, skipping translation",
+                                        detected_language.unwrap_or_default()
+                                    );
+                                    translation_skipped = true;
+                                    Some((Some(transcription.clone()), transcription))
+                                }
+                                // 流式翻译，收到的译文片段实时推送给前端；输入仍在翻译完成后一次性进行
+                                Some(translator) => {
+                                    let translate_start = Instant::now();
+                                    let translated = translator.translate_stream(&transcription, &mut |chunk| {
+                                        crate::voice_assistant::coordinator::emit_translation_chunk(chunk)
+                                    });
+                                    latency.translate_ms = Some(translate_start.elapsed().as_millis() as i64);
+                                    match translated {
+                                        Ok(translated) => Some((Some(transcription), translated)),
+                                        Err(VoiceError::Timeout(e)) => {
+                                            warn!("⏱️ Translation timed out, typing transcription instead: {}", e);
+                                            Some((Some(transcription.clone()), transcription))
+                                        }
+                                        Err(e) => {
+                                            warn!("❌ Translation failed, typing transcription instead: {}", e);
+                                            Some((Some(transcription.clone()), transcription))
                                         }
-                                    }
-                                    Err(e) => {
-                                        println!("❌ Failed to convert audio to WAV: {}", e);
-                                        Some(format!("❌ Audio conversion failed: {}", e))
                                     }
                                 }
-                            } else {
-                                println!("⚠️ No recorder found, nothing to translate");
-                                Some("❌ No recording found".to_string())
-                            };
-
-                            // Type the result
-                            if let Some(result_text) = final_result {
-                                let state_clone = state.clone();
-                                let temp_len_clone = temp_text_length.clone();
-                                let clipboard_clone = original_clipboard.clone();
-
-                                println!("⌨️ Typing translation result: \"{}\"", result_text);
-                                Self::type_text_internal(&state_clone, &temp_len_clone, &clipboard_clone, &result_text, None, &typing_delays_for_callback.lock().unwrap());
-                                println!("✅ Translation result typing completed");
+                                None => {
+                                    warn!("⚠️ No translation processor configured, typing transcription instead");
+                                    Some((Some(transcription.clone()), transcription))
+                                }
                             }
-
-                            // IMPORTANT: Reset state and flags immediately after processing
-                            println!("🔄 Resetting state after translation completion...");
-                            recording_started = false;
-                            *hotkey_start_time.lock().unwrap() = None;
-                            *state.lock().unwrap() = InputState::Idle;
-                        // Emit state change event
-                        crate::voice_assistant::coordinator::emit_voice_assistant_state_from_keyboard(&InputState::Idle);
                         }
-                        _ => {}
+                        Err(VoiceError::NoSpeech) => {
+                            info!("🔇 No speech detected, nothing to translate");
+                            None
+                        }
+                        Err(e) => {
+                            error!("❌ ASR processing failed: {}", e);
+                            failure = Some(e.to_string());
+                            Some((None, format!("ASR Error: {}", e)))
+                        }
                     }
                 }
-
-            }) {
-                eprintln!("Error listening for keyboard events: {:?}", e);
+                Err(e) => {
+                    error!("❌ Failed to convert audio to WAV: {}", e);
+                    failure = Some(e.to_string());
+                    Some((None, format!("Audio conversion error: {}", e)))
+                }
             }
-        });
+        };
+
+        // Type the result
+        if let Some((source_text, result_text)) = final_result {
+            let processing_time = job.started_at.map(|start_time| start_time.elapsed().as_millis() as i64);
+
+            tracing::debug!("Typing translation result ({} chars)", result_text.chars().count());
+            tracing::trace!("Translation result text: {}", redact(&result_text));
+            let typing_start = Instant::now();
+            Self::deliver_text(ctx, &result_text, job.queued);
+            latency.typing_ms = Some(typing_start.elapsed().as_millis() as i64);
+            info!("✅ Translation result typing completed");
+            info!(
+                "⏱️ Latency breakdown: record={:?}ms wav={:?}ms asr={:?}ms translate={:?}ms typing={:?}ms attempts={:?}",
+                latency.record_ms, latency.wav_ms, latency.asr_ms, latency.translate_ms, latency.typing_ms, latency.attempts
+            );
+
+            let processor_type = recognition.processor_type_or(ctx.asr_processor.as_ref());
+            ctx.save_result(match source_text {
+                Some(source_text) => PendingResult::Translation {
+                    source_text,
+                    translated_text: result_text,
+                    processor_type,
+                    processing_time_ms: processing_time,
+                    audio,
+                    translation_skipped,
+                    latency: Some(latency),
+                    recognition,
+                },
+                None => PendingResult::failed_translation(
+                    result_text,
+                    failure.unwrap_or_default(),
+                    processor_type,
+                    processing_time,
+                    audio,
+                    Some(latency),
+                    recognition,
+                ),
+            });
+        }
     }
 
     fn convert_to_wav_bytes(audio_data: &[f32], sample_rate: u32) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
@@ -486,8 +1117,11 @@ impl KeyboardManager {
     let mut cursor = std::io::Cursor::new(Vec::new());
     let mut writer = WavWriter::new(&mut cursor, spec)?;
 
+    // 送入 ASR 前按设置做归一化/高通/噪声门
+    let audio_data = crate::voice_assistant::audio_prep::prepare(audio_data, sample_rate);
+
     // Convert f32 samples to i16
-    for &sample in audio_data {
+    for &sample in &audio_data {
         let i16_sample = (sample * i16::MAX as f32) as i16;
         writer.write_sample(i16_sample)?;
     }
@@ -497,79 +1131,106 @@ impl KeyboardManager {
 }
 
 fn start_recording_internal(recorder: &mut Option<crate::voice_assistant::AudioRecorder>, save_wav_files: bool) {
-        if recorder.is_none() {
-            match crate::voice_assistant::AudioRecorder::new() {
-                Ok(mut r) => {
-                    // Set the save_wav_files option on the recorder
-                    r.set_save_wav_files(save_wav_files);
-
-                    if let Err(e) = r.start_recording() {
-                        eprintln!("Failed to start recording: {}", e);
-                    } else {
-                        println!("🎙️ Recording started (Save WAV: {})", save_wav_files);
-                        *recorder = Some(r);
-                    }
-                }
-                Err(e) => eprintln!("Failed to create recorder: {}", e),
+        // 已在录音则不处理；预录中的 recorder 直接开始录音，否则新建
+        let mut r = match recorder.take() {
+            Some(r) if r.is_recording() => {
+                *recorder = Some(r);
+                return;
             }
+            Some(r) => r,
+            None => match crate::voice_assistant::AudioRecorder::new() {
+                Ok(r) => r,
+                Err(e) => {
+                    error!("Failed to create recorder: {}", e);
+                    return;
+                }
+            },
+        };
+
+        // Set the save_wav_files option on the recorder
+        r.set_save_wav_files(save_wav_files);
+
+        if let Err(e) = r.start_recording() {
+            error!("Failed to start recording: {}", e);
+        } else {
+            info!("🎙️ Recording started (Save WAV: {})", save_wav_files);
+            // 录音浮窗的电平条和计时
+            r.start_level_meter(
+                Duration::from_millis(100),
+                crate::voice_assistant::coordinator::emit_recording_level,
+            );
+            *recorder = Some(r);
         }
     }
 
     fn type_text_internal(
-        state: &Arc<Mutex<InputState>>,
-        _temp_text_length: &Arc<Mutex<usize>>,
-        original_clipboard: &Arc<Mutex<Option<String>>>,
+        state: &Arc<Mutex<StateMachine>>,
+        temp_text_length: &Arc<Mutex<usize>>,
         text: &str,
         error: Option<&str>,
-        _delays: &TypingDelays,
+        delays: &TypingDelays,
+        injection_method: TextInjectionMethod,
     ) {
-        // 🔥 禁用temp_text_length机制，避免模拟退格触发rdev死循环
-        // 剪贴板输入已经可靠，不需要删除临时文本
-        println!("⌨️ Skipping temp_text_length cleanup (using clipboard input)");
-
         if let Some(err_msg) = error {
             // 显示错误消息
-            simulate_typing(&format!("❌ {}", err_msg), _delays);
+            simulate_typing(&format!("❌ {}", err_msg), delays, injection_method);
 
             // 2秒后清除错误消息 - use std sleep instead of tokio
             let state_clone = state.clone();
             std::thread::spawn(move || {
                 std::thread::sleep(Duration::from_secs(2));
-                if *state_clone.lock().unwrap() == InputState::Error {
-                    *state_clone.lock().unwrap() = InputState::Idle;
-                    // 🔥 不再删除临时文本，避免死循环
+                // 期间已开始新的录音时 clear_error 不生效
+                if let Ok(transition) = state_clone.lock().unwrap().clear_error() {
+                    Self::emit_transition(transition);
                 }
             });
 
-            *state.lock().unwrap() = InputState::Error;
+            if let Ok(transition) = state.lock().unwrap().fail() {
+                Self::emit_transition(transition);
+            }
         } else if !text.is_empty() {
-            // 输入最终文本
-            simulate_typing(text, _delays);
+            // 去掉控制字符、静音幻觉和失控的重复输出，过长的部分放到剪贴板
+            let sanitized = crate::voice_assistant::sanitize::sanitize(
+                text,
+                &crate::voice_assistant::settings::current().output_sanitizer,
+            );
+            if !sanitized.text.is_empty() {
+                // 按前台应用的输出配置调整文本和注入方式
+                let profile = crate::voice_assistant::output_profiles::current_profile();
+                let text = crate::voice_assistant::output_profiles::apply_profile_text(&sanitized.text, &profile);
+                simulate_typing(
+                    &text,
+                    profile.typing_delays.as_ref().unwrap_or(delays),
+                    profile.injection_method.unwrap_or(injection_method),
+                );
+                // 记录实际输入的长度，供口述命令 "delete that" 删除
+                *temp_text_length.lock().unwrap() = text.chars().count();
+            }
+
+            // 剪贴板只由 inject_with 在粘贴前后保存/恢复，粘贴失败时文本留在剪贴板上供手动粘贴
 
-            // 恢复剪贴板
-            let mut clipboard = original_clipboard.lock().unwrap();
-            if let Some(content) = clipboard.take() {
-                set_clipboard_content(&content);
+            if let Some(overflow) = sanitized.overflow {
+                clipboard::set_text(&overflow);
+                crate::voice_assistant::coordinator::emit_output_truncated(
+                    sanitized.text.chars().count(),
+                    overflow.chars().count(),
+                );
             }
         }
+        // 状态由识别线程在队列处理完后重置，排队的录音之间不回到 Idle
+    }
 
-        *state.lock().unwrap() = InputState::Idle;
+    /// 当前的录音/识别状态
+    pub fn state(&self) -> InputState {
+        self.state.lock().unwrap().state()
     }
 
     pub fn reset_state(&mut self) {
-        *self.state.lock().unwrap() = InputState::Idle;
+        self.state.lock().unwrap().reset();
         *self.temp_text_length.lock().unwrap() = 0;
         self.pressed_keys.lock().unwrap().clear();
-        *self.hotkey_start_time.lock().unwrap() = None;
 
-        // 🔥 不再删除临时文本，避免enigo模拟退格触发rdev死循环
-        println!("🔄 State reset (skipping temp_text cleanup)");
-
-        // 恢复剪贴板
-        let mut clipboard = self.original_clipboard.lock().unwrap();
-        if let Some(content) = clipboard.take() {
-            set_clipboard_content(&content);
-        }
+        debug!("🔄 State reset");
     }
 
     // 可配置热键方法
@@ -577,7 +1238,7 @@ fn start_recording_internal(recorder: &mut Option<crate::voice_assistant::AudioR
         let _parsed_hotkey = crate::voice_assistant::hotkey_parser::ParsedHotkey::parse(hotkey_str)
             .map_err(|e| VoiceError::Other(e))?;
         // 由于我们使用简单的版本，暂时只打印日志
-        println!("Setting transcribe hotkey: {}", hotkey_str);
+        debug!("Setting transcribe hotkey: {}", hotkey_str);
         Ok(())
     }
 
@@ -585,471 +1246,640 @@ fn start_recording_internal(recorder: &mut Option<crate::voice_assistant::AudioR
         let _parsed_hotkey = crate::voice_assistant::hotkey_parser::ParsedHotkey::parse(hotkey_str)
             .map_err(|e| VoiceError::Other(e))?;
         // 由于我们使用简单的版本，暂时只打印日志
-        println!("Setting translate hotkey: {}", hotkey_str);
+        debug!("Setting translate hotkey: {}", hotkey_str);
         Ok(())
     }
 
     pub fn set_trigger_delay_ms(&self, delay_ms: i64) {
-        println!("Setting trigger delay: {}ms", delay_ms);
+        // 负数按 0 处理：不等待
+        let delay = Duration::from_millis(delay_ms.max(0) as u64);
+        self.state.lock().unwrap().set_trigger_delay(delay);
+        debug!("🔧 Trigger delay: {}ms", delay.as_millis());
     }
 
     pub fn set_anti_mistouch_enabled(&self, enabled: bool) {
-        println!("Setting anti-mistouch: {}", enabled);
+        self.state.lock().unwrap().set_anti_mistouch(enabled);
+        debug!("🔧 Anti-mistouch: {}", enabled);
     }
 
     /// 设置WAV文件保存开关
     pub fn set_save_wav_files(&self, save_wav_files: bool) {
         let mut setting = self.save_wav_files.lock().unwrap();
         *setting = save_wav_files;
-        println!("🔧 Save WAV Files setting updated to: {}", save_wav_files);
+        debug!("🔧 Save WAV Files setting updated to: {}", save_wav_files);
+    }
+
+    /// 设置ASR结果后处理开关
+    pub fn set_postprocess_options(&self, options: PostProcessOptions) {
+        *self.postprocess_options.lock().unwrap() = options;
+        debug!("🔧 Post-processing options updated: {:?}", options);
     }
 
     /// 设置延迟配置
     pub fn set_typing_delays(&self, typing_delays: TypingDelays) {
         let mut delays = self.typing_delays.lock().unwrap();
         *delays = typing_delays;
-        println!("🔧 Typing delays updated:");
-        println!("  - clipboard_update_ms: {}ms", delays.clipboard_update_ms);
-        println!("  - keyboard_events_settle_ms: {}ms", delays.keyboard_events_settle_ms);
-        println!("  - typing_complete_ms: {}ms", delays.typing_complete_ms);
-        println!("  - character_interval_ms: {}ms", delays.character_interval_ms);
-        println!("  - short_operation_ms: {}ms", delays.short_operation_ms);
+        debug!("🔧 Typing delays updated:");
+        debug!("  - clipboard_update_ms: {}ms", delays.clipboard_update_ms);
+        debug!("  - keyboard_events_settle_ms: {}ms", delays.keyboard_events_settle_ms);
+        debug!("  - typing_complete_ms: {}ms", delays.typing_complete_ms);
+        debug!("  - character_interval_ms: {}ms", delays.character_interval_ms);
+        debug!("  - short_operation_ms: {}ms", delays.short_operation_ms);
+    }
+
+    /// 设置文本注入方式
+    pub fn set_text_injection_method(&self, method: TextInjectionMethod) {
+        *self.text_injection_method.lock().unwrap() = method;
+        debug!("🔧 Text injection method updated to: {}", method.as_str());
+    }
+
+    /// 设置热键后端，需重新调用 start_listening 生效
+    pub fn set_hotkey_backend(&self, backend: HotkeyBackend) {
+        *self.hotkey_backend.lock().unwrap() = backend;
+        debug!("🔧 Hotkey backend updated to: {}", backend.as_str());
+    }
+
+    pub fn hotkey_backend(&self) -> HotkeyBackend {
+        *self.hotkey_backend.lock().unwrap()
+    }
+
+    /// 设置录音模式，立即生效
+    pub fn set_recording_mode(&self, mode: RecordingMode) {
+        *self.recording_mode.lock().unwrap() = mode;
+        debug!("🔧 Recording mode updated to: {}", mode.as_str());
+    }
+
+    pub fn recording_mode(&self) -> RecordingMode {
+        *self.recording_mode.lock().unwrap()
+    }
+
+    /// 设置最长录音时长，从下一次录音开始生效
+    pub fn set_max_recording_secs(&self, secs: i64) {
+        let secs = secs.max(1) as u64;
+        *self.max_recording_duration.lock().unwrap() = Duration::from_secs(secs);
+        debug!("🔧 Max recording duration updated to: {}s", secs);
+    }
+
+    /// 设置预录时长，0 关闭预录并释放麦克风；信号线程空闲时生效
+    pub fn set_preroll_ms(&self, ms: i64) {
+        let duration = Duration::from_millis(ms.max(0) as u64);
+        *self.preroll_duration.lock().unwrap() = duration;
+        debug!("🔧 Pre-roll duration updated to: {}ms", duration.as_millis());
+    }
+
+    /// 设置识别期间排队的录音是否只复制到剪贴板
+    pub fn set_queued_to_clipboard(&self, enabled: bool) {
+        *self.queued_to_clipboard.lock().unwrap() = enabled;
+        debug!("🔧 Queued results to clipboard: {}", enabled);
+    }
+
+    /// 设置循环切换 ASR 配置方案的热键（rdev 后端），空字符串或 None 表示不使用
+    pub fn set_cycle_profile_hotkey(&self, hotkey: Option<&str>) {
+        let parsed = hotkey
+            .map(str::trim)
+            .filter(|key| !key.is_empty())
+            .and_then(|key| match ParsedHotkey::parse(key) {
+                Ok(parsed) => Some(parsed),
+                Err(e) => {
+                    warn!("⚠️ Invalid profile cycle hotkey {}: {}", key, e);
+                    None
+                }
+            });
+        debug!("🔧 Profile cycle hotkey: {:?}", hotkey.filter(|_| parsed.is_some()));
+        *self.cycle_profile_hotkey.lock().unwrap() = parsed;
+    }
+
+    /// 设置静音自动停止，从下一次录音开始生效
+    pub fn set_silence_auto_stop(&self, enabled: bool, silence_ms: i64) {
+        let silence = enabled.then(|| Duration::from_millis(silence_ms.max(1) as u64));
+        *self.silence_auto_stop.lock().unwrap() = silence;
+        match silence {
+            Some(d) => debug!("🔧 Silence auto-stop enabled: {}ms", d.as_millis()),
+            None => debug!("🔧 Silence auto-stop disabled"),
+        }
+    }
+}
+
+impl KeyboardManagerTrait for KeyboardManager {
+    fn start_listening(&mut self) {
+        self.start_listening();
+    }
+
+    fn type_text(&mut self, _text: &str, _error: Option<&str>) {
+        // This is handled internally by the state machine
+    }
+
+    fn reset_state(&mut self) {
+        self.reset_state();
+    }
+}
+
+/// 热键后端：rdev 全局键盘监听，或 tauri-plugin-global-shortcut 注册的系统快捷键
+/// rdev 在 macOS 需要输入监控权限、在 Wayland 下无法工作，此时可切换到插件
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum HotkeyBackend {
+    #[default]
+    Rdev,
+    Plugin,
+}
+
+impl HotkeyBackend {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "rdev" => Some(HotkeyBackend::Rdev),
+            "plugin" | "global-shortcut" => Some(HotkeyBackend::Plugin),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HotkeyBackend::Rdev => "rdev",
+            HotkeyBackend::Plugin => "plugin",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    Transcribe,
+    Translate,
+}
+
+/// 录音模式："hold" 按住录音、松开识别；"toggle" 按一次开始、再按一次停止并识别
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordingMode {
+    #[default]
+    Hold,
+    Toggle,
+}
+
+impl RecordingMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "hold" => Some(RecordingMode::Hold),
+            "toggle" => Some(RecordingMode::Toggle),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RecordingMode::Hold => "hold",
+            RecordingMode::Toggle => "toggle",
+        }
+    }
+}
+
+/// 热键事件（来自 rdev 或插件），以及录音超时/静音信号
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeySignal {
+    Pressed(HotkeyAction),
+    Released(HotkeyAction),
+    /// 第 n 次录音达到最长时长
+    MaxDuration(u64),
+    /// 第 n 次录音检测到持续静音
+    Silence(u64),
+}
+
+impl HotkeySignal {
+    /// 非热键触发的停止原因，作为 `recording-auto-stopped` 事件的 reason
+    pub fn auto_stop_reason(&self) -> Option<&'static str> {
+        match self {
+            HotkeySignal::MaxDuration(_) => Some("max_duration"),
+            HotkeySignal::Silence(_) => Some("silence"),
+            HotkeySignal::Pressed(_) | HotkeySignal::Released(_) => None,
+        }
+    }
+}
+
+/// 标记应用正在退出，之后的按键和热键信号都被忽略；已在队列中的录音继续识别
+pub fn begin_shutdown() {
+    SHUTTING_DOWN.store(true, Ordering::SeqCst);
+}
+
+pub fn is_shutting_down() -> bool {
+    SHUTTING_DOWN.load(Ordering::SeqCst)
+}
+
+/// 正在识别和排队等待识别的录音数；没有识别线程时为 0
+pub fn transcriptions_in_flight() -> usize {
+    TRANSCRIPTION_STATS.lock().unwrap().as_ref().map_or(0, |stats| {
+        stats.pending.load(Ordering::SeqCst) + usize::from(stats.busy.load(Ordering::SeqCst))
+    })
+}
+
+/// 把快捷键事件发给信号线程；线程未运行或应用正在退出时返回 false
+pub fn send_hotkey_signal(signal: HotkeySignal) -> bool {
+    if is_shutting_down() {
+        return false;
+    }
+    // 录制新热键期间按下的组合可能正是现有热键（插件后端仍会触发），不开始听写
+    if matches!(signal, HotkeySignal::Pressed(_)) && crate::voice_assistant::hotkey_capture::is_capturing() {
+        debug!("🎹 Ignoring hotkey while capturing a new hotkey");
+        return false;
+    }
+    match HOTKEY_SIGNALS.lock().unwrap().as_ref() {
+        Some(sender) => sender.send(signal).is_ok(),
+        None => false,
     }
 }
 
-impl KeyboardManagerTrait for KeyboardManager {
-    fn start_listening(&mut self) {
-        self.start_listening();
-    }
+/// 热键事件对应的状态转换：Idle 或识别中按下开始录音；
+/// 按住模式松开同一个热键、切换模式再次按下同一个热键，或录音超时/持续静音，进入识别/翻译
+pub fn next_state_for_signal(current: InputState, signal: HotkeySignal, mode: RecordingMode) -> Option<InputState> {
+    use HotkeyAction::{Transcribe, Translate};
 
-    fn type_text(&mut self, _text: &str, _error: Option<&str>) {
-        // This is handled internally by the state machine
-    }
+    let stop = |action: HotkeyAction| match (mode, signal) {
+        (RecordingMode::Hold, HotkeySignal::Released(a)) | (RecordingMode::Toggle, HotkeySignal::Pressed(a)) => a == action,
+        (_, HotkeySignal::MaxDuration(_) | HotkeySignal::Silence(_)) => true,
+        _ => false,
+    };
 
-    fn reset_state(&mut self) {
-        self.reset_state();
+    match (current, signal) {
+        (InputState::Idle, HotkeySignal::Pressed(Transcribe)) => Some(InputState::Recording),
+        (InputState::Idle, HotkeySignal::Pressed(Translate)) => Some(InputState::RecordingTranslate),
+        // 识别在后台进行，期间可以开始下一次录音（排队识别）
+        (InputState::Processing | InputState::Translating, HotkeySignal::Pressed(Transcribe)) => Some(InputState::Recording),
+        (InputState::Processing | InputState::Translating, HotkeySignal::Pressed(Translate)) => Some(InputState::RecordingTranslate),
+        (InputState::Recording, _) if stop(Transcribe) => Some(InputState::Processing),
+        (InputState::RecordingTranslate, _) if stop(Translate) => Some(InputState::Translating),
+        _ => None,
     }
 }
 
-fn simulate_typing(text: &str, _delays: &TypingDelays) {
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let output = Command::new("osascript")
-            .arg("-e")
-            .arg(&format!(
-                "tell application \"System Events\" to keystroke \"{}\"",
-                text.replace("\"", "\\\"").replace("\n", "\\n")
-            ))
-            .output();
+/// 文本注入方式（持久化设置）："auto" 按平台自动选择，"direct" 逐字符按键输入，"clipboard" 剪贴板粘贴
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextInjectionMethod {
+    #[default]
+    Auto,
+    Direct,
+    Clipboard,
+}
 
-        if let Err(e) = output {
-            eprintln!("Failed to type text: {}", e);
+impl TextInjectionMethod {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "auto" => Some(Self::Auto),
+            "direct" => Some(Self::Direct),
+            "clipboard" => Some(Self::Clipboard),
+            _ => None,
         }
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        // 🔥 Windows平台：使用逐字符模拟键盘输入
-        println!("⌨️ Using keyboard simulation for character-by-character input...");
-        println!("✅ Text to type: \"{}\"", text);
-
-        type_text_by_keypress(text);
-
-        println!("✅ Keyboard simulation completed");
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Auto => "auto",
+            Self::Direct => "direct",
+            Self::Clipboard => "clipboard",
+        }
     }
+}
 
-    #[cfg(target_os = "linux")]
-    {
-        // Linux 使用剪贴板粘贴方法，更可靠支持中文
-        println!("📋 Using clipboard paste method for Linux...");
-
-        // 保存当前剪贴板内容
-        let current_clipboard = match get_clipboard_content() {
-            Ok(content) => Some(content),
-            Err(_) => {
-                eprintln!("Warning: Failed to get current clipboard content");
-                None
-            }
-        };
-
-        // 将文本设置到剪贴板
-        set_clipboard_content(text);
-
-        // 等待剪贴板更新
-        std::thread::sleep(std::time::Duration::from_millis(delays.clipboard_update_ms as u64));
+/// 实际使用的注入策略
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectionStrategy {
+    /// 通过 enigo 逐字符发送按键事件
+    DirectTyping,
+    /// 写入剪贴板后模拟粘贴快捷键
+    ClipboardPaste,
+}
 
-        // For xterm and other terminals, direct typing is more reliable than clipboard paste
-        println!("🔧 Using direct typing for terminal compatibility...");
+/// Wayland 会话下 X11 按键模拟只能到达 XWayland 窗口
+pub fn is_wayland_session() -> bool {
+    std::env::var("XDG_SESSION_TYPE")
+        .map(|v| v.eq_ignore_ascii_case("wayland"))
+        .unwrap_or(false)
+}
 
-        // Method 1: Try direct typing first (most reliable for terminals)
-        if let Ok(_) = type_text_direct(text, delays) {
-            println!("✅ Direct typing successful");
-        } else {
-            println!("🔧 Direct typing failed, trying clipboard methods...");
-
-            // Method 2: Try Ctrl+Shift+V for terminal paste as fallback
-            std::thread::sleep(std::time::Duration::from_millis(delays.short_operation_ms as u64));
-            if let Ok(output) = Command::new("xdotool")
-                .args(&["key", "Ctrl+Shift+V"])
-                .output()
-            {
-                if output.status.success() {
-                    println!("✅ Ctrl+Shift+V paste successful");
-                } else {
-                    eprintln!("Ctrl+Shift+V failed: {:?}", String::from_utf8_lossy(&output.stderr));
-
-                    // Method 3: Try middle-click paste
-                    std::thread::sleep(std::time::Duration::from_millis(delays.short_operation_ms as u64));
-                    if let Ok(output2) = Command::new("xdotool")
-                        .args(&["click", "2"])
-                        .output()
-                    {
-                        if output2.status.success() {
-                            println!("✅ Middle-click paste successful");
-                        } else {
-                            eprintln!("All paste methods failed");
-                        }
-                    }
-                }
+/// 根据设置、平台和会话类型选择注入策略；显式设置优先于自动选择
+pub fn select_injection_strategy(method: TextInjectionMethod, os: &str, wayland: bool) -> InjectionStrategy {
+    match method {
+        TextInjectionMethod::Direct => InjectionStrategy::DirectTyping,
+        TextInjectionMethod::Clipboard => InjectionStrategy::ClipboardPaste,
+        TextInjectionMethod::Auto => {
+            if os == "linux" && wayland {
+                InjectionStrategy::ClipboardPaste
             } else {
-                eprintln!("xdotool not found");
+                InjectionStrategy::DirectTyping
             }
         }
+    }
+}
 
-        // 等待粘贴完成
-        std::thread::sleep(std::time::Duration::from_millis(delays.short_operation_ms as u64));
+/// 逐字符输入时的输入单元：换行和制表符需要作为按键发送，其他控制字符丢弃
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypingToken {
+    Text(String),
+    Enter,
+    Tab,
+}
 
-        // 恢复原始剪贴板内容
-        if let Some(original) = current_clipboard {
-            set_clipboard_content(&original);
+pub fn tokenize_for_typing(text: &str) -> Vec<TypingToken> {
+    let mut tokens = Vec::new();
+    let mut buffer = String::new();
+    let mut chars = text.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        let token = match ch {
+            '\r' => {
+                // \r\n 视为一次换行
+                if chars.peek() == Some(&'\n') {
+                    chars.next();
+                }
+                TypingToken::Enter
+            }
+            '\n' => TypingToken::Enter,
+            '\t' => TypingToken::Tab,
+            c if c.is_control() => continue,
+            c => {
+                buffer.push(c);
+                continue;
+            }
+        };
+        if !buffer.is_empty() {
+            tokens.push(TypingToken::Text(std::mem::take(&mut buffer)));
         }
+        tokens.push(token);
+    }
 
-        println!("✅ Clipboard paste completed");
+    if !buffer.is_empty() {
+        tokens.push(TypingToken::Text(buffer));
     }
+    tokens
 }
 
-/// Windows: 逐字符模拟键盘输入（支持Unicode）
-#[cfg(target_os = "windows")]
-fn type_text_by_keypress(text: &str) {
-    // 🔥 统一使用 Unicode 输入方式，完全绕过输入法和虚拟键码映射
-    // 这样可以避免 ctfmon.exe 错误和输入法干扰
-    println!("⌨️ Using Unicode input for all characters to bypass IME...");
-    println!("✅ Text to type: \"{}\"", text);
+/// 一次注入尝试中的单个步骤
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InjectionStep {
+    DirectTyping,
+    ClipboardPaste,
+}
 
-    for ch in text.chars() {
-        type_unicode_char(ch);
+impl InjectionStrategy {
+    /// 按顺序尝试的步骤：逐字符输入失败时退回剪贴板粘贴
+    pub fn steps(&self) -> &'static [InjectionStep] {
+        match self {
+            InjectionStrategy::DirectTyping => &[InjectionStep::DirectTyping, InjectionStep::ClipboardPaste],
+            InjectionStrategy::ClipboardPaste => &[InjectionStep::ClipboardPaste],
+        }
     }
-
-    println!("✅ Unicode input completed");
 }
 
-/// Windows: 输入Unicode字符（支持中文等）
-#[cfg(target_os = "windows")]
-fn type_unicode_char(ch: char) {
-    unsafe {
-        use winapi::um::winuser::{SendInput, INPUT, KEYBDINPUT, INPUT_KEYBOARD, KEYEVENTF_UNICODE, KEYEVENTF_KEYUP};
-        use winapi::shared::minwindef::WORD;
-        use std::mem;
-
-        let code_point = ch as u32;
-
-        // 按下 - Unicode扫描码
-        let mut key_down: INPUT = mem::zeroed();
-        key_down.type_ = INPUT_KEYBOARD;
-        *key_down.u.ki_mut() = KEYBDINPUT {
-            wVk: 0,
-            wScan: code_point as WORD,
-            dwFlags: KEYEVENTF_UNICODE,
-            time: 0,
-            dwExtraInfo: 0,
-        };
-
-        // 释放
-        let mut key_up: INPUT = mem::zeroed();
-        key_up.type_ = INPUT_KEYBOARD;
-        *key_up.u.ki_mut() = KEYBDINPUT {
-            wVk: 0,
-            wScan: code_point as WORD,
-            dwFlags: KEYEVENTF_UNICODE | KEYEVENTF_KEYUP,
-            time: 0,
-            dwExtraInfo: 0,
-        };
+/// 逐字符输入失败的原因
+#[derive(Debug)]
+pub struct TypingFailure {
+    pub error: VoiceError,
+    /// 失败前是否已经发出过按键；已经输入了一部分时不能再粘贴，否则文本会重复
+    pub keys_sent: bool,
+}
 
-        let mut inputs = [key_down, key_up];
-        let size = mem::size_of::<INPUT>() as i32;
-        let count = inputs.len() as u32;
+/// 文本注入用到的系统操作，测试时可以替换
+pub trait InjectionBackend {
+    type Snapshot;
+
+    fn type_text(&self, text: &str, delays: &TypingDelays) -> Result<(), TypingFailure>;
+    /// 写入剪贴板并发送粘贴快捷键
+    fn paste(&self, text: &str, delays: &TypingDelays) -> Result<(), VoiceError>;
+    fn save_clipboard(&self) -> Option<Self::Snapshot>;
+    fn restore_clipboard(&self, snapshot: Self::Snapshot);
+    /// 发送 count 次退格
+    fn backspace(&self, count: usize) -> Result<(), VoiceError>;
+}
 
-        SendInput(count, inputs.as_mut_ptr() as *mut INPUT, size);
-        std::thread::sleep(std::time::Duration::from_millis(15));
+/// 用退格删除最近输入的 count 个字符
+pub fn delete_chars_with<B: InjectionBackend>(backend: &B, count: usize) -> Result<(), VoiceError> {
+    if count == 0 {
+        return Ok(());
     }
+    backend.backspace(count)
 }
 
-// Fallback function: type text directly using xdotool
-#[allow(dead_code)]
-fn type_text_direct(text: &str, delays: &TypingDelays) -> Result<(), VoiceError> {
-    println!("🔧 Direct typing text: \"{}\"", text);
-
-    // For xterm compatibility, set text to BOTH clipboard and primary selection - DISABLED PRIMARY
-    println!("🔧 Setting text to clipboard only (PRIMARY selection disabled)...");
-    
-    // DEBUG: Show current clipboard content before setting
-    println!("🔍 DEBUG: Checking current clipboard content...");
-    if let Ok(clipboard_content) = get_clipboard_content() {
-        println!("📋 Current clipboard content: \"{}\"", clipboard_content);
-    } else {
-        println!("📋 Current clipboard content: <Failed to read>");
-    }
-    
-    // Set text to standard clipboard (Ctrl+C/Ctrl+V)
-    set_clipboard_content(text);
-    println!("📋 Text set to standard clipboard");
-    
-    // DEBUG: Verify clipboard content after setting
-    if let Ok(clipboard_content) = get_clipboard_content() {
-        println!("📋 Verification - Standard clipboard now contains: \"{}\"", clipboard_content);
-        if clipboard_content == text {
-            println!("✅ Standard clipboard verification SUCCESS");
-        } else {
-            println!("❌ Standard clipboard verification FAILED");
-        }
-    } else {
-        println!("❌ Failed to verify standard clipboard content");
-    }
-    
-    // PRIMARY selection code completely disabled
-    /*
-    println!("🔍 DETAILED PRIMARY DEBUG: Starting PRIMARY selection setup...");
-    
-    // Step 1: Check if xclip is available
-    if let Ok(which_output) = Command::new("which").arg("xclip").output() {
-        if which_output.status.success() {
-            println!("✅ xclip found for PRIMARY selection");
-            
-            // Step 2: FIRST - Clear PRIMARY selection completely
-            println!("🧹 CLEARING PRIMARY selection before testing...");
-            if let Ok(_clear_result) = Command::new("echo").arg("-n").arg("").stdin(std::process::Stdio::piped()).stdout(std::process::Stdio::piped()).spawn() {
-                // This creates an empty string to clear PRIMARY
-                if let Ok(mut clear_child) = Command::new("xclip")
-                    .args(&["-selection", "primary"])
-                    .stdin(std::process::Stdio::piped())
-                    .spawn()
-                {
-                    if let Some(stdin) = clear_child.stdin.as_mut() {
-                        if let Ok(_) = stdin.write_all(b"") {
-                            let _ = clear_child.wait();
-                            println!("✅ PRIMARY selection cleared");
-                        }
-                    }
+/// 按步骤注入文本，保证文本最多被输入一次；剪贴板在整个过程前后只保存/恢复一次
+pub fn inject_with<B: InjectionBackend>(
+    backend: &B,
+    strategy: InjectionStrategy,
+    text: &str,
+    delays: &TypingDelays,
+) -> Result<(), VoiceError> {
+    let mut saved_clipboard = None;
+    let mut last_error = None;
+
+    for step in strategy.steps() {
+        let result = match step {
+            InjectionStep::DirectTyping => match backend.type_text(text, delays) {
+                Ok(()) => Ok(()),
+                Err(failure) if failure.keys_sent => {
+                    warn!("⚠️ Direct typing failed after partial input, not retrying: {}", failure.error);
+                    return Err(failure.error);
                 }
+                Err(failure) => Err(failure.error),
+            },
+            InjectionStep::ClipboardPaste => {
+                saved_clipboard = backend.save_clipboard();
+                backend.paste(text, delays)
             }
-            
-            // Step 3: Wait a moment for clearing to take effect
-            std::thread::sleep(std::time::Duration::from_millis(100));
-            
-            // Step 4: Immediately check current PRIMARY selection content
-            println!("🔍 Checking current PRIMARY selection content...");
-            if let Ok(current_primary) = Command::new("xclip")
-                .args(&["-selection", "primary", "-o"])
-                .output()
-            {
-                if current_primary.status.success() {
-                    let current_text = String::from_utf8_lossy(&current_primary.stdout);
-                    let trimmed_text = current_text.trim_end_matches('\n');
-                    if !trimmed_text.is_empty() {
-                        println!("📋 CURRENT PRIMARY SELECTION: \"{}\"", trimmed_text);
-                        println!("📏 Length: {} characters", trimmed_text.len());
-                    } else {
-                        println!("📋 CURRENT PRIMARY SELECTION: <empty>");
-                    }
-                } else {
-                    println!("❌ Failed to read PRIMARY selection: {}", String::from_utf8_lossy(&current_primary.stderr));
+        };
+
+        match result {
+            Ok(()) => {
+                // 等待目标应用读取剪贴板后再恢复
+                if let Some(snapshot) = saved_clipboard {
+                    std::thread::sleep(Duration::from_millis(delays.typing_complete_ms.max(0) as u64));
+                    backend.restore_clipboard(snapshot);
                 }
-            } else {
-                println!("❌ Failed to execute xclip -selection primary -o command");
+                return Ok(());
             }
-            
-            // Step 6: Check current PRIMARY selection content BEFORE our setting
-            if let Ok(current_primary) = Command::new("xclip")
-                .args(&["-selection", "primary", "-o"])
-                .output()
-            {
-                let current_text = String::from_utf8_lossy(&current_primary.stdout);
-                println!("📋 PRIMARY content BEFORE setting our mock text: \"{}\"", current_text.trim_end_matches('\n'));
-            }
-            
-            // Step 3: Set new content to PRIMARY selection
-            println!("🔧 Setting PRIMARY selection with text: \"{}\"", text);
-            if let Ok(mut child) = Command::new("xclip")
-                .args(&["-selection", "primary"])
-                .stdin(std::process::Stdio::piped())
-                .spawn()
-            {
-                if let Some(stdin) = child.stdin.as_mut() {
-                    let bytes_written = match stdin.write_all(text.as_bytes()) {
-                        Ok(_) => {
-                            println!("✅ Bytes written to xclip stdin: {} bytes", text.as_bytes().len());
-                            "SUCCESS"
-                        }
-                        Err(e) => {
-                            println!("❌ Failed to write to xclip stdin: {}", e);
-                            "FAILED"
-                        }
-                    };
-                    
-                    // Step 4: Wait for xclip to complete
-                    println!("⏳ Waiting for xclip process to complete...");
-                    match child.wait() {
-                        Ok(status) => {
-                            if status.success() {
-                                println!("✅ xclip process completed successfully");
-                            } else {
-                                println!("❌ xclip process failed with status: {}", status);
-                            }
-                        }
-                        Err(e) => {
-                            println!("❌ Failed to wait for xclip: {}", e);
-                        }
-                    }
-                    
-                    if bytes_written == "SUCCESS" {
-                        println!("📋 Text set to PRIMARY selection for middle-click paste");
-                        
-                        // Step 5: Wait for X11 synchronization
-                        println!("⏱️ Waiting 500ms for X11 PRIMARY selection synchronization...");
-                        std::thread::sleep(std::time::Duration::from_millis(500));
-                        
-                        // Step 6: Verify PRIMARY selection content AFTER setting
-                        println!("🔍 Verifying PRIMARY selection content AFTER setting...");
-                        if let Ok(primary_output) = Command::new("xclip")
-                            .args(&["-selection", "primary", "-o"])
-                            .output()
-                        {
-                            if primary_output.status.success() {
-                                let primary_stdout = String::from_utf8_lossy(&primary_output.stdout);
-                                let primary_text = primary_stdout.trim_end_matches('\n');
-                                println!("📋 VERIFIED - PRIMARY selection now contains: \"{}\"", primary_text);
-                                println!("📏 Length: {} characters", primary_text.len());
-                                
-                                if primary_text == text {
-                                    println!("✅ PRIMARY selection verification COMPLETE SUCCESS");
-                                } else {
-                                    println!("❌ PRIMARY selection verification FAILED - Content mismatch");
-                                    println!("📋 Expected: \"{}\"", text);
-                                    println!("📋 Got:      \"{}\"", primary_text);
-                                    
-                                    // Show character-by-character comparison
-                                    println!("🔍 Character comparison:");
-                                    let expected_chars: Vec<char> = text.chars().collect();
-                                    let actual_chars: Vec<char> = primary_text.chars().collect();
-                                    for (i, (exp, act)) in expected_chars.iter().zip(actual_chars.iter()).enumerate() {
-                                        if exp == act {
-                                            println!("  [{}] '{}' = '{}' ✅", i, exp, act);
-                                        } else {
-                                            println!("  [{}] '{}' = '{}' ❌", i, exp, act);
-                                        }
-                                    }
-                                }
-                            } else {
-                                println!("❌ xclip -o command failed: {:?}", String::from_utf8_lossy(&primary_output.stderr));
-                            }
-                        } else {
-                            println!("❌ Failed to execute xclip -o command");
-                        }
-                    }
-                } else {
-                    println!("❌ Failed to get stdin handle for xclip");
-                }
-            } else {
-                println!("❌ Failed to spawn xclip process for PRIMARY selection");
+            Err(e) => {
+                warn!("⚠️ {:?} failed: {}", step, e);
+                last_error = Some(e);
             }
-        } else {
-            println!("❌ xclip not found: {}", String::from_utf8_lossy(&which_output.stderr));
         }
     }
-    
-    // Also try to set primary selection using xclip if available
-    if let Ok(_) = Command::new("xclip")
-        .args(&["-selection", "primary"])
-        .stdin(std::process::Stdio::piped())
-        .stdout(std::process::Stdio::piped())
-        .spawn()
-    {
-        // This is a fallback, continue with other methods
-    }
-    */
-    
-    // Wait for clipboard to update
-    std::thread::sleep(std::time::Duration::from_millis(delays.clipboard_update_ms as u64));
-    
-    // Use xdotool type command for direct text input
-    println!("🔧 Using xdotool type command for direct text input...");
-
-    if let Ok(_) = Command::new("which").arg("xdotool").output() {
-        println!("✅ xdotool found for direct typing");
-
-        // Add delay to ensure keyboard events are fully processed
-        println!("⏱️ Waiting {}ms for keyboard events to settle...", delays.keyboard_events_settle_ms);
-        std::thread::sleep(std::time::Duration::from_millis(delays.keyboard_events_settle_ms as u64));
-
-        // Use xdotool type to input text directly with slower typing speed for Chinese characters
-        match Command::new("xdotool")
-            .args(&["type", "--delay", &delays.character_interval_ms.to_string(), text])  // delay between characters for better Chinese input
-            .output()
-        {
-            Ok(output) => {
-                if output.status.success() {
-                    println!("✅ Direct text input successful via xdotool");
-                    println!("📝 Text typed: \"{}\"", text);
-                } else {
-                    println!("❌ xdotool type command failed: {}", String::from_utf8_lossy(&output.stderr));
+
+    // 粘贴失败时保留剪贴板中的文本，用户可以手动粘贴
+    if saved_clipboard.is_some() {
+        warn!("⚠️ Text left on clipboard for manual paste");
+    }
+    Err(last_error.unwrap_or_else(|| VoiceError::Other("No text injection step available".to_string())))
+}
+
+/// 按 token 逐段（interval 为 0 时）或逐字符输入文本，send 发送一段文本或一个按键。
+/// 文本一旦开始发送，失败时可能已经输入了前缀，按已发出按键处理，不能再粘贴
+fn type_tokens(
+    text: &str,
+    interval: Duration,
+    mut send: impl FnMut(&TypingToken) -> Result<(), VoiceError>,
+) -> Result<(), TypingFailure> {
+    let mut keys_sent = false;
+    for token in tokenize_for_typing(text) {
+        match &token {
+            TypingToken::Text(_) if interval.is_zero() => {
+                send(&token).map_err(|error| TypingFailure { error, keys_sent: true })?;
+            }
+            TypingToken::Text(chunk) => {
+                for ch in chunk.chars() {
+                    send(&TypingToken::Text(ch.to_string())).map_err(|error| TypingFailure { error, keys_sent: true })?;
+                    std::thread::sleep(interval);
                 }
             }
-            Err(e) => {
-                println!("❌ Failed to execute xdotool type: {}", e);
+            TypingToken::Enter | TypingToken::Tab => {
+                send(&token).map_err(|error| TypingFailure { error, keys_sent })?;
             }
         }
+        keys_sent = true;
+    }
+    Ok(())
+}
+
+/// 通过 enigo 和系统剪贴板注入文本
+pub struct SystemInjectionBackend;
+
+impl SystemInjectionBackend {
+    fn new_enigo() -> Result<Enigo, VoiceError> {
+        Enigo::new(&Settings::default())
+            .map_err(|e| VoiceError::Other(format!("Failed to initialize enigo: {}", e)))
+    }
+}
+
+impl InjectionBackend for SystemInjectionBackend {
+    type Snapshot = ClipboardSnapshot;
+
+    fn type_text(&self, text: &str, delays: &TypingDelays) -> Result<(), TypingFailure> {
+        let mut enigo = Self::new_enigo().map_err(|error| TypingFailure { error, keys_sent: false })?;
+        let interval = Duration::from_millis(delays.character_interval_ms.max(0) as u64);
+        type_tokens(text, interval, |token| {
+            let result = match token {
+                TypingToken::Text(chunk) => enigo.text(chunk),
+                TypingToken::Enter => enigo.key(EnigoKey::Return, Direction::Click),
+                TypingToken::Tab => enigo.key(EnigoKey::Tab, Direction::Click),
+            };
+            result.map_err(|e| VoiceError::Other(format!("Failed to send key events: {}", e)))
+        })
+    }
+
+    fn paste(&self, text: &str, delays: &TypingDelays) -> Result<(), VoiceError> {
+        clipboard::set_text(text);
+        std::thread::sleep(Duration::from_millis(delays.clipboard_update_ms.max(0) as u64));
+
+        // 与退格相同，Wayland 下 enigo 的 Ctrl+V 到不了原生窗口
+        if cfg!(target_os = "linux") && is_wayland_session() {
+            return wayland_keys::send(&wayland_keys::paste_args());
+        }
+        let modifier = if cfg!(target_os = "macos") { EnigoKey::Meta } else { EnigoKey::Control };
+        let mut enigo = Self::new_enigo()?;
+        let input_err = |e: enigo::InputError| VoiceError::Other(format!("Failed to send paste shortcut: {}", e));
+        enigo.key(modifier, Direction::Press).map_err(input_err)?;
+        let click = enigo.key(EnigoKey::Unicode('v'), Direction::Click).map_err(input_err);
+        enigo.key(modifier, Direction::Release).map_err(input_err)?;
+        click
+    }
 
-        // Add a small delay to ensure typing completes
-        std::thread::sleep(std::time::Duration::from_millis(delays.typing_complete_ms as u64));
+    fn save_clipboard(&self) -> Option<Self::Snapshot> {
+        clipboard::snapshot()
+    }
 
-    } else {
-        println!("❌ xdotool not found, cannot use direct typing");
+    fn restore_clipboard(&self, snapshot: Self::Snapshot) {
+        clipboard::restore(snapshot);
     }
 
-    println!("🔧 Text input complete");
-    return Ok(());
+    fn backspace(&self, count: usize) -> Result<(), VoiceError> {
+        // enigo 在 Linux 上走 X11，按键到不了原生 Wayland 窗口
+        if cfg!(target_os = "linux") && is_wayland_session() {
+            return wayland_keys::send(&wayland_keys::backspace_args(count));
+        }
+        let mut enigo = Self::new_enigo()?;
+        for _ in 0..count {
+            enigo
+                .key(EnigoKey::Backspace, Direction::Click)
+                .map_err(|e| VoiceError::Other(format!("Failed to send backspace: {}", e)))?;
+        }
+        Ok(())
+    }
 }
 
-#[allow(dead_code)]
-fn simulate_backspace() {
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let _ = Command::new("osascript")
-            .arg("-e")
-            .arg("tell application \"System Events\" to key code 51 using command down")
-            .output();
+/// Wayland 下通过 wtype（wlroots 系合成器）或 ydotool（需要 ydotoold 和 uinput 权限）发送按键
+mod wayland_keys {
+    use std::process::Command;
+    use crate::voice_assistant::VoiceError;
+
+    /// ydotool 使用的 Linux 输入事件键码
+    const KEY_BACKSPACE: u16 = 14;
+    const KEY_LEFTCTRL: u16 = 29;
+    const KEY_V: u16 = 47;
+
+    /// 同一组按键分别用两个工具表示
+    #[derive(Debug, PartialEq, Eq)]
+    pub struct KeyArgs {
+        pub wtype: Vec<String>,
+        pub ydotool: Vec<String>,
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        // 🔥 使用PowerShell的SendKeys模拟退格键（不会被rdev捕获）
-        use std::process::Command;
-        let _ = Command::new("powershell")
-            .arg("-WindowStyle")
-            .arg("Hidden")
-            .arg("-Command")
-            .arg("$wshell = New-Object -ComObject wscript.shell; $wshell.SendKeys('{BACKSPACE}')")
-            .output();
+    pub fn backspace_args(count: usize) -> KeyArgs {
+        KeyArgs {
+            wtype: (0..count).flat_map(|_| ["-k".to_string(), "BackSpace".to_string()]).collect(),
+            ydotool: std::iter::once("key".to_string())
+                .chain((0..count).flat_map(|_| [format!("{}:1", KEY_BACKSPACE), format!("{}:0", KEY_BACKSPACE)]))
+                .collect(),
+        }
     }
 
-    #[cfg(target_os = "linux")]
-    {
-        if let Ok(_) = Command::new("xdotool").arg("key").arg("BackSpace").output() {
-            // Success
+    /// Ctrl+V
+    pub fn paste_args() -> KeyArgs {
+        KeyArgs {
+            wtype: ["-M", "ctrl", "-k", "v", "-m", "ctrl"].map(String::from).to_vec(),
+            ydotool: std::iter::once("key".to_string())
+                .chain([(KEY_LEFTCTRL, 1), (KEY_V, 1), (KEY_V, 0), (KEY_LEFTCTRL, 0)].map(|(key, state)| format!("{}:{}", key, state)))
+                .collect(),
         }
     }
+
+    /// 先试 wtype，失败或没有安装时再试 ydotool
+    pub fn send(args: &KeyArgs) -> Result<(), VoiceError> {
+        let mut errors = Vec::new();
+        for (tool, tool_args) in [("wtype", &args.wtype), ("ydotool", &args.ydotool)] {
+            match Command::new(tool).args(tool_args).output() {
+                Ok(output) if output.status.success() => return Ok(()),
+                Ok(output) => errors.push(format!("{}: {}", tool, String::from_utf8_lossy(&output.stderr).trim())),
+                Err(e) => errors.push(format!("{}: {}", tool, e)),
+            }
+        }
+        Err(VoiceError::Other(format!(
+            "Key simulation is not supported in this Wayland session; install wtype or ydotool ({})",
+            errors.join("; ")
+        )))
+    }
+}
+
+/// 统一的文本注入入口，按策略选择 enigo 按键输入或剪贴板粘贴
+pub struct TextInjector {
+    strategy: InjectionStrategy,
+}
+
+impl TextInjector {
+    pub fn new(method: TextInjectionMethod) -> Self {
+        let strategy = select_injection_strategy(method, std::env::consts::OS, is_wayland_session());
+        Self { strategy }
+    }
+
+    pub fn strategy(&self) -> InjectionStrategy {
+        self.strategy
+    }
+
+    pub fn inject(&self, text: &str, delays: &TypingDelays) -> Result<(), VoiceError> {
+        inject_with(&SystemInjectionBackend, self.strategy, text, delays)
+    }
+}
+
+fn simulate_typing(text: &str, delays: &TypingDelays, method: TextInjectionMethod) {
+    let injector = TextInjector::new(method);
+    info!("⌨️ Injecting text via {:?}", injector.strategy());
+    tracing::trace!("Text to type: {}", redact(text));
+
+    match injector.inject(text, delays) {
+        Ok(()) => info!("✅ Text injection completed"),
+        Err(e) => error!("❌ Failed to inject text: {}", e),
+    }
 }
 
 #[allow(dead_code)]
@@ -1065,7 +1895,7 @@ fn simulate_paste(_text: &str) {
 
     #[cfg(target_os = "windows")]
     {
-        println!("🔧 simulate_paste: Starting Windows paste implementation");
+        debug!("🔧 simulate_paste: Starting Windows paste implementation");
 
         // Detect if foreground window is a terminal
         let is_terminal = unsafe {
@@ -1079,7 +1909,7 @@ fn simulate_paste(_text: &str) {
                 let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), 512);
                 if len > 0 {
                     let title = String::from_utf16_lossy(&buffer[..len as usize]).to_lowercase();
-                    println!("🔧 simulate_paste: Foreground window title: \"{}\"", title);
+                    debug!("🔧 simulate_paste: Foreground window title: \"{}\"", title);
                     // Check for common terminal names
                     title.contains("windows terminal")
                         || title.contains("powershell")
@@ -1096,7 +1926,7 @@ fn simulate_paste(_text: &str) {
         };
 
         if is_terminal {
-            println!("🔧 simulate_paste: Terminal detected, using Ctrl+Shift+V");
+            debug!("🔧 simulate_paste: Terminal detected, using Ctrl+Shift+V");
             // Try Ctrl+Shift+V for terminals (Windows Terminal, some modern terminals)
             unsafe {
                 use winapi::um::winuser::{SendInput, INPUT, KEYBDINPUT, INPUT_KEYBOARD, KEYEVENTF_KEYUP, VK_CONTROL, VK_SHIFT};
@@ -1174,12 +2004,12 @@ fn simulate_paste(_text: &str) {
                 let size = mem::size_of::<INPUT>() as i32;
                 let count = inputs.len() as u32;
 
-                println!("🔧 simulate_paste: Sending Ctrl+Shift+V ({}) inputs", count);
+                debug!("🔧 simulate_paste: Sending Ctrl+Shift+V ({}) inputs", count);
                 let result = SendInput(count, inputs.as_mut_ptr(), size);
-                println!("🔧 simulate_paste: SendInput returned {} (expected {})", result, count);
+                debug!("🔧 simulate_paste: SendInput returned {} (expected {})", result, count);
             }
         } else {
-            println!("🔧 simulate_paste: Non-terminal detected, using Ctrl+V");
+            debug!("🔧 simulate_paste: Non-terminal detected, using Ctrl+V");
             // Standard Ctrl+V for GUI applications
             unsafe {
                 use winapi::um::winuser::{SendInput, INPUT, KEYBDINPUT, INPUT_KEYBOARD, KEYEVENTF_KEYUP, VK_CONTROL};
@@ -1235,16 +2065,16 @@ fn simulate_paste(_text: &str) {
                 let size = mem::size_of::<INPUT>() as i32;
                 let count = inputs.len() as u32;
 
-                println!("🔧 simulate_paste: Sending Ctrl+V ({} inputs)", count);
+                debug!("🔧 simulate_paste: Sending Ctrl+V ({} inputs)", count);
                 let result = SendInput(count, inputs.as_mut_ptr(), size);
-                println!("🔧 simulate_paste: SendInput returned {} (expected {})", result, count);
+                debug!("🔧 simulate_paste: SendInput returned {} (expected {})", result, count);
             }
         }
 
         // Wait for paste to complete
-        println!("🔧 simulate_paste: Waiting for paste to complete");
+        debug!("🔧 simulate_paste: Waiting for paste to complete");
         std::thread::sleep(std::time::Duration::from_millis(100));
-        println!("🔧 simulate_paste: Paste operation completed");
+        debug!("🔧 simulate_paste: Paste operation completed");
     }
 
     #[cfg(target_os = "linux")]
@@ -1255,205 +2085,318 @@ fn simulate_paste(_text: &str) {
     }
 }
 
-fn get_clipboard_content() -> Result<String, VoiceError> {
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let output = Command::new("pbpaste").output()
-            .map_err(|e| VoiceError::Other(format!("Failed to get clipboard: {}", e)))?;
+/// 占位符 ASR 处理器，用于释放实际处理器时使用
+struct DefaultAsrProcessor;
+
+// 实现Send和Sync，因为ASR处理器需要在线程间共享
+unsafe impl Send for DefaultAsrProcessor {}
+unsafe impl Sync for DefaultAsrProcessor {}
+
+impl AsrProcessor for DefaultAsrProcessor {
+    fn process_audio(
+        &self,
+        _audio_buffer: std::io::Cursor<Vec<u8>>,
+        _mode: crate::voice_assistant::Mode,
+        _prompt: &str,
+    ) -> Result<AsrTranscript, VoiceError> {
+        Err(VoiceError::Other("ASR processor not available".to_string()))
+    }
 
-        Ok(String::from_utf8(output.stdout)?)
+    fn get_processor_type(&self) -> &str {
+        "default-placeholder"
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        // Use Windows API directly instead of PowerShell
-        unsafe {
-            use winapi::um::winuser::{OpenClipboard, CloseClipboard, GetClipboardData, CF_UNICODETEXT};
-            use winapi::um::winnt::WCHAR;
+    fn health_check(&self) -> Result<(), VoiceError> {
+        Err(VoiceError::Other("ASR processor not available".to_string()))
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_auto_strategy_selection() {
+        let auto = TextInjectionMethod::Auto;
+        assert_eq!(select_injection_strategy(auto, "macos", false), InjectionStrategy::DirectTyping);
+        assert_eq!(select_injection_strategy(auto, "windows", false), InjectionStrategy::DirectTyping);
+        assert_eq!(select_injection_strategy(auto, "linux", false), InjectionStrategy::DirectTyping);
+        assert_eq!(select_injection_strategy(auto, "linux", true), InjectionStrategy::ClipboardPaste);
+    }
 
-            if OpenClipboard(std::ptr::null_mut()) == 0 {
-                return Err(VoiceError::Other("Failed to open clipboard".to_string()));
-            }
+    #[test]
+    fn test_explicit_method_overrides_auto_selection() {
+        assert_eq!(
+            select_injection_strategy(TextInjectionMethod::Direct, "linux", true),
+            InjectionStrategy::DirectTyping
+        );
+        assert_eq!(
+            select_injection_strategy(TextInjectionMethod::Clipboard, "windows", false),
+            InjectionStrategy::ClipboardPaste
+        );
+        assert_eq!(TextInjectionMethod::parse(" Clipboard "), Some(TextInjectionMethod::Clipboard));
+        assert_eq!(TextInjectionMethod::parse("xdotool"), None);
+    }
+
+    #[test]
+    fn test_strategy_steps() {
+        assert_eq!(
+            InjectionStrategy::DirectTyping.steps(),
+            &[InjectionStep::DirectTyping, InjectionStep::ClipboardPaste]
+        );
+        assert_eq!(InjectionStrategy::ClipboardPaste.steps(), &[InjectionStep::ClipboardPaste]);
+    }
+
+    /// 记录调用次数的注入后端
+    #[derive(Default)]
+    struct MockBackend {
+        typing: Option<bool>, // None 成功，Some(keys_sent) 失败
+        /// 通过 type_tokens 输入，第一段文本输入两个字符后失败
+        fails_mid_chunk: bool,
+        paste_fails: bool,
+        typed: std::cell::Cell<u32>,
+        typed_chars: std::cell::Cell<usize>,
+        pasted: std::cell::Cell<u32>,
+        saved: std::cell::Cell<u32>,
+        restored: std::cell::Cell<u32>,
+        backspaces: std::cell::Cell<usize>,
+    }
+
+    impl MockBackend {
+        fn injections(&self) -> u32 {
+            let typed_ok = if self.typing.is_none() { self.typed.get() } else { 0 };
+            let pasted_ok = if self.paste_fails { 0 } else { self.pasted.get() };
+            typed_ok + pasted_ok
+        }
+    }
 
-            let clipboard_data = GetClipboardData(CF_UNICODETEXT);
-            if clipboard_data.is_null() {
-                CloseClipboard();
-                return Err(VoiceError::Other("Failed to get clipboard data".to_string()));
+    impl InjectionBackend for MockBackend {
+        type Snapshot = ();
+
+        fn type_text(&self, text: &str, _delays: &TypingDelays) -> Result<(), TypingFailure> {
+            self.typed.set(self.typed.get() + 1);
+            if self.fails_mid_chunk {
+                return type_tokens(text, Duration::ZERO, |token| match token {
+                    TypingToken::Text(chunk) => {
+                        self.typed_chars.set(self.typed_chars.get() + chunk.chars().count().min(2));
+                        Err(VoiceError::Other("enigo failed".to_string()))
+                    }
+                    TypingToken::Enter | TypingToken::Tab => Ok(()),
+                });
             }
+            match self.typing {
+                None => Ok(()),
+                Some(keys_sent) => Err(TypingFailure { error: VoiceError::Other("typing failed".to_string()), keys_sent }),
+            }
+        }
 
-            let text_ptr = clipboard_data as *const WCHAR;
-            let mut len = 0;
-            while *text_ptr.offset(len) != 0 {
-                len += 1;
+        fn paste(&self, _text: &str, _delays: &TypingDelays) -> Result<(), VoiceError> {
+            self.pasted.set(self.pasted.get() + 1);
+            if self.paste_fails {
+                Err(VoiceError::Other("paste failed".to_string()))
+            } else {
+                Ok(())
             }
+        }
 
-            let slice = std::slice::from_raw_parts(text_ptr, len as usize);
-            let text = String::from_utf16(slice)
-                .map_err(|e| VoiceError::Other(format!("Failed to parse clipboard text: {}", e)))?;
+        fn save_clipboard(&self) -> Option<()> {
+            self.saved.set(self.saved.get() + 1);
+            Some(())
+        }
 
-            CloseClipboard();
-            Ok(text)
+        fn restore_clipboard(&self, _snapshot: ()) {
+            self.restored.set(self.restored.get() + 1);
         }
-    }
 
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Command;
-        if let Ok(output) = Command::new("xclip").arg("-selection").arg("clipboard").arg("-o").output() {
-            Ok(String::from_utf8(output.stdout)?)
-        } else {
-            Err(VoiceError::Other("xclip not found".to_string()))
+        fn backspace(&self, count: usize) -> Result<(), VoiceError> {
+            self.backspaces.set(self.backspaces.get() + count);
+            Ok(())
         }
     }
 
-    #[cfg(not(any(target_os = "macos", target_os = "windows", target_os = "linux")))]
-    {
-        Err(VoiceError::Other("Platform not supported".to_string()))
+    fn no_delays() -> TypingDelays {
+        TypingDelays {
+            clipboard_update_ms: 0,
+            keyboard_events_settle_ms: 0,
+            typing_complete_ms: 0,
+            character_interval_ms: 0,
+            short_operation_ms: 0,
+        }
     }
-}
 
-fn set_clipboard_content(text: &str) {
-    #[cfg(target_os = "macos")]
-    {
-        use std::process::Command;
-        let _ = Command::new("pbcopy")
-            .write_all(text.as_bytes());
+    #[test]
+    fn test_direct_typing_success_does_not_touch_clipboard() {
+        let backend = MockBackend::default();
+        inject_with(&backend, InjectionStrategy::DirectTyping, "hello", &no_delays()).unwrap();
+        assert_eq!(backend.injections(), 1);
+        assert_eq!(backend.pasted.get(), 0);
+        assert_eq!(backend.saved.get(), 0);
     }
 
-    #[cfg(target_os = "windows")]
-    {
-        // Use Windows API directly instead of PowerShell
-        unsafe {
-            use winapi::um::winuser::{OpenClipboard, CloseClipboard, EmptyClipboard, SetClipboardData, CF_UNICODETEXT};
-            use winapi::um::winbase::{GlobalAlloc, GlobalLock, GlobalUnlock, GMEM_MOVEABLE};
-            use winapi::um::winnt::WCHAR;
+    #[test]
+    fn test_paste_fallback_injects_once_and_restores_clipboard_once() {
+        let backend = MockBackend { typing: Some(false), ..Default::default() };
+        inject_with(&backend, InjectionStrategy::DirectTyping, "hello", &no_delays()).unwrap();
+        assert_eq!(backend.typed.get(), 1);
+        assert_eq!(backend.pasted.get(), 1);
+        assert_eq!(backend.injections(), 1);
+        assert_eq!(backend.saved.get(), 1);
+        assert_eq!(backend.restored.get(), 1);
+    }
 
-            if OpenClipboard(std::ptr::null_mut()) == 0 {
-                return;
-            }
+    #[test]
+    fn test_partial_typing_is_not_pasted_again() {
+        let backend = MockBackend { typing: Some(true), ..Default::default() };
+        assert!(inject_with(&backend, InjectionStrategy::DirectTyping, "hello", &no_delays()).is_err());
+        assert_eq!(backend.pasted.get(), 0);
+        assert_eq!(backend.saved.get(), 0);
+    }
 
-            EmptyClipboard();
+    #[test]
+    fn test_failure_inside_a_text_chunk_is_not_pasted_again() {
+        let backend = MockBackend { fails_mid_chunk: true, ..Default::default() };
+        assert!(inject_with(&backend, InjectionStrategy::DirectTyping, "hello", &no_delays()).is_err());
+        assert_eq!(backend.typed_chars.get(), 2);
+        assert_eq!(backend.pasted.get(), 0);
+        assert_eq!(backend.saved.get(), 0);
+    }
 
-            // Convert string to UTF-16
-            let utf16_text: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
-            let byte_len = utf16_text.len() * std::mem::size_of::<WCHAR>();
+    #[test]
+    fn test_key_failure_before_any_text_falls_back_to_paste() {
+        let failure = type_tokens("\nhello", Duration::ZERO, |token| match token {
+            TypingToken::Enter => Err(VoiceError::Other("enigo failed".to_string())),
+            _ => Ok(()),
+        })
+        .unwrap_err();
+        assert!(!failure.keys_sent);
+    }
 
-            // Allocate global memory
-            let handle = GlobalAlloc(GMEM_MOVEABLE, byte_len);
-            if handle.is_null() {
-                CloseClipboard();
-                return;
-            }
+    #[test]
+    fn test_delete_sends_one_backspace_per_char() {
+        let backend = MockBackend::default();
+        delete_chars_with(&backend, 0).unwrap();
+        assert_eq!(backend.backspaces.get(), 0);
+        delete_chars_with(&backend, 5).unwrap();
+        assert_eq!(backend.backspaces.get(), 5);
+        assert_eq!(backend.injections(), 0);
+    }
 
-            // Lock and copy data
-            let ptr = GlobalLock(handle);
-            if ptr.is_null() {
-                GlobalUnlock(handle);
-                CloseClipboard();
-                return;
-            }
+    #[test]
+    fn test_wayland_backspace_args() {
+        let args = wayland_keys::backspace_args(3);
+        assert_eq!(args.wtype.iter().filter(|a| *a == "BackSpace").count(), 3);
+        assert_eq!(args.ydotool, ["key", "14:1", "14:0", "14:1", "14:0", "14:1", "14:0"]);
+        assert_eq!(wayland_keys::backspace_args(0).ydotool, ["key"]);
+    }
 
-            std::ptr::copy_nonoverlapping(utf16_text.as_ptr(), ptr as *mut WCHAR, utf16_text.len());
-            GlobalUnlock(handle);
+    #[test]
+    fn test_wayland_paste_args() {
+        let args = wayland_keys::paste_args();
+        assert_eq!(args.wtype, ["-M", "ctrl", "-k", "v", "-m", "ctrl"]);
+        assert_eq!(args.ydotool, ["key", "29:1", "47:1", "47:0", "29:0"]);
+    }
 
-            // Set clipboard data
-            SetClipboardData(CF_UNICODETEXT, handle);
-            CloseClipboard();
-        }
+    #[test]
+    fn test_failed_paste_leaves_text_on_clipboard() {
+        let backend = MockBackend { paste_fails: true, ..Default::default() };
+        assert!(inject_with(&backend, InjectionStrategy::ClipboardPaste, "hello", &no_delays()).is_err());
+        assert_eq!(backend.typed.get(), 0);
+        assert_eq!(backend.pasted.get(), 1);
+        assert_eq!(backend.restored.get(), 0);
     }
 
-    #[cfg(target_os = "linux")]
-    {
-        use std::process::Command;
-        
-        // Try multiple clipboard methods
-        let mut success = false;
-        
-        // Method 1: Try xclip (most common)
-        if let Ok(output) = Command::new("which").arg("xclip").output() {
-            if output.status.success() {
-                if let Ok(mut child) = Command::new("xclip")
-                    .args(&["-selection", "clipboard"])
-                    .stdin(std::process::Stdio::piped())
-                    .spawn() 
-                {
-                    if let Some(stdin) = child.stdin.as_mut() {
-                        if let Ok(_) = stdin.write_all(text.as_bytes()) {
-                            let _ = child.wait();
-                            success = true;
-                            println!("✅ Text set to clipboard via xclip");
-                        }
-                    }
-                }
-            }
-        }
-        
-        // Method 2: Try xsel if xclip fails
-        if !success {
-            if let Ok(output) = Command::new("which").arg("xsel").output() {
-                if output.status.success() {
-                    if let Ok(mut child) = Command::new("xsel")
-                        .args(&["--clipboard", "--input"])
-                        .stdin(std::process::Stdio::piped())
-                        .spawn()
-                    {
-                        if let Some(stdin) = child.stdin.as_mut() {
-                            if let Ok(_) = stdin.write_all(text.as_bytes()) {
-                                let _ = child.wait();
-                                success = true;
-                                println!("✅ Text set to clipboard via xsel");
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        // Method 3: Try wl-copy (Wayland)
-        if !success {
-            if let Ok(output) = Command::new("which").arg("wl-copy").output() {
-                if output.status.success() {
-                    if let Ok(_) = Command::new("wl-copy")
-                        .arg(text)
-                        .output()
-                    {
-                        success = true;
-                        println!("✅ Text set to clipboard via wl-copy");
-                    }
-                }
-            }
-        }
-        
-        if !success {
-            eprintln!("❌ Warning: No clipboard utility found (xclip, xsel, wl-copy)");
-            eprintln!("💡 Install one of: sudo apt install xclip");
-            eprintln!("📝 Falling back to echo command for basic output");
+    #[test]
+    fn test_plugin_signals_follow_rdev_transitions() {
+        use HotkeyAction::*;
+        use HotkeySignal::*;
+        let hold = RecordingMode::Hold;
+
+        assert_eq!(next_state_for_signal(InputState::Idle, Pressed(Transcribe), hold), Some(InputState::Recording));
+        assert_eq!(next_state_for_signal(InputState::Idle, Pressed(Translate), hold), Some(InputState::RecordingTranslate));
+        assert_eq!(next_state_for_signal(InputState::Recording, Released(Transcribe), hold), Some(InputState::Processing));
+        assert_eq!(next_state_for_signal(InputState::RecordingTranslate, Released(Translate), hold), Some(InputState::Translating));
+
+        // 录音中按另一个热键不改变状态
+        assert_eq!(next_state_for_signal(InputState::Recording, Pressed(Translate), hold), None);
+        assert_eq!(next_state_for_signal(InputState::Recording, Released(Translate), hold), None);
+        // 识别中按下开始下一次录音，排队识别；松开不改变状态
+        assert_eq!(next_state_for_signal(InputState::Processing, Pressed(Transcribe), hold), Some(InputState::Recording));
+        assert_eq!(next_state_for_signal(InputState::Translating, Pressed(Translate), hold), Some(InputState::RecordingTranslate));
+        assert_eq!(next_state_for_signal(InputState::Processing, Released(Transcribe), hold), None);
+        assert_eq!(next_state_for_signal(InputState::Idle, Released(Transcribe), hold), None);
+    }
 
-            // As a last resort, just print to stdout so user can see it
-            println!("📋 Text to copy manually: {}", text);
-        }
+    #[test]
+    fn test_toggle_mode_transitions() {
+        use HotkeyAction::*;
+        use HotkeySignal::*;
+        let toggle = RecordingMode::Toggle;
+
+        assert_eq!(next_state_for_signal(InputState::Idle, Pressed(Transcribe), toggle), Some(InputState::Recording));
+        // 松开不停止录音，再次按下才停止
+        assert_eq!(next_state_for_signal(InputState::Recording, Released(Transcribe), toggle), None);
+        assert_eq!(next_state_for_signal(InputState::Recording, Pressed(Transcribe), toggle), Some(InputState::Processing));
+        assert_eq!(next_state_for_signal(InputState::RecordingTranslate, Pressed(Translate), toggle), Some(InputState::Translating));
+        assert_eq!(next_state_for_signal(InputState::Recording, Pressed(Translate), toggle), None);
+
+        // 超时在两种模式下都会停止录音，Idle 时忽略
+        assert_eq!(next_state_for_signal(InputState::Recording, MaxDuration(1), toggle), Some(InputState::Processing));
+        assert_eq!(next_state_for_signal(InputState::RecordingTranslate, MaxDuration(1), RecordingMode::Hold), Some(InputState::Translating));
+        assert_eq!(next_state_for_signal(InputState::Idle, MaxDuration(1), toggle), None);
+
+        // 静音自动停止与超时相同
+        assert_eq!(next_state_for_signal(InputState::Recording, Silence(2), RecordingMode::Hold), Some(InputState::Processing));
+        assert_eq!(next_state_for_signal(InputState::RecordingTranslate, Silence(2), toggle), Some(InputState::Translating));
+        assert_eq!(next_state_for_signal(InputState::Processing, Silence(2), toggle), None);
+
+        assert_eq!(MaxDuration(1).auto_stop_reason(), Some("max_duration"));
+        assert_eq!(Silence(1).auto_stop_reason(), Some("silence"));
+        assert_eq!(Released(Transcribe).auto_stop_reason(), None);
     }
-}
 
-/// 占位符 ASR 处理器，用于释放实际处理器时使用
-struct DefaultAsrProcessor;
+    #[test]
+    fn test_max_duration_and_release_at_the_limit() {
+        use HotkeyAction::*;
+        use HotkeySignal::*;
 
-// 实现Send和Sync，因为ASR处理器需要在线程间共享
-unsafe impl Send for DefaultAsrProcessor {}
-unsafe impl Sync for DefaultAsrProcessor {}
+        // 到达上限时超时信号和松开同时到达：先到的进入识别，后到的被忽略，识别照常进行
+        let hold = RecordingMode::Hold;
+        let state = next_state_for_signal(InputState::Recording, MaxDuration(3), hold).unwrap();
+        assert_eq!(state, InputState::Processing);
+        assert_eq!(next_state_for_signal(state, Released(Transcribe), hold), None);
 
-impl AsrProcessor for DefaultAsrProcessor {
-    fn process_audio(
-        &self,
-        _audio_buffer: std::io::Cursor<Vec<u8>>,
-        _mode: crate::voice_assistant::Mode,
-        _prompt: &str,
-    ) -> Result<String, VoiceError> {
-        Err(VoiceError::Other("ASR processor not available".to_string()))
+        let state = next_state_for_signal(InputState::Recording, Released(Transcribe), hold).unwrap();
+        assert_eq!(state, InputState::Processing);
+        assert_eq!(next_state_for_signal(state, MaxDuration(3), hold), None);
+
+        assert_eq!(RecordingMode::parse(" Toggle "), Some(RecordingMode::Toggle));
+        assert_eq!(RecordingMode::parse("latch"), None);
+    }
+
+    #[test]
+    fn test_hotkey_backend_parse() {
+        assert_eq!(HotkeyBackend::parse("rdev"), Some(HotkeyBackend::Rdev));
+        assert_eq!(HotkeyBackend::parse(" Plugin "), Some(HotkeyBackend::Plugin));
+        assert_eq!(HotkeyBackend::parse("x11"), None);
+        assert_eq!(HotkeyBackend::default().as_str(), "rdev");
     }
 
-    fn get_processor_type(&self) -> Option<&str> {
-        Some("default-placeholder")
+    #[test]
+    fn test_tokenize_special_characters() {
+        assert_eq!(
+            tokenize_for_typing("第一行\r\n\"quoted\"\tend\n"),
+            vec![
+                TypingToken::Text("第一行".to_string()),
+                TypingToken::Enter,
+                TypingToken::Text("\"quoted\"".to_string()),
+                TypingToken::Tab,
+                TypingToken::Text("end".to_string()),
+                TypingToken::Enter,
+            ]
+        );
+        // 其他控制字符被丢弃，emoji 和反斜杠原样保留
+        assert_eq!(
+            tokenize_for_typing("a\u{7}b 🎉 \\"),
+            vec![TypingToken::Text("ab 🎉 \\".to_string())]
+        );
     }
-}
\ No newline at end of file
+}
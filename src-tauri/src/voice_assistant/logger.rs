@@ -1,13 +1,136 @@
-use tracing::{info, warn, error, debug, Level};
-use tracing_appender::{rolling, non_blocking};
+use tracing::{info, warn, error, debug, Level, Event, Subscriber};
+use tracing::field::{Field, Visit};
+use tracing_appender::non_blocking::NonBlockingBuilder;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::{
     fmt::{self, format::FmtSpan},
-    layer::SubscriberExt,
+    layer::{Context, SubscriberExt},
+    reload,
     util::SubscriberInitExt,
-    EnvFilter, Registry,
+    EnvFilter, Layer, Registry,
 };
+use std::collections::VecDeque;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+
+const DEFAULT_LOG_LEVEL: &str = "info";
+const LOG_LEVELS: [&str; 5] = ["trace", "debug", "info", "warn", "error"];
+/// 内存中保留的最近日志行数，供设置页查看
+const RECENT_LOG_CAPACITY: usize = 1000;
+/// 日志文件按天轮转，文件名形如 voicetype.2024-03-01.log
+const LOG_FILE_PREFIX: &str = "voicetype";
+const LOG_FILE_SUFFIX: &str = "log";
+/// 保留最近 7 天的日志文件
+const MAX_LOG_FILES: usize = 7;
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+static CURRENT_LEVEL: OnceLock<Mutex<String>> = OnceLock::new();
+static RECENT_LOGS: OnceLock<Mutex<VecDeque<String>>> = OnceLock::new();
+/// 日志文件所在目录，文件日志初始化失败时为空
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// 是否在日志中输出用户文本/剪贴板内容（默认脱敏）
+static LOG_SENSITIVE: OnceLock<AtomicBool> = OnceLock::new();
+/// 是否记录每次按键（默认关闭，仅用于调试热键问题）
+static LOG_KEYSTROKES: OnceLock<AtomicBool> = OnceLock::new();
+
+fn env_flag(name: &str) -> bool {
+    std::env::var(name)
+        .map(|v| matches!(v.to_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+fn sensitive_flag() -> &'static AtomicBool {
+    LOG_SENSITIVE.get_or_init(|| AtomicBool::new(env_flag("VOICETYPE_LOG_SENSITIVE")))
+}
+
+fn keystroke_flag() -> &'static AtomicBool {
+    LOG_KEYSTROKES.get_or_init(|| AtomicBool::new(env_flag("VOICETYPE_DEBUG_KEYS")))
+}
+
+/// 用户文本、剪贴板内容等敏感数据在日志中只显示长度，
+/// 设置 VOICETYPE_LOG_SENSITIVE=1 才输出原文
+pub fn redact(text: &str) -> String {
+    if sensitive_flag().load(Ordering::Relaxed) {
+        format!("{:?}", text)
+    } else {
+        format!("<redacted {} chars>", text.chars().count())
+    }
+}
+
+pub fn set_sensitive_logging(enabled: bool) {
+    sensitive_flag().store(enabled, Ordering::Relaxed);
+}
+
+/// 按键日志需要显式开启（VOICETYPE_DEBUG_KEYS=1）
+pub fn keystroke_logging_enabled() -> bool {
+    keystroke_flag().load(Ordering::Relaxed)
+}
+
+pub fn set_keystroke_logging(enabled: bool) {
+    keystroke_flag().store(enabled, Ordering::Relaxed);
+}
+
+fn filter_for_level(level: &str) -> EnvFilter {
+    EnvFilter::new(format!("voicetype_lib={}", level))
+}
+
+fn recent_logs() -> &'static Mutex<VecDeque<String>> {
+    RECENT_LOGS.get_or_init(|| Mutex::new(VecDeque::with_capacity(RECENT_LOG_CAPACITY)))
+}
+
+/// 把日志事件格式化后放入环形缓冲区
+struct RecentLogsLayer;
+
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: Vec<String>,
+}
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            self.message = format!("{:?}", value);
+        } else {
+            self.fields.push(format!("{}={:?}", field.name(), value));
+        }
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message = value.to_string();
+        } else {
+            self.fields.push(format!("{}={}", field.name(), value));
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RecentLogsLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let mut line = format!(
+            "{} {:>5} {}",
+            chrono::Local::now().format("%Y-%m-%d %H:%M:%S"),
+            event.metadata().level(),
+            visitor.message
+        );
+        if !visitor.fields.is_empty() {
+            line.push(' ');
+            line.push_str(&visitor.fields.join(" "));
+        }
+
+        let mut buffer = recent_logs().lock().unwrap();
+        if buffer.len() >= RECENT_LOG_CAPACITY {
+            buffer.pop_front();
+        }
+        buffer.push_back(line);
+    }
+}
 
 pub struct Logger {
     _guards: Vec<tracing_appender::non_blocking::WorkerGuard>,
@@ -17,45 +140,59 @@ impl Logger {
     pub fn new() -> Result<Self, Box<dyn std::error::Error>> {
         let mut guards = Vec::new();
 
-        // Create logs directory if it doesn't exist
-        let logs_dir = Path::new("logs");
-        if !logs_dir.exists() {
-            fs::create_dir_all(logs_dir)?;
-        }
+        // 文件日志写到应用数据目录，失败时只保留控制台和内存日志
+        let logs_dir = crate::utils::platform::get_logs_dir();
+        let file_layer = match rolling_file_appender(&logs_dir) {
+            Ok(file_appender) => {
+                // 非阻塞写入：缓冲区满时丢弃日志，不会卡住录音/键盘线程
+                let (non_blocking_file, file_guard) = NonBlockingBuilder::default()
+                    .lossy(true)
+                    .finish(file_appender);
+                guards.push(file_guard);
+                LOG_DIR.set(logs_dir).ok();
 
-        // Set up file appender with rotation
-        let file_appender = rolling::never("logs", "app.log");
-        let (non_blocking_file, file_guard) = non_blocking(file_appender);
-        guards.push(file_guard);
+                Some(
+                    fmt::layer()
+                        .with_writer(non_blocking_file)
+                        .with_target(false)
+                        .with_timer(tracing_subscriber::fmt::time::ChronoUtc::new("%Y-%m-%d %H:%M:%S".to_string()))
+                        .with_ansi(false)
+                        .with_level(true)
+                        .compact(),
+                )
+            }
+            Err(e) => {
+                eprintln!("⚠️ Failed to create log file in {}: {}", logs_dir.display(), e);
+                None
+            }
+        };
 
-        // Set up console layer with colors
-        let console_layer = fmt::layer()
+        // 控制台输出只在 debug 构建中保留
+        let console_layer = cfg!(debug_assertions).then(|| fmt::layer()
             .with_target(false)
             .with_span_events(FmtSpan::CLOSE)
             .with_timer(tracing_subscriber::fmt::time::ChronoUtc::new("%H:%M:%S".to_string()))
             .with_level(true)
             .with_ansi(true)
-            .compact();
-
-        // Set up file layer (no colors, simple format)
-        let file_layer = fmt::layer()
-            .with_writer(non_blocking_file)
-            .with_target(false)
-            .with_timer(tracing_subscriber::fmt::time::ChronoUtc::new("%Y-%m-%d %H:%M:%S".to_string()))
-            .with_ansi(false)
-            .with_level(true)
-            .compact();
+            .compact());
 
-        // Set up environment filter (can be overridden by RUST_LOG env var)
+        // Set up environment filter (can be overridden by RUST_LOG env var),
+        // wrapped in a reload layer so the level can be changed at runtime
         let env_filter = EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| EnvFilter::new("hello_tauri_lib=info"));
+            .unwrap_or_else(|_| filter_for_level(DEFAULT_LOG_LEVEL));
+        let (filter_layer, filter_handle) = reload::Layer::new(env_filter);
+        FILTER_HANDLE.set(filter_handle).ok();
+        CURRENT_LEVEL.get_or_init(|| Mutex::new(
+            std::env::var("RUST_LOG").unwrap_or_else(|_| DEFAULT_LOG_LEVEL.to_string())
+        ));
 
-        // Initialize global subscriber with both layers
+        // Initialize global subscriber with console, file and ring buffer layers
         Registry::default()
-            .with(env_filter)
+            .with(filter_layer)
             .with(console_layer)
             .with(file_layer)
-            .init();
+            .with(RecentLogsLayer)
+            .try_init()?;
 
         info!("Logger initialized successfully");
         debug!("Debug logging enabled");
@@ -64,13 +201,33 @@ impl Logger {
     }
 
     pub fn set_level(&self, level: Level) {
-        // Note: In tracing, changing log level dynamically after initialization
-        // requires more complex setup. For now, this is a placeholder.
-        // In practice, you'd use directives or rebuild the subscriber.
-        info!("Log level set to {:?}", level);
+        if let Err(e) = set_log_level_internal(&level.to_string().to_lowercase()) {
+            warn!("Failed to set log level: {}", e);
+        }
     }
 }
 
+fn rolling_file_appender(logs_dir: &Path) -> Result<RollingFileAppender, Box<dyn std::error::Error>> {
+    fs::create_dir_all(logs_dir)?;
+    let appender = RollingFileAppender::builder()
+        .rotation(Rotation::DAILY)
+        .filename_prefix(LOG_FILE_PREFIX)
+        .filename_suffix(LOG_FILE_SUFFIX)
+        .max_log_files(MAX_LOG_FILES)
+        .build(logs_dir)?;
+    Ok(appender)
+}
+
+/// 当前正在写入的日志文件（按 UTC 日期轮转）
+fn current_log_file(logs_dir: &Path) -> PathBuf {
+    logs_dir.join(format!(
+        "{}.{}.{}",
+        LOG_FILE_PREFIX,
+        chrono::Utc::now().format("%Y-%m-%d"),
+        LOG_FILE_SUFFIX
+    ))
+}
+
 impl Default for Logger {
     fn default() -> Self {
         Self::new().expect("Failed to initialize logger")
@@ -100,8 +257,11 @@ static INIT: std::sync::Once = std::sync::Once::new();
 
 pub fn init_logger() -> Result<(), Box<dyn std::error::Error>> {
     INIT.call_once(|| {
-        unsafe {
-            GLOBAL_LOGGER = Some(Logger::new().expect("Failed to initialize global logger"));
+        match Logger::new() {
+            Ok(logger) => unsafe {
+                GLOBAL_LOGGER = Some(logger);
+            },
+            Err(e) => eprintln!("Failed to initialize global logger: {}", e),
         }
     });
     Ok(())
@@ -114,6 +274,56 @@ pub fn get_logger() -> Option<&'static Logger> {
     }
 }
 
+/// 运行时修改日志级别
+pub fn set_log_level_internal(level: &str) -> Result<(), String> {
+    let level = level.trim().to_lowercase();
+    if !LOG_LEVELS.contains(&level.as_str()) {
+        return Err(format!("Invalid log level: {} (expected one of {})", level, LOG_LEVELS.join(", ")));
+    }
+
+    let handle = FILTER_HANDLE.get().ok_or_else(|| "Logger not initialized".to_string())?;
+    handle
+        .reload(filter_for_level(&level))
+        .map_err(|e| format!("Failed to reload log filter: {}", e))?;
+
+    *CURRENT_LEVEL.get_or_init(|| Mutex::new(DEFAULT_LOG_LEVEL.to_string())).lock().unwrap() = level.clone();
+    info!("Log level set to {}", level);
+    Ok(())
+}
+
+/// 最近的 n 条日志，按时间顺序
+pub fn get_recent_log_lines(n: usize) -> Vec<String> {
+    let buffer = recent_logs().lock().unwrap();
+    let skip = buffer.len().saturating_sub(n);
+    buffer.iter().skip(skip).cloned().collect()
+}
+
+#[tauri::command]
+pub async fn get_log_level() -> Result<String, String> {
+    Ok(CURRENT_LEVEL
+        .get()
+        .map(|level| level.lock().unwrap().clone())
+        .unwrap_or_else(|| DEFAULT_LOG_LEVEL.to_string()))
+}
+
+#[tauri::command]
+pub async fn set_log_level(level: String) -> Result<String, String> {
+    set_log_level_internal(&level)?;
+    get_log_level().await
+}
+
+/// 当前日志文件的路径，前端用于"打开日志文件夹"
+#[tauri::command]
+pub async fn get_log_file_path() -> Result<String, String> {
+    let logs_dir = LOG_DIR.get().ok_or_else(|| "File logging is not enabled".to_string())?;
+    Ok(current_log_file(logs_dir).to_string_lossy().to_string())
+}
+
+#[tauri::command]
+pub async fn get_recent_logs(n: Option<usize>) -> Result<Vec<String>, String> {
+    Ok(get_recent_log_lines(n.unwrap_or(200)))
+}
+
 // Macro-based logging for more idiomatic Rust usage
 #[macro_export]
 macro_rules! log_info {
@@ -141,4 +351,43 @@ macro_rules! log_debug {
     ($($arg:tt)*) => {
         tracing::debug!($($arg)*);
     };
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_hides_text_by_default() {
+        set_sensitive_logging(false);
+        assert_eq!(redact("我的密码是 hunter2"), "<redacted 13 chars>");
+        set_sensitive_logging(true);
+        assert_eq!(redact("hello"), "\"hello\"");
+        set_sensitive_logging(false);
+    }
+
+    #[test]
+    fn test_recent_logs_returns_tail() {
+        {
+            let mut buffer = recent_logs().lock().unwrap();
+            buffer.clear();
+            for i in 0..5 {
+                buffer.push_back(format!("line {}", i));
+            }
+        }
+        assert_eq!(get_recent_log_lines(2), vec!["line 3".to_string(), "line 4".to_string()]);
+        assert_eq!(get_recent_log_lines(10).len(), 5);
+    }
+
+    #[test]
+    fn test_current_log_file_name() {
+        let path = current_log_file(Path::new("/tmp/logs"));
+        let name = path.file_name().unwrap().to_string_lossy().to_string();
+        assert!(name.starts_with("voicetype.") && name.ends_with(".log"));
+        assert_eq!(path.parent(), Some(Path::new("/tmp/logs")));
+    }
+
+    #[test]
+    fn test_invalid_level_rejected() {
+        assert!(set_log_level_internal("verbose").is_err());
+    }
+}
@@ -1,15 +1,33 @@
 pub mod traits;
 pub mod recorder;
+pub mod audio;
+pub mod audio_decoder;
 pub mod keyboard;
+pub mod clipboard;
 pub mod logger;
 pub mod asr;
 pub mod global_whisper;
 pub mod translate;
 pub mod coordinator;
 pub mod hotkey_parser;
-// pub mod system_tray;
+pub mod hotkey_capture;
+pub mod postprocess;
+pub mod settings;
+pub mod audio_prep;
+pub mod replacements;
+pub mod dictation_commands;
+pub mod output_profiles;
+pub mod sanitize;
+pub mod transcript;
+pub mod result_sink;
+pub mod state_machine;
+#[cfg(desktop)]
+pub mod system_tray;
+pub mod overlay;
 pub mod global_hotkey;
 pub mod model_manager;
+pub mod net;
+pub mod http_api;
 
 pub use traits::*;
 pub use recorder::*;
@@ -18,6 +36,7 @@ pub use asr::*;
 pub use translate::*;
 pub use logger::*;
 pub use coordinator::*;
-// pub use system_tray::*;
+#[cfg(desktop)]
+pub use system_tray::*;
 pub use global_hotkey::*;
 pub use model_manager::*;
\ No newline at end of file
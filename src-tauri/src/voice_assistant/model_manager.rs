@@ -2,7 +2,7 @@ use std::path::{Path, PathBuf};
 use std::fs;
 use std::process::Command;
 use serde::{Serialize, Deserialize};
-use tauri::{AppHandle, Emitter, Manager};
+use tauri::{AppHandle, Emitter};
 use crate::voice_assistant::VoiceError;
 
 /// Download site configuration
@@ -86,6 +86,12 @@ pub struct WhisperModel {
     pub file_path: Option<String>,
     pub download_progress: f64,
     pub is_downloading: bool,
+    /// 加载需要的内存估算（MB），UI 用它把放不下的模型置灰
+    #[serde(default)]
+    pub estimated_memory_mb: u64,
+    /// 按当前后端的可用内存/显存判断能否加载，无法查询时为 None
+    #[serde(default)]
+    pub fits_in_memory: Option<bool>,
 }
 
 impl WhisperModel {
@@ -101,6 +107,8 @@ impl WhisperModel {
             file_path: None,
             download_progress: 0.0,
             is_downloading: false,
+            estimated_memory_mb: crate::voice_assistant::asr::memory_check::estimate_required_mb((size_mb * 1024.0 * 1024.0) as u64),
+            fits_in_memory: None,
         }
     }
 
@@ -119,11 +127,7 @@ pub struct ModelManager {
 
 impl ModelManager {
     pub fn new(app_handle: AppHandle) -> Result<Self, VoiceError> {
-        let models_dir = app_handle
-            .path()
-            .app_data_dir()
-            .unwrap_or_else(|_| std::env::current_dir().unwrap().join("data"))
-            .join("models");
+        let models_dir = crate::utils::platform::get_models_dir();
 
         // Create models directory if it doesn't exist
         fs::create_dir_all(&models_dir)
@@ -450,8 +454,14 @@ impl ModelManager {
             .find(|m| m.name == model_name && m.is_downloaded)
             .ok_or_else(|| VoiceError::Other(format!("Downloaded model '{}' not found", model_name)))?;
 
-        // Set environment variable
-        std::env::set_var("WHISPER_MODEL_PATH", &model.file_path.as_ref().unwrap());
+        // 保存激活的模型，重新加载设置后仍然有效
+        let active_path = model.file_path.clone().unwrap_or_default();
+        tokio::spawn(async move {
+            if let Err(e) = crate::voice_assistant::settings::persist(crate::voice_assistant::settings::WHISPER_MODEL_PATH, &active_path).await {
+                tracing::warn!("⚠️ Failed to save active model: {}", e);
+                crate::voice_assistant::settings::update(|s| s.whisper_model_path = Some(active_path));
+            }
+        });
 
         // 🔥 NEW: 预加载模型到GPU
         println!("🚀 Pre-loading model '{}' to GPU...", model_name);
@@ -525,8 +535,8 @@ impl ModelManager {
     }
 
     pub fn get_active_model(&self) -> Option<String> {
-        std::env::var("WHISPER_MODEL_PATH")
-            .ok()
+        crate::voice_assistant::settings::current()
+            .whisper_model_path
             .and_then(|path| {
                 self.models
                     .iter()
@@ -566,7 +576,18 @@ pub async fn get_available_models(app_handle: AppHandle) -> Result<Vec<WhisperMo
             e.to_string()
         })?;
 
-    let models = manager.get_models();
+    let mut models = manager.get_models();
+
+    // 按当前选择的后端标记放不下的模型
+    let backend = crate::voice_assistant::global_whisper::get_global_whisper_manager()
+        .read()
+        .await
+        .requested_backend()
+        .clone();
+    let available = crate::voice_assistant::asr::memory_check::estimate_for_backend(0, &backend).available_mb;
+    for model in &mut models {
+        model.fits_in_memory = available.map(|available_mb| model.estimated_memory_mb <= available_mb);
+    }
 
     println!("📋 Available models count: {}", models.len());
     for model in &models {
@@ -622,7 +643,7 @@ pub async fn set_active_model(app_handle: AppHandle, model_name: String) -> Resu
 
 #[tauri::command]
 pub async fn get_active_model_info() -> Result<Option<String>, String> {
-    Ok(std::env::var("WHISPER_MODEL_PATH").ok())
+    Ok(crate::voice_assistant::settings::current().whisper_model_path)
 }
 
 #[tauri::command]
@@ -0,0 +1,269 @@
+use crate::voice_assistant::VoiceError;
+use reqwest::StatusCode;
+use std::sync::{OnceLock, RwLock};
+use std::time::{Duration, Instant};
+use tracing::{debug, info, warn};
+
+/// 默认最多重试次数（不含第一次请求）
+pub const DEFAULT_MAX_RETRIES: u32 = 3;
+/// 默认总耗时预算：一次热键识别最多等待这么久
+pub const DEFAULT_TIME_BUDGET_MS: i64 = 20_000;
+pub const MAX_RETRIES_LIMIT: u32 = 10;
+pub const MIN_TIME_BUDGET_MS: i64 = 1_000;
+pub const MAX_TIME_BUDGET_MS: i64 = 120_000;
+
+const BASE_BACKOFF: Duration = Duration::from_millis(500);
+const MAX_BACKOFF: Duration = Duration::from_secs(8);
+
+/// 云端 ASR 和翻译请求的重试设置
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    pub max_retries: u32,
+    pub time_budget: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_retries: DEFAULT_MAX_RETRIES,
+            time_budget: Duration::from_millis(DEFAULT_TIME_BUDGET_MS as u64),
+        }
+    }
+}
+
+// 全局设置，所有云端处理器共用
+static RETRY_POLICY: OnceLock<RwLock<RetryPolicy>> = OnceLock::new();
+
+fn policy() -> &'static RwLock<RetryPolicy> {
+    RETRY_POLICY.get_or_init(|| RwLock::new(RetryPolicy::default()))
+}
+
+pub fn retry_policy() -> RetryPolicy {
+    *policy().read().unwrap()
+}
+
+pub fn set_retry_policy(new_policy: RetryPolicy) {
+    info!(
+        "🔁 Network retry: max_retries={}, budget={}ms",
+        new_policy.max_retries,
+        new_policy.time_budget.as_millis()
+    );
+    *policy().write().unwrap() = new_policy;
+}
+
+/// 从数据库重新加载重试设置
+pub async fn reload_retry_config() -> Result<(), String> {
+    let config = crate::commands::get_network_retry_config_internal().await?;
+    set_retry_policy(config.map(|c| c.policy()).unwrap_or_default());
+    Ok(())
+}
+
+/// 一次调用的重试额度：重试次数和截止时间。
+/// 同一次识别内的多个请求（如逐个尝试响应格式）共用一个预算
+#[derive(Debug, Clone, Copy)]
+pub struct RetryBudget {
+    max_retries: u32,
+    deadline: Instant,
+}
+
+impl RetryBudget {
+    pub fn new(policy: RetryPolicy) -> Self {
+        Self {
+            max_retries: policy.max_retries,
+            deadline: Instant::now() + policy.time_budget,
+        }
+    }
+
+    /// 按当前全局设置开始计时
+    pub fn start() -> Self {
+        Self::new(retry_policy())
+    }
+
+    pub fn remaining(&self) -> Duration {
+        self.deadline.saturating_duration_since(Instant::now())
+    }
+}
+
+pub fn is_retryable_status(status: StatusCode) -> bool {
+    status.is_server_error() || status == StatusCode::TOO_MANY_REQUESTS
+}
+
+/// 指数退避加抖动：基础延迟 500ms 每次翻倍，最多 8s，实际等待在 [delay/2, delay] 之间
+pub fn backoff_delay(retry: u32, jitter: f64) -> Duration {
+    let delay = (BASE_BACKOFF * 2u32.pow(retry.min(5))).min(MAX_BACKOFF);
+    delay.mul_f64(0.5 + 0.5 * jitter.clamp(0.0, 1.0))
+}
+
+fn random_jitter() -> f64 {
+    (uuid::Uuid::new_v4().as_u128() % 1000) as f64 / 1000.0
+}
+
+/// 解析 Retry-After：秒数或 HTTP 日期
+pub fn parse_retry_after(value: &str) -> Option<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Some(Duration::from_secs(secs));
+    }
+    let date = chrono::DateTime::parse_from_rfc2822(value).ok()?;
+    let wait = date.with_timezone(&chrono::Utc) - chrono::Utc::now();
+    Some(wait.to_std().unwrap_or(Duration::ZERO))
+}
+
+pub fn retry_summary(retries: u32) -> String {
+    if retries > 0 {
+        format!(" (after {} retr{})", retries, if retries == 1 { "y" } else { "ies" })
+    } else {
+        String::new()
+    }
+}
+
+/// 成功发出的请求及尝试次数（含第一次）
+pub struct RetryResponse {
+    pub response: reqwest::Response,
+    pub attempts: u32,
+}
+
+/// 发送请求，连接错误和 5xx/429 按预算重试；其余 4xx 直接返回。
+/// `build` 每次尝试都会调用，multipart 表单发送后会被消耗，需要重新构造。
+/// 重试用完或预算耗尽时返回最后一次的响应，由调用方按状态码处理
+pub async fn send_with_retry<F>(label: &str, budget: &RetryBudget, mut build: F) -> Result<RetryResponse, VoiceError>
+where
+    F: FnMut() -> Result<reqwest::RequestBuilder, VoiceError>,
+{
+    let mut retries: u32 = 0;
+    loop {
+        let remaining = budget.remaining();
+        if remaining.is_zero() {
+            return Err(VoiceError::Other(format!("{}: time budget exhausted{}", label, retry_summary(retries))));
+        }
+
+        let attempt_start = Instant::now();
+        let result = build()?.timeout(remaining).send().await;
+        let elapsed_ms = attempt_start.elapsed().as_millis();
+
+        let (reason, retry_after) = match result {
+            Ok(response) if is_retryable_status(response.status()) => {
+                let retry_after = response
+                    .headers()
+                    .get(reqwest::header::RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(parse_retry_after);
+                if retries >= budget.max_retries {
+                    return Ok(RetryResponse { response, attempts: retries + 1 });
+                }
+                (format!("HTTP {}", response.status()), retry_after)
+            }
+            Ok(response) => {
+                debug!("📡 {}: HTTP {} in {}ms (attempt {})", label, response.status(), elapsed_ms, retries + 1);
+                return Ok(RetryResponse { response, attempts: retries + 1 });
+            }
+            Err(e) if (e.is_connect() || e.is_timeout() || e.is_request()) && retries < budget.max_retries => {
+                (format!("request failed: {}", e), None)
+            }
+            Err(e) => {
+                return Err(VoiceError::Other(format!("{}: request failed: {}{}", label, e, retry_summary(retries))));
+            }
+        };
+
+        let delay = retry_after.unwrap_or_else(|| backoff_delay(retries, random_jitter()));
+        if delay >= budget.remaining() {
+            warn!("⏱️ {}: {} after {}ms, no time left to retry", label, reason, elapsed_ms);
+            return Err(VoiceError::Other(format!("{}: {}, time budget exhausted{}", label, reason, retry_summary(retries))));
+        }
+
+        retries += 1;
+        warn!(
+            "🔄 {}: {} after {}ms - retry {}/{} in {}ms",
+            label, reason, elapsed_ms, retries, budget.max_retries, delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::TcpListener;
+
+    /// 依次返回给定的响应，之后一直返回最后一个
+    async fn spawn_server(responses: Vec<&'static str>) -> (String, Arc<AtomicUsize>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let hits = Arc::new(AtomicUsize::new(0));
+        let hits_clone = hits.clone();
+
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let hit = hits_clone.fetch_add(1, Ordering::SeqCst);
+                let mut buf = [0u8; 4096];
+                let _ = socket.read(&mut buf).await;
+                let response = responses[hit.min(responses.len() - 1)];
+                let _ = socket.write_all(response.as_bytes()).await;
+                let _ = socket.shutdown().await;
+            }
+        });
+
+        (format!("http://{}/", addr), hits)
+    }
+
+    const OK: &str = "HTTP/1.1 200 OK\r\nContent-Length: 2\r\nConnection: close\r\n\r\nok";
+    const TOO_MANY: &str = "HTTP/1.1 429 Too Many Requests\r\nRetry-After: 0\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    const BAD_REQUEST: &str = "HTTP/1.1 400 Bad Request\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+
+    fn budget(max_retries: u32) -> RetryBudget {
+        RetryBudget::new(RetryPolicy { max_retries, time_budget: Duration::from_secs(10) })
+    }
+
+    #[test]
+    fn test_backoff_grows_with_jitter_and_cap() {
+        assert_eq!(backoff_delay(0, 1.0), Duration::from_millis(500));
+        assert_eq!(backoff_delay(0, 0.0), Duration::from_millis(250));
+        assert_eq!(backoff_delay(2, 1.0), Duration::from_secs(2));
+        assert_eq!(backoff_delay(10, 1.0), MAX_BACKOFF);
+    }
+
+    #[test]
+    fn test_retryable_status_and_retry_after() {
+        assert!(is_retryable_status(StatusCode::SERVICE_UNAVAILABLE));
+        assert!(is_retryable_status(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!is_retryable_status(StatusCode::UNAUTHORIZED));
+        assert!(!is_retryable_status(StatusCode::BAD_REQUEST));
+
+        assert_eq!(parse_retry_after(" 3 "), Some(Duration::from_secs(3)));
+        assert_eq!(parse_retry_after("Wed, 21 Oct 2015 07:28:00 GMT"), Some(Duration::ZERO));
+        assert_eq!(parse_retry_after("soon"), None);
+    }
+
+    #[tokio::test]
+    async fn test_429_is_retried_honoring_retry_after() {
+        let (url, hits) = spawn_server(vec![TOO_MANY, OK]).await;
+        let client = reqwest::Client::new();
+
+        let result = send_with_retry("test", &budget(3), || Ok(client.get(&url))).await.unwrap();
+        assert!(result.response.status().is_success());
+        assert_eq!(result.attempts, 2);
+        assert_eq!(hits.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_client_errors_are_not_retried() {
+        let (url, hits) = spawn_server(vec![BAD_REQUEST, OK]).await;
+        let client = reqwest::Client::new();
+
+        let result = send_with_retry("test", &budget(3), || Ok(client.get(&url))).await.unwrap();
+        assert_eq!(result.response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(result.attempts, 1);
+        assert_eq!(hits.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_budget_exhausted_stops_retrying() {
+        let client = reqwest::Client::new();
+        let expired = RetryBudget::new(RetryPolicy { max_retries: 3, time_budget: Duration::ZERO });
+        let err = send_with_retry("test", &expired, || Ok(client.get("http://127.0.0.1:9/"))).await.err().unwrap();
+        assert!(err.to_string().contains("time budget exhausted"));
+    }
+}
@@ -0,0 +1,348 @@
+use std::sync::{OnceLock, RwLock};
+use tracing::{debug, info, warn};
+use crate::database::{OutputProfileRecord, TypingDelays};
+use crate::voice_assistant::keyboard::TextInjectionMethod;
+use crate::voice_assistant::VoiceError;
+use crate::voice_assistant::logger::redact;
+
+/// 匹配所有应用的规则，用作用户自定义的默认配置
+pub const MATCH_ALL_PATTERN: &str = "*";
+
+/// 针对某个应用解析出的输出方式，默认值即没有规则匹配时的行为
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OutputProfile {
+    /// None 表示沿用全局的文本注入设置
+    pub injection_method: Option<TextInjectionMethod>,
+    pub trailing_text: String,
+    pub capitalize_sentences: bool,
+    /// None 表示沿用全局的输入延迟
+    pub typing_delays: Option<TypingDelays>,
+}
+
+/// 前台窗口的标识，用于匹配规则
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FocusedWindow {
+    /// 窗口类名（Linux 的 WM_CLASS），其他平台为 None
+    pub class: Option<String>,
+    /// macOS 为进程名，其他平台为窗口标题
+    pub name: Option<String>,
+}
+
+impl FocusedWindow {
+    /// 参与匹配的名称，类名在前：类名稳定，标题会随打开的文件/网页变化
+    fn candidates(&self) -> impl Iterator<Item = &str> {
+        self.class.iter().chain(self.name.iter()).map(String::as_str)
+    }
+
+    /// 展示给用户、用来填写规则的名称
+    pub fn display_name(&self) -> Option<String> {
+        self.class.clone().or_else(|| self.name.clone())
+    }
+}
+
+/// 一条编译好的规则：窗口类名/应用名/窗口标题包含 pattern（不区分大小写）即匹配
+#[derive(Debug, Clone)]
+struct ProfileRule {
+    pattern: String,
+    profile: OutputProfile,
+}
+
+impl ProfileRule {
+    fn from_record(record: &OutputProfileRecord) -> Result<Self, VoiceError> {
+        let pattern = record.app_pattern.trim().to_lowercase();
+        if pattern.is_empty() {
+            return Err(VoiceError::Other("App name pattern must not be empty".to_string()));
+        }
+        let injection_method = parse_injection_method(&record.injection_method)?;
+        Ok(Self {
+            pattern,
+            profile: OutputProfile {
+                injection_method,
+                trailing_text: record.trailing_text.clone(),
+                capitalize_sentences: record.capitalize_sentences,
+                typing_delays: record.typing_delays(),
+            },
+        })
+    }
+
+    fn matches(&self, app_name: &str) -> bool {
+        self.pattern == MATCH_ALL_PATTERN || app_name.to_lowercase().contains(&self.pattern)
+    }
+}
+
+/// "default" 表示沿用全局设置，其余同 TextInjectionMethod
+pub fn parse_injection_method(value: &str) -> Result<Option<TextInjectionMethod>, VoiceError> {
+    if value.trim().eq_ignore_ascii_case("default") {
+        return Ok(None);
+    }
+    TextInjectionMethod::parse(value)
+        .map(Some)
+        .ok_or_else(|| VoiceError::Other(format!("Unknown text injection method: {}", value)))
+}
+
+/// 先按窗口类名、再按标题匹配具体规则（同一名称按添加顺序），具体规则优先于 "*"；
+/// 没有匹配时使用内置默认配置
+fn resolve(rules: &[ProfileRule], window: &FocusedWindow) -> OutputProfile {
+    let specific = window.candidates().find_map(|app| {
+        rules
+            .iter()
+            .find(|rule| rule.pattern != MATCH_ALL_PATTERN && rule.matches(app))
+    });
+    specific
+        .or_else(|| rules.iter().find(|rule| rule.pattern == MATCH_ALL_PATTERN))
+        .map(|rule| rule.profile.clone())
+        .unwrap_or_default()
+}
+
+/// 句首（包括整段开头）的字母转大写
+pub fn capitalize_sentences(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut at_sentence_start = true;
+    for c in text.chars() {
+        if at_sentence_start && c.is_alphabetic() {
+            result.extend(c.to_uppercase());
+            at_sentence_start = false;
+            continue;
+        }
+        if matches!(c, '.' | '!' | '?' | '\n') {
+            at_sentence_start = true;
+        } else if !c.is_whitespace() && !matches!(c, '"' | '\'' | '(' | '“') {
+            at_sentence_start = false;
+        }
+        result.push(c);
+    }
+    result
+}
+
+/// 按配置调整要输入的文本
+pub fn apply_profile_text(text: &str, profile: &OutputProfile) -> String {
+    let mut text = if profile.capitalize_sentences {
+        capitalize_sentences(text)
+    } else {
+        text.to_string()
+    };
+    text.push_str(&profile.trailing_text);
+    text
+}
+
+/// 获取当前前台应用的名称，优先返回窗口类名
+pub fn detect_focused_app() -> Option<String> {
+    detect_focused_window().display_name()
+}
+
+#[cfg(target_os = "linux")]
+fn xdotool_active_window(query: &str) -> Option<String> {
+    let output = std::process::Command::new("xdotool")
+        .args(["getactivewindow", query])
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    (!name.is_empty()).then_some(name)
+}
+
+/// 获取前台窗口的类名和名称（macOS 为进程名，其他平台为窗口标题）
+pub fn detect_focused_window() -> FocusedWindow {
+    #[cfg(target_os = "linux")]
+    {
+        FocusedWindow {
+            class: xdotool_active_window("getwindowclassname"),
+            name: xdotool_active_window("getwindowname"),
+        }
+    }
+
+    #[cfg(target_os = "macos")]
+    {
+        let name = std::process::Command::new("osascript")
+            .arg("-e")
+            .arg("tell application \"System Events\" to get name of first application process whose frontmost is true")
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+            .filter(|name| !name.is_empty());
+        FocusedWindow { class: None, name }
+    }
+
+    #[cfg(target_os = "windows")]
+    {
+        let name = unsafe {
+            use winapi::um::winuser::{GetForegroundWindow, GetWindowTextW};
+
+            let hwnd = GetForegroundWindow();
+            if hwnd.is_null() {
+                None
+            } else {
+                let mut buffer = [0u16; 512];
+                let len = GetWindowTextW(hwnd, buffer.as_mut_ptr(), buffer.len() as i32);
+                (len > 0).then(|| String::from_utf16_lossy(&buffer[..len as usize]))
+            }
+        };
+        FocusedWindow { class: None, name }
+    }
+
+    #[cfg(not(any(target_os = "linux", target_os = "macos", target_os = "windows")))]
+    {
+        FocusedWindow::default()
+    }
+}
+
+// 全局规则缓存，规则列表变化时刷新
+static PROFILES: OnceLock<RwLock<Vec<ProfileRule>>> = OnceLock::new();
+
+fn profiles() -> &'static RwLock<Vec<ProfileRule>> {
+    PROFILES.get_or_init(|| RwLock::new(Vec::new()))
+}
+
+/// 用数据库中的记录刷新全局规则
+pub fn set_output_profiles(records: &[OutputProfileRecord]) {
+    let rules: Vec<ProfileRule> = records
+        .iter()
+        .filter_map(|record| match ProfileRule::from_record(record) {
+            Ok(rule) => Some(rule),
+            Err(e) => {
+                warn!("⚠️ Skipping output profile {}: {}", record.id, e);
+                None
+            }
+        })
+        .collect();
+    info!("🪟 Loaded {} output profile(s)", rules.len());
+    *profiles().write().unwrap() = rules;
+}
+
+/// 检测前台应用并返回匹配的配置；没有规则时不做检测
+pub fn current_profile() -> OutputProfile {
+    let rules = profiles().read().unwrap();
+    if rules.is_empty() {
+        return OutputProfile::default();
+    }
+    let window = detect_focused_window();
+    let profile = resolve(&rules, &window);
+    // 窗口标题可能包含文档名、网页标题等，和转写文本一样脱敏
+    debug!(
+        "🪟 Focused window: class={:?}, name={} -> {:?}",
+        window.class,
+        window.name.as_deref().map(redact).unwrap_or_default(),
+        profile
+    );
+    profile
+}
+
+/// 从数据库重新加载输出配置
+pub async fn reload_output_profiles() -> Result<(), String> {
+    let records = crate::commands::get_output_profiles_internal().await?;
+    set_output_profiles(&records);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn record(pattern: &str, method: &str, trailing: &str, capitalize: bool) -> OutputProfileRecord {
+        OutputProfileRecord {
+            id: pattern.to_string(),
+            app_pattern: pattern.to_string(),
+            injection_method: method.to_string(),
+            trailing_text: trailing.to_string(),
+            capitalize_sentences: capitalize,
+            typing_delays_json: None,
+            created_at: Utc::now(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    fn rules(records: &[OutputProfileRecord]) -> Vec<ProfileRule> {
+        records.iter().map(|r| ProfileRule::from_record(r).unwrap()).collect()
+    }
+
+    fn window(class: Option<&str>, name: &str) -> FocusedWindow {
+        FocusedWindow {
+            class: class.map(str::to_string),
+            name: Some(name.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_resolve_matches_app_name_case_insensitively() {
+        let rules = rules(&[
+            record("*", "default", " ", false),
+            record("Terminal", "direct", "", false),
+            record("slack", "clipboard", " ", false),
+        ]);
+
+        let terminal = resolve(&rules, &window(None, "gnome-terminal: ~/src"));
+        assert_eq!(terminal.injection_method, Some(TextInjectionMethod::Direct));
+        assert_eq!(terminal.trailing_text, "");
+
+        let slack = resolve(&rules, &window(None, "Slack | general"));
+        assert_eq!(slack.injection_method, Some(TextInjectionMethod::Clipboard));
+
+        // 没有具体规则匹配时使用 "*"
+        let other = resolve(&rules, &window(None, "Firefox"));
+        assert_eq!(other.injection_method, None);
+        assert_eq!(other.trailing_text, " ");
+    }
+
+    #[test]
+    fn test_resolve_falls_back_to_builtin_default() {
+        let rules = rules(&[record("word", "default", "", true)]);
+        assert_eq!(resolve(&rules, &window(None, "Firefox")), OutputProfile::default());
+        assert_eq!(resolve(&rules, &FocusedWindow::default()), OutputProfile::default());
+    }
+
+    #[test]
+    fn test_window_class_takes_precedence_over_title() {
+        let mut terminal = record("kitty", "direct", "", false);
+        terminal.typing_delays_json = Some(
+            r#"{"clipboard_update_ms":20,"keyboard_events_settle_ms":50,"typing_complete_ms":80,"character_interval_ms":5,"short_operation_ms":20}"#.to_string(),
+        );
+        let rules = rules(&[
+            record("*", "clipboard", "", false),
+            record("vim", "auto", "", false),
+            terminal,
+        ]);
+
+        // 标题匹配 "vim"，但类名匹配的规则优先，即使它添加得更晚
+        let profile = resolve(&rules, &window(Some("kitty"), "vim ~/notes.md"));
+        assert_eq!(profile.injection_method, Some(TextInjectionMethod::Direct));
+        assert_eq!(profile.typing_delays.map(|d| d.character_interval_ms), Some(5));
+
+        // 类名没有匹配时再看标题
+        let profile = resolve(&rules, &window(Some("Alacritty"), "vim ~/notes.md"));
+        assert_eq!(profile.injection_method, Some(TextInjectionMethod::Auto));
+        assert_eq!(profile.typing_delays, None);
+
+        // 都不匹配时使用 "*"
+        let profile = resolve(&rules, &window(Some("firefox"), "Mozilla Firefox"));
+        assert_eq!(profile.injection_method, Some(TextInjectionMethod::Clipboard));
+    }
+
+    #[test]
+    fn test_invalid_records_are_rejected() {
+        assert!(ProfileRule::from_record(&record("  ", "auto", "", false)).is_err());
+        assert!(ProfileRule::from_record(&record("word", "xdotool", "", false)).is_err());
+    }
+
+    #[test]
+    fn test_capitalize_sentences() {
+        assert_eq!(capitalize_sentences("hello. how are you? fine!"), "Hello. How are you? Fine!");
+        assert_eq!(capitalize_sentences("first\nsecond"), "First\nSecond");
+        assert_eq!(capitalize_sentences("你好。world"), "你好。world");
+    }
+
+    #[test]
+    fn test_apply_profile_text() {
+        let profile = OutputProfile {
+            injection_method: None,
+            trailing_text: " ".to_string(),
+            capitalize_sentences: true,
+            typing_delays: None,
+        };
+        assert_eq!(apply_profile_text("ok. done", &profile), "Ok. Done ");
+        assert_eq!(apply_profile_text("ok. done", &OutputProfile::default()), "ok. done");
+    }
+}
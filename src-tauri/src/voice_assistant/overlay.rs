@@ -0,0 +1,325 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{OnceLock, RwLock};
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Listener, Manager, PhysicalPosition, PhysicalSize, WebviewUrl, WebviewWindow, WebviewWindowBuilder};
+use tracing::{info, warn};
+
+pub const OVERLAY_LABEL: &str = "overlay";
+
+const OVERLAY_WIDTH: f64 = 220.0;
+const OVERLAY_HEIGHT: f64 = 56.0;
+/// 浮窗相对光标的偏移，避免遮住正在输入的位置
+const CURSOR_OFFSET: (i32, i32) = (16, 24);
+/// 固定在屏幕角落时与屏幕边缘的距离
+const EDGE_MARGIN: i32 = 24;
+/// 回到空闲后稍等再隐藏，让用户看到处理结束
+const HIDE_DELAY: Duration = Duration::from_millis(800);
+
+/// 浮窗位置：跟随光标或固定在光标所在显示器的某个角
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OverlayPosition {
+    #[default]
+    Cursor,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+
+impl OverlayPosition {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "cursor" => Some(Self::Cursor),
+            "top-left" => Some(Self::TopLeft),
+            "top-right" => Some(Self::TopRight),
+            "bottom-left" => Some(Self::BottomLeft),
+            "bottom-right" => Some(Self::BottomRight),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Cursor => "cursor",
+            Self::TopLeft => "top-left",
+            Self::TopRight => "top-right",
+            Self::BottomLeft => "bottom-left",
+            Self::BottomRight => "bottom-right",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OverlaySettings {
+    pub enabled: bool,
+    pub position: OverlayPosition,
+}
+
+impl Default for OverlaySettings {
+    fn default() -> Self {
+        Self { enabled: true, position: OverlayPosition::Cursor }
+    }
+}
+
+impl From<&crate::database::OverlayConfig> for OverlaySettings {
+    fn from(config: &crate::database::OverlayConfig) -> Self {
+        Self {
+            enabled: config.enabled,
+            position: OverlayPosition::parse(&config.position).unwrap_or_default(),
+        }
+    }
+}
+
+static SETTINGS: OnceLock<RwLock<OverlaySettings>> = OnceLock::new();
+// 每次状态变化递增，延迟隐藏据此判断是否已被新状态取代
+static HIDE_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+fn settings() -> &'static RwLock<OverlaySettings> {
+    SETTINGS.get_or_init(|| RwLock::new(OverlaySettings::default()))
+}
+
+pub fn overlay_settings() -> OverlaySettings {
+    *settings().read().unwrap()
+}
+
+/// 更新设置；关闭时立即隐藏浮窗
+pub fn set_overlay_settings(app: &AppHandle, new_settings: OverlaySettings) {
+    info!("🪟 Overlay: enabled={}, position={}", new_settings.enabled, new_settings.position.as_str());
+    *settings().write().unwrap() = new_settings;
+    if !new_settings.enabled {
+        if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+            let _ = window.hide();
+        }
+    }
+}
+
+/// 从数据库重新加载浮窗设置
+pub async fn reload_overlay_config(app: &AppHandle) -> Result<(), String> {
+    let config = crate::commands::get_overlay_config_internal().await?;
+    set_overlay_settings(app, config.as_ref().map(OverlaySettings::from).unwrap_or_default());
+    Ok(())
+}
+
+/// 浮窗显示的内容，由 `voice-assistant-state-changed` 的状态字符串映射而来
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OverlayMode {
+    /// 显示计时和电平条
+    Recording,
+    /// 显示转圈
+    Processing,
+    Hidden,
+}
+
+impl OverlayMode {
+    pub fn from_state_event(state: &str) -> Self {
+        match state {
+            "Recording" | "RecordingTranslate" => OverlayMode::Recording,
+            "Processing" | "Translating" => OverlayMode::Processing,
+            _ => OverlayMode::Hidden,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OverlayMode::Recording => "recording",
+            OverlayMode::Processing => "processing",
+            OverlayMode::Hidden => "hidden",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            OverlayMode::Recording => "🎤 Recording",
+            OverlayMode::Processing => "🔄 Processing",
+            OverlayMode::Hidden => "",
+        }
+    }
+}
+
+/// 计算浮窗左上角位置：放在光标右下方，并限制在光标所在显示器内
+pub fn overlay_position(
+    cursor: (i32, i32),
+    monitor_position: (i32, i32),
+    monitor_size: (u32, u32),
+    overlay_size: (u32, u32),
+) -> (i32, i32) {
+    let clamp_axis = |desired: i32, start: i32, monitor_len: u32, overlay_len: u32| {
+        let end = start + monitor_len as i32 - overlay_len as i32;
+        desired.min(end).max(start)
+    };
+
+    (
+        clamp_axis(cursor.0 + CURSOR_OFFSET.0, monitor_position.0, monitor_size.0, overlay_size.0),
+        clamp_axis(cursor.1 + CURSOR_OFFSET.1, monitor_position.1, monitor_size.1, overlay_size.1),
+    )
+}
+
+/// 固定在显示器某个角时的浮窗位置
+pub fn corner_position(
+    corner: OverlayPosition,
+    monitor_position: (i32, i32),
+    monitor_size: (u32, u32),
+    overlay_size: (u32, u32),
+) -> (i32, i32) {
+    let left = monitor_position.0 + EDGE_MARGIN;
+    let top = monitor_position.1 + EDGE_MARGIN;
+    let right = monitor_position.0 + monitor_size.0 as i32 - overlay_size.0 as i32 - EDGE_MARGIN;
+    let bottom = monitor_position.1 + monitor_size.1 as i32 - overlay_size.1 as i32 - EDGE_MARGIN;
+
+    match corner {
+        OverlayPosition::TopLeft | OverlayPosition::Cursor => (left, top),
+        OverlayPosition::TopRight => (right, top),
+        OverlayPosition::BottomLeft => (left, bottom),
+        OverlayPosition::BottomRight => (right, bottom),
+    }
+}
+
+/// 创建录音浮窗（初始隐藏）：无边框、透明、置顶、不出现在任务栏、鼠标穿透且不抢焦点
+pub fn create_overlay_window(app: &AppHandle) -> tauri::Result<WebviewWindow> {
+    if let Some(window) = app.get_webview_window(OVERLAY_LABEL) {
+        return Ok(window);
+    }
+
+    let builder = WebviewWindowBuilder::new(app, OVERLAY_LABEL, WebviewUrl::App("overlay.html".into()))
+        .title("VoiceType Overlay")
+        .inner_size(OVERLAY_WIDTH, OVERLAY_HEIGHT)
+        .decorations(false)
+        .always_on_top(true)
+        .skip_taskbar(true)
+        .resizable(false)
+        .shadow(false)
+        .focused(false)
+        .visible(false);
+
+    // macOS 的透明窗口需要 macos-private-api，这里只在其他平台开启
+    #[cfg(not(target_os = "macos"))]
+    let builder = builder.transparent(true);
+
+    let window = builder.build()?;
+    window.set_ignore_cursor_events(true)?;
+    Ok(window)
+}
+
+/// 创建浮窗并跟随语音助手状态显示/隐藏
+pub fn init_overlay(app: &AppHandle) -> tauri::Result<()> {
+    create_overlay_window(app)?;
+
+    let reload_handle = app.clone();
+    tauri::async_runtime::spawn(async move {
+        if let Err(e) = reload_overlay_config(&reload_handle).await {
+            warn!("⚠️ Failed to load overlay config: {}", e);
+        }
+    });
+
+    // 电平条由浮窗页面直接监听 recording-level 事件
+    let handle = app.clone();
+    app.listen("voice-assistant-state-changed", move |event| {
+        match serde_json::from_str::<String>(event.payload()) {
+            Ok(state) => apply_overlay_mode(&handle, OverlayMode::from_state_event(&state)),
+            Err(e) => warn!("⚠️ Overlay: unexpected state payload {}: {}", event.payload(), e),
+        }
+    });
+
+    info!("✅ Recording overlay initialized");
+    Ok(())
+}
+
+fn apply_overlay_mode(app: &AppHandle, mode: OverlayMode) {
+    let Some(window) = app.get_webview_window(OVERLAY_LABEL) else {
+        return;
+    };
+
+    let generation = HIDE_GENERATION.fetch_add(1, Ordering::SeqCst) + 1;
+    let settings = overlay_settings();
+    if !settings.enabled {
+        let _ = window.hide();
+        return;
+    }
+
+    if mode == OverlayMode::Hidden {
+        // 延迟隐藏；期间又开始录音则保持显示
+        std::thread::spawn(move || {
+            std::thread::sleep(HIDE_DELAY);
+            if HIDE_GENERATION.load(Ordering::SeqCst) == generation {
+                let _ = window.hide();
+            }
+        });
+    } else {
+        // 录音开始时定位；处理阶段保持原位
+        if mode == OverlayMode::Recording {
+            if let Err(e) = position_window(app, &window, settings.position) {
+                warn!("⚠️ Overlay: failed to position window: {}", e);
+            }
+        }
+        // 只 show 不 set_focus，避免抢走目标应用的输入焦点
+        let _ = window.show();
+    }
+
+    let payload = serde_json::json!({ "mode": mode.as_str(), "label": mode.label() });
+    if let Err(e) = app.emit_to(OVERLAY_LABEL, "overlay-state", payload) {
+        warn!("⚠️ Overlay: failed to emit overlay state: {}", e);
+    }
+}
+
+fn position_window(app: &AppHandle, window: &WebviewWindow, position: OverlayPosition) -> tauri::Result<()> {
+    let cursor = app.cursor_position()?;
+    let Some(monitor) = app
+        .monitor_from_point(cursor.x, cursor.y)?
+        .or(window.current_monitor()?)
+    else {
+        return Ok(());
+    };
+
+    let monitor_position: &PhysicalPosition<i32> = monitor.position();
+    let monitor_size: &PhysicalSize<u32> = monitor.size();
+    let overlay_size = window.outer_size()?;
+
+    let monitor_position = (monitor_position.x, monitor_position.y);
+    let monitor_size = (monitor_size.width, monitor_size.height);
+    let overlay_size = (overlay_size.width, overlay_size.height);
+    let (x, y) = match position {
+        OverlayPosition::Cursor => overlay_position((cursor.x as i32, cursor.y as i32), monitor_position, monitor_size, overlay_size),
+        corner => corner_position(corner, monitor_position, monitor_size, overlay_size),
+    };
+    window.set_position(PhysicalPosition::new(x, y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_overlay_mode_from_state_event() {
+        assert_eq!(OverlayMode::from_state_event("Recording"), OverlayMode::Recording);
+        assert_eq!(OverlayMode::from_state_event("RecordingTranslate"), OverlayMode::Recording);
+        assert_eq!(OverlayMode::from_state_event("Processing"), OverlayMode::Processing);
+        assert_eq!(OverlayMode::from_state_event("Translating"), OverlayMode::Processing);
+        assert_eq!(OverlayMode::from_state_event("Running"), OverlayMode::Hidden);
+        assert_eq!(OverlayMode::from_state_event("Idle"), OverlayMode::Hidden);
+        assert_eq!(OverlayMode::from_state_event("Error"), OverlayMode::Hidden);
+    }
+
+    #[test]
+    fn test_corner_position_and_parse() {
+        assert_eq!(OverlayPosition::parse("Bottom-Right"), Some(OverlayPosition::BottomRight));
+        assert_eq!(OverlayPosition::parse("middle"), None);
+        assert_eq!(OverlayPosition::parse(OverlayPosition::TopLeft.as_str()), Some(OverlayPosition::TopLeft));
+
+        let monitor = ((0, 0), (1920, 1080));
+        assert_eq!(corner_position(OverlayPosition::TopLeft, monitor.0, monitor.1, (220, 56)), (24, 24));
+        assert_eq!(corner_position(OverlayPosition::BottomRight, monitor.0, monitor.1, (220, 56)), (1676, 1000));
+        // 副屏在主屏左侧
+        assert_eq!(corner_position(OverlayPosition::TopRight, (-1280, 0), (1280, 1024), (220, 56)), (-244, 24));
+    }
+
+    #[test]
+    fn test_overlay_position_clamps_to_monitor() {
+        // 光标在屏幕中间：放在右下方
+        assert_eq!(overlay_position((500, 400), (0, 0), (1920, 1080), (220, 56)), (516, 424));
+        // 光标在右下角：不超出屏幕
+        assert_eq!(overlay_position((1910, 1075), (0, 0), (1920, 1080), (220, 56)), (1700, 1024));
+        // 副屏在主屏左侧（负坐标）
+        assert_eq!(overlay_position((-100, 10), (-1280, 0), (1280, 1024), (220, 56)), (-220, 34));
+        assert_eq!(overlay_position((-1280, -50), (-1280, 0), (1280, 1024), (220, 56)), (-1264, 0));
+    }
+}
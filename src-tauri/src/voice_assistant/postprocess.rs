@@ -0,0 +1,505 @@
+use once_cell::sync::Lazy;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tracing::{info, trace, warn};
+use crate::voice_assistant::TranslateProcessor;
+use crate::voice_assistant::logger::redact;
+
+/// LLM 优化的默认时间预算（毫秒），超时后直接输入原文
+pub const DEFAULT_OPTIMIZE_TIMEOUT_MS: u64 = 3000;
+
+/// 少于这么多字符（不含空白）的结果不值得调用 LLM
+const MIN_OPTIMIZE_CHARS: usize = 6;
+
+/// ASR 结果后处理开关
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PostProcessOptions {
+    pub convert_to_simplified: bool,
+    pub add_symbol: bool,
+    pub optimize_result: bool,
+    /// LLM 优化最多等待的时间（毫秒）
+    pub optimize_timeout_ms: u64,
+}
+
+impl Default for PostProcessOptions {
+    fn default() -> Self {
+        Self {
+            convert_to_simplified: true,
+            add_symbol: true,
+            optimize_result: false,
+            optimize_timeout_ms: DEFAULT_OPTIMIZE_TIMEOUT_MS,
+        }
+    }
+}
+
+// 繁体 → 简体 常用字映射表（两个字符串按位置一一对应）
+const TRADITIONAL_CHARS: &str = concat!(
+    "這個們來時會說對過還後裡裏為麼樣見點學開關東車長門問間聽話語認識讓請謝讀寫號電腦",
+    "網頁機場發現實際經濟國華動員頭題應該準備難氣熱沒處從無業務與幾嗎買賣錢銀貨質價體",
+    "區醫藥療聲樂歡種類選擇標確鍵盤設計劃總結給線紅綠藍顏風雲雖邊遠運轉連進達遲錄譯試",
+    "驗測況報導錯誤愛親戰爭黨權歷歲數據庫檔條則規範圍傳統術專廣飛鳥魚馬龍園書館飯麵雞",
+    "鐘錶週聯繫係麗豐獨寶貝貴帶當將舊壞滿聖節嚴隊陽陰險陳隨屬層歸憶夢願態慣懷復記許論",
+    "議證評詞講課調談誰變讚豬貓狀獲執壓塊壇靜鬆閉顯順須預領頻額飲養驚髮鬥齊齒龜產辦單",
+    "紀約級練組織細終絕維綜緊縣續繼鐵鏡閱雙響頂項頓飄筆紙義勞師徑簡習慮戲戶掃換擊擁擴",
+    "擬擔攝敵斷昇晝暫曆朧極構槍樓樹橋檢歐殺殼決淚淨淺湯溝溫滅漢潔澤濃濕灣災烏煙煩燈燒",
+    "營爐爺牆犧猶獎獻環畫異盡監眾睜礎禮禍穩窮競築籃糧糾納純紛紹緒編緣縮績繩罷聞聰職肅",
+    "腳膠臉興舉艦艱藝蘇蘭蟲衛補製複襲視覺覽觀觸訊訓託訪訴診証詢詩詳誌誕誠諸謀謂護豈負",
+    "財貢貧責貸費資賓賞賴購贈趕趙跡踐蹤軌軍軟較載輔輕輩輪辭農遊違遞適遷遺郵鄉醜釋針鈴",
+    "銷鋼鍋鎖鎮鏈鑰閃閒閣闆階隱雜離靈韓頑頒頗顧飽餘駐騎騙驅鬧魯鮮鹽麥黃齡幫",
+);
+
+const SIMPLIFIED_CHARS: &str = concat!(
+    "这个们来时会说对过还后里里为么样见点学开关东车长门问间听话语认识让请谢读写号电脑",
+    "网页机场发现实际经济国华动员头题应该准备难气热没处从无业务与几吗买卖钱银货质价体",
+    "区医药疗声乐欢种类选择标确键盘设计划总结给线红绿蓝颜风云虽边远运转连进达迟录译试",
+    "验测况报导错误爱亲战争党权历岁数据库档条则规范围传统术专广飞鸟鱼马龙园书馆饭面鸡",
+    "钟表周联系系丽丰独宝贝贵带当将旧坏满圣节严队阳阴险陈随属层归忆梦愿态惯怀复记许论",
+    "议证评词讲课调谈谁变赞猪猫状获执压块坛静松闭显顺须预领频额饮养惊发斗齐齿龟产办单",
+    "纪约级练组织细终绝维综紧县续继铁镜阅双响顶项顿飘笔纸义劳师径简习虑戏户扫换击拥扩",
+    "拟担摄敌断升昼暂历胧极构枪楼树桥检欧杀壳决泪净浅汤沟温灭汉洁泽浓湿湾灾乌烟烦灯烧",
+    "营炉爷墙牺犹奖献环画异尽监众睁础礼祸稳穷竞筑篮粮纠纳纯纷绍绪编缘缩绩绳罢闻聪职肃",
+    "脚胶脸兴举舰艰艺苏兰虫卫补制复袭视觉览观触讯训托访诉诊证询诗详志诞诚诸谋谓护岂负",
+    "财贡贫责贷费资宾赏赖购赠赶赵迹践踪轨军软较载辅轻辈轮辞农游违递适迁遗邮乡丑释针铃",
+    "销钢锅锁镇链钥闪闲阁板阶隐杂离灵韩顽颁颇顾饱余驻骑骗驱闹鲁鲜盐麦黄龄帮",
+);
+
+static TRADITIONAL_TO_SIMPLIFIED: Lazy<HashMap<char, char>> = Lazy::new(|| {
+    TRADITIONAL_CHARS.chars().zip(SIMPLIFIED_CHARS.chars()).collect()
+});
+
+// Whisper 在静音/背景音上输出的非语音标注
+const NOISE_ANNOTATIONS: &[&str] = &[
+    "music", "applause", "laughter", "laughs", "silence", "noise", "blank_audio",
+    "音乐", "掌声", "笑声", "静音",
+];
+
+/// 是否为中日韩表意文字
+fn is_cjk_ideograph(c: char) -> bool {
+    matches!(c as u32,
+        0x4E00..=0x9FFF | 0x3400..=0x4DBF | 0xF900..=0xFAFF | 0x20000..=0x2A6DF)
+}
+
+/// 是否为全角标点（CJK 标点或全角形式）
+fn is_fullwidth_punct(c: char) -> bool {
+    matches!(c as u32, 0x3000..=0x303F | 0xFF00..=0xFF0F | 0xFF1A..=0xFF20 | 0xFF3B..=0xFF40 | 0xFF5B..=0xFF65)
+}
+
+fn is_cjk(c: char) -> bool {
+    is_cjk_ideograph(c) || is_fullwidth_punct(c)
+}
+
+/// 是否包含中文字符
+pub fn contains_chinese(text: &str) -> bool {
+    text.chars().any(is_cjk_ideograph)
+}
+
+/// 日文假名或韩文谚文
+fn is_kana_or_hangul(c: char) -> bool {
+    matches!(c as u32,
+        0x3040..=0x30FF | 0x31F0..=0x31FF | 0xFF66..=0xFF9F | 0x1100..=0x11FF | 0xAC00..=0xD7AF)
+}
+
+/// 识别结果是否为中文：含汉字且不含假名/谚文，日文、韩文中的汉字不能做繁简转换
+pub fn is_chinese_text(text: &str) -> bool {
+    contains_chinese(text) && !text.chars().any(is_kana_or_hangul)
+}
+
+/// 移除 Whisper 的非语音标注，如 "[BLANK_AUDIO]"、"(music)"、"（掌声）"
+pub fn strip_artifacts(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let chars: Vec<char> = text.chars().collect();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let close = match c {
+            '[' => Some(']'),
+            '(' => Some(')'),
+            '（' => Some('）'),
+            _ => None,
+        };
+
+        if let Some(close) = close {
+            if let Some(offset) = chars[i + 1..].iter().position(|&ch| ch == close) {
+                let inner: String = chars[i + 1..i + 1 + offset].iter().collect();
+                let inner_lower = inner.to_lowercase();
+                // 方括号内容都是 Whisper 标注；圆括号只移除已知的噪声标注
+                let is_annotation = c == '['
+                    || NOISE_ANNOTATIONS.iter().any(|a| inner_lower.contains(a));
+                if is_annotation {
+                    i += offset + 2;
+                    continue;
+                }
+            }
+        }
+
+        output.push(c);
+        i += 1;
+    }
+
+    output
+}
+
+/// 合并连续空白、去除首尾空白，并删除中文字符之间多余的空格
+pub fn normalize_whitespace(text: &str) -> String {
+    let collapsed = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    let chars: Vec<char> = collapsed.chars().collect();
+    let mut output = String::with_capacity(collapsed.len());
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c == ' ' && i > 0 && i + 1 < chars.len() && is_cjk(chars[i - 1]) && is_cjk(chars[i + 1]) {
+            continue;
+        }
+        output.push(c);
+    }
+
+    output
+}
+
+/// 繁体中文转简体中文
+pub fn to_simplified(text: &str) -> String {
+    text.chars()
+        .map(|c| *TRADITIONAL_TO_SIMPLIFIED.get(&c).unwrap_or(&c))
+        .collect()
+}
+
+/// 只有中文文本才做繁简转换，其它语言原样返回
+pub fn simplify_if_chinese(text: &str) -> String {
+    if is_chinese_text(text) {
+        to_simplified(text)
+    } else {
+        text.to_string()
+    }
+}
+
+/// 中文语境下将半角标点转换为全角标点
+pub fn normalize_punctuation(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut skip_space = false;
+
+    for (i, &c) in chars.iter().enumerate() {
+        if skip_space && c == ' ' {
+            continue;
+        }
+        skip_space = false;
+
+        let fullwidth = match c {
+            ',' => Some('，'),
+            '.' => Some('。'),
+            '?' => Some('？'),
+            '!' => Some('！'),
+            ':' => Some('：'),
+            ';' => Some('；'),
+            _ => None,
+        };
+
+        if let Some(fw) = fullwidth {
+            let prev_is_cjk = i > 0 && is_cjk_ideograph(chars[i - 1]);
+            if prev_is_cjk {
+                output.push(fw);
+                skip_space = true;
+                continue;
+            }
+        }
+
+        output.push(c);
+    }
+
+    output
+}
+
+/// 句末标点（半角和全角）
+fn is_sentence_punct(c: char) -> bool {
+    matches!(c, '.' | ',' | '!' | '?' | ';' | ':' | '。' | '，' | '！' | '？' | '；' | '：' | '、' | '…')
+}
+
+/// 合并连续重复的标点（"！！！" → "！"，"。。" → "。"），省略号 "..." / "……" 保留
+pub fn collapse_repeated_punctuation(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut output = String::with_capacity(text.len());
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let mut run = 1;
+        while i + run < chars.len() && chars[i + run] == c {
+            run += 1;
+        }
+
+        let is_ellipsis = (c == '.' && run >= 3) || c == '…';
+        if is_sentence_punct(c) && !is_ellipsis {
+            output.push(c);
+        } else {
+            output.extend(std::iter::repeat(c).take(run));
+        }
+        i += run;
+    }
+
+    output
+}
+
+/// 中文结果以单个句号结尾：没有句末标点时补 "。"，以逗号/顿号结尾时替换为 "。"
+pub fn ensure_chinese_terminal(text: &str) -> String {
+    let trimmed = text.trim_end();
+    match trimmed.chars().last() {
+        None => String::new(),
+        Some('。' | '！' | '？' | '…' | '」' | '”' | '）') => trimmed.to_string(),
+        Some(c) if is_sentence_punct(c) => {
+            let mut output: String = trimmed.trim_end_matches(is_sentence_punct).to_string();
+            output.push('。');
+            output
+        }
+        Some(_) => format!("{}。", trimmed),
+    }
+}
+
+/// 去掉 Whisper 自动加在句末的标点
+pub fn strip_trailing_punctuation(text: &str) -> String {
+    text.trim_end().trim_end_matches(is_sentence_punct).trim_end().to_string()
+}
+
+/// 太短的结果（如 "好的"、"OK"）直接跳过 LLM 优化
+pub fn should_optimize(text: &str) -> bool {
+    text.chars().filter(|c| !c.is_whitespace()).count() >= MIN_OPTIMIZE_CHARS
+}
+
+/// 调用 LLM 清理转录文本；失败或超过时间预算时返回原文，不阻塞输入
+pub fn optimize_with_llm(
+    text: &str,
+    processor: &Arc<dyn TranslateProcessor + Send + Sync>,
+    timeout: Duration,
+) -> String {
+    let (tx, rx) = std::sync::mpsc::channel();
+    let processor = processor.clone();
+    let input = text.to_string();
+    // 超时后线程继续运行到请求结束，结果被丢弃
+    std::thread::spawn(move || {
+        let _ = tx.send(processor.refine(&input));
+    });
+
+    let result = match rx.recv_timeout(timeout) {
+        Ok(result) => result,
+        Err(_) => {
+            warn!("⏰ LLM optimization exceeded {}ms, keeping original text", timeout.as_millis());
+            return text.to_string();
+        }
+    };
+
+    match result {
+        Ok(refined) if !refined.trim().is_empty() => {
+            trace!("✨ LLM optimized result: \"{}\"", redact(&refined));
+            refined
+        }
+        Ok(_) => {
+            info!("⚠️ LLM returned empty result, keeping original text");
+            text.to_string()
+        }
+        Err(e) => {
+            warn!("⚠️ LLM optimization failed, keeping original text: {}", e);
+            text.to_string()
+        }
+    }
+}
+
+/// 按顺序执行全部后处理步骤：去标注 → 空白规整 → 繁简转换 → 标点 → LLM 优化。
+/// add_symbol 开启时规整标点，关闭时去掉句末标点
+pub fn apply(
+    text: &str,
+    options: &PostProcessOptions,
+    refiner: Option<&Arc<dyn TranslateProcessor + Send + Sync>>,
+) -> String {
+    let mut result = normalize_whitespace(&strip_artifacts(text));
+
+    if options.convert_to_simplified {
+        result = simplify_if_chinese(&result);
+    }
+
+    if options.add_symbol {
+        let chinese = contains_chinese(&result);
+        if chinese {
+            result = normalize_punctuation(&result);
+        }
+        result = collapse_repeated_punctuation(&result);
+        if chinese {
+            result = ensure_chinese_terminal(&result);
+        }
+    } else {
+        result = strip_trailing_punctuation(&result);
+    }
+
+    if options.optimize_result && should_optimize(&result) {
+        if let Some(processor) = refiner {
+            let timeout = Duration::from_millis(options.optimize_timeout_ms);
+            result = normalize_whitespace(&optimize_with_llm(&result, processor, timeout));
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::voice_assistant::VoiceError;
+
+    struct UppercaseRefiner;
+
+    impl TranslateProcessor for UppercaseRefiner {
+        fn translate_to(&self, text: &str, _target: &str) -> Result<String, VoiceError> {
+            Ok(text.to_string())
+        }
+
+        fn refine(&self, text: &str) -> Result<String, VoiceError> {
+            Ok(text.to_uppercase())
+        }
+    }
+
+    struct FailingRefiner;
+
+    impl TranslateProcessor for FailingRefiner {
+        fn translate_to(&self, _text: &str, _target: &str) -> Result<String, VoiceError> {
+            Err(VoiceError::Other("offline".to_string()))
+        }
+
+        fn refine(&self, _text: &str) -> Result<String, VoiceError> {
+            Err(VoiceError::Other("offline".to_string()))
+        }
+    }
+
+    struct SlowRefiner;
+
+    impl TranslateProcessor for SlowRefiner {
+        fn translate_to(&self, text: &str, _target: &str) -> Result<String, VoiceError> {
+            Ok(text.to_string())
+        }
+
+        fn refine(&self, text: &str) -> Result<String, VoiceError> {
+            std::thread::sleep(Duration::from_secs(2));
+            Ok(text.to_uppercase())
+        }
+    }
+
+    fn refiner<T: TranslateProcessor + Send + Sync + 'static>(processor: T) -> Arc<dyn TranslateProcessor + Send + Sync> {
+        Arc::new(processor)
+    }
+
+    #[test]
+    fn test_strip_artifacts() {
+        assert_eq!(strip_artifacts("[BLANK_AUDIO]"), "");
+        assert_eq!(strip_artifacts("hello [Music] world"), "hello  world");
+        assert_eq!(strip_artifacts("(upbeat music) hi"), " hi");
+        assert_eq!(strip_artifacts("你好（掌声）"), "你好");
+        assert_eq!(strip_artifacts("call me (maybe)"), "call me (maybe)");
+    }
+
+    #[test]
+    fn test_normalize_whitespace() {
+        assert_eq!(normalize_whitespace("  hello   world \n"), "hello world");
+        assert_eq!(normalize_whitespace("你好 世界"), "你好世界");
+        assert_eq!(normalize_whitespace("你好 world"), "你好 world");
+    }
+
+    #[test]
+    fn test_to_simplified() {
+        assert_eq!(to_simplified("這個語音輸入軟體"), "这个语音输入软体");
+        assert_eq!(to_simplified("謝謝你們"), "谢谢你们");
+        assert_eq!(to_simplified("hello world"), "hello world");
+        assert_eq!(to_simplified("開會時間還沒確認"), "开会时间还没确认");
+        assert_eq!(to_simplified("請幫我打開檔案"), "请帮我打开档案");
+    }
+
+    #[test]
+    fn test_simplify_only_chinese_text() {
+        assert_eq!(simplify_if_chinese("電腦網頁"), "电脑网页");
+        // 日文中的汉字保持原样
+        assert_eq!(simplify_if_chinese("會議の時間です"), "會議の時間です");
+        assert_eq!(simplify_if_chinese("한국어 學校"), "한국어 學校");
+        assert_eq!(simplify_if_chinese("Hello, world!"), "Hello, world!");
+        assert!(is_chinese_text("這是中文"));
+        assert!(!is_chinese_text("ひらがな"));
+    }
+
+    #[test]
+    fn test_normalize_punctuation() {
+        assert_eq!(normalize_punctuation("你好, 世界."), "你好，世界。");
+        assert_eq!(normalize_punctuation("真的吗?"), "真的吗？");
+        assert_eq!(normalize_punctuation("版本 3.5, ok"), "版本 3.5, ok");
+    }
+
+    #[test]
+    fn test_collapse_repeated_punctuation() {
+        assert_eq!(collapse_repeated_punctuation("Really?? Yes!!!"), "Really? Yes!");
+        assert_eq!(collapse_repeated_punctuation("好的。。"), "好的。");
+        assert_eq!(collapse_repeated_punctuation("wait... 等等……"), "wait... 等等……");
+    }
+
+    #[test]
+    fn test_ensure_chinese_terminal() {
+        assert_eq!(ensure_chinese_terminal("今天天气不错"), "今天天气不错。");
+        assert_eq!(ensure_chinese_terminal("今天天气不错，"), "今天天气不错。");
+        assert_eq!(ensure_chinese_terminal("真的吗？"), "真的吗？");
+        assert_eq!(ensure_chinese_terminal(""), "");
+    }
+
+    #[test]
+    fn test_add_symbol_toggle() {
+        let with_symbols = PostProcessOptions {
+            convert_to_simplified: false,
+            add_symbol: true,
+            optimize_result: false,
+            optimize_timeout_ms: DEFAULT_OPTIMIZE_TIMEOUT_MS,
+        };
+        let without_symbols = PostProcessOptions { add_symbol: false, ..with_symbols };
+
+        assert_eq!(apply("Hello world!!", &with_symbols, None), "Hello world!");
+        assert_eq!(apply("Hello world.", &with_symbols, None), "Hello world.");
+        assert_eq!(apply("今天开会, 下午三点", &with_symbols, None), "今天开会，下午三点。");
+
+        assert_eq!(apply("Hello world.", &without_symbols, None), "Hello world");
+        assert_eq!(apply("Is it ready?", &without_symbols, None), "Is it ready");
+        assert_eq!(apply("今天开会，下午三点。", &without_symbols, None), "今天开会，下午三点");
+        assert_eq!(apply("version 3.5", &without_symbols, None), "version 3.5");
+    }
+
+    #[test]
+    fn test_optimize_with_llm_falls_back_on_error() {
+        let budget = Duration::from_secs(5);
+        assert_eq!(optimize_with_llm("hello", &refiner(UppercaseRefiner), budget), "HELLO");
+        assert_eq!(optimize_with_llm("hello", &refiner(FailingRefiner), budget), "hello");
+    }
+
+    #[test]
+    fn test_optimize_with_llm_keeps_original_on_timeout() {
+        let started = std::time::Instant::now();
+        let result = optimize_with_llm("hello world", &refiner(SlowRefiner), Duration::from_millis(50));
+        assert_eq!(result, "hello world");
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_apply_respects_flags() {
+        let all_off = PostProcessOptions {
+            convert_to_simplified: false,
+            add_symbol: false,
+            optimize_result: false,
+            optimize_timeout_ms: DEFAULT_OPTIMIZE_TIMEOUT_MS,
+        };
+        assert_eq!(apply(" [BLANK_AUDIO] 這個, 好 ", &all_off, None), "這個, 好");
+
+        let all_on = PostProcessOptions {
+            convert_to_simplified: true,
+            add_symbol: true,
+            optimize_result: true,
+            optimize_timeout_ms: DEFAULT_OPTIMIZE_TIMEOUT_MS,
+        };
+        assert_eq!(apply(" [BLANK_AUDIO] 這個, 好 ", &all_on, None), "这个，好。");
+        assert_eq!(apply("hello world", &all_on, Some(&refiner(UppercaseRefiner))), "HELLO WORLD");
+        // 太短的结果不调用 LLM
+        assert_eq!(apply("hello", &all_on, Some(&refiner(UppercaseRefiner))), "hello");
+        assert!(!should_optimize("好的。"));
+        assert!(should_optimize("明天下午开会"));
+    }
+}
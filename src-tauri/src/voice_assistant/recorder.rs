@@ -1,8 +1,10 @@
 use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
 use cpal::{Stream, StreamConfig, SampleFormat, Host};
 use hound::{WavWriter, WavSpec};
+use std::collections::VecDeque;
 use std::io::Cursor;
 use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
 use crate::voice_assistant::VoiceError;
 
 pub struct AudioRecorder {
@@ -15,6 +17,135 @@ pub struct AudioRecorder {
     save_wav_files: bool,
     _host: Host,
     recording_audio_data: Option<std::sync::Arc<std::sync::Mutex<Vec<f32>>>>,
+    // 电平表/静音检测线程的运行标记，每次录音新建，停止录音时置为 false
+    monitors_active: std::sync::Arc<std::sync::atomic::AtomicBool>,
+    // 输入流回调写入的位置（录音缓冲或预录环形缓冲）
+    sink: Arc<Mutex<CaptureSink>>,
+    preroll_duration: std::time::Duration,
+}
+
+/// 只保留最近 `capacity` 个采样的环形缓冲，用于录音开始前的预录
+pub struct PreRollBuffer {
+    samples: VecDeque<f32>,
+    capacity: usize,
+}
+
+impl PreRollBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    pub fn push(&mut self, samples: &[f32]) {
+        if self.capacity == 0 {
+            return;
+        }
+        let samples = &samples[samples.len().saturating_sub(self.capacity)..];
+        let overflow = (self.samples.len() + samples.len()).saturating_sub(self.capacity);
+        self.samples.drain(..overflow);
+        self.samples.extend(samples.iter().copied());
+    }
+
+    /// 取出缓冲中的全部采样（按时间顺序）并清空
+    pub fn drain(&mut self) -> Vec<f32> {
+        self.samples.drain(..).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+/// 按固定采样数分窗计算 RMS，每凑满一个窗口产出一次电平
+pub struct RollingRms {
+    window: usize,
+    sum_squares: f64,
+    count: usize,
+}
+
+impl RollingRms {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            sum_squares: 0.0,
+            count: 0,
+        }
+    }
+
+    /// 送入一段采样，有窗口完成时返回最后一个完成窗口的电平
+    pub fn push(&mut self, samples: &[f32]) -> Option<f32> {
+        let mut level = None;
+        for &sample in samples {
+            self.sum_squares += (sample as f64) * (sample as f64);
+            self.count += 1;
+            if self.count == self.window {
+                level = Some((self.sum_squares / self.count as f64).sqrt() as f32);
+                self.sum_squares = 0.0;
+                self.count = 0;
+            }
+        }
+        level
+    }
+}
+
+/// 录音电平回调：由输入流回调驱动，参数为 RMS 电平和已录音时长（毫秒）
+struct LevelMeter {
+    rms: RollingRms,
+    started: std::time::Instant,
+    on_level: Box<dyn Fn(f32, u64) + Send>,
+}
+
+/// 录音中写入录音缓冲，否则写入预录缓冲
+struct CaptureSink {
+    recording: Option<Arc<Mutex<Vec<f32>>>>,
+    preroll: PreRollBuffer,
+    level_meter: Option<LevelMeter>,
+}
+
+impl CaptureSink {
+    fn write(&mut self, samples: &[f32]) {
+        match &self.recording {
+            Some(buffer) => {
+                if let Ok(mut buffer) = buffer.lock() {
+                    buffer.extend_from_slice(samples);
+                }
+                if let Some(meter) = self.level_meter.as_mut() {
+                    if let Some(level) = meter.rms.push(samples) {
+                        (meter.on_level)(level, meter.started.elapsed().as_millis() as u64);
+                    }
+                }
+            }
+            None => self.preroll.push(samples),
+        }
+    }
+
+    /// 停止录音：之后回调不再写入录音缓冲，也不再回调电平
+    fn stop(&mut self) {
+        self.recording = None;
+        self.level_meter = None;
+    }
+}
+
+/// 计算一段采样的均方根电平
+pub fn rms(samples: &[f32]) -> f32 {
+    if samples.is_empty() {
+        return 0.0;
+    }
+    (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+}
+
+/// 录音 WAV 的保存目录（工作目录下的 .tauri-data/audio），清理和导出也按这里查找
+pub fn audio_directory() -> PathBuf {
+    std::env::current_dir()
+        .unwrap_or_else(|_| PathBuf::from("."))
+        .join(".tauri-data")
+        .join("audio")
 }
 
 impl AudioRecorder {
@@ -40,14 +171,99 @@ impl AudioRecorder {
             save_wav_files: true, // Default to true
             _host: host,
             recording_audio_data: None,
+            monitors_active: std::sync::Arc::new(std::sync::atomic::AtomicBool::new(false)),
+            sink: Arc::new(Mutex::new(CaptureSink {
+                recording: None,
+                preroll: PreRollBuffer::new(0),
+                level_meter: None,
+            })),
+            preroll_duration: std::time::Duration::ZERO,
         })
     }
 
+    /// 在录音开始前打开输入流，持续保留最近 `duration` 的音频，开始录音时拼到录音开头，
+    /// 避免按键防误触延迟和打开设备的耗时切掉第一个字。
+    /// 注意：预录期间麦克风一直处于打开状态（系统的麦克风指示灯会亮），
+    /// 音频只保存在内存里且只保留最近这一小段，不开始录音就不会被保存或识别。
+    pub fn start_preroll(&mut self, duration: std::time::Duration) -> Result<(), VoiceError> {
+        if self.recording || self.stream.is_some() {
+            return Ok(());
+        }
+
+        let capacity = (self.sample_rate as f64 * duration.as_secs_f64()) as usize;
+        self.preroll_duration = duration;
+        if let Ok(mut sink) = self.sink.lock() {
+            sink.recording = None;
+            sink.preroll = PreRollBuffer::new(capacity);
+        }
+        if capacity == 0 {
+            return Ok(());
+        }
+
+        let stream = self.build_input_stream()?;
+        stream.play().map_err(|e| VoiceError::Audio(format!("Failed to play stream: {}", e)))?;
+        self.stream = Some(stream);
+        tracing::info!("⏪ Pre-roll started: keeping the last {}ms of audio", duration.as_millis());
+        Ok(())
+    }
+
+    pub fn preroll_duration(&self) -> std::time::Duration {
+        self.preroll_duration
+    }
+
     pub fn start_recording(&mut self) -> Result<(), VoiceError> {
         if self.recording {
             return Ok(());
         }
 
+        // 预录中的输入流直接复用，否则新建（此时还未 play，不会丢样本）
+        let new_stream = match self.stream {
+            Some(_) => None,
+            None => Some(self.build_input_stream()?),
+        };
+
+        // 在同一把锁内取出预录数据并切换到录音缓冲，两者之间不会漏掉采样
+        let audio_data = Arc::new(Mutex::new(Vec::new()));
+        let preroll_samples = match self.sink.lock() {
+            Ok(mut sink) => {
+                let preroll = sink.preroll.drain();
+                let count = preroll.len();
+                if let Ok(mut buffer) = audio_data.lock() {
+                    *buffer = preroll;
+                }
+                sink.recording = Some(audio_data.clone());
+                count
+            }
+            Err(_) => 0,
+        };
+        if preroll_samples > 0 {
+            tracing::debug!(
+                "⏪ Prepended {} pre-roll samples ({:.0}ms)",
+                preroll_samples,
+                preroll_samples as f64 * 1000.0 / self.sample_rate as f64
+            );
+        }
+
+        self.audio_data = Vec::new();
+        // Store the Arc to the audio data so we can retrieve it later
+        self.recording_audio_data = Some(audio_data);
+
+        if let Some(stream) = new_stream {
+            stream.play().map_err(|e| VoiceError::Audio(format!("Failed to play stream: {}", e)))?;
+            self.stream = Some(stream);
+        }
+
+        self.recording = true;
+        self.record_start_time = Some(std::time::Instant::now());
+        // 每次录音使用新的标记，上一轮的监控线程不会被重新激活
+        self.monitors_active = std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true));
+
+        println!("Recording started");
+        Ok(())
+    }
+
+    /// 打开默认输入设备的输入流（未 play），采样转为单声道后写入 `sink`
+    fn build_input_stream(&self) -> Result<Stream, VoiceError> {
         let host = cpal::default_host();
         let device = host.default_input_device()
             .ok_or_else(|| VoiceError::Audio("No default input device".to_string()))?;
@@ -77,8 +293,7 @@ impl AudioRecorder {
 
         println!("Starting recording on device: {:?}, config: {:?}", device.name(), config);
 
-        let audio_data = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
-        let audio_data_clone = audio_data.clone();
+        let sink = self.sink.clone();
 
         let hardware_channels = hardware_channels; // 用于闭包的副本
 
@@ -95,8 +310,8 @@ impl AudioRecorder {
                             .map(|chunk| chunk[0]) // 取左声道
                             .collect()
                     };
-                    if let Ok(mut buffer) = audio_data_clone.lock() {
-                        buffer.extend_from_slice(&samples);
+                    if let Ok(mut sink) = sink.lock() {
+                        sink.write(&samples);
                     }
                 },
                 |err| eprintln!("Error in input stream: {}", err),
@@ -118,8 +333,8 @@ impl AudioRecorder {
                                 .map(|chunk| f32::from(chunk[0]) / i16::MAX as f32)
                                 .collect()
                         };
-                        if let Ok(mut buffer) = audio_data_clone.lock() {
-                            buffer.extend_from_slice(&samples);
+                        if let Ok(mut sink) = sink.lock() {
+                            sink.write(&samples);
                         }
                     },
                     |err| eprintln!("Error in input stream: {}", err),
@@ -142,8 +357,8 @@ impl AudioRecorder {
                                 .map(|chunk| (f32::from(chunk[0]) - u16::MAX as f32) / u16::MAX as f32)
                                 .collect()
                         };
-                        if let Ok(mut buffer) = audio_data_clone.lock() {
-                            buffer.extend_from_slice(&samples);
+                        if let Ok(mut sink) = sink.lock() {
+                            sink.write(&samples);
                         }
                     },
                     |err| eprintln!("Error in input stream: {}", err),
@@ -154,22 +369,7 @@ impl AudioRecorder {
             _ => return Err(VoiceError::Audio("Unsupported sample format".to_string())),
         };
 
-        self.audio_data = Vec::new();
-        if let Ok(mut buffer) = audio_data.lock() {
-            buffer.clear();
-        }
-
-        // Store the Arc to the audio data so we can retrieve it later
-        self.recording_audio_data = Some(audio_data.clone());
-
-        stream.play().map_err(|e| VoiceError::Audio(format!("Failed to play stream: {}", e)))?;
-
-        self.stream = Some(stream);
-        self.recording = true;
-        self.record_start_time = Some(std::time::Instant::now());
-
-        println!("Recording started");
-        Ok(())
+        Ok(stream)
     }
 
     pub fn stop_recording(&mut self) -> Result<String, VoiceError> {
@@ -179,10 +379,15 @@ impl AudioRecorder {
 
         println!("Stopping recording...");
         self.recording = false;
+        self.monitors_active.store(false, std::sync::atomic::Ordering::SeqCst);
 
         if let Some(stream) = self.stream.take() {
             drop(stream);
         }
+        // 在输入流回调使用的同一把锁内清掉电平回调，返回后不会再有电平事件
+        if let Ok(mut sink) = self.sink.lock() {
+            sink.stop();
+        }
 
         let duration = if let Some(start_time) = self.record_start_time {
             start_time.elapsed().as_secs_f64()
@@ -280,11 +485,7 @@ impl AudioRecorder {
     }
     
     fn get_audio_directory(&self) -> Result<PathBuf, VoiceError> {
-        let mut audio_dir = std::env::current_dir()
-            .map_err(|e| VoiceError::Audio(format!("Failed to get current directory: {}", e)))?;
-
-        audio_dir.push(".tauri-data");
-        audio_dir.push("audio");
+        let audio_dir = audio_directory();
 
         // Create directory if it doesn't exist
         std::fs::create_dir_all(&audio_dir)
@@ -294,6 +495,70 @@ impl AudioRecorder {
         Ok(audio_dir)
     }
 
+    /// 录音期间每录满 `interval` 的音频回调一次该段的 RMS 电平和已录音时长（毫秒）。
+    /// 由输入流回调驱动，`stop_recording` 返回后不再回调
+    pub fn start_level_meter<F>(&self, interval: std::time::Duration, on_level: F)
+    where
+        F: Fn(f32, u64) + Send + 'static,
+    {
+        let Some(started) = self.record_start_time else {
+            return;
+        };
+
+        let window = (self.sample_rate as f64 * interval.as_secs_f64()) as usize;
+        if let Ok(mut sink) = self.sink.lock() {
+            if sink.recording.is_some() {
+                sink.level_meter = Some(LevelMeter {
+                    rms: RollingRms::new(window),
+                    started,
+                    on_level: Box::new(on_level),
+                });
+            }
+        }
+    }
+
+    /// 录音期间每 `interval` 把新录到的采样交给静音检测器，检测器判定应停止时回调一次 `on_silence`
+    pub fn start_silence_monitor<F>(
+        &self,
+        interval: std::time::Duration,
+        mut detector: crate::voice_assistant::asr::SilenceDetector,
+        on_silence: F,
+    ) where
+        F: FnOnce() + Send + 'static,
+    {
+        let Some(buffer) = self.recording_audio_data.clone() else {
+            return;
+        };
+        let active = self.monitors_active.clone();
+
+        std::thread::spawn(move || {
+            // 预录的部分不参与静音判断
+            let mut consumed = buffer.lock().map(|samples| samples.len()).unwrap_or(0);
+            loop {
+                std::thread::sleep(interval);
+                if !active.load(std::sync::atomic::Ordering::SeqCst) {
+                    return;
+                }
+                let should_stop = match buffer.lock() {
+                    Ok(samples) => {
+                        let should_stop = detector.feed(&samples[consumed.min(samples.len())..]);
+                        consumed = samples.len();
+                        should_stop
+                    }
+                    Err(_) => return,
+                };
+                if should_stop {
+                    on_silence();
+                    return;
+                }
+            }
+        });
+    }
+
+    pub fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
     pub fn is_recording(&self) -> bool {
         self.recording
     }
@@ -309,10 +574,15 @@ impl AudioRecorder {
 
         println!("Stopping recording...");
         self.recording = false;
+        self.monitors_active.store(false, std::sync::atomic::Ordering::SeqCst);
 
         if let Some(stream) = self.stream.take() {
             drop(stream);
         }
+        // 在输入流回调使用的同一把锁内清掉电平回调，返回后不会再有电平事件
+        if let Ok(mut sink) = self.sink.lock() {
+            sink.stop();
+        }
 
         let duration = if let Some(start_time) = self.record_start_time {
             start_time.elapsed().as_secs_f64()
@@ -423,4 +693,72 @@ impl Drop for AudioRecorder {
             let _ = self.stop_recording();
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_preroll_keeps_most_recent_samples() {
+        let mut preroll = PreRollBuffer::new(4);
+        preroll.push(&[1.0, 2.0, 3.0]);
+        preroll.push(&[4.0, 5.0]);
+        assert_eq!(preroll.len(), 4);
+        assert_eq!(preroll.drain(), vec![2.0, 3.0, 4.0, 5.0]);
+        assert!(preroll.is_empty());
+
+        // 单次写入超过容量时只保留末尾
+        preroll.push(&[1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        assert_eq!(preroll.drain(), vec![3.0, 4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    fn test_capture_sink_switches_from_preroll_to_recording() {
+        let mut sink = CaptureSink {
+            recording: None,
+            preroll: PreRollBuffer::new(2),
+            level_meter: None,
+        };
+        sink.write(&[0.1, 0.2, 0.3]);
+
+        let buffer = Arc::new(Mutex::new(sink.preroll.drain()));
+        sink.recording = Some(buffer.clone());
+        sink.write(&[0.4]);
+        assert_eq!(*buffer.lock().unwrap(), vec![0.2, 0.3, 0.4]);
+
+        // 关闭预录（容量 0）时不缓存任何数据
+        let mut disabled = PreRollBuffer::new(0);
+        disabled.push(&[0.5]);
+        assert!(disabled.is_empty());
+    }
+
+    #[test]
+    fn test_rolling_rms_emits_once_per_window() {
+        let mut rms = RollingRms::new(4);
+        assert_eq!(rms.push(&[0.5, -0.5]), None);
+        assert!((rms.push(&[0.5, -0.5, 0.1]).unwrap() - 0.5).abs() < 1e-6);
+        // 一次送入多个窗口时返回最后一个
+        assert!((rms.push(&[0.1, 0.1, 0.1, 0.2, 0.2, 0.2, 0.2]).unwrap() - 0.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_level_meter_stops_with_recording() {
+        let levels = Arc::new(Mutex::new(Vec::new()));
+        let sink_levels = levels.clone();
+        let mut sink = CaptureSink {
+            recording: Some(Arc::new(Mutex::new(Vec::new()))),
+            preroll: PreRollBuffer::new(0),
+            level_meter: Some(LevelMeter {
+                rms: RollingRms::new(2),
+                started: std::time::Instant::now(),
+                on_level: Box::new(move |level, _| sink_levels.lock().unwrap().push(level)),
+            }),
+        };
+
+        sink.write(&[0.3, 0.3, 0.3]);
+        sink.stop();
+        sink.write(&[0.3, 0.3, 0.3]);
+        assert_eq!(levels.lock().unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,156 @@
+use regex::Regex;
+use std::sync::{OnceLock, RwLock};
+use tracing::{info, warn};
+use crate::database::TranscriptionReplacement;
+use crate::voice_assistant::VoiceError;
+
+/// 编译后的替换规则
+#[derive(Debug, Clone)]
+enum CompiledPattern {
+    Literal(String),
+    Regex(Regex),
+}
+
+#[derive(Debug, Clone)]
+pub struct ReplacementRule {
+    pattern: CompiledPattern,
+    replacement: String,
+}
+
+impl ReplacementRule {
+    /// 编译规则，正则错误在添加时就返回
+    pub fn compile(pattern: &str, replacement: &str, is_regex: bool) -> Result<Self, VoiceError> {
+        if pattern.is_empty() {
+            return Err(VoiceError::Other("Replacement pattern must not be empty".to_string()));
+        }
+
+        let pattern = if is_regex {
+            let regex = Regex::new(pattern)
+                .map_err(|e| VoiceError::Other(format!("Invalid regex '{}': {}", pattern, e)))?;
+            CompiledPattern::Regex(regex)
+        } else {
+            CompiledPattern::Literal(pattern.to_string())
+        };
+
+        Ok(Self {
+            pattern,
+            replacement: replacement.to_string(),
+        })
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        match &self.pattern {
+            CompiledPattern::Literal(literal) => text.replace(literal.as_str(), &self.replacement),
+            CompiledPattern::Regex(regex) => regex.replace_all(text, self.replacement.as_str()).into_owned(),
+        }
+    }
+}
+
+/// 按顺序应用的替换规则集合
+#[derive(Debug, Clone, Default)]
+pub struct ReplacementSet {
+    rules: Vec<ReplacementRule>,
+}
+
+impl ReplacementSet {
+    /// 从数据库记录构建；无法编译的旧记录会被跳过
+    pub fn from_records(records: &[TranscriptionReplacement]) -> Self {
+        let rules = records
+            .iter()
+            .filter_map(|r| match ReplacementRule::compile(&r.pattern, &r.replacement, r.is_regex) {
+                Ok(rule) => Some(rule),
+                Err(e) => {
+                    warn!("⚠️ Skipping replacement {}: {}", r.id, e);
+                    None
+                }
+            })
+            .collect();
+
+        Self { rules }
+    }
+
+    pub fn apply(&self, text: &str) -> String {
+        self.rules
+            .iter()
+            .fold(text.to_string(), |acc, rule| rule.apply(&acc))
+    }
+
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+// 全局缓存，规则只在列表变化时重新编译
+static REPLACEMENTS: OnceLock<RwLock<ReplacementSet>> = OnceLock::new();
+
+fn replacements() -> &'static RwLock<ReplacementSet> {
+    REPLACEMENTS.get_or_init(|| RwLock::new(ReplacementSet::default()))
+}
+
+/// 用数据库中的记录刷新全局替换规则
+pub fn set_replacements(records: &[TranscriptionReplacement]) {
+    let set = ReplacementSet::from_records(records);
+    info!("📖 Loaded {} transcription replacement(s)", set.len());
+    *replacements().write().unwrap() = set;
+}
+
+/// 对ASR结果应用全局替换规则
+pub fn apply_replacements(text: &str) -> String {
+    let set = replacements().read().unwrap();
+    if set.is_empty() {
+        return text.to_string();
+    }
+    set.apply(text)
+}
+
+/// 从数据库重新加载替换规则
+pub async fn reload_replacements() -> Result<(), String> {
+    let records = crate::commands::get_replacements_internal().await?;
+    set_replacements(&records);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_literal_replacement() {
+        let rule = ReplacementRule::compile("voice type", "VoiceType", false).unwrap();
+        assert_eq!(rule.apply("I use voice type daily"), "I use VoiceType daily");
+
+        let rule = ReplacementRule::compile("语音打字", "VoiceType", false).unwrap();
+        assert_eq!(rule.apply("我在用语音打字写代码"), "我在用VoiceType写代码");
+    }
+
+    #[test]
+    fn test_regex_replacement() {
+        let rule = ReplacementRule::compile(r"(?i)\bgit ?hub\b", "GitHub", true).unwrap();
+        assert_eq!(rule.apply("push to git hub and Github"), "push to GitHub and GitHub");
+
+        let rule = ReplacementRule::compile(r"泰(瑞|锐)", "Tauri", true).unwrap();
+        assert_eq!(rule.apply("泰瑞和泰锐都是"), "Tauri和Tauri都是");
+    }
+
+    #[test]
+    fn test_invalid_regex_rejected_at_compile_time() {
+        assert!(ReplacementRule::compile("(unclosed", "x", true).is_err());
+        assert!(ReplacementRule::compile("(unclosed", "x", false).is_ok());
+        assert!(ReplacementRule::compile("", "x", false).is_err());
+    }
+
+    #[test]
+    fn test_rules_apply_in_order() {
+        let set = ReplacementSet {
+            rules: vec![
+                ReplacementRule::compile("whisper", "Whisper", false).unwrap(),
+                ReplacementRule::compile("Whisper cpp", "whisper.cpp", false).unwrap(),
+            ],
+        };
+        assert_eq!(set.apply("whisper cpp is fast"), "whisper.cpp is fast");
+    }
+}
@@ -0,0 +1,379 @@
+//! 识别结果写库通道：识别线程只把结果发到通道里，由 Tauri 异步运行时上的一个任务统一写入历史记录，
+//! 不再每次识别都新建 Tokio 运行时。写库任务持有数据库句柄，短时间内到达的多条结果合并成一批，
+//! 在一个事务中写入；写入失败时整批保留重试，重试用尽才丢弃并通知前端
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+use std::time::Duration;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::{error, info, warn};
+use crate::database::{Database, LatencyBreakdown, NewHistoryRecord};
+use crate::voice_assistant::coordinator::{self, AsrResult, RecognitionInfo, RecordedAudio};
+
+/// 一批最多写入的结果数
+const MAX_BATCH: usize = 16;
+/// 一批结果最多尝试写入的次数
+const MAX_WRITE_ATTEMPTS: u32 = 3;
+/// 第 n 次重试前等待 n 倍的间隔
+const RETRY_DELAY: Duration = Duration::from_millis(500);
+
+/// 等待写入历史的一次结果
+#[derive(Debug)]
+pub enum PendingResult {
+    Transcription {
+        text: String,
+        processor_type: String,
+        processing_time_ms: Option<i64>,
+        error_message: Option<String>,
+        audio: RecordedAudio,
+        latency: Option<LatencyBreakdown>,
+        recognition: RecognitionInfo,
+    },
+    /// 翻译模式或自动翻译的结果，原文和译文一起保存
+    Translation {
+        source_text: String,
+        translated_text: String,
+        processor_type: String,
+        processing_time_ms: Option<i64>,
+        audio: RecordedAudio,
+        translation_skipped: bool,
+        /// 各阶段耗时，翻译单独计入 translate_ms
+        latency: Option<LatencyBreakdown>,
+        recognition: RecognitionInfo,
+    },
+    /// 识别前检查没有发现语音的录音，没有调用 ASR
+    Skipped {
+        translate: bool,
+        speech_ms: i64,
+        audio: RecordedAudio,
+    },
+    /// 结束的转录会话，全文保存为一条记录
+    Session {
+        text: String,
+        duration_ms: i64,
+        audio_duration_ms: Option<i64>,
+        /// 每次听写的时间，导出 SRT 时使用
+        segments_json: Option<String>,
+    },
+}
+
+impl PendingResult {
+    /// 日志中只记录结果类型和处理器，不输出识别文本
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PendingResult::Transcription { .. } => "transcription",
+            PendingResult::Translation { .. } => "translation",
+            PendingResult::Skipped { .. } => "skipped",
+            PendingResult::Session { .. } => "session",
+        }
+    }
+
+    pub fn processor_type(&self) -> Option<&str> {
+        match self {
+            PendingResult::Transcription { processor_type, .. } | PendingResult::Translation { processor_type, .. } => Some(processor_type),
+            PendingResult::Skipped { .. } | PendingResult::Session { .. } => None,
+        }
+    }
+
+    /// 翻译热键在拿到原文前就失败（录音、WAV 转换或 ASR 出错）：没有可翻译的原文，
+    /// 按失败的识别记录保存，服务状态、失败统计和用量里都能看到
+    pub fn failed_translation(
+        text: String,
+        error_message: String,
+        processor_type: String,
+        processing_time_ms: Option<i64>,
+        audio: RecordedAudio,
+        latency: Option<LatencyBreakdown>,
+        recognition: RecognitionInfo,
+    ) -> Self {
+        PendingResult::Transcription {
+            text,
+            processor_type,
+            processing_time_ms,
+            error_message: Some(error_message),
+            audio,
+            latency,
+            recognition,
+        }
+    }
+
+    /// 转成要写入的历史记录和延迟明细
+    pub fn into_record(self) -> (NewHistoryRecord, Option<LatencyBreakdown>) {
+        match self {
+            PendingResult::Transcription { text, processor_type, processing_time_ms, error_message, audio, latency, recognition } => {
+                let record = NewHistoryRecord {
+                    record_type: "asr".to_string(),
+                    input_text: None,
+                    output_text: Some(text),
+                    audio_file_path: audio.file_path,
+                    processor_type: Some(processor_type),
+                    processing_time_ms,
+                    audio_duration_ms: audio.duration_ms,
+                    success: error_message.is_none(),
+                    error_message,
+                    translation_skipped: false,
+                    detected_language: recognition.detected_language,
+                    confidence: recognition.confidence,
+                    segments_json: None,
+                };
+                (record, latency)
+            }
+            PendingResult::Translation { source_text, translated_text, processor_type, processing_time_ms, audio, translation_skipped, latency, recognition } => {
+                let record = NewHistoryRecord {
+                    audio_duration_ms: audio.duration_ms,
+                    translation_skipped,
+                    detected_language: recognition.detected_language,
+                    confidence: recognition.confidence,
+                    ..NewHistoryRecord::translation(
+                        source_text,
+                        translated_text,
+                        &processor_type,
+                        processing_time_ms,
+                        audio.file_path,
+                    )
+                };
+                (record, latency)
+            }
+            PendingResult::Skipped { translate, speech_ms, audio } => {
+                let record = NewHistoryRecord {
+                    record_type: "skipped".to_string(),
+                    input_text: None,
+                    output_text: None,
+                    audio_file_path: audio.file_path,
+                    processor_type: None,
+                    processing_time_ms: None,
+                    audio_duration_ms: audio.duration_ms,
+                    success: false,
+                    error_message: Some(format!(
+                        "Empty {} recording skipped: {}ms of speech detected",
+                        if translate { "translation" } else { "transcription" },
+                        speech_ms
+                    )),
+                    translation_skipped: false,
+                    detected_language: None,
+                    confidence: None,
+                    segments_json: None,
+                };
+                (record, None)
+            }
+            PendingResult::Session { text, duration_ms, audio_duration_ms, segments_json } => {
+                let record = NewHistoryRecord {
+                    record_type: "session".to_string(),
+                    input_text: None,
+                    output_text: Some(text),
+                    audio_file_path: None,
+                    processor_type: None,
+                    processing_time_ms: Some(duration_ms),
+                    audio_duration_ms,
+                    success: true,
+                    error_message: None,
+                    translation_skipped: false,
+                    detected_language: None,
+                    confidence: None,
+                    segments_json,
+                };
+                (record, None)
+            }
+        }
+    }
+}
+
+/// 发送端，可在任意线程中使用
+#[derive(Debug, Clone)]
+pub struct ResultSink {
+    sender: UnboundedSender<PendingResult>,
+}
+
+static SINK: OnceLock<ResultSink> = OnceLock::new();
+// 已发送但还没写完的结果数，退出时等待它归零再关闭数据库
+static PENDING: AtomicUsize = AtomicUsize::new(0);
+
+impl ResultSink {
+    /// 启动写库任务，只由 VoiceAssistant::start 调用；重复调用返回同一个发送端
+    pub fn start() -> ResultSink {
+        SINK.get_or_init(|| {
+            let (sink, receiver) = ResultSink::channel();
+            tauri::async_runtime::spawn(run_writer(receiver));
+            info!("💾 Result sink started");
+            sink
+        }).clone()
+    }
+
+    /// 已启动的全局发送端，写库任务还没启动时为 None
+    pub fn global() -> Option<ResultSink> {
+        SINK.get().cloned()
+    }
+
+    /// 不启动写库任务的通道，接收端由调用方消费
+    pub fn channel() -> (ResultSink, UnboundedReceiver<PendingResult>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        (ResultSink { sender }, receiver)
+    }
+
+    pub fn send(&self, result: PendingResult) {
+        PENDING.fetch_add(1, Ordering::SeqCst);
+        if let Err(e) = self.sender.send(result) {
+            PENDING.fetch_sub(1, Ordering::SeqCst);
+            error!(
+                "❌ Result sink is closed, dropping {} result from {}",
+                e.0.kind(),
+                e.0.processor_type().unwrap_or("-")
+            );
+        }
+    }
+}
+
+/// 等待下一条结果，并带上通道里已经排队的结果（最多 MAX_BATCH 条）；通道关闭后返回 None
+pub async fn next_batch(receiver: &mut UnboundedReceiver<PendingResult>) -> Option<Vec<PendingResult>> {
+    let mut batch = vec![receiver.recv().await?];
+    while batch.len() < MAX_BATCH {
+        match receiver.try_recv() {
+            Ok(result) => batch.push(result),
+            Err(_) => break,
+        }
+    }
+    Some(batch)
+}
+
+async fn run_writer(mut receiver: UnboundedReceiver<PendingResult>) {
+    let mut database: Option<Database> = None;
+    while let Some(batch) = next_batch(&mut receiver).await {
+        let records: Vec<_> = batch.into_iter().map(PendingResult::into_record).collect();
+        let count = records.len();
+        write_with_retry(&mut database, records).await;
+        PENDING.fetch_sub(count, Ordering::SeqCst);
+    }
+}
+
+/// 写入一批结果，失败时保留整批重试；重试用尽后丢弃并通过事件通知前端
+async fn write_with_retry(database: &mut Option<Database>, records: Vec<(NewHistoryRecord, Option<LatencyBreakdown>)>) {
+    for attempt in 1..=MAX_WRITE_ATTEMPTS {
+        match write_batch(database, &records).await {
+            Ok(()) => return,
+            Err(e) if attempt < MAX_WRITE_ATTEMPTS => {
+                warn!("⚠️ Result sink: failed to save {} result(s), retrying (attempt {}/{}): {}", records.len(), attempt, MAX_WRITE_ATTEMPTS, e);
+                tokio::time::sleep(RETRY_DELAY * attempt).await;
+            }
+            Err(e) => {
+                error!("❌ Result sink: dropping {} result(s) after {} attempts: {}", records.len(), MAX_WRITE_ATTEMPTS, e);
+                coordinator::emit_history_save_failed(records.len(), &e);
+            }
+        }
+    }
+}
+
+/// 在一个事务中写入一批结果，成功后逐条通知前端。出错后丢弃数据库句柄，下次重新获取
+async fn write_batch(database: &mut Option<Database>, records: &[(NewHistoryRecord, Option<LatencyBreakdown>)]) -> Result<(), String> {
+    if database.is_none() {
+        *database = Some(Database::from_global_pool().await.map_err(|e| format!("failed to get database: {}", e))?);
+    }
+    let Some(db) = database.as_ref() else { return Ok(()) };
+
+    if let Err(e) = db.add_history_records(records.to_vec()).await {
+        *database = None;
+        return Err(e.to_string());
+    }
+
+    for (record, _) in records {
+        // 跳过的录音没有识别结果，前端只收到 empty-recording 事件；会话记录只刷新历史
+        if !matches!(record.record_type.as_str(), "skipped" | "session") {
+            coordinator::emit_asr_result_event(&AsrResult::from_record(record));
+        }
+    }
+    info!("💾 Result sink saved {} result(s)", records.len());
+    // 一批只刷新一次历史和服务状态
+    coordinator::emit_new_history_record_event();
+    coordinator::emit_service_status_updated_event();
+    Ok(())
+}
+
+/// 还没写入数据库的结果数
+pub fn pending() -> usize {
+    PENDING.load(Ordering::SeqCst)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transcription(text: &str) -> PendingResult {
+        PendingResult::Transcription {
+            text: text.to_string(),
+            processor_type: "whisper-rs".to_string(),
+            processing_time_ms: Some(120),
+            error_message: None,
+            audio: RecordedAudio { file_path: None, duration_ms: Some(1500) },
+            latency: None,
+            recognition: RecognitionInfo { detected_language: Some("en".to_string()), confidence: Some(0.9), ..Default::default() },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_queued_results_are_batched() {
+        let (sink, mut receiver) = ResultSink::channel();
+        for text in ["one", "two", "three"] {
+            sink.send(transcription(text));
+        }
+
+        let batch = next_batch(&mut receiver).await.unwrap();
+        let texts: Vec<_> = batch.into_iter().map(|r| r.into_record().0.output_text.unwrap()).collect();
+        assert_eq!(texts, ["one", "two", "three"]);
+
+        for i in 0..MAX_BATCH + 1 {
+            sink.send(transcription(&i.to_string()));
+        }
+        assert_eq!(next_batch(&mut receiver).await.unwrap().len(), MAX_BATCH);
+        assert_eq!(next_batch(&mut receiver).await.unwrap().len(), 1);
+
+        drop(sink);
+        assert!(next_batch(&mut receiver).await.is_none());
+    }
+
+    #[test]
+    fn test_into_record() {
+        let (record, _) = transcription("hello").into_record();
+        assert_eq!(record.record_type, "asr");
+        assert!(record.success);
+        assert_eq!(record.audio_duration_ms, Some(1500));
+        assert_eq!(record.detected_language.as_deref(), Some("en"));
+        assert_eq!(record.confidence, Some(0.9));
+
+        let (record, latency) = PendingResult::Translation {
+            source_text: "你好".to_string(),
+            translated_text: "Hello".to_string(),
+            processor_type: "siliconflow".to_string(),
+            processing_time_ms: Some(300),
+            audio: RecordedAudio::default(),
+            translation_skipped: true,
+            latency: Some(LatencyBreakdown { asr_ms: Some(120), ..Default::default() }),
+            recognition: RecognitionInfo::default(),
+        }
+        .into_record();
+        assert_eq!(record.record_type, "translate");
+        assert_eq!(record.input_text.as_deref(), Some("你好"));
+        assert!(record.translation_skipped);
+        assert_eq!(latency.unwrap().asr_ms, Some(120));
+
+        let (record, _) = PendingResult::Skipped {
+            translate: false,
+            speech_ms: 90,
+            audio: RecordedAudio { file_path: None, duration_ms: Some(400) },
+        }
+        .into_record();
+        assert_eq!(record.record_type, "skipped");
+        assert!(record.output_text.is_none());
+        assert_eq!(record.audio_duration_ms, Some(400));
+        assert!(record.error_message.unwrap().contains("90ms"));
+
+        let (record, _) = PendingResult::Session {
+            text: "full text".to_string(),
+            duration_ms: 60_000,
+            audio_duration_ms: Some(20_000),
+            segments_json: Some("[]".to_string()),
+        }
+        .into_record();
+        assert_eq!(record.record_type, "session");
+        assert_eq!(record.output_text.as_deref(), Some("full text"));
+        assert_eq!(record.processing_time_ms, Some(60_000));
+        assert_eq!(record.segments_json.as_deref(), Some("[]"));
+    }
+}
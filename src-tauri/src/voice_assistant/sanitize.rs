@@ -0,0 +1,259 @@
+//! 输入前的最后一道检查：Whisper 偶尔会在静音上输出 "Thanks for watching!"，或者把同一句话重复上千次，
+//! 这些文本不应该被打进编辑器
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info};
+use crate::voice_assistant::logger::redact;
+
+/// 一次最多输入的字符数，超出的部分放到剪贴板
+pub const DEFAULT_MAX_OUTPUT_CHARS: usize = 5000;
+
+/// 同一句话连续出现超过这个次数时，多出的部分视为幻觉删除
+pub const DEFAULT_MAX_SENTENCE_REPEATS: usize = 3;
+
+// Whisper 在静音/噪声上常见的幻觉输出（比较时忽略大小写和首尾标点）
+const SILENCE_HALLUCINATIONS: &[&str] = &[
+    "thanks for watching",
+    "thank you for watching",
+    "thanks for watching and see you next time",
+    "please subscribe",
+    "please like and subscribe",
+    "subtitles by the amara.org community",
+    "字幕由amara.org社区提供",
+    "谢谢观看",
+    "感谢观看",
+    "请不吝点赞 订阅 转发 打赏支持明镜与点点栏目",
+];
+
+/// 各项规则的开关，保存在 app_settings 中
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SanitizeOptions {
+    /// 删除换行、制表符以外的控制字符
+    pub strip_control_chars: bool,
+    /// 删除连续重复超过 max_sentence_repeats 次的句子
+    pub collapse_repetition: bool,
+    pub max_sentence_repeats: usize,
+    /// 整段结果只有静音幻觉（"[BLANK_AUDIO]"、"Thanks for watching!" 等）时不输入
+    pub suppress_hallucinations: bool,
+    /// 超过 max_output_chars 的部分不输入，放到剪贴板
+    pub cap_output_length: bool,
+    pub max_output_chars: usize,
+}
+
+impl Default for SanitizeOptions {
+    fn default() -> Self {
+        Self {
+            strip_control_chars: true,
+            collapse_repetition: true,
+            max_sentence_repeats: DEFAULT_MAX_SENTENCE_REPEATS,
+            suppress_hallucinations: true,
+            cap_output_length: true,
+            max_output_chars: DEFAULT_MAX_OUTPUT_CHARS,
+        }
+    }
+}
+
+/// 处理后要输入的文本；overflow 是超出长度限制、没有输入的部分
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Sanitized {
+    pub text: String,
+    pub overflow: Option<String>,
+}
+
+fn is_sentence_end(c: char) -> bool {
+    matches!(c, '.' | '!' | '?' | '\n' | '。' | '！' | '？' | '…')
+}
+
+/// 按句末标点切分，标点和后面的空白留在前一句中，拼接后与原文相同
+fn split_sentences(text: &str) -> Vec<&str> {
+    let mut sentences = Vec::new();
+    let mut start = 0;
+    let mut after_end = false;
+    for (i, c) in text.char_indices() {
+        if is_sentence_end(c) {
+            after_end = true;
+        } else if after_end && !c.is_whitespace() {
+            sentences.push(&text[start..i]);
+            start = i;
+            after_end = false;
+        }
+    }
+    if start < text.len() {
+        sentences.push(&text[start..]);
+    }
+    sentences
+}
+
+/// 比较句子时忽略大小写和首尾的标点/空白
+fn sentence_key(sentence: &str) -> String {
+    sentence.trim_matches(|c: char| !c.is_alphanumeric()).to_lowercase()
+}
+
+/// 删除换行、制表符以外的控制字符（\r\n 保留为 \n）
+pub fn strip_control_chars(text: &str) -> String {
+    text.chars()
+        .filter(|&c| !c.is_control() || c == '\n' || c == '\t')
+        .collect()
+}
+
+/// 同一句话连续出现超过 max_repeats 次时只保留前 max_repeats 次
+pub fn collapse_repetition(text: &str, max_repeats: usize) -> String {
+    let max_repeats = max_repeats.max(1);
+    let mut output = String::with_capacity(text.len());
+    let mut previous: Option<String> = None;
+    let mut repeats = 0;
+    let mut collapsed = 0;
+
+    for sentence in split_sentences(text) {
+        let key = sentence_key(sentence);
+        if !key.is_empty() && previous.as_deref() == Some(key.as_str()) {
+            repeats += 1;
+            if repeats > max_repeats {
+                collapsed += 1;
+                continue;
+            }
+        } else {
+            previous = Some(key);
+            repeats = 1;
+        }
+        output.push_str(sentence);
+    }
+
+    if collapsed > 0 {
+        info!("🧹 Collapsed {} repeated sentence(s)", collapsed);
+        output.truncate(output.trim_end().len());
+    }
+    output
+}
+
+/// 整段文本是否只有非语音标注或已知的静音幻觉
+pub fn is_silence_hallucination(text: &str) -> bool {
+    if text.trim().is_empty() {
+        return false;
+    }
+    let stripped = crate::voice_assistant::postprocess::strip_artifacts(text);
+    let keys: Vec<String> = split_sentences(&stripped)
+        .into_iter()
+        .map(sentence_key)
+        .filter(|key| !key.is_empty())
+        .collect();
+    keys.iter().all(|key| SILENCE_HALLUCINATIONS.contains(&key.as_str()))
+}
+
+/// 按字符数切分，返回前 max_chars 个字符和剩余部分
+pub fn split_at_char_limit(text: &str, max_chars: usize) -> (String, Option<String>) {
+    match text.char_indices().nth(max_chars) {
+        Some((index, _)) => (text[..index].to_string(), Some(text[index..].to_string())),
+        None => (text.to_string(), None),
+    }
+}
+
+/// 按开关依次执行：删除控制字符、过滤静音幻觉、合并重复句子、限制长度
+pub fn sanitize(text: &str, options: &SanitizeOptions) -> Sanitized {
+    let mut text = if options.strip_control_chars {
+        strip_control_chars(text)
+    } else {
+        text.to_string()
+    };
+
+    if options.suppress_hallucinations && is_silence_hallucination(&text) {
+        debug!("🔇 Suppressed silence hallucination: {}", redact(&text));
+        return Sanitized::default();
+    }
+
+    if options.collapse_repetition {
+        text = collapse_repetition(&text, options.max_sentence_repeats);
+    }
+
+    if options.cap_output_length {
+        let (head, overflow) = split_at_char_limit(&text, options.max_output_chars);
+        if overflow.is_some() {
+            info!("✂️ Output capped at {} characters", options.max_output_chars);
+        }
+        return Sanitized { text: head, overflow };
+    }
+
+    Sanitized { text, overflow: None }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn only(f: impl FnOnce(&mut SanitizeOptions)) -> SanitizeOptions {
+        let mut options = SanitizeOptions {
+            strip_control_chars: false,
+            collapse_repetition: false,
+            max_sentence_repeats: DEFAULT_MAX_SENTENCE_REPEATS,
+            suppress_hallucinations: false,
+            cap_output_length: false,
+            max_output_chars: DEFAULT_MAX_OUTPUT_CHARS,
+        };
+        f(&mut options);
+        options
+    }
+
+    #[test]
+    fn test_strip_control_chars() {
+        assert_eq!(strip_control_chars("a\u{0}b\u{1b}[2Jc\r\nd\te\u{7f}"), "ab[2Jc\nd\te");
+        assert_eq!(strip_control_chars("你好\n世界"), "你好\n世界");
+
+        let raw = "a\u{7}b";
+        assert_eq!(sanitize(raw, &only(|_| {})).text, raw);
+        assert_eq!(sanitize(raw, &only(|o| o.strip_control_chars = true)).text, "ab");
+    }
+
+    #[test]
+    fn test_collapse_repetition() {
+        let runaway = "Hello there. Thank you. Thank you. thank you! Thank you. Thank you. Thank you";
+        assert_eq!(collapse_repetition(runaway, 3), "Hello there. Thank you. Thank you. thank you!");
+        assert_eq!(collapse_repetition(runaway, 1), "Hello there. Thank you.");
+        assert_eq!(collapse_repetition("好的。好的。好的。好的。", 2), "好的。好的。");
+
+        // 不连续的重复和未超过上限的重复保持原样
+        let normal = "Yes. No. Yes. No. Yes.";
+        assert_eq!(collapse_repetition(normal, 1), normal);
+        assert_eq!(collapse_repetition("Go. Go. Go.", 3), "Go. Go. Go.");
+
+        assert_eq!(sanitize(runaway, &only(|_| {})).text, runaway);
+        assert_eq!(
+            sanitize(runaway, &only(|o| { o.collapse_repetition = true; o.max_sentence_repeats = 1; })).text,
+            "Hello there. Thank you."
+        );
+    }
+
+    #[test]
+    fn test_silence_hallucinations_are_suppressed() {
+        assert!(is_silence_hallucination("[BLANK_AUDIO]"));
+        assert!(is_silence_hallucination(" Thanks for watching! "));
+        assert!(is_silence_hallucination("Thank you for watching. Please subscribe."));
+        assert!(is_silence_hallucination("(music) 谢谢观看"));
+        assert!(!is_silence_hallucination("Thanks for watching the kids yesterday."));
+        assert!(!is_silence_hallucination("Thank you."));
+        assert!(!is_silence_hallucination(""));
+
+        let options = only(|o| o.suppress_hallucinations = true);
+        assert_eq!(sanitize("Thanks for watching!", &options), Sanitized::default());
+        assert_eq!(sanitize("Thanks for watching!", &only(|_| {})).text, "Thanks for watching!");
+        assert_eq!(sanitize("Meeting notes.", &options).text, "Meeting notes.");
+    }
+
+    #[test]
+    fn test_output_length_cap() {
+        assert_eq!(split_at_char_limit("你好世界", 2), ("你好".to_string(), Some("世界".to_string())));
+        assert_eq!(split_at_char_limit("abc", 3), ("abc".to_string(), None));
+
+        let options = only(|o| { o.cap_output_length = true; o.max_output_chars = 5; });
+        let sanitized = sanitize("abcdefgh", &options);
+        assert_eq!(sanitized.text, "abcde");
+        assert_eq!(sanitized.overflow.as_deref(), Some("fgh"));
+        assert_eq!(sanitize("abcdefgh", &only(|o| o.max_output_chars = 5)).overflow, None);
+    }
+
+    #[test]
+    fn test_default_options_enable_every_rule() {
+        let text = format!("{}\u{0}", "Again. ".repeat(2000));
+        let sanitized = sanitize(&text, &SanitizeOptions::default());
+        assert_eq!(sanitized.text, "Again. Again. Again.");
+        assert_eq!(sanitized.overflow, None);
+    }
+}
@@ -0,0 +1,603 @@
+use std::sync::{Arc, OnceLock, RwLock};
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+use crate::database::{AppSetting, Database};
+use crate::voice_assistant::asr::whisper_rs::{DecodingStrategy, WhisperBackend, WhisperDecoding, DEFAULT_BEAM_SIZE};
+use crate::voice_assistant::sanitize::SanitizeOptions;
+use crate::voice_assistant::AsrConfidence;
+
+// app_settings 表中的键
+pub const SERVICE_PLATFORM: &str = "service_platform";
+pub const CONVERT_TO_SIMPLIFIED: &str = "convert_to_simplified";
+pub const ADD_SYMBOL: &str = "add_symbol";
+pub const OPTIMIZE_RESULT: &str = "optimize_result";
+pub const OPTIMIZE_TIMEOUT_MS: &str = "optimize_timeout_ms";
+pub const WHISPER_ENABLE_VAD: &str = "whisper_enable_vad";
+pub const WHISPER_BACKEND: &str = "whisper_backend";
+pub const WHISPER_GPU_DEVICE_ID: &str = "whisper_gpu_device_id";
+pub const WHISPER_MODEL_PATH: &str = "whisper_model_path";
+pub const GROQ_API_ENDPOINT: &str = "groq_api_endpoint";
+pub const WHISPER_DECODING_STRATEGY: &str = "whisper_decoding_strategy";
+pub const WHISPER_BEAM_SIZE: &str = "whisper_beam_size";
+pub const WHISPER_TEMPERATURE: &str = "whisper_temperature";
+pub const WHISPER_NO_SPEECH_THRESHOLD: &str = "whisper_no_speech_threshold";
+pub const NO_SPEECH_FILTER_THRESHOLD: &str = "no_speech_filter_threshold";
+pub const WHISPER_MIN_SEGMENT_CONFIDENCE: &str = "whisper_min_segment_confidence";
+pub const ASR_RACE_MODE: &str = "asr_race_mode";
+pub const SPEECH_PRECHECK_ENABLED: &str = "speech_precheck_enabled";
+pub const SPEECH_PRECHECK_MIN_MS: &str = "speech_precheck_min_ms";
+pub const DEFAULT_PROMPT: &str = "default_prompt";
+pub const AUTO_TRANSLATE_FOREIGN: &str = "auto_translate_foreign";
+pub const PRIMARY_LANGUAGE: &str = "primary_language";
+pub const HTTP_API_ENABLED: &str = "http_api_enabled";
+pub const HTTP_API_PORT: &str = "http_api_port";
+pub const HTTP_API_TOKEN: &str = "http_api_token";
+pub const OUTPUT_STRIP_CONTROL_CHARS: &str = "output_strip_control_chars";
+pub const OUTPUT_COLLAPSE_REPETITION: &str = "output_collapse_repetition";
+pub const OUTPUT_MAX_SENTENCE_REPEATS: &str = "output_max_sentence_repeats";
+pub const OUTPUT_SUPPRESS_HALLUCINATIONS: &str = "output_suppress_hallucinations";
+pub const OUTPUT_CAP_LENGTH: &str = "output_cap_length";
+pub const OUTPUT_MAX_CHARS: &str = "output_max_chars";
+
+pub const SETTING_KEYS: &[&str] = &[
+    SERVICE_PLATFORM,
+    CONVERT_TO_SIMPLIFIED,
+    ADD_SYMBOL,
+    OPTIMIZE_RESULT,
+    OPTIMIZE_TIMEOUT_MS,
+    WHISPER_ENABLE_VAD,
+    WHISPER_BACKEND,
+    WHISPER_GPU_DEVICE_ID,
+    WHISPER_MODEL_PATH,
+    GROQ_API_ENDPOINT,
+    WHISPER_DECODING_STRATEGY,
+    WHISPER_BEAM_SIZE,
+    WHISPER_TEMPERATURE,
+    WHISPER_NO_SPEECH_THRESHOLD,
+    NO_SPEECH_FILTER_THRESHOLD,
+    WHISPER_MIN_SEGMENT_CONFIDENCE,
+    ASR_RACE_MODE,
+    SPEECH_PRECHECK_ENABLED,
+    SPEECH_PRECHECK_MIN_MS,
+    DEFAULT_PROMPT,
+    AUTO_TRANSLATE_FOREIGN,
+    PRIMARY_LANGUAGE,
+    HTTP_API_ENABLED,
+    HTTP_API_PORT,
+    HTTP_API_TOKEN,
+    OUTPUT_STRIP_CONTROL_CHARS,
+    OUTPUT_COLLAPSE_REPETITION,
+    OUTPUT_MAX_SENTENCE_REPEATS,
+    OUTPUT_SUPPRESS_HALLUCINATIONS,
+    OUTPUT_CAP_LENGTH,
+    OUTPUT_MAX_CHARS,
+];
+
+/// 无语音概率超过这个值的识别结果丢弃不输入
+pub const DEFAULT_NO_SPEECH_FILTER_THRESHOLD: f32 = 0.8;
+
+/// 录音中能量超过 VAD 阈值的时长少于这个值时不调用 ASR
+pub const DEFAULT_SPEECH_PRECHECK_MIN_MS: u64 = 300;
+
+/// 本地 HTTP 接口的默认端口
+pub const DEFAULT_HTTP_API_PORT: u16 = 47821;
+
+const SERVICE_PLATFORMS: &[&str] = &["siliconflow", "groq"];
+
+/// 运行时设置。保存在 app_settings 表中，数据库中没有的项使用环境变量或内置默认值
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Settings {
+    /// 云端 ASR 平台（siliconflow / groq），ASR 配置未指定时使用
+    pub service_platform: String,
+    /// 以下三项是没有后处理配置时的默认值
+    pub convert_to_simplified: bool,
+    pub add_symbol: bool,
+    pub optimize_result: bool,
+    /// 没有后处理配置时 LLM 优化的时间预算（毫秒）
+    pub optimize_timeout_ms: u64,
+    /// 本地 Whisper 推理前裁掉静音
+    pub whisper_enable_vad: bool,
+    /// 指定的 Whisper 后端，None 时使用编译进来的第一个 GPU 后端
+    pub whisper_backend: Option<String>,
+    /// 多显卡时使用的设备
+    pub whisper_gpu_device_id: Option<u32>,
+    /// 当前激活的 Whisper 模型文件
+    pub whisper_model_path: Option<String>,
+    /// 本地识别失败时兜底的 Groq 兼容接口，ASR 设置中没有云端配置时使用
+    pub groq_api_endpoint: Option<String>,
+    /// 本地 Whisper 的解码方式、温度和无语音阈值
+    pub whisper_decoding: WhisperDecoding,
+    /// 识别结果的无语音概率超过该值时视为什么都没听到，不输入；1.0 表示不过滤
+    pub no_speech_filter_threshold: f32,
+    /// 本地 Whisper 丢弃平均 token 概率低于该值的段落，0.0 表示不过滤
+    pub whisper_min_segment_confidence: f32,
+    /// 同时用本地 whisper 和云端识别，采用先返回的结果；会额外产生云端费用，默认关闭
+    pub asr_race_mode: bool,
+    /// 识别前检查录音里有没有语音，误触热键的空录音直接跳过；很小声说话时可以关闭
+    pub speech_precheck_enabled: bool,
+    pub speech_precheck_min_ms: u64,
+    /// 本地 Whisper 的 initial_prompt（人名、术语等），识别时没有指定提示词时使用
+    pub default_prompt: Option<String>,
+    /// 转写热键识别出的语言不是主语言时，自动翻译成主语言
+    pub auto_translate_foreign: bool,
+    /// 主语言代码（如 "zh"），自动翻译的判断依据和目标语言
+    pub primary_language: Option<String>,
+    /// 本地 HTTP 接口（只监听 127.0.0.1），默认关闭
+    pub http_api_enabled: bool,
+    pub http_api_port: u16,
+    /// 调用本地 HTTP 接口需要的 Bearer token，首次启用时生成
+    pub http_api_token: Option<String>,
+    /// 输入识别结果前的清理规则
+    pub output_sanitizer: SanitizeOptions,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            service_platform: "siliconflow".to_string(),
+            convert_to_simplified: true,
+            add_symbol: true,
+            optimize_result: true,
+            optimize_timeout_ms: crate::voice_assistant::postprocess::DEFAULT_OPTIMIZE_TIMEOUT_MS,
+            whisper_enable_vad: false,
+            whisper_backend: None,
+            whisper_gpu_device_id: None,
+            whisper_model_path: None,
+            groq_api_endpoint: None,
+            whisper_decoding: WhisperDecoding::default(),
+            no_speech_filter_threshold: DEFAULT_NO_SPEECH_FILTER_THRESHOLD,
+            whisper_min_segment_confidence: 0.0,
+            asr_race_mode: false,
+            speech_precheck_enabled: true,
+            speech_precheck_min_ms: DEFAULT_SPEECH_PRECHECK_MIN_MS,
+            default_prompt: None,
+            auto_translate_foreign: false,
+            primary_language: None,
+            http_api_enabled: false,
+            http_api_port: DEFAULT_HTTP_API_PORT,
+            http_api_token: None,
+            output_sanitizer: SanitizeOptions::default(),
+        }
+    }
+}
+
+fn parse_bool(value: &str) -> Result<bool, String> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" | "yes" | "on" => Ok(true),
+        "false" | "0" | "no" | "off" => Ok(false),
+        other => Err(format!("Invalid boolean value: {}", other)),
+    }
+}
+
+/// whisper 的提示词最多用 224 个 token，过长的部分会被截掉
+const MAX_PROMPT_CHARS: usize = 1000;
+
+/// 0.0..=1.0 之间的小数（温度、阈值）
+fn parse_unit_interval(value: &str, name: &str) -> Result<f32, String> {
+    value.parse::<f32>().ok()
+        .filter(|v| (0.0..=1.0).contains(v))
+        .ok_or_else(|| format!("Invalid {}: {} (expected 0.0 to 1.0)", name, value))
+}
+
+fn parse_positive(value: &str, name: &str) -> Result<usize, String> {
+    value.parse::<usize>().ok()
+        .filter(|n| *n >= 1)
+        .ok_or_else(|| format!("Invalid {}: {} (must be at least 1)", name, value))
+}
+
+fn non_empty(value: &str) -> Option<String> {
+    Some(value.trim().to_string()).filter(|v| !v.is_empty())
+}
+
+impl Settings {
+    /// 自动翻译模式下这次识别结果要翻译成的语言；未开启、没有主语言或已经是主语言时返回 None
+    pub fn auto_translate_target(&self, detected_language: Option<&str>) -> Option<String> {
+        if !self.auto_translate_foreign {
+            return None;
+        }
+        let primary = self.primary_language.as_deref()?;
+        crate::voice_assistant::translate::is_foreign_language(detected_language?, primary).then(|| primary.to_string())
+    }
+
+    /// 识别结果是否应按"没有听到语音"丢弃；不提供可信度的处理器（云端）从不过滤
+    pub fn is_no_speech(&self, confidence: Option<AsrConfidence>) -> bool {
+        confidence.is_some_and(|c| c.no_speech_prob > self.no_speech_filter_threshold)
+    }
+
+    /// 录音中的语音时长不足，按空录音跳过识别
+    pub fn is_empty_recording(&self, speech: std::time::Duration) -> bool {
+        self.speech_precheck_enabled && speech < std::time::Duration::from_millis(self.speech_precheck_min_ms)
+    }
+
+    /// 内置默认值，旧版本使用的环境变量（CONVERT_TO_SIMPLIFIED、WHISPER_BACKEND 等）可以覆盖
+    pub fn from_env() -> Self {
+        let mut settings = Self::default();
+        let env_overrides = [
+            (SERVICE_PLATFORM, "SERVICE_PLATFORM"),
+            (CONVERT_TO_SIMPLIFIED, "CONVERT_TO_SIMPLIFIED"),
+            (ADD_SYMBOL, "ADD_SYMBOL"),
+            (OPTIMIZE_RESULT, "OPTIMIZE_RESULT"),
+            (OPTIMIZE_TIMEOUT_MS, "OPTIMIZE_TIMEOUT_MS"),
+            (WHISPER_ENABLE_VAD, "WHISPER_ENABLE_VAD"),
+            (WHISPER_BACKEND, "WHISPER_BACKEND"),
+            (WHISPER_GPU_DEVICE_ID, "WHISPER_GPU_DEVICE"),
+            (WHISPER_MODEL_PATH, "WHISPER_MODEL_PATH"),
+            (GROQ_API_ENDPOINT, "GROQ_API_ENDPOINT"),
+        ];
+        for (key, env) in env_overrides {
+            if let Some(value) = std::env::var(env).ok().as_deref().and_then(non_empty) {
+                if let Err(e) = settings.apply(key, &value) {
+                    warn!("⚠️ Ignoring {}: {}", env, e);
+                }
+            }
+        }
+        settings
+    }
+
+    /// 在默认值上叠加数据库中保存的设置，无效的值跳过
+    pub fn with_saved(mut self, saved: &[AppSetting]) -> Self {
+        for setting in saved {
+            if let Err(e) = self.apply(&setting.key, &setting.value) {
+                warn!("⚠️ Ignoring saved setting {}: {}", setting.key, e);
+            }
+        }
+        self
+    }
+
+    /// 解析并设置一项，返回规范化后要保存的值
+    pub fn apply(&mut self, key: &str, value: &str) -> Result<String, String> {
+        let value = value.trim();
+        match key {
+            SERVICE_PLATFORM => {
+                let platform = value.to_ascii_lowercase();
+                if !SERVICE_PLATFORMS.contains(&platform.as_str()) {
+                    return Err(format!("Unknown service platform '{}'. Valid options: {:?}", value, SERVICE_PLATFORMS));
+                }
+                self.service_platform = platform.clone();
+                Ok(platform)
+            }
+            CONVERT_TO_SIMPLIFIED => {
+                self.convert_to_simplified = parse_bool(value)?;
+                Ok(self.convert_to_simplified.to_string())
+            }
+            ADD_SYMBOL => {
+                self.add_symbol = parse_bool(value)?;
+                Ok(self.add_symbol.to_string())
+            }
+            OPTIMIZE_RESULT => {
+                self.optimize_result = parse_bool(value)?;
+                Ok(self.optimize_result.to_string())
+            }
+            OPTIMIZE_TIMEOUT_MS => {
+                self.optimize_timeout_ms = value.parse::<u64>()
+                    .map_err(|_| format!("Invalid optimize timeout: {}", value))?;
+                Ok(self.optimize_timeout_ms.to_string())
+            }
+            WHISPER_ENABLE_VAD => {
+                self.whisper_enable_vad = parse_bool(value)?;
+                Ok(self.whisper_enable_vad.to_string())
+            }
+            WHISPER_BACKEND => {
+                let backend = WhisperBackend::parse(value)
+                    .ok_or_else(|| format!("Invalid backend '{}'. Valid options: [\"CUDA\", \"Vulkan\", \"Metal\", \"CPU\", \"OpenCL\"]", value))?;
+                self.whisper_backend = Some(backend.to_string());
+                Ok(backend.to_string())
+            }
+            WHISPER_GPU_DEVICE_ID => {
+                let device = value.parse::<u32>().map_err(|_| format!("Invalid GPU device id: {}", value))?;
+                self.whisper_gpu_device_id = Some(device);
+                Ok(device.to_string())
+            }
+            WHISPER_MODEL_PATH => {
+                self.whisper_model_path = non_empty(value);
+                Ok(value.to_string())
+            }
+            GROQ_API_ENDPOINT => {
+                self.groq_api_endpoint = non_empty(value);
+                Ok(value.to_string())
+            }
+            WHISPER_DECODING_STRATEGY => {
+                let strategy = DecodingStrategy::parse(value)
+                    .ok_or_else(|| format!("Invalid decoding strategy '{}'. Valid options: [\"greedy\", \"beam\"]", value))?;
+                self.whisper_decoding.strategy = Some(strategy);
+                Ok(strategy.as_str().to_string())
+            }
+            WHISPER_BEAM_SIZE => {
+                let beam_size = value.parse::<u32>().ok().filter(|n| *n >= 1)
+                    .ok_or_else(|| format!("Invalid beam size: {} (must be at least 1)", value))?;
+                self.whisper_decoding.beam_size = beam_size;
+                Ok(beam_size.to_string())
+            }
+            WHISPER_TEMPERATURE => {
+                let temperature = parse_unit_interval(value, "temperature")?;
+                self.whisper_decoding.temperature = Some(temperature);
+                Ok(temperature.to_string())
+            }
+            WHISPER_NO_SPEECH_THRESHOLD => {
+                let threshold = parse_unit_interval(value, "no-speech threshold")?;
+                self.whisper_decoding.no_speech_threshold = Some(threshold);
+                Ok(threshold.to_string())
+            }
+            NO_SPEECH_FILTER_THRESHOLD => {
+                self.no_speech_filter_threshold = parse_unit_interval(value, "no-speech filter threshold")?;
+                Ok(self.no_speech_filter_threshold.to_string())
+            }
+            WHISPER_MIN_SEGMENT_CONFIDENCE => {
+                self.whisper_min_segment_confidence = parse_unit_interval(value, "minimum segment confidence")?;
+                Ok(self.whisper_min_segment_confidence.to_string())
+            }
+            ASR_RACE_MODE => {
+                self.asr_race_mode = parse_bool(value)?;
+                Ok(self.asr_race_mode.to_string())
+            }
+            SPEECH_PRECHECK_ENABLED => {
+                self.speech_precheck_enabled = parse_bool(value)?;
+                Ok(self.speech_precheck_enabled.to_string())
+            }
+            SPEECH_PRECHECK_MIN_MS => {
+                self.speech_precheck_min_ms = value.trim().parse::<u64>()
+                    .map_err(|_| format!("Invalid minimum speech duration: {}", value))?;
+                Ok(self.speech_precheck_min_ms.to_string())
+            }
+            DEFAULT_PROMPT => {
+                if value.chars().count() > MAX_PROMPT_CHARS {
+                    return Err(format!("Prompt is too long (max {} characters)", MAX_PROMPT_CHARS));
+                }
+                self.default_prompt = non_empty(value);
+                Ok(value.to_string())
+            }
+            AUTO_TRANSLATE_FOREIGN => {
+                self.auto_translate_foreign = parse_bool(value)?;
+                Ok(self.auto_translate_foreign.to_string())
+            }
+            PRIMARY_LANGUAGE => {
+                self.primary_language = crate::voice_assistant::translate::normalize_language(Some(value));
+                Ok(self.primary_language.clone().unwrap_or_default())
+            }
+            HTTP_API_ENABLED => {
+                self.http_api_enabled = parse_bool(value)?;
+                Ok(self.http_api_enabled.to_string())
+            }
+            HTTP_API_PORT => {
+                let port = value.parse::<u16>().ok().filter(|p| *p != 0)
+                    .ok_or_else(|| format!("Invalid port: {}", value))?;
+                self.http_api_port = port;
+                Ok(port.to_string())
+            }
+            HTTP_API_TOKEN => {
+                self.http_api_token = non_empty(value);
+                Ok(value.to_string())
+            }
+            OUTPUT_STRIP_CONTROL_CHARS => {
+                self.output_sanitizer.strip_control_chars = parse_bool(value)?;
+                Ok(self.output_sanitizer.strip_control_chars.to_string())
+            }
+            OUTPUT_COLLAPSE_REPETITION => {
+                self.output_sanitizer.collapse_repetition = parse_bool(value)?;
+                Ok(self.output_sanitizer.collapse_repetition.to_string())
+            }
+            OUTPUT_MAX_SENTENCE_REPEATS => {
+                self.output_sanitizer.max_sentence_repeats = parse_positive(value, "sentence repeat limit")?;
+                Ok(self.output_sanitizer.max_sentence_repeats.to_string())
+            }
+            OUTPUT_SUPPRESS_HALLUCINATIONS => {
+                self.output_sanitizer.suppress_hallucinations = parse_bool(value)?;
+                Ok(self.output_sanitizer.suppress_hallucinations.to_string())
+            }
+            OUTPUT_CAP_LENGTH => {
+                self.output_sanitizer.cap_output_length = parse_bool(value)?;
+                Ok(self.output_sanitizer.cap_output_length.to_string())
+            }
+            OUTPUT_MAX_CHARS => {
+                self.output_sanitizer.max_output_chars = parse_positive(value, "output length limit")?;
+                Ok(self.output_sanitizer.max_output_chars.to_string())
+            }
+            _ => Err(format!("Unknown setting '{}'. Valid keys: {:?}", key, SETTING_KEYS)),
+        }
+    }
+
+    pub fn whisper_backend(&self) -> Option<WhisperBackend> {
+        self.whisper_backend.as_deref().and_then(WhisperBackend::parse)
+    }
+}
+
+// 全局设置缓存，启动时和刷新配置时从数据库加载
+static SETTINGS: OnceLock<Arc<RwLock<Settings>>> = OnceLock::new();
+
+fn settings() -> &'static Arc<RwLock<Settings>> {
+    SETTINGS.get_or_init(|| Arc::new(RwLock::new(Settings::from_env())))
+}
+
+pub fn current() -> Settings {
+    settings().read().unwrap().clone()
+}
+
+/// 只修改内存中的设置，不写数据库（如从 ASR 配置同步的 VAD 开关）
+pub fn update(f: impl FnOnce(&mut Settings)) {
+    f(&mut settings().write().unwrap());
+}
+
+pub fn set_settings(new_settings: Settings) {
+    *settings().write().unwrap() = new_settings;
+}
+
+/// 从数据库重新加载设置
+pub async fn reload_settings() -> Result<Settings, String> {
+    let saved = crate::commands::get_app_settings_internal().await?;
+    let loaded = Settings::from_env().with_saved(&saved);
+    set_settings(loaded.clone());
+    Ok(loaded)
+}
+
+pub async fn load_from(database: &Database) -> Result<Settings, String> {
+    let saved = database.get_app_settings().await
+        .map_err(|e| format!("Failed to get app settings: {}", e))?;
+    let loaded = Settings::from_env().with_saved(&saved);
+    set_settings(loaded.clone());
+    Ok(loaded)
+}
+
+/// 校验并保存一项设置后刷新缓存；空值删除已保存的值，恢复为默认
+pub async fn save(database: &Database, key: &str, value: &str) -> Result<Settings, String> {
+    if !SETTING_KEYS.contains(&key) {
+        return Err(format!("Unknown setting '{}'. Valid keys: {:?}", key, SETTING_KEYS));
+    }
+
+    if value.trim().is_empty() {
+        database.delete_app_setting(key).await
+            .map_err(|e| format!("Failed to reset setting {}: {}", key, e))?;
+    } else {
+        let normalized = current().apply(key, value)?;
+        database.set_app_setting(key, &normalized).await
+            .map_err(|e| format!("Failed to save setting {}: {}", key, e))?;
+    }
+
+    load_from(database).await
+}
+
+/// 没有数据库句柄的地方（模型管理、全局 whisper）使用：通过全局连接池保存，重新加载设置后仍然有效
+pub async fn persist(key: &str, value: &str) -> Result<Settings, String> {
+    let database = Database::from_global_pool().await
+        .map_err(|e| format!("Failed to open database: {}", e))?;
+    save(&database, key, value).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+
+    fn saved(key: &str, value: &str) -> AppSetting {
+        AppSetting {
+            key: key.to_string(),
+            value: value.to_string(),
+            updated_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_saved_settings_override_defaults() {
+        let settings = Settings::default().with_saved(&[
+            saved(SERVICE_PLATFORM, "Groq"),
+            saved(ADD_SYMBOL, "false"),
+            saved(WHISPER_BACKEND, "cuda"),
+            saved(WHISPER_GPU_DEVICE_ID, "1"),
+            saved(GROQ_API_ENDPOINT, " "),
+            saved(OPTIMIZE_TIMEOUT_MS, "1500"),
+        ]);
+
+        assert_eq!(settings.service_platform, "groq");
+        assert!(!settings.add_symbol);
+        assert!(settings.convert_to_simplified);
+        assert_eq!(settings.whisper_backend(), Some(WhisperBackend::CUDA));
+        assert_eq!(settings.whisper_gpu_device_id, Some(1));
+        assert_eq!(settings.groq_api_endpoint, None);
+        assert_eq!(settings.optimize_timeout_ms, 1500);
+        assert_eq!(settings.whisper_decoding.beam_size, DEFAULT_BEAM_SIZE);
+    }
+
+    #[test]
+    fn test_auto_translate_target() {
+        let mut settings = Settings::default().with_saved(&[saved(PRIMARY_LANGUAGE, " ZH-TW ")]);
+        assert_eq!(settings.primary_language.as_deref(), Some("zh-tw"));
+        // 未开启时不翻译
+        assert_eq!(settings.auto_translate_target(Some("en")), None);
+
+        settings.apply(AUTO_TRANSLATE_FOREIGN, "true").unwrap();
+        assert_eq!(settings.auto_translate_target(Some("en")), Some("zh-tw".to_string()));
+        // whisper 只报告 "zh"，不区分简繁，视为主语言
+        assert_eq!(settings.auto_translate_target(Some("zh")), None);
+        // 检测不到语言（云端处理器）时不翻译
+        assert_eq!(settings.auto_translate_target(None), None);
+
+        settings.apply(PRIMARY_LANGUAGE, "").unwrap();
+        assert_eq!(settings.auto_translate_target(Some("en")), None);
+    }
+
+    #[test]
+    fn test_whisper_decoding_settings() {
+        let settings = Settings::default().with_saved(&[
+            saved(WHISPER_DECODING_STRATEGY, "Beam"),
+            saved(WHISPER_BEAM_SIZE, "3"),
+            saved(WHISPER_TEMPERATURE, "0.4"),
+            saved(WHISPER_NO_SPEECH_THRESHOLD, "0.6"),
+        ]);
+        let decoding = settings.whisper_decoding;
+        assert_eq!(decoding.strategy, Some(DecodingStrategy::Beam));
+        assert_eq!(decoding.temperature, Some(0.4));
+        assert_eq!(decoding.no_speech_threshold, Some(0.6));
+        assert!(matches!(
+            decoding.sampling_strategy(&crate::voice_assistant::asr::whisper_rs::SamplingStrategyConfig::Greedy { best_of: 1 }),
+            crate::voice_assistant::asr::whisper_rs::SamplingStrategyConfig::Beam { beam_size: 3, .. }
+        ));
+    }
+
+    #[test]
+    fn test_no_speech_filter() {
+        let heard = |no_speech_prob| Some(AsrConfidence { confidence: 0.9, no_speech_prob });
+        let mut settings = Settings::default();
+        assert!(settings.is_no_speech(heard(0.95)));
+        assert!(!settings.is_no_speech(heard(0.3)));
+        // 云端处理器没有可信度，不过滤
+        assert!(!settings.is_no_speech(None));
+
+        settings.apply(NO_SPEECH_FILTER_THRESHOLD, "1").unwrap();
+        assert!(!settings.is_no_speech(heard(1.0)));
+        assert!(settings.apply(NO_SPEECH_FILTER_THRESHOLD, "2").is_err());
+    }
+
+    #[test]
+    fn test_speech_precheck() {
+        let mut settings = Settings::default();
+        assert!(settings.is_empty_recording(std::time::Duration::from_millis(120)));
+        assert!(!settings.is_empty_recording(std::time::Duration::from_millis(300)));
+
+        settings.apply(SPEECH_PRECHECK_MIN_MS, "50").unwrap();
+        assert!(!settings.is_empty_recording(std::time::Duration::from_millis(120)));
+        assert!(settings.apply(SPEECH_PRECHECK_MIN_MS, "-1").is_err());
+
+        settings.apply(SPEECH_PRECHECK_ENABLED, "false").unwrap();
+        assert!(!settings.is_empty_recording(std::time::Duration::ZERO));
+    }
+
+    #[test]
+    fn test_output_sanitizer_settings() {
+        let settings = Settings::default().with_saved(&[
+            saved(OUTPUT_SUPPRESS_HALLUCINATIONS, "off"),
+            saved(OUTPUT_MAX_SENTENCE_REPEATS, "2"),
+            saved(OUTPUT_MAX_CHARS, "800"),
+        ]);
+        let options = settings.output_sanitizer;
+        assert!(!options.suppress_hallucinations);
+        assert!(options.strip_control_chars && options.collapse_repetition && options.cap_output_length);
+        assert_eq!(options.max_sentence_repeats, 2);
+        assert_eq!(options.max_output_chars, 800);
+    }
+
+    #[test]
+    fn test_invalid_values_are_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.apply(ADD_SYMBOL, "maybe").is_err());
+        assert!(settings.apply(WHISPER_BACKEND, "tpu").is_err());
+        assert!(settings.apply(SERVICE_PLATFORM, "").is_err());
+        assert!(settings.apply("unknown_key", "1").is_err());
+        assert!(settings.apply(HTTP_API_PORT, "0").is_err());
+        assert!(settings.apply(WHISPER_BEAM_SIZE, "0").is_err());
+        assert!(settings.apply(WHISPER_TEMPERATURE, "1.5").is_err());
+        assert!(settings.apply(WHISPER_TEMPERATURE, "NaN").is_err());
+        assert!(settings.apply(WHISPER_DECODING_STRATEGY, "sampling").is_err());
+        assert!(settings.apply(HTTP_API_PORT, "70000").is_err());
+        assert!(settings.apply(OUTPUT_MAX_CHARS, "0").is_err());
+        assert!(settings.apply(OUTPUT_MAX_SENTENCE_REPEATS, "-2").is_err());
+        assert!(settings.apply(OPTIMIZE_TIMEOUT_MS, "-1").is_err());
+        assert_eq!(settings, Settings::default());
+
+        // 无效的已保存值被跳过，不影响其他项
+        let settings = Settings::default().with_saved(&[
+            saved(WHISPER_GPU_DEVICE_ID, "-1"),
+            saved(WHISPER_ENABLE_VAD, "on"),
+        ]);
+        assert_eq!(settings.whisper_gpu_device_id, None);
+        assert!(settings.whisper_enable_vad);
+    }
+}
@@ -0,0 +1,306 @@
+use std::time::{Duration, Instant};
+use thiserror::Error;
+use crate::voice_assistant::InputState;
+
+/// 防误触阈值的默认值（与 hotkey_configs.trigger_delay_ms 的默认值一致），按住时间短于阈值不触发录音
+pub const DEFAULT_TRIGGER_DELAY: Duration = Duration::from_millis(300);
+
+/// 防误触判断：关闭防误触时立即触发，否则按住时间达到配置的 trigger_delay 才触发
+pub fn should_trigger(elapsed: Duration, trigger_delay: Duration, anti_mistouch_enabled: bool) -> bool {
+    !anti_mistouch_enabled || elapsed >= trigger_delay
+}
+
+/// 不合法的状态转换，状态保持不变
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("Illegal state transition {from:?} -> {to:?}")]
+pub struct IllegalTransition {
+    pub from: InputState,
+    pub to: InputState,
+}
+
+/// 一次成功的状态转换，`to` 即需要发给前端的状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Transition {
+    pub from: InputState,
+    pub to: InputState,
+}
+
+impl Transition {
+    pub fn changed(&self) -> bool {
+        self.from != self.to
+    }
+}
+
+/// 录音/识别状态机：所有状态修改都经过这里，并在这里校验是否合法
+#[derive(Debug)]
+pub struct StateMachine {
+    state: InputState,
+    /// 当前录音开始的时间，处理耗时和防误触都从这里算起
+    recording_started_at: Option<Instant>,
+    /// rdev 按住模式下热键首次按下的时间，尚未触发录音
+    hotkey_pressed_at: Option<Instant>,
+    anti_mistouch: bool,
+    hold_threshold: Duration,
+}
+
+impl Default for StateMachine {
+    fn default() -> Self {
+        Self {
+            state: InputState::Idle,
+            recording_started_at: None,
+            hotkey_pressed_at: None,
+            anti_mistouch: true,
+            hold_threshold: DEFAULT_TRIGGER_DELAY,
+        }
+    }
+}
+
+impl StateMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn state(&self) -> InputState {
+        self.state
+    }
+
+    pub fn recording_started_at(&self) -> Option<Instant> {
+        self.recording_started_at
+    }
+
+    pub fn set_anti_mistouch(&mut self, enabled: bool) {
+        self.anti_mistouch = enabled;
+    }
+
+    /// 设置防误触阈值（hotkey_configs.trigger_delay_ms）
+    pub fn set_trigger_delay(&mut self, delay: Duration) {
+        self.hold_threshold = delay;
+    }
+
+    fn transition(&mut self, allowed: bool, to: InputState) -> Result<Transition, IllegalTransition> {
+        let from = self.state;
+        if !allowed {
+            return Err(IllegalTransition { from, to });
+        }
+        self.state = to;
+        Ok(Transition { from, to })
+    }
+
+    /// rdev 按住模式收到热键按下（含自动重复）：按住超过阈值才触发，返回是否应该开始录音
+    pub fn hotkey_down(&mut self, now: Instant) -> bool {
+        // 首次按下时开始计时，阈值为 0 或关闭防误触时立即触发
+        let pressed_at = *self.hotkey_pressed_at.get_or_insert(now);
+        let triggered = should_trigger(now.duration_since(pressed_at), self.hold_threshold, self.anti_mistouch);
+        if triggered {
+            self.hotkey_pressed_at = None;
+        }
+        triggered
+    }
+
+    /// 所有按键都已松开，重新开始防误触计时
+    pub fn hotkey_up(&mut self) {
+        self.hotkey_pressed_at = None;
+    }
+
+    /// Idle 或识别中（排队下一次录音）开始录音
+    pub fn start_recording(&mut self, translate: bool, now: Instant) -> Result<Transition, IllegalTransition> {
+        let to = if translate { InputState::RecordingTranslate } else { InputState::Recording };
+        let allowed = matches!(self.state, InputState::Idle | InputState::Processing | InputState::Translating);
+        let transition = self.transition(allowed, to)?;
+        self.recording_started_at = Some(now);
+        Ok(transition)
+    }
+
+    /// 松开得太早时返回按住的时长，调用方应 `cancel` 丢弃这次录音
+    pub fn released_too_early(&self, now: Instant) -> Option<Duration> {
+        if !self.state.is_recording() {
+            return None;
+        }
+        let held = now.duration_since(self.recording_started_at?);
+        (!should_trigger(held, self.hold_threshold, self.anti_mistouch)).then_some(held)
+    }
+
+    /// 停止录音，进入识别/翻译
+    pub fn stop_to_processing(&mut self) -> Result<Transition, IllegalTransition> {
+        let (allowed, to) = match self.state {
+            InputState::Recording => (true, InputState::Processing),
+            InputState::RecordingTranslate => (true, InputState::Translating),
+            _ => (false, InputState::Processing),
+        };
+        let transition = self.transition(allowed, to)?;
+        self.recording_started_at = None;
+        Ok(transition)
+    }
+
+    /// 识别线程开始处理排队的任务；正在录音时不覆盖录音状态
+    pub fn begin_processing(&mut self, translate: bool) -> Result<Transition, IllegalTransition> {
+        let to = if translate { InputState::Translating } else { InputState::Processing };
+        let allowed = matches!(self.state, InputState::Idle | InputState::Processing | InputState::Translating);
+        self.transition(allowed, to)
+    }
+
+    /// 队列处理完毕，回到 Idle
+    pub fn complete(&mut self) -> Result<Transition, IllegalTransition> {
+        let allowed = matches!(self.state, InputState::Processing | InputState::Translating);
+        self.transition(allowed, InputState::Idle)
+    }
+
+    /// 识别出错；正在进行下一次录音时不打断录音
+    pub fn fail(&mut self) -> Result<Transition, IllegalTransition> {
+        let allowed = !self.state.is_recording();
+        self.transition(allowed, InputState::Error)
+    }
+
+    /// 错误/警告提示结束后回到 Idle
+    pub fn clear_error(&mut self) -> Result<Transition, IllegalTransition> {
+        let allowed = matches!(self.state, InputState::Error | InputState::Warning);
+        self.transition(allowed, InputState::Idle)
+    }
+
+    /// 丢弃当前录音，不做识别
+    pub fn cancel(&mut self) -> Result<Transition, IllegalTransition> {
+        let allowed = self.state.is_recording();
+        let transition = self.transition(allowed, InputState::Idle)?;
+        self.recording_started_at = None;
+        Ok(transition)
+    }
+
+    /// 无条件回到 Idle（停止服务、重置状态）
+    pub fn reset(&mut self) -> Transition {
+        let from = self.state;
+        *self = Self {
+            anti_mistouch: self.anti_mistouch,
+            hold_threshold: self.hold_threshold,
+            ..Self::default()
+        };
+        Transition { from, to: InputState::Idle }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MS: Duration = Duration::from_millis(1);
+
+    #[test]
+    fn test_full_cycle() {
+        let t0 = Instant::now();
+        let mut machine = StateMachine::new();
+        assert_eq!(machine.start_recording(false, t0).unwrap().to, InputState::Recording);
+        assert_eq!(machine.recording_started_at(), Some(t0));
+        assert_eq!(machine.stop_to_processing().unwrap().to, InputState::Processing);
+        assert_eq!(machine.recording_started_at(), None);
+        assert_eq!(machine.complete().unwrap().to, InputState::Idle);
+
+        machine.start_recording(true, t0).unwrap();
+        assert_eq!(machine.stop_to_processing().unwrap().to, InputState::Translating);
+    }
+
+    #[test]
+    fn test_illegal_transitions_keep_state() {
+        let mut machine = StateMachine::new();
+        let err = machine.stop_to_processing().unwrap_err();
+        assert_eq!(err, IllegalTransition { from: InputState::Idle, to: InputState::Processing });
+        assert!(machine.complete().is_err());
+        assert!(machine.cancel().is_err());
+        assert!(machine.clear_error().is_err());
+        assert_eq!(machine.state(), InputState::Idle);
+
+        machine.start_recording(false, Instant::now()).unwrap();
+        assert!(machine.start_recording(true, Instant::now()).is_err());
+        assert!(machine.begin_processing(false).is_err());
+        // 识别线程报错不能打断正在进行的录音
+        assert!(machine.fail().is_err());
+        assert_eq!(machine.state(), InputState::Recording);
+    }
+
+    #[test]
+    fn test_recording_can_start_while_processing() {
+        let now = Instant::now();
+        let mut machine = StateMachine::new();
+        machine.start_recording(false, now).unwrap();
+        machine.stop_to_processing().unwrap();
+        assert_eq!(machine.start_recording(true, now).unwrap().from, InputState::Processing);
+        machine.stop_to_processing().unwrap();
+        // 识别线程开始处理下一条排队任务
+        let transition = machine.begin_processing(false).unwrap();
+        assert_eq!(transition.to, InputState::Processing);
+        assert!(transition.changed());
+        assert!(!machine.begin_processing(false).unwrap().changed());
+    }
+
+    #[test]
+    fn test_error_is_cleared_back_to_idle() {
+        let mut machine = StateMachine::new();
+        machine.begin_processing(false).unwrap();
+        assert_eq!(machine.fail().unwrap().to, InputState::Error);
+        assert!(machine.start_recording(false, Instant::now()).is_err());
+        assert_eq!(machine.clear_error().unwrap().to, InputState::Idle);
+    }
+
+    #[test]
+    fn test_press_release_before_threshold_then_press_again() {
+        let t0 = Instant::now();
+        let mut machine = StateMachine::new();
+
+        // 第一次按下只开始计时，松开后计时清零
+        assert!(!machine.hotkey_down(t0));
+        assert!(!machine.hotkey_down(t0 + 100 * MS));
+        machine.hotkey_up();
+
+        // 再次按下重新计时，不会因为上一次按下而立即触发
+        let t1 = t0 + 400 * MS;
+        assert!(!machine.hotkey_down(t1));
+        assert!(!machine.hotkey_down(t1 + 200 * MS));
+        assert!(machine.hotkey_down(t1 + 300 * MS));
+        assert_eq!(machine.state(), InputState::Idle);
+    }
+
+    #[test]
+    fn test_released_too_early_is_discarded() {
+        let t0 = Instant::now();
+        let mut machine = StateMachine::new();
+        machine.start_recording(false, t0).unwrap();
+        assert_eq!(machine.released_too_early(t0 + 100 * MS), Some(100 * MS));
+        assert_eq!(machine.cancel().unwrap().to, InputState::Idle);
+
+        machine.start_recording(false, t0).unwrap();
+        assert_eq!(machine.released_too_early(t0 + 500 * MS), None);
+
+        // 关闭防误触后立即触发，也不检查按住时长
+        machine.set_anti_mistouch(false);
+        assert_eq!(machine.released_too_early(t0 + MS), None);
+        machine.reset();
+        assert!(machine.hotkey_down(t0));
+    }
+
+    #[test]
+    fn test_should_trigger() {
+        assert!(!should_trigger(100 * MS, 300 * MS, true));
+        assert!(should_trigger(300 * MS, 300 * MS, true));
+        assert!(should_trigger(Duration::ZERO, Duration::ZERO, true));
+        // 关闭防误触时忽略配置的延迟
+        assert!(should_trigger(Duration::ZERO, 300 * MS, false));
+    }
+
+    #[test]
+    fn test_configured_trigger_delay() {
+        let t0 = Instant::now();
+        let mut machine = StateMachine::new();
+        machine.set_trigger_delay(800 * MS);
+        assert!(!machine.hotkey_down(t0));
+        assert!(!machine.hotkey_down(t0 + 500 * MS));
+        assert!(machine.hotkey_down(t0 + 800 * MS));
+
+        // reset 保留配置的延迟
+        machine.reset();
+        machine.start_recording(false, t0).unwrap();
+        assert_eq!(machine.released_too_early(t0 + 500 * MS), Some(500 * MS));
+
+        // 延迟为 0 时第一次按下就触发
+        machine.set_trigger_delay(Duration::ZERO);
+        machine.reset();
+        assert!(machine.hotkey_down(t0));
+    }
+}
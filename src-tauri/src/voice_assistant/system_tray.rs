@@ -1,202 +1,439 @@
-use tauri::{AppHandle, WebviewWindow, tray::TrayIconBuilder, Size, PhysicalSize};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tauri::image::Image;
+use tauri::menu::{CheckMenuItem, IsMenuItem, Menu, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::{MouseButton, MouseButtonState, TrayIcon, TrayIconBuilder, TrayIconEvent};
+use tauri::{AppHandle, Listener, Manager, Wry};
+use tracing::{error, info, warn};
 
-pub struct SystemTrayManager {
-    app_handle: AppHandle,
-    overlay_window: Arc<Mutex<Option<WebviewWindow>>>,
+const TRAY_ID: &str = "voicetype-tray";
+const MENU_TOGGLE_ASSISTANT: &str = "toggle_assistant";
+const MENU_TOGGLE_TRANSCRIPT: &str = "toggle_transcript";
+const MENU_OPEN_SETTINGS: &str = "open_settings";
+const MENU_QUIT: &str = "quit";
+const MODEL_ID_PREFIX: &str = "model:";
+
+/// 处理中旋转动画的帧数和帧间隔
+const SPINNER_FRAMES: usize = 8;
+const SPINNER_INTERVAL: Duration = Duration::from_millis(120);
+
+/// 托盘图标上叠加的状态标记
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrayIndicator {
+    /// 语音助手未运行，图标置灰
+    Stopped,
+    /// 运行中，等待热键
+    Idle,
+    /// 录音中，右下角红点
+    Recording,
+    /// 识别/翻译中，右下角旋转动画的某一帧
+    Processing(usize),
+    Error,
 }
 
-impl SystemTrayManager {
-    pub fn new(app_handle: AppHandle) -> Self {
-        Self {
-            app_handle,
-            overlay_window: Arc::new(Mutex::new(None)),
+impl TrayIndicator {
+    /// 将 voice-assistant-state-changed 事件的内容映射为托盘状态
+    pub fn from_state_event(state: &str, frame: usize) -> Self {
+        match state {
+            "Idle" => TrayIndicator::Stopped,
+            "Recording" | "RecordingTranslate" => TrayIndicator::Recording,
+            "Processing" | "Translating" => TrayIndicator::Processing(frame % SPINNER_FRAMES),
+            "Error" => TrayIndicator::Error,
+            _ => TrayIndicator::Idle,
         }
     }
 
-    pub fn create_tray_icon() -> Result<TrayIconBuilder<tauri::Wry>, Box<dyn std::error::Error>> {
-        let tray = TrayIconBuilder::new()
-            .title("Flash-Input")
-            .tooltip("Flash-Input 语音输入助手");
-
-        Ok(tray)
-    }
-
-    pub fn create_overlay_window(&self) -> Result<WebviewWindow, Box<dyn std::error::Error>> {
-        // Always use a fresh label to avoid conflicts
-        let timestamp = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_secs();
-        let window_label = format!("overlay_{}", timestamp);
-
-        println!("🔄 Creating new overlay window with label: {}", window_label);
-
-        // Create overlay window with transparent background and always on top
-        let window = tauri::WebviewWindowBuilder::new(
-            &self.app_handle,
-            window_label,
-            tauri::WebviewUrl::App("/overlay.html".into())
-        )
-        .title("麦克风悬浮窗")
-        .transparent(true)
-        .decorations(false)
-        .always_on_top(true)
-        .skip_taskbar(true)
-        .visible(true)
-        .inner_size(200.0, 200.0)
-        .resizable(false)
-        .shadow(false)
-        .build()?;
-
-        // Store reference to overlay window
-        let mut overlay = self.overlay_window.lock().unwrap();
-        *overlay = Some(window.clone());
-
-        // Set window position and size with retry logic
-        let window_clone = window.clone();
-        let screen_width = 1920;
-        let screen_height = 1280;
-        let margin_bottom = 80;
-
-        let bottom_x = screen_width / 2; // 960 (center horizontally)
-        let bottom_y = screen_height - margin_bottom; // 1280 - 80 = 1200
-        let target_x = bottom_x - 100; // 860 (center 200px window)
-        let target_y = bottom_y - 100; // 1100 (position 200px window)
-
-        println!("  - Attempting to set window to 200x200 at position ({}, {})", target_x, target_y);
-
-        // Try multiple times to set size and position
-        for attempt in 1..=3 {
-            std::thread::sleep(std::time::Duration::from_millis(100));
-
-            // Force set size
-            if let Ok(_) = window_clone.set_size(Size::Physical(PhysicalSize {
-                width: 200,
-                height: 200,
-            })) {
-                println!("  - Size set attempt {}: OK", attempt);
-            } else {
-                println!("  - Size set attempt {}: FAILED", attempt);
+    fn tooltip(&self) -> &'static str {
+        match self {
+            TrayIndicator::Stopped => "VoiceType - 已停止",
+            TrayIndicator::Idle => "VoiceType - 运行中",
+            TrayIndicator::Recording => "VoiceType - 录音中",
+            TrayIndicator::Processing(_) => "VoiceType - 识别中",
+            TrayIndicator::Error => "VoiceType - 出错",
+        }
+    }
+}
+
+fn blend_pixel(rgba: &mut [u8], width: u32, x: i64, y: i64, color: [u8; 4]) {
+    if x < 0 || y < 0 || x >= width as i64 {
+        return;
+    }
+    let idx = ((y as usize) * width as usize + x as usize) * 4;
+    if idx + 4 > rgba.len() {
+        return;
+    }
+    let alpha = color[3] as u32;
+    for c in 0..3 {
+        rgba[idx + c] = ((color[c] as u32 * alpha + rgba[idx + c] as u32 * (255 - alpha)) / 255) as u8;
+    }
+    rgba[idx + 3] = rgba[idx + 3].max(color[3]);
+}
+
+fn fill_circle(rgba: &mut [u8], width: u32, cx: f32, cy: f32, radius: f32, color: [u8; 4]) {
+    let r = radius.ceil() as i64;
+    for dy in -r..=r {
+        for dx in -r..=r {
+            let (px, py) = (cx + dx as f32, cy + dy as f32);
+            if (px - cx).powi(2) + (py - cy).powi(2) <= radius * radius {
+                blend_pixel(rgba, width, px.round() as i64, py.round() as i64, color);
             }
+        }
+    }
+}
+
+/// 在基础图标上绘制状态标记，返回新的 RGBA 数据
+pub fn render_tray_icon(base: &[u8], width: u32, height: u32, indicator: TrayIndicator) -> Vec<u8> {
+    let mut rgba = base.to_vec();
+    let size = width.min(height) as f32;
+    // 标记放在右下角，占图标约一半
+    let radius = size / 4.0;
+    let (cx, cy) = (width as f32 - radius - 1.0, height as f32 - radius - 1.0);
 
-            // Force set position
-            if let Ok(_) = window_clone.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                x: target_x,
-                y: target_y,
-            })) {
-                println!("  - Position set attempt {}: OK", attempt);
+    match indicator {
+        TrayIndicator::Idle => {}
+        TrayIndicator::Stopped => {
+            for px in rgba.chunks_exact_mut(4) {
+                let gray = ((px[0] as u32 * 30 + px[1] as u32 * 59 + px[2] as u32 * 11) / 100) as u8;
+                px[0] = gray;
+                px[1] = gray;
+                px[2] = gray;
+                px[3] = (px[3] as u32 * 3 / 5) as u8;
+            }
+        }
+        TrayIndicator::Recording | TrayIndicator::Error => {
+            let color = if indicator == TrayIndicator::Recording {
+                [230, 40, 40, 255]
             } else {
-                println!("  - Position set attempt {}: FAILED", attempt);
+                [245, 160, 20, 255]
+            };
+            fill_circle(&mut rgba, width, cx, cy, radius, [255, 255, 255, 255]);
+            fill_circle(&mut rgba, width, cx, cy, (radius - size / 32.0).max(1.0), color);
+        }
+        TrayIndicator::Processing(frame) => {
+            let dot_radius = (size / 16.0).max(1.0);
+            let orbit = radius - dot_radius;
+            for i in 0..SPINNER_FRAMES {
+                let angle = i as f32 / SPINNER_FRAMES as f32 * std::f32::consts::TAU;
+                let (dx, dy) = (orbit * angle.sin(), -orbit * angle.cos());
+                let color = if i == frame % SPINNER_FRAMES {
+                    [40, 120, 240, 255]
+                } else {
+                    [160, 160, 160, 200]
+                };
+                fill_circle(&mut rgba, width, cx + dx, cy + dy, dot_radius, color);
             }
         }
+    }
 
-        // Debug: Print final window info
-        println!("🔍 Final window state after corrections:");
-        if let Ok(is_visible) = window.is_visible() {
-            println!("  - Window visible: {}", is_visible);
-        }
+    rgba
+}
 
-        if let Ok(position) = window.outer_position() {
-            println!("  - Final window position: {:?}", position);
-        }
+/// 系统托盘：图标随 InputState 变化，菜单提供启动/停止、会话记录、模型切换、打开设置和退出
+pub struct SystemTrayManager {
+    app_handle: AppHandle,
+    base_icon: Option<Image<'static>>,
+    // 语音助手是否在运行，决定菜单显示"启动"还是"停止"
+    running: Mutex<bool>,
+    // 每次状态变化递增，旧的动画线程据此退出
+    spinner_generation: AtomicU64,
+    // 最近切换到的 ASR 配置方案，显示在提示文字中
+    asr_profile: Mutex<Option<String>>,
+}
 
-        if let Ok(size) = window.outer_size() {
-            println!("  - Final window size: {:?}", size);
-        }
+impl SystemTrayManager {
+    pub fn init(app: &AppHandle) -> tauri::Result<Arc<Self>> {
+        let base_icon = app.default_window_icon().map(|icon| icon.clone().to_owned());
 
-        if let Ok(inner_size) = window.inner_size() {
-            println!("  - Final window inner size: {:?}", inner_size);
-        }
+        let manager = Arc::new(Self {
+            app_handle: app.clone(),
+            base_icon,
+            running: Mutex::new(false),
+            spinner_generation: AtomicU64::new(0),
+            asr_profile: Mutex::new(None),
+        });
 
-        println!("  - Target position: ({}, {})", target_x, target_y);
-        println!("  - Target size: 200x200");
-        println!("  - Always on top: true");
-        println!("  - Transparent: true");
+        // 左键切换主窗口，右键弹出菜单
+        let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+            .tooltip(TrayIndicator::Stopped.tooltip())
+            .menu(&manager.build_menu()?)
+            .show_menu_on_left_click(false);
+        if let Some(icon) = manager.icon_for(TrayIndicator::Stopped) {
+            builder = builder.icon(icon);
+        }
 
-        Ok(window)
-    }
+        let menu_manager = manager.clone();
+        let click_manager = manager.clone();
+        builder
+            .on_menu_event(move |_app, event| menu_manager.handle_menu_event(event.id().as_ref()))
+            .on_tray_icon_event(move |_tray, event| {
+                if let TrayIconEvent::Click { button: MouseButton::Left, button_state: MouseButtonState::Up, .. } = event {
+                    click_manager.toggle_main_window();
+                }
+            })
+            .build(app)?;
 
-    pub fn toggle_overlay_window(&self) {
-        let overlay = self.overlay_window.lock().unwrap();
-        
-        if let Some(ref window) = *overlay {
-            if window.is_visible().unwrap_or(false) {
-                let _ = window.hide();
-            } else {
-                self.position_overlay_at_cursor(window);
-                let _ = window.show();
-                let _ = window.set_focus();
+        // 与前端监听同一个状态事件，保证托盘和界面显示一致
+        let state_manager = manager.clone();
+        app.listen("voice-assistant-state-changed", move |event| {
+            match serde_json::from_str::<String>(event.payload()) {
+                Ok(state) => state_manager.apply_state(&state),
+                Err(e) => warn!("⚠️ Tray: unexpected state payload {}: {}", event.payload(), e),
             }
-        } else {
-            // Create overlay window if it doesn't exist
-            drop(overlay); // Release the lock before creating new window
-            if let Ok(window) = self.create_overlay_window() {
-                self.position_overlay_at_cursor(&window);
-                let _ = window.show();
-                let _ = window.set_focus();
+        });
+
+        // 切换 ASR 配置方案后在提示文字中显示新方案
+        let profile_manager = manager.clone();
+        app.listen("asr-profile-changed", move |event| {
+            match serde_json::from_str::<serde_json::Value>(event.payload()) {
+                Ok(payload) => {
+                    let name = payload.get("name").and_then(|v| v.as_str()).map(str::to_string);
+                    info!("🎚️ Tray: ASR profile is now {:?}", name);
+                    *profile_manager.asr_profile.lock().unwrap() = name;
+                    // 只在空闲时能用热键切换，用运行状态刷新提示文字即可
+                    let running = *profile_manager.running.lock().unwrap();
+                    profile_manager.set_indicator(if running { TrayIndicator::Idle } else { TrayIndicator::Stopped });
+                }
+                Err(e) => warn!("⚠️ Tray: unexpected ASR profile payload {}: {}", event.payload(), e),
             }
-        }
+        });
+
+        // 会话记录开始/结束后更新菜单文字
+        let transcript_manager = manager.clone();
+        app.listen("transcript-session-changed", move |_event| transcript_manager.rebuild_menu());
+
+        info!("✅ System tray initialized");
+        Ok(manager)
+    }
+
+    fn tray(&self) -> Option<TrayIcon> {
+        self.app_handle.tray_by_id(TRAY_ID)
+    }
+
+    fn icon_for(&self, indicator: TrayIndicator) -> Option<Image<'static>> {
+        let base = self.base_icon.as_ref()?;
+        let rgba = render_tray_icon(base.rgba(), base.width(), base.height(), indicator);
+        Some(Image::new_owned(rgba, base.width(), base.height()))
     }
 
-    fn position_overlay_at_cursor(&self, window: &WebviewWindow) {
-        // Get current cursor position
-        if let Ok((x, y)) = self.get_cursor_position() {
-            let _ = window.set_position(tauri::Position::Physical(tauri::PhysicalPosition {
-                x: x - 30, // Center the 60x60 window on cursor
-                y: y - 30,
-            }));
+    fn build_menu(&self) -> tauri::Result<Menu<Wry>> {
+        let app = &self.app_handle;
+        let running = *self.running.lock().unwrap();
+
+        let toggle_label = if running { "停止语音助手" } else { "启动语音助手" };
+        let toggle = MenuItem::with_id(app, MENU_TOGGLE_ASSISTANT, toggle_label, true, None::<&str>)?;
+
+        let transcript_label = if crate::voice_assistant::transcript::is_active() { "结束会话记录" } else { "开始会话记录" };
+        let transcript = MenuItem::with_id(app, MENU_TOGGLE_TRANSCRIPT, transcript_label, true, None::<&str>)?;
+
+        let models = crate::commands::scan_whisper_models().unwrap_or_default();
+        let model_items = models
+            .iter()
+            .map(|model| {
+                CheckMenuItem::with_id(
+                    app,
+                    format!("{}{}", MODEL_ID_PREFIX, model.path),
+                    &model.name,
+                    true,
+                    model.is_active,
+                    None::<&str>,
+                )
+            })
+            .collect::<tauri::Result<Vec<_>>>()?;
+        let model_refs: Vec<&dyn IsMenuItem<Wry>> =
+            model_items.iter().map(|item| item as &dyn IsMenuItem<Wry>).collect();
+        let models_menu = Submenu::with_items(app, "Whisper 模型", !model_items.is_empty(), &model_refs)?;
+
+        let open_settings = MenuItem::with_id(app, MENU_OPEN_SETTINGS, "打开设置", true, None::<&str>)?;
+        let quit = MenuItem::with_id(app, MENU_QUIT, "退出", true, None::<&str>)?;
+
+        Menu::with_items(app, &[
+            &toggle,
+            &transcript,
+            &models_menu,
+            &PredefinedMenuItem::separator(app)?,
+            &open_settings,
+            &quit,
+        ])
+    }
+
+    /// 重新生成菜单（运行状态或模型列表变化后）
+    pub fn rebuild_menu(&self) {
+        let Some(tray) = self.tray() else { return };
+        match self.build_menu() {
+            Ok(menu) => {
+                if let Err(e) = tray.set_menu(Some(menu)) {
+                    warn!("⚠️ Tray: failed to update menu: {}", e);
+                }
+            }
+            Err(e) => warn!("⚠️ Tray: failed to build menu: {}", e),
         }
     }
 
-    fn get_cursor_position(&self) -> Result<(i32, i32), Box<dyn std::error::Error>> {
-        // This is a simplified implementation
-        // In a real implementation, you would use platform-specific APIs
-        // to get the current cursor position
-        use std::process::Command;
-        
-        if cfg!(target_os = "linux") {
-            let output = Command::new("xdotool")
-                .args(&["getmouselocation"])
-                .output()?;
-            
-            let output_str = String::from_utf8(output.stdout)?;
-            let parts: Vec<&str> = output_str.split_whitespace().collect();
-            
-            if parts.len() >= 2 {
-                let x: i32 = parts[0].trim_start_matches("x:").parse()?;
-                let y: i32 = parts[1].trim_start_matches("y:").parse()?;
-                return Ok((x, y));
+    fn handle_menu_event(self: &Arc<Self>, id: &str) {
+        match id {
+            MENU_TOGGLE_ASSISTANT => {
+                let running = *self.running.lock().unwrap();
+                let app = self.app_handle.clone();
+                tauri::async_runtime::spawn(async move {
+                    let result = if running {
+                        crate::voice_assistant::coordinator::stop_voice_assistant().await
+                    } else {
+                        crate::voice_assistant::coordinator::start_voice_assistant(app).await
+                    };
+                    if let Err(e) = result {
+                        error!("❌ Tray: {}", e);
+                    }
+                });
+            }
+            MENU_TOGGLE_TRANSCRIPT => {
+                tauri::async_runtime::spawn(async move {
+                    if crate::voice_assistant::transcript::is_active() {
+                        crate::voice_assistant::transcript::end_and_save();
+                    } else if let Err(e) = crate::voice_assistant::transcript::start(false) {
+                        error!("❌ Tray: {}", e);
+                    }
+                });
+            }
+            MENU_OPEN_SETTINGS => self.show_main_window(),
+            MENU_QUIT => {
+                info!("👋 Quit requested from tray");
+                self.app_handle.exit(0);
+            }
+            other => {
+                if let Some(model_path) = other.strip_prefix(MODEL_ID_PREFIX) {
+                    self.select_model(model_path.to_string());
+                }
             }
         }
-        
-        // Fallback to center of screen
-        Ok((800, 600))
     }
 
-    pub fn show_overlay_at_cursor(&self) {
-        let overlay = self.overlay_window.lock().unwrap();
-        
-        if let Some(ref window) = *overlay {
-            self.position_overlay_at_cursor(window);
+    fn show_main_window(&self) {
+        if let Some(window) = self.app_handle.get_webview_window("main") {
             let _ = window.show();
+            let _ = window.unminimize();
             let _ = window.set_focus();
+        }
+    }
+
+    /// 点击托盘图标：窗口可见时隐藏，否则显示并聚焦
+    fn toggle_main_window(&self) {
+        let Some(window) = self.app_handle.get_webview_window("main") else { return };
+        let visible = window.is_visible().unwrap_or(false) && !window.is_minimized().unwrap_or(false);
+        if visible {
+            let _ = window.hide();
         } else {
-            // Create overlay window if it doesn't exist
-            drop(overlay); // Release the lock before creating new window
-            if let Ok(window) = self.create_overlay_window() {
-                self.position_overlay_at_cursor(&window);
-                let _ = window.show();
-                let _ = window.set_focus();
+            self.show_main_window();
+        }
+    }
+
+    /// 与设置页相同的切换路径：设置活动模型，再让运行中的助手重新加载配置
+    fn select_model(self: &Arc<Self>, model_path: String) {
+        let manager = self.clone();
+        tauri::async_runtime::spawn(async move {
+            match crate::commands::set_active_whisper_model(model_path).await {
+                Ok(message) => info!("✅ Tray: {}", message),
+                Err(e) => error!("❌ Tray: {}", e),
+            }
+            if let Err(e) = crate::voice_assistant::coordinator::refresh_running_voice_assistant().await {
+                warn!("⚠️ Tray: failed to reload voice assistant with new model: {}", e);
             }
+            manager.rebuild_menu();
+        });
+    }
+
+    fn apply_state(self: &Arc<Self>, state: &str) {
+        let running_changed = {
+            let mut running = self.running.lock().unwrap();
+            // "Idle" 表示服务已停止，运行中的空闲状态是 "Running"
+            let now_running = state != "Idle";
+            let changed = *running != now_running;
+            *running = now_running;
+            changed
+        };
+
+        // 新状态使旧的动画线程失效
+        let generation = self.spinner_generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let indicator = TrayIndicator::from_state_event(state, 0);
+        self.set_indicator(indicator);
+
+        if matches!(indicator, TrayIndicator::Processing(_)) {
+            self.start_spinner(generation);
+        }
+        if running_changed {
+            self.rebuild_menu();
         }
     }
 
-    pub fn hide_overlay_window(&self) {
-        let overlay = self.overlay_window.lock().unwrap();
-        
-        if let Some(ref window) = *overlay {
-            let _ = window.hide();
+    fn set_indicator(&self, indicator: TrayIndicator) {
+        let Some(tray) = self.tray() else { return };
+        if let Some(icon) = self.icon_for(indicator) {
+            let _ = tray.set_icon(Some(icon));
         }
+        let tooltip = match self.asr_profile.lock().unwrap().as_deref() {
+            Some(profile) => format!("{} · ASR: {}", indicator.tooltip(), profile),
+            None => indicator.tooltip().to_string(),
+        };
+        let _ = tray.set_tooltip(Some(tooltip));
+    }
+
+    fn start_spinner(self: &Arc<Self>, generation: u64) {
+        let manager = self.clone();
+        std::thread::spawn(move || {
+            let mut frame = 0;
+            loop {
+                std::thread::sleep(SPINNER_INTERVAL);
+                if manager.spinner_generation.load(Ordering::SeqCst) != generation {
+                    break;
+                }
+                frame = (frame + 1) % SPINNER_FRAMES;
+                manager.set_indicator(TrayIndicator::Processing(frame));
+            }
+        });
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid_icon(size: u32) -> Vec<u8> {
+        [20u8, 200, 120, 255].repeat((size * size) as usize)
+    }
+
+    fn pixel(rgba: &[u8], width: u32, x: u32, y: u32) -> [u8; 4] {
+        let idx = ((y * width + x) * 4) as usize;
+        [rgba[idx], rgba[idx + 1], rgba[idx + 2], rgba[idx + 3]]
+    }
+
+    #[test]
+    fn test_state_event_mapping() {
+        assert_eq!(TrayIndicator::from_state_event("Idle", 0), TrayIndicator::Stopped);
+        assert_eq!(TrayIndicator::from_state_event("Running", 0), TrayIndicator::Idle);
+        assert_eq!(TrayIndicator::from_state_event("RecordingTranslate", 0), TrayIndicator::Recording);
+        assert_eq!(TrayIndicator::from_state_event("Translating", 9), TrayIndicator::Processing(1));
+        assert_eq!(TrayIndicator::from_state_event("Error", 0), TrayIndicator::Error);
+    }
+
+    #[test]
+    fn test_render_indicators() {
+        let size = 32;
+        let base = solid_icon(size);
+
+        assert_eq!(render_tray_icon(&base, size, size, TrayIndicator::Idle), base);
+
+        let recording = render_tray_icon(&base, size, size, TrayIndicator::Recording);
+        let center = size - size / 4 - 1;
+        assert_eq!(pixel(&recording, size, center, center), [230, 40, 40, 255]);
+        // 左上角不受影响
+        assert_eq!(pixel(&recording, size, 0, 0), [20, 200, 120, 255]);
+
+        let stopped = render_tray_icon(&base, size, size, TrayIndicator::Stopped);
+        let [r, g, b, a] = pixel(&stopped, size, 0, 0);
+        assert!(r == g && g == b && a < 255);
+
+        let frame0 = render_tray_icon(&base, size, size, TrayIndicator::Processing(0));
+        let frame1 = render_tray_icon(&base, size, size, TrayIndicator::Processing(1));
+        assert_ne!(frame0, frame1);
+    }
+}
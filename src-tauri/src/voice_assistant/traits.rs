@@ -1,4 +1,5 @@
 use std::io::Cursor;
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 
 #[derive(Clone, Copy, Debug, PartialEq)]
@@ -19,6 +20,12 @@ pub enum VoiceError {
     PermissionDenied,
     #[error("Too short recording")]
     TooShort,
+    #[error("No speech detected")]
+    NoSpeech,
+    #[error("Timeout: {0}")]
+    Timeout(String),
+    #[error("No whisper model found (searched: {})", .searched.join(", "))]
+    ModelNotFound { searched: Vec<String> },
     #[error("Other: {0}")]
     Other(String),
     #[error("UTF-8 error: {0}")]
@@ -37,15 +44,91 @@ impl From<&str> for VoiceError {
     }
 }
 
+/// 一次识别的可信度：confidence 为 token 概率的几何平均，no_speech_prob 为各段中最小的"无语音"概率
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct AsrConfidence {
+    pub confidence: f32,
+    pub no_speech_prob: f32,
+}
+
+/// 一次识别的结果。可信度和文本一起返回，而不是之后从处理器上读取：全局处理器会被并发调用
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AsrTranscript {
+    pub text: String,
+    /// 不提供概率的处理器（云端 API）为 None，不会被过滤
+    pub confidence: Option<AsrConfidence>,
+    /// 回退链/竞速模式中实际给出结果的处理器；None 表示就是被调用的处理器
+    pub processor_type: Option<String>,
+    /// 这次识别检测到的语言（语言代码，如 "en"），不支持语言检测的处理器为 None
+    pub language: Option<String>,
+    /// 云端请求次数（含重试），写入延迟记录；本地推理为 None
+    pub request_attempts: Option<u32>,
+    /// 竞速模式下的胜出方和两边的耗时，其他处理器为 None
+    pub race: Option<crate::voice_assistant::asr::race::RaceOutcome>,
+}
+
+impl AsrTranscript {
+    pub fn new(text: impl Into<String>, confidence: Option<AsrConfidence>) -> Self {
+        Self { text: text.into(), confidence, ..Default::default() }
+    }
+
+    /// 记录给出结果的处理器，已由内层处理器（如竞速模式）填写的保持不变
+    pub fn attributed_to(mut self, processor: &dyn AsrProcessor) -> Self {
+        if self.processor_type.is_none() {
+            self.processor_type = Some(processor.get_processor_type().to_string());
+        }
+        self
+    }
+
+    /// 写入历史记录的处理器标识
+    pub fn processor_type_or<'a>(&'a self, processor: &'a dyn AsrProcessor) -> &'a str {
+        self.processor_type.as_deref().unwrap_or_else(|| processor.get_processor_type())
+    }
+}
+
+impl From<String> for AsrTranscript {
+    fn from(text: String) -> Self {
+        Self { text, ..Default::default() }
+    }
+}
+
 pub trait AsrProcessor {
     fn process_audio(
         &self,
         audio_buffer: Cursor<Vec<u8>>,
         mode: Mode,
         prompt: &str,
-    ) -> Result<String, VoiceError>;
+    ) -> Result<AsrTranscript, VoiceError>;
 
-    fn get_processor_type(&self) -> Option<&str>;
+    /// 可中止的识别（竞速模式中较慢的一方），cancel 被置位后调用尽快返回错误。
+    /// 不支持中止的处理器忽略标记继续运行，结果被丢弃
+    fn process_audio_cancellable(
+        &self,
+        audio_buffer: Cursor<Vec<u8>>,
+        mode: Mode,
+        prompt: &str,
+        _cancel: &crate::voice_assistant::asr::race::CancelToken,
+    ) -> Result<AsrTranscript, VoiceError> {
+        self.process_audio(audio_buffer, mode, prompt)
+    }
+
+    /// 稳定的处理器标识（"whisper-rs"、"local"、"cloud-groq" 等），写入历史记录和事件
+    fn get_processor_type(&self) -> &str;
+
+    /// 检查处理器是否可以立即使用（模型已加载、服务可达、凭据已配置）。可能阻塞等待网络请求，需在 spawn_blocking 中调用
+    fn health_check(&self) -> Result<(), VoiceError> {
+        Ok(())
+    }
+
+    /// 回退链按顺序包含的处理器，自检时分别测试主处理器和备用处理器；单个处理器返回空
+    fn chain_members(&self) -> Vec<std::sync::Arc<dyn AsrProcessor + Send + Sync>> {
+        Vec::new()
+    }
+
+    /// 本地 whisper 实际使用的后端（请求的 GPU 后端不可用时为 CPU）；不是本地 whisper 时返回 None
+    fn whisper_backend(&self) -> Option<crate::voice_assistant::asr::whisper_rs::WhisperBackend> {
+        None
+    }
 
     /// 显式卸载模型并释放GPU内存
     fn unload(&mut self) {
@@ -54,7 +137,30 @@ pub trait AsrProcessor {
 }
 
 pub trait TranslateProcessor {
-    fn translate(&self, text: &str) -> Result<String, VoiceError>;
+    /// 翻译成指定语言（语言代码，如 "en"、"ja"）
+    fn translate_to(&self, text: &str, target: &str) -> Result<String, VoiceError>;
+
+    /// 配置的默认目标语言
+    fn target_language(&self) -> &str {
+        crate::voice_assistant::translate::DEFAULT_TARGET_LANGUAGE
+    }
+
+    fn translate(&self, text: &str) -> Result<String, VoiceError> {
+        self.translate_to(text, self.target_language())
+    }
+
+    /// 流式翻译：每收到一段译文调用一次 `on_chunk`，返回完整译文。
+    /// 不支持流式的处理器一次性返回整段
+    fn translate_stream(&self, text: &str, on_chunk: &mut dyn FnMut(&str)) -> Result<String, VoiceError> {
+        let translated = self.translate(text)?;
+        on_chunk(&translated);
+        Ok(translated)
+    }
+
+    /// 清理转录文本（去除口头禅、修正明显的识别错误），默认原样返回
+    fn refine(&self, text: &str) -> Result<String, VoiceError> {
+        Ok(text.to_string())
+    }
 }
 
 pub trait KeyboardManagerTrait {
@@ -63,7 +169,7 @@ pub trait KeyboardManagerTrait {
     fn reset_state(&mut self);
 }
 
-#[derive(Clone, Copy, Debug, PartialEq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum InputState {
     Idle,
     Recording,
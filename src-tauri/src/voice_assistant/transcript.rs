@@ -0,0 +1,268 @@
+//! 会话记录：开启后每次听写的结果按时间顺序追加到同一份记录中，结束时作为一条 "session" 历史记录保存
+use std::sync::{Mutex, OnceLock};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// 没有录音时长时（如排队失败的录音）每段字幕至少显示这么久
+const MIN_CUE_MS: i64 = 1000;
+
+/// 会话中的一次听写
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptEntry {
+    /// 开始录音的时间
+    pub recorded_at: DateTime<Utc>,
+    /// 相对会话开始的时间
+    pub offset_ms: i64,
+    pub duration_ms: Option<i64>,
+    pub text: String,
+}
+
+impl TranscriptEntry {
+    fn end_ms(&self) -> i64 {
+        self.offset_ms + self.duration_ms.unwrap_or(0).max(MIN_CUE_MS)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TranscriptSession {
+    pub started_at: DateTime<Utc>,
+    /// 只记录不输入：听写结果不打到光标处
+    pub transcript_only: bool,
+    pub entries: Vec<TranscriptEntry>,
+}
+
+impl TranscriptSession {
+    pub fn new(started_at: DateTime<Utc>, transcript_only: bool) -> Self {
+        Self {
+            started_at,
+            transcript_only,
+            entries: Vec::new(),
+        }
+    }
+
+    pub fn append(&mut self, text: &str, recorded_at: DateTime<Utc>, duration_ms: Option<i64>) {
+        let text = text.trim();
+        if text.is_empty() {
+            return;
+        }
+        let offset_ms = (recorded_at - self.started_at).num_milliseconds().max(0);
+        self.entries.push(TranscriptEntry {
+            recorded_at,
+            offset_ms,
+            duration_ms,
+            text: text.to_string(),
+        });
+    }
+
+    /// 每次听写一段
+    pub fn full_text(&self) -> String {
+        self.entries.iter().map(|entry| entry.text.as_str()).collect::<Vec<_>>().join("\n")
+    }
+
+    pub fn total_audio_ms(&self) -> Option<i64> {
+        let durations: Vec<i64> = self.entries.iter().filter_map(|entry| entry.duration_ms).collect();
+        (!durations.is_empty()).then(|| durations.iter().sum())
+    }
+
+    /// 保存到历史记录的每段时间，历史页面导出 SRT 时读回
+    pub fn segments_json(&self) -> Option<String> {
+        serde_json::to_string(&self.entries).ok()
+    }
+}
+
+/// 读回历史记录中保存的每段时间，旧记录或格式不对时返回 None
+pub fn parse_segments(json: &str) -> Option<Vec<TranscriptEntry>> {
+    serde_json::from_str(json).ok()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TranscriptFormat {
+    Text,
+    Markdown,
+    Srt,
+}
+
+impl TranscriptFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "txt" | "text" => Some(TranscriptFormat::Text),
+            "md" | "markdown" => Some(TranscriptFormat::Markdown),
+            "srt" => Some(TranscriptFormat::Srt),
+            _ => None,
+        }
+    }
+}
+
+pub fn format_srt_timestamp(ms: i64) -> String {
+    format!(
+        "{:02}:{:02}:{:02},{:03}",
+        ms / 3_600_000,
+        (ms % 3_600_000) / 60_000,
+        (ms % 60_000) / 1000,
+        ms % 1000
+    )
+}
+
+/// 按格式输出会话记录：纯文本、带时间的 Markdown、以每次听写为一段字幕的 SRT
+pub fn render(session: &TranscriptSession, format: TranscriptFormat) -> String {
+    match format {
+        TranscriptFormat::Text => format!("{}\n", session.full_text()),
+        TranscriptFormat::Markdown => {
+            let local_start = session.started_at.with_timezone(&chrono::Local);
+            let mut output = format!("# Transcript {}\n", local_start.format("%Y-%m-%d %H:%M"));
+            for entry in &session.entries {
+                let time = entry.recorded_at.with_timezone(&chrono::Local).format("%H:%M:%S");
+                output.push_str(&format!("\n**{}** {}\n", time, entry.text));
+            }
+            output
+        }
+        TranscriptFormat::Srt => render_srt(&session.entries),
+    }
+}
+
+/// 每次听写一段字幕，时间相对会话开始
+pub fn render_srt(entries: &[TranscriptEntry]) -> String {
+    entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            format!(
+                "{}\n{} --> {}\n{}\n",
+                i + 1,
+                format_srt_timestamp(entry.offset_ms),
+                format_srt_timestamp(entry.end_ms()),
+                entry.text
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+// 当前的会话，None 表示没有开启
+static SESSION: OnceLock<Mutex<Option<TranscriptSession>>> = OnceLock::new();
+
+fn session() -> &'static Mutex<Option<TranscriptSession>> {
+    SESSION.get_or_init(|| Mutex::new(None))
+}
+
+pub fn is_active() -> bool {
+    session().lock().unwrap().is_some()
+}
+
+/// 开始新的会话；已有会话时返回错误，避免丢掉未保存的内容
+pub fn start(transcript_only: bool) -> Result<TranscriptSession, String> {
+    let mut current = session().lock().unwrap();
+    if current.is_some() {
+        return Err("A transcript session is already active".to_string());
+    }
+    let started = TranscriptSession::new(Utc::now(), transcript_only);
+    tracing::info!("📝 Transcript session started (transcript only: {})", transcript_only);
+    *current = Some(started.clone());
+    drop(current);
+    crate::voice_assistant::coordinator::emit_transcript_session_changed(Some(&started));
+    Ok(started)
+}
+
+pub fn current() -> Option<TranscriptSession> {
+    session().lock().unwrap().clone()
+}
+
+/// 会话开启时追加一次听写结果，返回是否只记录不输入
+pub fn record(text: &str, recorded_at: DateTime<Utc>, duration_ms: Option<i64>) -> bool {
+    let mut current = session().lock().unwrap();
+    let Some(active) = current.as_mut() else {
+        return false;
+    };
+    active.append(text, recorded_at, duration_ms);
+    active.transcript_only
+}
+
+/// 结束会话并返回其内容，没有会话时返回 None
+pub fn end() -> Option<TranscriptSession> {
+    let ended = session().lock().unwrap().take();
+    if let Some(ref ended_session) = ended {
+        tracing::info!("📝 Transcript session ended ({} entries)", ended_session.entries.len());
+        crate::voice_assistant::coordinator::emit_transcript_session_changed(None);
+    }
+    ended
+}
+
+/// 结束会话并通过写库通道保存为一条 "session" 历史记录；会话为空时不保存
+pub fn end_and_save() -> Option<TranscriptSession> {
+    use crate::voice_assistant::result_sink::{PendingResult, ResultSink};
+
+    let ended = end()?;
+    if !ended.entries.is_empty() {
+        let result = PendingResult::Session {
+            text: ended.full_text(),
+            duration_ms: (Utc::now() - ended.started_at).num_milliseconds(),
+            audio_duration_ms: ended.total_audio_ms(),
+            segments_json: ended.segments_json(),
+        };
+        match ResultSink::global() {
+            Some(sink) => sink.send(result),
+            None => tracing::warn!("⚠️ Result sink is not running, transcript session was not saved"),
+        }
+    }
+    Some(ended)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn session_with_entries() -> TranscriptSession {
+        let start = Utc.with_ymd_and_hms(2024, 5, 1, 8, 30, 0).unwrap();
+        let mut session = TranscriptSession::new(start, false);
+        session.append(" First paragraph. ", start + chrono::Duration::milliseconds(1500), Some(2250));
+        session.append("   ", start + chrono::Duration::seconds(3), Some(500));
+        session.append("Second one.", start + chrono::Duration::seconds(65), None);
+        session
+    }
+
+    #[test]
+    fn test_append_skips_blank_results() {
+        let session = session_with_entries();
+        assert_eq!(session.entries.len(), 2);
+        assert_eq!(session.entries[0].offset_ms, 1500);
+        assert_eq!(session.full_text(), "First paragraph.\nSecond one.");
+        assert_eq!(session.total_audio_ms(), Some(2250));
+    }
+
+    #[test]
+    fn test_render_srt_uses_entry_times() {
+        let srt = render(&session_with_entries(), TranscriptFormat::Srt);
+        assert_eq!(
+            srt,
+            "1\n00:00:01,500 --> 00:00:03,750\nFirst paragraph.\n\n2\n00:01:05,000 --> 00:01:06,000\nSecond one.\n"
+        );
+    }
+
+    #[test]
+    fn test_saved_segments_render_the_same_srt() {
+        let session = session_with_entries();
+        let saved = parse_segments(&session.segments_json().unwrap()).unwrap();
+        assert_eq!(render_srt(&saved), render(&session, TranscriptFormat::Srt));
+        assert!(parse_segments("not json").is_none());
+    }
+
+    #[test]
+    fn test_render_text_and_markdown() {
+        let session = session_with_entries();
+        assert_eq!(render(&session, TranscriptFormat::Text), "First paragraph.\nSecond one.\n");
+
+        let markdown = render(&session, TranscriptFormat::Markdown);
+        assert!(markdown.starts_with("# Transcript "));
+        assert_eq!(markdown.matches("**").count(), 4);
+        assert!(markdown.contains("First paragraph.") && markdown.contains("Second one."));
+    }
+
+    #[test]
+    fn test_format_parse() {
+        assert_eq!(TranscriptFormat::parse("MD"), Some(TranscriptFormat::Markdown));
+        assert_eq!(TranscriptFormat::parse("txt"), Some(TranscriptFormat::Text));
+        assert_eq!(TranscriptFormat::parse("srt"), Some(TranscriptFormat::Srt));
+        assert_eq!(TranscriptFormat::parse("docx"), None);
+    }
+}
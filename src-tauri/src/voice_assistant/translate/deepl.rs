@@ -0,0 +1,222 @@
+use crate::voice_assistant::{TranslateProcessor, VoiceError};
+use crate::voice_assistant::net::{send_with_retry, RetryBudget};
+use serde_json::Value;
+use std::time::Duration;
+use tracing::debug;
+
+const DEEPL_FREE_URL: &str = "https://api-free.deepl.com";
+const DEEPL_PRO_URL: &str = "https://api.deepl.com";
+
+pub struct DeepLTranslateProcessor {
+    client: reqwest::Client,
+    auth_key: String,
+    target_lang: String,
+    source_lang: Option<String>,
+    base_url: String,
+}
+
+impl DeepLTranslateProcessor {
+    pub fn new() -> Result<Self, VoiceError> {
+        Self::with_settings(None, None)
+    }
+
+    /// 使用翻译设置中保存的 key 和服务地址，未保存时回退到 DEEPL_AUTH_KEY/DEEPL_API_URL 环境变量
+    pub fn with_settings(auth_key: Option<&str>, endpoint: Option<&str>) -> Result<Self, VoiceError> {
+        let auth_key = auth_key
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(str::to_string)
+            .or_else(|| std::env::var("DEEPL_AUTH_KEY").ok())
+            .ok_or_else(|| VoiceError::Other("DeepL auth key is not configured".to_string()))?;
+
+        let target_lang = std::env::var("DEEPL_TARGET_LANG")
+            .unwrap_or_else(|_| "EN-US".to_string());
+
+        let base_url = endpoint
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .map(str::to_string)
+            .or_else(|| std::env::var("DEEPL_API_URL").ok())
+            .unwrap_or_else(|| Self::default_base_url(&auth_key).to_string());
+
+        Self::with_config(auth_key, target_lang, base_url)
+    }
+
+    pub fn with_config(auth_key: String, target_lang: String, base_url: String) -> Result<Self, VoiceError> {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(30))
+            .build()
+            .map_err(|e| VoiceError::Network(e))?;
+
+        Ok(Self {
+            client,
+            auth_key,
+            target_lang,
+            source_lang: None,
+            base_url,
+        })
+    }
+
+    /// DeepL Free 的 key 以 ":fx" 结尾，需要使用单独的域名
+    fn default_base_url(auth_key: &str) -> &'static str {
+        if auth_key.ends_with(":fx") {
+            DEEPL_FREE_URL
+        } else {
+            DEEPL_PRO_URL
+        }
+    }
+
+    /// 设置默认目标语言和可选的源语言，通用语言代码会转换成 DeepL 的格式
+    pub fn with_languages(mut self, target_language: &str, source_language: Option<&str>) -> Self {
+        self.target_lang = deepl_target_code(target_language);
+        self.source_lang = source_language.map(deepl_source_code);
+        self
+    }
+
+    /// 构造翻译请求；未配置 source_lang 时由 DeepL 自动检测源语言
+    fn build_request(&self, text: &str, target_lang: &str) -> reqwest::RequestBuilder {
+        let mut params = vec![
+            ("text", text),
+            ("target_lang", target_lang),
+        ];
+        if let Some(source_lang) = self.source_lang.as_deref() {
+            params.push(("source_lang", source_lang));
+        }
+
+        let url = format!("{}/v2/translate", self.base_url.trim_end_matches('/'));
+        self.client
+            .post(url)
+            .header(reqwest::header::AUTHORIZATION, format!("DeepL-Auth-Key {}", self.auth_key))
+            .form(&params)
+    }
+
+    async fn call_api(&self, text: &str, target_lang: &str) -> Result<String, VoiceError> {
+        // 456（额度用完）和 403 属于 4xx，不会重试
+        let response = send_with_retry("DeepL", &RetryBudget::start(), || Ok(self.build_request(text, target_lang)))
+            .await?
+            .response;
+
+        let status = response.status();
+        let body = response.text().await?;
+        parse_response(status, &body)
+    }
+}
+
+/// 解析 DeepL 的响应，额度用完和鉴权失败单独提示
+fn parse_response(status: reqwest::StatusCode, body: &str) -> Result<String, VoiceError> {
+    if status.as_u16() == 456 {
+        return Err(VoiceError::Other(
+            "DeepL quota exceeded: the character limit for this billing period has been reached".to_string()
+        ));
+    }
+    if status.as_u16() == 403 {
+        return Err(VoiceError::Other("DeepL authentication failed: please check your auth key".to_string()));
+    }
+    if !status.is_success() {
+        return Err(VoiceError::Other(format!("DeepL API error: {} - {}", status, body)));
+    }
+
+    let result: Value = serde_json::from_str(body)
+        .map_err(|e| VoiceError::Other(format!("Invalid DeepL response: {}", e)))?;
+
+    let translation = result
+        .get("translations")
+        .and_then(|v| v.as_array())
+        .and_then(|arr| arr.first())
+        .ok_or_else(|| VoiceError::Other("No translation content in DeepL response".to_string()))?;
+
+    if let Some(source) = translation.get("detected_source_language").and_then(|v| v.as_str()) {
+        debug!("🌐 DeepL detected source language: {}", source);
+    }
+
+    translation
+        .get("text")
+        .and_then(|v| v.as_str())
+        .map(|s| s.trim().to_string())
+        .ok_or_else(|| VoiceError::Other("No translation content in DeepL response".to_string()))
+}
+
+/// DeepL 目标语言要求大写，英语和葡萄牙语需要指定地区变体
+fn deepl_target_code(code: &str) -> String {
+    match code.trim().to_uppercase().as_str() {
+        "EN" => "EN-US".to_string(),
+        "PT" => "PT-BR".to_string(),
+        "ZH-TW" => "ZH-HANT".to_string(),
+        other => other.to_string(),
+    }
+}
+
+/// DeepL 源语言不区分地区变体
+fn deepl_source_code(code: &str) -> String {
+    let upper = code.trim().to_uppercase();
+    upper.split('-').next().unwrap_or_default().to_string()
+}
+
+impl TranslateProcessor for DeepLTranslateProcessor {
+    fn translate_to(&self, text: &str, target: &str) -> Result<String, VoiceError> {
+        if text.trim().is_empty() {
+            return Ok(String::new());
+        }
+
+        let target_lang = deepl_target_code(target);
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
+
+        rt.block_on(async {
+            self.call_api(text, &target_lang).await
+        })
+    }
+
+    fn target_language(&self) -> &str {
+        &self.target_lang
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_deepl_language_codes() {
+        assert_eq!(deepl_target_code("en"), "EN-US");
+        assert_eq!(deepl_target_code("EN-GB"), "EN-GB");
+        assert_eq!(deepl_target_code("ja"), "JA");
+        assert_eq!(deepl_target_code("zh-tw"), "ZH-HANT");
+        assert_eq!(deepl_source_code("en-us"), "EN");
+        assert_eq!(deepl_source_code("zh"), "ZH");
+    }
+
+    #[test]
+    fn test_request_uses_saved_credentials_and_languages() {
+        let processor = DeepLTranslateProcessor::with_settings(Some("secret:fx"), None)
+            .unwrap()
+            .with_languages("en", Some("zh-cn"));
+        assert_eq!(processor.base_url, DEEPL_FREE_URL);
+
+        let request = processor.build_request("你好", &processor.target_lang).build().unwrap();
+        assert_eq!(request.url().as_str(), "https://api-free.deepl.com/v2/translate");
+        assert_eq!(request.headers()[reqwest::header::AUTHORIZATION], "DeepL-Auth-Key secret:fx");
+        let body = std::str::from_utf8(request.body().unwrap().as_bytes().unwrap()).unwrap();
+        assert_eq!(body, "text=%E4%BD%A0%E5%A5%BD&target_lang=EN-US&source_lang=ZH");
+
+        // 保存的服务地址优先于按 key 推断的默认地址
+        let processor = DeepLTranslateProcessor::with_settings(Some("secret"), Some("http://127.0.0.1:8080/")).unwrap();
+        let request = processor.build_request("hi", "JA").build().unwrap();
+        assert_eq!(request.url().as_str(), "http://127.0.0.1:8080/v2/translate");
+    }
+
+    #[test]
+    fn test_parse_response() {
+        let ok = r#"{"translations":[{"detected_source_language":"ZH","text":" Hello "}]}"#;
+        assert_eq!(parse_response(reqwest::StatusCode::OK, ok).unwrap(), "Hello");
+
+        let quota = parse_response(reqwest::StatusCode::from_u16(456).unwrap(), "").unwrap_err().to_string();
+        assert!(quota.contains("quota exceeded"));
+        let auth = parse_response(reqwest::StatusCode::FORBIDDEN, "").unwrap_err().to_string();
+        assert!(auth.contains("authentication failed"));
+        let server = parse_response(reqwest::StatusCode::BAD_GATEWAY, "upstream down").unwrap_err().to_string();
+        assert!(server.contains("upstream down"));
+        assert!(parse_response(reqwest::StatusCode::OK, r#"{"translations":[]}"#).is_err());
+        assert!(parse_response(reqwest::StatusCode::OK, "not json").is_err());
+    }
+}
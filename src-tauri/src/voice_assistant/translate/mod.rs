@@ -1,5 +1,140 @@
 pub mod siliconflow;
 pub mod ollama;
+pub mod deepl;
 
 pub use siliconflow::*;
-pub use ollama::*;
\ No newline at end of file
+pub use ollama::*;
+pub use deepl::*;
+use std::future::Future;
+use std::time::{Duration, Instant};
+use crate::voice_assistant::VoiceError;
+use crate::voice_assistant::net::{retry_policy, RetryBudget, RetryPolicy};
+
+/// 未配置目标语言时翻译成英文，与之前的行为一致
+pub const DEFAULT_TARGET_LANGUAGE: &str = "en";
+/// 翻译请求默认超时（秒），服务无响应时不会一直卡在翻译状态
+pub const DEFAULT_TRANSLATE_TIMEOUT_SECS: i64 = 30;
+pub const MAX_TRANSLATE_TIMEOUT_SECS: i64 = 300;
+
+/// 在 `timeout` 内完成一次翻译请求，重试也计入这段时间；
+/// 超时（包括重试预算在此之前用完）返回 `VoiceError::Timeout`
+pub async fn request_with_timeout<F, Fut>(label: &str, timeout: Duration, request: F) -> Result<String, VoiceError>
+where
+    F: FnOnce(RetryBudget) -> Fut,
+    Fut: Future<Output = Result<String, VoiceError>>,
+{
+    let policy = retry_policy();
+    let budget = RetryBudget::new(RetryPolicy {
+        max_retries: policy.max_retries,
+        time_budget: policy.time_budget.min(timeout),
+    });
+    let started = Instant::now();
+    let timed_out = || VoiceError::Timeout(format!("{} did not respond within {}ms", label, started.elapsed().as_millis()));
+
+    match tokio::time::timeout(timeout, request(budget)).await {
+        Ok(Err(VoiceError::Network(e))) if e.is_timeout() => Err(timed_out()),
+        Ok(Err(_)) if budget.remaining().is_zero() => Err(timed_out()),
+        Ok(result) => result,
+        Err(_) => Err(timed_out()),
+    }
+}
+
+/// 常用语言代码对应的英文名称，用于拼接提示词；未收录的代码原样使用
+const LANGUAGE_NAMES: &[(&str, &str)] = &[
+    ("zh", "Simplified Chinese"),
+    ("zh-tw", "Traditional Chinese"),
+    ("en", "English"),
+    ("ja", "Japanese"),
+    ("ko", "Korean"),
+    ("de", "German"),
+    ("fr", "French"),
+    ("es", "Spanish"),
+    ("it", "Italian"),
+    ("pt", "Portuguese"),
+    ("ru", "Russian"),
+];
+
+pub fn language_name(code: &str) -> String {
+    let code = code.trim();
+    LANGUAGE_NAMES
+        .iter()
+        .find(|(c, _)| c.eq_ignore_ascii_case(code))
+        .map(|(_, name)| name.to_string())
+        .unwrap_or_else(|| code.to_string())
+}
+
+/// 空字符串视为未配置
+pub fn normalize_language(code: Option<&str>) -> Option<String> {
+    code.map(str::trim).filter(|c| !c.is_empty()).map(str::to_lowercase)
+}
+
+/// ASR 检测到的语言是否已经是目标语言。目标带地区/文字后缀（如 "zh-tw"）时要求完全一致，
+/// 因为 whisper 只报告 "zh"，不区分简繁
+pub fn is_same_language(detected: &str, target: &str) -> bool {
+    let (Some(detected), Some(target)) = (normalize_language(Some(detected)), normalize_language(Some(target))) else {
+        return false;
+    };
+    if target.contains('-') {
+        return detected == target;
+    }
+    detected.split('-').next() == Some(target.as_str())
+}
+
+/// 自动翻译时判断说的是否是外语：只比较主语言标签，"zh" 和 "zh-tw" 视为同一种语言
+pub fn is_foreign_language(detected: &str, primary: &str) -> bool {
+    let primary = primary.split('-').next().unwrap_or(primary);
+    !primary.trim().is_empty() && !is_same_language(detected, primary)
+}
+
+/// 翻译提示词；源语言未配置时由模型自行判断
+pub fn translate_system_prompt(target: &str, source: Option<&str>) -> String {
+    let target = language_name(target);
+    match source {
+        Some(source) => format!(
+            "You are a translation assistant. The user's input is in {}. Please translate it into {}. Reply with the translation only.",
+            language_name(source),
+            target
+        ),
+        None => format!(
+            "You are a translation assistant. Please translate the user's input into {}. Reply with the translation only.",
+            target
+        ),
+    }
+}
+
+/// 转录文本清理提示词
+pub const REFINE_SYSTEM_PROMPT: &str = "You clean up speech-to-text transcripts. Remove filler words and disfluencies, fix obvious recognition errors and punctuation, and keep the original meaning and language. Reply with the cleaned transcript only.";
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_prompt_uses_configured_languages() {
+        assert!(translate_system_prompt("ja", None).contains("into Japanese"));
+        let prompt = translate_system_prompt("DE", Some("en"));
+        assert!(prompt.contains("in English"));
+        assert!(prompt.contains("into German"));
+        // 未收录的代码原样写进提示词
+        assert!(translate_system_prompt("nl", None).contains("into nl"));
+    }
+
+    #[test]
+    fn test_normalize_language() {
+        assert_eq!(normalize_language(Some(" JA ")), Some("ja".to_string()));
+        assert_eq!(normalize_language(Some("  ")), None);
+        assert_eq!(normalize_language(None), None);
+    }
+
+    #[test]
+    fn test_is_same_language() {
+        assert!(is_same_language("en", "EN"));
+        assert!(is_same_language("zh", "zh"));
+        assert!(!is_same_language("zh", "zh-tw"));
+        assert!(!is_same_language("en", "ja"));
+        assert!(!is_same_language("", "en"));
+        assert!(is_foreign_language("en", "zh-tw"));
+        assert!(!is_foreign_language("zh", "zh-tw"));
+        assert!(!is_foreign_language("en", ""));
+    }
+}
@@ -1,23 +1,112 @@
 use crate::voice_assistant::{TranslateProcessor, VoiceError};
+use crate::voice_assistant::net::{send_with_retry, RetryBudget};
+use super::{request_with_timeout, translate_system_prompt, DEFAULT_TARGET_LANGUAGE, DEFAULT_TRANSLATE_TIMEOUT_SECS, REFINE_SYSTEM_PROMPT};
 use serde_json::{json, Value};
 use std::time::Duration;
 
+const DEFAULT_OLLAMA_URL: &str = "http://192.168.8.107:11434/api/chat";
+const DEFAULT_OLLAMA_MODEL: &str = "gpt-oss:latest";
+
+/// `ollama list` 中的一个模型
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct OllamaModel {
+    pub name: String,
+    pub size: u64,
+    pub modified_at: Option<String>,
+}
+
+/// 服务地址：可以填 "http://host:11434" 也可以填完整的 /api/chat 地址
+pub fn ollama_base_url(endpoint: &str) -> String {
+    let endpoint = endpoint.trim().trim_end_matches('/');
+    endpoint
+        .strip_suffix("/api/chat")
+        .or_else(|| endpoint.strip_suffix("/api/tags"))
+        .unwrap_or(endpoint)
+        .to_string()
+}
+
+/// 没有写 tag 的模型名等同于 ":latest"
+pub fn model_matches(installed: &str, wanted: &str) -> bool {
+    let normalize = |name: &str| {
+        let name = name.trim();
+        if name.contains(':') { name.to_string() } else { format!("{}:latest", name) }
+    };
+    normalize(installed) == normalize(wanted)
+}
+
+pub fn parse_tags_response(body: &Value) -> Vec<OllamaModel> {
+    body.get("models")
+        .and_then(|v| v.as_array())
+        .map(|models| {
+            models
+                .iter()
+                .filter_map(|m| {
+                    Some(OllamaModel {
+                        name: m.get("name").or_else(|| m.get("model"))?.as_str()?.to_string(),
+                        size: m.get("size").and_then(|v| v.as_u64()).unwrap_or(0),
+                        modified_at: m.get("modified_at").and_then(|v| v.as_str()).map(str::to_string),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// 查询 /api/tags 获取已拉取的模型
+pub async fn list_models(endpoint: &str) -> Result<Vec<OllamaModel>, VoiceError> {
+    let url = format!("{}/api/tags", ollama_base_url(endpoint));
+    let client = reqwest::Client::builder()
+        .timeout(Duration::from_secs(5))
+        .build()?;
+
+    let response = client.get(&url).send().await.map_err(|e| {
+        VoiceError::Other(format!("Cannot reach Ollama at {}: {} — is `ollama serve` running?", ollama_base_url(endpoint), e))
+    })?;
+    let status = response.status();
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_default();
+        return Err(VoiceError::Other(format!("Ollama API error: {} - {}", status, error_text)));
+    }
+
+    let body: Value = response.json().await?;
+    Ok(parse_tags_response(&body))
+}
+
+pub fn default_ollama_url() -> String {
+    std::env::var("OLLAMA_URL").unwrap_or_else(|_| DEFAULT_OLLAMA_URL.to_string())
+}
+
+pub fn default_ollama_model() -> String {
+    std::env::var("OLLAMA_MODEL").unwrap_or_else(|_| DEFAULT_OLLAMA_MODEL.to_string())
+}
+
 pub struct OllamaTranslateProcessor {
     client: reqwest::Client,
     url: String,
     model: String,
+    target_language: String,
+    source_language: Option<String>,
+    timeout: Duration,
 }
 
 impl OllamaTranslateProcessor {
     pub fn new() -> Result<Self, VoiceError> {
-        let url = std::env::var("OLLAMA_URL")
-            .unwrap_or_else(|_| "http://192.168.8.107:11434/api/chat".to_string());
+        Self::with_settings(None, None)
+    }
 
-        let model = std::env::var("OLLAMA_MODEL")
-            .unwrap_or_else(|_| "gpt-oss:latest".to_string());
+    /// 使用保存的服务地址和模型，未保存时回退到环境变量/默认值
+    pub fn with_settings(endpoint: Option<&str>, model: Option<&str>) -> Result<Self, VoiceError> {
+        let url = match endpoint {
+            Some(endpoint) => format!("{}/api/chat", ollama_base_url(endpoint)),
+            None => default_ollama_url(),
+        };
+        let model = model.map(str::to_string).unwrap_or_else(default_ollama_model);
+        Self::with_config(url, model)
+    }
 
+    pub fn with_config(url: String, model: String) -> Result<Self, VoiceError> {
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
+            .timeout(Duration::from_secs(DEFAULT_TRANSLATE_TIMEOUT_SECS as u64))
             .default_headers({
                 let mut headers = reqwest::header::HeaderMap::new();
                 headers.insert(
@@ -33,33 +122,53 @@ impl OllamaTranslateProcessor {
             client,
             url,
             model,
+            target_language: DEFAULT_TARGET_LANGUAGE.to_string(),
+            source_language: None,
+            timeout: Duration::from_secs(DEFAULT_TRANSLATE_TIMEOUT_SECS as u64),
         })
     }
 
-    pub fn with_config(url: String, model: String) -> Result<Self, VoiceError> {
-        let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
-            .default_headers({
-                let mut headers = reqwest::header::HeaderMap::new();
-                headers.insert(
-                    reqwest::header::CONTENT_TYPE,
-                    reqwest::header::HeaderValue::from_static("application/json")
-                );
-                headers
-            })
-            .build()
-            .map_err(|e| VoiceError::Network(e))?;
+    /// 单次翻译（含重试和读取流式响应）的最长时间，超时返回 `VoiceError::Timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
 
-        Ok(Self {
-            client,
-            url,
-            model,
+    /// 设置默认目标语言和可选的源语言（语言代码）
+    pub fn with_languages(mut self, target_language: &str, source_language: Option<&str>) -> Self {
+        self.target_language = target_language.to_string();
+        self.source_language = source_language.map(str::to_string);
+        self
+    }
+
+    /// 流式翻译成默认目标语言，每收到一段译文调用一次 `on_chunk`，返回完整译文
+    pub fn translate_stream(&self, text: &str, on_chunk: impl FnMut(&str)) -> Result<String, VoiceError> {
+        self.translate_to_stream(text, &self.target_language, on_chunk)
+    }
+
+    pub fn translate_to_stream(&self, text: &str, target: &str, mut on_chunk: impl FnMut(&str)) -> Result<String, VoiceError> {
+        if text.trim().is_empty() {
+            return Ok(String::new());
+        }
+
+        let system_prompt = translate_system_prompt(target, self.source_language.as_deref());
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
+
+        rt.block_on(async {
+            self.call_api(&system_prompt, text, &mut on_chunk).await
         })
     }
 
-    async fn call_api(&self, text: &str) -> Result<String, VoiceError> {
-        let system_prompt = "You are a translation assistant. Please translate the user's input into English.";
+    async fn call_api(&self, system_prompt: &str, text: &str, on_chunk: &mut dyn FnMut(&str)) -> Result<String, VoiceError> {
+        request_with_timeout("Ollama", self.timeout, move |budget| {
+            self.send_chat(budget, system_prompt, text, on_chunk)
+        })
+        .await
+    }
 
+    /// 以 `stream: true` 请求，逐行解析 Ollama 返回的 NDJSON
+    async fn send_chat(&self, budget: RetryBudget, system_prompt: &str, text: &str, on_chunk: &mut dyn FnMut(&str)) -> Result<String, VoiceError> {
         let payload = json!({
             "model": self.model,
             "messages": [
@@ -72,15 +181,14 @@ impl OllamaTranslateProcessor {
                     "content": text
                 }
             ],
-            "stream": false
+            "stream": true
         });
 
-        let response = self.client
-            .post(&self.url)
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| VoiceError::Network(e))?;
+        let mut response = send_with_retry("Ollama", &budget, || {
+            Ok(self.client.post(&self.url).timeout(self.timeout).json(&payload))
+        })
+        .await?
+        .response;
 
         let status = response.status();
         if !status.is_success() {
@@ -88,23 +196,100 @@ impl OllamaTranslateProcessor {
             return Err(VoiceError::Other(format!("Ollama API error: {} - {}", status, error_text)));
         }
 
-        let result: Value = response.json().await
-            .map_err(|e| VoiceError::Network(e))?;
+        let mut stream = ChatStream::default();
+        // 已开始输出后断开不重试，由调用方决定如何处理已收到的部分
+        while let Some(bytes) = response.chunk().await.map_err(VoiceError::Network)? {
+            if stream.feed(&bytes, on_chunk)? {
+                break;
+            }
+        }
+        stream.finish(on_chunk)
+    }
+}
+
+/// Ollama `/api/chat` 流式响应的解析状态：每行一个 JSON 对象，一行可能被拆到多个网络分片中
+#[derive(Default)]
+struct ChatStream {
+    pending: Vec<u8>,
+    content: String,
+    done: bool,
+}
+
+impl ChatStream {
+    /// 处理一个网络分片，返回是否已收到 `done: true`
+    fn feed(&mut self, bytes: &[u8], on_chunk: &mut dyn FnMut(&str)) -> Result<bool, VoiceError> {
+        self.pending.extend_from_slice(bytes);
+        while let Some(newline) = self.pending.iter().position(|b| *b == b'\n') {
+            let line: Vec<u8> = self.pending.drain(..=newline).collect();
+            self.parse_line(&line, on_chunk)?;
+            if self.done {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    /// 响应结束：处理最后一行没有换行符的数据，返回完整内容。
+    /// 没有收到 `done: true` 说明连接中途断开，已收到的部分不能当作完整译文
+    fn finish(mut self, on_chunk: &mut dyn FnMut(&str)) -> Result<String, VoiceError> {
+        if !self.done {
+            let rest = std::mem::take(&mut self.pending);
+            self.parse_line(&rest, on_chunk)?;
+        }
+        if !self.done {
+            return Err(VoiceError::Other(format!(
+                "Ollama stream ended before completion ({} chars received)",
+                self.content.chars().count()
+            )));
+        }
+        if self.content.trim().is_empty() {
+            return Err(VoiceError::Other("No translation content in Ollama response".to_string()));
+        }
+        Ok(self.content.trim().to_string())
+    }
 
-        if let Some(content) = result
+    fn parse_line(&mut self, line: &[u8], on_chunk: &mut dyn FnMut(&str)) -> Result<(), VoiceError> {
+        let line = String::from_utf8_lossy(line);
+        let line = line.trim();
+        if line.is_empty() {
+            return Ok(());
+        }
+
+        let value: Value = serde_json::from_str(line).map_err(|e| {
+            VoiceError::Other(format!("Malformed Ollama stream line ({}): {}", e, line))
+        })?;
+        if let Some(error) = value.get("error").and_then(|v| v.as_str()) {
+            return Err(VoiceError::Other(format!("Ollama stream error: {}", error)));
+        }
+        if let Some(content) = value
             .get("message")
             .and_then(|msg| msg.get("content"))
             .and_then(|v| v.as_str())
+            .filter(|content| !content.is_empty())
         {
-            Ok(content.trim().to_string())
-        } else {
-            Err(VoiceError::Other("No translation content in Ollama response".to_string()))
+            self.content.push_str(content);
+            on_chunk(content);
         }
+        self.done = value.get("done").and_then(|v| v.as_bool()).unwrap_or(false);
+        Ok(())
     }
 }
 
 impl TranslateProcessor for OllamaTranslateProcessor {
-    fn translate(&self, text: &str) -> Result<String, VoiceError> {
+    fn translate_to(&self, text: &str, target: &str) -> Result<String, VoiceError> {
+        // 收集流式输出，行为与之前的阻塞调用一致
+        self.translate_to_stream(text, target, |_| {})
+    }
+
+    fn target_language(&self) -> &str {
+        &self.target_language
+    }
+
+    fn translate_stream(&self, text: &str, on_chunk: &mut dyn FnMut(&str)) -> Result<String, VoiceError> {
+        OllamaTranslateProcessor::translate_stream(self, text, on_chunk)
+    }
+
+    fn refine(&self, text: &str) -> Result<String, VoiceError> {
         if text.trim().is_empty() {
             return Ok(String::new());
         }
@@ -113,7 +298,108 @@ impl TranslateProcessor for OllamaTranslateProcessor {
             .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
 
         rt.block_on(async {
-            self.call_api(text).await
+            self.call_api(REFINE_SYSTEM_PROMPT, text, &mut |_| {}).await
         })
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ollama_base_url() {
+        assert_eq!(ollama_base_url("http://localhost:11434/api/chat"), "http://localhost:11434");
+        assert_eq!(ollama_base_url(" http://localhost:11434/ "), "http://localhost:11434");
+        assert_eq!(ollama_base_url("http://localhost:11434/api/tags"), "http://localhost:11434");
+    }
+
+    #[test]
+    fn test_model_matches_latest_tag() {
+        assert!(model_matches("qwen2.5:latest", "qwen2.5"));
+        assert!(model_matches("qwen2.5:7b", "qwen2.5:7b"));
+        assert!(!model_matches("qwen2.5:7b", "qwen2.5"));
+    }
+
+    #[test]
+    fn test_parse_tags_response() {
+        let body = json!({
+            "models": [
+                {"name": "qwen2.5:latest", "size": 4683087332u64, "modified_at": "2024-10-01T10:00:00Z"},
+                {"model": "llama3:8b"},
+                {"size": 1}
+            ]
+        });
+        let models = parse_tags_response(&body);
+        assert_eq!(models.len(), 2);
+        assert_eq!(models[0].name, "qwen2.5:latest");
+        assert_eq!(models[0].size, 4683087332);
+        assert_eq!(models[1].name, "llama3:8b");
+        assert!(parse_tags_response(&json!({})).is_empty());
+    }
+
+    #[test]
+    fn test_chat_stream_handles_split_lines() {
+        let mut stream = ChatStream::default();
+        let mut chunks = Vec::new();
+        let mut on_chunk = |c: &str| chunks.push(c.to_string());
+
+        // 第二行被拆到两个分片中
+        let first = b"{\"message\":{\"content\":\"Hel\"},\"done\":false}\n{\"message\":{\"con";
+        let second = b"tent\":\"lo \"},\"done\":false}\n{\"message\":{\"content\":\"\"},\"done\":true}\n";
+        assert!(!stream.feed(first, &mut on_chunk).unwrap());
+        assert!(stream.feed(second, &mut on_chunk).unwrap());
+        assert_eq!(stream.finish(&mut on_chunk).unwrap(), "Hello");
+        assert_eq!(chunks, vec!["Hel", "lo "]);
+    }
+
+    #[test]
+    fn test_chat_stream_last_line_without_newline() {
+        let mut stream = ChatStream::default();
+        let mut on_chunk = |_: &str| {};
+        assert!(!stream.feed(b"{\"message\":{\"content\":\"Hi\"},\"done\":true}", &mut on_chunk).unwrap());
+        assert_eq!(stream.finish(&mut on_chunk).unwrap(), "Hi");
+    }
+
+    #[test]
+    fn test_chat_stream_errors() {
+        let mut on_chunk = |_: &str| {};
+
+        let mut stream = ChatStream::default();
+        let error = stream.feed(b"{\"message\":{\"content\":\"Hi\"}}\n{\"error\":\"model not found\"}\n", &mut on_chunk);
+        assert!(error.unwrap_err().to_string().contains("model not found"));
+
+        // 连接在一行中间结束
+        let mut stream = ChatStream::default();
+        stream.feed(b"{\"message\":{\"content\":\"Hi\"},\"done\":false}\n{\"message\":", &mut on_chunk).unwrap();
+        assert!(stream.finish(&mut on_chunk).is_err());
+
+        // 每行都完整，但一直没有收到 done
+        let mut stream = ChatStream::default();
+        assert!(!stream.feed(b"{\"message\":{\"content\":\"Hi\"},\"done\":false}\n", &mut on_chunk).unwrap());
+        assert!(stream.finish(&mut on_chunk).unwrap_err().to_string().contains("before completion"));
+
+        assert!(ChatStream::default().finish(&mut on_chunk).is_err());
+    }
+
+    #[test]
+    fn test_unresponsive_server_times_out() {
+        // 接受连接但从不返回响应
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api/chat", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let mut held = Vec::new();
+            for stream in listener.incoming().flatten() {
+                held.push(stream);
+            }
+        });
+
+        let processor = OllamaTranslateProcessor::with_config(url, "test".to_string())
+            .unwrap()
+            .with_timeout(Duration::from_millis(300));
+        let started = std::time::Instant::now();
+        let result = processor.translate("你好");
+
+        assert!(matches!(result, Err(VoiceError::Timeout(_))), "unexpected result: {:?}", result);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -1,4 +1,6 @@
 use crate::voice_assistant::{TranslateProcessor, VoiceError};
+use crate::voice_assistant::net::{send_with_retry, RetryBudget};
+use super::{request_with_timeout, translate_system_prompt, DEFAULT_TARGET_LANGUAGE, DEFAULT_TRANSLATE_TIMEOUT_SECS, REFINE_SYSTEM_PROMPT};
 use serde_json::{json, Value};
 use std::time::Duration;
 
@@ -8,6 +10,9 @@ pub struct SiliconFlowTranslateProcessor {
     api_key: String,
     model: String,
     base_url: String,
+    target_language: String,
+    source_language: Option<String>,
+    timeout: Duration,
 }
 
 impl SiliconFlowTranslateProcessor {
@@ -22,7 +27,7 @@ impl SiliconFlowTranslateProcessor {
             .unwrap_or_else(|_| "https://api.siliconflow.cn".to_string());
 
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
+            .timeout(Duration::from_secs(DEFAULT_TRANSLATE_TIMEOUT_SECS as u64))
             .default_headers({
                 let mut headers = reqwest::header::HeaderMap::new();
                 headers.insert(
@@ -44,12 +49,15 @@ impl SiliconFlowTranslateProcessor {
             api_key,
             model,
             base_url,
+            target_language: DEFAULT_TARGET_LANGUAGE.to_string(),
+            source_language: None,
+            timeout: Duration::from_secs(DEFAULT_TRANSLATE_TIMEOUT_SECS as u64),
         })
     }
 
     pub fn with_config(api_key: String, model: String, base_url: String) -> Result<Self, VoiceError> {
         let client = reqwest::Client::builder()
-            .timeout(Duration::from_secs(30))
+            .timeout(Duration::from_secs(DEFAULT_TRANSLATE_TIMEOUT_SECS as u64))
             .default_headers({
                 let mut headers = reqwest::header::HeaderMap::new();
                 headers.insert(
@@ -71,12 +79,33 @@ impl SiliconFlowTranslateProcessor {
             api_key,
             model,
             base_url,
+            target_language: DEFAULT_TARGET_LANGUAGE.to_string(),
+            source_language: None,
+            timeout: Duration::from_secs(DEFAULT_TRANSLATE_TIMEOUT_SECS as u64),
         })
     }
 
-    async fn call_api(&self, text: &str) -> Result<String, VoiceError> {
-        let system_prompt = "You are a translation assistant. Please translate the user's input into English.";
+    /// 设置默认目标语言和可选的源语言（语言代码）
+    pub fn with_languages(mut self, target_language: &str, source_language: Option<&str>) -> Self {
+        self.target_language = target_language.to_string();
+        self.source_language = source_language.map(str::to_string);
+        self
+    }
+
+    /// 单次翻译（含重试）的最长时间，超时返回 `VoiceError::Timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    async fn call_api(&self, system_prompt: &str, text: &str) -> Result<String, VoiceError> {
+        request_with_timeout("SiliconFlow", self.timeout, |budget| {
+            self.send_completion(budget, system_prompt, text)
+        })
+        .await
+    }
 
+    async fn send_completion(&self, budget: RetryBudget, system_prompt: &str, text: &str) -> Result<String, VoiceError> {
         let payload = json!({
             "model": self.model,
             "messages": [
@@ -91,12 +120,12 @@ impl SiliconFlowTranslateProcessor {
             ]
         });
 
-        let response = self.client
-            .post(&format!("{}/v1/chat/completions", self.base_url))
-            .json(&payload)
-            .send()
-            .await
-            .map_err(|e| VoiceError::Network(e))?;
+        let url = format!("{}/v1/chat/completions", self.base_url);
+        let response = send_with_retry("SiliconFlow", &budget, || {
+            Ok(self.client.post(&url).timeout(self.timeout).json(&payload))
+        })
+        .await?
+        .response;
 
         let status = response.status();
         if !status.is_success() {
@@ -123,7 +152,25 @@ impl SiliconFlowTranslateProcessor {
 }
 
 impl TranslateProcessor for SiliconFlowTranslateProcessor {
-    fn translate(&self, text: &str) -> Result<String, VoiceError> {
+    fn translate_to(&self, text: &str, target: &str) -> Result<String, VoiceError> {
+        if text.trim().is_empty() {
+            return Ok(String::new());
+        }
+
+        let system_prompt = translate_system_prompt(target, self.source_language.as_deref());
+        let rt = tokio::runtime::Runtime::new()
+            .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
+
+        rt.block_on(async {
+            self.call_api(&system_prompt, text).await
+        })
+    }
+
+    fn target_language(&self) -> &str {
+        &self.target_language
+    }
+
+    fn refine(&self, text: &str) -> Result<String, VoiceError> {
         if text.trim().is_empty() {
             return Ok(String::new());
         }
@@ -132,7 +179,7 @@ impl TranslateProcessor for SiliconFlowTranslateProcessor {
             .map_err(|e| VoiceError::Other(format!("Failed to create runtime: {}", e)))?;
 
         rt.block_on(async {
-            self.call_api(text).await
+            self.call_api(REFINE_SYSTEM_PROMPT, text).await
         })
     }
 }
\ No newline at end of file